@@ -159,6 +159,14 @@ if [ $AGENT_EXIT -ne 0 ]; then
 fi
 
 # === 8. AUTO-MERGE (if enabled) ===
+# Task worktrees (bn agent spawn --task) join the merge queue, which rebases,
+# runs the task's linked tests and merges on the host (bn merge-queue run)
+if [ -n "$BN_TASK_ID" ] && [ "$BN_NO_MERGE" != "true" ]; then
+    echo "🔀 Adding $BN_TASK_ID to the merge queue..."
+    bn merge-queue add "$BN_TASK_ID" --target "${BN_MERGE_TARGET:-main}" -H
+    exit $?
+fi
+
 BN_AUTO_MERGE="${BN_AUTO_MERGE:-false}"
 BN_MERGE_TARGET="${BN_MERGE_TARGET:-main}"
 
@@ -183,31 +183,47 @@ fi
 echo "$BN_INITIAL_PROMPT"
 echo "--- END PROMPT ---"
 
-# Blocked MCP tools - orient/goodbye must use shell for proper agent lifecycle
-BLOCKED_TOOLS=(
-    --deny-tool "binnacle(binnacle-orient)"
-    --deny-tool "binnacle(binnacle-goodbye)"
-)
-
-# Use container-local pinned copilot binary
-COPILOT_PATH_INFO=$(bn system copilot path 2>/dev/null || true)
-COPILOT_BIN=$(echo "$COPILOT_PATH_INFO" | jq -r '.path // empty')
-COPILOT_EXISTS=$(echo "$COPILOT_PATH_INFO" | jq -r '.exists // false')
-COPILOT_VERSION=$(echo "$COPILOT_PATH_INFO" | jq -r '.version // empty')
-
-if [ "$COPILOT_EXISTS" = "true" ] && [ -n "$COPILOT_BIN" ] && [ -x "$COPILOT_BIN" ]; then
-    echo "🤖 Using pinned copilot $COPILOT_VERSION: $COPILOT_BIN"
-    "$COPILOT_BIN" --allow-all --no-auto-update "${BLOCKED_TOOLS[@]}" -p "$FULL_PROMPT"
-    AGENT_EXIT=$?
-elif command -v claude &> /dev/null; then
-    echo "🤖 Using claude CLI"
-    claude -p "$FULL_PROMPT"
-    AGENT_EXIT=$?
-else
-    echo "❌ No AI agent found (copilot or claude CLI)"
-    echo "   Expected copilot at: $COPILOT_BIN"
+# Resolve the launch plan for the agent's CLI backend (from the agent definition,
+# or BN_AGENT_BACKEND set by `bn agent spawn --backend`). The container is the
+# sandbox, so per-tool approval is skipped; orient/goodbye stay blocked because
+# they must run through the shell for proper agent lifecycle.
+launch_plan() {
+    bn config agents launch "$BN_AGENT_TYPE" --allow-all \
+        --deny "binnacle(binnacle-orient)" \
+        --deny "binnacle(binnacle-goodbye)" \
+        --prompt "$FULL_PROMPT" --write "$@"
+}
+
+if ! LAUNCH_JSON=$(launch_plan); then
+    echo "❌ Failed to resolve agent launch plan for '$BN_AGENT_TYPE'"
     exit 1
 fi
+AGENT_BACKEND=$(echo "$LAUNCH_JSON" | jq -r '.backend')
+AGENT_BIN=$(echo "$LAUNCH_JSON" | jq -r '.argv[0]')
+
+# Fall back to claude when the configured copilot binary is missing
+if ! command -v "$AGENT_BIN" &> /dev/null && [ "$AGENT_BACKEND" = "copilot" ] && command -v claude &> /dev/null; then
+    echo "⚠️  copilot not found at $AGENT_BIN, falling back to claude CLI"
+    LAUNCH_JSON=$(launch_plan --backend claude) || exit 1
+    AGENT_BACKEND=claude
+    AGENT_BIN=$(echo "$LAUNCH_JSON" | jq -r '.argv[0]')
+fi
+
+if ! command -v "$AGENT_BIN" &> /dev/null; then
+    echo "❌ No AI agent found for backend '$AGENT_BACKEND'"
+    echo "   Expected: $AGENT_BIN"
+    exit 1
+fi
+
+# NUL-delimited so the prompt stays a single argument
+mapfile -d '' -t AGENT_CMD < <(echo "$LAUNCH_JSON" | jq -j '.argv[] + "\u0000"')
+while IFS= read -r -d '' kv; do
+    export "${kv?}"
+done < <(echo "$LAUNCH_JSON" | jq -j '.env | to_entries[] | "\(.key)=\(.value)\u0000"')
+
+echo "🤖 Using $AGENT_BACKEND: $AGENT_BIN"
+"${AGENT_CMD[@]}"
+AGENT_EXIT=$?
 
 if [ $AGENT_EXIT -ne 0 ]; then
     echo "❌ Agent exited with error code $AGENT_EXIT"
@@ -215,6 +231,14 @@ if [ $AGENT_EXIT -ne 0 ]; then
 fi
 
 # === 8. AUTO-MERGE (if enabled) ===
+# Task worktrees (bn agent spawn --task) join the merge queue, which rebases,
+# runs the task's linked tests and merges on the host (bn merge-queue run)
+if [ -n "$BN_TASK_ID" ] && [ "$BN_NO_MERGE" != "true" ]; then
+    echo "🔀 Adding $BN_TASK_ID to the merge queue..."
+    bn merge-queue add "$BN_TASK_ID" --target "${BN_MERGE_TARGET:-main}" -H
+    exit $?
+fi
+
 BN_AUTO_MERGE="${BN_AUTO_MERGE:-false}"
 BN_MERGE_TARGET="${BN_MERGE_TARGET:-main}"
 
@@ -3,6 +3,7 @@
 //! This module provides comprehensive logging of all binnacle commands and operations
 //! to a structured log file in JSONL format.
 
+use crate::models::{TranscriptEntry, TranscriptKind};
 use crate::storage::Storage;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -41,6 +42,10 @@ pub struct ActionLog {
     /// Agent ID if the command was run by an agent (from BN_AGENT_ID env var)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub agent_id: Option<String>,
+
+    /// GUI token ID if the action was performed through the GUI/session server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_id: Option<String>,
 }
 
 /// Log an action to the configured log file.
@@ -54,6 +59,22 @@ pub fn log_action(
     success: bool,
     error: Option<String>,
     duration_ms: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    log_action_with_token(repo_path, command, args, success, error, duration_ms, None)
+}
+
+/// Log an action performed through the GUI/session server.
+///
+/// Identical to [`log_action`], but records the ID of the bearer token that
+/// authorized the request so writes can be audited per token.
+pub fn log_action_with_token(
+    repo_path: &Path,
+    command: &str,
+    args: serde_json::Value,
+    success: bool,
+    error: Option<String>,
+    duration_ms: u64,
+    token_id: Option<&str>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // Check if logging is enabled
     let enabled = match get_config_bool(repo_path, "action_log_enabled") {
@@ -101,6 +122,7 @@ pub fn log_action(
         duration_ms,
         user,
         agent_id,
+        token_id: token_id.map(|s| s.to_string()),
     };
 
     // Write to log file (JSONL format for backward compatibility)
@@ -108,17 +130,36 @@ pub fn log_action(
         eprintln!("Warning: Failed to write action log: {}", e);
     }
 
-    // Also write to SQLite cache for efficient pagination queries
-    if let Ok(storage) = Storage::open(repo_path)
-        && let Err(e) = storage.add_action_log(&entry)
-    {
-        // Non-fatal: SQLite write failure shouldn't block logging
-        eprintln!("Warning: Failed to write action log to cache: {}", e);
+    if let Ok(storage) = Storage::open(repo_path) {
+        // Also write to SQLite cache for efficient pagination queries
+        if let Err(e) = storage.add_action_log(&entry) {
+            // Non-fatal: SQLite write failure shouldn't block logging
+            eprintln!("Warning: Failed to write action log to cache: {}", e);
+        }
+
+        // Mirror agent commands into the agent's transcript (`bn agent transcript`)
+        if let Some(ref agent_id) = entry.agent_id
+            && let Err(e) = storage.append_transcript_entry(agent_id, &transcript_entry(&entry))
+        {
+            eprintln!("Warning: Failed to write agent transcript: {}", e);
+        }
     }
 
     Ok(())
 }
 
+/// The transcript line recording a logged command.
+fn transcript_entry(entry: &ActionLog) -> TranscriptEntry {
+    TranscriptEntry {
+        at: entry.timestamp,
+        kind: TranscriptKind::Command,
+        text: entry.command.clone(),
+        args: Some(entry.args.clone()),
+        success: Some(entry.success),
+        duration_ms: Some(entry.duration_ms),
+    }
+}
+
 /// Get the log file path from configuration.
 fn get_log_path(repo_path: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
     // Try to get custom path from config
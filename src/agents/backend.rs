@@ -0,0 +1,1296 @@
+//! Agent CLI backends.
+//!
+//! An agent definition names the coding-agent CLI that runs it. Each backend
+//! turns a resolved [`AgentDefinition`] into a [`LaunchPlan`]: the argv to
+//! exec, extra environment, and any config files that must be written first
+//! (MCP server registration, permission files).
+//!
+//! | Backend    | Binary     | Tool permissions                         | MCP config                          |
+//! |------------|------------|------------------------------------------|-------------------------------------|
+//! | `copilot`  | `copilot`  | `--allow-tool` / `--deny-tool`           | `--additional-mcp-config @file`     |
+//! | `claude`   | `claude`   | `--allowedTools` / `--disallowedTools`   | `--mcp-config file`                 |
+//! | `codex`    | `codex`    | sandbox mode (no per-command patterns)   | `-c mcp_servers.binnacle.*`         |
+//! | `opencode` | `opencode` | `permission` block in `OPENCODE_CONFIG`  | `mcp` block in `OPENCODE_CONFIG`    |
+//! | `aider`    | `aider`    | `--yes-always` / `--dry-run`             | (none, aider has no MCP support)    |
+//! | `custom`   | template   | `{allow}` / `{deny}` placeholders        | `{mcp_config}` placeholder          |
+//! | `fake`     | `sh`       | echoed as `--allow` / `--deny`           | generic `mcpServers` file           |
+//!
+//! ## KDL Configuration
+//!
+//! ```kdl
+//! agent "worker" {
+//!     backend "claude"           // copilot | claude | codex | opencode | aider | custom | fake
+//!     backend-model "opus"       // passed as --model to non-Copilot backends
+//! }
+//!
+//! agent "buddy" {
+//!     backend "custom"
+//!     backend-command "my-agent --model {model} {allow} -- {prompt}"
+//! }
+//! ```
+//!
+//! `BN_AGENT_BACKEND` overrides the configured backend for a single launch.
+
+use crate::agents::definitions::{AgentDefinition, ToolPermissions};
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Environment variable that overrides the configured backend.
+pub const BACKEND_ENV_VAR: &str = "BN_AGENT_BACKEND";
+
+/// Container env vars forwarded to bn's MCP server when they are set.
+pub const MCP_PASSTHROUGH_ENV: &[&str] = &[
+    "BN_DATA_DIR",
+    "BN_CONTAINER_MODE",
+    "BN_STORAGE_HASH",
+    "BN_AGENT_ID",
+    "BN_AGENT_NAME",
+    "BN_AGENT_TYPE",
+];
+
+/// Supported agent CLI backends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    /// GitHub Copilot CLI.
+    #[default]
+    Copilot,
+    /// Anthropic Claude Code.
+    Claude,
+    /// OpenAI Codex CLI.
+    Codex,
+    /// OpenCode.
+    OpenCode,
+    /// Aider.
+    Aider,
+    /// User-supplied command template.
+    Custom,
+    /// Local fake agent for exercising the spawn pipeline.
+    Fake,
+}
+
+impl BackendKind {
+    /// All backends, in documentation order.
+    pub const ALL: [BackendKind; 7] = [
+        BackendKind::Copilot,
+        BackendKind::Claude,
+        BackendKind::Codex,
+        BackendKind::OpenCode,
+        BackendKind::Aider,
+        BackendKind::Custom,
+        BackendKind::Fake,
+    ];
+}
+
+impl std::fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BackendKind::Copilot => write!(f, "copilot"),
+            BackendKind::Claude => write!(f, "claude"),
+            BackendKind::Codex => write!(f, "codex"),
+            BackendKind::OpenCode => write!(f, "opencode"),
+            BackendKind::Aider => write!(f, "aider"),
+            BackendKind::Custom => write!(f, "custom"),
+            BackendKind::Fake => write!(f, "fake"),
+        }
+    }
+}
+
+impl std::str::FromStr for BackendKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "copilot" => Ok(BackendKind::Copilot),
+            "claude" | "claude-code" => Ok(BackendKind::Claude),
+            "codex" => Ok(BackendKind::Codex),
+            "opencode" => Ok(BackendKind::OpenCode),
+            "aider" => Ok(BackendKind::Aider),
+            "custom" => Ok(BackendKind::Custom),
+            "fake" => Ok(BackendKind::Fake),
+            _ => Err(Error::InvalidInput(format!(
+                "Invalid agent backend: '{}'. Expected one of: {}.",
+                s,
+                BackendKind::ALL
+                    .iter()
+                    .map(|k| k.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))),
+        }
+    }
+}
+
+/// Backend selection for an agent definition.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackendConfig {
+    /// Which CLI runs the agent.
+    pub kind: BackendKind,
+    /// Command template for the `custom` backend.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Model passed to non-Copilot backends (Copilot uses `CopilotConfig`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+/// Options for a single agent launch.
+#[derive(Debug, Clone, Default)]
+pub struct LaunchRequest {
+    /// Prompt passed to the agent.
+    pub prompt: String,
+    /// Start an interactive session seeded with the prompt instead of a one-shot run.
+    pub interactive: bool,
+    /// Skip per-tool approval entirely; deny patterns still apply where supported.
+    pub allow_all: bool,
+    /// Deny patterns added on top of the definition's own.
+    pub extra_deny: Vec<String>,
+    /// Override the backend binary (e.g. the pinned Copilot path).
+    pub program: Option<String>,
+    /// Directory where generated config files are placed.
+    pub config_dir: PathBuf,
+    /// Environment passed to bn's MCP server.
+    pub mcp_env: BTreeMap<String, String>,
+}
+
+/// A config file a backend needs before launch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaunchFile {
+    /// Absolute path to write.
+    pub path: PathBuf,
+    /// File contents.
+    pub content: String,
+}
+
+/// Everything needed to start an agent with a given backend.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaunchPlan {
+    /// Backend that produced this plan.
+    pub backend: BackendKind,
+    /// Program and arguments; `argv[0]` is the program.
+    pub argv: Vec<String>,
+    /// Extra environment variables for the agent process.
+    pub env: BTreeMap<String, String>,
+    /// Files to write before exec.
+    pub files: Vec<LaunchFile>,
+}
+
+impl LaunchPlan {
+    /// Create a plan that runs `program` with no arguments.
+    pub fn new(backend: BackendKind, program: impl Into<String>) -> Self {
+        Self {
+            backend,
+            argv: vec![program.into()],
+            env: BTreeMap::new(),
+            files: Vec::new(),
+        }
+    }
+
+    /// Append arguments.
+    pub fn args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.argv.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Deep-merge a JSON object into a generated file, creating it if needed.
+    ///
+    /// Lets a backend build one config file from separate permission and MCP steps.
+    pub fn merge_json(&mut self, path: &Path, value: Value) {
+        let idx = match self.files.iter().position(|f| f.path == path) {
+            Some(idx) => idx,
+            None => {
+                self.files.push(LaunchFile {
+                    path: path.to_path_buf(),
+                    content: "{}".to_string(),
+                });
+                self.files.len() - 1
+            }
+        };
+        let mut merged: Value = serde_json::from_str(&self.files[idx].content)
+            .unwrap_or_else(|_| Value::Object(Map::new()));
+        deep_merge(&mut merged, value);
+        self.files[idx].content = serde_json::to_string_pretty(&merged).unwrap_or_default();
+    }
+
+    /// Write all generated files, creating parent directories.
+    pub fn write_files(&self) -> Result<()> {
+        for file in &self.files {
+            if let Some(parent) = file.path.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| {
+                    Error::Other(format!("Failed to create {}: {}", parent.display(), e))
+                })?;
+            }
+            std::fs::write(&file.path, format!("{}\n", file.content)).map_err(|e| {
+                Error::Other(format!("Failed to write {}: {}", file.path.display(), e))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+fn deep_merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                deep_merge(base.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// A tool pattern from `ToolPermissions`, in binnacle's (Copilot-style) syntax.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolPattern<'a> {
+    /// `shell(cmd:args)`; `args` is `None` for a bare `shell(cmd)`.
+    Shell {
+        command: &'a str,
+        args: Option<&'a str>,
+    },
+    /// `server` or `server(tool)` for an MCP server.
+    Mcp {
+        server: &'a str,
+        tool: Option<&'a str>,
+    },
+    /// Built-in tool name such as `write`, `view` or `grep`.
+    Builtin(&'a str),
+}
+
+/// Built-in tool names binnacle uses in tool permissions.
+const BUILTIN_TOOLS: &[&str] = &[
+    "write", "edit", "create", "view", "read", "grep", "glob", "lsp", "shell", "url",
+];
+
+/// Built-in tools that modify files.
+const WRITE_TOOLS: &[&str] = &["write", "edit", "create"];
+
+impl<'a> ToolPattern<'a> {
+    /// Parse a pattern such as `shell(git:*)`, `binnacle(binnacle-orient)` or `write`.
+    pub fn parse(pattern: &'a str) -> Self {
+        let Some((head, rest)) = pattern.split_once('(') else {
+            return if BUILTIN_TOOLS.contains(&pattern) {
+                ToolPattern::Builtin(pattern)
+            } else {
+                ToolPattern::Mcp {
+                    server: pattern,
+                    tool: None,
+                }
+            };
+        };
+        let inner = rest.strip_suffix(')').unwrap_or(rest);
+        if head == "shell" {
+            match inner.rsplit_once(':') {
+                Some((command, args)) => ToolPattern::Shell {
+                    command,
+                    args: Some(args),
+                },
+                None => ToolPattern::Shell {
+                    command: inner,
+                    args: None,
+                },
+            }
+        } else {
+            ToolPattern::Mcp {
+                server: head,
+                tool: Some(inner),
+            }
+        }
+    }
+
+    /// Whether this pattern grants file modification.
+    pub fn is_write(&self) -> bool {
+        matches!(self, ToolPattern::Builtin(name) if WRITE_TOOLS.contains(name))
+    }
+
+    /// Shell command prefix with arguments folded in (`git log`, `git *`).
+    fn shell_prefix(command: &str, args: Option<&str>) -> String {
+        match args {
+            Some("*") | None => command.to_string(),
+            Some(args) => format!("{} {}", command, args),
+        }
+    }
+}
+
+/// The bn MCP server as a backend should register it.
+#[derive(Debug, Clone)]
+pub struct McpServer {
+    /// Command to start the server.
+    pub command: String,
+    /// Arguments to the command.
+    pub args: Vec<String>,
+    /// Environment for the server process.
+    pub env: BTreeMap<String, String>,
+}
+
+impl McpServer {
+    /// `bn mcp serve` with the given environment.
+    pub fn bn(env: BTreeMap<String, String>) -> Self {
+        Self {
+            command: "bn".to_string(),
+            args: vec!["mcp".to_string(), "serve".to_string()],
+            env,
+        }
+    }
+
+    /// Standard `{"mcpServers": {"binnacle": ...}}` document.
+    fn mcp_servers_json(&self, extra: Value) -> Value {
+        let mut server = json!({
+            "command": self.command,
+            "args": self.args,
+            "env": self.env,
+        });
+        deep_merge(&mut server, extra);
+        json!({ "mcpServers": { "binnacle": server } })
+    }
+}
+
+/// An agent CLI that binnacle can launch.
+///
+/// Implementations translate binnacle's tool permissions and MCP server into
+/// the CLI's own flags or config files. The provided [`AgentBackend::launch`]
+/// composes them; backends with a fixed argv shape (custom, fake) override it.
+pub trait AgentBackend {
+    /// Which backend this is.
+    fn kind(&self) -> BackendKind;
+
+    /// Default binary name.
+    fn program(&self) -> String;
+
+    /// Translate allow/deny patterns into the CLI's auto-approve format.
+    fn apply_permissions(
+        &self,
+        plan: &mut LaunchPlan,
+        tools: &ToolPermissions,
+        req: &LaunchRequest,
+    );
+
+    /// Register bn's MCP server with the CLI. The default does nothing.
+    fn apply_mcp(&self, _plan: &mut LaunchPlan, _server: &McpServer, _config_dir: &Path) {}
+
+    /// Arguments selecting the model, if one is configured.
+    fn model_args(&self, agent: &AgentDefinition) -> Vec<String> {
+        match &agent.backend.model {
+            Some(model) => vec!["--model".to_string(), model.clone()],
+            None => Vec::new(),
+        }
+    }
+
+    /// Arguments passing the prompt (always last on the command line).
+    fn prompt_args(&self, prompt: &str, interactive: bool) -> Vec<String>;
+
+    /// Build the full launch plan for an agent.
+    fn launch(&self, agent: &AgentDefinition, req: &LaunchRequest) -> Result<LaunchPlan> {
+        let program = req.program.clone().unwrap_or_else(|| self.program());
+        let mut plan = LaunchPlan::new(self.kind(), program);
+        plan.args(self.model_args(agent));
+        self.apply_permissions(&mut plan, &effective_tools(agent, req), req);
+        self.apply_mcp(
+            &mut plan,
+            &McpServer::bn(req.mcp_env.clone()),
+            &req.config_dir,
+        );
+        plan.args(self.prompt_args(&req.prompt, req.interactive));
+        Ok(plan)
+    }
+}
+
+/// The definition's tool permissions plus the request's extra deny patterns.
+fn effective_tools(agent: &AgentDefinition, req: &LaunchRequest) -> ToolPermissions {
+    let mut tools = agent.tools.clone();
+    for pattern in &req.extra_deny {
+        if !tools.deny.contains(pattern) {
+            tools.deny.push(pattern.clone());
+        }
+    }
+    tools
+}
+
+/// GitHub Copilot CLI. Binnacle's tool patterns are Copilot's own syntax.
+pub struct CopilotBackend;
+
+impl AgentBackend for CopilotBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Copilot
+    }
+
+    fn program(&self) -> String {
+        "copilot".to_string()
+    }
+
+    fn apply_permissions(
+        &self,
+        plan: &mut LaunchPlan,
+        tools: &ToolPermissions,
+        req: &LaunchRequest,
+    ) {
+        // Prevent mid-workflow updates
+        plan.args(["--no-auto-update"]);
+        if req.allow_all {
+            plan.args(["--allow-all"]);
+        } else {
+            plan.args(["--allow-all-urls"]);
+            for pattern in &tools.allow {
+                plan.args(["--allow-tool", pattern]);
+            }
+        }
+        for pattern in &tools.deny {
+            plan.args(["--deny-tool", pattern]);
+        }
+    }
+
+    fn apply_mcp(&self, plan: &mut LaunchPlan, server: &McpServer, config_dir: &Path) {
+        // Copilot zeros out MCP env except PATH, so the vars are passed explicitly
+        let path = config_dir.join("copilot-mcp.json");
+        plan.merge_json(
+            &path,
+            server.mcp_servers_json(json!({ "type": "local", "tools": ["*"] })),
+        );
+        plan.args([
+            "--additional-mcp-config".to_string(),
+            format!("@{}", path.display()),
+        ]);
+    }
+
+    fn model_args(&self, _agent: &AgentDefinition) -> Vec<String> {
+        // Model comes from ~/.copilot/config.json (see `bn config agents copilot-config`)
+        Vec::new()
+    }
+
+    fn prompt_args(&self, prompt: &str, interactive: bool) -> Vec<String> {
+        let flag = if interactive { "-i" } else { "-p" };
+        vec![flag.to_string(), prompt.to_string()]
+    }
+}
+
+/// Anthropic Claude Code.
+pub struct ClaudeBackend;
+
+impl ClaudeBackend {
+    /// Map a binnacle tool pattern to Claude Code permission rules.
+    pub fn map_pattern(pattern: &str) -> Vec<String> {
+        match ToolPattern::parse(pattern) {
+            ToolPattern::Shell { command, args } => {
+                let prefix = ToolPattern::shell_prefix(command, args);
+                if args.is_some() {
+                    vec![format!("Bash({}:*)", prefix)]
+                } else {
+                    vec![format!("Bash({})", prefix)]
+                }
+            }
+            ToolPattern::Mcp { server, tool: None } => vec![format!("mcp__{}", server)],
+            ToolPattern::Mcp {
+                server,
+                tool: Some(tool),
+            } => vec![format!("mcp__{}__{}", server, tool)],
+            ToolPattern::Builtin(name) => match name {
+                "write" | "edit" | "create" => vec!["Edit".to_string(), "Write".to_string()],
+                "view" | "read" => vec!["Read".to_string()],
+                "grep" => vec!["Grep".to_string()],
+                "glob" => vec!["Glob".to_string()],
+                "shell" => vec!["Bash".to_string()],
+                "url" => vec!["WebFetch".to_string()],
+                // No Claude Code equivalent
+                _ => Vec::new(),
+            },
+        }
+    }
+
+    fn map_all(patterns: &[String]) -> Vec<String> {
+        let mut mapped: Vec<String> = Vec::new();
+        for rule in patterns.iter().flat_map(|p| Self::map_pattern(p)) {
+            if !mapped.contains(&rule) {
+                mapped.push(rule);
+            }
+        }
+        mapped
+    }
+}
+
+impl AgentBackend for ClaudeBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Claude
+    }
+
+    fn program(&self) -> String {
+        "claude".to_string()
+    }
+
+    fn apply_permissions(
+        &self,
+        plan: &mut LaunchPlan,
+        tools: &ToolPermissions,
+        req: &LaunchRequest,
+    ) {
+        if req.allow_all {
+            plan.args(["--dangerously-skip-permissions"]);
+        } else {
+            let allowed = Self::map_all(&tools.allow);
+            if !allowed.is_empty() {
+                plan.args(["--allowedTools".to_string(), allowed.join(",")]);
+            }
+        }
+        let denied = Self::map_all(&tools.deny);
+        if !denied.is_empty() {
+            plan.args(["--disallowedTools".to_string(), denied.join(",")]);
+        }
+    }
+
+    fn apply_mcp(&self, plan: &mut LaunchPlan, server: &McpServer, config_dir: &Path) {
+        let path = config_dir.join("claude-mcp.json");
+        plan.merge_json(&path, server.mcp_servers_json(json!({ "type": "stdio" })));
+        plan.args(["--mcp-config".to_string(), path.display().to_string()]);
+    }
+
+    fn prompt_args(&self, prompt: &str, interactive: bool) -> Vec<String> {
+        if interactive {
+            vec![prompt.to_string()]
+        } else {
+            vec!["-p".to_string(), prompt.to_string()]
+        }
+    }
+}
+
+/// OpenAI Codex CLI.
+///
+/// Codex has no per-command allow list, so permissions map to a sandbox
+/// mode: read-only unless the definition allows a write tool.
+pub struct CodexBackend;
+
+impl AgentBackend for CodexBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Codex
+    }
+
+    fn program(&self) -> String {
+        "codex".to_string()
+    }
+
+    fn apply_permissions(
+        &self,
+        plan: &mut LaunchPlan,
+        tools: &ToolPermissions,
+        req: &LaunchRequest,
+    ) {
+        if req.allow_all {
+            plan.args(["--dangerously-bypass-approvals-and-sandbox"]);
+            return;
+        }
+        let writes = tools.allow.iter().any(|p| ToolPattern::parse(p).is_write())
+            && !tools.deny.iter().any(|p| ToolPattern::parse(p).is_write());
+        let sandbox = if writes {
+            "workspace-write"
+        } else {
+            "read-only"
+        };
+        plan.args(["--sandbox", sandbox]);
+    }
+
+    fn apply_mcp(&self, plan: &mut LaunchPlan, server: &McpServer, _config_dir: &Path) {
+        // Codex reads MCP servers from config.toml; `-c` overrides avoid touching it
+        let args = server
+            .args
+            .iter()
+            .map(|a| format!("{:?}", a))
+            .collect::<Vec<_>>()
+            .join(",");
+        plan.args([
+            "-c".to_string(),
+            format!("mcp_servers.binnacle.command={:?}", server.command),
+            "-c".to_string(),
+            format!("mcp_servers.binnacle.args=[{}]", args),
+        ]);
+        if !server.env.is_empty() {
+            let env = server
+                .env
+                .iter()
+                .map(|(k, v)| format!("{}={:?}", k, v))
+                .collect::<Vec<_>>()
+                .join(",");
+            plan.args([
+                "-c".to_string(),
+                format!("mcp_servers.binnacle.env={{{}}}", env),
+            ]);
+        }
+    }
+
+    fn prompt_args(&self, prompt: &str, _interactive: bool) -> Vec<String> {
+        vec![prompt.to_string()]
+    }
+
+    fn launch(&self, agent: &AgentDefinition, req: &LaunchRequest) -> Result<LaunchPlan> {
+        // `exec` is a subcommand, so it must precede the global flags
+        let program = req.program.clone().unwrap_or_else(|| self.program());
+        let mut plan = LaunchPlan::new(self.kind(), program);
+        if !req.interactive {
+            plan.args(["exec"]);
+        }
+        plan.args(self.model_args(agent));
+        self.apply_permissions(&mut plan, &effective_tools(agent, req), req);
+        self.apply_mcp(
+            &mut plan,
+            &McpServer::bn(req.mcp_env.clone()),
+            &req.config_dir,
+        );
+        plan.args(self.prompt_args(&req.prompt, req.interactive));
+        Ok(plan)
+    }
+}
+
+/// OpenCode. Permissions and MCP both live in a generated `OPENCODE_CONFIG` file.
+pub struct OpenCodeBackend;
+
+impl OpenCodeBackend {
+    fn config_path(config_dir: &Path) -> PathBuf {
+        config_dir.join("opencode.json")
+    }
+}
+
+impl AgentBackend for OpenCodeBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::OpenCode
+    }
+
+    fn program(&self) -> String {
+        "opencode".to_string()
+    }
+
+    fn apply_permissions(
+        &self,
+        plan: &mut LaunchPlan,
+        tools: &ToolPermissions,
+        req: &LaunchRequest,
+    ) {
+        let default = if req.allow_all { "allow" } else { "ask" };
+        let mut bash = Map::new();
+        bash.insert("*".to_string(), json!(default));
+        let mut edit = default;
+        let mut webfetch = default;
+
+        for (patterns, verdict) in [(&tools.allow, "allow"), (&tools.deny, "deny")] {
+            for pattern in patterns {
+                match ToolPattern::parse(pattern) {
+                    ToolPattern::Shell { command, args } => {
+                        let prefix = ToolPattern::shell_prefix(command, args);
+                        let key = if args.is_some() {
+                            format!("{} *", prefix)
+                        } else {
+                            prefix
+                        };
+                        bash.insert(key, json!(verdict));
+                    }
+                    ToolPattern::Builtin("shell") => {
+                        bash.insert("*".to_string(), json!(verdict));
+                    }
+                    ToolPattern::Builtin("url") => webfetch = verdict,
+                    p if p.is_write() => edit = verdict,
+                    _ => {}
+                }
+            }
+        }
+
+        let path = Self::config_path(&req.config_dir);
+        plan.merge_json(
+            &path,
+            json!({
+                "$schema": "https://opencode.ai/config.json",
+                "permission": { "edit": edit, "bash": bash, "webfetch": webfetch },
+            }),
+        );
+        plan.env
+            .insert("OPENCODE_CONFIG".to_string(), path.display().to_string());
+    }
+
+    fn apply_mcp(&self, plan: &mut LaunchPlan, server: &McpServer, config_dir: &Path) {
+        let mut command = vec![server.command.clone()];
+        command.extend(server.args.iter().cloned());
+        plan.merge_json(
+            &Self::config_path(config_dir),
+            json!({
+                "mcp": {
+                    "binnacle": {
+                        "type": "local",
+                        "command": command,
+                        "environment": server.env,
+                        "enabled": true,
+                    }
+                }
+            }),
+        );
+    }
+
+    fn prompt_args(&self, prompt: &str, interactive: bool) -> Vec<String> {
+        if interactive {
+            vec!["--prompt".to_string(), prompt.to_string()]
+        } else {
+            vec![prompt.to_string()]
+        }
+    }
+
+    fn launch(&self, agent: &AgentDefinition, req: &LaunchRequest) -> Result<LaunchPlan> {
+        // `run` is a subcommand, so it must precede the flags
+        let program = req.program.clone().unwrap_or_else(|| self.program());
+        let mut plan = LaunchPlan::new(self.kind(), program);
+        if !req.interactive {
+            plan.args(["run"]);
+        }
+        plan.args(self.model_args(agent));
+        self.apply_permissions(&mut plan, &effective_tools(agent, req), req);
+        self.apply_mcp(
+            &mut plan,
+            &McpServer::bn(req.mcp_env.clone()),
+            &req.config_dir,
+        );
+        plan.args(self.prompt_args(&req.prompt, req.interactive));
+        Ok(plan)
+    }
+}
+
+/// Aider. It has no tool model or MCP support; permissions only decide
+/// whether edits are applied and shell commands are suggested.
+pub struct AiderBackend;
+
+impl AgentBackend for AiderBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Aider
+    }
+
+    fn program(&self) -> String {
+        "aider".to_string()
+    }
+
+    fn apply_permissions(
+        &self,
+        plan: &mut LaunchPlan,
+        tools: &ToolPermissions,
+        req: &LaunchRequest,
+    ) {
+        let writes = req.allow_all
+            || (tools.allow.iter().any(|p| ToolPattern::parse(p).is_write())
+                && !tools.deny.iter().any(|p| ToolPattern::parse(p).is_write()));
+        let shell = req.allow_all
+            || tools.allow.iter().any(|p| {
+                matches!(
+                    ToolPattern::parse(p),
+                    ToolPattern::Shell { .. } | ToolPattern::Builtin("shell")
+                )
+            });
+
+        if writes {
+            plan.args(["--yes-always"]);
+        } else {
+            plan.args(["--dry-run"]);
+        }
+        if !shell {
+            plan.args(["--no-suggest-shell-commands"]);
+        }
+    }
+
+    fn prompt_args(&self, prompt: &str, interactive: bool) -> Vec<String> {
+        if interactive {
+            // Aider can't seed an interactive chat; the prompt becomes read-only context
+            Vec::new()
+        } else {
+            vec!["--message".to_string(), prompt.to_string()]
+        }
+    }
+
+    fn launch(&self, agent: &AgentDefinition, req: &LaunchRequest) -> Result<LaunchPlan> {
+        let program = req.program.clone().unwrap_or_else(|| self.program());
+        let mut plan = LaunchPlan::new(self.kind(), program);
+        plan.args(self.model_args(agent));
+        self.apply_permissions(&mut plan, &effective_tools(agent, req), req);
+        if req.interactive {
+            let path = req.config_dir.join("aider-prompt.md");
+            plan.files.push(LaunchFile {
+                path: path.clone(),
+                content: req.prompt.clone(),
+            });
+            plan.args(["--read".to_string(), path.display().to_string()]);
+        } else {
+            plan.args(self.prompt_args(&req.prompt, false));
+        }
+        Ok(plan)
+    }
+}
+
+/// A user-supplied command template.
+///
+/// The template is split on whitespace (no shell quoting). Placeholders:
+/// `{prompt}`, `{model}`, `{mcp_config}` (path to a generic `mcpServers`
+/// JSON file) and `{agent}` are substituted within arguments; an argument
+/// that is exactly `{allow}` or `{deny}` expands to one argument per pattern.
+pub struct CustomBackend {
+    template: String,
+}
+
+impl CustomBackend {
+    /// Create a custom backend from a command template.
+    pub fn new(template: impl Into<String>) -> Self {
+        Self {
+            template: template.into(),
+        }
+    }
+}
+
+impl AgentBackend for CustomBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Custom
+    }
+
+    fn program(&self) -> String {
+        self.template
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    fn apply_permissions(
+        &self,
+        _plan: &mut LaunchPlan,
+        _tools: &ToolPermissions,
+        _req: &LaunchRequest,
+    ) {
+        // Permissions are substituted through {allow} / {deny}
+    }
+
+    fn prompt_args(&self, _prompt: &str, _interactive: bool) -> Vec<String> {
+        // The prompt is substituted through {prompt}
+        Vec::new()
+    }
+
+    fn launch(&self, agent: &AgentDefinition, req: &LaunchRequest) -> Result<LaunchPlan> {
+        let mut words = self.template.split_whitespace();
+        let Some(first) = words.next() else {
+            return Err(Error::InvalidInput(format!(
+                "Agent '{}' uses the custom backend but its backend-command is empty",
+                agent.name
+            )));
+        };
+        let tools = effective_tools(agent, req);
+        let mcp_path = req.config_dir.join("mcp.json");
+        let model = agent.backend.model.clone().unwrap_or_default();
+        let substitute = |word: &str| {
+            word.replace("{prompt}", &req.prompt)
+                .replace("{model}", &model)
+                .replace("{mcp_config}", &mcp_path.display().to_string())
+                .replace("{agent}", &agent.name)
+        };
+
+        let program = req.program.clone().unwrap_or_else(|| substitute(first));
+        let mut plan = LaunchPlan::new(self.kind(), program);
+        for word in words {
+            match word {
+                "{allow}" => {
+                    plan.args(tools.allow.iter().cloned());
+                }
+                "{deny}" => {
+                    plan.args(tools.deny.iter().cloned());
+                }
+                _ => {
+                    plan.args([substitute(word)]);
+                }
+            }
+        }
+        if self.template.contains("{mcp_config}") {
+            plan.merge_json(
+                &mcp_path,
+                McpServer::bn(req.mcp_env.clone()).mcp_servers_json(json!({ "type": "stdio" })),
+            );
+        }
+        Ok(plan)
+    }
+}
+
+/// Script run by the fake backend.
+///
+/// Prints the prompt, appends its arguments to `$BN_FAKE_AGENT_LOG` (one per
+/// line) when set, and exits with `$BN_FAKE_AGENT_EXIT` (default 0).
+const FAKE_AGENT_SCRIPT: &str = r#"printf 'fake agent: %s\n' "$1"
+if [ -n "$BN_FAKE_AGENT_LOG" ]; then printf '%s\n' "$@" >> "$BN_FAKE_AGENT_LOG"; fi
+exit "${BN_FAKE_AGENT_EXIT:-0}""#;
+
+/// A local stand-in agent for testing the spawn pipeline without an AI CLI.
+pub struct FakeBackend;
+
+impl AgentBackend for FakeBackend {
+    fn kind(&self) -> BackendKind {
+        BackendKind::Fake
+    }
+
+    fn program(&self) -> String {
+        "sh".to_string()
+    }
+
+    fn apply_permissions(
+        &self,
+        plan: &mut LaunchPlan,
+        tools: &ToolPermissions,
+        req: &LaunchRequest,
+    ) {
+        if req.allow_all {
+            plan.args(["--allow-all"]);
+        }
+        for pattern in &tools.allow {
+            plan.args(["--allow", pattern]);
+        }
+        for pattern in &tools.deny {
+            plan.args(["--deny", pattern]);
+        }
+    }
+
+    fn apply_mcp(&self, plan: &mut LaunchPlan, server: &McpServer, config_dir: &Path) {
+        let path = config_dir.join("fake-mcp.json");
+        plan.merge_json(&path, server.mcp_servers_json(json!({ "type": "stdio" })));
+        plan.args(["--mcp-config".to_string(), path.display().to_string()]);
+    }
+
+    fn model_args(&self, _agent: &AgentDefinition) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn prompt_args(&self, _prompt: &str, interactive: bool) -> Vec<String> {
+        if interactive {
+            vec!["--interactive".to_string()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn launch(&self, agent: &AgentDefinition, req: &LaunchRequest) -> Result<LaunchPlan> {
+        // sh -c SCRIPT $0 $1 ...: the prompt is $1 so the script can echo it
+        let program = req.program.clone().unwrap_or_else(|| self.program());
+        let mut plan = LaunchPlan::new(self.kind(), program);
+        plan.args(["-c", FAKE_AGENT_SCRIPT, "bn-fake-agent"]);
+        plan.args([req.prompt.clone()]);
+        self.apply_permissions(&mut plan, &effective_tools(agent, req), req);
+        self.apply_mcp(
+            &mut plan,
+            &McpServer::bn(req.mcp_env.clone()),
+            &req.config_dir,
+        );
+        plan.args(self.prompt_args(&req.prompt, req.interactive));
+        Ok(plan)
+    }
+}
+
+/// Create the backend for a configuration.
+///
+/// # Errors
+/// Returns an error if the custom backend has no command template.
+pub fn backend_for(config: &BackendConfig) -> Result<Box<dyn AgentBackend>> {
+    Ok(match config.kind {
+        BackendKind::Copilot => Box::new(CopilotBackend),
+        BackendKind::Claude => Box::new(ClaudeBackend),
+        BackendKind::Codex => Box::new(CodexBackend),
+        BackendKind::OpenCode => Box::new(OpenCodeBackend),
+        BackendKind::Aider => Box::new(AiderBackend),
+        BackendKind::Fake => Box::new(FakeBackend),
+        BackendKind::Custom => {
+            let template = config.command.clone().ok_or_else(|| {
+                Error::InvalidInput(
+                    "The custom agent backend requires `backend-command` in config.kdl".to_string(),
+                )
+            })?;
+            Box::new(CustomBackend::new(template))
+        }
+    })
+}
+
+/// The backend for an agent, honoring `BN_AGENT_BACKEND`.
+pub fn effective_backend(agent: &AgentDefinition) -> Result<BackendConfig> {
+    let mut config = agent.backend.clone();
+    if let Ok(value) = std::env::var(BACKEND_ENV_VAR) {
+        if !value.trim().is_empty() {
+            config.kind = value.trim().parse()?;
+        }
+    }
+    Ok(config)
+}
+
+/// Environment for bn's MCP server: the passthrough vars that are set.
+pub fn mcp_env_from_process() -> BTreeMap<String, String> {
+    MCP_PASSTHROUGH_ENV
+        .iter()
+        .filter_map(|var| std::env::var(var).ok().map(|v| (var.to_string(), v)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::definitions::{ExecutionMode, LifecycleMode};
+
+    fn agent(backend: BackendConfig) -> AgentDefinition {
+        let mut agent = AgentDefinition::new(
+            "worker",
+            "Test worker",
+            ExecutionMode::Container,
+            LifecycleMode::Stateful,
+            "Prompt",
+        )
+        .with_tools(
+            ToolPermissions::new()
+                .allow("write")
+                .allow("shell(git:*)")
+                .allow("shell(git:log)")
+                .allow("binnacle")
+                .deny("binnacle(binnacle-orient)"),
+        );
+        agent.backend = backend;
+        agent
+    }
+
+    fn request() -> LaunchRequest {
+        LaunchRequest {
+            prompt: "do the thing".to_string(),
+            config_dir: PathBuf::from("/cfg"),
+            mcp_env: BTreeMap::from([("BN_AGENT_ID".to_string(), "bn-1234".to_string())]),
+            ..Default::default()
+        }
+    }
+
+    fn launch(kind: BackendKind, req: &LaunchRequest) -> LaunchPlan {
+        let config = BackendConfig {
+            kind,
+            ..Default::default()
+        };
+        backend_for(&config)
+            .unwrap()
+            .launch(&agent(config), req)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_backend_kind_parse() {
+        for kind in BackendKind::ALL {
+            assert_eq!(kind.to_string().parse::<BackendKind>().unwrap(), kind);
+        }
+        assert_eq!(
+            "Claude-Code".parse::<BackendKind>().unwrap(),
+            BackendKind::Claude
+        );
+        assert!("cursor".parse::<BackendKind>().is_err());
+    }
+
+    #[test]
+    fn test_tool_pattern_parse() {
+        assert_eq!(
+            ToolPattern::parse("shell(git:*)"),
+            ToolPattern::Shell {
+                command: "git",
+                args: Some("*")
+            }
+        );
+        assert_eq!(
+            ToolPattern::parse("shell(bn agent terminate:*)"),
+            ToolPattern::Shell {
+                command: "bn agent terminate",
+                args: Some("*")
+            }
+        );
+        assert_eq!(
+            ToolPattern::parse("binnacle(binnacle-orient)"),
+            ToolPattern::Mcp {
+                server: "binnacle",
+                tool: Some("binnacle-orient")
+            }
+        );
+        assert_eq!(
+            ToolPattern::parse("binnacle"),
+            ToolPattern::Mcp {
+                server: "binnacle",
+                tool: None
+            }
+        );
+        assert_eq!(ToolPattern::parse("write"), ToolPattern::Builtin("write"));
+        assert!(ToolPattern::parse("create").is_write());
+        assert!(!ToolPattern::parse("view").is_write());
+    }
+
+    #[test]
+    fn test_copilot_launch() {
+        let plan = launch(BackendKind::Copilot, &request());
+        let argv = plan.argv.join(" ");
+        assert_eq!(plan.argv[0], "copilot");
+        assert!(argv.contains("--allow-tool shell(git:*)"));
+        assert!(argv.contains("--deny-tool binnacle(binnacle-orient)"));
+        assert!(argv.contains("--additional-mcp-config @/cfg/copilot-mcp.json"));
+        assert_eq!(plan.argv[plan.argv.len() - 2..], ["-p", "do the thing"]);
+
+        let mcp: Value = serde_json::from_str(&plan.files[0].content).unwrap();
+        assert_eq!(mcp["mcpServers"]["binnacle"]["type"], "local");
+        assert_eq!(
+            mcp["mcpServers"]["binnacle"]["env"]["BN_AGENT_ID"],
+            "bn-1234"
+        );
+    }
+
+    #[test]
+    fn test_copilot_allow_all_keeps_denies() {
+        let req = LaunchRequest {
+            allow_all: true,
+            extra_deny: vec!["binnacle(binnacle-goodbye)".to_string()],
+            program: Some("/opt/copilot".to_string()),
+            interactive: true,
+            ..request()
+        };
+        let plan = launch(BackendKind::Copilot, &req);
+        assert_eq!(plan.argv[0], "/opt/copilot");
+        assert!(plan.argv.contains(&"--allow-all".to_string()));
+        assert!(!plan.argv.contains(&"--allow-tool".to_string()));
+        assert!(
+            plan.argv
+                .contains(&"binnacle(binnacle-goodbye)".to_string())
+        );
+        assert!(plan.argv.contains(&"-i".to_string()));
+    }
+
+    #[test]
+    fn test_claude_pattern_mapping() {
+        assert_eq!(ClaudeBackend::map_pattern("shell(git:*)"), ["Bash(git:*)"]);
+        assert_eq!(
+            ClaudeBackend::map_pattern("shell(git:log)"),
+            ["Bash(git log:*)"]
+        );
+        assert_eq!(ClaudeBackend::map_pattern("write"), ["Edit", "Write"]);
+        assert_eq!(ClaudeBackend::map_pattern("binnacle"), ["mcp__binnacle"]);
+        assert_eq!(
+            ClaudeBackend::map_pattern("binnacle(binnacle-orient)"),
+            ["mcp__binnacle__binnacle-orient"]
+        );
+        assert!(ClaudeBackend::map_pattern("lsp").is_empty());
+
+        let plan = launch(BackendKind::Claude, &request());
+        let allowed = plan
+            .argv
+            .iter()
+            .position(|a| a == "--allowedTools")
+            .unwrap();
+        assert_eq!(
+            plan.argv[allowed + 1],
+            "Edit,Write,Bash(git:*),Bash(git log:*),mcp__binnacle"
+        );
+        assert!(plan.argv.contains(&"/cfg/claude-mcp.json".to_string()));
+    }
+
+    #[test]
+    fn test_codex_launch() {
+        let plan = launch(BackendKind::Codex, &request());
+        assert_eq!(plan.argv[..2], ["codex", "exec"]);
+        assert!(plan.argv.contains(&"workspace-write".to_string()));
+        assert!(
+            plan.argv
+                .contains(&"mcp_servers.binnacle.args=[\"mcp\",\"serve\"]".to_string())
+        );
+        assert!(
+            plan.argv
+                .contains(&"mcp_servers.binnacle.env={BN_AGENT_ID=\"bn-1234\"}".to_string())
+        );
+        assert_eq!(plan.argv.last().unwrap(), "do the thing");
+        assert!(plan.files.is_empty());
+    }
+
+    #[test]
+    fn test_opencode_config_file() {
+        let plan = launch(BackendKind::OpenCode, &request());
+        assert_eq!(plan.argv[..2], ["opencode", "run"]);
+        assert_eq!(plan.env["OPENCODE_CONFIG"], "/cfg/opencode.json");
+        assert_eq!(plan.files.len(), 1);
+
+        let config: Value = serde_json::from_str(&plan.files[0].content).unwrap();
+        assert_eq!(config["permission"]["edit"], "allow");
+        assert_eq!(config["permission"]["bash"]["git *"], "allow");
+        assert_eq!(config["permission"]["bash"]["git log *"], "allow");
+        assert_eq!(config["permission"]["bash"]["*"], "ask");
+        assert_eq!(
+            config["mcp"]["binnacle"]["command"],
+            json!(["bn", "mcp", "serve"])
+        );
+    }
+
+    #[test]
+    fn test_aider_launch() {
+        let plan = launch(BackendKind::Aider, &request());
+        assert!(plan.argv.contains(&"--yes-always".to_string()));
+        assert_eq!(
+            plan.argv[plan.argv.len() - 2..],
+            ["--message", "do the thing"]
+        );
+
+        let read_only =
+            AgentDefinition::new("ask", "", ExecutionMode::Host, LifecycleMode::Stateless, "")
+                .with_tools(ToolPermissions::new().allow("view").deny("write"));
+        let plan = AiderBackend.launch(&read_only, &request()).unwrap();
+        assert!(plan.argv.contains(&"--dry-run".to_string()));
+        assert!(
+            plan.argv
+                .contains(&"--no-suggest-shell-commands".to_string())
+        );
+    }
+
+    #[test]
+    fn test_custom_template() {
+        let config = BackendConfig {
+            kind: BackendKind::Custom,
+            command: Some("my-agent --model {model} --cfg={mcp_config} {deny} -- {prompt}".into()),
+            model: Some("big".to_string()),
+        };
+        let plan = backend_for(&config)
+            .unwrap()
+            .launch(&agent(config.clone()), &request())
+            .unwrap();
+        assert_eq!(
+            plan.argv,
+            [
+                "my-agent",
+                "--model",
+                "big",
+                "--cfg=/cfg/mcp.json",
+                "binnacle(binnacle-orient)",
+                "--",
+                "do the thing"
+            ]
+        );
+        assert_eq!(plan.files[0].path, PathBuf::from("/cfg/mcp.json"));
+
+        let missing = BackendConfig {
+            kind: BackendKind::Custom,
+            ..Default::default()
+        };
+        assert!(backend_for(&missing).is_err());
+    }
+
+    #[test]
+    fn test_fake_backend_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("fake.log");
+        let req = LaunchRequest {
+            config_dir: dir.path().to_path_buf(),
+            ..request()
+        };
+        let plan = launch(BackendKind::Fake, &req);
+        plan.write_files().unwrap();
+        assert!(dir.path().join("fake-mcp.json").exists());
+
+        let output = std::process::Command::new(&plan.argv[0])
+            .args(&plan.argv[1..])
+            .env("BN_FAKE_AGENT_LOG", &log)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            "fake agent: do the thing\n"
+        );
+        let logged = std::fs::read_to_string(&log).unwrap();
+        assert!(logged.contains("--deny\nbinnacle(binnacle-orient)\n"));
+    }
+}
@@ -0,0 +1,299 @@
+//! Model usage budgets.
+//!
+//! Agents (or the wrapper that launched them) report token, request and cost
+//! usage with `bn agent usage report`. Each report is added to the agent's
+//! record and appended to `usage.jsonl`, attributed evenly to the tasks the
+//! agent is working on. Budgets from the `budgets` block of config.kdl (see
+//! [`BudgetConfig`]) are checked against that ledger:
+//!
+//! - **milestone**: usage attributed to the milestone's descendant tasks and bugs
+//! - **queue**: usage attributed to items currently in the work queue
+//! - **agent-type**: everything reported by agents of that type
+//!
+//! An exhausted milestone or queue budget pauses worker spawning (workers
+//! pick from the shared ready list, so any new worker may land on that
+//! work); an exhausted agent-type budget pauses that type. Workers learn
+//! about exhausted budgets from `bn agent usage report` and `bn ready` and
+//! are expected to `bn goodbye`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+
+use crate::Result;
+use crate::config::{BudgetConfig, BudgetLimits, BudgetScope};
+use crate::models::{Agent, AgentType, AgentUsage, EdgeType, UsageRecord};
+use crate::storage::Storage;
+
+/// A budget with the usage charged against it so far.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BudgetStatus {
+    pub scope: BudgetScope,
+    pub limits: BudgetLimits,
+    pub used: AgentUsage,
+    /// The limit that was reached, if any (e.g. "tokens 2100/2000")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exhausted: Option<String>,
+    /// Task and bug IDs covered by a milestone or queue budget
+    #[serde(skip)]
+    members: HashSet<String>,
+}
+
+impl BudgetStatus {
+    /// Agent types whose spawning this budget pauses while exhausted.
+    pub fn pauses(&self) -> Option<AgentType> {
+        self.exhausted.as_ref()?;
+        Some(match &self.scope {
+            BudgetScope::AgentType(agent_type) => parse_agent_type(agent_type)?,
+            BudgetScope::Milestone(_) | BudgetScope::Queue => AgentType::Worker,
+        })
+    }
+
+    /// Whether this exhausted budget covers work the agent is doing.
+    ///
+    /// Milestone and queue budgets apply to workers whose tasks are in scope;
+    /// agent-type budgets apply to every agent of that type.
+    pub fn applies_to(&self, agent: &Agent, tasks: &[String]) -> bool {
+        if self.exhausted.is_none() {
+            return false;
+        }
+        match &self.scope {
+            BudgetScope::AgentType(agent_type) => {
+                parse_agent_type(agent_type).as_ref() == Some(&agent.agent_type)
+            }
+            BudgetScope::Milestone(_) | BudgetScope::Queue => {
+                agent.agent_type == AgentType::Worker
+                    && tasks.iter().any(|t| self.members.contains(t))
+            }
+        }
+    }
+
+    /// One-line description, e.g. "milestone bn-a1b2 - tokens 2100/2000".
+    pub fn summary(&self) -> String {
+        match &self.exhausted {
+            Some(limit) => format!("{} - {}", self.scope, limit),
+            None => self.scope.to_string(),
+        }
+    }
+}
+
+fn parse_agent_type(agent_type: &str) -> Option<AgentType> {
+    match agent_type {
+        "worker" => Some(AgentType::Worker),
+        "planner" => Some(AgentType::Planner),
+        "buddy" => Some(AgentType::Buddy),
+        "ask" => Some(AgentType::Ask),
+        _ => None,
+    }
+}
+
+/// The first limit `used` has reached, formatted as "tokens 2100/2000".
+pub fn limit_reached(limits: &BudgetLimits, used: &AgentUsage) -> Option<String> {
+    if let Some(limit) = limits.tokens
+        && used.total_tokens() >= limit
+    {
+        return Some(format!("tokens {}/{}", used.total_tokens(), limit));
+    }
+    if let Some(limit) = limits.requests
+        && used.requests >= limit
+    {
+        return Some(format!("requests {}/{}", used.requests, limit));
+    }
+    if let Some(limit) = limits.cost_usd
+        && used.cost_usd >= limit
+    {
+        return Some(format!("cost-usd {:.2}/{:.2}", used.cost_usd, limit));
+    }
+    None
+}
+
+/// Usage charged to a set of tasks: each record is split evenly across its
+/// tasks and only the shares for `members` count.
+fn attributed_usage(records: &[UsageRecord], members: &HashSet<String>) -> AgentUsage {
+    let mut used = AgentUsage::default();
+    for record in records {
+        let share = record.usage.share(record.tasks.len());
+        let covered = record.tasks.iter().filter(|t| members.contains(*t)).count();
+        for _ in 0..covered {
+            used.add(&share);
+        }
+    }
+    used
+}
+
+/// Everything under `id` through `child_of` edges, at any depth.
+fn descendants(storage: &Storage, id: &str) -> Result<HashSet<String>> {
+    let mut members = HashSet::new();
+    let mut queue = VecDeque::from([id.to_string()]);
+    while let Some(parent) = queue.pop_front() {
+        for edge in storage.list_edges(Some(EdgeType::ChildOf), None, Some(&parent))? {
+            if edge.source != id && members.insert(edge.source.clone()) {
+                queue.push_back(edge.source);
+            }
+        }
+    }
+    Ok(members)
+}
+
+/// Check every configured budget against the usage ledger.
+pub fn evaluate_budgets(storage: &Storage, config: &BudgetConfig) -> Result<Vec<BudgetStatus>> {
+    if config.budgets.is_empty() {
+        return Ok(Vec::new());
+    }
+    let records = storage.list_usage_records()?;
+
+    let mut statuses = Vec::new();
+    for budget in &config.budgets {
+        let (used, members) = match &budget.scope {
+            BudgetScope::AgentType(agent_type) => {
+                let mut used = AgentUsage::default();
+                for record in records
+                    .iter()
+                    .filter(|r| parse_agent_type(agent_type).as_ref() == Some(&r.agent_type))
+                {
+                    used.add(&record.usage);
+                }
+                (used, HashSet::new())
+            }
+            BudgetScope::Milestone(id) => {
+                let members = descendants(storage, id)?;
+                (attributed_usage(&records, &members), members)
+            }
+            BudgetScope::Queue => {
+                let members: HashSet<String> = storage
+                    .get_queued_tasks()?
+                    .into_iter()
+                    .map(|t| t.core.id)
+                    .chain(storage.get_queued_bugs()?.into_iter().map(|b| b.core.id))
+                    .collect();
+                (attributed_usage(&records, &members), members)
+            }
+        };
+        statuses.push(BudgetStatus {
+            scope: budget.scope.clone(),
+            limits: budget.limits.clone(),
+            exhausted: limit_reached(&budget.limits, &used),
+            used,
+            members,
+        });
+    }
+    Ok(statuses)
+}
+
+/// Agent types whose spawning is paused by exhausted budgets.
+pub fn paused_agent_types(statuses: &[BudgetStatus]) -> Vec<AgentType> {
+    let mut paused = Vec::new();
+    for agent_type in statuses.iter().filter_map(BudgetStatus::pauses) {
+        if !paused.contains(&agent_type) {
+            paused.push(agent_type);
+        }
+    }
+    paused
+}
+
+/// Tasks an agent's usage is attributed to: `Agent::tasks` plus the targets
+/// of its `working_on` edges.
+pub fn attributed_tasks(storage: &Storage, agent: &Agent) -> Result<Vec<String>> {
+    let mut tasks = agent.tasks.clone();
+    for edge in storage.list_edges(Some(EdgeType::WorkingOn), Some(&agent.id), None)? {
+        if !tasks.contains(&edge.target) {
+            tasks.push(edge.target);
+        }
+    }
+    Ok(tasks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn record(agent_type: AgentType, tasks: &[&str], tokens: u64, cost_usd: f64) -> UsageRecord {
+        UsageRecord {
+            at: Utc::now(),
+            agent_id: "bn-a1b2".to_string(),
+            agent_type,
+            tasks: tasks.iter().map(|t| t.to_string()).collect(),
+            usage: AgentUsage {
+                input_tokens: tokens,
+                requests: 1,
+                cost_usd,
+                ..AgentUsage::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_limit_reached() {
+        let limits = BudgetLimits {
+            tokens: Some(1000),
+            cost_usd: Some(2.0),
+            ..BudgetLimits::default()
+        };
+        let mut used = AgentUsage {
+            input_tokens: 600,
+            output_tokens: 300,
+            cost_usd: 1.5,
+            ..AgentUsage::default()
+        };
+        assert_eq!(limit_reached(&limits, &used), None);
+
+        used.output_tokens = 400;
+        assert_eq!(
+            limit_reached(&limits, &used),
+            Some("tokens 1000/1000".to_string())
+        );
+
+        used.output_tokens = 0;
+        used.cost_usd = 2.25;
+        assert_eq!(
+            limit_reached(&limits, &used),
+            Some("cost-usd 2.25/2.00".to_string())
+        );
+    }
+
+    #[test]
+    fn test_attributed_usage_splits_across_tasks() {
+        let records = vec![
+            record(AgentType::Worker, &["bn-0001", "bn-0002"], 1000, 1.0),
+            record(AgentType::Worker, &["bn-0002"], 300, 0.5),
+            record(AgentType::Worker, &[], 5000, 9.0),
+        ];
+        let members: HashSet<String> = ["bn-0001".to_string()].into_iter().collect();
+        let used = attributed_usage(&records, &members);
+        assert_eq!(used.input_tokens, 500);
+        assert_eq!(used.cost_usd, 0.5);
+
+        let members: HashSet<String> = ["bn-0001".to_string(), "bn-0002".to_string()]
+            .into_iter()
+            .collect();
+        let used = attributed_usage(&records, &members);
+        assert_eq!(used.input_tokens, 1300);
+        assert_eq!(used.requests, 1);
+    }
+
+    #[test]
+    fn test_exhausted_budgets_pause_and_apply() {
+        let exhausted = |scope: BudgetScope, members: &[&str]| BudgetStatus {
+            scope,
+            limits: BudgetLimits::default(),
+            used: AgentUsage::default(),
+            exhausted: Some("tokens 10/10".to_string()),
+            members: members.iter().map(|m| m.to_string()).collect(),
+        };
+        let milestone = exhausted(BudgetScope::Milestone("bn-m001".to_string()), &["bn-0001"]);
+        let planners = exhausted(BudgetScope::AgentType("planner".to_string()), &[]);
+        assert_eq!(
+            paused_agent_types(&[milestone.clone(), planners.clone()]),
+            vec![AgentType::Worker, AgentType::Planner]
+        );
+
+        let worker = Agent::new(0, 0, "worker-a1b2".to_string(), AgentType::Worker);
+        assert!(milestone.applies_to(&worker, &["bn-0001".to_string()]));
+        assert!(!milestone.applies_to(&worker, &["bn-0002".to_string()]));
+        assert!(!planners.applies_to(&worker, &[]));
+
+        let planner = Agent::new(0, 0, "planner-a1b2".to_string(), AgentType::Planner);
+        assert!(planners.applies_to(&planner, &[]));
+        assert_eq!(planners.summary(), "agent-type planner - tokens 10/10");
+    }
+}
@@ -2,10 +2,12 @@
 //!
 //! This module defines the core types for agent definitions:
 //! - `AgentDefinition`: Full agent configuration with tools and prompt
-//! - `ToolPermissions`: Allowed and denied tool patterns
+//! - `ToolPermissions`: Allowed and denied tool patterns (also used for the
+//!   bn subcommands an agent may run)
 //! - `ExecutionMode`: Where the agent runs (Host or Container)
 //! - `LifecycleMode`: How the agent manages its session (Stateful or Stateless)
 
+use crate::agents::backend::BackendConfig;
 use serde::{Deserialize, Serialize};
 
 /// Execution mode for an agent.
@@ -165,11 +167,19 @@ pub struct AgentDefinition {
     pub lifecycle: LifecycleMode,
     /// Tool permissions (allowed and denied patterns).
     pub tools: ToolPermissions,
+    /// bn subcommands the agent may run, enforced by bn itself (see
+    /// [`crate::agents::policy`]). Patterns match command names such as
+    /// `task close`; `*` matches any text.
+    #[serde(default)]
+    pub commands: ToolPermissions,
     /// The agent's prompt content.
     pub prompt: String,
     /// Copilot-specific configuration.
     #[serde(default)]
     pub copilot: CopilotConfig,
+    /// Agent CLI backend that runs this agent.
+    #[serde(default)]
+    pub backend: BackendConfig,
 }
 
 impl AgentDefinition {
@@ -187,8 +197,10 @@ impl AgentDefinition {
             execution,
             lifecycle,
             tools: ToolPermissions::default(),
+            commands: ToolPermissions::default(),
             prompt: prompt.into(),
             copilot: CopilotConfig::default(),
+            backend: BackendConfig::default(),
         }
     }
 
@@ -198,12 +210,24 @@ impl AgentDefinition {
         self
     }
 
+    /// Set bn command permissions.
+    pub fn with_commands(mut self, commands: ToolPermissions) -> Self {
+        self.commands = commands;
+        self
+    }
+
     /// Set copilot configuration.
     pub fn with_copilot(mut self, copilot: CopilotConfig) -> Self {
         self.copilot = copilot;
         self
     }
 
+    /// Set the agent CLI backend.
+    pub fn with_backend(mut self, backend: BackendConfig) -> Self {
+        self.backend = backend;
+        self
+    }
+
     /// Check if this agent is stateful (manages session state).
     pub fn is_stateful(&self) -> bool {
         self.lifecycle == LifecycleMode::Stateful
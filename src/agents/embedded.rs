@@ -155,6 +155,73 @@ pub mod tool_sets {
     }
 }
 
+/// bn command permission sets for different agent types.
+///
+/// Unlike tool permissions, which are handed to the agent CLI, these are
+/// enforced by bn on every command an agent runs. Patterns match command
+/// names as they appear in the action log (e.g. `task close`).
+pub mod command_sets {
+    use super::*;
+
+    fn patterns(list: &[&str]) -> Vec<String> {
+        list.iter().map(|p| p.to_string()).collect()
+    }
+
+    /// Commands that delete data. Only humans and free agents run these.
+    const DELETES: &[&str] = &["* delete", "* rm", "session store clear"];
+
+    /// Workers can do anything except delete.
+    pub fn worker_commands() -> ToolPermissions {
+        ToolPermissions {
+            allow: Vec::new(),
+            deny: patterns(DELETES),
+        }
+    }
+
+    /// Planners create and organize work but leave closing it to workers.
+    pub fn planner_commands() -> ToolPermissions {
+        let mut deny = patterns(DELETES);
+        deny.extend(patterns(&["task close", "bug close", "issue close"]));
+        ToolPermissions {
+            allow: Vec::new(),
+            deny,
+        }
+    }
+
+    /// Buddies file work quickly and can't delete.
+    pub fn buddy_commands() -> ToolPermissions {
+        worker_commands()
+    }
+
+    /// Ask agents are read-only (apart from messaging and usage reports).
+    pub fn ask_commands() -> ToolPermissions {
+        ToolPermissions {
+            allow: patterns(&[
+                "show",
+                "status",
+                "ready",
+                "blocked",
+                "log",
+                "log show",
+                "* list",
+                "* show",
+                "* progress",
+                "search *",
+                "graph *",
+                "test flaky",
+                "doc history",
+                "config get",
+                "session status",
+                "agent transcript",
+                "agent usage *",
+                "msg *",
+                "ask",
+            ]),
+            deny: Vec::new(),
+        }
+    }
+}
+
 /// Get the embedded agent definition for a given agent type.
 ///
 /// Returns `None` if the agent type is not recognized.
@@ -192,6 +259,7 @@ fn worker_agent() -> AgentDefinition {
         WORKER_PROMPT.trim(),
     )
     .with_tools(tool_sets::worker_tools())
+    .with_commands(command_sets::worker_commands())
     .with_copilot(CopilotConfig::default())
 }
 
@@ -205,6 +273,7 @@ fn do_agent() -> AgentDefinition {
         DO_PROMPT_TEMPLATE.trim(),
     )
     .with_tools(tool_sets::worker_tools())
+    .with_commands(command_sets::worker_commands())
     .with_copilot(CopilotConfig::default())
 }
 
@@ -218,6 +287,7 @@ fn prd_agent() -> AgentDefinition {
         PRD_PROMPT.trim(),
     )
     .with_tools(tool_sets::prd_tools())
+    .with_commands(command_sets::planner_commands())
     .with_copilot(CopilotConfig::default())
 }
 
@@ -231,6 +301,7 @@ fn buddy_agent() -> AgentDefinition {
         BUDDY_PROMPT.trim(),
     )
     .with_tools(tool_sets::buddy_tools())
+    .with_commands(command_sets::buddy_commands())
     .with_copilot(CopilotConfig::default())
 }
 
@@ -244,6 +315,7 @@ fn ask_agent() -> AgentDefinition {
         ASK_PROMPT.trim(),
     )
     .with_tools(tool_sets::ask_tools())
+    .with_commands(command_sets::ask_commands())
     .with_copilot(CopilotConfig::default())
 }
 
@@ -0,0 +1,429 @@
+//! Agent handoff documents.
+//!
+//! When an agent leaves with work in progress, either by running
+//! `bn goodbye` or by being cleaned up after it went stale, each of its
+//! in-progress tasks and bugs gets a `handoff` doc so the next agent doesn't
+//! start cold. A handoff records:
+//!
+//! - **Commits** made since the agent claimed the task, from the task's
+//!   `bn/<task-id>` branch (or `HEAD`) and from `bn commit link`
+//! - **Tests** linked to the task with their latest result
+//! - **Recent actions**: the agent's last bn commands from the action log
+//! - **Next steps** the agent stated with `bn goodbye --next`
+//!
+//! The claim time is taken from the agent's `working_on` edge to the task.
+//! `bn orient` lists the latest handoff of every open task.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::Result;
+use crate::agents::worktree;
+use crate::git;
+use crate::models::{Agent, Doc, DocType, Edge, EdgeType, Editor, TaskStatus};
+use crate::storage::Storage;
+
+/// Most recent agent actions included in a handoff.
+const MAX_ACTIONS: usize = 10;
+
+/// Action log rows scanned for an agent's recent actions.
+const ACTION_SCAN_LIMIT: u32 = 500;
+
+/// The latest handoff for an open task.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HandoffRef {
+    pub doc_id: String,
+    pub task_id: String,
+    pub title: String,
+    /// Agent that wrote the handoff
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Title and status of a task or bug.
+fn work_item(storage: &Storage, id: &str) -> Option<(String, TaskStatus)> {
+    if let Ok(task) = storage.get_task(id) {
+        Some((task.core.title, task.status))
+    } else if let Ok(bug) = storage.get_bug(id) {
+        Some((bug.core.title, bug.status))
+    } else {
+        None
+    }
+}
+
+/// When `agent` claimed `task_id`, from its `working_on` edge.
+fn claimed_at(storage: &Storage, agent: &Agent, task_id: &str) -> Option<DateTime<Utc>> {
+    storage
+        .get_edges_between(&agent.id, task_id)
+        .ok()?
+        .into_iter()
+        .filter(|e| e.edge_type == EdgeType::WorkingOn)
+        .map(|e| e.created_at)
+        .min()
+}
+
+/// Commits made on the task since `since`, as `<short sha> <subject>`.
+///
+/// Reads the task branch when it exists, otherwise `HEAD`, and adds commits
+/// linked with `bn commit link` that git didn't report.
+fn commits_since(
+    storage: &Storage,
+    repo_root: Option<&Path>,
+    task_id: &str,
+    since: DateTime<Utc>,
+) -> Vec<String> {
+    let mut commits = Vec::new();
+    if let Some(root) = repo_root.filter(|r| r.exists()) {
+        let branch = worktree::branch_name(task_id);
+        let rev = if git::rev_parse(root, &branch).is_ok() {
+            branch.as_str()
+        } else {
+            "HEAD"
+        };
+        commits = git::commits_since(root, rev, since).unwrap_or_default();
+    }
+
+    let linked = storage.get_commits_for_task(task_id).unwrap_or_default();
+    for link in linked.into_iter().filter(|l| l.linked_at >= since) {
+        let short: String = link.sha.chars().take(7).collect();
+        if !commits.iter().any(|c| c.starts_with(&short)) {
+            commits.push(link.sha);
+        }
+    }
+    commits
+}
+
+/// Markdown list of the task's linked tests and their latest results.
+fn test_lines(storage: &Storage, task_id: &str) -> Vec<String> {
+    storage
+        .get_tests_for_task(task_id)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|test| {
+            let status = match storage.get_last_test_result(&test.id) {
+                Ok(Some(result)) if result.passed => "passed".to_string(),
+                Ok(Some(result)) => format!("failed (exit {})", result.exit_code),
+                _ => "not run".to_string(),
+            };
+            format!("- {} {}: {}", test.id, test.name, status)
+        })
+        .collect()
+}
+
+/// Markdown list of the agent's last bn commands since `since`, oldest first.
+fn action_lines(storage: &Storage, agent: &Agent, since: DateTime<Utc>) -> Vec<String> {
+    let after = since.to_rfc3339();
+    let mut lines: Vec<String> = storage
+        .query_action_logs(
+            Some(ACTION_SCAN_LIMIT),
+            None,
+            None,
+            Some(&after),
+            None,
+            None,
+            None,
+        )
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|log| log.agent_id.as_deref() == Some(agent.id.as_str()))
+        .take(MAX_ACTIONS)
+        .map(|log| {
+            let outcome = match (log.success, log.error) {
+                (true, _) => String::new(),
+                (false, Some(error)) => format!(" (failed: {})", error),
+                (false, None) => " (failed)".to_string(),
+            };
+            format!(
+                "- {} `bn {}`{}",
+                log.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                log.command,
+                outcome
+            )
+        })
+        .collect();
+    lines.reverse();
+    lines
+}
+
+/// Render one markdown section, with a placeholder when it is empty.
+fn section(out: &mut String, heading: &str, lines: &[String], empty: &str) {
+    out.push_str(&format!("\n\n## {}\n\n", heading));
+    if lines.is_empty() {
+        out.push_str(empty);
+    } else {
+        out.push_str(&lines.join("\n"));
+    }
+}
+
+/// Build the markdown content of a handoff.
+fn render(
+    storage: &Storage,
+    repo_root: Option<&Path>,
+    agent: &Agent,
+    task_id: &str,
+    reason: Option<&str>,
+    next_steps: &[String],
+) -> String {
+    let since = claimed_at(storage, agent, task_id).unwrap_or(agent.started_at);
+    let mut out = format!(
+        "# Summary\n\n{} ({}) stopped working on {}, claimed {}.",
+        agent.name,
+        agent.id,
+        task_id,
+        since.format("%Y-%m-%d %H:%M UTC")
+    );
+    if let Some(reason) = reason {
+        out.push_str(&format!("\n\nReason: {}", reason));
+    }
+
+    let next: Vec<String> = next_steps.iter().map(|s| format!("- {}", s)).collect();
+    section(&mut out, "Next Steps", &next, "_None stated._");
+    section(
+        &mut out,
+        "Commits",
+        &commits_since(storage, repo_root, task_id, since),
+        "_No commits since the task was claimed._",
+    );
+    section(
+        &mut out,
+        "Tests",
+        &test_lines(storage, task_id),
+        "_No tests linked to this task._",
+    );
+    section(
+        &mut out,
+        "Recent Actions",
+        &action_lines(storage, agent, since),
+        "_No actions recorded._",
+    );
+    out.push('\n');
+    out
+}
+
+/// Write a handoff doc for each in-progress task or bug of `agent`.
+///
+/// `repo_root` is where git history is read from; without it only linked
+/// commits are listed. Returns the IDs of the docs written.
+pub fn write_handoffs(
+    storage: &mut Storage,
+    repo_root: Option<&Path>,
+    agent: &Agent,
+    reason: Option<&str>,
+    next_steps: &[String],
+) -> Result<Vec<String>> {
+    let mut written = Vec::new();
+    for task_id in &agent.tasks {
+        let Some((title, status)) = work_item(storage, task_id) else {
+            continue;
+        };
+        if status != TaskStatus::InProgress {
+            continue;
+        }
+
+        let content = render(storage, repo_root, agent, task_id, reason, next_steps);
+        let doc_title = format!("Handoff: {}", title);
+        let id = storage.generate_unique_id("bn", &doc_title);
+        let mut doc = Doc::new(id.clone(), doc_title);
+        doc.doc_type = DocType::Handoff;
+        doc.core.description = reason.map(str::to_string);
+        doc.add_editor(Editor::agent(agent.id.clone()));
+        doc.set_content(&content)
+            .map_err(|e| crate::Error::InvalidInput(e.to_string()))?;
+        storage.add_doc(&doc)?;
+
+        let edge_id = storage.generate_edge_id(&id, task_id, EdgeType::Documents);
+        let edge = Edge::new(edge_id, id.clone(), task_id.clone(), EdgeType::Documents);
+        storage.add_edge(&edge)?;
+        written.push(id);
+    }
+    Ok(written)
+}
+
+/// The latest handoff of every open task or bug, newest first.
+pub fn open_handoffs(storage: &Storage) -> Result<Vec<HandoffRef>> {
+    let mut latest: HashMap<String, HandoffRef> = HashMap::new();
+    let mut closed: HashSet<String> = HashSet::new();
+    for doc in storage.list_docs(None, Some(&DocType::Handoff), None, None)? {
+        let agent_id = doc.editors.first().map(|e| e.identifier.clone());
+        let edges = storage.list_edges(Some(EdgeType::Documents), Some(&doc.core.id), None)?;
+        for edge in edges {
+            if closed.contains(&edge.target) {
+                continue;
+            }
+            let open = work_item(storage, &edge.target).is_some_and(|(_, status)| {
+                matches!(
+                    status,
+                    TaskStatus::Pending
+                        | TaskStatus::InProgress
+                        | TaskStatus::Blocked
+                        | TaskStatus::Reopened
+                )
+            });
+            if !open {
+                closed.insert(edge.target);
+                continue;
+            }
+            let newer = latest
+                .get(&edge.target)
+                .is_none_or(|h| h.created_at < doc.core.created_at);
+            if newer {
+                latest.insert(
+                    edge.target.clone(),
+                    HandoffRef {
+                        doc_id: doc.core.id.clone(),
+                        task_id: edge.target,
+                        title: doc.core.title.clone(),
+                        agent_id: agent_id.clone(),
+                        created_at: doc.core.created_at,
+                    },
+                );
+            }
+        }
+    }
+    let mut handoffs: Vec<HandoffRef> = latest.into_values().collect();
+    handoffs.sort_by_key(|h| std::cmp::Reverse(h.created_at));
+    Ok(handoffs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AgentType, Task, TestNode, TestResult};
+    use crate::test_utils::TestEnv;
+
+    fn setup() -> (TestEnv, Storage) {
+        let env = TestEnv::new();
+        let storage = env.init_storage();
+        (env, storage)
+    }
+
+    fn create_task(storage: &mut Storage, id: &str, status: TaskStatus) {
+        let mut task = Task::new(id.to_string(), format!("Task {}", id));
+        task.status = status;
+        storage.create_task(&task).unwrap();
+    }
+
+    /// Register an agent working on `tasks` and return it as stored.
+    fn agent_on(storage: &mut Storage, tasks: &[&str]) -> Agent {
+        let agent = Agent::new_with_id(
+            "bn-a1b2".to_string(),
+            0,
+            0,
+            "worker-a1b2".to_string(),
+            AgentType::Worker,
+        );
+        storage.register_agent(&agent).unwrap();
+        for task_id in tasks {
+            let agent = storage.get_agent_by_id("bn-a1b2").unwrap();
+            storage.agent_add_task_by_agent(agent, task_id).unwrap();
+        }
+        storage.get_agent_by_id("bn-a1b2").unwrap()
+    }
+
+    #[test]
+    fn test_handoff_written_for_in_progress_tasks_only() {
+        let (_env, mut storage) = setup();
+        create_task(&mut storage, "bn-t001", TaskStatus::InProgress);
+        create_task(&mut storage, "bn-t002", TaskStatus::Pending);
+        storage
+            .create_test(&TestNode::new(
+                "bnt-0001".to_string(),
+                "Parser works".to_string(),
+                "false".to_string(),
+            ))
+            .unwrap();
+        storage.link_test_to_task("bnt-0001", "bn-t001").unwrap();
+        storage
+            .save_test_result(&TestResult {
+                test_id: "bnt-0001".to_string(),
+                passed: false,
+                exit_code: 1,
+                stdout: None,
+                stderr: None,
+                duration_ms: 5,
+                timed_out: false,
+                commit: None,
+                executed_at: Utc::now(),
+            })
+            .unwrap();
+        let agent = agent_on(&mut storage, &["bn-t001", "bn-t002"]);
+        storage.link_commit("abc1234def", "bn-t001").unwrap();
+
+        let written = write_handoffs(
+            &mut storage,
+            None,
+            &agent,
+            Some("Out of context"),
+            &["Finish the parser".to_string()],
+        )
+        .unwrap();
+        assert_eq!(written.len(), 1);
+
+        let doc = storage.get_doc(&written[0]).unwrap();
+        assert_eq!(doc.doc_type, DocType::Handoff);
+        assert_eq!(doc.core.title, "Handoff: Task bn-t001");
+        assert_eq!(doc.editors, vec![Editor::agent("bn-a1b2".to_string())]);
+        let content = doc.get_content().unwrap();
+        assert!(
+            content.starts_with("# Summary\n\nworker-a1b2 (bn-a1b2) stopped working on bn-t001")
+        );
+        assert!(content.contains("Reason: Out of context"));
+        assert!(content.contains("## Next Steps\n\n- Finish the parser"));
+        assert!(content.contains("## Commits\n\nabc1234def"));
+        assert!(content.contains("- bnt-0001 Parser works: failed (exit 1)"));
+        assert!(content.contains("## Recent Actions\n\n_No actions recorded._"));
+
+        let edges = storage
+            .list_edges(Some(EdgeType::Documents), Some(&written[0]), None)
+            .unwrap();
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].target, "bn-t001");
+    }
+
+    #[test]
+    fn test_open_handoffs_keeps_latest_per_open_task() {
+        let (_env, mut storage) = setup();
+        create_task(&mut storage, "bn-t001", TaskStatus::InProgress);
+        create_task(&mut storage, "bn-t002", TaskStatus::InProgress);
+        let agent = agent_on(&mut storage, &["bn-t001", "bn-t002"]);
+
+        let first = write_handoffs(&mut storage, None, &agent, None, &[]).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        let second = write_handoffs(&mut storage, None, &agent, None, &[]).unwrap();
+        assert_eq!(first.len(), 2);
+
+        let mut done = storage.get_task("bn-t002").unwrap();
+        done.status = TaskStatus::Done;
+        storage.update_task(&done).unwrap();
+
+        let handoffs = open_handoffs(&storage).unwrap();
+        assert_eq!(handoffs.len(), 1);
+        assert_eq!(handoffs[0].task_id, "bn-t001");
+        assert_eq!(handoffs[0].doc_id, second[0]);
+        assert_eq!(handoffs[0].agent_id.as_deref(), Some("bn-a1b2"));
+    }
+
+    #[test]
+    fn test_stale_cleanup_writes_handoff() {
+        let (_env, mut storage) = setup();
+        create_task(&mut storage, "bn-t001", TaskStatus::InProgress);
+        // A PID that is not running, so cleanup removes the agent
+        let agent = Agent::new(u32::MAX - 1, 0, "gone".to_string(), AgentType::Worker);
+        storage.register_agent(&agent).unwrap();
+        let agent = storage.get_agent(u32::MAX - 1).unwrap();
+        storage.agent_add_task_by_agent(agent, "bn-t001").unwrap();
+
+        assert_eq!(storage.cleanup_stale_agents().unwrap(), vec![u32::MAX - 1]);
+        let handoffs = open_handoffs(&storage).unwrap();
+        assert_eq!(handoffs.len(), 1);
+        let content = storage
+            .get_doc(&handoffs[0].doc_id)
+            .unwrap()
+            .get_content()
+            .unwrap();
+        assert!(content.contains("Reason: Agent process exited without saying goodbye"));
+    }
+}
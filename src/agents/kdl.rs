@@ -18,12 +18,23 @@
 //!     show-reasoning #true
 //!     render-markdown #true
 //!     
+//!     // Agent CLI backend (optional, default "copilot")
+//!     backend "claude"
+//!     backend-model "opus"
+//!     backend-command "my-agent {prompt}"  // required for backend "custom"
+//!     
 //!     tools {
 //!         allow "write"
 //!         allow "shell(bn:*)"
 //!         deny "shell(bn agent terminate:*)"
 //!     }
 //!     
+//!     // bn subcommands the agent may run (enforced by bn itself)
+//!     commands {
+//!         allow "task create"
+//!         deny "task close"
+//!     }
+//!     
 //!     // Optional: override prompt from file
 //!     prompt-file "worker/prompt.md"
 //! }
@@ -38,6 +49,7 @@
 //! 4. **Project** (.binnacle/agents/config.kdl) - Repo-specific customizations
 
 use crate::Error;
+use crate::agents::backend::BackendKind;
 use crate::agents::definitions::{AgentDefinition, ExecutionMode, LifecycleMode, ToolPermissions};
 use kdl::{KdlDocument, KdlNode};
 use std::path::Path;
@@ -60,6 +72,10 @@ pub struct AgentOverride {
     pub tools_allow: Vec<String>,
     /// Tools to add to deny list.
     pub tools_deny: Vec<String>,
+    /// bn commands to add to allow list.
+    pub commands_allow: Vec<String>,
+    /// bn commands to add to deny list.
+    pub commands_deny: Vec<String>,
     /// Path to custom prompt file (relative to config location).
     pub prompt_file: Option<String>,
     /// Inline prompt content.
@@ -72,6 +88,12 @@ pub struct AgentOverride {
     pub show_reasoning: Option<bool>,
     /// Override render_markdown.
     pub render_markdown: Option<bool>,
+    /// Override agent CLI backend.
+    pub backend: Option<BackendKind>,
+    /// Override custom backend command template.
+    pub backend_command: Option<String>,
+    /// Override backend model.
+    pub backend_model: Option<String>,
 }
 
 impl AgentOverride {
@@ -118,6 +140,15 @@ impl AgentOverride {
             result.tools.merge(&overlay);
         }
 
+        // Merge command permissions (additive)
+        if !self.commands_allow.is_empty() || !self.commands_deny.is_empty() {
+            let overlay = ToolPermissions {
+                allow: self.commands_allow.clone(),
+                deny: self.commands_deny.clone(),
+            };
+            result.commands.merge(&overlay);
+        }
+
         // Override prompt if prompt_file is set
         if let Some(ref prompt_file) = self.prompt_file {
             if let Some(base_path) = prompt_base_path {
@@ -147,6 +178,17 @@ impl AgentOverride {
             result.copilot.render_markdown = render_markdown;
         }
 
+        // Merge backend config
+        if let Some(backend) = self.backend {
+            result.backend.kind = backend;
+        }
+        if let Some(ref command) = self.backend_command {
+            result.backend.command = Some(command.clone());
+        }
+        if let Some(ref model) = self.backend_model {
+            result.backend.model = Some(model.clone());
+        }
+
         result
     }
 }
@@ -227,8 +269,30 @@ fn parse_agent_node(node: &KdlNode) -> Result<AgentOverride, Error> {
                         override_def.render_markdown = Some(val);
                     }
                 }
+                "backend" => {
+                    if let Some(backend) = get_string_arg(child) {
+                        override_def.backend = Some(backend.parse()?);
+                    }
+                }
+                "backend-command" => {
+                    if let Some(command) = get_string_arg(child) {
+                        override_def.backend_command = Some(command);
+                    }
+                }
+                "backend-model" => {
+                    if let Some(model) = get_string_arg(child) {
+                        override_def.backend_model = Some(model);
+                    }
+                }
                 "tools" => {
-                    parse_tools_node(child, &mut override_def)?;
+                    let (allow, deny) = parse_allow_deny(child);
+                    override_def.tools_allow.extend(allow);
+                    override_def.tools_deny.extend(deny);
+                }
+                "commands" => {
+                    let (allow, deny) = parse_allow_deny(child);
+                    override_def.commands_allow.extend(allow);
+                    override_def.commands_deny.extend(deny);
                 }
                 _ => {
                     // Ignore unknown fields for forward compatibility
@@ -240,28 +304,23 @@ fn parse_agent_node(node: &KdlNode) -> Result<AgentOverride, Error> {
     Ok(override_def)
 }
 
-/// Parse a tools block.
-fn parse_tools_node(node: &KdlNode, override_def: &mut AgentOverride) -> Result<(), Error> {
+/// Parse the `allow`/`deny` children of a block.
+fn parse_allow_deny(node: &KdlNode) -> (Vec<String>, Vec<String>) {
+    let mut allow = Vec::new();
+    let mut deny = Vec::new();
     if let Some(children) = node.children() {
         for child in children.nodes() {
-            match child.name().value() {
-                "allow" => {
-                    if let Some(pattern) = get_string_arg(child) {
-                        override_def.tools_allow.push(pattern);
-                    }
-                }
-                "deny" => {
-                    if let Some(pattern) = get_string_arg(child) {
-                        override_def.tools_deny.push(pattern);
-                    }
-                }
-                _ => {
-                    // Ignore unknown fields
-                }
+            let list = match child.name().value() {
+                "allow" => &mut allow,
+                "deny" => &mut deny,
+                _ => continue,
+            };
+            if let Some(pattern) = get_string_arg(child) {
+                list.push(pattern);
             }
         }
     }
-    Ok(())
+    (allow, deny)
 }
 
 /// Get a string argument from a node's first entry.
@@ -346,6 +405,37 @@ mod tests {
         assert_eq!(prd.tools_deny, vec!["shell(rm:*)"]);
     }
 
+    #[test]
+    fn test_parse_agent_override_with_commands() {
+        let kdl = r#"
+            agent "worker" {
+                commands {
+                    allow "task *"
+                    deny "task close"
+                }
+            }
+        "#;
+
+        let doc: KdlDocument = kdl.parse().unwrap();
+        let overrides = parse_agent_overrides(&doc).unwrap();
+        let worker = &overrides[0];
+        assert_eq!(worker.commands_allow, vec!["task *"]);
+        assert_eq!(worker.commands_deny, vec!["task close"]);
+        assert!(worker.tools_allow.is_empty());
+
+        let base = AgentDefinition::new(
+            "worker",
+            "Worker",
+            ExecutionMode::Container,
+            LifecycleMode::Stateful,
+            "Prompt",
+        )
+        .with_commands(ToolPermissions::new().deny("* delete"));
+        let result = worker.apply_to(&base, None);
+        assert_eq!(result.commands.allow, vec!["task *"]);
+        assert_eq!(result.commands.deny, vec!["* delete", "task close"]);
+    }
+
     #[test]
     fn test_parse_agent_override_with_prompt_file() {
         let kdl = r#"
@@ -402,12 +492,17 @@ mod tests {
             lifecycle: None, // Keep original
             tools_allow: vec!["custom-tool".to_string()],
             tools_deny: vec![],
+            commands_allow: vec![],
+            commands_deny: vec![],
             prompt_file: None,
             prompt: Some("Custom prompt".to_string()),
             model: None,
             reasoning_effort: None,
             show_reasoning: None,
             render_markdown: None,
+            backend: None,
+            backend_command: None,
+            backend_model: None,
         };
 
         let result = override_def.apply_to(&base, None);
@@ -635,4 +730,39 @@ mod tests {
         assert_eq!(worker.render_markdown, Some(true));
         assert_eq!(worker.tools_allow, vec!["write"]);
     }
+
+    #[test]
+    fn test_parse_and_apply_backend() {
+        let kdl = r#"
+            agent "buddy" {
+                backend "custom"
+                backend-command "my-agent {prompt}"
+                backend-model "big"
+            }
+        "#;
+
+        let doc: KdlDocument = kdl.parse().unwrap();
+        let overrides = parse_agent_overrides(&doc).unwrap();
+        let buddy = &overrides[0];
+        assert_eq!(buddy.backend, Some(BackendKind::Custom));
+
+        let base = AgentDefinition::new(
+            "buddy",
+            "Test buddy",
+            ExecutionMode::Host,
+            LifecycleMode::Stateful,
+            "Prompt",
+        );
+        let result = buddy.apply_to(&base, None);
+        assert_eq!(result.backend.kind, BackendKind::Custom);
+        assert_eq!(result.backend.command.as_deref(), Some("my-agent {prompt}"));
+        assert_eq!(result.backend.model.as_deref(), Some("big"));
+    }
+
+    #[test]
+    fn test_parse_invalid_backend_errors() {
+        let kdl = r#"agent "worker" { backend "cursor" }"#;
+        let doc: KdlDocument = kdl.parse().unwrap();
+        assert!(parse_agent_overrides(&doc).is_err());
+    }
 }
@@ -38,7 +38,13 @@
 //!         deny "shell(rm:*)"
 //!     }
 //!     
+//!     commands {                 // bn subcommands, enforced by bn itself
+//!         deny "task close"
+//!     }
+//!     
 //!     prompt-file "worker/custom-prompt.md"  // Optional custom prompt
+//!
+//!     backend "claude"  // copilot | claude | codex | opencode | aider | custom | fake
 //! }
 //! ```
 //!
@@ -59,19 +65,34 @@
 //! }
 //! ```
 
+pub mod backend;
+pub mod budget;
 pub mod definitions;
 pub mod embedded;
+pub mod handoff;
 pub mod kdl;
+pub mod policy;
 pub mod resolver;
+pub mod supervisor;
+pub mod transcript;
+pub mod worktree;
 
 // Re-export commonly used types
+pub use backend::{
+    AgentBackend, BackendConfig, BackendKind, LaunchPlan, LaunchRequest, backend_for,
+    effective_backend,
+};
+pub use budget::{BudgetStatus, evaluate_budgets, paused_agent_types};
 pub use definitions::{
     AGENT_ASK, AGENT_BUDDY, AGENT_DO, AGENT_FREE, AGENT_PRD, AGENT_TYPES, AGENT_WORKER,
     AgentDefinition, CopilotConfig, ExecutionMode, LifecycleMode, ToolPermissions,
 };
 pub use embedded::{do_prompt, get_all_embedded_agents, get_embedded_agent};
+pub use handoff::{HandoffRef, open_handoffs, write_handoffs};
 pub use kdl::{AgentOverride, load_overrides_from_file, parse_agent_overrides};
 pub use resolver::{
     AgentPaths, AgentResolver, ResolvedAgent, resolve_agent, resolve_agent_for_repo,
     resolve_all_agents, resolve_all_agents_for_repo,
 };
+pub use supervisor::{Supervisor, SupervisorStatus, supervise};
+pub use transcript::{TimelineItem, TranscriptLink, agent_timeline, task_transcripts};
@@ -0,0 +1,192 @@
+//! Capability policy for the bn commands an agent may run.
+//!
+//! Tool permissions are handed to the agent CLI and enforced there. The
+//! `commands` permissions of an agent definition are enforced by bn itself:
+//! before running a command, bn finds the calling agent's type and checks the
+//! command name (as recorded in the action log, e.g. `task close`) against the
+//! definition's allow and deny patterns. Denials fail the command, which the
+//! action log records like any other failure.
+//!
+//! Callers that aren't agents (humans) are never restricted. An agent's type
+//! is fixed when it registers: `bn orient` refuses to change it, so an agent
+//! can't swap its policy for a more permissive one.
+
+use crate::agents::definitions::{
+    AGENT_ASK, AGENT_BUDDY, AGENT_PRD, AGENT_TYPES, AGENT_WORKER, ToolPermissions,
+};
+use crate::models::Agent;
+use crate::models::AgentType;
+
+/// Commands every agent may run so it can register and leave.
+const LIFECYCLE_COMMANDS: &[&str] = &["orient", "goodbye"];
+
+/// Whether `command` is one every agent may run (`orient`, `goodbye`).
+pub fn is_lifecycle(command: &str) -> bool {
+    LIFECYCLE_COMMANDS.contains(&command)
+}
+
+/// Commands the container entrypoint runs before any agent is launched.
+const CONTAINER_SETUP_COMMANDS: &[&str] = &["system host-init"];
+
+/// Whether `command` is container setup (`system host-init`), which runs
+/// without an agent identity.
+pub fn is_container_setup(command: &str) -> bool {
+    CONTAINER_SETUP_COMMANDS.contains(&command)
+}
+
+/// The agent definition that applies to a registered agent type.
+pub fn definition_for(agent_type: &AgentType) -> &'static str {
+    match agent_type {
+        AgentType::Worker => AGENT_WORKER,
+        AgentType::Planner => AGENT_PRD,
+        AgentType::Buddy => AGENT_BUDDY,
+        AgentType::Ask => AGENT_ASK,
+    }
+}
+
+/// The type an agent launched from a definition registers as
+/// (`do` and `free` agents are workers).
+pub fn agent_type_for_definition(name: &str) -> AgentType {
+    match name.trim().to_lowercase().as_str() {
+        AGENT_PRD | "planner" => AgentType::Planner,
+        AGENT_BUDDY => AgentType::Buddy,
+        AGENT_ASK => AgentType::Ask,
+        _ => AgentType::Worker,
+    }
+}
+
+/// Refuse to re-register `agent` under a different type, which would swap
+/// its command policy.
+pub fn check_type_unchanged(agent: &Agent, requested: &AgentType) -> Result<(), String> {
+    if agent.agent_type == *requested {
+        return Ok(());
+    }
+    Err(format!(
+        "agent {} is registered as {}; run `bn goodbye` before orienting as {}",
+        agent.id,
+        definition_for(&agent.agent_type),
+        definition_for(requested)
+    ))
+}
+
+/// The agent definition named by `BN_AGENT_TYPE`: a definition name
+/// (`worker`, `do`, ...) or a registered type (`planner`).
+pub fn definition_for_name(name: &str) -> Option<&'static str> {
+    let name = name.trim().to_lowercase();
+    if name == "planner" {
+        return Some(AGENT_PRD);
+    }
+    AGENT_TYPES.iter().copied().find(|t| *t == name)
+}
+
+/// Whether `pattern` matches `command`. `*` matches any text, including
+/// spaces, so `* delete` matches `task delete` and `graph *` matches every
+/// graph subcommand.
+fn pattern_matches(pattern: &str, command: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == command;
+    }
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if command.len() < first.len() + last.len()
+        || !command.starts_with(first)
+        || !command.ends_with(last)
+    {
+        return false;
+    }
+    let mut rest = &command[first.len()..command.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Check a command against a permission set.
+///
+/// Deny patterns win. A non-empty allow list makes the policy an allow list:
+/// commands it doesn't match are denied. Returns the reason for a denial.
+pub fn check(permissions: &ToolPermissions, command: &str) -> Result<(), String> {
+    if is_lifecycle(command) {
+        return Ok(());
+    }
+    if let Some(pattern) = permissions
+        .deny
+        .iter()
+        .find(|p| pattern_matches(p, command))
+    {
+        return Err(format!("denied by '{}'", pattern));
+    }
+    if !permissions.allow.is_empty()
+        && !permissions
+            .allow
+            .iter()
+            .any(|p| pattern_matches(p, command))
+    {
+        return Err("not in the allowed commands".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::embedded::command_sets;
+
+    #[test]
+    fn test_pattern_matches() {
+        assert!(pattern_matches("task close", "task close"));
+        assert!(!pattern_matches("task close", "task closed"));
+        assert!(pattern_matches("* delete", "task delete"));
+        assert!(pattern_matches("* delete", "milestone delete"));
+        assert!(!pattern_matches("* delete", "delete"));
+        assert!(pattern_matches("graph *", "graph lineage"));
+        assert!(pattern_matches("*", "anything at all"));
+        assert!(pattern_matches("agent * report", "agent usage report"));
+        assert!(!pattern_matches("agent * report", "agent usage show"));
+    }
+
+    #[test]
+    fn test_default_policies() {
+        let worker = command_sets::worker_commands();
+        assert!(check(&worker, "task close").is_ok());
+        assert!(check(&worker, "task delete").is_err());
+        assert!(check(&worker, "link rm").is_err());
+
+        let planner = command_sets::planner_commands();
+        assert!(check(&planner, "task create").is_ok());
+        assert_eq!(
+            check(&planner, "task close"),
+            Err("denied by 'task close'".to_string())
+        );
+
+        let ask = command_sets::ask_commands();
+        assert!(check(&ask, "task list").is_ok());
+        assert!(check(&ask, "graph lineage").is_ok());
+        assert!(check(&ask, "goodbye").is_ok());
+        assert!(check(&ask, "task create").is_err());
+        assert!(check(&ask, "test run").is_err());
+    }
+
+    #[test]
+    fn test_definition_lookup() {
+        assert_eq!(definition_for(&AgentType::Planner), AGENT_PRD);
+        assert_eq!(definition_for_name("planner"), Some(AGENT_PRD));
+        assert_eq!(definition_for_name("Worker"), Some(AGENT_WORKER));
+        assert_eq!(definition_for_name("do"), Some("do"));
+        assert_eq!(definition_for_name("robot"), None);
+        assert_eq!(agent_type_for_definition("prd"), AgentType::Planner);
+        assert_eq!(agent_type_for_definition("do"), AgentType::Worker);
+        assert_eq!(agent_type_for_definition("ask"), AgentType::Ask);
+    }
+
+    #[test]
+    fn test_type_change_refused() {
+        let agent = Agent::new(42, 43, "a".to_string(), AgentType::Ask);
+        assert!(check_type_unchanged(&agent, &AgentType::Ask).is_ok());
+        let err = check_type_unchanged(&agent, &AgentType::Worker).unwrap_err();
+        assert!(err.contains("registered as ask"));
+    }
+}
@@ -0,0 +1,939 @@
+//! Long-running agent pool supervisor.
+//!
+//! `bn agent reconcile` runs a single pass. The supervisor repeats that pass
+//! on an interval, either in the foreground (`bn agent supervise`) or inside
+//! the session server (`bn session serve --supervise`), and adds:
+//!
+//! - **Crash restarts**: a container agent whose container is no longer
+//!   running (and that never called goodbye) is removed from the registry and
+//!   restarted under the same name after an exponential backoff. The delay
+//!   starts at `backoff-secs`, doubles per consecutive crash and is capped at
+//!   `max-backoff-secs`; an agent that ran longer than the cap before crashing
+//!   starts over at `backoff-secs`.
+//! - **Timeouts**: container agents that exceed their type's wall-clock or
+//!   idle limit are stopped. The next reconciliation replaces them if they
+//!   are still needed.
+//! - **Budgets**: while a budget from the `budgets` block is exhausted (see
+//!   [`crate::agents::budget`]), spawning and restarts of the agent types it
+//!   covers are paused. Pausing and resuming are reported as events.
+//!
+//! Scaling still comes from `bn agent scale`: workers follow the ready-queue
+//! depth between `min` and `max`, other types are held at `min`. Slots with a
+//! pending restart are reserved so reconciliation does not spawn around the
+//! backoff. Settings live in the `supervisor` block of config.kdl (see
+//! [`SupervisorConfig`]).
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+
+use crate::agents::budget::{BudgetStatus, evaluate_budgets, paused_agent_types};
+use crate::agents::embedded::get_embedded_agent;
+use crate::commands::{
+    AgentTypeCounts, Output, ReconcileHold, agent_reconcile_with_hold, agent_spawn,
+    agent_worktree_gc, container_stop, merge_queue_run,
+};
+use crate::config::SupervisorConfig;
+use crate::container::runtime::find_runtime;
+use crate::models::{Agent, AgentType, MergeQueueStatus};
+use crate::storage::Storage;
+use crate::{Error, Result};
+
+/// Number of recent events kept in [`SupervisorStatus::events`].
+pub const MAX_EVENTS: usize = 100;
+
+/// What the supervisor did (or would do, in dry-run mode).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SupervisorEventKind {
+    /// Reconciliation spawned an agent
+    Spawn,
+    /// Reconciliation stopped an agent (scale-down or goodbye)
+    Stop,
+    /// A container exited without calling goodbye
+    Crash,
+    /// A crashed agent was started again
+    Restart,
+    /// A pending restart was dropped because the slot is no longer needed
+    Cancel,
+    /// An agent exceeded its wall-clock or idle limit and was stopped
+    Timeout,
+    /// Spawning an agent type was paused because a budget is exhausted
+    Pause,
+    /// Spawning an agent type resumed after its budgets recovered
+    Resume,
+    /// The merge queue merged a task branch or rejected it
+    Merge,
+    /// The worktree of a finished task was removed
+    Cleanup,
+}
+
+impl std::fmt::Display for SupervisorEventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Spawn => "spawn",
+            Self::Stop => "stop",
+            Self::Crash => "crash",
+            Self::Restart => "restart",
+            Self::Cancel => "cancel",
+            Self::Timeout => "timeout",
+            Self::Pause => "pause",
+            Self::Resume => "resume",
+            Self::Merge => "merge",
+            Self::Cleanup => "cleanup",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One supervisor event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SupervisorEvent {
+    /// When the event happened
+    pub at: DateTime<Utc>,
+    /// Pass that produced the event
+    pub pass: u64,
+    /// Event kind
+    pub kind: SupervisorEventKind,
+    /// Agent type affected
+    pub agent_type: String,
+    /// Agent ID, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+    /// Agent name, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_name: Option<String>,
+    /// Human-readable reason
+    pub reason: String,
+    /// Whether the action was carried out (false in dry-run mode or on failure)
+    pub executed: bool,
+}
+
+/// A crashed agent waiting out its backoff.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingRestart {
+    /// Name the agent is restarted under
+    pub agent_name: String,
+    /// Agent type
+    pub agent_type: String,
+    /// Consecutive crashes, including the one that scheduled this restart
+    pub crashes: u32,
+    /// Earliest time the restart happens
+    pub restart_at: DateTime<Utc>,
+}
+
+/// A registered agent as seen by the supervisor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SupervisedAgent {
+    pub id: String,
+    pub name: String,
+    pub agent_type: String,
+    /// Activity status (active, idle, stale)
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub last_activity_at: DateTime<Utc>,
+    /// Consecutive crashes recorded for this agent name
+    pub crashes: u32,
+}
+
+/// Snapshot of the supervisor, served on `/api/agents` and the WebSocket.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SupervisorStatus {
+    /// When the supervisor started
+    pub started_at: DateTime<Utc>,
+    /// When the last pass finished
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_pass_at: Option<DateTime<Utc>>,
+    /// Number of passes run so far
+    pub passes: u64,
+    /// Delay between passes
+    pub interval_secs: u64,
+    /// Whether actions are only reported
+    pub dry_run: bool,
+    /// Ready work count (tasks + bugs) seen by the last pass
+    pub work_count: usize,
+    /// Agent counts by type before the last pass acted
+    pub current_counts: AgentTypeCounts,
+    /// Desired agent counts by type
+    pub desired_counts: AgentTypeCounts,
+    /// Registered agents after the last pass
+    pub agents: Vec<SupervisedAgent>,
+    /// Crashed agents waiting to be restarted
+    pub pending_restarts: Vec<PendingRestart>,
+    /// Configured budgets as of the last pass
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub budgets: Vec<BudgetStatus>,
+    /// Agent types whose spawning is paused by exhausted budgets
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paused: Vec<String>,
+    /// Recent events, oldest first (at most [`MAX_EVENTS`])
+    pub events: Vec<SupervisorEvent>,
+    /// Error from the last pass, if it failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+impl Output for SupervisorStatus {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        let mut lines = vec![format!(
+            "Supervisor pass {}{}:",
+            self.passes,
+            if self.dry_run { " (dry run)" } else { "" }
+        )];
+        lines.push(format!(
+            "  Work available: {} ready item(s)",
+            self.work_count
+        ));
+        for (name, current, desired) in [
+            (
+                "Workers",
+                self.current_counts.worker,
+                self.desired_counts.worker,
+            ),
+            (
+                "Planners",
+                self.current_counts.planner,
+                self.desired_counts.planner,
+            ),
+            (
+                "Buddies",
+                self.current_counts.buddy,
+                self.desired_counts.buddy,
+            ),
+        ] {
+            lines.push(format!(
+                "  {}: {} current, {} desired",
+                name, current, desired
+            ));
+        }
+        if !self.paused.is_empty() {
+            lines.push(format!(
+                "  Paused (budget exhausted): {}",
+                self.paused.join(", ")
+            ));
+        }
+
+        let events: Vec<&SupervisorEvent> = self
+            .events
+            .iter()
+            .filter(|e| e.pass == self.passes)
+            .collect();
+        if !events.is_empty() {
+            lines.push(format!("\n  Events ({})", events.len()));
+            for event in events {
+                let who = event
+                    .agent_name
+                    .as_deref()
+                    .or(event.agent_id.as_deref())
+                    .unwrap_or("-");
+                let status = if event.executed {
+                    ""
+                } else {
+                    " (not executed)"
+                };
+                lines.push(format!(
+                    "    {} {} {} - {}{}",
+                    event.kind, event.agent_type, who, event.reason, status
+                ));
+            }
+        }
+
+        if !self.pending_restarts.is_empty() {
+            lines.push(format!(
+                "\n  Pending restarts ({})",
+                self.pending_restarts.len()
+            ));
+            for pending in &self.pending_restarts {
+                lines.push(format!(
+                    "    {} ({}) at {} after {} crash(es)",
+                    pending.agent_name,
+                    pending.agent_type,
+                    pending.restart_at.format("%H:%M:%S"),
+                    pending.crashes
+                ));
+            }
+        }
+
+        if let Some(err) = &self.last_error {
+            lines.push(format!("\nError: {}", err));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Restart delay after `crashes` consecutive crashes.
+pub fn backoff_delay(config: &SupervisorConfig, crashes: u32) -> Duration {
+    let doublings = crashes.saturating_sub(1).min(20);
+    let secs = config
+        .backoff_secs
+        .saturating_mul(1u64 << doublings)
+        .min(config.max_backoff_secs);
+    Duration::seconds(secs as i64)
+}
+
+/// Why an agent should be stopped for exceeding its limits, if it should.
+pub fn timeout_reason(
+    config: &SupervisorConfig,
+    agent: &Agent,
+    now: DateTime<Utc>,
+) -> Option<String> {
+    let limits = config.timeouts_for(&agent_type_name(agent))?;
+    if let Some(limit) = limits.wall_clock_secs {
+        let running = now.signed_duration_since(agent.started_at).num_seconds();
+        if running > limit as i64 {
+            return Some(format!(
+                "Running for {}s, wall-clock limit is {}s",
+                running, limit
+            ));
+        }
+    }
+    if let Some(limit) = limits.idle_secs {
+        let idle = now
+            .signed_duration_since(agent.last_activity_at)
+            .num_seconds();
+        if idle > limit as i64 {
+            return Some(format!("Idle for {}s, idle limit is {}s", idle, limit));
+        }
+    }
+    None
+}
+
+fn agent_type_name(agent: &Agent) -> String {
+    format!("{:?}", agent.agent_type).to_lowercase()
+}
+
+/// Embedded agent whose prompt a freshly spawned agent of this type receives.
+fn spawn_prompt(agent_type: &str) -> Option<String> {
+    let definition = match agent_type {
+        "planner" => "prd",
+        other => other,
+    };
+    get_embedded_agent(definition).map(|a| a.prompt)
+}
+
+fn count_mut<'a>(counts: &'a mut AgentTypeCounts, agent_type: &str) -> Option<&'a mut usize> {
+    match agent_type {
+        "worker" => Some(&mut counts.worker),
+        "planner" => Some(&mut counts.planner),
+        "buddy" => Some(&mut counts.buddy),
+        "ask" => Some(&mut counts.ask),
+        _ => None,
+    }
+}
+
+fn count(counts: &AgentTypeCounts, agent_type: &str) -> usize {
+    let mut counts = counts.clone();
+    count_mut(&mut counts, agent_type).map_or(0, |c| *c)
+}
+
+/// Agent pool supervisor state carried between passes.
+pub struct Supervisor {
+    repo_path: PathBuf,
+    dry_run: bool,
+    /// Consecutive crashes by agent name
+    crashes: HashMap<String, u32>,
+    pending: Vec<PendingRestart>,
+    events: VecDeque<SupervisorEvent>,
+    status: SupervisorStatus,
+}
+
+impl Supervisor {
+    /// Create a supervisor for a repository. No pass runs until [`Self::tick`].
+    pub fn new(repo_path: &Path, dry_run: bool) -> Self {
+        Self {
+            repo_path: repo_path.to_path_buf(),
+            dry_run,
+            crashes: HashMap::new(),
+            pending: Vec::new(),
+            events: VecDeque::new(),
+            status: SupervisorStatus {
+                started_at: Utc::now(),
+                last_pass_at: None,
+                passes: 0,
+                interval_secs: SupervisorConfig::default().interval_secs,
+                dry_run,
+                work_count: 0,
+                current_counts: AgentTypeCounts::default(),
+                desired_counts: AgentTypeCounts::default(),
+                agents: Vec::new(),
+                pending_restarts: Vec::new(),
+                budgets: Vec::new(),
+                paused: Vec::new(),
+                events: Vec::new(),
+                last_error: None,
+            },
+        }
+    }
+
+    /// Status as of the last pass.
+    pub fn status(&self) -> &SupervisorStatus {
+        &self.status
+    }
+
+    /// Run one pass: restart or time out agents, then reconcile the pool.
+    ///
+    /// A failed pass is recorded in [`SupervisorStatus::last_error`] and
+    /// returned; the supervisor can keep ticking afterwards.
+    pub fn tick(&mut self) -> Result<&SupervisorStatus> {
+        self.status.passes += 1;
+        let result = self.run_pass();
+        self.status.last_pass_at = Some(Utc::now());
+        self.status.last_error = result.as_ref().err().map(|e| e.to_string());
+        self.status.pending_restarts = self.pending.clone();
+        self.status.events = self.events.iter().cloned().collect();
+        result.map(|_| &self.status)
+    }
+
+    fn run_pass(&mut self) -> Result<()> {
+        let mut storage = Storage::open(&self.repo_path)?;
+        let config = storage.get_supervisor_kdl()?;
+        self.status.interval_secs = config.interval_secs;
+        let now = Utc::now();
+
+        let _ = storage.update_agent_statuses();
+        let agents = storage.list_agents(None)?;
+        let runtime = find_runtime(&self.repo_path)?;
+        let grace = Duration::seconds(config.startup_grace_secs as i64);
+
+        for agent in agents
+            .iter()
+            .filter(|a| a.container_id.is_some() && a.goodbye_at.is_none())
+        {
+            let container = agent.container_id.clone().unwrap_or_default();
+            let agent_type = agent_type_name(agent);
+
+            // Crashed: the container is gone but the agent never said goodbye
+            let started_long_ago = now.signed_duration_since(agent.started_at) > grace;
+            let crashed = started_long_ago
+                && runtime
+                    .as_ref()
+                    .is_some_and(|rt| matches!(rt.is_running(&container), Ok(false)));
+            if crashed {
+                self.handle_crash(&mut storage, &config, agent, now);
+                continue;
+            }
+
+            if let Some(reason) = timeout_reason(&config, agent, now) {
+                let executed = !self.dry_run;
+                if executed {
+                    let _ = container_stop(&self.repo_path, Some(container), false);
+                    let _ = storage.remove_agent(agent.pid);
+                }
+                self.push_event(
+                    SupervisorEventKind::Timeout,
+                    &agent_type,
+                    Some(agent),
+                    reason,
+                    executed,
+                );
+            }
+        }
+
+        let budgets = evaluate_budgets(&storage, &storage.get_budgets_kdl()?)?;
+        let paused = paused_agent_types(&budgets);
+        drop(storage);
+        self.update_paused(&budgets, &paused);
+
+        // Reconcile the pool, holding slots for agents waiting to restart
+        let mut hold = ReconcileHold {
+            paused,
+            ..ReconcileHold::default()
+        };
+        for pending in &self.pending {
+            if let Some(slot) = count_mut(&mut hold.reserved, &pending.agent_type) {
+                *slot += 1;
+            }
+        }
+        let reconcile = agent_reconcile_with_hold(&self.repo_path, self.dry_run, &hold)?;
+        for action in &reconcile.actions {
+            let kind = if action.action == "spawn" {
+                SupervisorEventKind::Spawn
+            } else {
+                SupervisorEventKind::Stop
+            };
+            self.push_event_raw(
+                kind,
+                &action.agent_type,
+                action.agent_id.clone(),
+                action.agent_name.clone(),
+                action.reason.clone(),
+                action.executed,
+            );
+        }
+
+        self.restart_due(
+            &config,
+            &reconcile.current_counts,
+            &reconcile.desired_counts,
+            &hold.reserved,
+        );
+
+        // Merge finished task branches before collecting their worktrees
+        if !self.dry_run
+            && let Ok(run) = merge_queue_run(&self.repo_path, false)
+        {
+            for entry in run.processed {
+                let reason = match entry.status {
+                    MergeQueueStatus::Merged => {
+                        format!("Merged {} into {}", entry.branch, entry.target)
+                    }
+                    _ => format!(
+                        "{} not merged: {}",
+                        entry.branch,
+                        entry.message.as_deref().unwrap_or("failed")
+                    ),
+                };
+                self.push_event_raw(
+                    SupervisorEventKind::Merge,
+                    "worker",
+                    entry.agent_id,
+                    None,
+                    reason,
+                    true,
+                );
+            }
+        }
+
+        // Outside a git repository there are no task worktrees to collect
+        if let Ok(gc) = agent_worktree_gc(&self.repo_path, None, false, self.dry_run) {
+            for worktree in gc.removed {
+                self.push_event_raw(
+                    SupervisorEventKind::Cleanup,
+                    "worker",
+                    None,
+                    None,
+                    format!(
+                        "Removed worktree for {} ({})",
+                        worktree.task_id, worktree.branch
+                    ),
+                    !self.dry_run,
+                );
+            }
+        }
+
+        self.status.budgets = budgets;
+        self.status.work_count = reconcile.work_count;
+        self.status.current_counts = reconcile.current_counts;
+        self.status.desired_counts = reconcile.desired_counts;
+        self.status.agents = Storage::open(&self.repo_path)?
+            .list_agents(None)?
+            .iter()
+            .map(|agent| SupervisedAgent {
+                id: agent.id.clone(),
+                name: agent.name.clone(),
+                agent_type: agent_type_name(agent),
+                status: format!("{:?}", agent.status).to_lowercase(),
+                container: agent.container_id.clone(),
+                started_at: agent.started_at,
+                last_activity_at: agent.last_activity_at,
+                crashes: self.crashes.get(&agent.name).copied().unwrap_or(0),
+            })
+            .collect();
+        Ok(())
+    }
+
+    /// Record pause and resume events when the set of paused types changes.
+    fn update_paused(&mut self, budgets: &[BudgetStatus], paused: &[AgentType]) {
+        let paused: Vec<String> = paused
+            .iter()
+            .map(|t| format!("{:?}", t).to_lowercase())
+            .collect();
+        let newly_paused: Vec<String> = paused
+            .iter()
+            .filter(|t| !self.status.paused.contains(t))
+            .cloned()
+            .collect();
+        for agent_type in newly_paused {
+            let reasons: Vec<String> = budgets
+                .iter()
+                .filter(|b| {
+                    b.pauses()
+                        .is_some_and(|p| format!("{:?}", p).to_lowercase() == agent_type)
+                })
+                .map(BudgetStatus::summary)
+                .collect();
+            self.push_event_raw(
+                SupervisorEventKind::Pause,
+                &agent_type,
+                None,
+                None,
+                format!("Budget exhausted: {}", reasons.join("; ")),
+                true,
+            );
+        }
+        let resumed: Vec<String> = self
+            .status
+            .paused
+            .iter()
+            .filter(|t| !paused.contains(t))
+            .cloned()
+            .collect();
+        for agent_type in resumed {
+            self.push_event_raw(
+                SupervisorEventKind::Resume,
+                &agent_type,
+                None,
+                None,
+                "No budget is exhausted".to_string(),
+                true,
+            );
+        }
+        self.status.paused = paused;
+    }
+
+    /// Record a crash and schedule the restart.
+    fn handle_crash(
+        &mut self,
+        storage: &mut Storage,
+        config: &SupervisorConfig,
+        agent: &Agent,
+        now: DateTime<Utc>,
+    ) {
+        let agent_type = agent_type_name(agent);
+        if self.dry_run {
+            self.push_event(
+                SupervisorEventKind::Crash,
+                &agent_type,
+                Some(agent),
+                "Container is not running".to_string(),
+                false,
+            );
+            return;
+        }
+        let _ = storage.remove_agent(agent.pid);
+
+        // An agent that stayed up longer than the backoff cap starts over
+        let ran_for = now.signed_duration_since(agent.started_at).num_seconds();
+        let previous = if ran_for > config.max_backoff_secs as i64 {
+            0
+        } else {
+            self.crashes.get(&agent.name).copied().unwrap_or(0)
+        };
+        let crashes = previous + 1;
+        self.crashes.insert(agent.name.clone(), crashes);
+        let delay = backoff_delay(config, crashes);
+
+        self.pending.retain(|p| p.agent_name != agent.name);
+        self.pending.push(PendingRestart {
+            agent_name: agent.name.clone(),
+            agent_type: agent_type.clone(),
+            crashes,
+            restart_at: now + delay,
+        });
+        self.push_event(
+            SupervisorEventKind::Crash,
+            &agent_type,
+            Some(agent),
+            format!(
+                "Container is not running; restarting in {}s (crash {})",
+                delay.num_seconds(),
+                crashes
+            ),
+            true,
+        );
+    }
+
+    /// Restart pending agents whose backoff elapsed, dropping ones no longer needed.
+    fn restart_due(
+        &mut self,
+        config: &SupervisorConfig,
+        current: &AgentTypeCounts,
+        desired: &AgentTypeCounts,
+        reserved: &AgentTypeCounts,
+    ) {
+        let now = Utc::now();
+        let mut pending = std::mem::take(&mut self.pending);
+        pending.sort_by_key(|p| p.restart_at);
+
+        let mut room = AgentTypeCounts::default();
+        for agent_type in ["worker", "planner", "buddy", "ask"] {
+            let free = count(desired, agent_type)
+                .saturating_sub(count(current, agent_type))
+                .min(count(reserved, agent_type));
+            if let Some(slot) = count_mut(&mut room, agent_type) {
+                *slot = free;
+            }
+        }
+
+        for restart in pending {
+            let Some(slot) = count_mut(&mut room, &restart.agent_type).filter(|s| **s > 0) else {
+                self.crashes.remove(&restart.agent_name);
+                self.push_event_raw(
+                    SupervisorEventKind::Cancel,
+                    &restart.agent_type,
+                    None,
+                    Some(restart.agent_name.clone()),
+                    "Slot is no longer needed".to_string(),
+                    true,
+                );
+                continue;
+            };
+            *slot -= 1;
+
+            // Paused types keep their slot until the budget allows spawning again
+            let paused = self.status.paused.contains(&restart.agent_type);
+            if restart.restart_at > now || self.dry_run || paused {
+                self.pending.push(restart);
+                continue;
+            }
+
+            let spawned = agent_spawn(
+                &self.repo_path,
+                &restart.agent_type,
+                Some(restart.agent_name.clone()),
+                None,
+                None,
+                None,
+                None,
+                "main",
+                false,
+                spawn_prompt(&restart.agent_type).as_deref(),
+                None, // backend from agent definition
+            )
+            .map_err(|e| e.to_string())
+            .and_then(|r| {
+                if r.success {
+                    Ok(r)
+                } else {
+                    Err(r.error.unwrap_or_else(|| "spawn failed".to_string()))
+                }
+            });
+
+            match spawned {
+                Ok(result) => self.push_event_raw(
+                    SupervisorEventKind::Restart,
+                    &restart.agent_type,
+                    result.agent_id,
+                    Some(restart.agent_name.clone()),
+                    format!("Restarted after {} crash(es)", restart.crashes),
+                    true,
+                ),
+                Err(e) => {
+                    // A failed restart counts as another crash
+                    let crashes = restart.crashes + 1;
+                    self.crashes.insert(restart.agent_name.clone(), crashes);
+                    let delay = backoff_delay(config, crashes);
+                    self.push_event_raw(
+                        SupervisorEventKind::Restart,
+                        &restart.agent_type,
+                        None,
+                        Some(restart.agent_name.clone()),
+                        format!(
+                            "Restart failed: {}; retrying in {}s",
+                            e,
+                            delay.num_seconds()
+                        ),
+                        false,
+                    );
+                    self.pending.push(PendingRestart {
+                        crashes,
+                        restart_at: now + delay,
+                        ..restart
+                    });
+                }
+            }
+        }
+    }
+
+    fn push_event(
+        &mut self,
+        kind: SupervisorEventKind,
+        agent_type: &str,
+        agent: Option<&Agent>,
+        reason: String,
+        executed: bool,
+    ) {
+        self.push_event_raw(
+            kind,
+            agent_type,
+            agent.map(|a| a.id.clone()),
+            agent.map(|a| a.name.clone()),
+            reason,
+            executed,
+        );
+    }
+
+    fn push_event_raw(
+        &mut self,
+        kind: SupervisorEventKind,
+        agent_type: &str,
+        agent_id: Option<String>,
+        agent_name: Option<String>,
+        reason: String,
+        executed: bool,
+    ) {
+        self.events.push_back(SupervisorEvent {
+            at: Utc::now(),
+            pass: self.status.passes,
+            kind,
+            agent_type: agent_type.to_string(),
+            agent_id,
+            agent_name,
+            reason,
+            executed,
+        });
+        while self.events.len() > MAX_EVENTS {
+            self.events.pop_front();
+        }
+    }
+}
+
+/// Run the supervisor in the foreground until interrupted (or for one pass).
+///
+/// `on_pass` receives the status after every pass, including failed ones.
+pub fn supervise(
+    repo_path: &Path,
+    dry_run: bool,
+    once: bool,
+    interval: Option<u64>,
+    mut on_pass: impl FnMut(&SupervisorStatus),
+) -> Result<()> {
+    if !Storage::exists(repo_path)? {
+        return Err(Error::NotInitialized);
+    }
+    let mut supervisor = Supervisor::new(repo_path, dry_run);
+    loop {
+        let result = supervisor.tick().map(|_| ());
+        on_pass(supervisor.status());
+        if once {
+            return result;
+        }
+        let secs = interval.unwrap_or(supervisor.status().interval_secs).max(1);
+        std::thread::sleep(std::time::Duration::from_secs(secs));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AgentTimeouts;
+    use crate::models::AgentType;
+
+    fn config() -> SupervisorConfig {
+        SupervisorConfig {
+            backoff_secs: 10,
+            max_backoff_secs: 60,
+            timeouts: vec![AgentTimeouts {
+                agent_type: "worker".to_string(),
+                wall_clock_secs: Some(3600),
+                idle_secs: Some(600),
+            }],
+            ..SupervisorConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        let config = config();
+        let delays: Vec<i64> = (1..=5)
+            .map(|n| backoff_delay(&config, n).num_seconds())
+            .collect();
+        assert_eq!(delays, vec![10, 20, 40, 60, 60]);
+        assert_eq!(backoff_delay(&config, u32::MAX).num_seconds(), 60);
+    }
+
+    #[test]
+    fn test_timeout_reason() {
+        let config = config();
+        let now = Utc::now();
+        let mut agent = Agent::new(0, 0, "worker-a1b2".to_string(), AgentType::Worker);
+        agent.started_at = now - Duration::seconds(100);
+        agent.last_activity_at = now - Duration::seconds(10);
+        assert_eq!(timeout_reason(&config, &agent, now), None);
+
+        agent.last_activity_at = now - Duration::seconds(601);
+        assert!(
+            timeout_reason(&config, &agent, now)
+                .unwrap()
+                .contains("idle limit")
+        );
+
+        agent.started_at = now - Duration::seconds(3601);
+        assert!(
+            timeout_reason(&config, &agent, now)
+                .unwrap()
+                .contains("wall-clock")
+        );
+
+        // No limits configured for planners
+        agent.agent_type = AgentType::Planner;
+        assert_eq!(timeout_reason(&config, &agent, now), None);
+    }
+
+    #[test]
+    fn test_restart_due_cancels_unneeded_slots() {
+        let env = crate::test_utils::TestEnv::new_isolated();
+        let mut supervisor = Supervisor::new(env.path(), true);
+        let now = Utc::now();
+        for (name, agent_type) in [
+            ("worker-1", "worker"),
+            ("worker-2", "worker"),
+            ("buddy-1", "buddy"),
+        ] {
+            supervisor.pending.push(PendingRestart {
+                agent_name: name.to_string(),
+                agent_type: agent_type.to_string(),
+                crashes: 1,
+                restart_at: now + Duration::seconds(30),
+            });
+        }
+
+        // One worker slot is still needed, buddies are at their desired count
+        let current = AgentTypeCounts {
+            buddy: 1,
+            ..AgentTypeCounts::default()
+        };
+        let desired = AgentTypeCounts {
+            worker: 1,
+            buddy: 1,
+            ..AgentTypeCounts::default()
+        };
+        let reserved = AgentTypeCounts {
+            worker: 2,
+            buddy: 1,
+            ..AgentTypeCounts::default()
+        };
+        supervisor.restart_due(&config(), &current, &desired, &reserved);
+
+        let kept: Vec<&str> = supervisor
+            .pending
+            .iter()
+            .map(|p| p.agent_name.as_str())
+            .collect();
+        assert_eq!(kept, vec!["worker-1"]);
+        let cancelled: Vec<&str> = supervisor
+            .events
+            .iter()
+            .filter(|e| e.kind == SupervisorEventKind::Cancel)
+            .filter_map(|e| e.agent_name.as_deref())
+            .collect();
+        assert_eq!(cancelled, vec!["worker-2", "buddy-1"]);
+    }
+
+    #[test]
+    fn test_status_human_lists_pass_events() {
+        let env = crate::test_utils::TestEnv::new_isolated();
+        let mut supervisor = Supervisor::new(env.path(), false);
+        supervisor.status.passes = 2;
+        supervisor.push_event_raw(
+            SupervisorEventKind::Timeout,
+            "worker",
+            Some("bn-a1b2".to_string()),
+            Some("worker-a1b2".to_string()),
+            "Idle for 700s, idle limit is 600s".to_string(),
+            true,
+        );
+        supervisor.status.events = supervisor.events.iter().cloned().collect();
+
+        let human = supervisor.status().to_human();
+        assert!(human.contains("Supervisor pass 2:"));
+        assert!(human.contains("timeout worker worker-a1b2 - Idle for 700s"));
+    }
+}
@@ -0,0 +1,253 @@
+//! Agent transcripts.
+//!
+//! Each agent gets a transcript at `transcripts/<agent-id>.jsonl` in the
+//! repository's storage directory, fed by two writers:
+//!
+//! - **Commands**: the action log mirrors every bn command run with
+//!   `BN_AGENT_ID` set into that agent's transcript
+//! - **Output**: `bn agent spawn` pipes the container's stdout and stderr
+//!   through detached `bn agent transcript <id> --capture <stream>` helpers,
+//!   which timestamp each line and keep writing the raw `agentlogs` file
+//!
+//! Transcripts outlive the agent registry, so they stay reachable from the
+//! tasks an agent worked on after it says goodbye. [`agent_timeline`]
+//! interleaves a transcript with the graph changes to those tasks.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, Write};
+
+use crate::Result;
+use crate::commands::LogEntry;
+use crate::models::{EdgeType, TranscriptEntry, TranscriptKind};
+use crate::storage::Storage;
+
+/// Timestamp format shared with graph change log entries.
+const TIMELINE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// A transcript available for a task.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TranscriptLink {
+    pub agent_id: String,
+    pub path: String,
+}
+
+/// One item on an agent's timeline.
+#[derive(Clone, Serialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum TimelineItem {
+    /// A transcript line
+    Transcript(TranscriptEntry),
+    /// A graph change to a task the agent touched
+    Change(LogEntry),
+}
+
+impl TimelineItem {
+    fn sort_key(&self) -> String {
+        match self {
+            TimelineItem::Transcript(entry) => entry.at.format(TIMELINE_FORMAT).to_string(),
+            TimelineItem::Change(change) => change.timestamp.clone(),
+        }
+    }
+}
+
+/// Copy `input` into an agent's transcript line by line.
+///
+/// Each line is also written unchanged to `raw_log` when given. Returns the
+/// number of lines captured once `input` reaches EOF.
+pub fn capture(
+    storage: &Storage,
+    agent_id: &str,
+    kind: TranscriptKind,
+    mut input: impl BufRead,
+    mut raw_log: Option<File>,
+) -> Result<u64> {
+    let mut lines = 0;
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        if input.read_until(b'\n', &mut buf)? == 0 {
+            break;
+        }
+        if let Some(log) = raw_log.as_mut() {
+            log.write_all(&buf)?;
+        }
+        let text = String::from_utf8_lossy(&buf);
+        let text = text.trim_end_matches(['\n', '\r']);
+        storage.append_transcript_entry(agent_id, &TranscriptEntry::output(kind, text))?;
+        lines += 1;
+    }
+    Ok(lines)
+}
+
+/// Tasks an agent touched: its `working_on`/`worked_on` edge targets plus
+/// the tasks on its registry entry, if it is still registered.
+pub fn touched_tasks(storage: &Storage, agent_id: &str) -> Result<Vec<String>> {
+    let mut tasks = Vec::new();
+    if let Ok(agent) = storage.get_agent_by_id(agent_id) {
+        tasks.extend(agent.tasks);
+    }
+    for edge_type in [EdgeType::WorkingOn, EdgeType::WorkedOn] {
+        for edge in storage.list_edges(Some(edge_type), Some(agent_id), None)? {
+            if !tasks.contains(&edge.target) {
+                tasks.push(edge.target);
+            }
+        }
+    }
+    Ok(tasks)
+}
+
+/// Transcripts of the agents that worked on a task.
+pub fn task_transcripts(storage: &Storage, task_id: &str) -> Vec<TranscriptLink> {
+    let mut seen = HashSet::new();
+    let mut links = Vec::new();
+    for edge_type in [EdgeType::WorkingOn, EdgeType::WorkedOn] {
+        let edges = storage
+            .list_edges(Some(edge_type), None, Some(task_id))
+            .unwrap_or_default();
+        for edge in edges {
+            if !seen.insert(edge.source.clone()) {
+                continue;
+            }
+            if let Ok(path) = storage.transcript_path(&edge.source)
+                && path.exists()
+            {
+                links.push(TranscriptLink {
+                    agent_id: edge.source,
+                    path: path.to_string_lossy().to_string(),
+                });
+            }
+        }
+    }
+    links
+}
+
+/// An agent's transcript interleaved with graph changes to the tasks it
+/// touched, oldest first.
+///
+/// Only changes made since the transcript started are included.
+pub fn agent_timeline(storage: &Storage, agent_id: &str) -> Result<Vec<TimelineItem>> {
+    let (entries, _) = storage.read_transcript(agent_id, 0)?;
+    let Some(first) = entries.first() else {
+        return Ok(Vec::new());
+    };
+    let start = first.at.format(TIMELINE_FORMAT).to_string();
+
+    let mut items = Vec::new();
+    for task_id in touched_tasks(storage, agent_id)? {
+        for change in storage.get_log_entries(Some(&task_id))? {
+            if change.timestamp >= start {
+                items.push(TimelineItem::Change(change));
+            }
+        }
+    }
+    // Changes go first so that, within the same second, they precede the
+    // command entry (which is logged once the command finishes)
+    items.extend(entries.into_iter().map(TimelineItem::Transcript));
+    items.sort_by_key(TimelineItem::sort_key);
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Agent, AgentType, Edge, Task};
+    use crate::test_utils::TestEnv;
+    use chrono::{Duration, Utc};
+    use std::io::Cursor;
+
+    fn setup() -> (TestEnv, Storage) {
+        let env = TestEnv::new();
+        let storage = env.init_storage();
+        (env, storage)
+    }
+
+    #[test]
+    fn test_capture_writes_transcript_and_raw_log() {
+        let (env, storage) = setup();
+        let raw_path = env.data_path().join("raw.log");
+        let raw = File::create(&raw_path).unwrap();
+
+        let input = Cursor::new(b"hello\r\nworld\npartial".to_vec());
+        let lines = capture(
+            &storage,
+            "bn-a1b2",
+            TranscriptKind::Stderr,
+            input,
+            Some(raw),
+        )
+        .unwrap();
+        assert_eq!(lines, 3);
+        assert_eq!(
+            std::fs::read_to_string(&raw_path).unwrap(),
+            "hello\r\nworld\npartial"
+        );
+
+        let (entries, offset) = storage.read_transcript("bn-a1b2", 0).unwrap();
+        let texts: Vec<&str> = entries.iter().map(|e| e.text.as_str()).collect();
+        assert_eq!(texts, vec!["hello", "world", "partial"]);
+        assert!(entries.iter().all(|e| e.kind == TranscriptKind::Stderr));
+
+        // Resuming from the returned offset yields only new lines
+        storage
+            .append_transcript_entry(
+                "bn-a1b2",
+                &TranscriptEntry::output(TranscriptKind::Stdout, "more"),
+            )
+            .unwrap();
+        let (entries, _) = storage.read_transcript("bn-a1b2", offset).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].text, "more");
+    }
+
+    #[test]
+    fn test_transcript_path_rejects_traversal() {
+        let (_env, storage) = setup();
+        assert!(storage.transcript_path("../../etc/passwd").is_err());
+        assert!(storage.transcript_path("").is_err());
+        assert!(matches!(
+            storage.read_transcript("bn-none", 0),
+            Err(crate::Error::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_timeline_interleaves_task_changes() {
+        let (_env, mut storage) = setup();
+        let agent = Agent::new_with_id(
+            "bn-a1b2".to_string(),
+            0,
+            0,
+            "worker-a1b2".to_string(),
+            AgentType::Worker,
+        );
+        storage.register_agent(&agent).unwrap();
+        let mut started = TranscriptEntry::output(TranscriptKind::Stdout, "starting");
+        started.at = Utc::now() - Duration::minutes(1);
+        storage
+            .append_transcript_entry("bn-a1b2", &started)
+            .unwrap();
+
+        let task = Task::new("bn-t001".to_string(), "Transcribed work".to_string());
+        storage.create_task(&task).unwrap();
+        storage
+            .add_edge(&Edge::new(
+                "bn-e001".to_string(),
+                "bn-a1b2".to_string(),
+                "bn-t001".to_string(),
+                EdgeType::WorkingOn,
+            ))
+            .unwrap();
+
+        assert_eq!(touched_tasks(&storage, "bn-a1b2").unwrap(), vec!["bn-t001"]);
+        let links = task_transcripts(&storage, "bn-t001");
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].agent_id, "bn-a1b2");
+
+        let timeline = agent_timeline(&storage, "bn-a1b2").unwrap();
+        assert_eq!(timeline.len(), 2);
+        assert!(matches!(&timeline[0], TimelineItem::Transcript(e) if e.text == "starting"));
+        assert!(matches!(&timeline[1], TimelineItem::Change(c) if c.entity_id == "bn-t001"));
+    }
+}
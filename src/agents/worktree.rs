@@ -0,0 +1,382 @@
+//! Per-task git worktrees for spawned agents.
+//!
+//! `bn agent spawn --task <id>` gives the agent its own worktree at
+//! `worktrees/<task-id>` in the repository's storage directory, on a branch
+//! named `bn/<task-id>` cut from the merge target. When the agent exits, the
+//! branch joins the merge queue (`bn merge-queue`), which rebases it onto the
+//! target, runs the task's linked tests in the worktree and fast-forwards the
+//! target. `bn agent worktree finish` does the same for one task right away,
+//! merging instead of rebasing. A conflict or failing test leaves a bug
+//! linked to the task instead.
+//!
+//! `bn agent worktree gc` (also run by `bn agent supervise`) removes
+//! worktrees whose task is closed and whose branch has been merged.
+//!
+//! This module only wraps git (through [`crate::git`]); the commands layer
+//! decides what to do with the outcomes.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::git::{git, git_ok, rev_parse};
+use crate::storage::Storage;
+use crate::{Error, Result};
+
+/// Prefix of the branches binnacle creates for task worktrees.
+pub const BRANCH_PREFIX: &str = "bn/";
+
+/// A worktree binnacle created for a task.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TaskWorktree {
+    pub task_id: String,
+    pub path: String,
+    pub branch: String,
+    /// Commit the worktree is at
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub head: Option<String>,
+}
+
+/// Outcome of merging the target branch into a task worktree.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeOutcome {
+    /// The target is now part of the branch
+    Clean,
+    /// The merge conflicted and was aborted
+    Conflict { files: Vec<String> },
+}
+
+/// Branch name for a task worktree, e.g. `bn/bn-a1b2`.
+pub fn branch_name(task_id: &str) -> String {
+    format!("{}{}", BRANCH_PREFIX, task_id)
+}
+
+/// A `git worktree list` entry: (path, branch, head).
+type WorktreeListing = (PathBuf, Option<String>, Option<String>);
+
+/// Every worktree of the repository.
+fn list_all(repo_root: &Path) -> Result<Vec<WorktreeListing>> {
+    let porcelain = git(repo_root, &["worktree", "list", "--porcelain"])?;
+    let mut worktrees = Vec::new();
+    for block in porcelain.split("\n\n") {
+        let mut path = None;
+        let mut branch = None;
+        let mut head = None;
+        for line in block.lines() {
+            if let Some(p) = line.strip_prefix("worktree ") {
+                path = Some(PathBuf::from(p));
+            } else if let Some(b) = line.strip_prefix("branch ") {
+                branch = Some(b.trim_start_matches("refs/heads/").to_string());
+            } else if let Some(h) = line.strip_prefix("HEAD ") {
+                head = Some(h.to_string());
+            }
+        }
+        if let Some(path) = path {
+            worktrees.push((path, branch, head));
+        }
+    }
+    Ok(worktrees)
+}
+
+/// Task worktrees of the repository (branches under `bn/`).
+pub fn list(repo_root: &Path) -> Result<Vec<TaskWorktree>> {
+    Ok(list_all(repo_root)?
+        .into_iter()
+        .filter_map(|(path, branch, head)| {
+            let branch = branch?;
+            let task_id = branch.strip_prefix(BRANCH_PREFIX)?.to_string();
+            Some(TaskWorktree {
+                task_id,
+                path: path.to_string_lossy().to_string(),
+                branch,
+                head,
+            })
+        })
+        .collect())
+}
+
+/// The worktree for a task, if one exists.
+pub fn find(repo_root: &Path, task_id: &str) -> Result<Option<TaskWorktree>> {
+    Ok(list(repo_root)?.into_iter().find(|w| w.task_id == task_id))
+}
+
+/// Create the worktree and branch for a task, or return the existing one.
+///
+/// The branch is cut from `base` unless it already exists. Returns the
+/// worktree and whether it was created.
+pub fn create(
+    storage: &Storage,
+    repo_root: &Path,
+    task_id: &str,
+    base: &str,
+) -> Result<(TaskWorktree, bool)> {
+    if let Some(existing) = find(repo_root, task_id)? {
+        return Ok((existing, false));
+    }
+    if task_id.is_empty()
+        || !task_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(Error::InvalidInput(format!(
+            "Invalid task ID for a worktree: '{}'",
+            task_id
+        )));
+    }
+
+    let path = storage.root.join("worktrees").join(task_id);
+    std::fs::create_dir_all(storage.root.join("worktrees"))?;
+    let path_str = path.to_string_lossy().to_string();
+    let branch = branch_name(task_id);
+    let branch_ref = format!("refs/heads/{}", branch);
+    if git_ok(repo_root, &["show-ref", "--verify", "--quiet", &branch_ref]) {
+        git(repo_root, &["worktree", "add", &path_str, &branch])?;
+    } else {
+        git(
+            repo_root,
+            &["worktree", "add", "-b", &branch, &path_str, base],
+        )?;
+    }
+
+    let worktree = find(repo_root, task_id)?.ok_or_else(|| {
+        Error::Other(format!("git did not register the worktree at {}", path_str))
+    })?;
+    Ok((worktree, true))
+}
+
+/// Remove a task worktree, optionally deleting its branch.
+pub fn remove(repo_root: &Path, worktree: &TaskWorktree, delete_branch: bool) -> Result<()> {
+    if Path::new(&worktree.path).exists() {
+        git(
+            repo_root,
+            &["worktree", "remove", "--force", worktree.path.as_str()],
+        )?;
+    }
+    git(repo_root, &["worktree", "prune"])?;
+    if delete_branch {
+        git(repo_root, &["branch", "-D", worktree.branch.as_str()])?;
+    }
+    Ok(())
+}
+
+/// Whether everything on `branch` is already on `target`.
+pub fn is_merged(repo_root: &Path, branch: &str, target: &str) -> bool {
+    git_ok(repo_root, &["merge-base", "--is-ancestor", branch, target])
+}
+
+/// Merge `target` into the branch checked out in `worktree_dir`.
+///
+/// A conflicting merge is aborted so the worktree is left as it was.
+pub fn merge_target_into(worktree_dir: &Path, target: &str) -> Result<MergeOutcome> {
+    let message = format!("Merge {} into task branch", target);
+    let merge = git(
+        worktree_dir,
+        &["merge", "--no-edit", "-m", &message, target],
+    );
+    let Err(merge_error) = merge else {
+        return Ok(MergeOutcome::Clean);
+    };
+    let files: Vec<String> = git(worktree_dir, &["diff", "--name-only", "--diff-filter=U"])?
+        .lines()
+        .map(str::to_string)
+        .collect();
+    // Not a conflict (e.g. unknown target or local changes): report git's error
+    if files.is_empty() {
+        return Err(merge_error);
+    }
+    git(worktree_dir, &["merge", "--abort"])?;
+    Ok(MergeOutcome::Conflict { files })
+}
+
+/// Rebase the branch checked out in `worktree_dir` onto `target`.
+///
+/// A conflicting rebase is aborted so the worktree is left as it was.
+pub fn rebase_onto(worktree_dir: &Path, target: &str) -> Result<MergeOutcome> {
+    let Err(rebase_error) = git(worktree_dir, &["rebase", target]) else {
+        return Ok(MergeOutcome::Clean);
+    };
+    let files: Vec<String> = git(worktree_dir, &["diff", "--name-only", "--diff-filter=U"])?
+        .lines()
+        .map(str::to_string)
+        .collect();
+    // Abort even when git stopped for another reason, so the next entry
+    // does not find a half-finished rebase
+    let _ = git(worktree_dir, &["rebase", "--abort"]);
+    if files.is_empty() {
+        return Err(rebase_error);
+    }
+    Ok(MergeOutcome::Conflict { files })
+}
+
+/// Fast-forward `target` to `branch` and return the new target commit.
+///
+/// When the target is checked out in a worktree, that worktree is updated
+/// with `git merge --ff-only`; otherwise the ref is moved directly.
+pub fn fast_forward(repo_root: &Path, target: &str, branch: &str) -> Result<String> {
+    let new_head = rev_parse(repo_root, branch)?;
+    let old_head = rev_parse(repo_root, target)?;
+    if !git_ok(
+        repo_root,
+        &["merge-base", "--is-ancestor", &old_head, &new_head],
+    ) {
+        return Err(Error::Other(format!(
+            "{} has moved past {}; merge it into the branch first",
+            target, branch
+        )));
+    }
+
+    let checked_out = list_all(repo_root)?
+        .into_iter()
+        .find(|(_, b, _)| b.as_deref() == Some(target))
+        .map(|(path, _, _)| path);
+    match checked_out {
+        Some(path) if path.exists() => {
+            git(&path, &["merge", "--ff-only", branch])?;
+        }
+        Some(path) => {
+            return Err(Error::Other(format!(
+                "{} is checked out at {}, which is not accessible here; \
+                 run `bn agent worktree finish` on the host",
+                target,
+                path.display()
+            )));
+        }
+        None => {
+            let target_ref = format!("refs/heads/{}", target);
+            git(
+                repo_root,
+                &["update-ref", &target_ref, &new_head, &old_head],
+            )?;
+        }
+    }
+    Ok(new_head)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::{commits_between, current_branch, is_dirty};
+    use crate::test_utils::TestEnv;
+    use std::fs;
+
+    fn init_repo(dir: &Path) {
+        for args in [
+            vec!["init", "-q", "-b", "main"],
+            vec!["config", "user.email", "test@example.com"],
+            vec!["config", "user.name", "Test"],
+        ] {
+            git(dir, &args).unwrap();
+        }
+        fs::write(dir.join("README.md"), "hello\n").unwrap();
+        git(dir, &["add", "-A"]).unwrap();
+        git(dir, &["commit", "-q", "-m", "initial"]).unwrap();
+    }
+
+    fn commit_file(dir: &Path, name: &str, content: &str) {
+        fs::write(dir.join(name), content).unwrap();
+        git(dir, &["add", "-A"]).unwrap();
+        git(dir, &["commit", "-q", "-m", name]).unwrap();
+    }
+
+    #[test]
+    fn test_create_list_and_remove() {
+        let env = TestEnv::new();
+        init_repo(env.path());
+        let storage = env.init_storage();
+
+        let (worktree, created) = create(&storage, env.path(), "bn-a1b2", "main").unwrap();
+        assert!(created);
+        assert_eq!(worktree.branch, "bn/bn-a1b2");
+        assert!(Path::new(&worktree.path).join("README.md").exists());
+        assert_eq!(
+            current_branch(Path::new(&worktree.path)).as_deref(),
+            Some("bn/bn-a1b2")
+        );
+
+        let (again, created) = create(&storage, env.path(), "bn-a1b2", "main").unwrap();
+        assert!(!created);
+        assert_eq!(again.path, worktree.path);
+        assert_eq!(list(env.path()).unwrap().len(), 1);
+
+        remove(env.path(), &worktree, true).unwrap();
+        assert!(list(env.path()).unwrap().is_empty());
+        assert!(!Path::new(&worktree.path).exists());
+        assert!(create(&storage, env.path(), "../escape", "main").is_err());
+    }
+
+    #[test]
+    fn test_merge_and_fast_forward() {
+        let env = TestEnv::new();
+        init_repo(env.path());
+        let storage = env.init_storage();
+        let (worktree, _) = create(&storage, env.path(), "bn-a1b2", "main").unwrap();
+        let wt = Path::new(&worktree.path);
+
+        commit_file(wt, "feature.txt", "feature\n");
+        commit_file(env.path(), "other.txt", "other\n");
+        assert!(!is_merged(env.path(), &worktree.branch, "main"));
+
+        // main moved on, so it must be merged into the branch first
+        assert!(fast_forward(env.path(), "main", &worktree.branch).is_err());
+        assert_eq!(merge_target_into(wt, "main").unwrap(), MergeOutcome::Clean);
+        let head = fast_forward(env.path(), "main", &worktree.branch).unwrap();
+        assert_eq!(rev_parse(env.path(), "main").unwrap(), head);
+        assert!(env.path().join("feature.txt").exists());
+        assert!(is_merged(env.path(), &worktree.branch, "main"));
+    }
+
+    #[test]
+    fn test_rebase_onto_target() {
+        let env = TestEnv::new();
+        init_repo(env.path());
+        let storage = env.init_storage();
+        let (worktree, _) = create(&storage, env.path(), "bn-a1b2", "main").unwrap();
+        let wt = Path::new(&worktree.path);
+
+        commit_file(wt, "feature.txt", "feature\n");
+        commit_file(env.path(), "other.txt", "other\n");
+        let old_main = rev_parse(env.path(), "main").unwrap();
+
+        assert_eq!(rebase_onto(wt, "main").unwrap(), MergeOutcome::Clean);
+        let head = fast_forward(env.path(), "main", &worktree.branch).unwrap();
+        // Rebased, so main gains exactly the branch's own commit
+        assert_eq!(
+            commits_between(env.path(), &old_main, &head).unwrap(),
+            vec![head.clone()]
+        );
+
+        commit_file(wt, "README.md", "branch\n");
+        commit_file(env.path(), "README.md", "main\n");
+        assert_eq!(
+            rebase_onto(wt, "main").unwrap(),
+            MergeOutcome::Conflict {
+                files: vec!["README.md".to_string()]
+            }
+        );
+        assert!(!is_dirty(wt));
+        assert_eq!(current_branch(wt).as_deref(), Some("bn/bn-a1b2"));
+    }
+
+    #[test]
+    fn test_merge_conflict_is_aborted() {
+        let env = TestEnv::new();
+        init_repo(env.path());
+        let storage = env.init_storage();
+        let (worktree, _) = create(&storage, env.path(), "bn-a1b2", "main").unwrap();
+        let wt = Path::new(&worktree.path);
+
+        commit_file(wt, "README.md", "branch\n");
+        commit_file(env.path(), "README.md", "main\n");
+
+        assert_eq!(
+            merge_target_into(wt, "main").unwrap(),
+            MergeOutcome::Conflict {
+                files: vec!["README.md".to_string()]
+            }
+        );
+        assert!(!is_dirty(wt));
+        assert_eq!(
+            fs::read_to_string(wt.join("README.md")).unwrap(),
+            "branch\n"
+        );
+    }
+}
@@ -31,6 +31,32 @@ pub fn package_version() -> &'static str {
     env!("CARGO_PKG_VERSION")
 }
 
+/// Parse a duration such as `300s`, `5m`, `1h` or `90` (seconds) into seconds.
+pub fn parse_duration_secs(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => value.split_at(i),
+        None => (value, "s"),
+    };
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        _ => {
+            return Err(format!(
+                "invalid duration '{}' (use e.g. 90s, 5m, 1h)",
+                value
+            ));
+        }
+    };
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .filter(|secs| *secs > 0)
+        .ok_or_else(|| format!("invalid duration '{}' (use e.g. 90s, 5m, 1h)", value))
+}
+
 /// Build version string with timestamp and commit
 fn build_version() -> &'static str {
     // Use a static to ensure we only format this once
@@ -102,6 +128,11 @@ pub enum Commands {
         /// Optional reason for termination
         reason: Option<String>,
 
+        /// Next step for whoever picks up your in-progress tasks (repeatable).
+        /// Recorded in the handoff doc written for each task.
+        #[arg(long = "next", value_name = "STEP")]
+        next: Vec<String>,
+
         /// Log goodbye without actually terminating (for testing)
         #[arg(long)]
         dry_run: bool,
@@ -115,6 +146,10 @@ pub enum Commands {
     Show {
         /// Entity ID (e.g., bn-a1b2, bnt-0001)
         id: String,
+
+        /// Show the entity as archived at this git revision (or its nearest archived ancestor)
+        #[arg(long, value_name = "REV")]
+        at: Option<String>,
     },
 
     /// Task management commands
@@ -264,6 +299,33 @@ pub enum Commands {
         command: McpCommands,
     },
 
+    /// Annotate lines of a file with the tasks and bugs that produced them
+    ///
+    /// Runs `git blame` and looks up the tasks and bugs linked to each commit.
+    Blame {
+        /// File to blame, optionally with a line or range (e.g., src/main.rs:42 or src/main.rs:10-20)
+        target: String,
+    },
+
+    /// Show entities and edges added, changed, or closed between two git revisions
+    ///
+    /// Compares the commit archives closest to each revision.
+    Diff {
+        /// Earlier revision (e.g., main, HEAD~5, a commit hash)
+        rev_a: String,
+
+        /// Later revision
+        rev_b: String,
+    },
+
+    /// Generate PR descriptions and changelogs from closed tasks and bugs
+    ///
+    /// Templates are customizable with `report` blocks in config.kdl.
+    Report {
+        #[command(subcommand)]
+        command: ReportCommands,
+    },
+
     /// Graph analysis commands
     Graph {
         #[command(subcommand)]
@@ -300,9 +362,53 @@ pub enum Commands {
         command: AgentCommands,
     },
 
-    /// Container management commands (requires containerd/buildah)
+    /// Merge queue for agent task branches (rebase, test, then merge)
+    MergeQueue {
+        #[command(subcommand)]
+        command: MergeQueueCommands,
+    },
+
+    /// Messages between agents and humans
+    Msg {
+        #[command(subcommand)]
+        command: MsgCommands,
+    },
+
+    /// Ask a question (to a human by default) and optionally wait for the answer
+    ///
+    /// Humans answer in the GUI, the TUI (`:answer <id> <text>`) or with
+    /// `bn msg reply <id> "answer"`.
+    Ask {
+        /// The question
+        question: String,
+
+        /// Who to ask: an agent ID or name, a role (worker, planner, buddy, ask) or human
+        #[arg(long, default_value = "human")]
+        to: String,
+
+        /// Block until the question is answered
+        #[arg(long)]
+        wait: bool,
+
+        /// Stop waiting after this many seconds
+        #[arg(long, requires = "wait", value_name = "SECONDS")]
+        timeout: Option<u64>,
+    },
+
+    /// Container management commands (containerd, podman, docker or bubblewrap)
     #[command(
-        long_about = "Container management commands (requires containerd/buildah)
+        long_about = "Container management commands (containerd, podman, docker or bubblewrap)
+
+RUNTIME SELECTION:
+
+The container runtime is chosen from, in order:
+   BN_CONTAINER_RUNTIME=<containerd|podman|docker|bubblewrap|auto>
+   runtime \"<name>\" in .binnacle/containers/config.kdl (project)
+   runtime \"<name>\" in ~/.local/share/binnacle/containers/config.kdl (host)
+   auto-detection: containerd, podman, docker, then bubblewrap
+
+bubblewrap runs agents in a namespace sandbox over the host filesystem,
+so it needs no image builds but relies on the host's installed tooling.
 
 ROOTLESS SETUP (recommended to avoid sudo):
 
@@ -380,6 +486,10 @@ for security. CI environments typically set this environment variable.
         /// Load from a .bng archive file (imports to temp directory and serves from there)
         #[arg(long, global = true)]
         archive: Option<String>,
+
+        /// Load the commit archive closest to this git revision
+        #[arg(long, global = true, value_name = "REV", conflicts_with = "archive")]
+        rev: Option<String>,
     },
 
     /// Terminal UI for real-time cluster monitoring (requires 'tui' feature)
@@ -403,6 +513,17 @@ for security. CI environments typically set this environment variable.
         /// auto-launching a local session server. Overrides --port and --host.
         #[arg(long)]
         url: Option<String>,
+
+        /// Bearer token for servers with authentication enabled (see `bn gui token create`)
+        #[arg(long, env = "BN_GUI_TOKEN", hide_env_values = true)]
+        token: Option<String>,
+
+        /// Pin the server certificate by SHA-256 fingerprint for wss:// URLs (repeatable)
+        ///
+        /// Required to connect to servers using a self-signed certificate.
+        /// The fingerprint is printed by `bn session serve --tls`.
+        #[arg(long = "tls-pin", value_name = "FINGERPRINT", requires = "url")]
+        tls_pin: Vec<String>,
     },
 
     /// [REMOVED] Start the session server
@@ -536,9 +657,14 @@ pub enum TaskCommands {
         #[arg(long)]
         reason: Option<String>,
 
-        /// Force close even with incomplete dependencies or missing commits (use with caution)
+        /// Force close even with incomplete dependencies, missing commits or
+        /// failing tests (use with caution; failing tests need a --reason)
         #[arg(long)]
         force: bool,
+
+        /// Run linked tests that haven't passed since the last commit first
+        #[arg(long)]
+        run_tests: bool,
     },
 
     /// Reopen a closed task
@@ -703,9 +829,14 @@ pub enum BugCommands {
         #[arg(long)]
         reason: Option<String>,
 
-        /// Force close even with incomplete dependencies (use with caution)
+        /// Force close even with incomplete dependencies or failing tests
+        /// (use with caution; failing tests need a --reason)
         #[arg(long)]
         force: bool,
+
+        /// Run linked tests that haven't passed since the last commit first
+        #[arg(long)]
+        run_tests: bool,
     },
 
     /// Reopen a closed bug
@@ -1085,6 +1216,14 @@ pub enum DocCommands {
         /// Clear the summary_dirty flag
         #[arg(long)]
         clear_dirty: bool,
+
+        /// If the doc has a newer version, three-way merge this update onto it
+        #[arg(long, conflicts_with = "fork")]
+        merge: bool,
+
+        /// If the doc has a newer version, branch the version chain here anyway
+        #[arg(long)]
+        fork: bool,
     },
 
     /// Show version history for a doc
@@ -1093,6 +1232,20 @@ pub enum DocCommands {
         id: String,
     },
 
+    /// Show a unified diff of the content of two doc versions
+    Diff {
+        /// Doc ID (any version in the chain)
+        id: String,
+
+        /// Older version (e.g., v1, 1, or a doc ID) [default: the version before --to]
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Newer version (e.g., v3, 3, or a doc ID) [default: the given doc]
+        #[arg(long)]
+        to: Option<String>,
+    },
+
     /// Attach a doc to another entity (creates 'documents' edge)
     Attach {
         /// Doc ID (e.g., bn-a1b2)
@@ -1467,6 +1620,40 @@ pub enum GuiCommands {
         #[arg(long)]
         archive: Option<String>,
     },
+
+    /// Manage bearer tokens for the GUI and session servers
+    ///
+    /// Once at least one token exists, every API route and WebSocket
+    /// connection requires a token with a sufficient role.
+    Token {
+        #[command(subcommand)]
+        command: GuiTokenCommands,
+    },
+}
+
+/// GUI token subcommands (server authentication)
+#[cfg(feature = "gui")]
+#[derive(Subcommand, Debug)]
+pub enum GuiTokenCommands {
+    /// Create a new token (the secret is shown once)
+    Create {
+        /// Role granted by the token: viewer (read-only), editor (read/write), admin (everything)
+        #[arg(long, value_parser = ["viewer", "editor", "admin"], value_name = "viewer|editor|admin")]
+        role: String,
+
+        /// Optional label to identify the token
+        #[arg(long)]
+        name: Option<String>,
+    },
+
+    /// List configured tokens
+    List,
+
+    /// Revoke a token by ID
+    Revoke {
+        /// Token ID (e.g., gtk-1a2b3c4d)
+        id: String,
+    },
 }
 
 /// Link subcommands (relationship management)
@@ -1537,6 +1724,18 @@ pub enum TestCommands {
         /// Link to a bug (for verifying bug fixes)
         #[arg(long)]
         bug: Option<String>,
+
+        /// Kill the test after this long, overriding `bn test run --timeout` (e.g. 90s, 5m)
+        #[arg(long, value_parser = parse_duration_secs, value_name = "DURATION")]
+        timeout: Option<u64>,
+
+        /// Environment variable for the command (repeatable)
+        #[arg(long = "env", value_name = "KEY=VALUE")]
+        env: Vec<String>,
+
+        /// Comma-separated globs of the files this test covers (for --affected)
+        #[arg(long, value_name = "GLOBS")]
+        pattern: Option<String>,
     },
 
     /// List test nodes
@@ -1600,6 +1799,59 @@ pub enum TestCommands {
         /// Run only previously failed tests
         #[arg(long)]
         failed: bool,
+
+        /// Run tests whose patterns match files changed since --since, plus
+        /// tests of tasks whose commits touched those files
+        #[arg(long)]
+        affected: bool,
+
+        /// Git ref to diff the worktree against for --affected
+        #[arg(
+            long,
+            value_name = "REF",
+            default_value = "HEAD",
+            requires = "affected"
+        )]
+        since: String,
+
+        /// Number of tests to run at once
+        #[arg(long, short = 'j', default_value = "1", value_parser = clap::value_parser!(u32).range(1..))]
+        jobs: u32,
+
+        /// Kill tests that run longer than this (e.g. 300s, 5m); tests can override it
+        #[arg(long, value_parser = parse_duration_secs, value_name = "DURATION")]
+        timeout: Option<u64>,
+    },
+
+    /// Import results from a JUnit XML, TAP or cargo test JSON report
+    Import {
+        /// Report file
+        file: std::path::PathBuf,
+
+        /// Report format
+        #[arg(long, value_parser = ["junit", "tap", "cargo-json"], value_name = "junit|tap|cargo-json")]
+        format: String,
+
+        /// Command for test nodes created from the report ({name} is the case name)
+        #[arg(long)]
+        cmd: Option<String>,
+
+        /// Link each case to the tasks and bugs tagged in its name ([bn-a1b2] or bn_a1b2)
+        #[arg(long)]
+        link_tags: bool,
+    },
+
+    /// List tests whose outcome flips between reruns of the same commit
+    Flaky,
+
+    /// Quarantine a test so its failures don't reopen linked tasks
+    Quarantine {
+        /// Test ID
+        id: String,
+
+        /// Release the test from quarantine instead
+        #[arg(long)]
+        release: bool,
     },
 }
 
@@ -1627,6 +1879,14 @@ pub enum CommitCommands {
         /// Entity ID (task or bug, e.g., bn-a1b2)
         entity_id: String,
     },
+
+    /// Link commits from `Binnacle-Task:`/`Fixes:` trailers (or bn-xxxx mentions
+    /// when there are none), closing bugs whose fixes reached the default branch
+    Scan {
+        /// Only scan commits after this git ref (default: all of HEAD's history)
+        #[arg(long, value_name = "REF")]
+        since: Option<String>,
+    },
 }
 
 /// Configuration subcommands
@@ -1693,6 +1953,45 @@ pub enum ConfigAgentsCommands {
         /// Agent type name (e.g., worker, do, prd, buddy, ask, free)
         name: String,
     },
+
+    /// Emit the launch plan for an agent's CLI backend
+    ///
+    /// Outputs the argv to exec, extra environment, and config files (MCP
+    /// registration, tool permissions) for the agent's backend: copilot,
+    /// claude, codex, opencode, aider, custom or fake. Used by bn-agent and
+    /// the container entrypoint.
+    Launch {
+        /// Agent name (worker, do, prd, buddy, ask, free)
+        name: String,
+
+        /// Prompt to pass (defaults to the agent's resolved prompt)
+        #[arg(long)]
+        prompt: Option<String>,
+
+        /// Start an interactive session seeded with the prompt
+        #[arg(long)]
+        interactive: bool,
+
+        /// Skip per-tool approval (deny patterns still apply)
+        #[arg(long)]
+        allow_all: bool,
+
+        /// Additional tool pattern to deny (repeatable)
+        #[arg(long = "deny", value_name = "PATTERN")]
+        deny: Vec<String>,
+
+        /// Override the agent's backend
+        #[arg(long, env = "BN_AGENT_BACKEND")]
+        backend: Option<String>,
+
+        /// Directory for generated config files (default: ~/.config/binnacle/backends/<agent>)
+        #[arg(long)]
+        config_dir: Option<std::path::PathBuf>,
+
+        /// Write the generated config files
+        #[arg(long)]
+        write: bool,
+    },
 }
 
 /// MCP server subcommands
@@ -1709,6 +2008,28 @@ pub enum McpCommands {
     Manifest,
 }
 
+/// Report subcommands
+#[derive(Subcommand, Debug)]
+pub enum ReportCommands {
+    /// Markdown PR description for the tasks and bugs closed by commits since a ref
+    Pr {
+        /// Base of the commit range (e.g., main, origin/main, v1.2.0)
+        #[arg(long)]
+        since: String,
+    },
+
+    /// Markdown changelog for the tasks and bugs closed under a milestone
+    Changelog {
+        /// Milestone ID
+        #[arg(long)]
+        milestone: String,
+
+        /// Only include entries linked to commits since this ref
+        #[arg(long)]
+        since: Option<String>,
+    },
+}
+
 /// Log subcommands
 #[derive(Subcommand, Debug)]
 pub enum LogCommands {
@@ -2126,6 +2447,51 @@ pub enum SessionCommands {
         /// Upstream hub URL to connect to (e.g., wss://hub.example.com/sessions)
         #[arg(long)]
         upstream: Option<String>,
+
+        /// Pin the upstream hub certificate by SHA-256 fingerprint (repeatable)
+        ///
+        /// When set, the hub is trusted only if its certificate matches one of
+        /// the pins, which allows self-signed hub certificates.
+        #[arg(
+            long = "upstream-pin",
+            value_name = "FINGERPRINT",
+            requires = "upstream"
+        )]
+        upstream_pin: Vec<String>,
+
+        /// Bearer token to authenticate to the upstream hub
+        ///
+        /// Sent as `Authorization: Bearer <token>` when connecting to the hub.
+        /// Ignored without --upstream.
+        #[arg(
+            long,
+            value_name = "TOKEN",
+            env = "BN_UPSTREAM_TOKEN",
+            hide_env_values = true
+        )]
+        upstream_token: Option<String>,
+
+        /// Serve over TLS (HTTPS/WSS) using a self-signed certificate
+        ///
+        /// The certificate is generated on first use and stored in the session
+        /// directory, so its fingerprint stays stable across restarts.
+        #[arg(long)]
+        tls: bool,
+
+        /// PEM certificate chain to serve over TLS (requires --tls-key)
+        #[arg(long, value_name = "PATH", requires = "tls_key")]
+        tls_cert: Option<std::path::PathBuf>,
+
+        /// PEM private key to serve over TLS (requires --tls-cert)
+        #[arg(long, value_name = "PATH", requires = "tls_cert")]
+        tls_key: Option<std::path::PathBuf>,
+
+        /// Supervise the agent pool from the server (see `bn agent supervise`)
+        ///
+        /// Supervisor state is served on /api/agents and broadcast to
+        /// WebSocket clients after every pass.
+        #[arg(long)]
+        supervise: bool,
     },
 
     /// Check session server status
@@ -2458,6 +2824,10 @@ pub enum AgentCommands {
         #[arg(long)]
         worktree: Option<String>,
 
+        /// Give the agent its own worktree and `bn/<task-id>` branch for this task
+        #[arg(long, conflicts_with = "worktree")]
+        task: Option<String>,
+
         /// Branch to merge into on exit (default: main)
         #[arg(long, default_value = "main")]
         merge_target: String,
@@ -2469,6 +2839,10 @@ pub enum AgentCommands {
         /// Custom initial prompt for the AI agent
         #[arg(long)]
         prompt: Option<String>,
+
+        /// Agent CLI backend inside the container (overrides the agent definition)
+        #[arg(long, value_parser = ["copilot", "claude", "codex", "opencode", "aider", "custom", "fake"])]
+        backend: Option<String>,
     },
 
     /// Run reconciliation loop once (spawn/stop containers to match desired counts)
@@ -2477,12 +2851,216 @@ pub enum AgentCommands {
         #[arg(long)]
         dry_run: bool,
     },
+
+    /// Supervise the agent pool continuously (scale, restart crashes, enforce timeouts)
+    ///
+    /// Runs reconciliation every `interval-secs` (see the `supervisor` block in
+    /// config.kdl) and prints the supervisor state after each pass. Use
+    /// `bn session serve --supervise` to run it inside the session server.
+    Supervise {
+        /// Show what would be done without making changes
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Run a single pass and exit
+        #[arg(long)]
+        once: bool,
+
+        /// Seconds between passes (overrides config.kdl)
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: Option<u64>,
+    },
+
+    /// Report and inspect model usage (tokens, requests, cost) and budgets
+    Usage {
+        #[command(subcommand)]
+        command: AgentUsageCommands,
+    },
+
+    /// Show an agent's transcript (output lines and bn commands it ran)
+    ///
+    /// Transcripts are kept after the agent exits and are linked from the
+    /// tasks it worked on (`bn task show`).
+    Transcript {
+        /// Agent ID or name
+        id: String,
+
+        /// Keep printing new lines until the agent leaves the registry
+        #[arg(long, short = 'f')]
+        follow: bool,
+
+        /// Capture a stream from stdin into the transcript (used by `bn agent spawn`)
+        #[arg(long, hide = true, value_parser = ["stdout", "stderr"])]
+        capture: Option<String>,
+
+        /// Also append captured lines unchanged to this file
+        #[arg(long, hide = true, requires = "capture")]
+        raw_log: Option<String>,
+    },
+
+    /// Per-task git worktrees (created by `bn agent spawn --task`)
+    Worktree {
+        #[command(subcommand)]
+        command: AgentWorktreeCommands,
+    },
+}
+
+/// Agent usage subcommands
+#[derive(Subcommand, Debug)]
+pub enum AgentUsageCommands {
+    /// Record usage for an agent session
+    ///
+    /// Usage is added to the agent record and attributed evenly to the tasks
+    /// the agent is working on. The output says whether a budget covering the
+    /// agent's work is exhausted and the agent should run `bn goodbye`.
+    Report {
+        /// Agent ID, PID, or name (defaults to the calling agent)
+        #[arg(long)]
+        agent: Option<String>,
+
+        /// Prompt tokens used since the last report
+        #[arg(long, default_value = "0")]
+        input_tokens: u64,
+
+        /// Completion tokens used since the last report
+        #[arg(long, default_value = "0")]
+        output_tokens: u64,
+
+        /// Model requests made since the last report
+        #[arg(long, default_value = "0")]
+        requests: u64,
+
+        /// Cost in US dollars since the last report
+        #[arg(long, default_value = "0")]
+        cost: f64,
+    },
+
+    /// Show usage totals by agent type, task and agent, and budget status
+    Show,
 }
 
-/// Container management subcommands (requires containerd/buildah)
+/// Agent worktree subcommands
+#[derive(Subcommand, Debug)]
+pub enum AgentWorktreeCommands {
+    /// Create the worktree and `bn/<task-id>` branch for a task
+    Create {
+        /// Task or bug ID
+        task: String,
+
+        /// Commit or branch to cut the branch from (default: current branch)
+        #[arg(long)]
+        base: Option<String>,
+    },
+
+    /// List task worktrees
+    List,
+
+    /// Run the task's linked tests and merge its branch into the target
+    ///
+    /// A merge conflict or failing test files a bug linked to the task
+    /// (`caused_by`) instead of merging.
+    Finish {
+        /// Task or bug ID
+        task: String,
+
+        /// Branch to merge into (default: BN_MERGE_TARGET or the current branch)
+        #[arg(long)]
+        target: Option<String>,
+
+        /// Merge without running the task's linked tests
+        #[arg(long)]
+        no_tests: bool,
+    },
+
+    /// Remove worktrees of closed tasks whose branches have been merged
+    Gc {
+        /// Branch the task branches must be merged into (default: current branch)
+        #[arg(long)]
+        target: Option<String>,
+
+        /// Also remove unmerged or dirty worktrees of closed tasks
+        #[arg(long)]
+        force: bool,
+
+        /// Show what would be removed without removing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// Merge queue subcommands
+#[derive(Subcommand, Debug)]
+pub enum MergeQueueCommands {
+    /// Add a task's `bn/<task-id>` branch to the queue
+    Add {
+        /// Task or bug ID
+        task: String,
+
+        /// Branch to merge into (default: BN_MERGE_TARGET or the current branch)
+        #[arg(long)]
+        target: Option<String>,
+    },
+
+    /// Show queued and running merges
+    List {
+        /// Include merged, failed and cancelled entries
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Process the queue: rebase each branch onto its target, run the task's
+    /// linked tests and merge when they pass
+    ///
+    /// A conflict or failing test files a bug linked to the task (`caused_by`)
+    /// and reopens the task if it was closed. `bn agent supervise` runs this
+    /// on every pass.
+    Run {
+        /// Process at most one entry
+        #[arg(long)]
+        once: bool,
+    },
+
+    /// Remove a queued entry (by entry ID or task ID)
+    Remove {
+        /// Entry ID (bnmq-xxxx) or task ID
+        id: String,
+    },
+}
+
+/// Message subcommands
+#[derive(Subcommand, Debug)]
+pub enum MsgCommands {
+    /// Send a message
+    Send {
+        /// Recipient: an agent ID or name, a role (worker, planner, buddy, ask) or human
+        #[arg(long)]
+        to: String,
+
+        /// Message text
+        body: String,
+    },
+
+    /// Show unread messages for you (your agent and its role, or human) and mark them read
+    Inbox {
+        /// Include messages you have already read
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Reply to a message; replying to a question answers it
+    Reply {
+        /// Message ID (bnmsg-xxxx)
+        id: String,
+
+        /// Reply text
+        body: String,
+    },
+}
+
+/// Container management subcommands
 #[derive(Subcommand, Debug)]
 pub enum ContainerCommands {
-    /// Build container image(s) using buildah
+    /// Build container image(s) with the selected runtime
     ///
     /// With no arguments, lists available definitions.
     /// With a definition name, builds that definition and its dependencies.
@@ -2608,4 +3186,15 @@ mod tests {
         // This will panic if the CLI is misconfigured
         Cli::command().debug_assert();
     }
+
+    #[test]
+    fn test_parse_duration_secs() {
+        assert_eq!(parse_duration_secs("300s"), Ok(300));
+        assert_eq!(parse_duration_secs("90"), Ok(90));
+        assert_eq!(parse_duration_secs("5m"), Ok(300));
+        assert_eq!(parse_duration_secs("1h"), Ok(3600));
+        assert!(parse_duration_secs("0s").is_err());
+        assert!(parse_duration_secs("5d").is_err());
+        assert!(parse_duration_secs("s").is_err());
+    }
 }
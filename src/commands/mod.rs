@@ -10,10 +10,20 @@
 
 use crate::agents;
 use crate::config::resolver::resolve_state;
+use crate::container::runtime::{
+    BuildSpec, ContainerRuntime, RunMount, RunSpec, definition_mounts, find_runtime,
+    runtime_missing_message, select_runtime,
+};
+pub use crate::container::runtime::{ContainerdMode, detect_containerd_mode};
+use crate::git;
 use crate::models::{
-    Agent, AgentType, Bug, BugSeverity, Doc, DocType, Edge, EdgeDirection, EdgeType, Editor, Idea,
-    IdeaStatus, Issue, IssueStatus, Milestone, Mission, Queue, SessionState, Task, TaskStatus,
-    TestNode, TestResult, complexity::analyze_complexity, graph::UnionFind,
+    Agent, AgentType, Bug, BugSeverity, Doc, DocType, Edge, EdgeDirection, EdgeType, Editor,
+    Flakiness, Idea, IdeaStatus, Issue, IssueStatus, Milestone, Mission, Queue, SessionState, Task,
+    TaskStatus, TestGateOverride, TestNode, TestResult,
+    complexity::analyze_complexity,
+    doc_diff::{merge3, unified_diff},
+    graph::UnionFind,
+    test_report::{self, CaseOutcome, ReportFormat},
 };
 use crate::storage::{
     EntityType, Storage, find_git_root, generate_id, get_test_mode_info, parse_status,
@@ -810,9 +820,10 @@ output-format "json"
     // Build container image if requested and not already present
     let container_built = if build_container {
         let image_name = "localhost/binnacle-self:latest";
-        if !container_image_exists(image_name)? {
+        let runtime = select_runtime(&std::env::current_dir()?)?;
+        if !runtime.image_exists(image_name)? {
             eprintln!("📦 Building binnacle container image...");
-            match build_embedded_container("latest", false) {
+            match build_embedded_container(runtime.as_ref(), "latest", false) {
                 Ok(()) => {
                     eprintln!("✅ Container image built successfully");
                     true
@@ -1458,7 +1469,7 @@ fn init_with_options(
     // Build container image if requested and not already present
     let container_built = if build_container {
         let image_name = "localhost/binnacle-self:latest";
-        if !container_image_exists(image_name)? {
+        if !container_image_exists(repo_path, image_name)? {
             eprintln!("📦 Building binnacle container image...");
             match container_build(
                 repo_path,
@@ -1578,6 +1589,7 @@ Links connect entities in the task graph to model dependencies, relationships, a
 - `bn test create "Name" --cmd "cargo test" --task <id>` - Create and link test
 - `bn test run --all` - Run all tests
 - `bn test run --task <id>` - Run tests for a specific task
+- `bn test run --affected` - Run tests affected by uncommitted changes
 - `bn test list` - List all tests
 
 ### Project Health
@@ -1705,7 +1717,7 @@ bn graph descendants bn-xxxx --depth 1
 5. **After completing work**:
    - Run `bn ready` to check related tasks
    - Close ALL completed tasks: `bn task close <id> --reason "description"`
-   - Run tests: `bn test run --all`
+   - Run the tests your changes affect: `bn test run --affected` (`--all` for everything)
 6. **End of session**: Run `bn goodbye "summary of what was accomplished"` to gracefully terminate
 
 ## Git Rules (CRITICAL)
@@ -2097,14 +2109,7 @@ fn write_mcp_copilot_config_with_container_env() -> Result<bool> {
     // Build env object with container env vars that are actually set
     // Only include vars that are present in the environment
     let mut env_obj = Map::new();
-    for var in &[
-        "BN_DATA_DIR",
-        "BN_CONTAINER_MODE",
-        "BN_STORAGE_HASH",
-        "BN_AGENT_ID",
-        "BN_AGENT_NAME",
-        "BN_AGENT_TYPE",
-    ] {
+    for var in crate::agents::backend::MCP_PASSTHROUGH_ENV {
         if let Ok(val) = std::env::var(var) {
             env_obj.insert(var.to_string(), Value::String(val));
         }
@@ -3032,7 +3037,7 @@ fn get_copilot_data_dir() -> Result<PathBuf> {
 /// disk space compared to /tmp which may have tight quotas on some systems.
 ///
 /// The directory is created if it doesn't exist.
-fn get_binnacle_temp_dir() -> Result<PathBuf> {
+pub(crate) fn get_binnacle_temp_dir() -> Result<PathBuf> {
     let base_dir = if let Ok(override_dir) = std::env::var("BN_DATA_DIR") {
         PathBuf::from(override_dir)
     } else {
@@ -3647,6 +3652,197 @@ pub fn token_test() -> Result<TokenTestResult> {
     }
 }
 
+// === GUI Token Commands ===
+
+/// Prefix for GUI token secrets (makes leaked tokens easy to recognize)
+const GUI_TOKEN_SECRET_PREFIX: &str = "bng_";
+
+/// Result of `bn gui token create`
+#[derive(Debug, Serialize)]
+pub struct GuiTokenCreateResult {
+    /// Public token ID (recorded in audit logs)
+    pub id: String,
+    /// Role granted by the token
+    pub role: String,
+    /// Optional label
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The bearer secret (only shown once)
+    pub token: String,
+}
+
+impl Output for GuiTokenCreateResult {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        let mut lines = vec![format!("✓ Created GUI token {} ({})", self.id, self.role)];
+        if let Some(ref name) = self.name {
+            lines.push(format!("  Name: {}", name));
+        }
+        lines.push(format!("  Token: {}", self.token));
+        lines.push(String::new());
+        lines.push("Store this token now - it cannot be shown again.".to_string());
+        lines.push(
+            "Use it as 'Authorization: Bearer <token>' or open the GUI with ?token=<token>"
+                .to_string(),
+        );
+        lines.join("\n")
+    }
+}
+
+/// Create a new GUI bearer token with the given role.
+///
+/// Tokens are stored (hashed) in the session state.kdl. Creating the first
+/// token enables authentication on the GUI and session servers.
+pub fn gui_token_create(
+    repo_path: &Path,
+    role: &str,
+    name: Option<String>,
+) -> Result<GuiTokenCreateResult> {
+    use crate::config::{GuiRole, GuiToken};
+
+    let role = GuiRole::parse(role).ok_or_else(|| {
+        Error::InvalidInput(format!(
+            "Invalid role '{}'. Valid roles: viewer, editor, admin",
+            role
+        ))
+    })?;
+
+    let storage = Storage::open(repo_path)?;
+    let secret = format!(
+        "{}{}",
+        GUI_TOKEN_SECRET_PREFIX,
+        uuid::Uuid::new_v4().simple()
+    );
+    let id = format!("gtk-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+
+    let token = GuiToken {
+        id: id.clone(),
+        name: name.clone(),
+        role,
+        hash: GuiToken::hash_secret(&secret),
+        created_at: Utc::now(),
+    };
+    storage.update_binnacle_state(|state| state.gui_tokens.push(token))?;
+
+    Ok(GuiTokenCreateResult {
+        id,
+        role: role.to_string(),
+        name,
+        token: secret,
+    })
+}
+
+/// Public information about a GUI token (never includes the secret or hash).
+#[derive(Debug, Serialize)]
+pub struct GuiTokenInfo {
+    pub id: String,
+    pub role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub created_at: String,
+}
+
+/// Result of `bn gui token list`
+#[derive(Debug, Serialize)]
+pub struct GuiTokenListResult {
+    /// Whether the GUI/session server requires authentication
+    pub auth_enabled: bool,
+    pub tokens: Vec<GuiTokenInfo>,
+    pub count: usize,
+}
+
+impl Output for GuiTokenListResult {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        if self.tokens.is_empty() {
+            return "No GUI tokens configured (authentication disabled).\n\nTo create one, run:\n  bn gui token create --role admin".to_string();
+        }
+
+        let mut lines = vec![format!("{} GUI token(s):", self.count)];
+        for token in &self.tokens {
+            let name = token
+                .name
+                .as_deref()
+                .map(|n| format!(" \"{}\"", n))
+                .unwrap_or_default();
+            lines.push(format!(
+                "  {} [{}]{} created {}",
+                token.id, token.role, name, token.created_at
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// List configured GUI tokens.
+pub fn gui_token_list(repo_path: &Path) -> Result<GuiTokenListResult> {
+    let storage = Storage::open(repo_path)?;
+    let state = storage.read_binnacle_state()?;
+
+    let tokens: Vec<GuiTokenInfo> = state
+        .gui_tokens
+        .iter()
+        .map(|t| GuiTokenInfo {
+            id: t.id.clone(),
+            role: t.role.to_string(),
+            name: t.name.clone(),
+            created_at: t.created_at.to_rfc3339(),
+        })
+        .collect();
+
+    Ok(GuiTokenListResult {
+        auth_enabled: state.gui_auth_enabled(),
+        count: tokens.len(),
+        tokens,
+    })
+}
+
+/// Result of `bn gui token revoke`
+#[derive(Debug, Serialize)]
+pub struct GuiTokenRevokeResult {
+    pub id: String,
+    pub revoked: bool,
+    /// Number of tokens remaining (0 means authentication is now disabled)
+    pub remaining: usize,
+}
+
+impl Output for GuiTokenRevokeResult {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        let mut out = format!("✓ Revoked GUI token {}", self.id);
+        if self.remaining == 0 {
+            out.push_str("\n  No tokens remain - GUI authentication is now disabled");
+        }
+        out
+    }
+}
+
+/// Revoke (delete) a GUI token by ID.
+pub fn gui_token_revoke(repo_path: &Path, id: &str) -> Result<GuiTokenRevokeResult> {
+    let storage = Storage::open(repo_path)?;
+    let mut state = storage.read_binnacle_state()?;
+
+    if !state.revoke_gui_token(id) {
+        return Err(Error::NotFound(format!("GUI token not found: {}", id)));
+    }
+    storage.write_binnacle_state(&state)?;
+
+    Ok(GuiTokenRevokeResult {
+        id: id.to_string(),
+        revoked: true,
+        remaining: state.gui_tokens.len(),
+    })
+}
+
 // === Orient Command ===
 
 #[derive(Debug, Serialize)]
@@ -3695,6 +3891,10 @@ pub struct OrientResult {
     pub test_id: Option<String>,
     /// The data root directory being used (useful for debugging test isolation).
     pub data_root: String,
+    /// Latest handoff doc of each open task, left by agents that stopped
+    /// working on it. Read these before resuming the task.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub handoffs: Vec<agents::HandoffRef>,
 }
 
 /// Queue info for orient output.
@@ -3777,6 +3977,17 @@ impl Output for OrientResult {
         // In progress
         lines.push(format!("  In progress: {}", self.in_progress_count));
 
+        // Handoffs left by previous agents
+        if !self.handoffs.is_empty() {
+            lines.push(format!(
+                "  Handoffs: {} (read with 'bn doc show <id>' before resuming)",
+                self.handoffs.len()
+            ));
+            for handoff in self.handoffs.iter().take(5) {
+                lines.push(format!("    └─ {} → {}", handoff.task_id, handoff.doc_id));
+            }
+        }
+
         // Folded secondary sections: Issues, Ideas, Milestones, Queue
         let mut secondary = Vec::new();
 
@@ -4019,7 +4230,8 @@ pub fn orient(
         // Check if already registered (by env ID > MCP session > PID)
         let id = if let Some(mut existing_agent) = existing_by_env_id {
             // BN_AGENT_ID mode - agent already registered with this ID
-            existing_agent.agent_type = agent_type.clone();
+            crate::agents::policy::check_type_unchanged(&existing_agent, &agent_type)
+                .map_err(Error::PermissionDenied)?;
             if purpose.is_some() {
                 existing_agent.purpose = purpose;
             }
@@ -4067,7 +4279,8 @@ pub fn orient(
             id
         } else if let Some(mut existing_agent) = existing_by_session {
             // MCP session already registered - update it
-            existing_agent.agent_type = agent_type.clone();
+            crate::agents::policy::check_type_unchanged(&existing_agent, &agent_type)
+                .map_err(Error::PermissionDenied)?;
             if purpose.is_some() {
                 existing_agent.purpose = purpose;
             }
@@ -4083,7 +4296,8 @@ pub fn orient(
             && let Ok(mut existing_agent) = storage.get_agent(agent_pid)
         {
             // Normal PID-based lookup (no MCP session)
-            existing_agent.agent_type = agent_type.clone();
+            crate::agents::policy::check_type_unchanged(&existing_agent, &agent_type)
+                .map_err(Error::PermissionDenied)?;
             if purpose.is_some() {
                 existing_agent.purpose = purpose;
             }
@@ -4271,6 +4485,9 @@ pub fn orient(
     let (test_mode, test_id, data_root_path) = get_test_mode_info(repo_path)?;
     let data_root = data_root_path.display().to_string();
 
+    // Handoffs left on open tasks by agents that stopped working on them
+    let handoffs = agents::open_handoffs(&storage).unwrap_or_default();
+
     Ok(OrientResult {
         ready: true, // Always true when orient succeeds - store is ready to use
         just_initialized,
@@ -4294,6 +4511,7 @@ pub fn orient(
         test_mode,
         test_id,
         data_root,
+        handoffs,
     })
 }
 
@@ -4446,6 +4664,59 @@ fn get_current_agent(storage: &Storage) -> Option<Agent> {
     storage.get_agent(agent_pid).ok()
 }
 
+/// Whether bn is running inside an agent container (see `bn container run`).
+fn in_agent_container() -> bool {
+    read_agent_env_var("BN_CONTAINER_MODE").is_some() || Path::new("/binnacle").exists()
+}
+
+/// Enforce the calling agent's command policy (see [`crate::agents::policy`]).
+///
+/// The agent type comes from the registered agent (`bn orient --type`) or,
+/// before it registers, from `BN_AGENT_TYPE`. Humans are not restricted, but
+/// inside an agent container a caller that can't be identified (its agent
+/// env vars were removed) may only run `bn orient`, `bn goodbye` and the
+/// entrypoint's `bn system host-init`.
+pub fn check_command_permission(repo_path: &Path, command: &str) -> Result<()> {
+    let registered = if Storage::exists(repo_path).unwrap_or(false) {
+        Storage::open(repo_path)
+            .ok()
+            .and_then(|storage| get_current_agent(&storage))
+    } else {
+        None
+    };
+    let (definition, who) = match registered {
+        Some(agent) => (
+            Some(crate::agents::policy::definition_for(&agent.agent_type)),
+            agent.id,
+        ),
+        None => match read_agent_env_var("BN_AGENT_TYPE") {
+            Some(name) => (crate::agents::policy::definition_for_name(&name), name),
+            None if in_agent_container()
+                && !crate::agents::policy::is_lifecycle(command)
+                && !crate::agents::policy::is_container_setup(command) =>
+            {
+                return Err(Error::PermissionDenied(format!(
+                    "unidentified agents may not run `bn {}` (BN_AGENT_ID and BN_AGENT_TYPE are unset)",
+                    command
+                )));
+            }
+            None => return Ok(()),
+        },
+    };
+    let Some(definition) = definition else {
+        return Ok(());
+    };
+    let Some(resolved) = resolve_agent_for_repo(definition, repo_path)? else {
+        return Ok(());
+    };
+    crate::agents::policy::check(&resolved.agent.commands, command).map_err(|reason| {
+        Error::PermissionDenied(format!(
+            "{} agents may not run `bn {}` ({}; agent {})",
+            definition, command, reason, who
+        ))
+    })
+}
+
 // === Generic Show Command ===
 
 /// Result for generic show command - contains entity type and data.
@@ -4587,6 +4858,11 @@ impl Output for GenericShowResult {
 /// Generic show command - auto-detects entity type and returns formatted data.
 pub fn generic_show(repo_path: &Path, id: &str) -> Result<GenericShowResult> {
     let storage = Storage::open(repo_path)?;
+    generic_show_with(&storage, id)
+}
+
+/// [`generic_show`] against an already open store.
+pub fn generic_show_with(storage: &Storage, id: &str) -> Result<GenericShowResult> {
     let entity_type = storage.get_entity_type(id)?;
 
     let mut result = GenericShowResult {
@@ -4605,13 +4881,13 @@ pub fn generic_show(repo_path: &Path, id: &str) -> Result<GenericShowResult> {
 
     match entity_type {
         EntityType::Task => {
-            let response = task_show(repo_path, id)?;
+            let response = task_show_with(storage, id)?;
             if let TaskShowResponse::Found(task_result) = response {
                 result.task = Some(*task_result);
             }
         }
         EntityType::Bug => {
-            let response = bug_show(repo_path, id)?;
+            let response = bug_show_with(storage, id)?;
             if let BugShowResponse::Found(bug_result) = response {
                 result.bug = Some(*bug_result);
             }
@@ -4620,13 +4896,13 @@ pub fn generic_show(repo_path: &Path, id: &str) -> Result<GenericShowResult> {
             result.issue = Some(storage.get_issue(id)?);
         }
         EntityType::Idea => {
-            result.idea = Some(idea_show(repo_path, id)?);
+            result.idea = Some(storage.get_idea(id)?);
         }
         EntityType::Test => {
-            result.test = Some(test_show(repo_path, id)?);
+            result.test = Some(storage.get_test(id)?);
         }
         EntityType::Milestone => {
-            result.milestone = Some(milestone_show(repo_path, id)?);
+            result.milestone = Some(milestone_show_with(storage, id)?);
         }
         EntityType::Edge => {
             result.edge = Some(storage.get_edge(id)?);
@@ -4635,7 +4911,7 @@ pub fn generic_show(repo_path: &Path, id: &str) -> Result<GenericShowResult> {
             result.queue = Some(storage.get_queue_by_id(id)?);
         }
         EntityType::Doc => {
-            result.doc = Some(doc_show(repo_path, id, false)?);
+            result.doc = Some(doc_show_with(storage, id, false)?);
         }
         EntityType::Agent => {
             result.agent = Some(storage.get_agent_by_id(id)?);
@@ -5011,6 +5287,9 @@ pub struct TaskShowResult {
     pub edges: Vec<TaskEdgeInfo>,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
     pub linked_docs: Vec<LinkedDocInfo>,
+    /// Transcripts of agents that worked on this task (`bn agent transcript`)
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub transcripts: Vec<agents::TranscriptLink>,
 }
 
 /// Information about a doc linked to an entity.
@@ -5217,6 +5496,17 @@ impl Output for TaskShowResult {
             }
         }
 
+        if !self.transcripts.is_empty() {
+            lines.push(String::new());
+            lines.push(format!("Agent Transcripts ({}):", self.transcripts.len()));
+            for link in &self.transcripts {
+                lines.push(format!(
+                    "  {} (bn agent transcript {})",
+                    link.agent_id, link.agent_id
+                ));
+            }
+        }
+
         if let Some(ref blocking) = self.blocking_info {
             lines.push(format!("\n{}", blocking.summary));
         }
@@ -5226,6 +5516,12 @@ impl Output for TaskShowResult {
             if let Some(ref reason) = self.task.closed_reason {
                 lines.push(format!("Reason: {}", reason));
             }
+            if let Some(ref gate_override) = self.task.test_gate_override {
+                lines.push(format!(
+                    "Closed past failing tests: {}",
+                    gate_override.tests.join(", ")
+                ));
+            }
         }
 
         lines.join("\n")
@@ -5454,14 +5750,17 @@ fn build_blocker_summary(blockers: &[DirectBlocker], count: usize) -> String {
 /// Show a task by ID with optional blocking analysis.
 pub fn task_show(repo_path: &Path, id: &str) -> Result<TaskShowResponse> {
     let storage = Storage::open(repo_path)?;
+    task_show_with(&storage, id)
+}
 
+fn task_show_with(storage: &Storage, id: &str) -> Result<TaskShowResponse> {
     // Try to get the task
     match storage.get_task(id) {
         Ok(task) => {
             // Analyze blocking status if task has dependencies (legacy or edge-based)
             let edge_deps = storage.get_edge_dependencies(id).unwrap_or_default();
             let blocking_info = if !task.depends_on.is_empty() || !edge_deps.is_empty() {
-                Some(analyze_blockers(&storage, &task)?)
+                Some(analyze_blockers(storage, &task)?)
             } else {
                 None
             };
@@ -5512,13 +5811,15 @@ pub fn task_show(repo_path: &Path, id: &str) -> Result<TaskShowResponse> {
                 .collect();
 
             // Get linked docs for this task
-            let linked_docs = get_linked_docs_for_entity(&storage, id, false);
+            let linked_docs = get_linked_docs_for_entity(storage, id, false);
+            let transcripts = agents::task_transcripts(storage, id);
 
             Ok(TaskShowResponse::Found(Box::new(TaskShowResult {
                 task,
                 blocking_info,
                 edges,
                 linked_docs,
+                transcripts,
             })))
         }
         Err(Error::NotFound(_)) => {
@@ -5528,13 +5829,13 @@ pub fn task_show(repo_path: &Path, id: &str) -> Result<TaskShowResponse> {
                     let bug = storage.get_bug(id)?;
                     let edge_deps = storage.get_edge_dependencies(id).unwrap_or_default();
                     let blocking_info = if !bug.depends_on.is_empty() || !edge_deps.is_empty() {
-                        Some(analyze_bug_blockers(&storage, &bug)?)
+                        Some(analyze_bug_blockers(storage, &bug)?)
                     } else {
                         None
                     };
                     let hydrated_edges = storage.get_edges_for_entity(id).unwrap_or_default();
-                    let edges = build_edges_info(&storage, hydrated_edges);
-                    let linked_docs = get_linked_docs_for_entity(&storage, id, false);
+                    let edges = build_edges_info(storage, hydrated_edges);
+                    let linked_docs = get_linked_docs_for_entity(storage, id, false);
 
                     Ok(TaskShowResponse::TypeMismatch(Box::new(
                         EntityMismatchResult {
@@ -5978,6 +6279,9 @@ pub struct TaskClosed {
     /// Milestones that were auto-completed because all their child tasks/bugs are now done
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub auto_completed_milestones: Vec<String>,
+    /// Linked tests run by `--run-tests` before closing
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tests_run: Vec<TestRunResult>,
 }
 
 impl Output for TaskClosed {
@@ -5986,7 +6290,11 @@ impl Output for TaskClosed {
     }
 
     fn to_human(&self) -> String {
-        let mut output = format!("Closed task {}", self.id);
+        let mut output = String::new();
+        for result in &self.tests_run {
+            output.push_str(&format!("{}\n", result.to_human()));
+        }
+        output.push_str(&format!("Closed task {}", self.id));
         if let Some(warning) = &self.warning {
             output.push_str(&format!("\nWarning: {}", warning));
         }
@@ -6282,6 +6590,18 @@ pub fn task_close(
     id: &str,
     reason: Option<String>,
     force: bool,
+) -> Result<TaskClosed> {
+    task_close_with(repo_path, id, reason, force, false)
+}
+
+/// Close a task like [`task_close`], first running linked tests that stand
+/// in the way of the test gate when `run_tests` is set.
+pub fn task_close_with(
+    repo_path: &Path,
+    id: &str,
+    reason: Option<String>,
+    force: bool,
+    run_tests: bool,
 ) -> Result<TaskClosed> {
     let mut storage = Storage::open(repo_path)?;
     let task = storage.get_task(id)?;
@@ -6344,6 +6664,17 @@ pub fn task_close(
         }
     }
 
+    let (tests_run, test_gate_override) = check_test_gate(
+        &mut storage,
+        repo_path,
+        "task",
+        id,
+        &task.core.tags,
+        reason.as_deref(),
+        force,
+        run_tests,
+    )?;
+
     // Validate that linked commits exist in the git repo (warn if missing)
     let commits = storage.get_commits_for_task(id)?;
     let missing_commits: Vec<String> = commits
@@ -6358,6 +6689,8 @@ pub fn task_close(
     task.closed_at = Some(Utc::now());
     task.closed_reason = reason;
     task.core.updated_at = Utc::now();
+    let gate_warning = test_gate_override.as_ref().map(test_gate_warning);
+    task.test_gate_override = test_gate_override;
 
     storage.update_task(&task)?;
     promote_partial_tasks(&mut storage)?;
@@ -6393,6 +6726,7 @@ pub fn task_close(
             ));
         }
     }
+    warnings.extend(gate_warning);
     // Warn if no commits are linked to this task
     if commits.is_empty() {
         warnings.push("No commits linked to this task".to_string());
@@ -6417,6 +6751,7 @@ pub fn task_close(
         hint,
         removed_from_queues,
         auto_completed_milestones,
+        tests_run,
     })
 }
 
@@ -6444,6 +6779,7 @@ pub fn task_reopen(repo_path: &Path, id: &str) -> Result<TaskReopened> {
     task.status = TaskStatus::Reopened;
     task.closed_at = None;
     task.closed_reason = None;
+    task.test_gate_override = None;
     task.core.updated_at = Utc::now();
 
     storage.update_task(&task)?;
@@ -6902,6 +7238,12 @@ impl Output for BugShowResult {
             if let Some(ref reason) = self.bug.closed_reason {
                 lines.push(format!("Reason: {}", reason));
             }
+            if let Some(ref gate_override) = self.bug.test_gate_override {
+                lines.push(format!(
+                    "Closed past failing tests: {}",
+                    gate_override.tests.join(", ")
+                ));
+            }
         }
 
         lines.join("\n")
@@ -6951,24 +7293,27 @@ impl Output for BugShowResponse {
 /// Show a bug by ID with edge information.
 pub fn bug_show(repo_path: &Path, id: &str) -> Result<BugShowResponse> {
     let storage = Storage::open(repo_path)?;
+    bug_show_with(&storage, id)
+}
 
+fn bug_show_with(storage: &Storage, id: &str) -> Result<BugShowResponse> {
     // Try to get the bug
     match storage.get_bug(id) {
         Ok(bug) => {
             // Analyze blocking status if bug has dependencies (legacy or edge-based)
             let edge_deps = storage.get_edge_dependencies(id).unwrap_or_default();
             let blocking_info = if !bug.depends_on.is_empty() || !edge_deps.is_empty() {
-                Some(analyze_bug_blockers(&storage, &bug)?)
+                Some(analyze_bug_blockers(storage, &bug)?)
             } else {
                 None
             };
 
             // Fetch edges for this bug
             let hydrated_edges = storage.get_edges_for_entity(id).unwrap_or_default();
-            let edges = build_edges_info(&storage, hydrated_edges);
+            let edges = build_edges_info(storage, hydrated_edges);
 
             // Get linked docs for this bug
-            let linked_docs = get_linked_docs_for_entity(&storage, id, false);
+            let linked_docs = get_linked_docs_for_entity(storage, id, false);
 
             Ok(BugShowResponse::Found(Box::new(BugShowResult {
                 bug,
@@ -6984,13 +7329,13 @@ pub fn bug_show(repo_path: &Path, id: &str) -> Result<BugShowResponse> {
                     let task = storage.get_task(id)?;
                     let edge_deps = storage.get_edge_dependencies(id).unwrap_or_default();
                     let blocking_info = if !task.depends_on.is_empty() || !edge_deps.is_empty() {
-                        Some(analyze_blockers(&storage, &task)?)
+                        Some(analyze_blockers(storage, &task)?)
                     } else {
                         None
                     };
                     let hydrated_edges = storage.get_edges_for_entity(id).unwrap_or_default();
-                    let edges = build_edges_info(&storage, hydrated_edges);
-                    let linked_docs = get_linked_docs_for_entity(&storage, id, false);
+                    let edges = build_edges_info(storage, hydrated_edges);
+                    let linked_docs = get_linked_docs_for_entity(storage, id, false);
 
                     Ok(BugShowResponse::TypeMismatch(Box::new(
                         EntityMismatchResult {
@@ -7001,6 +7346,7 @@ pub fn bug_show(repo_path: &Path, id: &str) -> Result<BugShowResponse> {
                                 blocking_info,
                                 edges,
                                 linked_docs,
+                                transcripts: agents::task_transcripts(storage, id),
                             }),
                             bug: None,
                             test: None,
@@ -7353,6 +7699,9 @@ pub struct BugClosed {
     /// Milestones that were auto-completed because all their child tasks/bugs are now done
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub auto_completed_milestones: Vec<String>,
+    /// Linked tests run by `--run-tests` before closing
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tests_run: Vec<TestRunResult>,
 }
 
 impl Output for BugClosed {
@@ -7361,7 +7710,11 @@ impl Output for BugClosed {
     }
 
     fn to_human(&self) -> String {
-        let mut output = format!("Closed bug {}", self.id);
+        let mut output = String::new();
+        for result in &self.tests_run {
+            output.push_str(&format!("{}\n", result.to_human()));
+        }
+        output.push_str(&format!("Closed bug {}", self.id));
         if let Some(warning) = &self.warning {
             output.push_str(&format!("\nWarning: {}", warning));
         }
@@ -7396,6 +7749,18 @@ pub fn bug_close(
     id: &str,
     reason: Option<String>,
     force: bool,
+) -> Result<BugClosed> {
+    bug_close_with(repo_path, id, reason, force, false)
+}
+
+/// Close a bug like [`bug_close`], first running linked tests that stand
+/// in the way of the test gate when `run_tests` is set.
+pub fn bug_close_with(
+    repo_path: &Path,
+    id: &str,
+    reason: Option<String>,
+    force: bool,
+    run_tests: bool,
 ) -> Result<BugClosed> {
     let mut storage = Storage::open(repo_path)?;
     let bug = storage.get_bug(id)?;
@@ -7428,6 +7793,17 @@ pub fn bug_close(
         )));
     }
 
+    let (tests_run, test_gate_override) = check_test_gate(
+        &mut storage,
+        repo_path,
+        "bug",
+        id,
+        &bug.core.tags,
+        reason.as_deref(),
+        force,
+        run_tests,
+    )?;
+
     // Validate that linked commits exist in the git repo (warn if missing)
     let commits = storage.get_commits_for_entity(id)?;
     let missing_commits: Vec<String> = commits
@@ -7441,6 +7817,8 @@ pub fn bug_close(
     bug.closed_at = Some(Utc::now());
     bug.closed_reason = reason;
     bug.core.updated_at = Utc::now();
+    let gate_warning = test_gate_override.as_ref().map(test_gate_warning);
+    bug.test_gate_override = test_gate_override;
 
     storage.update_bug(&bug)?;
 
@@ -7469,6 +7847,7 @@ pub fn bug_close(
             ));
         }
     }
+    warnings.extend(gate_warning);
     // Warn if no commits are linked to this bug
     if commits.is_empty() {
         warnings.push("No commits linked to this bug".to_string());
@@ -7494,6 +7873,7 @@ pub fn bug_close(
         removed_from_queues,
         auto_resolved_issues,
         auto_completed_milestones,
+        tests_run,
     })
 }
 
@@ -7534,6 +7914,7 @@ pub fn bug_reopen(repo_path: &Path, id: &str) -> Result<BugReopened> {
     bug.status = TaskStatus::Reopened;
     bug.closed_at = None;
     bug.closed_reason = None;
+    bug.test_gate_override = None;
     bug.core.updated_at = Utc::now();
 
     storage.update_bug(&bug)?;
@@ -8896,6 +9277,10 @@ fn extract_summary_section(content: &str) -> Option<String> {
 /// Show a single documentation node.
 pub fn doc_show(repo_path: &Path, id: &str, full: bool) -> Result<DocShowResult> {
     let storage = Storage::open(repo_path)?;
+    doc_show_with(&storage, id, full)
+}
+
+fn doc_show_with(storage: &Storage, id: &str, full: bool) -> Result<DocShowResult> {
     let doc = storage.get_doc(id)?;
 
     // Get linked entities (entities this doc documents)
@@ -9058,6 +9443,13 @@ pub struct DocVersionCreated {
     pub title: String,
     /// Number of edges transferred from old version
     pub edges_transferred: usize,
+    /// Superseded version the update was made against, when it was merged
+    /// onto the latest version
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merged_from: Option<String>,
+    /// True if the update branched off a version that was already superseded
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub forked: bool,
 }
 
 impl Output for DocVersionCreated {
@@ -9066,10 +9458,17 @@ impl Output for DocVersionCreated {
     }
 
     fn to_human(&self) -> String {
-        format!(
+        let mut out = format!(
             "Created new version {} (supersedes {})\n  Title: {}\n  Edges transferred: {}",
             self.new_id, self.previous_id, self.title, self.edges_transferred
-        )
+        );
+        if let Some(base) = &self.merged_from {
+            out.push_str(&format!("\n  Merged changes made against {}", base));
+        }
+        if self.forked {
+            out.push_str("\n  Forked: the previous version already had a newer version");
+        }
+        out
     }
 }
 
@@ -9077,6 +9476,12 @@ impl Output for DocVersionCreated {
 ///
 /// This creates a new doc entity with modifications, sets `supersedes` to point
 /// to the previous version, and transfers all edges from the old doc to the new one.
+///
+/// Updating a version that has already been superseded is refused unless
+/// `merge` or `fork` is set. `merge` applies the changes onto the latest
+/// version with a three-way merge, using the given version as the base, and
+/// fails if the two edits conflict. `fork` branches the chain at the given
+/// version.
 #[allow(clippy::too_many_arguments)]
 pub fn doc_update(
     repo_path: &Path,
@@ -9087,9 +9492,47 @@ pub fn doc_update(
     description: Option<String>,
     editor: Option<&str>,
     clear_dirty: bool,
+    merge: bool,
+    fork: bool,
 ) -> Result<DocVersionCreated> {
     let mut storage = Storage::open(repo_path)?;
 
+    let head_id = storage.get_doc_head(id)?;
+    let mut content = content;
+    let mut merged_from = None;
+    let forked = head_id != id && fork;
+    if head_id != id && !fork {
+        if !merge {
+            return Err(Error::InvalidInput(format!(
+                "Doc {} has been superseded; the latest version is {}. \
+                 Pass --merge to apply this update onto {} with a three-way merge, \
+                 or --fork to branch from {}",
+                id, head_id, head_id, id
+            )));
+        }
+        if let Some(ours) = &content {
+            let decode = |doc: Doc| {
+                doc.get_content()
+                    .map_err(|e| Error::InvalidInput(e.to_string()))
+            };
+            let base = decode(storage.get_doc(id)?)?;
+            let theirs = decode(storage.get_doc(&head_id)?)?;
+            let merged = merge3(&base, ours, &theirs, [id, "update", &head_id]);
+            if merged.conflicts > 0 {
+                return Err(Error::InvalidInput(format!(
+                    "Merging onto {} left {} conflicting section(s). \
+                     See `bn doc diff {} --from {}` for what changed since {}, \
+                     then update {} directly, or pass --fork",
+                    head_id, merged.conflicts, head_id, id, id, head_id
+                )));
+            }
+            content = Some(merged.text);
+        }
+        merged_from = Some(id.to_string());
+    }
+    // The version being superseded
+    let id = if merged_from.is_some() { &head_id } else { id };
+
     // Get the old doc
     let old_doc = storage.get_doc(id)?;
 
@@ -9197,6 +9640,8 @@ pub fn doc_update(
         previous_id: id.to_string(),
         title: new_doc.core.title,
         edges_transferred,
+        merged_from,
+        forked,
     })
 }
 
@@ -9233,6 +9678,8 @@ pub struct DocHistory {
 #[derive(Serialize)]
 pub struct DocHistoryEntry {
     pub id: String,
+    /// Position in the supersedes chain, oldest first (v1 is the original)
+    pub version: usize,
     pub title: String,
     pub editors: Vec<Editor>,
     pub created_at: String,
@@ -9251,7 +9698,7 @@ impl Output for DocHistory {
             self.versions.len()
         )];
 
-        for (i, version) in self.versions.iter().enumerate() {
+        for version in &self.versions {
             let current_marker = if version.is_current { " (current)" } else { "" };
             let editors_str = if version.editors.is_empty() {
                 "unknown".to_string()
@@ -9265,8 +9712,8 @@ impl Output for DocHistory {
             };
 
             lines.push(format!(
-                "  {}. {}{} - {} by {}",
-                i + 1,
+                "  v{} {}{} - {} by {}",
+                version.version,
                 version.id,
                 current_marker,
                 &version.created_at[..10], // Just the date
@@ -9300,6 +9747,7 @@ pub fn doc_history(repo_path: &Path, id: &str) -> Result<DocHistory> {
             id: doc.core.id.clone(),
             title: doc.core.title.clone(),
             editors: doc.editors.clone(),
+            version: 0,
             created_at: doc.core.created_at.to_rfc3339(),
             is_current: versions.is_empty(), // First one we find is current
         });
@@ -9311,12 +9759,170 @@ pub fn doc_history(repo_path: &Path, id: &str) -> Result<DocHistory> {
         }
     }
 
+    let count = versions.len();
+    for (i, version) in versions.iter_mut().enumerate() {
+        version.version = count - i;
+    }
+
     Ok(DocHistory {
         current_id: id.to_string(),
         versions,
     })
 }
 
+/// One side of a doc diff.
+#[derive(Serialize)]
+pub struct DocDiffSide {
+    pub id: String,
+    /// Position in the supersedes chain, oldest first (v1 is the original)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<usize>,
+    pub title: String,
+}
+
+impl DocDiffSide {
+    fn label(&self) -> String {
+        match self.version {
+            Some(v) => format!("{} (v{})", self.id, v),
+            None => self.id.clone(),
+        }
+    }
+}
+
+/// Result from diffing two versions of a doc.
+#[derive(Serialize)]
+pub struct DocDiff {
+    /// Older version, or `None` when diffing against an empty doc
+    pub from: Option<DocDiffSide>,
+    pub to: DocDiffSide,
+    pub added: usize,
+    pub removed: usize,
+    /// Unified diff of the markdown content, empty if unchanged
+    pub diff: String,
+}
+
+impl Output for DocDiff {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        let from = self
+            .from
+            .as_ref()
+            .map_or("(empty)".to_string(), DocDiffSide::label);
+        if self.diff.is_empty() {
+            return format!(
+                "No content changes between {} and {}",
+                from,
+                self.to.label()
+            );
+        }
+        format!(
+            "{} → {} (+{} -{})\n\n{}",
+            from,
+            self.to.label(),
+            self.added,
+            self.removed,
+            self.diff.trim_end()
+        )
+    }
+}
+
+/// Show what changed between two versions of a doc as a unified diff.
+///
+/// `from` and `to` are versions in the doc's supersedes chain (`v2` or `2`,
+/// with v1 the original) or doc IDs. `to` defaults to `id` and `from` to the
+/// version `to` supersedes; the original version is diffed against an empty doc.
+pub fn doc_diff(
+    repo_path: &Path,
+    id: &str,
+    from: Option<&str>,
+    to: Option<&str>,
+) -> Result<DocDiff> {
+    let storage = Storage::open(repo_path)?;
+    doc_diff_with(&storage, id, from, to)
+}
+
+/// `doc_diff` against an already open store.
+pub fn doc_diff_with(
+    storage: &Storage,
+    id: &str,
+    from: Option<&str>,
+    to: Option<&str>,
+) -> Result<DocDiff> {
+    // The chain from the original version to the latest, oldest first
+    let mut chain = Vec::new();
+    let mut current = Some(storage.get_doc_head(id)?);
+    let mut seen = std::collections::HashSet::new();
+    while let Some(doc_id) = current {
+        // Prevent infinite loops from circular references
+        if !seen.insert(doc_id.clone()) {
+            break;
+        }
+        current = storage.get_doc(&doc_id)?.supersedes;
+        chain.push(doc_id);
+    }
+    chain.reverse();
+
+    let resolve = |spec: &str| -> Result<Doc> {
+        let number = spec.strip_prefix('v').unwrap_or(spec);
+        match number.parse::<usize>() {
+            Ok(n) => match chain.get(n.wrapping_sub(1)) {
+                Some(doc_id) => storage.get_doc(doc_id),
+                None => Err(Error::InvalidInput(format!(
+                    "Doc {} has {} version(s); no version {}",
+                    id,
+                    chain.len(),
+                    spec
+                ))),
+            },
+            Err(_) => storage.get_doc(spec),
+        }
+    };
+    let to_doc = resolve(to.unwrap_or(id))?;
+    let from_doc = match from {
+        Some(spec) => Some(resolve(spec)?),
+        None => to_doc
+            .supersedes
+            .as_deref()
+            .map(|prev| storage.get_doc(prev))
+            .transpose()?,
+    };
+
+    let side = |doc: &Doc| DocDiffSide {
+        id: doc.core.id.clone(),
+        version: chain.iter().position(|c| *c == doc.core.id).map(|i| i + 1),
+        title: doc.core.title.clone(),
+    };
+    let content = |doc: &Doc| {
+        doc.get_content()
+            .map_err(|e| Error::InvalidInput(e.to_string()))
+    };
+    let old = match &from_doc {
+        Some(doc) => content(doc)?,
+        None => String::new(),
+    };
+    let from = from_doc.as_ref().map(side);
+    let to = side(&to_doc);
+    let diff = unified_diff(
+        &old,
+        &content(&to_doc)?,
+        &from
+            .as_ref()
+            .map_or("/dev/null".to_string(), DocDiffSide::label),
+        &to.label(),
+    );
+
+    Ok(DocDiff {
+        from,
+        to,
+        added: diff.added,
+        removed: diff.removed,
+        diff: diff.text,
+    })
+}
+
 #[derive(Serialize)]
 pub struct DocAttached {
     pub doc_id: String,
@@ -9626,6 +10232,10 @@ impl Output for MilestoneShowResult {
 /// Show milestone details with progress.
 pub fn milestone_show(repo_path: &Path, id: &str) -> Result<MilestoneShowResult> {
     let storage = Storage::open(repo_path)?;
+    milestone_show_with(&storage, id)
+}
+
+fn milestone_show_with(storage: &Storage, id: &str) -> Result<MilestoneShowResult> {
     let milestone = storage.get_milestone(id)?;
     let progress = storage.get_milestone_progress(id)?;
 
@@ -13025,6 +13635,9 @@ pub struct ReadyTasks {
     pub in_progress_bug_count: usize,
     pub recently_completed_tasks: Vec<Task>,
     pub recently_completed_bugs: Vec<Bug>,
+    /// Exhausted budgets that cover worker spawning (workers should `bn goodbye`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub budget_exhausted: Vec<String>,
 }
 
 impl Output for ReadyTasks {
@@ -13351,10 +13964,20 @@ impl Output for ReadyTasks {
             }
         }
 
-        if lines.is_empty() {
-            return "No in-progress or ready tasks/bugs.".to_string();
-        }
-
+        if !self.budget_exhausted.is_empty() {
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.push("Budget exhausted (workers should run `bn goodbye`):".to_string());
+            for budget in &self.budget_exhausted {
+                lines.push(format!("  {}", budget));
+            }
+        }
+
+        if lines.is_empty() {
+            return "No in-progress or ready tasks/bugs.".to_string();
+        }
+
         lines.join("\n")
     }
 }
@@ -13558,6 +14181,16 @@ pub fn ready(repo_path: &Path, bugs_only: bool, tasks_only: bool) -> Result<Read
     let recently_completed_tasks = storage.get_recently_completed_tasks().unwrap_or_default();
     let recently_completed_bugs = storage.get_recently_completed_bugs().unwrap_or_default();
 
+    // Budget problems are reported by `bn agent usage`; they must not break `bn ready`
+    let budget_exhausted = storage
+        .get_budgets_kdl()
+        .and_then(|config| agents::evaluate_budgets(&storage, &config))
+        .unwrap_or_default()
+        .iter()
+        .filter(|b| b.pauses() == Some(AgentType::Worker))
+        .map(|b| b.summary())
+        .collect();
+
     Ok(ReadyTasks {
         tasks: task_items,
         bugs: bug_items,
@@ -13571,6 +14204,7 @@ pub fn ready(repo_path: &Path, bugs_only: bool, tasks_only: bool) -> Result<Read
         in_progress_bug_count,
         recently_completed_tasks,
         recently_completed_bugs,
+        budget_exhausted,
     })
 }
 
@@ -13747,6 +14381,7 @@ impl Output for TestCreated {
 }
 
 /// Create a new test node.
+#[allow(clippy::too_many_arguments)]
 pub fn test_create(
     repo_path: &Path,
     name: String,
@@ -13754,12 +14389,30 @@ pub fn test_create(
     working_dir: String,
     task_id: Option<String>,
     bug_id: Option<String>,
+    timeout_secs: Option<u64>,
+    env: Vec<String>,
+    pattern: Option<String>,
 ) -> Result<TestCreated> {
     let mut storage = Storage::open(repo_path)?;
 
     let id = generate_id("bnt", &name);
     let mut test = TestNode::new(id.clone(), name.clone(), command);
     test.working_dir = working_dir;
+    test.timeout_secs = timeout_secs;
+    test.pattern = pattern.filter(|p| !p.trim().is_empty());
+    for var in env {
+        match var.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                test.env.insert(key.trim().to_string(), value.to_string());
+            }
+            _ => {
+                return Err(Error::InvalidInput(format!(
+                    "Invalid environment variable '{}': expected KEY=VALUE",
+                    var
+                )));
+            }
+        }
+    }
 
     // If task_id provided, link immediately
     if let Some(tid) = task_id {
@@ -13793,6 +14446,18 @@ impl Output for TestNode {
         if let Some(ref pattern) = self.pattern {
             lines.push(format!("  Pattern: {}", pattern));
         }
+        if let Some(secs) = self.timeout_secs {
+            lines.push(format!("  Timeout: {}s", secs));
+        }
+        if self.quarantined {
+            match self.flaky_bug {
+                Some(ref bug) => lines.push(format!("  Quarantined: yes (bug {})", bug)),
+                None => lines.push("  Quarantined: yes".to_string()),
+            }
+        }
+        for (key, value) in &self.env {
+            lines.push(format!("  Env: {}={}", key, value));
+        }
         if self.linked_tasks.is_empty() {
             lines.push("  Linked tasks: (none)".to_string());
         } else {
@@ -13817,6 +14482,92 @@ pub fn test_show(repo_path: &Path, id: &str) -> Result<TestNode> {
     storage.get_test(id)
 }
 
+#[derive(Serialize)]
+pub struct TestUpdated {
+    pub id: String,
+    pub updated_fields: Vec<String>,
+}
+
+impl Output for TestUpdated {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        format!(
+            "Updated test {}: {}",
+            self.id,
+            self.updated_fields.join(", ")
+        )
+    }
+}
+
+/// Update a test node's name, command, or working directory.
+pub fn test_update(
+    repo_path: &Path,
+    id: &str,
+    name: Option<String>,
+    command: Option<String>,
+    working_dir: Option<String>,
+) -> Result<TestUpdated> {
+    let mut storage = Storage::open(repo_path)?;
+    let mut test = storage.get_test(id)?;
+    let mut updated_fields = Vec::new();
+
+    if let Some(n) = name {
+        if n.trim().is_empty() {
+            return Err(Error::InvalidInput("Test name cannot be empty".to_string()));
+        }
+        test.name = n;
+        updated_fields.push("name".to_string());
+    }
+
+    if let Some(c) = command {
+        if c.trim().is_empty() {
+            return Err(Error::InvalidInput(
+                "Test command cannot be empty".to_string(),
+            ));
+        }
+        test.command = c;
+        updated_fields.push("command".to_string());
+    }
+
+    if let Some(w) = working_dir {
+        test.working_dir = w;
+        updated_fields.push("working_dir".to_string());
+    }
+
+    storage.update_test(&test)?;
+
+    Ok(TestUpdated {
+        id: id.to_string(),
+        updated_fields,
+    })
+}
+
+#[derive(Serialize)]
+pub struct TestDeleted {
+    pub id: String,
+}
+
+impl Output for TestDeleted {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        format!("Deleted test {}", self.id)
+    }
+}
+
+/// Delete a test node.
+pub fn test_delete(repo_path: &Path, id: &str) -> Result<TestDeleted> {
+    let mut storage = Storage::open(repo_path)?;
+    storage.delete_test(id)?;
+
+    Ok(TestDeleted { id: id.to_string() })
+}
+
 #[derive(Serialize)]
 pub struct TestList {
     pub tests: Vec<TestNode>,
@@ -13965,13 +14716,17 @@ pub fn test_unlink_bug(repo_path: &Path, test_id: &str, bug_id: &str) -> Result<
     })
 }
 
-#[derive(Serialize)]
+#[derive(Debug, Serialize)]
 pub struct TestRunResult {
     pub test_id: String,
     pub test_name: String,
     pub passed: bool,
     pub exit_code: i32,
     pub duration_ms: u64,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub quarantined: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stdout: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -13986,11 +14741,25 @@ impl Output for TestRunResult {
     }
 
     fn to_human(&self) -> String {
-        let status = if self.passed { "PASSED" } else { "FAILED" };
+        let status = if self.timed_out {
+            "TIMEOUT"
+        } else if self.passed {
+            "PASSED"
+        } else {
+            "FAILED"
+        };
         let mut lines = Vec::new();
         lines.push(format!(
-            "{} {} ({}) - {}ms",
-            status, self.test_name, self.test_id, self.duration_ms
+            "{} {} ({}) - {}ms{}",
+            status,
+            self.test_name,
+            self.test_id,
+            self.duration_ms,
+            if self.quarantined {
+                " [quarantined]"
+            } else {
+                ""
+            }
         ));
 
         if !self.passed
@@ -14024,6 +14793,35 @@ pub struct TestRunResults {
     pub total: usize,
     pub passed: usize,
     pub failed: usize,
+    pub timed_out: usize,
+    /// Files changed since the `--affected` ref, when tests were selected that way
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changed_files: Option<Vec<String>>,
+}
+
+impl TestRunResults {
+    /// The closing summary line, e.g. `Results: 2 passed, 1 failed, 3 total`.
+    pub fn summary(&self) -> String {
+        if self.results.is_empty() {
+            return match self.changed_files {
+                Some(ref files) => format!(
+                    "No tests affected by {} changed file{}.",
+                    files.len(),
+                    if files.len() == 1 { "" } else { "s" }
+                ),
+                None => "No tests to run.".to_string(),
+            };
+        }
+        let timed_out = if self.timed_out > 0 {
+            format!(" ({} timed out)", self.timed_out)
+        } else {
+            String::new()
+        };
+        format!(
+            "Results: {} passed, {} failed{}, {} total",
+            self.passed, self.failed, timed_out, self.total
+        )
+    }
 }
 
 impl Output for TestRunResults {
@@ -14033,7 +14831,7 @@ impl Output for TestRunResults {
 
     fn to_human(&self) -> String {
         if self.results.is_empty() {
-            return "No tests to run.".to_string();
+            return self.summary();
         }
 
         let mut lines = Vec::new();
@@ -14043,101 +14841,311 @@ impl Output for TestRunResults {
         }
 
         lines.push(String::new());
-        lines.push(format!(
-            "Results: {} passed, {} failed, {} total",
-            self.passed, self.failed, self.total
-        ));
+        lines.push(self.summary());
 
         lines.join("\n")
     }
 }
 
-/// Run a single test and return the result.
-fn run_single_test(
-    storage: &mut Storage,
+/// What running a test command produced, before it is recorded.
+struct TestExecution {
+    exit_code: i32,
+    passed: bool,
+    timed_out: bool,
+    stdout: Option<String>,
+    stderr: Option<String>,
+    duration_ms: u64,
+    commit: Option<String>,
+}
+
+/// Kill a test's whole process group, so commands it spawned die with it.
+#[cfg(unix)]
+fn kill_process_group(child: &mut std::process::Child) {
+    use nix::sys::signal::{Signal, killpg};
+    use nix::unistd::Pid;
+
+    let _ = killpg(Pid::from_raw(child.id() as i32), Signal::SIGKILL);
+    let _ = child.kill();
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(child: &mut std::process::Child) {
+    let _ = child.kill();
+}
+
+/// Run a test's command in `repo_path`, killing it after `timeout_secs`.
+///
+/// The command runs in its own process group with the test's environment
+/// variables set. On timeout the group is killed and the shell reaped.
+fn execute_test(
     test: &TestNode,
     repo_path: &Path,
-) -> Result<TestRunResult> {
+    timeout_secs: Option<u64>,
+) -> Result<TestExecution> {
+    use std::io::Read;
+
     let start = Instant::now();
 
-    // Execute the command
     let working_dir = if test.working_dir == "." {
         repo_path.to_path_buf()
     } else {
         repo_path.join(&test.working_dir)
     };
 
-    let output = Command::new("sh")
+    let mut command = Command::new("sh");
+    command
         .arg("-c")
         .arg(&test.command)
         .current_dir(&working_dir)
-        .output()
+        .envs(&test.env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    let mut child = command
+        .spawn()
         .map_err(|e| Error::Other(format!("Failed to execute command: {}", e)))?;
 
+    // Drain the pipes on threads so a chatty test can't block on a full pipe
+    let mut stdout_pipe = child.stdout.take();
+    let mut stderr_pipe = child.stderr.take();
+    let stdout_reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(pipe) = stdout_pipe.as_mut() {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    });
+    let stderr_reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(pipe) = stderr_pipe.as_mut() {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    });
+
+    let deadline = timeout_secs.map(|secs| start + std::time::Duration::from_secs(secs));
+    let mut timed_out = false;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            timed_out = true;
+            kill_process_group(&mut child);
+            break child.wait()?;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    };
+
     let duration_ms = start.elapsed().as_millis() as u64;
-    let exit_code = output.status.code().unwrap_or(-1);
-    let passed = output.status.success();
+    let stdout = String::from_utf8_lossy(&stdout_reader.join().unwrap_or_default()).to_string();
+    let mut stderr = String::from_utf8_lossy(&stderr_reader.join().unwrap_or_default()).to_string();
+    if timed_out {
+        if !stderr.is_empty() && !stderr.ends_with('\n') {
+            stderr.push('\n');
+        }
+        stderr.push_str(&format!(
+            "Timed out after {}s",
+            timeout_secs.unwrap_or_default()
+        ));
+    }
+
+    Ok(TestExecution {
+        exit_code: status.code().unwrap_or(-1),
+        passed: status.success() && !timed_out,
+        timed_out,
+        stdout: (!stdout.is_empty()).then_some(stdout),
+        stderr: (!stderr.is_empty()).then_some(stderr),
+        duration_ms,
+        commit: tested_commit(repo_path),
+    })
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+/// The commit a test run can be attributed to: HEAD, unless the work tree
+/// has uncommitted changes (reruns on a dirty tree aren't reruns of a commit).
+fn tested_commit(repo_path: &Path) -> Option<String> {
+    if git::is_dirty(repo_path) {
+        return None;
+    }
+    git::rev_parse(repo_path, "HEAD").ok()
+}
 
-    // Save test result
+/// Save a test execution, quarantine the test if it has turned flaky, and
+/// reopen linked tasks if it failed.
+fn record_test_result(
+    storage: &mut Storage,
+    test: &TestNode,
+    execution: TestExecution,
+) -> Result<TestRunResult> {
     let result = TestResult {
         test_id: test.id.clone(),
-        passed,
-        exit_code,
-        stdout: if stdout.is_empty() {
-            None
-        } else {
-            Some(stdout.clone())
-        },
-        stderr: if stderr.is_empty() {
-            None
-        } else {
-            Some(stderr.clone())
-        },
-        duration_ms,
+        passed: execution.passed,
+        exit_code: execution.exit_code,
+        stdout: execution.stdout.clone(),
+        stderr: execution.stderr.clone(),
+        duration_ms: execution.duration_ms,
+        timed_out: execution.timed_out,
+        commit: execution.commit,
         executed_at: Utc::now(),
     };
     storage.save_test_result(&result)?;
+    let quarantined = quarantine_if_flaky(storage, &test.id)?.is_some() || test.quarantined;
 
     // Handle regression detection
     let mut reopened_tasks = Vec::new();
-    if !passed {
+    if !execution.passed {
         reopened_tasks = storage.reopen_linked_tasks_on_failure(&test.id)?;
     }
 
     Ok(TestRunResult {
         test_id: test.id.clone(),
         test_name: test.name.clone(),
-        passed,
-        exit_code,
-        duration_ms,
-        stdout: if stdout.is_empty() {
-            None
-        } else {
-            Some(stdout)
-        },
-        stderr: if stderr.is_empty() {
-            None
-        } else {
-            Some(stderr)
-        },
+        passed: execution.passed,
+        exit_code: execution.exit_code,
+        duration_ms: execution.duration_ms,
+        timed_out: execution.timed_out,
+        quarantined,
+        stdout: execution.stdout,
+        stderr: execution.stderr,
         reopened_tasks,
     })
 }
 
+/// Run a single test and return the result.
+///
+/// The test's own timeout wins over `default_timeout_secs`.
+fn run_single_test(
+    storage: &mut Storage,
+    test: &TestNode,
+    repo_path: &Path,
+    default_timeout_secs: Option<u64>,
+) -> Result<TestRunResult> {
+    let execution = execute_test(test, repo_path, test.timeout_secs.or(default_timeout_secs))?;
+    record_test_result(storage, test, execution)
+}
+
+/// Options for [`test_run`].
+#[derive(Debug, Clone, Copy)]
+pub struct TestRunOptions {
+    /// Number of tests to run at once
+    pub jobs: usize,
+    /// Timeout for tests that don't set their own
+    pub timeout_secs: Option<u64>,
+}
+
+impl Default for TestRunOptions {
+    fn default() -> Self {
+        Self {
+            jobs: 1,
+            timeout_secs: None,
+        }
+    }
+}
+
+/// Tests affected by the files changed between `since` and the working
+/// tree of `work_dir`, along with those files.
+///
+/// A test is affected when one of its pattern globs matches a changed file,
+/// or when a commit linked to one of its tasks or bugs touched a changed file.
+fn affected_tests(
+    storage: &Storage,
+    work_dir: &Path,
+    since: &str,
+) -> Result<(Vec<TestNode>, Vec<String>)> {
+    use std::collections::{HashMap, HashSet};
+
+    let changed = git::changed_files(work_dir, since)?;
+    if changed.is_empty() {
+        return Ok((Vec::new(), changed));
+    }
+    let changed_set: HashSet<&str> = changed.iter().map(String::as_str).collect();
+
+    // Commits are shared between tests of the same task, so look each up once
+    let mut commit_touches: HashMap<String, bool> = HashMap::new();
+    let mut affected = Vec::new();
+    for test in storage.list_tests(None)? {
+        if changed.iter().any(|f| test.matches_path(f)) {
+            affected.push(test);
+            continue;
+        }
+        // Linked entities may have been deleted since, and commits missing
+        // from this clone touch nothing we can see
+        let touched = test
+            .linked_tasks
+            .iter()
+            .chain(&test.linked_bugs)
+            .any(|entity| {
+                let links = storage.get_commits_for_entity(entity).unwrap_or_default();
+                links.iter().any(|link| {
+                    *commit_touches.entry(link.sha.clone()).or_insert_with(|| {
+                        git::files_in_commit(work_dir, &link.sha)
+                            .unwrap_or_default()
+                            .iter()
+                            .any(|f| changed_set.contains(f.as_str()))
+                    })
+                })
+            });
+        if touched {
+            affected.push(test);
+        }
+    }
+    Ok((affected, changed))
+}
+
 /// Run tests based on the provided options.
+///
+/// Results are passed to `on_result` as each test finishes; the returned
+/// results keep the order the tests were selected in.
+#[allow(clippy::too_many_arguments)]
 pub fn test_run(
     repo_path: &Path,
     test_id: Option<&str>,
     task_id: Option<&str>,
     all: bool,
     failed_only: bool,
+    affected_since: Option<&str>,
+    options: TestRunOptions,
+    on_result: &mut dyn FnMut(&TestRunResult),
 ) -> Result<TestRunResults> {
+    test_run_in(
+        repo_path,
+        repo_path,
+        test_id,
+        task_id,
+        all,
+        failed_only,
+        affected_since,
+        options,
+        on_result,
+    )
+}
+
+/// Run tests like [`test_run`], but with commands running in `work_dir`
+/// (e.g. a task worktree) instead of the repository.
+#[allow(clippy::too_many_arguments)]
+pub fn test_run_in(
+    repo_path: &Path,
+    work_dir: &Path,
+    test_id: Option<&str>,
+    task_id: Option<&str>,
+    all: bool,
+    failed_only: bool,
+    affected_since: Option<&str>,
+    options: TestRunOptions,
+    on_result: &mut dyn FnMut(&TestRunResult),
+) -> Result<TestRunResults> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc;
+
     let mut storage = Storage::open(repo_path)?;
 
     // Determine which tests to run
+    let mut changed_files = None;
     let tests: Vec<TestNode> = if let Some(id) = test_id {
         // Run specific test
         vec![storage.get_test(id)?]
@@ -14147,70 +15155,701 @@ pub fn test_run(
     } else if failed_only {
         // Run only previously failed tests
         storage.get_failed_tests()?
+    } else if let Some(since) = affected_since {
+        // Run tests affected by changes since a git ref
+        let (tests, changed) = affected_tests(&storage, work_dir, since)?;
+        changed_files = Some(changed);
+        tests
     } else if all {
         // Run all tests
         storage.list_tests(None)?
     } else {
         return Err(Error::Other(
-            "Specify --all, --failed, --task, or a test ID".to_string(),
+            "Specify --all, --failed, --affected, --task, or a test ID".to_string(),
         ));
     };
 
-    if tests.is_empty() {
-        return Ok(TestRunResults {
-            results: vec![],
-            total: 0,
-            passed: 0,
-            failed: 0,
-        });
-    }
+    // Workers only execute commands; results are recorded (and streamed)
+    // here as they arrive, since storage stays on this thread
+    let next = AtomicUsize::new(0);
+    let mut slots: Vec<Option<TestRunResult>> = tests.iter().map(|_| None).collect();
+    let mut first_error = None;
+    std::thread::scope(|scope| {
+        let (tx, rx) = mpsc::channel();
+        for _ in 0..options.jobs.clamp(1, tests.len().max(1)) {
+            let tx = tx.clone();
+            let (tests, next) = (&tests, &next);
+            scope.spawn(move || {
+                loop {
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(test) = tests.get(index) else {
+                        break;
+                    };
+                    let timeout = test.timeout_secs.or(options.timeout_secs);
+                    if tx
+                        .send((index, execute_test(test, work_dir, timeout)))
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
 
-    let mut results = Vec::new();
-    for test in &tests {
-        let result = run_single_test(&mut storage, test, repo_path)?;
-        results.push(result);
+        for (index, execution) in rx {
+            match execution.and_then(|e| record_test_result(&mut storage, &tests[index], e)) {
+                Ok(result) => {
+                    on_result(&result);
+                    slots[index] = Some(result);
+                }
+                Err(e) => {
+                    // Stop handing out tests; running ones finish
+                    next.store(tests.len(), Ordering::SeqCst);
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+    });
+    if let Some(e) = first_error {
+        return Err(e);
     }
 
+    let results: Vec<TestRunResult> = slots.into_iter().flatten().collect();
     let total = results.len();
     let passed = results.iter().filter(|r| r.passed).count();
     let failed = total - passed;
+    let timed_out = results.iter().filter(|r| r.timed_out).count();
 
     Ok(TestRunResults {
         results,
         total,
         passed,
         failed,
+        timed_out,
+        changed_files,
     })
 }
 
-// === Commit Tracking Commands ===
-
-use crate::models::CommitLink;
-
-// === Doctor Command ===
-
-/// A single issue detected by the doctor command.
-#[derive(Serialize, Clone)]
-pub struct DoctorIssue {
-    pub severity: String, // "error", "warning", "info"
-    pub category: String, // "orphan", "cycle", "consistency", "storage"
-    pub message: String,
+/// A test case recorded by `bn test import`.
+#[derive(Serialize)]
+pub struct ImportedCase {
+    pub test_id: String,
+    pub name: String,
+    pub outcome: CaseOutcome,
+    pub duration_ms: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub entity_id: Option<String>,
+    pub message: Option<String>,
 }
 
-/// Result of the doctor command.
 #[derive(Serialize)]
-pub struct DoctorResult {
-    pub healthy: bool,
-    pub issues: Vec<DoctorIssue>,
-    pub stats: DoctorStats,
+pub struct TestImported {
+    pub format: ReportFormat,
+    pub file: String,
+    pub total: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    /// Test nodes created for cases that didn't match an existing node
+    pub created: Vec<String>,
+    /// Links made from tags in case names (`bnt-xxxx -> bn-xxxx`)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub linked: Vec<String>,
+    /// Tests quarantined as flaky by this import
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub quarantined: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reopened_tasks: Vec<String>,
+    pub cases: Vec<ImportedCase>,
 }
 
-/// Statistics about the binnacle data.
-#[derive(Serialize)]
-pub struct DoctorStats {
-    pub total_tasks: usize,
+impl Output for TestImported {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        let mut lines = vec![format!(
+            "Imported {} test cases from {} ({}): {} passed, {} failed, {} skipped",
+            self.total,
+            self.file,
+            self.format.as_str(),
+            self.passed,
+            self.failed,
+            self.skipped
+        )];
+        if !self.created.is_empty() {
+            lines.push(format!("  Created test nodes: {}", self.created.join(", ")));
+        }
+        for link in &self.linked {
+            lines.push(format!("  Linked {}", link));
+        }
+        if !self.quarantined.is_empty() {
+            lines.push(format!(
+                "  Quarantined as flaky: {}",
+                self.quarantined.join(", ")
+            ));
+        }
+        for case in self
+            .cases
+            .iter()
+            .filter(|c| c.outcome == CaseOutcome::Failed)
+        {
+            let message = case
+                .message
+                .as_deref()
+                .and_then(|m| m.lines().next())
+                .unwrap_or_default();
+            lines.push(format!(
+                "  FAILED {} ({}) {}",
+                case.name, case.test_id, message
+            ));
+        }
+        if !self.reopened_tasks.is_empty() {
+            lines.push(format!(
+                "  Regression detected! Reopened tasks: {}",
+                self.reopened_tasks.join(", ")
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Default command template for test nodes created from a report.
+fn default_import_command(format: ReportFormat) -> Option<&'static str> {
+    match format {
+        ReportFormat::CargoJson => Some("cargo test -- --exact {name}"),
+        ReportFormat::Junit | ReportFormat::Tap => None,
+    }
+}
+
+/// Import results from a JUnit XML, TAP or cargo test JSON report.
+///
+/// Each case is recorded against the test node with the same name. Missing
+/// nodes are created with `command_template` (`{name}` is replaced with the
+/// case name), which cargo JSON reports default to `cargo test -- --exact
+/// {name}`. With `link_tags`, task and bug IDs tagged in case names are
+/// linked to the case's node. Failures reopen linked tasks like
+/// `bn test run` does; skipped cases are counted but not recorded.
+pub fn test_import(
+    repo_path: &Path,
+    format: ReportFormat,
+    file: &Path,
+    command_template: Option<&str>,
+    link_tags: bool,
+) -> Result<TestImported> {
+    let content = fs::read_to_string(file).map_err(|e| {
+        Error::InvalidInput(format!("Cannot read report {}: {}", file.display(), e))
+    })?;
+    let cases = test_report::parse_report(format, &content).map_err(|e| {
+        Error::InvalidInput(format!(
+            "Invalid {} report {}: {}",
+            format.as_str(),
+            file.display(),
+            e
+        ))
+    })?;
+
+    let mut storage = Storage::open(repo_path)?;
+    let commit = tested_commit(repo_path);
+    let mut nodes: std::collections::HashMap<String, TestNode> = storage
+        .list_tests(None)?
+        .into_iter()
+        .map(|t| (t.name.clone(), t))
+        .collect();
+
+    // Check up front that every new node can be created, so a bad import
+    // records nothing
+    let template = command_template.or(default_import_command(format));
+    if template.is_none()
+        && let Some(missing) = cases
+            .iter()
+            .find(|c| c.outcome != CaseOutcome::Skipped && !nodes.contains_key(&c.name))
+    {
+        return Err(Error::InvalidInput(format!(
+            "No test node named '{}'; pass --cmd to create test nodes for {} reports",
+            missing.name,
+            format.as_str()
+        )));
+    }
+
+    let mut result = TestImported {
+        format,
+        file: file.display().to_string(),
+        total: cases.len(),
+        passed: 0,
+        failed: 0,
+        skipped: 0,
+        created: Vec::new(),
+        linked: Vec::new(),
+        quarantined: Vec::new(),
+        reopened_tasks: Vec::new(),
+        cases: Vec::new(),
+    };
+
+    for case in cases {
+        if case.outcome == CaseOutcome::Skipped {
+            result.skipped += 1;
+            continue;
+        }
+
+        let mut test = match nodes.get(&case.name) {
+            Some(test) => test.clone(),
+            None => {
+                let command = template.unwrap_or_default().replace("{name}", &case.name);
+                let test =
+                    TestNode::new(generate_id("bnt", &case.name), case.name.clone(), command);
+                storage.create_test(&test)?;
+                result.created.push(test.id.clone());
+                test
+            }
+        };
+
+        if link_tags {
+            for id in test_report::tagged_ids(&case.name) {
+                let linked = match storage.get_entity_type(&id) {
+                    Ok(EntityType::Task) if !test.linked_tasks.contains(&id) => {
+                        storage.link_test_to_task(&test.id, &id)?;
+                        true
+                    }
+                    Ok(EntityType::Bug) if !test.linked_bugs.contains(&id) => {
+                        storage.link_test_to_bug(&test.id, &id)?;
+                        true
+                    }
+                    _ => false,
+                };
+                if linked {
+                    result.linked.push(format!("{} -> {}", test.id, id));
+                    test = storage.get_test(&test.id)?;
+                }
+            }
+        }
+        nodes.insert(test.name.clone(), test.clone());
+
+        let passed = case.outcome == CaseOutcome::Passed;
+        storage.save_test_result(&TestResult {
+            test_id: test.id.clone(),
+            passed,
+            exit_code: if passed { 0 } else { 1 },
+            stdout: case.output,
+            stderr: case.message.clone(),
+            duration_ms: case.duration_ms,
+            timed_out: false,
+            commit: commit.clone(),
+            executed_at: Utc::now(),
+        })?;
+        if quarantine_if_flaky(&mut storage, &test.id)?.is_some() {
+            result.quarantined.push(test.id.clone());
+        }
+        if passed {
+            result.passed += 1;
+        } else {
+            result.failed += 1;
+            for task_id in storage.reopen_linked_tasks_on_failure(&test.id)? {
+                if !result.reopened_tasks.contains(&task_id) {
+                    result.reopened_tasks.push(task_id);
+                }
+            }
+        }
+
+        result.cases.push(ImportedCase {
+            test_id: test.id,
+            name: case.name,
+            outcome: case.outcome,
+            duration_ms: case.duration_ms,
+            message: case.message,
+        });
+    }
+
+    Ok(result)
+}
+
+// === Flaky Tests ===
+
+/// Default `test.flaky_threshold`: the flakiness score at which a test is
+/// quarantined.
+const DEFAULT_FLAKY_THRESHOLD: f64 = 0.3;
+
+/// Same-commit flips a test needs before it can be quarantined, so one
+/// unlucky rerun isn't enough.
+const MIN_FLAKY_FLIPS: usize = 2;
+
+fn flaky_threshold(storage: &Storage) -> f64 {
+    storage
+        .get_config("test.flaky_threshold")
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_FLAKY_THRESHOLD)
+}
+
+/// A test's flakiness, counting only results since it was last released
+/// from quarantine.
+fn test_flakiness(storage: &Storage, test: &TestNode) -> Result<Flakiness> {
+    let results: Vec<TestResult> = storage
+        .get_test_results(&test.id)?
+        .into_iter()
+        .filter(|r| {
+            test.quarantine_released_at
+                .is_none_or(|released| r.executed_at > released)
+        })
+        .collect();
+    Ok(Flakiness::from_results(&results))
+}
+
+/// Quarantine a test whose flakiness has reached the threshold, filing a
+/// bug the test is linked to with a `tests` edge.
+///
+/// Returns the bug ID if the test was quarantined by this call.
+fn quarantine_if_flaky(storage: &mut Storage, test_id: &str) -> Result<Option<String>> {
+    let mut test = storage.get_test(test_id)?;
+    if test.quarantined {
+        return Ok(None);
+    }
+    let flakiness = test_flakiness(storage, &test)?;
+    if flakiness.flips < MIN_FLAKY_FLIPS || flakiness.score < flaky_threshold(storage) {
+        return Ok(None);
+    }
+
+    let title = format!("Flaky test: {}", test.name);
+    let bug_id = storage.generate_unique_id("bn", &title);
+    let mut bug = Bug::new(bug_id.clone(), title);
+    bug.core.description = Some(format!(
+        "Test {} ({}) changed outcome {} times across {} reruns of the same commit \
+         (flakiness {:.2}). It has been quarantined: its failures no longer reopen linked tasks.\n\n\
+         Command: {}\n\nRelease it with `bn test quarantine {} --release` once it is fixed.",
+        test.name,
+        test.id,
+        flakiness.flips,
+        flakiness.reruns,
+        flakiness.score,
+        test.command,
+        test.id
+    ));
+    bug.core.tags = vec!["flaky".to_string()];
+    bug.severity = BugSeverity::Medium;
+    storage.add_bug(&bug)?;
+
+    let edge_id = storage.generate_edge_id(&test.id, &bug_id, EdgeType::Tests);
+    let mut edge = Edge::new(edge_id, test.id.clone(), bug_id.clone(), EdgeType::Tests);
+    edge.reason = Some("Quarantined as flaky".to_string());
+    storage.add_edge(&edge)?;
+
+    test.quarantined = true;
+    test.flaky_bug = Some(bug_id.clone());
+    storage.update_test(&test)?;
+    Ok(Some(bug_id))
+}
+
+#[derive(Serialize)]
+pub struct FlakyTest {
+    pub id: String,
+    pub name: String,
+    pub quarantined: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flaky_bug: Option<String>,
+    #[serde(flatten)]
+    pub flakiness: Flakiness,
+}
+
+#[derive(Serialize)]
+pub struct FlakyTestList {
+    pub threshold: f64,
+    pub tests: Vec<FlakyTest>,
+}
+
+impl Output for FlakyTestList {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        if self.tests.is_empty() {
+            return "No flaky tests.".to_string();
+        }
+        let mut lines = vec![format!(
+            "{} flaky test(s) (quarantine threshold {:.2}):",
+            self.tests.len(),
+            self.threshold
+        )];
+        for test in &self.tests {
+            let mut line = format!(
+                "  {} {} - flakiness {:.2} ({} flips in {} reruns)",
+                test.id,
+                test.name,
+                test.flakiness.score,
+                test.flakiness.flips,
+                test.flakiness.reruns
+            );
+            if test.quarantined {
+                line.push_str(" [quarantined");
+                if let Some(ref bug) = test.flaky_bug {
+                    line.push_str(&format!(", bug {}", bug));
+                }
+                line.push(']');
+            }
+            lines.push(line);
+        }
+        lines.join("\n")
+    }
+}
+
+/// List tests that have flipped outcome on the same commit, or are
+/// quarantined, flakiest first.
+pub fn test_flaky(repo_path: &Path) -> Result<FlakyTestList> {
+    let storage = Storage::open(repo_path)?;
+    let mut tests = Vec::new();
+    for test in storage.list_tests(None)? {
+        let flakiness = test_flakiness(&storage, &test)?;
+        if flakiness.flips == 0 && !test.quarantined {
+            continue;
+        }
+        tests.push(FlakyTest {
+            id: test.id,
+            name: test.name,
+            quarantined: test.quarantined,
+            flaky_bug: test.flaky_bug,
+            flakiness,
+        });
+    }
+    tests.sort_by(|a, b| {
+        b.quarantined
+            .cmp(&a.quarantined)
+            .then(b.flakiness.score.total_cmp(&a.flakiness.score))
+    });
+
+    Ok(FlakyTestList {
+        threshold: flaky_threshold(&storage),
+        tests,
+    })
+}
+
+#[derive(Serialize)]
+pub struct TestQuarantined {
+    pub id: String,
+    pub quarantined: bool,
+    /// Flaky-test bug closed by the release
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub closed_bug: Option<String>,
+}
+
+impl Output for TestQuarantined {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        if self.quarantined {
+            format!(
+                "Quarantined test {}: failures won't reopen linked tasks",
+                self.id
+            )
+        } else if let Some(bug) = &self.closed_bug {
+            format!(
+                "Released test {} from quarantine and closed flaky bug {}",
+                self.id, bug
+            )
+        } else {
+            format!("Released test {} from quarantine", self.id)
+        }
+    }
+}
+
+/// Quarantine a test by hand, or release it. Releasing resets its
+/// flakiness, so only later results can quarantine it again, and closes the
+/// bug filed when it was quarantined as flaky.
+pub fn test_quarantine(repo_path: &Path, id: &str, release: bool) -> Result<TestQuarantined> {
+    let mut storage = Storage::open(repo_path)?;
+    let mut test = storage.get_test(id)?;
+    test.quarantined = !release;
+    let mut closed_bug = None;
+    if release {
+        test.quarantine_released_at = Some(Utc::now());
+        if let Some(bug_id) = test.flaky_bug.take()
+            && let Ok(mut bug) = storage.get_bug(&bug_id)
+            && bug.status != TaskStatus::Done
+            && bug.status != TaskStatus::Cancelled
+        {
+            bug.status = TaskStatus::Done;
+            bug.closed_at = Some(Utc::now());
+            bug.closed_reason = Some("Test released from quarantine".to_string());
+            bug.core.updated_at = Utc::now();
+            storage.update_bug(&bug)?;
+            closed_bug = Some(bug_id);
+        }
+    }
+    storage.update_test(&test)?;
+
+    Ok(TestQuarantined {
+        id: test.id,
+        quarantined: test.quarantined,
+        closed_bug,
+    })
+}
+
+// === Test Gating ===
+
+/// Whether closing an entity with these tags requires its linked tests to
+/// pass. A `test.require_for_close.<tag>` setting on any of its tags decides
+/// (requiring wins if they disagree); otherwise `test.require_for_close` does.
+fn test_gate_required(storage: &Storage, tags: &[String]) -> bool {
+    let setting = |key: &str| {
+        storage
+            .get_config(key)
+            .ok()
+            .flatten()
+            .map(|v| matches!(v.to_lowercase().as_str(), "true" | "1" | "yes"))
+    };
+    let tag_settings: Vec<bool> = tags
+        .iter()
+        .filter_map(|tag| setting(&format!("test.require_for_close.{}", tag)))
+        .collect();
+    if tag_settings.is_empty() {
+        setting("test.require_for_close").unwrap_or(false)
+    } else {
+        tag_settings.contains(&true)
+    }
+}
+
+/// Linked tests of `entity_id` without a passing result newer than its most
+/// recent linked commit, with why. Commits are dated by git's committer date,
+/// so linking an old commit doesn't invalidate later runs; commits git can't
+/// find fall back to when they were linked. Quarantined tests never block.
+fn tests_blocking_close(
+    storage: &Storage,
+    repo_path: &Path,
+    entity_id: &str,
+) -> Result<Vec<(TestNode, &'static str)>> {
+    let last_commit = storage
+        .get_commits_for_entity(entity_id)?
+        .iter()
+        .map(|c| git::committed_at(repo_path, &c.sha).unwrap_or(c.linked_at))
+        .max();
+    let mut blocking = Vec::new();
+    for test in storage.list_tests(None)? {
+        let linked = test.linked_tasks.iter().any(|t| t == entity_id)
+            || test.linked_bugs.iter().any(|b| b == entity_id);
+        if !linked || test.quarantined {
+            continue;
+        }
+        let why = match storage.get_last_test_result(&test.id)? {
+            None => "never run",
+            Some(r) if !r.passed => "failing",
+            Some(r) if last_commit.is_some_and(|c| r.executed_at <= c) => {
+                "not run since the last commit"
+            }
+            Some(_) => continue,
+        };
+        blocking.push((test, why));
+    }
+    Ok(blocking)
+}
+
+/// Enforce the test gate (see [`test_gate_required`]) before closing a
+/// task or bug.
+///
+/// With `run_tests`, blocking tests are run first. With `force`, the close
+/// goes ahead anyway but needs a reason, which is returned as the override
+/// to record on the entity. Returns the tests run and the override.
+#[allow(clippy::too_many_arguments)]
+fn check_test_gate(
+    storage: &mut Storage,
+    repo_path: &Path,
+    kind: &str,
+    entity_id: &str,
+    tags: &[String],
+    reason: Option<&str>,
+    force: bool,
+    run_tests: bool,
+) -> Result<(Vec<TestRunResult>, Option<TestGateOverride>)> {
+    if !test_gate_required(storage, tags) {
+        return Ok((Vec::new(), None));
+    }
+    let mut blocking = tests_blocking_close(storage, repo_path, entity_id)?;
+    let mut tests_run = Vec::new();
+    if run_tests && !blocking.is_empty() {
+        for (test, _) in &blocking {
+            tests_run.push(run_single_test(storage, test, repo_path, None)?);
+        }
+        blocking = tests_blocking_close(storage, repo_path, entity_id)?;
+    }
+    if blocking.is_empty() {
+        return Ok((tests_run, None));
+    }
+
+    if force {
+        let justification = reason
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+            .ok_or_else(|| {
+                Error::InvalidInput(format!(
+                    "Closing {} {} past failing or stale tests needs a justification: \
+                 pass --reason along with --force",
+                    kind, entity_id
+                ))
+            })?;
+        let gate_override = TestGateOverride {
+            justification: justification.to_string(),
+            tests: blocking.into_iter().map(|(t, _)| t.id).collect(),
+            at: Utc::now(),
+        };
+        return Ok((tests_run, Some(gate_override)));
+    }
+
+    let list: Vec<String> = blocking
+        .iter()
+        .map(|(t, why)| format!("{}: \"{}\" ({})", t.id, t.name, why))
+        .collect();
+    Err(Error::Other(format!(
+        "Cannot close {} {}. {} linked test(s) have not passed since its last commit:\n  - {}\n\n\
+         Run them with: bn {} close {} --run-tests\n\
+         Or close anyway with: bn {} close {} --force --reason \"<justification>\"",
+        kind,
+        entity_id,
+        list.len(),
+        list.join("\n  - "),
+        kind,
+        entity_id,
+        kind,
+        entity_id
+    )))
+}
+
+fn test_gate_warning(gate_override: &TestGateOverride) -> String {
+    format!(
+        "Closed past the test gate; not passing: {}",
+        gate_override.tests.join(", ")
+    )
+}
+
+// === Commit Tracking Commands ===
+
+use crate::models::CommitLink;
+
+// === Doctor Command ===
+
+/// A single issue detected by the doctor command.
+#[derive(Serialize, Clone)]
+pub struct DoctorIssue {
+    pub severity: String, // "error", "warning", "info"
+    pub category: String, // "orphan", "cycle", "consistency", "storage"
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity_id: Option<String>,
+}
+
+/// Result of the doctor command.
+#[derive(Serialize)]
+pub struct DoctorResult {
+    pub healthy: bool,
+    pub issues: Vec<DoctorIssue>,
+    pub stats: DoctorStats,
+}
+
+/// Statistics about the binnacle data.
+#[derive(Serialize)]
+pub struct DoctorStats {
+    pub total_tasks: usize,
     pub total_bugs: usize,
     pub total_tests: usize,
     pub total_commits: usize,
@@ -14620,6 +16259,18 @@ pub fn doctor(repo_path: &Path) -> Result<DoctorResult> {
         });
     }
 
+    // Check commit trailers against commit links
+    if let Some(git_root) = find_git_root(repo_path)
+        && let Ok(log) = git::commit_log(&git_root, None)
+    {
+        let entity_ids: Vec<&str> = tasks
+            .iter()
+            .map(|t| t.core.id.as_str())
+            .chain(bugs.iter().map(|b| b.core.id.as_str()))
+            .collect();
+        issues.extend(commit_trailer_issues(&storage, &entity_ids, &log));
+    }
+
     // Check for legacy .tar.gz archives that need migration to .bng
     if let Some(archive_dir) = config_get_archive_directory(repo_path)
         && archive_dir.exists()
@@ -16022,11 +17673,17 @@ impl Output for ConfigSet {
 pub fn config_set(repo_path: &Path, key: &str, value: &str) -> Result<ConfigSet> {
     // Validate configuration values
     match key {
-        "action_log_enabled"
-        | "action_log_sanitize"
-        | "require_commit_for_close"
-        | "git.co-author.enabled"
-        | "git.anonymous.allow" => {
+        // Per-tag test gate overrides are booleans too
+        _ if matches!(
+            key,
+            "action_log_enabled"
+                | "action_log_sanitize"
+                | "require_commit_for_close"
+                | "test.require_for_close"
+                | "git.co-author.enabled"
+                | "git.anonymous.allow"
+        ) || key.starts_with("test.require_for_close.") =>
+        {
             // Validate boolean values
             let value_lower = value.to_lowercase();
             if value_lower != "true"
@@ -16042,6 +17699,15 @@ pub fn config_set(repo_path: &Path, key: &str, value: &str) -> Result<ConfigSet>
                 )));
             }
         }
+        "test.flaky_threshold" => match value.parse::<f64>() {
+            Ok(t) if t > 0.0 && t <= 1.0 => {}
+            _ => {
+                return Err(Error::Other(format!(
+                    "Invalid value for {}: {}. Must be a number above 0 and at most 1.",
+                    key, value
+                )));
+            }
+        },
         "action_log_max_entries" => {
             // Validate positive integer
             match value.parse::<u32>() {
@@ -16226,7 +17892,8 @@ pub fn config_list(repo_path: &Path) -> Result<ConfigList> {
 // === Agent Configuration Commands ===
 
 use crate::agents::{
-    AGENT_TYPES, CopilotConfig, resolve_agent_for_repo, resolve_all_agents_for_repo,
+    AGENT_TYPES, BackendConfig, BackendKind, CopilotConfig, LaunchPlan, LaunchRequest, backend_for,
+    effective_backend, resolve_agent_for_repo, resolve_all_agents_for_repo,
 };
 use crate::config::ValueSource;
 
@@ -16329,8 +17996,14 @@ pub struct ConfigAgentShow {
     pub tools_allow: Vec<String>,
     /// Denied tools
     pub tools_deny: Vec<String>,
+    /// Allowed bn commands (empty means all that aren't denied)
+    pub commands_allow: Vec<String>,
+    /// Denied bn commands
+    pub commands_deny: Vec<String>,
     /// Copilot runtime configuration
     pub copilot: CopilotConfig,
+    /// Agent CLI backend
+    pub backend: BackendConfig,
     /// Prompt preview (first 500 chars or configurable)
     pub prompt_preview: String,
     /// Full prompt length
@@ -16357,6 +18030,7 @@ impl Output for ConfigAgentShow {
                 }
             ),
             format!("Source: {}", self.source),
+            format!("Backend: {}", self.backend.kind),
             format!("Model: {}", self.copilot.model),
             format!("Reasoning Effort: {}", self.copilot.reasoning_effort),
             format!("Show Reasoning: {}", self.copilot.show_reasoning),
@@ -16383,6 +18057,26 @@ impl Output for ConfigAgentShow {
             }
         }
 
+        lines.push(String::new());
+        lines.push("bn Commands Allowed:".to_string());
+        if self.commands_allow.is_empty() {
+            lines.push("  (all not denied)".to_string());
+        } else {
+            for command in &self.commands_allow {
+                lines.push(format!("  {}", command));
+            }
+        }
+
+        lines.push(String::new());
+        lines.push("bn Commands Denied:".to_string());
+        if self.commands_deny.is_empty() {
+            lines.push("  (none)".to_string());
+        } else {
+            for command in &self.commands_deny {
+                lines.push(format!("  {}", command));
+            }
+        }
+
         lines.push(String::new());
         lines.push(format!(
             "Prompt ({} chars, first 500 shown):",
@@ -16421,7 +18115,10 @@ pub fn config_agents_show(repo_path: &Path, name: &str) -> Result<ConfigAgentSho
         tools_merged: resolved.tools_merged,
         tools_allow: agent.tools.allow.clone(),
         tools_deny: agent.tools.deny.clone(),
+        commands_allow: agent.commands.allow.clone(),
+        commands_deny: agent.commands.deny.clone(),
         copilot: agent.copilot.clone(),
+        backend: agent.backend.clone(),
         prompt_preview,
         prompt_length,
     })
@@ -16547,8 +18244,127 @@ pub fn config_agents_show_agent_file(repo_path: &Path, name: &str) -> Result<Str
     Ok(resolved.agent.generate_agent_file_content())
 }
 
-// === Agent Scaling Configuration ===
-
+/// Launch plan for an agent's CLI backend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigAgentLaunch {
+    /// Agent type name
+    pub name: String,
+    /// Backend, argv, env and config files
+    #[serde(flatten)]
+    pub plan: LaunchPlan,
+    /// Whether the config files were written
+    pub written: bool,
+}
+
+impl Output for ConfigAgentLaunch {
+    fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        let quote = |arg: &String| {
+            if !arg.is_empty()
+                && arg
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_./=:@,".contains(c))
+            {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', "'\\''"))
+            }
+        };
+
+        let mut lines = vec![format!(
+            "Agent: {} (backend: {})",
+            self.name, self.plan.backend
+        )];
+        for (key, value) in &self.plan.env {
+            lines.push(format!("export {}={}", key, quote(value)));
+        }
+        lines.push(
+            self.plan
+                .argv
+                .iter()
+                .map(quote)
+                .collect::<Vec<_>>()
+                .join(" "),
+        );
+        for file in &self.plan.files {
+            lines.push(format!(
+                "{} {}",
+                if self.written { "Wrote" } else { "Would write" },
+                file.path.display()
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Build the launch plan for an agent's CLI backend.
+///
+/// Fills in what the caller leaves empty: the agent's resolved prompt, the
+/// config directory (~/.config/binnacle/backends/<agent>), the MCP server
+/// environment, and the pinned Copilot binary. `backend` overrides both the
+/// definition and `BN_AGENT_BACKEND`.
+pub fn config_agents_launch(
+    repo_path: &Path,
+    name: &str,
+    backend: Option<&str>,
+    mut request: LaunchRequest,
+    write: bool,
+) -> Result<ConfigAgentLaunch> {
+    // Validate agent name
+    if !AGENT_TYPES.contains(&name) {
+        return Err(Error::InvalidInput(format!(
+            "Unknown agent type '{}'. Valid types: {}",
+            name,
+            AGENT_TYPES.join(", ")
+        )));
+    }
+
+    let resolved = resolve_agent_for_repo(name, repo_path)?
+        .ok_or_else(|| Error::NotFound(format!("Agent '{}' not found", name)))?;
+    let mut agent = resolved.agent;
+    agent.backend = effective_backend(&agent)?;
+    if let Some(backend) = backend {
+        agent.backend.kind = backend.parse()?;
+    }
+
+    if request.prompt.is_empty() {
+        request.prompt = agent.prompt.clone();
+    }
+    if request.config_dir.as_os_str().is_empty() {
+        let base = Storage::system_config_kdl_path()
+            .and_then(|p| p.parent().map(Path::to_path_buf))
+            .unwrap_or_else(|| std::env::temp_dir().join("binnacle"));
+        request.config_dir = base.join("backends").join(name);
+    }
+    if request.mcp_env.is_empty() {
+        request.mcp_env = crate::agents::backend::mcp_env_from_process();
+    }
+    if request.program.is_none() && agent.backend.kind == BackendKind::Copilot {
+        // Prefer the pinned Copilot install (bn system copilot install)
+        if let Ok(pinned) = copilot_path(repo_path) {
+            if pinned.exists {
+                request.program = Some(pinned.path);
+            }
+        }
+    }
+
+    let plan = backend_for(&agent.backend)?.launch(&agent, &request)?;
+    if write {
+        plan.write_files()?;
+    }
+
+    Ok(ConfigAgentLaunch {
+        name: agent.name,
+        plan,
+        written: write,
+    })
+}
+
+// === Agent Scaling Configuration ===
+
 /// Configuration for agent scaling.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AgentScalingConfig {
@@ -17012,6 +18828,7 @@ pub fn system_store_dump(repo_path: &Path) -> Result<StoreDumpResult> {
         "commits.jsonl",
         "test-results.jsonl",
         "agents.jsonl",
+        "usage.jsonl",
     ];
 
     let mut files = Vec::new();
@@ -17145,6 +18962,7 @@ pub fn system_store_export(
         "ideas.jsonl",
         "docs.jsonl",
         "milestones.jsonl",
+        "missions.jsonl",
         "queues.jsonl",
         "agents.jsonl",
         "edges.jsonl",
@@ -18685,6 +20503,388 @@ fn system_store_import_from_folder(
     })
 }
 
+// === Graph Snapshots ===
+
+/// The commit archive standing in for a git revision.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveAtRev {
+    /// Revision as given
+    pub rev: String,
+    /// Commit the revision resolves to
+    pub commit: String,
+    /// Nearest ancestor of `commit` (or `commit` itself) with an archive
+    pub archive_commit: String,
+    /// How many commits back from `commit` the archive was found
+    pub commits_back: usize,
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+impl ArchiveAtRev {
+    fn describe(&self) -> String {
+        let short = &self.archive_commit[..7.min(self.archive_commit.len())];
+        match self.commits_back {
+            0 => format!("{} (archive {})", self.rev, short),
+            n => format!(
+                "{} (archive {}, {} commit{} earlier)",
+                self.rev,
+                short,
+                n,
+                if n == 1 { "" } else { "s" }
+            ),
+        }
+    }
+}
+
+/// Find the commit archive closest to `rev`: the archive of `rev` itself, or
+/// of its nearest ancestor that has one.
+pub fn commit_archive_for_rev(repo_path: &Path, rev: &str) -> Result<ArchiveAtRev> {
+    let git_root = find_git_root(repo_path)
+        .ok_or_else(|| Error::Other("Not inside a git repository".to_string()))?;
+    let archive_dir = config_get_archive_directory(repo_path).ok_or_else(|| {
+        Error::Other("Commit archives are disabled (archive.directory is empty)".to_string())
+    })?;
+    let commit = git::rev_parse(&git_root, rev)
+        .map_err(|_| Error::InvalidInput(format!("Unknown git revision: {}", rev)))?;
+
+    for (commits_back, sha) in git::ancestors(&git_root, &commit)?.into_iter().enumerate() {
+        let path = archive_dir.join(format!("bn_{}.bng", sha));
+        if path.is_file() {
+            return Ok(ArchiveAtRev {
+                rev: rev.to_string(),
+                commit,
+                archive_commit: sha,
+                commits_back,
+                path,
+            });
+        }
+    }
+    Err(Error::NotFound(format!(
+        "No graph archive at or before {} in {}. Archives are written by the post-commit hook or `bn system store archive <commit>`.",
+        rev,
+        archive_dir.display()
+    )))
+}
+
+/// A commit archive unpacked into a throwaway store, removed on drop.
+pub struct GraphSnapshot {
+    pub at: ArchiveAtRev,
+    root: tempfile::TempDir,
+}
+
+impl GraphSnapshot {
+    /// Load the archive closest to `rev`.
+    pub fn open(repo_path: &Path, rev: &str) -> Result<Self> {
+        let at = commit_archive_for_rev(repo_path, rev)?;
+        let root = tempfile::tempdir()?;
+        let mut storage = Storage::init_at_root(root.path().to_path_buf())?;
+        unpack_archive_jsonl(&at.path, storage.root())?;
+        storage.rebuild_cache()?;
+        Ok(Self { at, root })
+    }
+
+    /// Open the snapshot's store.
+    pub fn storage(&self) -> Result<Storage> {
+        Storage::open_at_root(self.root.path().to_path_buf())
+    }
+}
+
+/// Write the JSONL files of a `.bng` archive into `dest` unchanged.
+fn unpack_archive_jsonl(archive: &Path, dest: &Path) -> Result<()> {
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use zstd::stream::read::Decoder as ZstdDecoder;
+
+    let data = fs::read(archive)?;
+    let reader: Box<dyn Read> = if data.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
+        Box::new(ZstdDecoder::new(&data[..]).map_err(|e| Error::Io(std::io::Error::other(e)))?)
+    } else {
+        Box::new(GzDecoder::new(&data[..]))
+    };
+    let mut tar = tar::Archive::new(reader);
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.to_path_buf();
+        // Only take the file name so entries can't escape `dest`
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if name.ends_with(".jsonl") {
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents)?;
+            fs::write(dest.join(name), contents)?;
+        }
+    }
+    Ok(())
+}
+
+/// Result of `bn show <id> --at <rev>`.
+#[derive(Serialize)]
+pub struct GenericShowAtResult {
+    pub at: ArchiveAtRev,
+    #[serde(flatten)]
+    pub entity: GenericShowResult,
+}
+
+impl Output for GenericShowAtResult {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        format!("At {}\n\n{}", self.at.describe(), self.entity.to_human())
+    }
+}
+
+/// Show an entity as it was in the archive closest to `rev`.
+pub fn generic_show_at(repo_path: &Path, id: &str, rev: &str) -> Result<GenericShowAtResult> {
+    let snapshot = GraphSnapshot::open(repo_path, rev)?;
+    let entity = generic_show_with(&snapshot.storage()?, id).map_err(|e| match e {
+        Error::NotFound(msg) => Error::NotFound(format!("{} at {}", msg, rev)),
+        other => other,
+    })?;
+    Ok(GenericShowAtResult {
+        at: snapshot.at.clone(),
+        entity,
+    })
+}
+
+/// An entity as listed in a graph diff.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffEntity {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub entity_type: String,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+}
+
+/// An entity whose fields differ between the two revisions.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffChange {
+    #[serde(flatten)]
+    pub entity: DiffEntity,
+    /// Top-level fields that changed, `updated_at` aside
+    pub fields: Vec<String>,
+}
+
+/// Result of `bn diff <rev-a> <rev-b>`.
+#[derive(Debug, Serialize)]
+pub struct GraphDiff {
+    pub from: ArchiveAtRev,
+    pub to: ArchiveAtRev,
+    pub added: Vec<DiffEntity>,
+    pub changed: Vec<DiffChange>,
+    /// Entities that moved to a closed status (done, cancelled, promoted, discarded)
+    pub closed: Vec<DiffEntity>,
+    pub removed: Vec<DiffEntity>,
+    pub edges_added: Vec<Edge>,
+    pub edges_removed: Vec<Edge>,
+}
+
+impl Output for GraphDiff {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        let mut lines = vec![format!(
+            "Graph changes from {} to {}",
+            self.from.describe(),
+            self.to.describe()
+        )];
+        let entity_line = |mark: &str, e: &DiffEntity| {
+            format!("  {} {} [{}] {}", mark, e.id, e.entity_type, e.title)
+        };
+        let edge_line = |mark: &str, e: &Edge| {
+            format!(
+                "  {} {} --[{}]--> {}",
+                mark, e.source, e.edge_type, e.target
+            )
+        };
+
+        if !self.added.is_empty() {
+            lines.push(format!("\nAdded ({}):", self.added.len()));
+            lines.extend(self.added.iter().map(|e| entity_line("+", e)));
+        }
+        if !self.changed.is_empty() {
+            lines.push(format!("\nChanged ({}):", self.changed.len()));
+            lines.extend(
+                self.changed
+                    .iter()
+                    .map(|c| format!("{} ({})", entity_line("~", &c.entity), c.fields.join(", "))),
+            );
+        }
+        if !self.closed.is_empty() {
+            lines.push(format!("\nClosed ({}):", self.closed.len()));
+            lines.extend(self.closed.iter().map(|e| {
+                format!(
+                    "{} -> {}",
+                    entity_line("x", e),
+                    e.status.as_deref().unwrap_or("")
+                )
+            }));
+        }
+        if !self.removed.is_empty() {
+            lines.push(format!("\nRemoved ({}):", self.removed.len()));
+            lines.extend(self.removed.iter().map(|e| entity_line("-", e)));
+        }
+        if !self.edges_added.is_empty() || !self.edges_removed.is_empty() {
+            lines.push(format!(
+                "\nEdges (+{} -{}):",
+                self.edges_added.len(),
+                self.edges_removed.len()
+            ));
+            lines.extend(self.edges_added.iter().map(|e| edge_line("+", e)));
+            lines.extend(self.edges_removed.iter().map(|e| edge_line("-", e)));
+        }
+        if lines.len() == 1 {
+            lines.push("No changes.".to_string());
+        }
+        lines.join("\n")
+    }
+}
+
+/// Entities and edges of a store, keyed by ID.
+struct GraphState {
+    entities: std::collections::BTreeMap<String, serde_json::Value>,
+    edges: std::collections::BTreeMap<String, Edge>,
+}
+
+impl GraphState {
+    fn load(storage: &Storage) -> Result<Self> {
+        let mut entities = std::collections::BTreeMap::new();
+        let mut add = |values: Vec<serde_json::Value>| {
+            for value in values {
+                if let Some(id) = value["id"].as_str() {
+                    entities.insert(id.to_string(), value);
+                }
+            }
+        };
+        fn to_values<T: Serialize>(items: Vec<T>) -> Vec<serde_json::Value> {
+            items
+                .iter()
+                .filter_map(|item| serde_json::to_value(item).ok())
+                .collect()
+        }
+        add(to_values(storage.list_tasks(None, None, None)?));
+        add(to_values(storage.list_bugs(None, None, None, None, true)?));
+        add(to_values(storage.list_issues(None, None, None, true)?));
+        add(to_values(storage.list_ideas(None, None)?));
+        add(to_values(storage.list_docs(None, None, None, None)?));
+        add(to_values(storage.list_milestones(None, None, None)?));
+        add(to_values(storage.list_missions(None, None, None)?));
+        add(to_values(storage.list_tests(None)?));
+        add(to_values(storage.get_queue().into_iter().collect()));
+
+        let edges = storage
+            .list_edges(None, None, None)?
+            .into_iter()
+            .map(|edge| (edge.id.clone(), edge))
+            .collect();
+        Ok(Self { entities, edges })
+    }
+
+    fn load_at(repo_path: &Path, rev: &str) -> Result<(ArchiveAtRev, Self)> {
+        let snapshot = GraphSnapshot::open(repo_path, rev)?;
+        let state = Self::load(&snapshot.storage()?)?;
+        Ok((snapshot.at.clone(), state))
+    }
+}
+
+fn diff_entity(value: &serde_json::Value) -> DiffEntity {
+    let text = |key: &str| value[key].as_str().map(str::to_string);
+    DiffEntity {
+        id: text("id").unwrap_or_default(),
+        entity_type: text("type").unwrap_or_default(),
+        // Tests carry a name rather than a title
+        title: text("title").or_else(|| text("name")).unwrap_or_default(),
+        status: text("status"),
+    }
+}
+
+fn is_closed_status(status: Option<&str>) -> bool {
+    matches!(
+        status,
+        Some("done")
+            | Some("cancelled")
+            | Some("promoted")
+            | Some("discarded")
+            | Some("resolved")
+            | Some("closed")
+            | Some("wont_fix")
+            | Some("by_design")
+            | Some("no_repro")
+    )
+}
+
+/// Compare the graph archived closest to `rev_a` with the one closest to `rev_b`.
+pub fn graph_diff(repo_path: &Path, rev_a: &str, rev_b: &str) -> Result<GraphDiff> {
+    let (from, before) = GraphState::load_at(repo_path, rev_a)?;
+    let (to, after) = GraphState::load_at(repo_path, rev_b)?;
+
+    let mut diff = GraphDiff {
+        from,
+        to,
+        added: Vec::new(),
+        changed: Vec::new(),
+        closed: Vec::new(),
+        removed: Vec::new(),
+        edges_added: Vec::new(),
+        edges_removed: Vec::new(),
+    };
+
+    for (id, new) in &after.entities {
+        let Some(old) = before.entities.get(id) else {
+            diff.added.push(diff_entity(new));
+            continue;
+        };
+        if !is_closed_status(old["status"].as_str()) && is_closed_status(new["status"].as_str()) {
+            diff.closed.push(diff_entity(new));
+            continue;
+        }
+        let (Some(old_fields), Some(new_fields)) = (old.as_object(), new.as_object()) else {
+            continue;
+        };
+        let mut fields: Vec<String> = old_fields
+            .keys()
+            .chain(new_fields.keys())
+            .filter(|key| key.as_str() != "updated_at" && old.get(*key) != new.get(*key))
+            .cloned()
+            .collect();
+        fields.sort();
+        fields.dedup();
+        if !fields.is_empty() {
+            diff.changed.push(DiffChange {
+                entity: diff_entity(new),
+                fields,
+            });
+        }
+    }
+    diff.removed = before
+        .entities
+        .iter()
+        .filter(|(id, _)| !after.entities.contains_key(*id))
+        .map(|(_, old)| diff_entity(old))
+        .collect();
+
+    diff.edges_added = after
+        .edges
+        .iter()
+        .filter(|(id, _)| !before.edges.contains_key(*id))
+        .map(|(_, edge)| edge.clone())
+        .collect();
+    diff.edges_removed = before
+        .edges
+        .iter()
+        .filter(|(id, _)| !after.edges.contains_key(*id))
+        .map(|(_, edge)| edge.clone())
+        .collect();
+
+    Ok(diff)
+}
+
 // === Store Clear Command ===
 
 /// Result of the `bn system store clear` command.
@@ -18958,6 +21158,7 @@ pub fn migrate_storage(repo_path: &Path, to_backend: &str, dry_run: bool) -> Res
         "commits.jsonl",
         "test-results.jsonl",
         "agents.jsonl",
+        "usage.jsonl",
     ];
 
     // Read all data from source
@@ -19508,135 +21709,854 @@ pub fn commit_list(repo_path: &Path, entity_id: &str) -> Result<CommitList> {
     })
 }
 
-// === Agent Commands ===
-
-use crate::models::AgentStatus;
-
-/// Result of agent list command.
+/// A link created by `bn commit scan`.
 #[derive(Serialize)]
-pub struct AgentListResult {
-    pub agents: Vec<AgentInfo>,
-    pub count: usize,
+pub struct ScannedLink {
+    pub sha: String,
+    pub entity_id: String,
 }
 
-/// Agent information for list output.
 #[derive(Serialize)]
-pub struct AgentInfo {
-    /// Unique identifier (e.g., "bn-a1b2")
-    pub id: String,
-    pub pid: u32,
-    pub parent_pid: u32,
-    pub name: String,
-    /// Agent's purpose/role (e.g., "Task Worker", "PRD Generator")
-    pub purpose: Option<String>,
-    pub status: String,
-    pub started_at: String,
-    pub last_activity_at: String,
-    pub tasks: Vec<String>,
-    pub command_count: u64,
-    /// When the agent called goodbye (for fade-out animation timing)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub goodbye_at: Option<String>,
-    /// Health status including stuck detection
-    pub health: crate::models::AgentHealth,
+pub struct CommitScanned {
+    pub scanned: usize,
+    pub linked: Vec<ScannedLink>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub closed_bugs: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
 }
 
-impl Output for AgentListResult {
+impl Output for CommitScanned {
     fn to_json(&self) -> String {
         serde_json::to_string(self).unwrap_or_default()
     }
 
     fn to_human(&self) -> String {
-        if self.agents.is_empty() {
-            return "No agents registered.".to_string();
+        let mut lines = vec![format!(
+            "Scanned {} commit(s): {} new link(s)",
+            self.scanned,
+            self.linked.len()
+        )];
+        for link in &self.linked {
+            let short = &link.sha[..link.sha.len().min(7)];
+            lines.push(format!("  {} -> {}", short, link.entity_id));
         }
+        if !self.closed_bugs.is_empty() {
+            lines.push(format!(
+                "Closed fixed bug(s): {}",
+                self.closed_bugs.join(", ")
+            ));
+        }
+        for warning in &self.warnings {
+            lines.push(format!("Warning: {}", warning));
+        }
+        lines.join("\n")
+    }
+}
 
-        let mut lines = Vec::new();
-        lines.push(format!("Agents ({}):", self.count));
-        lines.push(String::new());
-
-        for agent in &self.agents {
-            let purpose_str = agent
-                .purpose
-                .as_ref()
-                .map(|p| format!(" - {}", p))
-                .unwrap_or_default();
+/// Whether `id` is a task or bug, the entities commits can link to.
+fn is_commit_linkable(storage: &Storage, id: &str) -> bool {
+    matches!(
+        storage.get_entity_type(id),
+        Ok(EntityType::Task | EntityType::Bug)
+    )
+}
 
-            let health_str = if agent.health.is_stuck {
-                format!(" ⚠️ STUCK ({}m idle)", agent.health.idle_minutes)
-            } else {
-                String::new()
-            };
+/// Backfill commit links from the trailers in commit messages on HEAD (after
+/// `since`, if given), or from their `bn-xxxx` mentions when they have none.
+///
+/// Links are dated at the commit time. Bugs named in a `Fixes:` trailer are
+/// closed once the commit is on the default branch (`BN_MERGE_TARGET`, else
+/// `origin/HEAD`, `main` or `master`).
+pub fn commit_scan(repo_path: &Path, since: Option<&str>) -> Result<CommitScanned> {
+    use crate::models::commit_trailers::parse_commit_message;
 
-            lines.push(format!(
-                "  {} (PID {})  {}{}  [{}]{}",
-                agent.id, agent.pid, agent.name, purpose_str, agent.status, health_str
-            ));
-            lines.push(format!(
-                "    Started: {}  Last activity: {}",
-                agent.started_at, agent.last_activity_at
-            ));
-            if !agent.tasks.is_empty() {
-                lines.push(format!("    Tasks: {}", agent.tasks.join(", ")));
+    let repo_root = find_git_root(repo_path).ok_or_else(|| {
+        Error::InvalidInput(format!(
+            "Scanning commits needs a git repository: {}",
+            repo_path.display()
+        ))
+    })?;
+    let log = git::commit_log(&repo_root, since)?;
+    // Bugs close only once a fix reaches the default branch, wherever HEAD is
+    let target = std::env::var("BN_MERGE_TARGET")
+        .ok()
+        .filter(|t| !t.is_empty())
+        .or_else(|| git::default_branch(&repo_root));
+
+    let mut storage = Storage::open(repo_path)?;
+    let mut linked = Vec::new();
+    let mut fixed = Vec::new();
+    for commit in &log {
+        let refs = parse_commit_message(&commit.message);
+        for id in refs.linked_ids() {
+            if !is_commit_linkable(&storage, id) {
+                continue;
             }
-            lines.push(format!("    Commands: {}", agent.command_count));
-            if agent.health.is_stuck {
-                lines.push(format!(
-                    "    ⚠️ Health: Stuck on tasks for {} minutes",
-                    agent.health.idle_minutes
-                ));
+            // Links may use abbreviated SHAs
+            let already_linked = storage
+                .get_commits_for_entity(id)?
+                .iter()
+                .any(|c| commit.sha.starts_with(c.sha.as_str()));
+            if !already_linked {
+                storage.link_commit_at(&commit.sha, id, commit.committed_at)?;
+                linked.push(ScannedLink {
+                    sha: commit.sha.clone(),
+                    entity_id: id.clone(),
+                });
+            }
+        }
+        for id in refs.fixes {
+            let open = storage
+                .get_bug(&id)
+                .is_ok_and(|b| b.status != TaskStatus::Done && b.status != TaskStatus::Cancelled);
+            if let Some(target) = &target
+                && open
+                && !fixed.iter().any(|(bug, _)| *bug == id)
+                && agents::worktree::is_merged(&repo_root, &commit.sha, target)
+            {
+                let sha = &commit.sha[..commit.sha.len().min(7)];
+                fixed.push((id, format!("Fixed by {} on {}", sha, target)));
+            }
+        }
+    }
+    drop(storage);
+
+    // A bug that can't close (e.g. the test gate) stays open with a warning
+    let mut closed_bugs = Vec::new();
+    let mut warnings = Vec::new();
+    for (bug_id, reason) in fixed {
+        match bug_close(repo_path, &bug_id, Some(reason), false) {
+            Ok(_) => closed_bugs.push(bug_id),
+            Err(e) => warnings.push(format!("Could not close {}: {}", bug_id, e)),
+        }
+    }
+
+    Ok(CommitScanned {
+        scanned: log.len(),
+        linked,
+        closed_bugs,
+        warnings,
+    })
+}
+
+/// Doctor issues for commits whose trailers and links disagree: a trailer
+/// naming an entity the commit isn't linked to, or a link from a commit
+/// whose trailers name only other entities.
+fn commit_trailer_issues(
+    storage: &Storage,
+    entity_ids: &[&str],
+    log: &[git::CommitMessage],
+) -> Vec<DoctorIssue> {
+    use crate::models::commit_trailers::parse_commit_message;
+
+    // Links may use abbreviated SHAs
+    let links: Vec<(String, &str)> = entity_ids
+        .iter()
+        .flat_map(|id| {
+            let commits = storage.get_commits_for_entity(id).unwrap_or_default();
+            commits.into_iter().map(move |link| (link.sha, *id))
+        })
+        .collect();
+
+    let mut issues = Vec::new();
+    for commit in log {
+        let trailers = parse_commit_message(&commit.message).trailers;
+        if trailers.is_empty() {
+            continue;
+        }
+        let short = &commit.sha[..commit.sha.len().min(7)];
+        let linked: Vec<&str> = links
+            .iter()
+            .filter(|(sha, _)| commit.sha.starts_with(sha.as_str()))
+            .map(|(_, id)| *id)
+            .collect();
+        for id in &trailers {
+            if !linked.contains(&id.as_str()) && is_commit_linkable(storage, id) {
+                issues.push(DoctorIssue {
+                    severity: "warning".to_string(),
+                    category: "commits".to_string(),
+                    message: format!(
+                        "Commit {} names {} in a trailer but is not linked to it. \
+                         Run 'bn commit scan' to add the link.",
+                        short, id
+                    ),
+                    entity_id: Some(id.clone()),
+                });
+            }
+        }
+        for id in linked {
+            if !trailers.iter().any(|t| t == id) {
+                issues.push(DoctorIssue {
+                    severity: "warning".to_string(),
+                    category: "commits".to_string(),
+                    message: format!(
+                        "Commit {} is linked to {} but its trailers only name {}",
+                        short,
+                        id,
+                        trailers.join(", ")
+                    ),
+                    entity_id: Some(id.to_string()),
+                });
             }
-            lines.push(String::new());
         }
+    }
+    issues
+}
+
+/// A task or bug linked to the commit behind a blamed line.
+#[derive(Debug, Clone, Serialize)]
+pub struct BlameEntity {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub entity_type: EntityType,
+    pub title: String,
+    pub status: TaskStatus,
+}
+
+/// A line of `bn blame` output.
+#[derive(Debug, Clone, Serialize)]
+pub struct BlamedLine {
+    pub line: usize,
+    /// Commit that last changed the line, or `None` if it is uncommitted
+    pub commit: Option<String>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub summary: String,
+    pub content: String,
+    pub entities: Vec<BlameEntity>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BlameResult {
+    pub file: String,
+    pub lines: Vec<BlamedLine>,
+}
+
+impl Output for BlameResult {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        let labels: Vec<String> = self
+            .lines
+            .iter()
+            .map(|l| {
+                if l.commit.is_none() {
+                    "(uncommitted)".to_string()
+                } else if l.entities.is_empty() {
+                    "-".to_string()
+                } else {
+                    l.entities
+                        .iter()
+                        .map(|e| e.id.as_str())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                }
+            })
+            .collect();
+        let width = labels.iter().map(String::len).max().unwrap_or(0);
+        let number_width = self.lines.last().map_or(1, |l| l.line.to_string().len());
+
+        let mut lines: Vec<String> = self
+            .lines
+            .iter()
+            .zip(&labels)
+            .map(|(l, label)| {
+                let short = l
+                    .commit
+                    .as_deref()
+                    .map_or("0000000", |c| &c[..7.min(c.len())]);
+                format!(
+                    "{:>nw$} {} {:<w$} | {}",
+                    l.line,
+                    short,
+                    label,
+                    l.content,
+                    nw = number_width,
+                    w = width
+                )
+            })
+            .collect();
 
+        let mut seen = std::collections::HashSet::new();
+        let legend: Vec<String> = self
+            .lines
+            .iter()
+            .flat_map(|l| &l.entities)
+            .filter(|e| seen.insert(e.id.clone()))
+            .map(|e| {
+                format!(
+                    "  {} [{}] {} ({:?})",
+                    e.id, e.entity_type, e.title, e.status
+                )
+            })
+            .collect();
+        if !legend.is_empty() {
+            lines.push(String::new());
+            lines.extend(legend);
+        }
         lines.join("\n")
     }
 }
 
-/// List active agents.
-pub fn agent_list(repo_path: &Path, status: Option<&str>) -> Result<AgentListResult> {
-    let mut storage = Storage::open(repo_path)?;
-    // Update agent statuses (active/idle/stale) based on activity and process status
-    let _ = storage.update_agent_statuses();
-    // Clean up agents whose processes are dead
-    let _ = storage.cleanup_stale_agents();
-    let agents = storage.list_agents(status)?;
+/// Split `file[:line]` or `file[:start-end]` into the path and line range.
+fn parse_blame_target(target: &str) -> Result<(&str, Option<(usize, usize)>)> {
+    let Some((file, spec)) = target.rsplit_once(':') else {
+        return Ok((target, None));
+    };
+    let parse = |n: &str| n.trim().parse::<usize>().ok().filter(|n| *n > 0);
+    let range = match spec.split_once('-') {
+        Some((start, end)) => parse(start).zip(parse(end)),
+        None => parse(spec).map(|n| (n, n)),
+    };
+    match range {
+        Some((start, end)) if start <= end => Ok((file, Some((start, end)))),
+        Some(_) => Err(Error::InvalidInput(format!(
+            "Line range ends before it starts: {}",
+            spec
+        ))),
+        // Not a line spec, so the colon is part of the path
+        None => Ok((target, None)),
+    }
+}
 
-    let agent_infos: Vec<AgentInfo> = agents
-        .into_iter()
-        .map(|a| {
-            let status_str = match a.status {
-                AgentStatus::Active => "active",
-                AgentStatus::Idle => "idle",
-                AgentStatus::Stale => "stale",
+/// Annotate each line of a file with the tasks and bugs linked to the commit
+/// that last changed it. `target` is `file`, `file:line` or `file:start-end`,
+/// relative to `repo_path`.
+pub fn blame(repo_path: &Path, target: &str) -> Result<BlameResult> {
+    let storage = Storage::open(repo_path)?;
+    blame_with(&storage, repo_path, target)
+}
+
+/// `blame` against an already open store.
+pub fn blame_with(storage: &Storage, repo_path: &Path, target: &str) -> Result<BlameResult> {
+    let (file, range) = parse_blame_target(target)?;
+    let blamed = git::blame(repo_path, file, range)?;
+
+    let mut by_commit: std::collections::HashMap<String, Vec<BlameEntity>> =
+        std::collections::HashMap::new();
+    let mut lines = Vec::with_capacity(blamed.len());
+    for line in blamed {
+        let commit = Some(line.sha).filter(|sha| !sha.chars().all(|c| c == '0'));
+        let entities = match &commit {
+            Some(sha) => {
+                if !by_commit.contains_key(sha) {
+                    let mut entities = Vec::new();
+                    for id in storage.get_tasks_for_commit(sha)? {
+                        let entity = match storage.get_entity_type(&id) {
+                            Ok(EntityType::Task) => storage.get_task(&id).map(|t| BlameEntity {
+                                id: id.clone(),
+                                entity_type: EntityType::Task,
+                                title: t.core.title,
+                                status: t.status,
+                            }),
+                            Ok(EntityType::Bug) => storage.get_bug(&id).map(|b| BlameEntity {
+                                id: id.clone(),
+                                entity_type: EntityType::Bug,
+                                title: b.core.title,
+                                status: b.status,
+                            }),
+                            _ => continue,
+                        };
+                        // Links can outlive deleted entities
+                        if let Ok(entity) = entity {
+                            entities.push(entity);
+                        }
+                    }
+                    entities.sort_by(|a, b| a.id.cmp(&b.id));
+                    by_commit.insert(sha.clone(), entities);
+                }
+                by_commit[sha].clone()
+            }
+            None => Vec::new(),
+        };
+        lines.push(BlamedLine {
+            line: line.line,
+            commit,
+            summary: line.summary,
+            content: line.content,
+            entities,
+        });
+    }
+
+    Ok(BlameResult {
+        file: file.to_string(),
+        lines,
+    })
+}
+
+// === Reports ===
+
+use crate::config::ReportTemplate;
+
+/// A closed task or bug in a `bn report`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportEntry {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub entity_type: EntityType,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    pub tags: Vec<String>,
+    /// Linked commits, oldest first
+    pub commits: Vec<String>,
+    /// Group heading the entry is listed under
+    pub group: String,
+    #[serde(skip)]
+    closed_at: Option<chrono::DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportResult {
+    pub kind: String,
+    pub markdown: String,
+    pub entries: Vec<ReportEntry>,
+    /// Number of commits in the range, if the report covers one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commits: Option<usize>,
+    /// Tasks and bugs linked to commits in range that are not done yet
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub still_open: Vec<String>,
+}
+
+impl Output for ReportResult {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        let mut out = self.markdown.trim_end().to_string();
+        if !self.still_open.is_empty() {
+            out.push_str(&format!(
+                "\n\n<!-- still open: {} -->",
+                self.still_open.join(", ")
+            ));
+        }
+        out
+    }
+}
+
+/// Load a task or bug as a report entry, with its status.
+fn report_entry(storage: &Storage, id: &str) -> Option<(ReportEntry, TaskStatus)> {
+    let (entity_type, core, status, reason, closed_at) = match storage.get_entity_type(id) {
+        Ok(EntityType::Task) => {
+            let t = storage.get_task(id).ok()?;
+            (
+                EntityType::Task,
+                t.core,
+                t.status,
+                t.closed_reason,
+                t.closed_at,
+            )
+        }
+        Ok(EntityType::Bug) => {
+            let b = storage.get_bug(id).ok()?;
+            (
+                EntityType::Bug,
+                b.core,
+                b.status,
+                b.closed_reason,
+                b.closed_at,
+            )
+        }
+        _ => return None,
+    };
+    let entry = ReportEntry {
+        id: id.to_string(),
+        entity_type,
+        title: core.title,
+        reason: reason.filter(|r| !r.trim().is_empty()),
+        tags: core.tags,
+        commits: Vec::new(),
+        group: String::new(),
+        closed_at,
+    };
+    Some((entry, status))
+}
+
+/// Nearest milestone and mission above an entity, following child_of edges.
+fn report_ancestors(storage: &Storage, id: &str) -> Result<(Option<String>, Option<String>)> {
+    let mut milestone = None;
+    let mut mission = None;
+    let mut seen = std::collections::HashSet::from([id.to_string()]);
+    let mut queue = std::collections::VecDeque::from([id.to_string()]);
+    while let Some(current) = queue.pop_front() {
+        for edge in storage.list_edges(Some(EdgeType::ChildOf), Some(&current), None)? {
+            if !seen.insert(edge.target.clone()) {
+                continue;
+            }
+            if milestone.is_none()
+                && let Ok(m) = storage.get_milestone(&edge.target)
+            {
+                milestone = Some(m.core.title);
+            } else if mission.is_none()
+                && let Ok(m) = storage.get_mission(&edge.target)
+            {
+                mission = Some(m.core.title);
+            }
+            queue.push_back(edge.target);
+        }
+    }
+    Ok((milestone, mission))
+}
+
+/// Assign each entry to the first `group-by` dimension it has a value for.
+fn group_report_entries(
+    storage: &Storage,
+    template: &ReportTemplate,
+    entries: &mut [ReportEntry],
+) -> Result<()> {
+    for entry in entries.iter_mut() {
+        let (milestone, mission) = report_ancestors(storage, &entry.id)?;
+        entry.group = template
+            .group_by
+            .iter()
+            .find_map(|dimension| match dimension.as_str() {
+                "mission" => mission.clone(),
+                "milestone" => milestone.clone(),
+                "tag" => entry.tags.first().cloned(),
+                "type" => Some(match entry.entity_type {
+                    EntityType::Bug => "Bugs".to_string(),
+                    _ => "Tasks".to_string(),
+                }),
+                _ => None,
+            })
+            .unwrap_or_else(|| template.ungrouped.clone());
+    }
+    Ok(())
+}
+
+/// Render report entries as markdown. Groups keep the order their first
+/// entry appears in, with ungrouped entries last.
+fn render_report(template: &ReportTemplate, title: &str, entries: &[ReportEntry]) -> String {
+    let mut out = format!("{}\n", title);
+    if entries.is_empty() {
+        out.push_str(&format!("\n{}\n", template.empty));
+        return out;
+    }
+
+    let mut groups: Vec<&str> = Vec::new();
+    for entry in entries {
+        if !groups.contains(&entry.group.as_str()) {
+            groups.push(&entry.group);
+        }
+    }
+    groups.sort_by_key(|g| *g == template.ungrouped);
+
+    let link = |url: &Option<String>, key: &str, value: &str, text: &str| match url {
+        Some(url) => format!(
+            "[{}]({})",
+            text,
+            ReportTemplate::render(url, &[(key, value)])
+        ),
+        None => format!("`{}`", text),
+    };
+    for group in groups {
+        if !template.group_by.is_empty() {
+            out.push_str(&format!(
+                "\n{}\n\n",
+                ReportTemplate::render(&template.heading, &[("group", group)])
+            ));
+        } else {
+            out.push('\n');
+        }
+        for entry in entries.iter().filter(|e| e.group == group) {
+            let id = link(&template.link, "id", &entry.id, &entry.id);
+            let commits = entry
+                .commits
+                .iter()
+                .map(|sha| link(&template.commit_link, "sha", sha, &sha[..7.min(sha.len())]))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let entity_type = entry.entity_type.to_string();
+            let tags = entry.tags.join(", ");
+            let (pattern, reason) = match &entry.reason {
+                Some(reason) => (&template.entry, reason.as_str()),
+                None => (&template.entry_no_reason, ""),
             };
-            let health = a.compute_health();
-            AgentInfo {
-                id: a.id,
-                pid: a.pid,
-                parent_pid: a.parent_pid,
-                name: a.name,
-                purpose: a.purpose,
-                status: status_str.to_string(),
-                started_at: a.started_at.to_rfc3339(),
-                last_activity_at: a.last_activity_at.to_rfc3339(),
-                tasks: a.tasks,
-                command_count: a.command_count,
-                goodbye_at: a.goodbye_at.map(|t| t.to_rfc3339()),
-                health,
+            out.push_str(&ReportTemplate::render(
+                pattern,
+                &[
+                    ("id", &id),
+                    ("title", &entry.title),
+                    ("type", &entity_type),
+                    ("reason", reason),
+                    ("commits", &commits),
+                    ("tags", &tags),
+                ],
+            ));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn report_repo_root(repo_path: &Path) -> Result<PathBuf> {
+    find_git_root(repo_path).ok_or_else(|| {
+        Error::InvalidInput(format!(
+            "Reports need a git repository: {}",
+            repo_path.display()
+        ))
+    })
+}
+
+/// Entity IDs with their linked commits, in order of first commit.
+type ReportLinks = Vec<(String, Vec<String>)>;
+
+/// Number of commits in `since..HEAD` and the tasks and bugs linked to them.
+fn report_entities_in_range(
+    storage: &Storage,
+    repo_root: &Path,
+    since: &str,
+) -> Result<(usize, ReportLinks)> {
+    let commits = git::commits_between(repo_root, since, "HEAD")?;
+    let mut linked: ReportLinks = Vec::new();
+    for sha in &commits {
+        for id in storage.get_tasks_for_commit(sha)? {
+            match linked.iter_mut().find(|(e, _)| *e == id) {
+                Some((_, shas)) => shas.push(sha.clone()),
+                None => linked.push((id, vec![sha.clone()])),
             }
-        })
-        .collect();
+        }
+    }
+    Ok((commits.len(), linked))
+}
 
-    let count = agent_infos.len();
+/// Markdown PR description for the commits in `since..HEAD`, listing the
+/// tasks and bugs they closed.
+pub fn report_pr(repo_path: &Path, since: &str) -> Result<ReportResult> {
+    let repo_root = report_repo_root(repo_path)?;
+    let storage = Storage::open(repo_path)?;
+    let template = storage.get_report_template_kdl("pr")?;
+    let (commits, linked) = report_entities_in_range(&storage, &repo_root, since)?;
 
-    Ok(AgentListResult {
-        agents: agent_infos,
-        count,
+    let mut entries = Vec::new();
+    let mut still_open = Vec::new();
+    for (id, shas) in linked {
+        match report_entry(&storage, &id) {
+            Some((mut entry, TaskStatus::Done)) => {
+                entry.commits = shas;
+                entries.push(entry);
+            }
+            Some(_) => still_open.push(id),
+            None => {}
+        }
+    }
+    group_report_entries(&storage, &template, &mut entries)?;
+
+    let head = git::current_branch(&repo_root).unwrap_or_else(|| "HEAD".to_string());
+    let date = Utc::now().format("%Y-%m-%d").to_string();
+    let title = ReportTemplate::render(
+        &template.title,
+        &[("since", since), ("head", &head), ("date", &date)],
+    );
+    Ok(ReportResult {
+        kind: "pr".to_string(),
+        markdown: render_report(&template, &title, &entries),
+        entries,
+        commits: Some(commits),
+        still_open,
     })
 }
 
-/// Track agent activity if the current process is a registered agent.
+/// Markdown changelog for the tasks and bugs closed under a milestone,
+/// optionally limited to those linked to commits in `since..HEAD`.
+pub fn report_changelog(
+    repo_path: &Path,
+    milestone_id: &str,
+    since: Option<&str>,
+) -> Result<ReportResult> {
+    let storage = Storage::open(repo_path)?;
+    let milestone = storage.get_milestone(milestone_id)?;
+    let template = storage.get_report_template_kdl("changelog")?;
+    let in_range = match since {
+        Some(since) => {
+            let repo_root = report_repo_root(repo_path)?;
+            Some(report_entities_in_range(&storage, &repo_root, since)?)
+        }
+        None => None,
+    };
+
+    let mut entries = Vec::new();
+    let mut seen = std::collections::HashSet::from([milestone_id.to_string()]);
+    let mut queue = std::collections::VecDeque::from([milestone_id.to_string()]);
+    while let Some(parent) = queue.pop_front() {
+        for edge in storage.list_edges(Some(EdgeType::ChildOf), None, Some(&parent))? {
+            if !seen.insert(edge.source.clone()) {
+                continue;
+            }
+            // Descend before filtering, so children of skipped entries still count
+            queue.push_back(edge.source.clone());
+            if let Some((mut entry, TaskStatus::Done)) = report_entry(&storage, &edge.source) {
+                entry.commits = match &in_range {
+                    Some((_, linked)) => match linked.iter().find(|(id, _)| *id == entry.id) {
+                        Some((_, shas)) => shas.clone(),
+                        None => continue,
+                    },
+                    None => {
+                        let mut links = storage.get_commits_for_entity(&entry.id)?;
+                        links.sort_by_key(|l| l.linked_at);
+                        links.into_iter().map(|l| l.sha).collect()
+                    }
+                };
+                entries.push(entry);
+            }
+        }
+    }
+    entries.sort_by(|a, b| a.closed_at.cmp(&b.closed_at).then(a.id.cmp(&b.id)));
+    group_report_entries(&storage, &template, &mut entries)?;
+
+    let date = Utc::now().format("%Y-%m-%d").to_string();
+    let title = ReportTemplate::render(
+        &template.title,
+        &[
+            ("milestone", &milestone.core.title),
+            ("milestone_id", milestone_id),
+            ("since", since.unwrap_or("")),
+            ("head", "HEAD"),
+            ("date", &date),
+        ],
+    );
+    Ok(ReportResult {
+        kind: "changelog".to_string(),
+        markdown: render_report(&template, &title, &entries),
+        entries,
+        commits: in_range.map(|(commits, _)| commits),
+        still_open: Vec::new(),
+    })
+}
+
+// === Agent Commands ===
+
+use crate::models::AgentStatus;
+
+/// Result of agent list command.
+#[derive(Serialize)]
+pub struct AgentListResult {
+    pub agents: Vec<AgentInfo>,
+    pub count: usize,
+}
+
+/// Agent information for list output.
+#[derive(Serialize)]
+pub struct AgentInfo {
+    /// Unique identifier (e.g., "bn-a1b2")
+    pub id: String,
+    pub pid: u32,
+    pub parent_pid: u32,
+    pub name: String,
+    /// Agent's purpose/role (e.g., "Task Worker", "PRD Generator")
+    pub purpose: Option<String>,
+    pub status: String,
+    pub started_at: String,
+    pub last_activity_at: String,
+    pub tasks: Vec<String>,
+    pub command_count: u64,
+    /// When the agent called goodbye (for fade-out animation timing)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub goodbye_at: Option<String>,
+    /// Health status including stuck detection
+    pub health: crate::models::AgentHealth,
+}
+
+impl Output for AgentListResult {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        if self.agents.is_empty() {
+            return "No agents registered.".to_string();
+        }
+
+        let mut lines = Vec::new();
+        lines.push(format!("Agents ({}):", self.count));
+        lines.push(String::new());
+
+        for agent in &self.agents {
+            let purpose_str = agent
+                .purpose
+                .as_ref()
+                .map(|p| format!(" - {}", p))
+                .unwrap_or_default();
+
+            let health_str = if agent.health.is_stuck {
+                format!(" ⚠️ STUCK ({}m idle)", agent.health.idle_minutes)
+            } else {
+                String::new()
+            };
+
+            lines.push(format!(
+                "  {} (PID {})  {}{}  [{}]{}",
+                agent.id, agent.pid, agent.name, purpose_str, agent.status, health_str
+            ));
+            lines.push(format!(
+                "    Started: {}  Last activity: {}",
+                agent.started_at, agent.last_activity_at
+            ));
+            if !agent.tasks.is_empty() {
+                lines.push(format!("    Tasks: {}", agent.tasks.join(", ")));
+            }
+            lines.push(format!("    Commands: {}", agent.command_count));
+            if agent.health.is_stuck {
+                lines.push(format!(
+                    "    ⚠️ Health: Stuck on tasks for {} minutes",
+                    agent.health.idle_minutes
+                ));
+            }
+            lines.push(String::new());
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// List active agents.
+pub fn agent_list(repo_path: &Path, status: Option<&str>) -> Result<AgentListResult> {
+    let mut storage = Storage::open(repo_path)?;
+    // Update agent statuses (active/idle/stale) based on activity and process status
+    let _ = storage.update_agent_statuses();
+    // Clean up agents whose processes are dead
+    let _ = storage.cleanup_stale_agents();
+    let agents = storage.list_agents(status)?;
+
+    let agent_infos: Vec<AgentInfo> = agents
+        .into_iter()
+        .map(|a| {
+            let status_str = match a.status {
+                AgentStatus::Active => "active",
+                AgentStatus::Idle => "idle",
+                AgentStatus::Stale => "stale",
+            };
+            let health = a.compute_health();
+            AgentInfo {
+                id: a.id,
+                pid: a.pid,
+                parent_pid: a.parent_pid,
+                name: a.name,
+                purpose: a.purpose,
+                status: status_str.to_string(),
+                started_at: a.started_at.to_rfc3339(),
+                last_activity_at: a.last_activity_at.to_rfc3339(),
+                tasks: a.tasks,
+                command_count: a.command_count,
+                goodbye_at: a.goodbye_at.map(|t| t.to_rfc3339()),
+                health,
+            }
+        })
+        .collect();
+
+    let count = agent_infos.len();
+
+    Ok(AgentListResult {
+        agents: agent_infos,
+        count,
+    })
+}
+
+/// Track agent activity if the current process is a registered agent.
 /// Updates last_activity_at and increments command_count.
 /// Silently ignores errors (e.g., if not an agent or storage issues).
 pub fn track_agent_activity(repo_path: &Path) {
@@ -19654,6 +22574,1705 @@ pub fn track_agent_activity(repo_path: &Path) {
     }
 }
 
+// === Agent Usage Commands ===
+
+use crate::agents::BudgetStatus;
+use crate::models::{AgentUsage, UsageRecord};
+
+/// Find an agent by ID, PID, or name, or the calling agent when no target is given.
+fn resolve_usage_agent(storage: &Storage, target: Option<&str>) -> Result<Agent> {
+    match target {
+        Some(target) if target.starts_with("bn-") => storage.get_agent_by_id(target),
+        Some(target) => match target.parse::<u32>() {
+            Ok(pid) => storage.get_agent(pid),
+            Err(_) => storage.get_agent_by_name(target),
+        },
+        None => get_current_agent(storage).ok_or_else(|| {
+            Error::NotFound(
+                "No registered agent found for this session (pass --agent or set BN_AGENT_ID)"
+                    .to_string(),
+            )
+        }),
+    }
+}
+
+fn format_usage(usage: &AgentUsage) -> String {
+    format!(
+        "{} tokens ({} in, {} out), {} request(s), ${:.4}",
+        usage.total_tokens(),
+        usage.input_tokens,
+        usage.output_tokens,
+        usage.requests,
+        usage.cost_usd
+    )
+}
+
+/// Result of `bn agent usage report`.
+#[derive(Serialize)]
+pub struct AgentUsageReportResult {
+    pub agent_id: String,
+    pub agent_name: String,
+    pub agent_type: String,
+    /// Usage recorded by this report
+    pub reported: AgentUsage,
+    /// Usage reported for the agent session so far
+    pub total: AgentUsage,
+    /// Tasks and bugs the report was attributed to
+    pub tasks: Vec<String>,
+    /// Exhausted budgets that cover this agent's work
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exhausted_budgets: Vec<BudgetStatus>,
+    /// True when the agent should wrap up and run `bn goodbye`
+    pub should_goodbye: bool,
+}
+
+impl Output for AgentUsageReportResult {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        let mut lines = vec![format!(
+            "Recorded usage for {} ({}): {}",
+            self.agent_name,
+            self.agent_id,
+            format_usage(&self.reported)
+        )];
+        lines.push(format!("  Session total: {}", format_usage(&self.total)));
+        if self.tasks.is_empty() {
+            lines.push("  Attributed to: (no tasks)".to_string());
+        } else {
+            lines.push(format!("  Attributed to: {}", self.tasks.join(", ")));
+        }
+        if !self.exhausted_budgets.is_empty() {
+            lines.push(String::new());
+            for budget in &self.exhausted_budgets {
+                lines.push(format!("Budget exhausted: {}", budget.summary()));
+            }
+            if self.should_goodbye {
+                lines.push("Finish up and run `bn goodbye`.".to_string());
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Record model usage for an agent session.
+///
+/// The usage is added to the agent record and appended to usage.jsonl,
+/// attributed evenly to the tasks the agent is working on.
+pub fn agent_usage_report(
+    repo_path: &Path,
+    target: Option<&str>,
+    usage: AgentUsage,
+) -> Result<AgentUsageReportResult> {
+    if usage.is_empty() {
+        return Err(Error::InvalidInput(
+            "Nothing to report: pass --input-tokens, --output-tokens, --requests or --cost"
+                .to_string(),
+        ));
+    }
+    if !usage.cost_usd.is_finite() || usage.cost_usd < 0.0 {
+        return Err(Error::InvalidInput(
+            "--cost must be a non-negative number".to_string(),
+        ));
+    }
+
+    let mut storage = Storage::open(repo_path)?;
+    let mut agent = resolve_usage_agent(&storage, target)?;
+    let tasks = agents::budget::attributed_tasks(&storage, &agent)?;
+
+    agent.usage.add(&usage);
+    storage.update_agent(&agent)?;
+    storage.append_usage_record(&UsageRecord {
+        at: Utc::now(),
+        agent_id: agent.id.clone(),
+        agent_type: agent.agent_type.clone(),
+        tasks: tasks.clone(),
+        usage: usage.clone(),
+    })?;
+
+    let budgets = agents::evaluate_budgets(&storage, &storage.get_budgets_kdl()?)?;
+    let exhausted_budgets: Vec<BudgetStatus> = budgets
+        .into_iter()
+        .filter(|b| b.applies_to(&agent, &tasks))
+        .collect();
+    // Planners are stateless and only leave when forced, so only nudge the rest
+    let should_goodbye = !exhausted_budgets.is_empty() && agent.agent_type != AgentType::Planner;
+
+    Ok(AgentUsageReportResult {
+        agent_id: agent.id,
+        agent_name: agent.name,
+        agent_type: format!("{:?}", agent.agent_type).to_lowercase(),
+        reported: usage,
+        total: agent.usage,
+        tasks,
+        exhausted_budgets,
+        should_goodbye,
+    })
+}
+
+/// Usage reported by one registered agent.
+#[derive(Serialize)]
+pub struct AgentUsageEntry {
+    pub id: String,
+    pub name: String,
+    pub agent_type: String,
+    pub usage: AgentUsage,
+}
+
+/// Result of `bn agent usage show`.
+#[derive(Serialize)]
+pub struct AgentUsageShowResult {
+    /// All usage in the ledger
+    pub total: AgentUsage,
+    /// Usage by agent type
+    pub by_agent_type: std::collections::BTreeMap<String, AgentUsage>,
+    /// Usage attributed to each task or bug
+    pub by_task: std::collections::BTreeMap<String, AgentUsage>,
+    /// Registered agents and their session usage
+    pub agents: Vec<AgentUsageEntry>,
+    /// Configured budgets and what has been charged against them
+    pub budgets: Vec<BudgetStatus>,
+}
+
+impl Output for AgentUsageShowResult {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        let mut lines = vec![format!("Total usage: {}", format_usage(&self.total))];
+
+        if !self.by_agent_type.is_empty() {
+            lines.push("\nBy agent type:".to_string());
+            for (agent_type, usage) in &self.by_agent_type {
+                lines.push(format!("  {}: {}", agent_type, format_usage(usage)));
+            }
+        }
+        if !self.by_task.is_empty() {
+            lines.push("\nBy task:".to_string());
+            for (task, usage) in &self.by_task {
+                lines.push(format!("  {}: {}", task, format_usage(usage)));
+            }
+        }
+        if !self.agents.is_empty() {
+            lines.push("\nRegistered agents:".to_string());
+            for agent in &self.agents {
+                lines.push(format!(
+                    "  {} ({}, {}): {}",
+                    agent.name,
+                    agent.id,
+                    agent.agent_type,
+                    format_usage(&agent.usage)
+                ));
+            }
+        }
+        if !self.budgets.is_empty() {
+            lines.push("\nBudgets:".to_string());
+            for budget in &self.budgets {
+                let state = match &budget.exhausted {
+                    Some(limit) => format!("EXHAUSTED ({})", limit),
+                    None => "ok".to_string(),
+                };
+                lines.push(format!(
+                    "  {}: {} - {}",
+                    budget.scope,
+                    state,
+                    format_usage(&budget.used)
+                ));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Summarize the usage ledger and budgets.
+pub fn agent_usage_show(repo_path: &Path) -> Result<AgentUsageShowResult> {
+    let storage = Storage::open(repo_path)?;
+    let records = storage.list_usage_records()?;
+
+    let mut total = AgentUsage::default();
+    let mut by_agent_type = std::collections::BTreeMap::new();
+    let mut by_task = std::collections::BTreeMap::new();
+    for record in &records {
+        total.add(&record.usage);
+        by_agent_type
+            .entry(format!("{:?}", record.agent_type).to_lowercase())
+            .or_insert_with(AgentUsage::default)
+            .add(&record.usage);
+        let share = record.usage.share(record.tasks.len());
+        for task in &record.tasks {
+            by_task
+                .entry(task.clone())
+                .or_insert_with(AgentUsage::default)
+                .add(&share);
+        }
+    }
+
+    let agents = storage
+        .list_agents(None)?
+        .into_iter()
+        .filter(|a| !a.usage.is_empty())
+        .map(|a| AgentUsageEntry {
+            agent_type: format!("{:?}", a.agent_type).to_lowercase(),
+            id: a.id,
+            name: a.name,
+            usage: a.usage,
+        })
+        .collect();
+    let budgets = agents::evaluate_budgets(&storage, &storage.get_budgets_kdl()?)?;
+
+    Ok(AgentUsageShowResult {
+        total,
+        by_agent_type,
+        by_task,
+        agents,
+        budgets,
+    })
+}
+
+// === Agent Transcript Commands ===
+
+use crate::models::{TranscriptEntry, TranscriptKind};
+
+/// How often `bn agent transcript --follow` checks for new lines.
+const TRANSCRIPT_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Map an agent name to its ID; IDs pass through unchanged so transcripts of
+/// agents that already left the registry stay reachable.
+fn resolve_transcript_agent(storage: &Storage, id: &str) -> String {
+    if id.starts_with("bn-") {
+        return id.to_string();
+    }
+    storage
+        .get_agent_by_name(id)
+        .map(|a| a.id)
+        .unwrap_or_else(|_| id.to_string())
+}
+
+/// Format a transcript line for terminal output.
+pub fn format_transcript_entry(entry: &TranscriptEntry) -> String {
+    let at = entry.at.format("%H:%M:%S");
+    match entry.kind {
+        TranscriptKind::Command => {
+            let status = match entry.success {
+                Some(false) => "failed",
+                _ => "ok",
+            };
+            format!(
+                "[{}] $ bn {} ({}, {}ms)",
+                at,
+                entry.text,
+                status,
+                entry.duration_ms.unwrap_or(0)
+            )
+        }
+        TranscriptKind::Stdout => format!("[{}] {}", at, entry.text),
+        TranscriptKind::Stderr => format!("[{}] ! {}", at, entry.text),
+    }
+}
+
+/// Result of `bn agent transcript`.
+#[derive(Serialize)]
+pub struct AgentTranscriptResult {
+    pub agent_id: String,
+    pub path: String,
+    /// Tasks the agent worked on
+    pub tasks: Vec<String>,
+    pub entries: Vec<TranscriptEntry>,
+}
+
+impl Output for AgentTranscriptResult {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        let mut lines = vec![format!(
+            "Transcript for {} ({} line(s)): {}",
+            self.agent_id,
+            self.entries.len(),
+            self.path
+        )];
+        if !self.tasks.is_empty() {
+            lines.push(format!("Tasks: {}", self.tasks.join(", ")));
+        }
+        lines.push(String::new());
+        lines.extend(self.entries.iter().map(format_transcript_entry));
+        lines.join("\n")
+    }
+}
+
+/// Read an agent's transcript.
+pub fn agent_transcript(repo_path: &Path, id: &str) -> Result<AgentTranscriptResult> {
+    let storage = Storage::open(repo_path)?;
+    let agent_id = resolve_transcript_agent(&storage, id);
+    let (entries, _) = storage.read_transcript(&agent_id, 0)?;
+
+    Ok(AgentTranscriptResult {
+        path: storage
+            .transcript_path(&agent_id)?
+            .to_string_lossy()
+            .to_string(),
+        tasks: agents::transcript::touched_tasks(&storage, &agent_id)?,
+        agent_id,
+        entries,
+    })
+}
+
+/// Stream an agent's transcript to `on_entry`, waiting for new lines until
+/// the agent leaves the registry (or says goodbye).
+pub fn agent_transcript_follow(
+    repo_path: &Path,
+    id: &str,
+    on_entry: &mut dyn FnMut(&TranscriptEntry),
+) -> Result<()> {
+    let storage = Storage::open(repo_path)?;
+    let agent_id = resolve_transcript_agent(&storage, id);
+
+    let mut offset = 0;
+    loop {
+        let running = storage
+            .get_agent_by_id(&agent_id)
+            .is_ok_and(|a| a.goodbye_at.is_none());
+        let (entries, next) = storage.read_transcript(&agent_id, offset)?;
+        offset = next;
+        for entry in &entries {
+            on_entry(entry);
+        }
+        // Checking liveness before reading means the final lines are printed
+        if !running {
+            return Ok(());
+        }
+        std::thread::sleep(TRANSCRIPT_POLL_INTERVAL);
+    }
+}
+
+/// Result of a transcript capture helper.
+#[derive(Serialize)]
+pub struct AgentTranscriptCaptureResult {
+    pub agent_id: String,
+    pub stream: String,
+    pub lines: u64,
+}
+
+impl Output for AgentTranscriptCaptureResult {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        format!(
+            "Captured {} {} line(s) for {}",
+            self.lines, self.stream, self.agent_id
+        )
+    }
+}
+
+/// Copy stdin into an agent's transcript until EOF.
+///
+/// `bn agent spawn` starts one of these per container stream so output is
+/// timestamped even though bn itself exits right after starting the agent.
+pub fn agent_transcript_capture(
+    repo_path: &Path,
+    id: &str,
+    stream: &str,
+    raw_log: Option<&str>,
+) -> Result<AgentTranscriptCaptureResult> {
+    let kind = match stream {
+        "stdout" => TranscriptKind::Stdout,
+        "stderr" => TranscriptKind::Stderr,
+        other => {
+            return Err(Error::InvalidInput(format!(
+                "Unknown transcript stream '{}' (expected stdout or stderr)",
+                other
+            )));
+        }
+    };
+    let storage = Storage::open(repo_path)?;
+    let raw_log = raw_log
+        .map(|path| fs::OpenOptions::new().create(true).append(true).open(path))
+        .transpose()?;
+    let lines = agents::transcript::capture(&storage, id, kind, std::io::stdin().lock(), raw_log)?;
+
+    Ok(AgentTranscriptCaptureResult {
+        agent_id: id.to_string(),
+        stream: stream.to_string(),
+        lines,
+    })
+}
+
+// === Agent Worktree Commands ===
+
+use crate::agents::worktree::{self as task_worktree, MergeOutcome, TaskWorktree};
+
+/// Git repository root for worktree commands.
+fn worktree_repo_root(repo_path: &Path) -> Result<PathBuf> {
+    find_git_root(repo_path).ok_or_else(|| {
+        Error::InvalidInput(format!(
+            "Task worktrees need a git repository: {}",
+            repo_path.display()
+        ))
+    })
+}
+
+/// Branch that task branches merge into when none is given.
+fn default_merge_target(repo_root: &Path) -> String {
+    std::env::var("BN_MERGE_TARGET")
+        .ok()
+        .filter(|t| !t.is_empty())
+        .or_else(|| git::current_branch(repo_root))
+        .unwrap_or_else(|| "main".to_string())
+}
+
+/// Status of the task or bug a worktree belongs to, or `None` if it no
+/// longer exists.
+fn worktree_task_status(storage: &Storage, task_id: &str) -> Option<TaskStatus> {
+    storage
+        .get_task(task_id)
+        .map(|t| t.status)
+        .or_else(|_| storage.get_bug(task_id).map(|b| b.status))
+        .ok()
+}
+
+fn status_name(status: &TaskStatus) -> String {
+    serde_json::to_value(status)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Result of `bn agent worktree create`.
+#[derive(Serialize)]
+pub struct WorktreeCreated {
+    #[serde(flatten)]
+    pub worktree: TaskWorktree,
+    pub created: bool,
+}
+
+impl Output for WorktreeCreated {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        let verb = if self.created {
+            "Created"
+        } else {
+            "Reusing existing"
+        };
+        format!(
+            "{} worktree for {} at {} (branch {})",
+            verb, self.worktree.task_id, self.worktree.path, self.worktree.branch
+        )
+    }
+}
+
+/// Create (or reuse) the worktree and `bn/<task-id>` branch for a task.
+pub fn agent_worktree_create(
+    repo_path: &Path,
+    task_id: &str,
+    base: Option<&str>,
+) -> Result<WorktreeCreated> {
+    let storage = Storage::open(repo_path)?;
+    if worktree_task_status(&storage, task_id).is_none() {
+        return Err(Error::NotFound(format!("Task not found: {}", task_id)));
+    }
+    let repo_root = worktree_repo_root(repo_path)?;
+    let base = base
+        .map(str::to_string)
+        .unwrap_or_else(|| default_merge_target(&repo_root));
+    let (worktree, created) = task_worktree::create(&storage, &repo_root, task_id, &base)?;
+    Ok(WorktreeCreated { worktree, created })
+}
+
+/// A task worktree with the state of its task.
+#[derive(Serialize)]
+pub struct WorktreeEntry {
+    #[serde(flatten)]
+    pub worktree: TaskWorktree,
+    /// Status of the task, or `None` if it was deleted
+    pub task_status: Option<String>,
+    pub dirty: bool,
+}
+
+/// Result of `bn agent worktree list`.
+#[derive(Serialize)]
+pub struct WorktreeList {
+    pub worktrees: Vec<WorktreeEntry>,
+    pub count: usize,
+}
+
+impl Output for WorktreeList {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        if self.worktrees.is_empty() {
+            return "No task worktrees.".to_string();
+        }
+        let mut lines = vec![format!("{} task worktree(s):", self.count)];
+        for entry in &self.worktrees {
+            lines.push(format!(
+                "  {} [{}] {} -> {}{}",
+                entry.worktree.task_id,
+                entry.task_status.as_deref().unwrap_or("deleted"),
+                entry.worktree.branch,
+                entry.worktree.path,
+                if entry.dirty {
+                    " (uncommitted changes)"
+                } else {
+                    ""
+                }
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// List the worktrees binnacle created for tasks.
+pub fn agent_worktree_list(repo_path: &Path) -> Result<WorktreeList> {
+    let storage = Storage::open(repo_path)?;
+    let repo_root = worktree_repo_root(repo_path)?;
+    let worktrees: Vec<WorktreeEntry> = task_worktree::list(&repo_root)?
+        .into_iter()
+        .map(|worktree| WorktreeEntry {
+            task_status: worktree_task_status(&storage, &worktree.task_id)
+                .as_ref()
+                .map(status_name),
+            dirty: git::is_dirty(Path::new(&worktree.path)),
+            worktree,
+        })
+        .collect();
+    Ok(WorktreeList {
+        count: worktrees.len(),
+        worktrees,
+    })
+}
+
+/// Result of `bn agent worktree finish`.
+#[derive(Serialize)]
+pub struct WorktreeFinishResult {
+    pub task_id: String,
+    pub branch: String,
+    pub target: String,
+    pub merged: bool,
+    /// Commit the target now points at
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    pub tests_run: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failed_tests: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<String>,
+    /// Bug filed against the task when the merge conflicted or tests failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bug_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Output for WorktreeFinishResult {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        let mut lines = Vec::new();
+        if self.merged {
+            lines.push(format!(
+                "Merged {} into {} at {} ({} test(s) passed)",
+                self.branch,
+                self.target,
+                self.commit
+                    .as_deref()
+                    .map(|c| &c[..c.len().min(7)])
+                    .unwrap_or("?"),
+                self.tests_run
+            ));
+        } else if !self.conflicts.is_empty() {
+            lines.push(format!(
+                "Merge conflict between {} and {} in: {}",
+                self.branch,
+                self.target,
+                self.conflicts.join(", ")
+            ));
+        } else if !self.failed_tests.is_empty() {
+            lines.push(format!(
+                "{} of {} test(s) failed: {}",
+                self.failed_tests.len(),
+                self.tests_run,
+                self.failed_tests.join(", ")
+            ));
+        }
+        if let Some(error) = &self.error {
+            lines.push(format!("Not merged: {}", error));
+        }
+        if let Some(bug_id) = &self.bug_id {
+            lines.push(format!("Filed bug {} (caused_by {})", bug_id, self.task_id));
+        }
+        lines.join("\n")
+    }
+}
+
+/// File a bug against a task whose worktree could not be merged.
+fn file_worktree_bug(
+    repo_path: &Path,
+    task_id: &str,
+    title: String,
+    description: String,
+    severity: &str,
+) -> Result<String> {
+    let bug = bug_create_with_queue(
+        repo_path,
+        title,
+        None,
+        Some(description),
+        None,
+        Some(severity.to_string()),
+        vec!["worktree".to_string()],
+        None,
+        None,
+        None,
+        false,
+        None,
+    )?;
+    link_add(
+        repo_path,
+        &bug.id,
+        task_id,
+        "caused_by",
+        Some("Task branch could not be merged".to_string()),
+        false,
+    )?;
+    Ok(bug.id)
+}
+
+/// Merge a task's branch into the target after its linked tests pass.
+///
+/// The target is merged into the task branch first, so conflicts surface in
+/// the worktree and the target only ever moves forward. A conflict or a
+/// failing test files a bug linked to the task and leaves the target alone.
+pub fn agent_worktree_finish(
+    repo_path: &Path,
+    task_id: &str,
+    target: Option<&str>,
+    run_tests: bool,
+) -> Result<WorktreeFinishResult> {
+    let mut storage = Storage::open(repo_path)?;
+    if worktree_task_status(&storage, task_id).is_none() {
+        return Err(Error::NotFound(format!("Task not found: {}", task_id)));
+    }
+    let repo_root = worktree_repo_root(repo_path)?;
+    let target = target
+        .map(str::to_string)
+        .unwrap_or_else(|| default_merge_target(&repo_root));
+    let branch = task_worktree::branch_name(task_id);
+
+    // Inside an agent container the listed host path is not reachable, but
+    // the working directory is the worktree itself
+    let worktree_dir = if git::current_branch(repo_path).as_deref() == Some(&branch) {
+        repo_path.to_path_buf()
+    } else {
+        let worktree = task_worktree::find(&repo_root, task_id)?.ok_or_else(|| {
+            Error::NotFound(format!(
+                "No worktree for {} (create one with `bn agent worktree create {}`)",
+                task_id, task_id
+            ))
+        })?;
+        PathBuf::from(worktree.path)
+    };
+    if git::is_dirty(&worktree_dir) {
+        return Err(Error::InvalidInput(format!(
+            "Worktree {} has uncommitted changes; commit them before finishing",
+            worktree_dir.display()
+        )));
+    }
+
+    let mut result = WorktreeFinishResult {
+        task_id: task_id.to_string(),
+        branch: branch.clone(),
+        target: target.clone(),
+        merged: false,
+        commit: None,
+        tests_run: 0,
+        failed_tests: Vec::new(),
+        conflicts: Vec::new(),
+        bug_id: None,
+        error: None,
+    };
+
+    if let MergeOutcome::Conflict { files } =
+        task_worktree::merge_target_into(&worktree_dir, &target)?
+    {
+        result.bug_id = Some(file_worktree_bug(
+            repo_path,
+            task_id,
+            format!("Merge conflict merging {} into {}", branch, target),
+            format!(
+                "Merging {} into {} conflicted in:\n{}\n\nResolve the conflict in the task worktree and run `bn agent worktree finish {}`.",
+                target,
+                branch,
+                files
+                    .iter()
+                    .map(|f| format!("- {}", f))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                task_id
+            ),
+            "medium",
+        )?);
+        result.conflicts = files;
+        return Ok(result);
+    }
+
+    if run_tests {
+        let tests = storage.get_tests_for_task(task_id)?;
+        let mut failed = Vec::new();
+        for test in &tests {
+            let run = run_single_test(&mut storage, test, &worktree_dir, None)?;
+            if !run.passed {
+                failed.push(test.clone());
+            }
+        }
+        result.tests_run = tests.len();
+        if !failed.is_empty() {
+            let bug_id = file_worktree_bug(
+                repo_path,
+                task_id,
+                format!("Tests failed on {}", branch),
+                format!(
+                    "Linked tests failed on {} after merging {}:\n{}",
+                    branch,
+                    target,
+                    failed
+                        .iter()
+                        .map(|t| format!("- {} ({}): {}", t.name, t.id, t.command))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                ),
+                "high",
+            )?;
+            for test in &failed {
+                storage.link_test_to_bug(&test.id, &bug_id)?;
+            }
+            result.failed_tests = failed.into_iter().map(|t| t.id).collect();
+            result.bug_id = Some(bug_id);
+            return Ok(result);
+        }
+    }
+
+    match task_worktree::fast_forward(&repo_root, &target, &branch) {
+        Ok(commit) => {
+            result.merged = true;
+            result.commit = Some(commit);
+        }
+        Err(e) => result.error = Some(e.to_string()),
+    }
+    Ok(result)
+}
+
+/// A worktree `bn agent worktree gc` left in place.
+#[derive(Serialize)]
+pub struct WorktreeKept {
+    pub task_id: String,
+    pub reason: String,
+}
+
+/// Result of `bn agent worktree gc`.
+#[derive(Serialize)]
+pub struct WorktreeGcResult {
+    pub dry_run: bool,
+    pub removed: Vec<TaskWorktree>,
+    pub kept: Vec<WorktreeKept>,
+}
+
+impl Output for WorktreeGcResult {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        let verb = if self.dry_run {
+            "Would remove"
+        } else {
+            "Removed"
+        };
+        let mut lines = vec![format!("{} {} worktree(s)", verb, self.removed.len())];
+        for worktree in &self.removed {
+            lines.push(format!("  - {} ({})", worktree.task_id, worktree.branch));
+        }
+        for kept in &self.kept {
+            lines.push(format!("  kept {}: {}", kept.task_id, kept.reason));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Remove worktrees (and branches) of finished tasks.
+///
+/// A worktree is removed when its task is done, cancelled or deleted, no
+/// agent is working on it, and its branch is merged into the target with no
+/// uncommitted changes. `force` skips the last two checks.
+pub fn agent_worktree_gc(
+    repo_path: &Path,
+    target: Option<&str>,
+    force: bool,
+    dry_run: bool,
+) -> Result<WorktreeGcResult> {
+    let storage = Storage::open(repo_path)?;
+    let repo_root = worktree_repo_root(repo_path)?;
+    let target = target
+        .map(str::to_string)
+        .unwrap_or_else(|| default_merge_target(&repo_root));
+    let agents = storage.list_agents(None)?;
+
+    let mut result = WorktreeGcResult {
+        dry_run,
+        removed: Vec::new(),
+        kept: Vec::new(),
+    };
+    for worktree in task_worktree::list(&repo_root)? {
+        let task_id = worktree.task_id.clone();
+        let status = worktree_task_status(&storage, &task_id);
+        let working_agent = agents
+            .iter()
+            .find(|a| a.tasks.contains(&task_id))
+            .map(|a| a.id.clone())
+            .or_else(|| {
+                storage
+                    .list_edges(Some(EdgeType::WorkingOn), None, Some(&task_id))
+                    .ok()
+                    .and_then(|edges| edges.into_iter().next())
+                    .map(|e| e.source)
+            });
+        let reason = match status {
+            Some(ref s) if !matches!(s, TaskStatus::Done | TaskStatus::Cancelled) => {
+                Some(format!("task is {}", status_name(s)))
+            }
+            _ if working_agent.is_some() => Some(format!(
+                "agent {} is working on it",
+                working_agent.unwrap_or_default()
+            )),
+            _ if !force && git::is_dirty(Path::new(&worktree.path)) => {
+                Some("uncommitted changes".to_string())
+            }
+            _ if !force && !task_worktree::is_merged(&repo_root, &worktree.branch, &target) => {
+                Some(format!("{} is not merged into {}", worktree.branch, target))
+            }
+            _ => None,
+        };
+        match reason {
+            Some(reason) => result.kept.push(WorktreeKept { task_id, reason }),
+            None => {
+                if !dry_run {
+                    task_worktree::remove(&repo_root, &worktree, true)?;
+                }
+                result.removed.push(worktree);
+            }
+        }
+    }
+    Ok(result)
+}
+
+// === Merge Queue Commands ===
+
+use crate::models::{MergeQueueEntry, MergeQueueStatus};
+
+/// A running entry not updated for this long is assumed to belong to a
+/// processor that died, and is picked up again.
+const MERGE_QUEUE_STALE_MINUTES: i64 = 30;
+
+/// Result of `bn merge-queue add`.
+#[derive(Serialize)]
+pub struct MergeQueueAdded {
+    #[serde(flatten)]
+    pub entry: MergeQueueEntry,
+    /// 1-based position among active entries
+    pub position: usize,
+    /// The task already had an active entry, which was returned instead
+    pub already_queued: bool,
+}
+
+impl Output for MergeQueueAdded {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        let verb = if self.already_queued {
+            "Already queued"
+        } else {
+            "Queued"
+        };
+        format!(
+            "{} {} for {} as {} (position {})",
+            verb, self.entry.branch, self.entry.target, self.entry.id, self.position
+        )
+    }
+}
+
+/// Add a task's `bn/<task-id>` branch to the merge queue.
+pub fn merge_queue_add(
+    repo_path: &Path,
+    task_id: &str,
+    target: Option<&str>,
+) -> Result<MergeQueueAdded> {
+    let mut storage = Storage::open(repo_path)?;
+    if worktree_task_status(&storage, task_id).is_none() {
+        return Err(Error::NotFound(format!("Task not found: {}", task_id)));
+    }
+    let repo_root = worktree_repo_root(repo_path)?;
+    let branch = task_worktree::branch_name(task_id);
+    if git::rev_parse(&repo_root, &branch).is_err() {
+        return Err(Error::InvalidInput(format!(
+            "Branch {} does not exist (see `bn agent worktree create`)",
+            branch
+        )));
+    }
+
+    let queue = storage.list_merge_queue()?;
+    let active: Vec<&MergeQueueEntry> = queue.iter().filter(|e| e.status.is_active()).collect();
+    if let Some(position) = active.iter().position(|e| e.task_id == task_id) {
+        return Ok(MergeQueueAdded {
+            entry: active[position].clone(),
+            position: position + 1,
+            already_queued: true,
+        });
+    }
+
+    let target = target
+        .map(str::to_string)
+        .unwrap_or_else(|| default_merge_target(&repo_root));
+    let id = generate_id(
+        "bnmq",
+        &format!(
+            "{}-{}",
+            task_id,
+            Utc::now().timestamp_nanos_opt().unwrap_or(0)
+        ),
+    );
+    let mut entry = MergeQueueEntry::new(id, task_id, &branch, &target);
+    entry.agent_id = std::env::var("BN_AGENT_ID").ok().filter(|a| !a.is_empty());
+    storage.save_merge_queue_entry(&entry)?;
+
+    Ok(MergeQueueAdded {
+        entry,
+        position: active.len() + 1,
+        already_queued: false,
+    })
+}
+
+/// Result of `bn merge-queue list`.
+#[derive(Serialize)]
+pub struct MergeQueueList {
+    pub entries: Vec<MergeQueueEntry>,
+    pub queued: usize,
+    pub running: usize,
+}
+
+impl Output for MergeQueueList {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        if self.entries.is_empty() {
+            return "Merge queue is empty.".to_string();
+        }
+        let mut lines = vec![format!(
+            "Merge queue: {} queued, {} running",
+            self.queued, self.running
+        )];
+        for entry in &self.entries {
+            let mut line = format!(
+                "  {} [{}] {} -> {}",
+                entry.id, entry.status, entry.branch, entry.target
+            );
+            if let Some(commit) = &entry.commit {
+                line.push_str(&format!(" at {}", &commit[..commit.len().min(7)]));
+            }
+            if let Some(bug_id) = &entry.bug_id {
+                line.push_str(&format!(" (bug {})", bug_id));
+            }
+            if let Some(message) = &entry.message {
+                line.push_str(&format!(": {}", message));
+            }
+            lines.push(line);
+        }
+        lines.join("\n")
+    }
+}
+
+/// List the merge queue: active entries, or every entry with `all`.
+pub fn merge_queue_list(repo_path: &Path, all: bool) -> Result<MergeQueueList> {
+    let storage = Storage::open(repo_path)?;
+    let entries: Vec<MergeQueueEntry> = storage
+        .list_merge_queue()?
+        .into_iter()
+        .filter(|e| all || e.status.is_active())
+        .collect();
+    Ok(MergeQueueList {
+        queued: entries
+            .iter()
+            .filter(|e| e.status == MergeQueueStatus::Queued)
+            .count(),
+        running: entries
+            .iter()
+            .filter(|e| e.status == MergeQueueStatus::Running)
+            .count(),
+        entries,
+    })
+}
+
+/// Result of `bn merge-queue remove`.
+#[derive(Serialize)]
+pub struct MergeQueueRemoved {
+    #[serde(flatten)]
+    pub entry: MergeQueueEntry,
+}
+
+impl Output for MergeQueueRemoved {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        format!(
+            "Removed {} ({}) from the merge queue",
+            self.entry.id, self.entry.branch
+        )
+    }
+}
+
+/// Cancel a queued entry, by entry ID or task ID.
+pub fn merge_queue_remove(repo_path: &Path, id: &str) -> Result<MergeQueueRemoved> {
+    let mut storage = Storage::open(repo_path)?;
+    let mut entry = storage
+        .list_merge_queue()?
+        .into_iter()
+        .find(|e| e.status.is_active() && (e.id == id || e.task_id == id))
+        .ok_or_else(|| Error::NotFound(format!("No queued merge for {}", id)))?;
+    if entry.status == MergeQueueStatus::Running {
+        return Err(Error::InvalidInput(format!(
+            "{} is being merged and cannot be removed",
+            entry.id
+        )));
+    }
+    entry.status = MergeQueueStatus::Cancelled;
+    entry.updated_at = Utc::now();
+    storage.save_merge_queue_entry(&entry)?;
+    Ok(MergeQueueRemoved { entry })
+}
+
+/// Result of `bn merge-queue run`.
+#[derive(Serialize)]
+pub struct MergeQueueRunResult {
+    /// Entries processed in this run, in order
+    pub processed: Vec<MergeQueueEntry>,
+    /// Entries still queued
+    pub remaining: usize,
+    /// Another run is processing the queue
+    pub busy: bool,
+}
+
+impl Output for MergeQueueRunResult {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        if self.busy {
+            return "The merge queue is already being processed.".to_string();
+        }
+        if self.processed.is_empty() {
+            return "Merge queue is empty.".to_string();
+        }
+        let mut lines = Vec::new();
+        for entry in &self.processed {
+            match entry.status {
+                MergeQueueStatus::Merged => lines.push(format!(
+                    "✓ Merged {} into {} at {} ({} test(s) passed)",
+                    entry.branch,
+                    entry.target,
+                    entry
+                        .commit
+                        .as_deref()
+                        .map(|c| &c[..c.len().min(7)])
+                        .unwrap_or("?"),
+                    entry.tests_run
+                )),
+                _ => lines.push(format!(
+                    "✗ {} not merged: {}{}",
+                    entry.branch,
+                    entry.message.as_deref().unwrap_or("failed"),
+                    entry
+                        .bug_id
+                        .as_ref()
+                        .map(|b| format!(" (filed bug {})", b))
+                        .unwrap_or_default()
+                )),
+            }
+        }
+        if self.remaining > 0 {
+            lines.push(format!("{} still queued", self.remaining));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Process queued branches one at a time: rebase onto the target, run the
+/// task's linked tests (`bn test run --task`) and fast-forward the target
+/// only when they pass. With `once`, at most one entry is processed.
+pub fn merge_queue_run(repo_path: &Path, once: bool) -> Result<MergeQueueRunResult> {
+    let repo_root = worktree_repo_root(repo_path)?;
+    let mut result = MergeQueueRunResult {
+        processed: Vec::new(),
+        remaining: 0,
+        busy: false,
+    };
+
+    loop {
+        let mut storage = Storage::open(repo_path)?;
+        let queue = storage.list_merge_queue()?;
+        let stale_before = Utc::now() - chrono::Duration::minutes(MERGE_QUEUE_STALE_MINUTES);
+        if queue
+            .iter()
+            .any(|e| e.status == MergeQueueStatus::Running && e.updated_at > stale_before)
+        {
+            result.busy = result.processed.is_empty();
+            break;
+        }
+        let mut pending = queue.into_iter().filter(|e| e.status.is_active());
+        let Some(mut entry) = pending.next() else {
+            break;
+        };
+        if once && !result.processed.is_empty() {
+            result.remaining = 1 + pending.count();
+            break;
+        }
+
+        entry.status = MergeQueueStatus::Running;
+        entry.updated_at = Utc::now();
+        storage.save_merge_queue_entry(&entry)?;
+        drop(storage);
+
+        // Record the failure rather than leaving the entry running
+        let mut processed = match merge_queue_process(repo_path, &repo_root, entry.clone()) {
+            Ok(processed) => processed,
+            Err(e) => MergeQueueEntry {
+                status: MergeQueueStatus::Failed,
+                message: Some(e.to_string()),
+                ..entry
+            },
+        };
+        processed.updated_at = Utc::now();
+        Storage::open(repo_path)?.save_merge_queue_entry(&processed)?;
+        result.processed.push(processed);
+    }
+    Ok(result)
+}
+
+/// Rebase, test and merge one entry, updating the task and filing bugs.
+fn merge_queue_process(
+    repo_path: &Path,
+    repo_root: &Path,
+    mut entry: MergeQueueEntry,
+) -> Result<MergeQueueEntry> {
+    let storage = Storage::open(repo_path)?;
+    let (worktree, _) = task_worktree::create(&storage, repo_root, &entry.task_id, &entry.target)?;
+    drop(storage);
+    let worktree_dir = PathBuf::from(&worktree.path);
+    if git::is_dirty(&worktree_dir) {
+        entry.status = MergeQueueStatus::Failed;
+        entry.message = Some(format!("uncommitted changes in {}", worktree.path));
+        return Ok(entry);
+    }
+
+    let old_target = git::rev_parse(repo_root, &entry.target)?;
+    if let MergeOutcome::Conflict { files } =
+        task_worktree::rebase_onto(&worktree_dir, &entry.target)?
+    {
+        entry.bug_id = Some(file_worktree_bug(
+            repo_path,
+            &entry.task_id,
+            format!(
+                "Merge conflict rebasing {} onto {}",
+                entry.branch, entry.target
+            ),
+            format!(
+                "Rebasing {} onto {} in the merge queue conflicted in:\n{}\n\nResolve the conflict in the task worktree and run `bn merge-queue add {}`.",
+                entry.branch,
+                entry.target,
+                files
+                    .iter()
+                    .map(|f| format!("- {}", f))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                entry.task_id
+            ),
+            "medium",
+        )?);
+        entry.message = Some(format!("conflict in {}", files.join(", ")));
+        return merge_queue_failed(repo_path, entry);
+    }
+
+    // Rebasing rewrote the branch, so link the commits that will land. This
+    // happens before the tests run so the test gate sees their results as
+    // newer than the task's last commit when it is closed below.
+    let head = git::rev_parse(&worktree_dir, "HEAD")?;
+    let mut storage = Storage::open(repo_path)?;
+    let linked: Vec<String> = storage
+        .get_commits_for_entity(&entry.task_id)?
+        .into_iter()
+        .map(|c| c.sha)
+        .collect();
+    for sha in git::commits_between(repo_root, &old_target, &head)? {
+        if !linked.contains(&sha) {
+            storage.link_commit(&sha, &entry.task_id)?;
+        }
+    }
+    drop(storage);
+
+    // Validate exactly as `bn test run --task <id>` would, inside the worktree
+    let tests = test_run_in(
+        repo_path,
+        &worktree_dir,
+        None,
+        Some(&entry.task_id),
+        false,
+        false,
+        None,
+        TestRunOptions::default(),
+        &mut |_| {},
+    )?;
+    entry.tests_run = tests.total;
+    if tests.failed > 0 {
+        let failed: Vec<&TestRunResult> = tests.results.iter().filter(|r| !r.passed).collect();
+        let bug_id = file_worktree_bug(
+            repo_path,
+            &entry.task_id,
+            format!("Tests failed on {}", entry.branch),
+            format!(
+                "Linked tests failed on {} after rebasing onto {}:\n{}",
+                entry.branch,
+                entry.target,
+                failed
+                    .iter()
+                    .map(|r| format!(
+                        "- {} ({}), exit code {}",
+                        r.test_name, r.test_id, r.exit_code
+                    ))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
+            "high",
+        )?;
+        let mut storage = Storage::open(repo_path)?;
+        for result in &failed {
+            storage.link_test_to_bug(&result.test_id, &bug_id)?;
+        }
+        entry.message = Some(format!(
+            "{} of {} test(s) failed",
+            tests.failed, tests.total
+        ));
+        entry.bug_id = Some(bug_id);
+        return merge_queue_failed(repo_path, entry);
+    }
+
+    let commit = match task_worktree::fast_forward(repo_root, &entry.target, &entry.branch) {
+        Ok(commit) => commit,
+        Err(e) => {
+            entry.message = Some(e.to_string());
+            return merge_queue_failed(repo_path, entry);
+        }
+    };
+
+    let reason = format!(
+        "Merged {} into {} at {}",
+        entry.branch,
+        entry.target,
+        &commit[..commit.len().min(7)]
+    );
+    match worktree_task_status(&Storage::open(repo_path)?, &entry.task_id) {
+        Some(TaskStatus::Done) | Some(TaskStatus::Cancelled) | None => {}
+        Some(_) if is_bug_id(repo_path, &entry.task_id) => {
+            bug_close(repo_path, &entry.task_id, Some(reason), true)?;
+        }
+        Some(_) => {
+            task_close(repo_path, &entry.task_id, Some(reason), true)?;
+        }
+    }
+    entry.status = MergeQueueStatus::Merged;
+    entry.commit = Some(commit);
+    Ok(entry)
+}
+
+/// Mark an entry failed and reopen its task if it was already closed, since
+/// its work did not reach the target.
+fn merge_queue_failed(repo_path: &Path, mut entry: MergeQueueEntry) -> Result<MergeQueueEntry> {
+    if worktree_task_status(&Storage::open(repo_path)?, &entry.task_id) == Some(TaskStatus::Done) {
+        if is_bug_id(repo_path, &entry.task_id) {
+            bug_reopen(repo_path, &entry.task_id)?;
+        } else {
+            task_reopen(repo_path, &entry.task_id)?;
+        }
+    }
+    entry.status = MergeQueueStatus::Failed;
+    Ok(entry)
+}
+
+fn is_bug_id(repo_path: &Path, id: &str) -> bool {
+    Storage::open(repo_path).is_ok_and(|s| s.get_bug(id).is_ok())
+}
+
+// === Message Commands ===
+
+use crate::models::{HUMAN_RECIPIENT, Message, MessageKind};
+
+/// Roles (agent types) that can be addressed with `bn msg send --to`.
+const MESSAGE_ROLES: &[&str] = &["worker", "planner", "buddy", "ask"];
+
+/// How often `bn ask --wait` checks for an answer.
+const ASK_POLL_INTERVAL_MS: u64 = 500;
+
+/// How often a waiting `bn ask` refreshes the agent's activity so it isn't
+/// marked stale while parked.
+const ASK_TOUCH_INTERVAL_SECS: u64 = 60;
+
+/// The calling agent's ID and role, or `human` when no agent is registered.
+fn message_identity(storage: &Storage) -> (String, Option<String>, Option<Agent>) {
+    match get_current_agent(storage) {
+        Some(agent) => {
+            let role = format!("{:?}", agent.agent_type).to_lowercase();
+            (agent.id.clone(), Some(role), Some(agent))
+        }
+        None => (HUMAN_RECIPIENT.to_string(), None, None),
+    }
+}
+
+/// Resolve `--to` into a recipient: `human`, a role, or an agent ID (agents
+/// can also be named).
+fn resolve_recipient(storage: &Storage, to: &str) -> Result<String> {
+    let to = to.trim();
+    if to == HUMAN_RECIPIENT || MESSAGE_ROLES.contains(&to) {
+        return Ok(to.to_string());
+    }
+    if let Ok(agent) = storage.get_agent_by_id(to) {
+        return Ok(agent.id);
+    }
+    storage
+        .list_agents(None)?
+        .into_iter()
+        .find(|a| a.name == to)
+        .map(|a| a.id)
+        .ok_or_else(|| {
+            Error::NotFound(format!(
+                "No agent or role named '{}' (use an agent ID or name, one of {}, or {})",
+                to,
+                MESSAGE_ROLES.join(", "),
+                HUMAN_RECIPIENT
+            ))
+        })
+}
+
+/// Create and store a message.
+fn send_message(
+    storage: &mut Storage,
+    kind: MessageKind,
+    from: &str,
+    to: &str,
+    body: &str,
+    reply_to: Option<String>,
+) -> Result<Message> {
+    let body = body.trim();
+    if body.is_empty() {
+        return Err(Error::InvalidInput(
+            "Message body cannot be empty".to_string(),
+        ));
+    }
+    let id = generate_id(
+        "bnmsg",
+        &format!(
+            "{}-{}-{}",
+            from,
+            to,
+            Utc::now().timestamp_nanos_opt().unwrap_or(0)
+        ),
+    );
+    let mut message = Message::new(id, from, to, body);
+    message.kind = kind;
+    message.reply_to = reply_to;
+    storage.save_message(&message)?;
+    Ok(message)
+}
+
+fn format_message(message: &Message) -> String {
+    let kind = match message.kind {
+        MessageKind::Question => " [question]",
+        MessageKind::Message => "",
+    };
+    let mut line = format!(
+        "{}{} from {} to {} ({}): {}",
+        message.id,
+        kind,
+        message.from,
+        message.to,
+        message.created_at.format("%Y-%m-%d %H:%M"),
+        message.body
+    );
+    if let Some(ref reply_to) = message.reply_to {
+        line.push_str(&format!("\n  ↳ reply to {}", reply_to));
+    }
+    line
+}
+
+/// Result of `bn msg send` and `bn msg reply`.
+#[derive(Serialize)]
+pub struct MessageSent {
+    #[serde(flatten)]
+    pub message: Message,
+}
+
+impl Output for MessageSent {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        format!("Sent {} to {}", self.message.id, self.message.to)
+    }
+}
+
+/// Send a message to an agent, a role or `human`.
+pub fn msg_send(repo_path: &Path, to: &str, body: &str) -> Result<MessageSent> {
+    let mut storage = Storage::open(repo_path)?;
+    let to = resolve_recipient(&storage, to)?;
+    let (from, _, _) = message_identity(&storage);
+    let message = send_message(&mut storage, MessageKind::Message, &from, &to, body, None)?;
+    Ok(MessageSent { message })
+}
+
+/// Reply to message `id` as `from`; the reply goes to the original sender.
+/// Used by `bn msg reply` and by the GUI and TUI to answer questions.
+pub fn reply_to_message(
+    storage: &mut Storage,
+    id: &str,
+    from: &str,
+    body: &str,
+) -> Result<Message> {
+    let original = storage.get_message(id)?;
+    send_message(
+        storage,
+        MessageKind::Message,
+        from,
+        &original.from,
+        body,
+        Some(original.id),
+    )
+}
+
+/// Reply to a message; the reply goes to its sender. Replying to a
+/// question answers it.
+pub fn msg_reply(repo_path: &Path, id: &str, body: &str) -> Result<MessageSent> {
+    let mut storage = Storage::open(repo_path)?;
+    let (from, _, _) = message_identity(&storage);
+    let message = reply_to_message(&mut storage, id, &from, body)?;
+    Ok(MessageSent { message })
+}
+
+/// Result of `bn msg inbox`.
+#[derive(Serialize)]
+pub struct MessageInbox {
+    /// Whose inbox this is: an agent ID or `human`
+    pub reader: String,
+    pub messages: Vec<Message>,
+    /// Messages that were unread before this call
+    pub unread: usize,
+}
+
+impl Output for MessageInbox {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        if self.messages.is_empty() {
+            return format!("No new messages for {}.", self.reader);
+        }
+        let mut lines = vec![format!(
+            "Inbox for {}: {} message(s), {} unread",
+            self.reader,
+            self.messages.len(),
+            self.unread
+        )];
+        for message in &self.messages {
+            lines.push(format!(
+                "  {}",
+                format_message(message).replace('\n', "\n  ")
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Messages addressed to `reader` or its role, excluding its own.
+fn inbox_for(storage: &Storage, reader: &str, role: Option<&str>) -> Result<Vec<Message>> {
+    Ok(storage
+        .list_messages()?
+        .into_iter()
+        .filter(|m| m.is_for(reader, role) && m.from != reader)
+        .collect())
+}
+
+/// Show the caller's unread messages (all with `all`) and mark them read.
+///
+/// Agents receive messages sent to their ID or their role; callers without a
+/// registered agent read the `human` inbox.
+pub fn msg_inbox(repo_path: &Path, all: bool) -> Result<MessageInbox> {
+    let mut storage = Storage::open(repo_path)?;
+    let (reader, role, _) = message_identity(&storage);
+    let mut messages = Vec::new();
+    let mut unread = 0;
+    for mut message in inbox_for(&storage, &reader, role.as_deref())? {
+        if !message.read_by.contains(&reader) {
+            unread += 1;
+            storage.mark_message_read(&message.id, &reader)?;
+            message.read_by.push(reader.clone());
+        } else if !all {
+            continue;
+        }
+        messages.push(message);
+    }
+    Ok(MessageInbox {
+        reader,
+        messages,
+        unread,
+    })
+}
+
+/// Questions to `human` that have no reply yet, oldest first.
+pub fn open_questions(storage: &Storage) -> Result<Vec<Message>> {
+    let messages = storage.list_messages()?;
+    Ok(messages
+        .iter()
+        .filter(|m| m.kind == MessageKind::Question && m.to == HUMAN_RECIPIENT)
+        .filter(|q| !messages.iter().any(|m| m.reply_to.as_ref() == Some(&q.id)))
+        .cloned()
+        .collect())
+}
+
+/// Result of `bn ask`.
+#[derive(Serialize)]
+pub struct AskResult {
+    pub question: Message,
+    /// The first reply, when one arrived
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub answer: Option<Message>,
+    /// `--wait` gave up after `--timeout`
+    pub timed_out: bool,
+}
+
+impl Output for AskResult {
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    fn to_human(&self) -> String {
+        match &self.answer {
+            Some(answer) => format!("Answer from {}: {}", answer.from, answer.body),
+            None if self.timed_out => format!(
+                "No answer to {} yet. Check `bn msg inbox` later.",
+                self.question.id
+            ),
+            None => format!(
+                "Asked {} as {}. The answer will arrive in `bn msg inbox`.",
+                self.question.to, self.question.id
+            ),
+        }
+    }
+}
+
+/// Ask a question, by default to `human`.
+///
+/// With `wait`, the caller is parked until someone replies (in the GUI, the
+/// TUI or with `bn msg reply`) or `timeout_secs` pass. While parked, the
+/// agent's current action shows the question and its activity is refreshed so
+/// it isn't cleaned up as stale.
+pub fn ask(
+    repo_path: &Path,
+    question: &str,
+    to: Option<&str>,
+    wait: bool,
+    timeout_secs: Option<u64>,
+) -> Result<AskResult> {
+    let mut storage = Storage::open(repo_path)?;
+    let to = resolve_recipient(&storage, to.unwrap_or(HUMAN_RECIPIENT))?;
+    let (reader, _, agent) = message_identity(&storage);
+    let question = send_message(
+        &mut storage,
+        MessageKind::Question,
+        &reader,
+        &to,
+        question,
+        None,
+    )?;
+    if !wait {
+        return Ok(AskResult {
+            question,
+            answer: None,
+            timed_out: false,
+        });
+    }
+
+    let previous_action = agent.as_ref().and_then(|a| a.current_action.clone());
+    if let Some(mut parked) = agent.clone() {
+        parked.current_action = Some(format!("waiting for answer to {}", question.id));
+        storage.update_agent(&parked)?;
+    }
+
+    let started = Instant::now();
+    let mut last_touch = Instant::now();
+    let answer = loop {
+        let reply = storage
+            .list_messages()?
+            .into_iter()
+            .find(|m| m.reply_to.as_ref() == Some(&question.id));
+        if let Some(mut reply) = reply {
+            if !reply.read_by.contains(&reader) {
+                storage.mark_message_read(&reply.id, &reader)?;
+                reply.read_by.push(reader.clone());
+            }
+            break Some(reply);
+        }
+        if timeout_secs.is_some_and(|t| started.elapsed().as_secs() >= t) {
+            break None;
+        }
+        if let Some(ref agent) = agent
+            && last_touch.elapsed().as_secs() >= ASK_TOUCH_INTERVAL_SECS
+        {
+            let _ = storage.touch_agent(agent.pid);
+            last_touch = Instant::now();
+        }
+        std::thread::sleep(std::time::Duration::from_millis(ASK_POLL_INTERVAL_MS));
+    };
+
+    if let Some(agent) = agent
+        && let Ok(mut current) = storage.get_agent_by_id(&agent.id)
+    {
+        current.current_action = previous_action;
+        storage.update_agent(&current)?;
+    }
+
+    Ok(AskResult {
+        question,
+        timed_out: answer.is_none(),
+        answer,
+    })
+}
+
 // === Agent Kill Command ===
 
 /// Result of agent kill command.
@@ -19907,6 +24526,35 @@ pub fn agent_rm(
     })
 }
 
+/// Start a detached `bn agent transcript <id> --capture <stream>` helper
+/// reading `pipe`. It appends each line to the agent's transcript and to
+/// the raw log, and exits when the container closes the stream.
+fn spawn_transcript_capture(
+    repo_path: &Path,
+    agent_id: &str,
+    stream: &str,
+    pipe: Stdio,
+    raw_log: &Path,
+) -> std::io::Result<std::process::Child> {
+    Command::new(std::env::current_exe()?)
+        .args([
+            "agent",
+            "transcript",
+            agent_id,
+            "--capture",
+            stream,
+            "--raw-log",
+        ])
+        .arg(raw_log)
+        .current_dir(repo_path)
+        // The helper's own command must not land in a calling agent's transcript
+        .env_remove("BN_AGENT_ID")
+        .stdin(pipe)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+}
+
 /// Result of agent spawn operation.
 #[derive(Serialize)]
 pub struct AgentSpawnResult {
@@ -19948,6 +24596,12 @@ impl Output for AgentSpawnResult {
             if let Some(ref log) = self.log_path {
                 msg.push_str(&format!("\nLog: {}", log));
             }
+            if let Some(ref id) = self.agent_id {
+                msg.push_str(&format!(
+                    "\nTranscript: bn agent transcript {} --follow",
+                    id
+                ));
+            }
             msg
         } else {
             format!(
@@ -20045,12 +24699,14 @@ pub fn agent_spawn(
     cpus: Option<f64>,
     memory: Option<&str>,
     worktree: Option<&str>,
+    task: Option<&str>,
     merge_target: &str,
     no_merge: bool,
     prompt: Option<&str>,
+    backend: Option<&str>,
 ) -> Result<AgentSpawnResult> {
-    // Check for required tools
-    if !command_exists("ctr") {
+    // Pick the container runtime (configured or auto-detected)
+    let Some(runtime) = find_runtime(repo_path)? else {
         return Ok(AgentSpawnResult {
             success: false,
             agent_id: None,
@@ -20058,13 +24714,9 @@ pub fn agent_spawn(
             agent_type: agent_type.to_string(),
             container_name: None,
             log_path: None,
-            error: Some(container_deps_missing_message()),
+            error: Some(runtime_missing_message()),
         });
-    }
-
-    // Detect containerd mode (rootless vs system)
-    let containerd_mode = detect_containerd_mode()?;
-    warn_system_containerd_mode(&containerd_mode);
+    };
 
     // Discover container definitions and compute image name (same logic as container_run)
     use crate::container::{DefinitionSource, discover_definitions, generate_image_name};
@@ -20108,9 +24760,10 @@ pub fn agent_spawn(
             )
         }
     };
+    let definition = preferred.map(|d| d.definition.clone());
 
-    // Check if the worker image exists in containerd
-    if !container_image_exists(&image_name)? {
+    // Check if the worker image exists in the runtime
+    if !runtime.image_exists(&image_name)? {
         return Ok(AgentSpawnResult {
             success: false,
             agent_id: None,
@@ -20119,16 +24772,22 @@ pub fn agent_spawn(
             container_name: None,
             log_path: None,
             error: Some(format!(
-                "Image '{}' not found in containerd.\n\nRun 'bn container build {}' first to build the worker image.",
-                image_name, def_name
+                "Image '{}' not found in {}.\n\nRun 'bn container build {}' first to build the worker image.",
+                image_name,
+                runtime.kind(),
+                def_name
             )),
         });
     }
 
-    // Determine worktree path - either explicitly provided or use repo_path
-    let worktree_path = match worktree {
-        Some(wt) => PathBuf::from(wt),
-        None => repo_path.to_path_buf(),
+    // Determine worktree path - the task's own worktree, explicitly provided, or repo_path
+    let worktree_path = match (task, worktree) {
+        (Some(task_id), _) => {
+            let created = agent_worktree_create(repo_path, task_id, Some(merge_target))?;
+            PathBuf::from(created.worktree.path)
+        }
+        (None, Some(wt)) => PathBuf::from(wt),
+        (None, None) => repo_path.to_path_buf(),
     };
 
     // Validate worktree path exists
@@ -20169,77 +24828,22 @@ pub fn agent_spawn(
     // Generate container name
     let container_name = format!("binnacle-{}-{}", agent_type, &agent_id[3..]);
 
-    // Build ctr run command
-    let mut args = vec![
-        "-n".to_string(),
-        "binnacle".to_string(),
-        "run".to_string(),
-        "--rm".to_string(),
-    ];
-
-    // agent_spawn always runs detached (stdin=null, output to log file)
-    // so we never allocate a TTY - ctr panics if --tty is used without a real console
-
-    // Handle networking based on containerd mode
-    match &containerd_mode {
-        ContainerdMode::Rootless {
-            child_pid,
-            resolv_conf_path,
-            ..
-        } if *child_pid > 0 => {
-            // Rootless mode: disable cgroups (permission issues) and share network namespace
-            args.push("--cgroup".to_string());
-            args.push(String::new()); // Empty string disables cgroup
-
-            // Share network namespace with rootlesskit for slirp4netns connectivity
-            args.push("--with-ns".to_string());
-            args.push(format!("network:/proc/{}/ns/net", child_pid));
-
-            // Mount resolv.conf for DNS resolution
-            // Priority: rootlesskit state dir > namespace's /etc/resolv.conf
-            // When ctr runs via nsenter inside the rootlesskit namespace,
-            // /etc/resolv.conf resolves to the namespace's copy (with slirp4netns/pasta DNS).
-            let resolv_src = if Path::new(resolv_conf_path).exists() {
-                Some(resolv_conf_path.as_str())
-            } else {
-                // rootlesskit with slirp4netns/pasta creates resolv.conf inside the
-                // namespace via --copy-up=/etc. Since ctr runs inside the namespace
-                // (via nsenter), /etc/resolv.conf points to the namespace's copy.
-                Some("/etc/resolv.conf")
-            };
-            if let Some(resolv) = resolv_src {
-                args.push("--mount".to_string());
-                args.push(format!(
-                    "type=bind,src={},dst=/etc/resolv.conf,options=rbind:ro",
-                    resolv
-                ));
-            }
-            // In rootless mode with user namespaces, container UID 0 maps to host user
-            // So we don't need --user flag - running as root inside = host user outside
-        }
-        _ => {
-            // System mode or simple rootless: use host networking
-            // SECURITY: Host networking removes network namespace isolation.
-            // Required for AI agent API calls (OpenAI, Anthropic, etc.) and package installs.
-            args.push("--net-host".to_string());
-
-            // Run as host user's UID/GID (only for non-userns modes)
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::MetadataExt;
-                let meta = fs::metadata(&worktree_abs).map_err(|e| {
-                    Error::Other(format!(
-                        "Failed to read worktree metadata for user mapping: {}",
-                        e
-                    ))
-                })?;
-                let uid = meta.uid();
-                let gid = meta.gid();
-                args.push("--user".to_string());
-                args.push(format!("{}:{}", uid, gid));
-            }
-        }
-    }
+    // Host user owning the workspace, to preserve file ownership in mounts
+    // SECURITY: User mapping is mandatory to prevent running as root
+    #[cfg(unix)]
+    let user = {
+        use std::os::unix::fs::MetadataExt;
+        let meta = fs::metadata(&worktree_abs).map_err(|e| {
+            Error::Other(format!(
+                "Failed to read worktree metadata for user mapping: {}. \
+                Container cannot run without user mapping.",
+                e
+            ))
+        })?;
+        Some((meta.uid(), meta.gid()))
+    };
+    #[cfg(not(unix))]
+    let user = None;
 
     #[cfg(not(unix))]
     {
@@ -20256,66 +24860,48 @@ pub fn agent_spawn(
         });
     }
 
-    // Add resource limits if specified
-    if let Some(cpu_limit) = cpus {
-        args.push("--cpus".to_string());
-        args.push(cpu_limit.to_string());
-    }
-
-    if let Some(mem_limit) = memory {
-        let bytes = parse_memory_limit(mem_limit)?;
-        args.push("--memory-limit".to_string());
-        args.push(bytes.to_string());
-    }
-
-    // Add mounts
-    args.push("--mount".to_string());
-    args.push(format!(
-        "type=bind,src={},dst=/workspace,options=rbind:rw",
-        worktree_abs.display()
-    ));
+    // Resource limits: command-line flags override the definition's defaults
+    let defaults = definition.as_ref().and_then(|d| d.defaults.as_ref());
+    let cpus = cpus.or_else(|| defaults.and_then(|d| d.cpus).map(f64::from));
+    let memory_bytes = memory
+        .or_else(|| defaults.and_then(|d| d.memory.as_deref()))
+        .map(parse_memory_limit)
+        .transpose()?;
 
-    args.push("--mount".to_string());
-    args.push(format!(
-        "type=bind,src={},dst=/binnacle,options=rbind:rw",
-        binnacle_data.display()
-    ));
+    // Mounts: workspace, binnacle data, and the definition's own mounts
+    let mut mounts = definition_mounts(
+        definition.as_ref(),
+        repo_path,
+        &worktree_abs,
+        false,
+        &binnacle_data,
+    )?;
 
     // If this is a git worktree, mount the parent repo's .git directory
     if let Some(parent_git_dir) = detect_worktree_parent_git(&worktree_abs) {
-        args.push("--mount".to_string());
-        args.push(format!(
-            "type=bind,src={},dst={},options=rbind:rw",
-            parent_git_dir.display(),
-            parent_git_dir.display()
-        ));
+        mounts.push(RunMount {
+            target: parent_git_dir.display().to_string(),
+            source: parent_git_dir,
+            read_only: false,
+        });
     }
 
     // Add environment variables
-    args.push("--env".to_string());
-    args.push(format!("BN_AGENT_TYPE={}", agent_type));
-
-    args.push("--env".to_string());
-    args.push("BN_CONTAINER_MODE=true".to_string());
-
-    args.push("--env".to_string());
-    args.push(format!("BN_MERGE_TARGET={}", merge_target));
-
-    // Tell bn where to find the database
-    args.push("--env".to_string());
-    args.push("BN_DATA_DIR=/binnacle".to_string());
-
-    // Pass pre-assigned agent ID and name
-    args.push("--env".to_string());
-    args.push(format!("BN_AGENT_ID={}", agent_id));
-
-    args.push("--env".to_string());
-    args.push(format!("BN_AGENT_NAME={}", agent_name));
+    let mut env = vec![
+        ("BN_AGENT_TYPE".to_string(), agent_type.to_string()),
+        ("BN_CONTAINER_MODE".to_string(), "true".to_string()),
+        ("BN_MERGE_TARGET".to_string(), merge_target.to_string()),
+        // Tell bn where to find the database
+        ("BN_DATA_DIR".to_string(), "/binnacle".to_string()),
+        // Pass pre-assigned agent ID and name
+        ("BN_AGENT_ID".to_string(), agent_id.clone()),
+        ("BN_AGENT_NAME".to_string(), agent_name.clone()),
+    ];
 
     // Create agent record before starting container
     // This ensures the GUI shows the agent immediately
     if let Ok(mut storage) = Storage::open(repo_path) {
-        let parsed_agent_type = parse_agent_type(agent_type).unwrap_or(AgentType::Worker);
+        let parsed_agent_type = crate::agents::policy::agent_type_for_definition(agent_type);
         let mut agent = Agent::new_with_id(
             agent_id.clone(),
             0, // PID will be set when container's bn orient runs
@@ -20326,6 +24912,10 @@ pub fn agent_spawn(
         // Set container_id so is_alive() knows this is a container agent
         // and can check container status instead of /proc/pid
         agent.container_id = Some(container_name.clone());
+        agent.transcript_path = storage
+            .transcript_path(&agent_id)
+            .ok()
+            .map(|p| p.to_string_lossy().to_string());
         let _ = storage.register_agent(&agent);
     }
 
@@ -20335,24 +24925,41 @@ pub fn agent_spawn(
     hasher.update(repo_root.to_string_lossy().as_bytes());
     let hash = hasher.finalize();
     let storage_hash = &format!("{:x}", hash)[..12];
-    args.push("--env".to_string());
-    args.push(format!("BN_STORAGE_HASH={}", storage_hash));
+    env.push(("BN_STORAGE_HASH".to_string(), storage_hash.to_string()));
 
     if no_merge {
-        args.push("--env".to_string());
-        args.push("BN_NO_MERGE=true".to_string());
+        env.push(("BN_NO_MERGE".to_string(), "true".to_string()));
     }
 
-    // Pass custom prompt if provided
-    if let Some(custom_prompt) = prompt {
-        args.push("--env".to_string());
-        args.push(format!("BN_INITIAL_PROMPT={}", custom_prompt));
+    // A task worktree is finished (tested and merged) by the entrypoint on exit
+    if let Some(task_id) = task {
+        env.push(("BN_TASK_ID".to_string(), task_id.to_string()));
+    }
+
+    // Pass custom prompt if provided, defaulting to the task for task worktrees
+    let task_prompt = task.map(|task_id| {
+        format!(
+            "Work on {id}: claim it with `bn task update {id} --status in_progress`, complete it, \
+            commit your changes on branch {branch}, then run `bn goodbye`.",
+            id = task_id,
+            branch = agents::worktree::branch_name(task_id)
+        )
+    });
+    if let Some(custom_prompt) = prompt.or(task_prompt.as_deref()) {
+        env.push(("BN_INITIAL_PROMPT".to_string(), custom_prompt.to_string()));
+    }
+
+    // Pick the agent CLI backend inside the container (see `bn config agents launch`)
+    if let Some(backend) = backend {
+        env.push((
+            crate::agents::backend::BACKEND_ENV_VAR.to_string(),
+            backend.to_string(),
+        ));
     }
 
     // Pass through GitHub tokens if available
     if let Ok(token) = std::env::var("GH_TOKEN") {
-        args.push("--env".to_string());
-        args.push(format!("GH_TOKEN={}", token));
+        env.push(("GH_TOKEN".to_string(), token.to_string()));
     }
     // COPILOT_GITHUB_TOKEN: Use precedence resolution (env > session > system)
     // This allows tokens configured via `bn session init --token` to be injected
@@ -20360,8 +24967,7 @@ pub fn agent_spawn(
     if let Ok(storage) = Storage::open(repo_path) {
         if let Ok(state) = resolve_state(&storage) {
             if let Some(token) = state.token() {
-                args.push("--env".to_string());
-                args.push(format!("COPILOT_GITHUB_TOKEN={}", token));
+                env.push(("COPILOT_GITHUB_TOKEN".to_string(), token.to_string()));
             }
         }
     }
@@ -20410,57 +25016,75 @@ pub fn agent_spawn(
         }
     };
 
-    args.push("--env".to_string());
-    args.push(format!("GIT_AUTHOR_NAME={}", effective_name));
-    args.push("--env".to_string());
-    args.push(format!("GIT_COMMITTER_NAME={}", effective_name));
-    args.push("--env".to_string());
-    args.push(format!("GIT_AUTHOR_EMAIL={}", effective_email));
-    args.push("--env".to_string());
-    args.push(format!("GIT_COMMITTER_EMAIL={}", effective_email));
+    env.push(("GIT_AUTHOR_NAME".to_string(), effective_name.to_string()));
+    env.push(("GIT_COMMITTER_NAME".to_string(), effective_name.to_string()));
+    env.push(("GIT_AUTHOR_EMAIL".to_string(), effective_email.to_string()));
+    env.push((
+        "GIT_COMMITTER_EMAIL".to_string(),
+        effective_email.to_string(),
+    ));
 
     // Set flag to indicate when using anonymous identity (skips Co-authored-by trailer)
     if using_anonymous_identity {
-        args.push("--env".to_string());
-        args.push("BINNACLE_ANONYMOUS_IDENTITY=1".to_string());
+        env.push(("BINNACLE_ANONYMOUS_IDENTITY".to_string(), "1".to_string()));
     }
 
     // Pass through timezone
     if let Ok(tz) = std::env::var("TZ") {
         if !tz.is_empty() {
-            args.push("--env".to_string());
-            args.push(format!("TZ={}", tz));
+            env.push(("TZ".to_string(), tz.to_string()));
         }
     } else if let Ok(contents) = fs::read_to_string("/etc/timezone") {
         let tz = contents.trim();
         if !tz.is_empty() {
-            args.push("--env".to_string());
-            args.push(format!("TZ={}", tz));
+            env.push(("TZ".to_string(), tz.to_string()));
         }
     }
 
-    // Add image and container name
-    args.push(image_name.clone());
-    args.push(container_name.clone());
+    let spec = RunSpec {
+        image: image_name.clone(),
+        name: container_name.clone(),
+        mounts,
+        env,
+        cpus,
+        memory_bytes,
+        user,
+        ..Default::default()
+    };
 
     // Create agent logs directory and log file
     let logs_dir = binnacle_data.join("agentlogs");
     fs::create_dir_all(&logs_dir)?;
     let log_path = logs_dir.join(format!("{}.log", agent_id));
-    let log_file = fs::File::create(&log_path)?;
+    fs::File::create(&log_path)?;
     let log_path_str = log_path.to_string_lossy().to_string();
 
-    // Run container with output redirected to log file
+    // Run container with its output piped to transcript capture helpers
     // Use spawn() instead of status() so we can detach and not block
-    let child = ctr_command(&containerd_mode)
-        .args(&args)
+    let child = runtime
+        .run_command(&spec)?
         .stdin(Stdio::null())
-        .stdout(log_file.try_clone()?)
-        .stderr(log_file)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
         .spawn();
 
     match child {
-        Ok(_) => {
+        Ok(mut child) => {
+            let streams = [
+                ("stdout", child.stdout.take().map(Stdio::from)),
+                ("stderr", child.stderr.take().map(Stdio::from)),
+            ];
+            for (stream, pipe) in streams {
+                let Some(pipe) = pipe else { continue };
+                if let Err(e) =
+                    spawn_transcript_capture(repo_path, &agent_id, stream, pipe, &log_path)
+                {
+                    eprintln!(
+                        "Warning: Failed to capture {} {} into its transcript: {}",
+                        agent_id, stream, e
+                    );
+                }
+            }
             // Container started successfully in background
             Ok(AgentSpawnResult {
                 success: true,
@@ -20520,7 +25144,7 @@ pub struct AgentReconcileResult {
     pub error: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct AgentTypeCounts {
     pub worker: usize,
     pub planner: usize,
@@ -20642,6 +25266,27 @@ impl Output for AgentReconcileResult {
 /// - If current < desired: spawn (desired - current) agents
 /// - If current > desired: stop (current - desired) agents (prefer idle, then oldest)
 pub fn agent_reconcile(repo_path: &Path, dry_run: bool) -> Result<AgentReconcileResult> {
+    agent_reconcile_with_hold(repo_path, dry_run, &ReconcileHold::default())
+}
+
+/// Spawn restrictions the agent supervisor applies to reconciliation.
+#[derive(Debug, Clone, Default)]
+pub struct ReconcileHold {
+    /// Slots held for pending restarts. They count towards the current count
+    /// when deciding how many to spawn (the caller restarts them itself), but
+    /// not when deciding how many to stop.
+    pub reserved: AgentTypeCounts,
+    /// Agent types that must not be spawned (e.g. their budget is exhausted)
+    pub paused: Vec<AgentType>,
+}
+
+/// Run agent reconciliation with slots held back or spawning paused.
+pub fn agent_reconcile_with_hold(
+    repo_path: &Path,
+    dry_run: bool,
+    hold: &ReconcileHold,
+) -> Result<AgentReconcileResult> {
+    let reserved = &hold.reserved;
     let mut storage = Storage::open(repo_path)?;
 
     // Clean up stale agents first
@@ -20661,7 +25306,7 @@ pub fn agent_reconcile(repo_path: &Path, dry_run: bool) -> Result<AgentReconcile
         } else {
             // Stop the container (uses 15s timeout internally via container_stop_gracefully)
             if let Some(ref container_id) = agent.container_id {
-                let _ = container_stop(Some(container_id.clone()), false);
+                let _ = container_stop(repo_path, Some(container_id.clone()), false);
             }
             // Remove agent from registry
             let _ = storage.remove_agent(agent.pid);
@@ -20777,8 +25422,10 @@ pub fn agent_reconcile(repo_path: &Path, dry_run: bool) -> Result<AgentReconcile
     }
 
     // Reconcile workers
-    if current_worker_count < desired_worker_count {
-        let to_spawn = desired_worker_count - current_worker_count;
+    if !hold.paused.contains(&AgentType::Worker)
+        && current_worker_count + reserved.worker < desired_worker_count
+    {
+        let to_spawn = desired_worker_count - current_worker_count - reserved.worker;
         for _ in 0..to_spawn {
             let (executed, spawned_agent_id, spawned_agent_name, spawned_log_path) = if dry_run {
                 (false, None, None, None)
@@ -20791,11 +25438,13 @@ pub fn agent_reconcile(repo_path: &Path, dry_run: bool) -> Result<AgentReconcile
                     None, // default cpus
                     None, // default memory
                     None, // use repo_path
+                    None, // no task worktree
                     "main",
                     false,
                     agents::get_embedded_agent("worker")
                         .map(|a| a.prompt)
                         .as_deref(),
+                    None, // backend from agent definition
                 ) {
                     Ok(result) => (
                         result.success,
@@ -20829,7 +25478,7 @@ pub fn agent_reconcile(repo_path: &Path, dry_run: bool) -> Result<AgentReconcile
             } else {
                 // Stop the agent's container if it has one
                 if let Some(ref container_id) = agent.container_id {
-                    let _ = container_stop(Some(container_id.clone()), false);
+                    let _ = container_stop(repo_path, Some(container_id.clone()), false);
                 }
                 // Remove from registry
                 let _ = storage.remove_agent(agent.pid);
@@ -20856,8 +25505,10 @@ pub fn agent_reconcile(repo_path: &Path, dry_run: bool) -> Result<AgentReconcile
     }
 
     // Reconcile planners
-    if current_planner_count < desired_planner_count {
-        let to_spawn = desired_planner_count - current_planner_count;
+    if !hold.paused.contains(&AgentType::Planner)
+        && current_planner_count + reserved.planner < desired_planner_count
+    {
+        let to_spawn = desired_planner_count - current_planner_count - reserved.planner;
         for _ in 0..to_spawn {
             let (executed, spawned_agent_id, spawned_agent_name, spawned_log_path) = if dry_run {
                 (false, None, None, None)
@@ -20869,11 +25520,13 @@ pub fn agent_reconcile(repo_path: &Path, dry_run: bool) -> Result<AgentReconcile
                     None,
                     None,
                     None,
+                    None,
                     "main",
                     false,
                     agents::get_embedded_agent("prd")
                         .map(|a| a.prompt)
                         .as_deref(),
+                    None,
                 ) {
                     Ok(result) => (
                         result.success,
@@ -20906,7 +25559,7 @@ pub fn agent_reconcile(repo_path: &Path, dry_run: bool) -> Result<AgentReconcile
                 false
             } else {
                 if let Some(ref container_id) = agent.container_id {
-                    let _ = container_stop(Some(container_id.clone()), false);
+                    let _ = container_stop(repo_path, Some(container_id.clone()), false);
                 }
                 let _ = storage.remove_agent(agent.pid);
                 true
@@ -20928,8 +25581,10 @@ pub fn agent_reconcile(repo_path: &Path, dry_run: bool) -> Result<AgentReconcile
     }
 
     // Reconcile buddies
-    if current_buddy_count < desired_buddy_count {
-        let to_spawn = desired_buddy_count - current_buddy_count;
+    if !hold.paused.contains(&AgentType::Buddy)
+        && current_buddy_count + reserved.buddy < desired_buddy_count
+    {
+        let to_spawn = desired_buddy_count - current_buddy_count - reserved.buddy;
         for _ in 0..to_spawn {
             let (executed, spawned_agent_id, spawned_agent_name, spawned_log_path) = if dry_run {
                 (false, None, None, None)
@@ -20941,11 +25596,13 @@ pub fn agent_reconcile(repo_path: &Path, dry_run: bool) -> Result<AgentReconcile
                     None,
                     None,
                     None,
+                    None,
                     "main",
                     false,
                     agents::get_embedded_agent("buddy")
                         .map(|a| a.prompt)
                         .as_deref(),
+                    None,
                 ) {
                     Ok(result) => (
                         result.success,
@@ -20978,7 +25635,7 @@ pub fn agent_reconcile(repo_path: &Path, dry_run: bool) -> Result<AgentReconcile
                 false
             } else {
                 if let Some(ref container_id) = agent.container_id {
-                    let _ = container_stop(Some(container_id.clone()), false);
+                    let _ = container_stop(repo_path, Some(container_id.clone()), false);
                 }
                 let _ = storage.remove_agent(agent.pid);
                 true
@@ -21068,6 +25725,9 @@ pub struct GoodbyeResult {
     /// The agent type (if registered)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub agent_type: Option<String>,
+    /// Handoff docs written for the agent's in-progress tasks
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub handoffs: Vec<String>,
 }
 
 #[allow(dead_code)] // Used by serde for default deserialization
@@ -21116,6 +25776,10 @@ impl Output for GoodbyeResult {
             lines.push(format!("Reason: {}", reason));
         }
 
+        if !self.handoffs.is_empty() {
+            lines.push(format!("Handoff docs: {}", self.handoffs.join(", ")));
+        }
+
         lines.push(format!(
             "Terminating agent session (grandparent PID {})...",
             self.grandparent_pid
@@ -21144,7 +25808,15 @@ impl Output for GoodbyeResult {
 /// When `BN_AGENT_ID` env var is set, the agent is looked up by ID instead of PID.
 /// When `BN_MCP_SESSION` env var is set, the agent is looked up by session ID
 /// instead of by PID, and process termination is automatically skipped.
-pub fn goodbye(repo_path: &Path, reason: Option<String>, force: bool) -> Result<GoodbyeResult> {
+///
+/// Each task the agent still has in progress gets a handoff doc with the
+/// reason and `next_steps` (see [`agents::handoff`]).
+pub fn goodbye(
+    repo_path: &Path,
+    reason: Option<String>,
+    next_steps: Vec<String>,
+    force: bool,
+) -> Result<GoodbyeResult> {
     let bn_pid = std::process::id();
     let parent_pid = get_parent_pid().unwrap_or(0);
     let grandparent_pid = get_grandparent_pid().unwrap_or(0);
@@ -21225,6 +25897,19 @@ pub fn goodbye(repo_path: &Path, reason: Option<String>, force: bool) -> Result<
     // (the agent interprets the response and self-terminates)
     let will_terminate = mcp_session_id.is_none() && env_agent_id.is_none() && should_terminate;
 
+    // Leave a handoff on every task still in progress (planners that are
+    // refused termination keep working, so they don't hand off)
+    let handoffs = match &agent {
+        Some(agent_data) if should_terminate || env_agent_id.is_some() => agents::write_handoffs(
+            &mut storage,
+            Some(repo_path),
+            agent_data,
+            reason.as_deref(),
+            &next_steps,
+        )?,
+        _ => Vec::new(),
+    };
+
     // Update agent with goodbye status before removing (for GUI animation)
     if was_registered
         && let Some(pid) = agent_pid_for_update
@@ -21270,6 +25955,7 @@ pub fn goodbye(repo_path: &Path, reason: Option<String>, force: bool) -> Result<
         should_terminate,           // Whether the agent should self-terminate
         warning,
         agent_type,
+        handoffs,
     })
 }
 
@@ -21677,202 +26363,9 @@ fn write_embedded_default_container_files() -> Result<PathBuf> {
     Ok(temp_dir)
 }
 
-/// Check if a command exists on the system.
-fn command_exists(cmd: &str) -> bool {
-    Command::new("which")
-        .arg(cmd)
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
-}
-
-/// Containerd runtime mode.
-#[derive(Debug, Clone, PartialEq)]
-pub enum ContainerdMode {
-    /// System-wide containerd using sudo (default socket at /run/containerd/containerd.sock)
-    System,
-    /// Rootless containerd (socket at $XDG_RUNTIME_DIR/containerd/containerd.sock)
-    /// This mode requires nsenter to access the rootlesskit namespace.
-    Rootless {
-        /// Path to the containerd socket (inside the namespace, typically /run/containerd/containerd.sock)
-        socket_path: String,
-        /// PID of the rootlesskit child process (for nsenter)
-        child_pid: u32,
-        /// Path to resolv.conf for DNS (from containerd-rootless directory)
-        resolv_conf_path: String,
-    },
-}
-
-/// Detect the containerd runtime mode.
-/// Checks for rootless containerd first, falls back to system containerd.
-///
-/// # Errors
-/// Returns an error if system containerd would be used but `BN_ALLOW_SUDO=1` is not set.
-/// This prevents binnacle from running sudo commands without explicit user consent.
-pub fn detect_containerd_mode() -> Result<ContainerdMode> {
-    // Check for rootless containerd setup at XDG_RUNTIME_DIR/containerd-rootless
-    if let Ok(xdg_runtime) = std::env::var("XDG_RUNTIME_DIR") {
-        let rootless_dir = format!("{}/containerd-rootless", xdg_runtime);
-        let child_pid_file = format!("{}/child_pid", rootless_dir);
-        let resolv_conf = format!("{}/resolv.conf", rootless_dir);
-
-        // Check if the rootless containerd is set up (has child_pid file)
-        if let Ok(pid_str) = fs::read_to_string(&child_pid_file) {
-            if let Ok(child_pid) = pid_str.trim().parse::<u32>() {
-                // Verify the process is still running
-                if Path::new(&format!("/proc/{}", child_pid)).exists() {
-                    return Ok(ContainerdMode::Rootless {
-                        // Inside the namespace, the socket is at the standard location
-                        socket_path: "/run/containerd/containerd.sock".to_string(),
-                        child_pid,
-                        resolv_conf_path: resolv_conf,
-                    });
-                }
-            }
-        }
-
-        // Also check for the simple rootless socket (without rootlesskit)
-        let rootless_socket = format!("{}/containerd/containerd.sock", xdg_runtime);
-        if Path::new(&rootless_socket).exists() {
-            // This is a simpler rootless setup without user namespaces
-            // It won't work for container run, but we detect it for better error messages
-            return Ok(ContainerdMode::Rootless {
-                socket_path: rootless_socket,
-                child_pid: 0, // No namespace to enter
-                resolv_conf_path: String::new(),
-            });
-        }
-    }
-
-    // Fall back to system containerd - but require explicit opt-in for sudo
-    if std::env::var("BN_ALLOW_SUDO")
-        .map(|v| v == "1")
-        .unwrap_or(false)
-    {
-        Ok(ContainerdMode::System)
-    } else {
-        Err(Error::Other(
-            "System containerd requires sudo, which is blocked by default.\n\n\
-             To enable sudo-based container operations:\n\n\
-                 export BN_ALLOW_SUDO=1\n\n\
-             Recommended: Set up rootless containerd instead (no sudo needed):\n\n\
-                 bn help container\n\n\
-             For more information, see the rootless containerd documentation."
-                .to_string(),
-        ))
-    }
-}
-
-/// Create a Command for ctr with the appropriate mode.
-/// For rootless mode, uses nsenter to enter the rootlesskit namespace.
-/// For system mode, uses sudo.
-fn ctr_command(mode: &ContainerdMode) -> Command {
-    match mode {
-        ContainerdMode::Rootless {
-            socket_path,
-            child_pid,
-            ..
-        } => {
-            if *child_pid > 0 {
-                // Use nsenter to enter the rootlesskit namespace
-                let mut cmd = Command::new("nsenter");
-                cmd.arg("-U")
-                    .arg("--preserve-credentials")
-                    .arg("-m")
-                    .arg("-n")
-                    .arg("-t")
-                    .arg(child_pid.to_string())
-                    .arg("ctr")
-                    .arg("-a")
-                    .arg(socket_path);
-                cmd
-            } else {
-                // Simple rootless mode (no namespace)
-                let mut cmd = Command::new("ctr");
-                cmd.arg("-a").arg(socket_path);
-                cmd
-            }
-        }
-        ContainerdMode::System => {
-            let mut cmd = Command::new("sudo");
-            cmd.arg("ctr");
-            cmd
-        }
-    }
-}
-
-/// Print a warning when using system containerd (requires sudo).
-/// This is called once per operation that needs containerd access.
-fn warn_system_containerd_mode(mode: &ContainerdMode) {
-    if matches!(mode, ContainerdMode::System) {
-        eprintln!("⚠️  Using system containerd (requires sudo)");
-        eprintln!("   For rootless operation without sudo, see: bn help container");
-    }
-}
-
-/// Check if an error message indicates permission denied on containerd socket
-/// and provide a helpful error message.
-fn check_containerd_permission_error(stderr: &str) -> Option<String> {
-    let stderr_lower = stderr.to_lowercase();
-
-    // Common permission denied patterns for containerd socket
-    if (stderr_lower.contains("permission denied")
-        && (stderr_lower.contains("containerd.sock")
-            || stderr_lower.contains("/run/containerd")
-            || stderr_lower.contains("connect to containerd")))
-        || stderr_lower.contains("failed to dial")
-    {
-        return Some(
-            "Permission denied accessing containerd socket.\n\
-             \n\
-             To fix this, run with sudo:\n\
-             \n\
-             \x20   sudo bn container run <worktree>\n\
-             \n\
-             Or set up rootless containerd (see: bn help container)"
-                .to_string(),
-        );
-    }
-
-    None
-}
-
-/// Get the helpful installation message for container dependencies.
-fn container_deps_missing_message() -> String {
-    r#"Error: containerd or buildah not found
-
-To use binnacle containers, install containerd and buildah:
-
-  # Fedora/RHEL
-  sudo dnf install containerd buildah
-  sudo systemctl enable --now containerd
-
-  # Debian/Ubuntu
-  sudo apt install containerd buildah
-  sudo systemctl enable --now containerd
-
-For more info, see: https://github.com/containerd/containerd"#
-        .to_string()
-}
-
-/// Check if the binnacle worker image exists in containerd.
-fn container_image_exists(image_name: &str) -> Result<bool> {
-    let mode = detect_containerd_mode()?;
-    let filter = format!("name=={}", image_name);
-    Ok(ctr_command(&mode)
-        .args(["-n", "binnacle", "images", "check", &filter])
-        .output()
-        .map(|o| {
-            // If the command succeeds and has output lines beyond the header, the image exists
-            if o.status.success() {
-                let stdout = String::from_utf8_lossy(&o.stdout);
-                // The output includes a header line, so we need more than 1 line
-                stdout.lines().count() > 1
-            } else {
-                false
-            }
-        })
-        .unwrap_or(false))
+/// Check if an image exists in the repository's container runtime.
+fn container_image_exists(repo_path: &Path, image_name: &str) -> Result<bool> {
+    select_runtime(repo_path)?.image_exists(image_name)
 }
 
 /// Parse a memory limit string (e.g., "512m", "1g", "2048m") into bytes.
@@ -21914,7 +26407,7 @@ fn parse_memory_limit(s: &str) -> Result<u64> {
     Ok(num * multiplier)
 }
 
-/// Build container image(s) using buildah.
+/// Build container image(s) with the selected container runtime.
 ///
 /// # Modes
 /// - No definition name: Lists available definitions
@@ -21940,18 +26433,6 @@ pub fn container_build(
     };
     use std::collections::HashMap;
 
-    // Check for required tools
-    if !command_exists("buildah") || !command_exists("ctr") {
-        return Ok(ContainerBuildResult {
-            success: false,
-            tag: None,
-            built_definitions: None,
-            skipped_definitions: None,
-            available_definitions: None,
-            error: Some(container_deps_missing_message()),
-        });
-    }
-
     // Discover available definitions
     let defs_with_source = discover_definitions(repo_path)?;
 
@@ -22089,7 +26570,24 @@ pub fn container_build(
         compute_build_order(&subset_map)?
     };
 
-    eprintln!("📋 Build order: {}", to_build.join(" → "));
+    // Pick the container runtime (checks that its tools are installed)
+    let Some(runtime) = find_runtime(repo_path)? else {
+        return Ok(ContainerBuildResult {
+            success: false,
+            tag: None,
+            built_definitions: None,
+            skipped_definitions: None,
+            available_definitions: None,
+            error: Some(runtime_missing_message()),
+        });
+    };
+    let runtime = runtime.as_ref();
+
+    eprintln!(
+        "📋 Build order ({}): {}",
+        runtime.kind(),
+        to_build.join(" → ")
+    );
 
     // Build each definition in order, skipping those that already exist
     let mut built = Vec::new();
@@ -22108,11 +26606,21 @@ pub fn container_build(
             }
         };
 
-        // Check if image already exists in containerd
+        // Check if image already exists in the runtime
         let is_embedded = def_src.source == crate::container::DefinitionSource::Embedded;
         let image_name = generate_image_name_for_definition(repo_path, def_name, tag, is_embedded)?;
 
-        if container_image_exists(&image_name)? && !no_cache {
+        if !runtime.kind().uses_images() {
+            eprintln!(
+                "\n⏭️  Skipping {} ({} runs on the host filesystem)",
+                def_name,
+                runtime.kind()
+            );
+            skipped.push(def_name.clone());
+            continue;
+        }
+
+        if runtime.image_exists(&image_name)? && !no_cache {
             eprintln!("\n⏭️  Skipping {} (image exists: {})", def_name, image_name);
             skipped.push(def_name.clone());
             continue;
@@ -22124,10 +26632,10 @@ pub fn container_build(
         if is_embedded {
             if def_name == RESERVED_NAME {
                 // Legacy embedded build for binnacle-self (backward compatibility)
-                build_embedded_container(tag, no_cache)?;
+                build_embedded_container(runtime, tag, no_cache)?;
             } else if def_name == crate::container::EMBEDDED_DEFAULT_NAME {
                 // Embedded build for binnacle-default (minimal base image)
-                build_embedded_default_container(tag, no_cache)?;
+                build_embedded_default_container(runtime, tag, no_cache)?;
             } else {
                 return Err(Error::Other(format!(
                     "Unknown embedded container definition: {}",
@@ -22136,7 +26644,14 @@ pub fn container_build(
             }
         } else {
             // Build from definition
-            build_definition_container(repo_path, def_name, &def_src.definition, tag, no_cache)?;
+            build_definition_container(
+                runtime,
+                repo_path,
+                def_name,
+                &def_src.definition,
+                tag,
+                no_cache,
+            )?;
         }
 
         built.push(def_name.clone());
@@ -22169,13 +26684,17 @@ pub fn container_build(
 }
 
 /// Build the embedded binnacle container (legacy, for backward compatibility)
-fn build_embedded_container(tag: &str, no_cache: bool) -> Result<()> {
+fn build_embedded_container(
+    runtime: &dyn ContainerRuntime,
+    tag: &str,
+    no_cache: bool,
+) -> Result<()> {
     // Ensure binnacle-default base image exists before building binnacle-self
     // The worker Containerfile uses "FROM binnacle-default:latest"
     let default_image = format!("localhost/binnacle-default:{}", tag);
-    if !container_image_exists(&default_image)? {
+    if !runtime.image_exists(&default_image)? {
         eprintln!("📦 Building base image (binnacle-default) first...");
-        build_embedded_default_container(tag, no_cache)?;
+        build_embedded_default_container(runtime, tag, no_cache)?;
     }
 
     // Determine build context: prefer external files if available, otherwise use embedded
@@ -22226,31 +26745,15 @@ fn build_embedded_container(tag: &str, no_cache: bool) -> Result<()> {
         ))
     })?;
 
-    // Build with buildah - stream output for real-time feedback
-    eprintln!(
-        "📦 Building container image (localhost/binnacle-self:{})...",
-        tag
-    );
-    let mut build_cmd = Command::new("buildah");
-    build_cmd
-        .arg("bud")
-        .arg("--layers") // Enable layer caching for faster rebuilds
-        .arg("--network")
-        .arg("slirp4netns") // pasta fails with large route tables (e.g. VPN split-tunnel)
-        .arg("-t")
-        .arg(format!("localhost/binnacle-self:{}", tag))
-        .arg("-f")
-        .arg(&containerfile_path)
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit());
-
-    if no_cache {
-        build_cmd.arg("--no-cache");
-    }
-
-    build_cmd.arg(&build_context_dir);
-
-    let status = build_cmd.status()?;
+    // Build - stream output for real-time feedback
+    let image_ref = format!("localhost/binnacle-self:{}", tag);
+    eprintln!("📦 Building container image ({})...", image_ref);
+    let result = runtime.build_image(&BuildSpec {
+        image: image_ref,
+        containerfile: containerfile_path,
+        context: build_context_dir.clone(),
+        no_cache,
+    });
 
     // Clean up the copied binary and temp directory
     let _ = fs::remove_file(&binary_path);
@@ -22258,74 +26761,15 @@ fn build_embedded_container(tag: &str, no_cache: bool) -> Result<()> {
         let _ = fs::remove_dir_all(&build_context_dir);
     }
 
-    if !status.success() {
-        return Err(Error::Other("Build failed (see output above)".to_string()));
-    }
-
-    // Export and import to containerd
-    // Use docker-archive format (not oci-archive) because it embeds the full image
-    // reference in the manifest, allowing ctr to import it with the correct name.
-    // OCI archives require annotations that buildah doesn't always include.
-    eprintln!("📤 Exporting image to docker archive...");
-    let temp_dir = get_binnacle_temp_dir()?;
-    let temp_archive = temp_dir.join("binnacle-self.tar");
-    // Remove existing archive if present (docker-archive can't overwrite)
-    if temp_archive.exists() {
-        let _ = fs::remove_file(&temp_archive);
-    }
-    let temp_archive_str = temp_archive.to_string_lossy();
-    let image_ref = format!("localhost/binnacle-self:{}", tag);
-
-    let push_output = Command::new("buildah")
-        .args([
-            "push",
-            &image_ref,
-            &format!("docker-archive:{}:{}", temp_archive_str, image_ref),
-        ])
-        .output()?;
-
-    if !push_output.status.success() {
-        return Err(Error::Other(format!(
-            "Failed to export image: {}",
-            String::from_utf8_lossy(&push_output.stderr)
-        )));
-    }
-
-    eprintln!("📥 Importing image to containerd...");
-    let mode = detect_containerd_mode()?;
-    warn_system_containerd_mode(&mode);
-    let import_output = ctr_command(&mode)
-        .args([
-            "-n",
-            "binnacle",
-            "images",
-            "import",
-            temp_archive_str.as_ref(),
-        ])
-        .output()?;
-
-    // Clean up temp file
-    eprintln!("🧹 Cleaning up...");
-    let _ = fs::remove_file(&temp_archive);
-
-    if !import_output.status.success() {
-        let stderr = String::from_utf8_lossy(&import_output.stderr);
-
-        // Check for permission denied errors and provide helpful message
-        let error_msg = if let Some(helpful_msg) = check_containerd_permission_error(&stderr) {
-            helpful_msg
-        } else {
-            format!("Failed to import image to containerd: {}", stderr)
-        };
-
-        return Err(Error::Other(error_msg));
-    }
-
-    Ok(())
+    result
 }
 
 /// Build the embedded default container (binnacle-default base image)
-fn build_embedded_default_container(tag: &str, no_cache: bool) -> Result<()> {
+fn build_embedded_default_container(
+    runtime: &dyn ContainerRuntime,
+    tag: &str,
+    no_cache: bool,
+) -> Result<()> {
     // Determine build context: prefer external files if available, otherwise use embedded
     // Check in order: new location (.binnacle/containers/default/), legacy location (container/), embedded
     let (
@@ -22386,104 +26830,32 @@ fn build_embedded_default_container(tag: &str, no_cache: bool) -> Result<()> {
         ))
     })?;
 
-    // Build with buildah - stream output for real-time feedback
+    // Build - stream output for real-time feedback
     let image_ref = format!("localhost/binnacle-default:{}", tag);
-    eprintln!("📦 Building container image ({})...", image_ref);
-    let mut build_cmd = Command::new("buildah");
-    build_cmd
-        .arg("bud")
-        .arg("--layers") // Enable layer caching for faster rebuilds
-        .arg("--network")
-        .arg("slirp4netns") // pasta fails with large route tables (e.g. VPN split-tunnel)
-        .arg("-t")
-        .arg(&image_ref)
-        .arg("-f")
-        .arg(&containerfile_path)
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit());
-
-    if no_cache {
-        build_cmd.arg("--no-cache");
-    }
-
-    build_cmd.arg(&build_context_dir);
-
-    let status = build_cmd.status()?;
-
-    // Clean up the copied binary and temp directory
-    let _ = fs::remove_file(&binary_path);
-    // We don't need to clean up bn_entry_path or git_wrapper_path if using external files
-    // They are only used at build time via COPY commands
-    let _ = bn_entry_path; // suppress unused warning
-    let _ = git_wrapper_path; // suppress unused warning
-    if using_embedded {
-        let _ = fs::remove_dir_all(&build_context_dir);
-    }
-
-    if !status.success() {
-        return Err(Error::Other("Build failed (see output above)".to_string()));
-    }
-
-    // Export and import to containerd
-    eprintln!("📤 Exporting image to docker archive...");
-    let temp_dir = get_binnacle_temp_dir()?;
-    let temp_archive = temp_dir.join("binnacle-default.tar");
-    // Remove existing archive if present (docker-archive can't overwrite)
-    if temp_archive.exists() {
-        let _ = fs::remove_file(&temp_archive);
-    }
-    let temp_archive_str = temp_archive.to_string_lossy();
-
-    let push_output = Command::new("buildah")
-        .args([
-            "push",
-            &image_ref,
-            &format!("docker-archive:{}:{}", temp_archive_str, image_ref),
-        ])
-        .output()?;
-
-    if !push_output.status.success() {
-        return Err(Error::Other(format!(
-            "Failed to export image: {}",
-            String::from_utf8_lossy(&push_output.stderr)
-        )));
-    }
-
-    eprintln!("📥 Importing image to containerd...");
-    let mode = detect_containerd_mode()?;
-    warn_system_containerd_mode(&mode);
-    let import_output = ctr_command(&mode)
-        .args([
-            "-n",
-            "binnacle",
-            "images",
-            "import",
-            temp_archive_str.as_ref(),
-        ])
-        .output()?;
-
-    // Clean up temp file
-    eprintln!("🧹 Cleaning up...");
-    let _ = fs::remove_file(&temp_archive);
-
-    if !import_output.status.success() {
-        let stderr = String::from_utf8_lossy(&import_output.stderr);
-
-        // Check for permission denied errors and provide helpful message
-        let error_msg = if let Some(helpful_msg) = check_containerd_permission_error(&stderr) {
-            helpful_msg
-        } else {
-            format!("Failed to import image to containerd: {}", stderr)
-        };
+    eprintln!("📦 Building container image ({})...", image_ref);
+    let result = runtime.build_image(&BuildSpec {
+        image: image_ref,
+        containerfile: containerfile_path,
+        context: build_context_dir.clone(),
+        no_cache,
+    });
 
-        return Err(Error::Other(error_msg));
+    // Clean up the copied binary and temp directory
+    let _ = fs::remove_file(&binary_path);
+    // We don't need to clean up bn_entry_path or git_wrapper_path if using external files
+    // They are only used at build time via COPY commands
+    let _ = bn_entry_path; // suppress unused warning
+    let _ = git_wrapper_path; // suppress unused warning
+    if using_embedded {
+        let _ = fs::remove_dir_all(&build_context_dir);
     }
 
-    Ok(())
+    result
 }
 
 /// Build a container from a definition
 fn build_definition_container(
+    runtime: &dyn ContainerRuntime,
     repo_path: &Path,
     def_name: &str,
     _definition: &crate::container::ContainerDefinition,
@@ -22547,95 +26919,20 @@ fn build_definition_container(
         ))
     })?;
 
-    // Build with buildah - stream output for real-time feedback
-    eprintln!("📦 Building container image ({})...", image_ref);
-    let mut build_cmd = Command::new("buildah");
-    build_cmd
-        .arg("bud")
-        .arg("--layers") // Enable layer caching for faster rebuilds
-        .arg("--network")
-        .arg("slirp4netns") // pasta fails with large route tables (e.g. VPN split-tunnel)
-        .arg("-t")
-        .arg(&image_ref)
-        .arg("-f")
-        .arg(&containerfile_path)
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit());
-
-    if no_cache {
-        build_cmd.arg("--no-cache");
-    }
-
+    // Build - stream output for real-time feedback
     // Use repo root as build context (so COPY paths work correctly)
-    build_cmd.arg(repo_path);
-
-    let status = build_cmd.status()?;
+    eprintln!("📦 Building container image ({})...", image_ref);
+    let result = runtime.build_image(&BuildSpec {
+        image: image_ref,
+        containerfile: containerfile_path,
+        context: repo_path.to_path_buf(),
+        no_cache,
+    });
 
     // Clean up the copied binary
     let _ = fs::remove_file(&binary_path);
 
-    if !status.success() {
-        return Err(Error::Other("Build failed (see output above)".to_string()));
-    }
-
-    // Export and import to containerd
-    // Use docker-archive format (not oci-archive) because it embeds the full image
-    // reference in the manifest, allowing ctr to import it with the correct name.
-    eprintln!("📤 Exporting image to docker archive...");
-    let temp_dir = get_binnacle_temp_dir()?;
-    let temp_archive = temp_dir.join(format!("binnacle-{}.tar", def_name));
-    // Remove existing archive if present (docker-archive can't overwrite)
-    if temp_archive.exists() {
-        let _ = fs::remove_file(&temp_archive);
-    }
-    let temp_archive_str = temp_archive.to_string_lossy();
-
-    let push_output = Command::new("buildah")
-        .args([
-            "push",
-            &image_ref,
-            &format!("docker-archive:{}:{}", temp_archive_str, image_ref),
-        ])
-        .output()?;
-
-    if !push_output.status.success() {
-        return Err(Error::Other(format!(
-            "Failed to export image: {}",
-            String::from_utf8_lossy(&push_output.stderr)
-        )));
-    }
-
-    eprintln!("📥 Importing image to containerd...");
-    let mode = detect_containerd_mode()?;
-    warn_system_containerd_mode(&mode);
-    let import_output = ctr_command(&mode)
-        .args([
-            "-n",
-            "binnacle",
-            "images",
-            "import",
-            temp_archive_str.as_ref(),
-        ])
-        .output()?;
-
-    // Clean up temp file
-    eprintln!("🧹 Cleaning up...");
-    let _ = fs::remove_file(&temp_archive);
-
-    if !import_output.status.success() {
-        let stderr = String::from_utf8_lossy(&import_output.stderr);
-
-        // Check for permission denied errors and provide helpful message
-        let error_msg = if let Some(helpful_msg) = check_containerd_permission_error(&stderr) {
-            helpful_msg
-        } else {
-            format!("Failed to import image to containerd: {}", stderr)
-        };
-
-        return Err(Error::Other(error_msg));
-    }
-
-    Ok(())
+    result
 }
 
 /// Detect if a path is a git worktree and return the parent repo's .git directory.
@@ -22723,25 +27020,21 @@ pub fn container_run(
         DefinitionSource, discover_definitions, generate_image_name, resolve_definition,
     };
 
-    // Check for required tools
-    if !command_exists("ctr") {
+    // Pick the container runtime (configured or auto-detected)
+    let Some(runtime) = find_runtime(repo_path)? else {
         return Ok(ContainerRunResult {
             success: false,
             name: None,
             agent_id: None,
             agent_name: None,
-            error: Some(container_deps_missing_message()),
+            error: Some(runtime_missing_message()),
         });
-    }
-
-    // Detect containerd mode (rootless vs system)
-    let containerd_mode = detect_containerd_mode()?;
-    warn_system_containerd_mode(&containerd_mode);
+    };
 
     // Determine which container image to use
     let defs_with_source = discover_definitions(repo_path)?;
 
-    let (image_name, def_name) = if let Some(def_name) = definition {
+    let (image_name, def_name, definition) = if let Some(def_name) = definition {
         // User specified a definition - resolve it (handles conflicts)
         match resolve_definition(&defs_with_source, def_name, source_preference) {
             Ok(def_src) => {
@@ -22750,12 +27043,14 @@ pub fn container_run(
                     (
                         "localhost/binnacle-self:latest".to_string(),
                         def_name.to_string(),
+                        Some(def_src.definition),
                     )
                 } else {
                     // Use generated name for custom definitions
                     (
                         generate_image_name(repo_path, def_name)?,
                         def_name.to_string(),
+                        Some(def_src.definition),
                     )
                 }
             }
@@ -22791,11 +27086,13 @@ pub fn container_run(
                     (
                         "localhost/binnacle-self:latest".to_string(),
                         def_src.definition.name.clone(),
+                        Some(def_src.definition.clone()),
                     )
                 } else {
                     (
                         generate_image_name(repo_path, &def_src.definition.name)?,
                         def_src.definition.name.clone(),
+                        Some(def_src.definition.clone()),
                     )
                 }
             }
@@ -22804,21 +27101,24 @@ pub fn container_run(
                 (
                     "localhost/binnacle-self:latest".to_string(),
                     "binnacle".to_string(),
+                    None,
                 )
             }
         }
     };
 
-    // Check if the worker image exists in containerd
-    if !container_image_exists(&image_name)? {
+    // Check if the worker image exists in the runtime
+    if !runtime.image_exists(&image_name)? {
         return Ok(ContainerRunResult {
             success: false,
             name: None,
             agent_id: None,
             agent_name: None,
             error: Some(format!(
-                "Image '{}' not found in containerd.\n\nRun 'bn container build {}' first to build the image.",
-                image_name, def_name
+                "Image '{}' not found in {}.\n\nRun 'bn container build {}' first to build the image.",
+                image_name,
+                runtime.kind(),
+                def_name
             )),
         });
     }
@@ -22860,89 +27160,28 @@ pub fn container_run(
     let container_name =
         name.unwrap_or_else(|| format!("binnacle-{}-{}", agent_type, &agent_id[3..]));
 
-    // Build ctr run command
-    let mut args = vec![
-        "-n".to_string(),
-        "binnacle".to_string(),
-        "run".to_string(),
-        "--rm".to_string(),
-    ];
-
     // Check if we have a real terminal
+    // Without a TTY the container still inherits stdio but won't get a PTY, so
+    // interactive programs may not work correctly. This is acceptable for non-TTY contexts.
     use std::io::IsTerminal;
     let is_tty = std::io::stdin().is_terminal();
 
-    if is_tty {
-        args.push("--tty".to_string());
-    }
-    // Note: Without --tty, ctr still inherits stdio but won't allocate a PTY.
-    // This means the container can still output to stdout/stderr, but interactive
-    // programs may not work correctly. This is acceptable for non-TTY contexts.
-
-    // Handle networking based on containerd mode
-    match &containerd_mode {
-        ContainerdMode::Rootless {
-            child_pid,
-            resolv_conf_path,
-            ..
-        } if *child_pid > 0 => {
-            // Rootless mode: disable cgroups (permission issues) and share network namespace
-            args.push("--cgroup".to_string());
-            args.push(String::new()); // Empty string disables cgroup
-
-            // Share network namespace with rootlesskit for slirp4netns connectivity
-            args.push("--with-ns".to_string());
-            args.push(format!("network:/proc/{}/ns/net", child_pid));
-
-            // Mount resolv.conf for DNS resolution
-            // Priority: rootlesskit state dir > namespace's /etc/resolv.conf
-            // When ctr runs via nsenter inside the rootlesskit namespace,
-            // /etc/resolv.conf resolves to the namespace's copy (with slirp4netns/pasta DNS).
-            let resolv_src = if Path::new(resolv_conf_path).exists() {
-                Some(resolv_conf_path.as_str())
-            } else {
-                // rootlesskit with slirp4netns/pasta creates resolv.conf inside the
-                // namespace via --copy-up=/etc. Since ctr runs inside the namespace
-                // (via nsenter), /etc/resolv.conf points to the namespace's copy.
-                Some("/etc/resolv.conf")
-            };
-            if let Some(resolv) = resolv_src {
-                args.push("--mount".to_string());
-                args.push(format!(
-                    "type=bind,src={},dst=/etc/resolv.conf,options=rbind:ro",
-                    resolv
-                ));
-            }
-            // In rootless mode with user namespaces, container UID 0 maps to host user
-            // So we don't need --user flag - running as root inside = host user outside
-        }
-        _ => {
-            // SECURITY: Host networking removes network namespace isolation.
-            // Required for AI agent API calls (OpenAI, Anthropic, etc.) and package installs.
-            // The container can access all host network interfaces including localhost services.
-            // See bn-a3a2 for planned rootless support with more restrictive networking.
-            args.push("--net-host".to_string());
-
-            // Run as host user's UID/GID to preserve file ownership in mounted workspace
-            // Container uses nss_wrapper to provide user identity for Node.js, git, etc.
-            // SECURITY: User mapping is mandatory to prevent running as root
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::MetadataExt;
-                let meta = fs::metadata(&worktree_abs).map_err(|e| {
-                    Error::Other(format!(
-                        "Failed to read worktree metadata for user mapping: {}. \
-                        Container cannot run without user mapping.",
-                        e
-                    ))
-                })?;
-                let uid = meta.uid();
-                let gid = meta.gid();
-                args.push("--user".to_string());
-                args.push(format!("{}:{}", uid, gid));
-            }
-        }
-    }
+    // Host user owning the workspace, to preserve file ownership in mounts
+    // SECURITY: User mapping is mandatory to prevent running as root
+    #[cfg(unix)]
+    let user = {
+        use std::os::unix::fs::MetadataExt;
+        let meta = fs::metadata(&worktree_abs).map_err(|e| {
+            Error::Other(format!(
+                "Failed to read worktree metadata for user mapping: {}. \
+                Container cannot run without user mapping.",
+                e
+            ))
+        })?;
+        Some((meta.uid(), meta.gid()))
+    };
+    #[cfg(not(unix))]
+    let user = None;
 
     #[cfg(not(unix))]
     {
@@ -22957,37 +27196,26 @@ pub fn container_run(
         });
     }
 
-    // Add resource limits if specified
-    if let Some(cpu_limit) = cpus {
-        args.push("--cpus".to_string());
-        args.push(cpu_limit.to_string());
-    }
-
-    if let Some(mem_limit) = memory {
-        // Parse memory string (e.g., "512m", "1g", "2048m") to bytes
-        let bytes = parse_memory_limit(mem_limit)?;
-        args.push("--memory-limit".to_string());
-        args.push(bytes.to_string());
-    }
-
-    // Add mounts
-    args.push("--mount".to_string());
-    let workspace_mount_options = if readonly_workspace {
-        "rbind:ro"
-    } else {
-        "rbind:rw"
-    };
-    args.push(format!(
-        "type=bind,src={},dst=/workspace,options={}",
-        worktree_abs.display(),
-        workspace_mount_options
-    ));
+    // Resource limits: command-line flags override the definition's defaults
+    let defaults = definition.as_ref().and_then(|d| d.defaults.as_ref());
+    let cpus = cpus.or_else(|| defaults.and_then(|d| d.cpus).map(f64::from));
+    let memory_bytes = memory
+        .or_else(|| defaults.and_then(|d| d.memory.as_deref()))
+        .map(parse_memory_limit)
+        .transpose()?;
 
-    args.push("--mount".to_string());
-    args.push(format!(
-        "type=bind,src={},dst=/binnacle,options=rbind:rw",
-        binnacle_data.display()
-    ));
+    // Mounts: workspace, binnacle data, and the definition's own mounts.
+    // The definition may also make the workspace read-only.
+    let mut mounts = definition_mounts(
+        definition.as_ref(),
+        repo_path,
+        &worktree_abs,
+        readonly_workspace,
+        &binnacle_data,
+    )?;
+    let readonly_workspace = mounts
+        .iter()
+        .any(|m| m.target == "/workspace" && m.read_only);
 
     // Mount the archive directory if configured (to persist .bng graph snapshots)
     // The archive directory may be outside the standard binnacle data path,
@@ -23002,12 +27230,11 @@ pub fn container_run(
 
         // Only mount if the directory now exists
         if archive_dir.exists() {
-            args.push("--mount".to_string());
-            args.push(format!(
-                "type=bind,src={},dst={},options=rbind:rw",
-                archive_dir.display(),
-                archive_dir.display()
-            ));
+            mounts.push(RunMount {
+                target: archive_dir.display().to_string(),
+                source: archive_dir,
+                read_only: false,
+            });
         }
     }
 
@@ -23021,27 +27248,21 @@ pub fn container_run(
     // object store. Mounting read-only would prevent all git write operations (commit,
     // checkout, etc.). The container is trusted to work only on its designated worktree.
     if let Some(parent_git_dir) = detect_worktree_parent_git(&worktree_abs) {
-        args.push("--mount".to_string());
-        args.push(format!(
-            "type=bind,src={},dst={},options=rbind:rw",
-            parent_git_dir.display(),
-            parent_git_dir.display()
-        ));
+        mounts.push(RunMount {
+            target: parent_git_dir.display().to_string(),
+            source: parent_git_dir,
+            read_only: false,
+        });
     }
 
     // Add environment variables
-    args.push("--env".to_string());
-    args.push(format!("BN_AGENT_TYPE={}", agent_type));
-
-    args.push("--env".to_string());
-    args.push("BN_CONTAINER_MODE=true".to_string());
-
-    args.push("--env".to_string());
-    args.push(format!("BN_MERGE_TARGET={}", merge_target));
-
-    // Tell bn where to find the database (mounted at /binnacle)
-    args.push("--env".to_string());
-    args.push("BN_DATA_DIR=/binnacle".to_string());
+    let mut env = vec![
+        ("BN_AGENT_TYPE".to_string(), agent_type.to_string()),
+        ("BN_CONTAINER_MODE".to_string(), "true".to_string()),
+        ("BN_MERGE_TARGET".to_string(), merge_target.to_string()),
+        // Tell bn where to find the database (mounted at /binnacle)
+        ("BN_DATA_DIR".to_string(), "/binnacle".to_string()),
+    ];
 
     // Compute storage hash on host and pass to container.
     // IMPORTANT: Use find_git_root to resolve worktrees to main repo before hashing.
@@ -23053,30 +27274,25 @@ pub fn container_run(
     hasher.update(repo_root.to_string_lossy().as_bytes());
     let hash = hasher.finalize();
     let storage_hash = &format!("{:x}", hash)[..12];
-    args.push("--env".to_string());
-    args.push(format!("BN_STORAGE_HASH={}", storage_hash));
+    env.push(("BN_STORAGE_HASH".to_string(), storage_hash.to_string()));
 
     if no_merge {
-        args.push("--env".to_string());
-        args.push("BN_NO_MERGE=true".to_string());
+        env.push(("BN_NO_MERGE".to_string(), "true".to_string()));
     }
 
     if readonly_workspace {
-        args.push("--env".to_string());
-        args.push("BN_READONLY_WORKSPACE=true".to_string());
+        env.push(("BN_READONLY_WORKSPACE".to_string(), "true".to_string()));
     }
 
     // Pass pre-assigned agent ID and name
-    args.push("--env".to_string());
-    args.push(format!("BN_AGENT_ID={}", agent_id));
+    env.push(("BN_AGENT_ID".to_string(), agent_id.to_string()));
 
-    args.push("--env".to_string());
-    args.push(format!("BN_AGENT_NAME={}", agent_name));
+    env.push(("BN_AGENT_NAME".to_string(), agent_name.to_string()));
 
     // Create agent record before starting container
     // This ensures the GUI shows the agent immediately
     if let Ok(mut storage) = Storage::open(repo_path) {
-        let parsed_agent_type = parse_agent_type(agent_type).unwrap_or(AgentType::Worker);
+        let parsed_agent_type = crate::agents::policy::agent_type_for_definition(agent_type);
         let mut agent = Agent::new_with_id(
             agent_id.clone(),
             0, // PID will be set when container's bn orient runs
@@ -23092,15 +27308,13 @@ pub fn container_run(
 
     // Pass custom prompt if provided
     if let Some(custom_prompt) = prompt {
-        args.push("--env".to_string());
-        args.push(format!("BN_INITIAL_PROMPT={}", custom_prompt));
+        env.push(("BN_INITIAL_PROMPT".to_string(), custom_prompt.to_string()));
     }
 
     // Pass through GitHub tokens if available
     // GH_TOKEN is a common convention for GitHub CLI and other tools
     if let Ok(token) = std::env::var("GH_TOKEN") {
-        args.push("--env".to_string());
-        args.push(format!("GH_TOKEN={}", token));
+        env.push(("GH_TOKEN".to_string(), token.to_string()));
     }
     // COPILOT_GITHUB_TOKEN: Use precedence resolution (env > session > system)
     // This allows tokens configured via `bn session init --token` to be injected
@@ -23108,8 +27322,7 @@ pub fn container_run(
     if let Ok(storage) = Storage::open(repo_path) {
         if let Ok(state) = resolve_state(&storage) {
             if let Some(token) = state.token() {
-                args.push("--env".to_string());
-                args.push(format!("COPILOT_GITHUB_TOKEN={}", token));
+                env.push(("COPILOT_GITHUB_TOKEN".to_string(), token.to_string()));
             }
         }
     }
@@ -23185,51 +27398,52 @@ pub fn container_run(
         }
     };
 
-    args.push("--env".to_string());
-    args.push(format!("GIT_AUTHOR_NAME={}", effective_name));
-    args.push("--env".to_string());
-    args.push(format!("GIT_COMMITTER_NAME={}", effective_name));
-    args.push("--env".to_string());
-    args.push(format!("GIT_AUTHOR_EMAIL={}", effective_email));
-    args.push("--env".to_string());
-    args.push(format!("GIT_COMMITTER_EMAIL={}", effective_email));
+    env.push(("GIT_AUTHOR_NAME".to_string(), effective_name.to_string()));
+    env.push(("GIT_COMMITTER_NAME".to_string(), effective_name.to_string()));
+    env.push(("GIT_AUTHOR_EMAIL".to_string(), effective_email.to_string()));
+    env.push((
+        "GIT_COMMITTER_EMAIL".to_string(),
+        effective_email.to_string(),
+    ));
 
     // Set flag to indicate when using anonymous identity (skips Co-authored-by trailer)
     if using_anonymous_identity {
-        args.push("--env".to_string());
-        args.push("BINNACLE_ANONYMOUS_IDENTITY=1".to_string());
+        env.push(("BINNACLE_ANONYMOUS_IDENTITY".to_string(), "1".to_string()));
     }
 
     // Pass through timezone from TZ env var or /etc/timezone
     // This ensures commit timestamps use the host's timezone
     if let Ok(tz) = std::env::var("TZ") {
         if !tz.is_empty() {
-            args.push("--env".to_string());
-            args.push(format!("TZ={}", tz));
+            env.push(("TZ".to_string(), tz.to_string()));
         }
     } else if let Ok(contents) = fs::read_to_string("/etc/timezone") {
         let tz = contents.trim();
         if !tz.is_empty() {
-            args.push("--env".to_string());
-            args.push(format!("TZ={}", tz));
-        }
-    }
-
-    // Add image and container name
-    args.push(image_name.clone());
-    args.push(container_name.clone());
-
-    // For shell mode, override the entrypoint to run bash with "shell" argument
-    if shell {
-        args.push("/entrypoint.sh".to_string());
-        args.push("shell".to_string());
-    }
+            env.push(("TZ".to_string(), tz.to_string()));
+        }
+    }
+
+    let spec = RunSpec {
+        image: image_name,
+        name: container_name.clone(),
+        tty: is_tty,
+        mounts,
+        env,
+        cpus,
+        memory_bytes,
+        user,
+        // For shell mode, override the entrypoint to run bash with "shell" argument
+        command: if shell {
+            vec!["/entrypoint.sh".to_string(), "shell".to_string()]
+        } else {
+            Vec::new()
+        },
+    };
 
     // Inherit stdio so container output is visible.
-    // With --tty (TTY mode), ctr allocates a PTY for interactive programs.
-    // Without --tty (non-TTY mode), output still streams but interactive programs may not work.
-    let status = ctr_command(&containerd_mode)
-        .args(&args)
+    let status = runtime
+        .run_command(&spec)?
         .stdin(Stdio::inherit())
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
@@ -23257,153 +27471,58 @@ pub fn container_run(
 /// Default timeout for graceful container stop (in seconds).
 const CONTAINER_STOP_TIMEOUT_SECS: u64 = 15;
 
-/// Gracefully stop a container's task: send SIGTERM, wait for timeout, then SIGKILL if needed.
-/// Returns true if the task was stopped (either gracefully or forced).
-fn graceful_task_kill(
-    containerd_mode: &ContainerdMode,
-    container: &str,
-    timeout_secs: u64,
-) -> bool {
-    use std::thread;
-    use std::time::{Duration, Instant};
-
-    // Check if task is running first
-    let is_running = || {
-        let output = ctr_command(containerd_mode)
-            .args(["-n", "binnacle", "tasks", "list"])
-            .output()
-            .ok()?;
-
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            // Task list has header, then task_id in first column of each line
-            Some(
-                stdout
-                    .lines()
-                    .skip(1)
-                    .any(|line| line.split_whitespace().next() == Some(container)),
-            )
-        } else {
-            None
-        }
-    };
-
-    // If task isn't running, nothing to kill
-    if !is_running().unwrap_or(false) {
-        return true;
-    }
-
-    // Send SIGTERM (signal 15)
-    let term_result = ctr_command(containerd_mode)
-        .args([
-            "-n", "binnacle", "tasks", "kill", "--signal", "15", container,
-        ])
-        .output();
-
-    if term_result.is_err() {
-        return false;
-    }
-
-    // Wait for task to exit, checking every 500ms
-    let check_interval = Duration::from_millis(500);
-    let timeout = Duration::from_secs(timeout_secs);
-    let start = Instant::now();
-
-    while start.elapsed() < timeout {
-        if !is_running().unwrap_or(true) {
-            return true; // Task exited gracefully
-        }
-        thread::sleep(check_interval);
-    }
-
-    // Task still running after timeout - send SIGKILL (signal 9)
-    let _ = ctr_command(containerd_mode)
-        .args([
-            "-n", "binnacle", "tasks", "kill", "--signal", "9", container,
-        ])
-        .output();
-
-    // Give it a moment to die
-    thread::sleep(Duration::from_millis(100));
-
-    true
-}
-
 /// Stop binnacle containers.
-pub fn container_stop(name: Option<String>, all: bool) -> Result<ContainerStopResult> {
-    // Check for required tools
-    if !command_exists("ctr") {
+pub fn container_stop(
+    repo_path: &Path,
+    name: Option<String>,
+    all: bool,
+) -> Result<ContainerStopResult> {
+    let Some(runtime) = find_runtime(repo_path)? else {
         return Ok(ContainerStopResult {
             success: false,
             stopped_count: 0,
             stopped: vec![],
-            error: Some(container_deps_missing_message()),
+            error: Some(runtime_missing_message()),
         });
-    }
-
-    // Detect containerd mode (rootless vs system)
-    let containerd_mode = detect_containerd_mode()?;
-    warn_system_containerd_mode(&containerd_mode);
+    };
+    let timeout = std::time::Duration::from_secs(CONTAINER_STOP_TIMEOUT_SECS);
 
     let mut stopped = Vec::new();
 
     if all {
-        // Get list of containers in binnacle namespace
-        let output = ctr_command(&containerd_mode)
-            .args(["-n", "binnacle", "containers", "list", "-q"])
-            .output()?;
-
-        if !output.status.success() {
-            return Ok(ContainerStopResult {
-                success: false,
-                stopped_count: 0,
-                stopped: vec![],
-                error: Some(String::from_utf8_lossy(&output.stderr).to_string()),
-            });
-        }
-
-        let containers: Vec<String> = String::from_utf8_lossy(&output.stdout)
-            .lines()
-            .map(|s| s.to_string())
-            .filter(|s| !s.is_empty())
-            .collect();
+        let containers = match runtime.list() {
+            Ok(containers) => containers,
+            Err(e) => {
+                return Ok(ContainerStopResult {
+                    success: false,
+                    stopped_count: 0,
+                    stopped: vec![],
+                    error: Some(e.to_string()),
+                });
+            }
+        };
 
         for container in containers {
-            // Gracefully kill task (SIGTERM, wait 15s, then SIGKILL if needed)
-            graceful_task_kill(&containerd_mode, &container, CONTAINER_STOP_TIMEOUT_SECS);
+            // Gracefully stop (SIGTERM, wait 15s, then SIGKILL if needed)
+            let _ = runtime.stop(&container.name, timeout);
 
-            // Remove container
-            let rm_output = ctr_command(&containerd_mode)
-                .args(["-n", "binnacle", "containers", "rm", &container])
-                .output()?;
-
-            if rm_output.status.success() {
-                stopped.push(container);
+            if runtime.remove(&container.name).is_ok() {
+                stopped.push(container.name);
             }
         }
     } else if let Some(container_name) = name {
-        // Gracefully kill task (SIGTERM, wait 15s, then SIGKILL if needed)
-        graceful_task_kill(
-            &containerd_mode,
-            &container_name,
-            CONTAINER_STOP_TIMEOUT_SECS,
-        );
+        // Gracefully stop (SIGTERM, wait 15s, then SIGKILL if needed)
+        let _ = runtime.stop(&container_name, timeout);
 
-        // Remove container
-        let rm_output = ctr_command(&containerd_mode)
-            .args(["-n", "binnacle", "containers", "rm", &container_name])
-            .output()?;
-
-        if rm_output.status.success() {
-            stopped.push(container_name);
-        } else {
+        if let Err(e) = runtime.remove(&container_name) {
             return Ok(ContainerStopResult {
                 success: false,
                 stopped_count: 0,
                 stopped: vec![],
-                error: Some(String::from_utf8_lossy(&rm_output.stderr).to_string()),
+                error: Some(e.to_string()),
             });
         }
+        stopped.push(container_name);
     } else {
         return Ok(ContainerStopResult {
             success: false,
@@ -23422,84 +27541,37 @@ pub fn container_stop(name: Option<String>, all: bool) -> Result<ContainerStopRe
 }
 
 /// List binnacle containers.
-pub fn container_list(all: bool, _quiet: bool) -> Result<ContainerListResult> {
-    // Check for required tools
-    if !command_exists("ctr") {
-        return Ok(ContainerListResult {
-            success: false,
-            containers: vec![],
-            error: Some(container_deps_missing_message()),
-        });
-    }
-
-    // Detect containerd mode (rootless vs system)
-    let containerd_mode = detect_containerd_mode()?;
-    warn_system_containerd_mode(&containerd_mode);
-
-    // Get list of containers
-    let output = ctr_command(&containerd_mode)
-        .args(["-n", "binnacle", "containers", "list"])
-        .output()?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-
-        // Check for permission denied errors and provide helpful message
-        let error_msg = if let Some(helpful_msg) = check_containerd_permission_error(&stderr) {
-            helpful_msg
-        } else {
-            stderr.to_string()
-        };
-
+pub fn container_list(repo_path: &Path, all: bool, _quiet: bool) -> Result<ContainerListResult> {
+    let Some(runtime) = find_runtime(repo_path)? else {
         return Ok(ContainerListResult {
             success: false,
             containers: vec![],
-            error: Some(error_msg),
+            error: Some(runtime_missing_message()),
         });
-    }
-
-    // Get running tasks to determine status
-    let tasks_output = ctr_command(&containerd_mode)
-        .args(["-n", "binnacle", "tasks", "list"])
-        .output()?;
-
-    let running_tasks: std::collections::HashSet<String> = if tasks_output.status.success() {
-        String::from_utf8_lossy(&tasks_output.stdout)
-            .lines()
-            .skip(1) // Skip header
-            .filter_map(|line| line.split_whitespace().next())
-            .map(|s| s.to_string())
-            .collect()
-    } else {
-        std::collections::HashSet::new()
     };
 
-    // Parse container list output
-    let mut containers = Vec::new();
-    for line in String::from_utf8_lossy(&output.stdout).lines().skip(1) {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() >= 2 {
-            let name = parts[0].to_string();
-            let image = parts[1].to_string();
-            let status = if running_tasks.contains(&name) {
-                "running".to_string()
-            } else {
-                "stopped".to_string()
-            };
-
-            // Skip stopped containers if not showing all
-            if !all && status == "stopped" {
-                continue;
-            }
-
-            containers.push(ContainerInfo {
-                name,
-                status,
-                image,
-                created: None,
+    let listed = match runtime.list() {
+        Ok(listed) => listed,
+        Err(e) => {
+            return Ok(ContainerListResult {
+                success: false,
+                containers: vec![],
+                error: Some(e.to_string()),
             });
         }
-    }
+    };
+
+    let containers = listed
+        .into_iter()
+        // Skip stopped containers if not showing all
+        .filter(|c| all || c.running)
+        .map(|c| ContainerInfo {
+            name: c.name,
+            status: if c.running { "running" } else { "stopped" }.to_string(),
+            image: c.image,
+            created: None,
+        })
+        .collect();
 
     Ok(ContainerListResult {
         success: true,
@@ -23963,6 +28035,7 @@ pub fn sync(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::container::runtime::ctr_command;
     use crate::test_utils::TestEnv;
     use serial_test::serial;
 
@@ -25851,6 +29924,25 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_parse_blame_target() {
+        assert_eq!(
+            parse_blame_target("src/lib.rs").unwrap(),
+            ("src/lib.rs", None)
+        );
+        assert_eq!(
+            parse_blame_target("src/lib.rs:12").unwrap(),
+            ("src/lib.rs", Some((12, 12)))
+        );
+        assert_eq!(
+            parse_blame_target("src/lib.rs:10-20").unwrap(),
+            ("src/lib.rs", Some((10, 20)))
+        );
+        // A suffix that isn't a line spec stays part of the path
+        assert_eq!(parse_blame_target("a:b.txt").unwrap(), ("a:b.txt", None));
+        assert!(parse_blame_target("src/lib.rs:20-10").is_err());
+    }
+
     #[test]
 
     fn test_git_commit_exists_valid() {
@@ -26065,6 +30157,9 @@ mod tests {
             ".".to_string(),
             None,
             None,
+            None,
+            vec![],
+            None,
         )
         .unwrap();
 
@@ -26073,6 +30168,43 @@ mod tests {
         assert_eq!(result.stats.total_tests, 1);
     }
 
+    #[test]
+    fn test_test_update_and_delete() {
+        let temp = setup_isolated();
+        let created = test_create(
+            temp.path(),
+            "Unit".to_string(),
+            "cargo test".to_string(),
+            ".".to_string(),
+            None,
+            None,
+            None,
+            vec![],
+            None,
+        )
+        .unwrap();
+
+        let updated = test_update(
+            temp.path(),
+            &created.id,
+            Some("Unit tests".to_string()),
+            None,
+            Some("crates/core".to_string()),
+        )
+        .unwrap();
+        assert_eq!(updated.updated_fields, vec!["name", "working_dir"]);
+
+        let test = test_show(temp.path(), &created.id).unwrap();
+        assert_eq!(test.name, "Unit tests");
+        assert_eq!(test.command, "cargo test");
+        assert_eq!(test.working_dir, "crates/core");
+
+        assert!(test_update(temp.path(), &created.id, None, Some(" ".to_string()), None).is_err());
+
+        test_delete(temp.path(), &created.id).unwrap();
+        assert!(test_show(temp.path(), &created.id).is_err());
+    }
+
     #[test]
 
     fn test_doctor_bug_stats() {
@@ -27149,7 +31281,7 @@ mod tests {
         // Orient again with purpose - should update the existing agent
         let _result = orient(
             temp.path(),
-            "planner",
+            "worker",
             false,
             Some("update-agent".to_string()),
             Some("PRD Generator".to_string()),
@@ -31272,6 +35404,31 @@ mod tests {
         assert!(err_msg.contains("already has a parent"));
     }
 
+    // === Message Tests ===
+
+    #[test]
+    fn test_open_questions_excludes_answered() {
+        let temp = setup_isolated();
+        let asked = ask(temp.path(), "Which port?", None, false, None).unwrap();
+        ask(temp.path(), "Which host?", None, false, None).unwrap();
+        msg_send(temp.path(), "human", "FYI").unwrap();
+
+        let storage = Storage::open(temp.path()).unwrap();
+        assert_eq!(open_questions(&storage).unwrap().len(), 2);
+        drop(storage);
+
+        let reply = msg_reply(temp.path(), &asked.question.id, "8080").unwrap();
+        assert_eq!(
+            reply.message.reply_to.as_deref(),
+            Some(asked.question.id.as_str())
+        );
+
+        let storage = Storage::open(temp.path()).unwrap();
+        let open = open_questions(&storage).unwrap();
+        assert_eq!(open.len(), 1);
+        assert_eq!(open[0].body, "Which host?");
+    }
+
     // === Goodbye Tests ===
 
     #[test]
@@ -31280,7 +35437,7 @@ mod tests {
         let temp = setup_isolated();
 
         // Goodbye without orient should still work (unregistered agent)
-        let result = goodbye(temp.path(), None, false);
+        let result = goodbye(temp.path(), None, Vec::new(), false);
         assert!(result.is_ok());
 
         let goodbye_result = result.unwrap();
@@ -31295,7 +35452,12 @@ mod tests {
     fn test_goodbye_with_reason() {
         let temp = setup_isolated();
 
-        let result = goodbye(temp.path(), Some("Task completed".to_string()), false);
+        let result = goodbye(
+            temp.path(),
+            Some("Task completed".to_string()),
+            Vec::new(),
+            false,
+        );
         assert!(result.is_ok());
 
         let goodbye_result = result.unwrap();
@@ -31307,7 +35469,7 @@ mod tests {
     fn test_goodbye_output_format() {
         let temp = setup_isolated();
 
-        let result = goodbye(temp.path(), Some("Done".to_string()), false).unwrap();
+        let result = goodbye(temp.path(), Some("Done".to_string()), Vec::new(), false).unwrap();
 
         // Test JSON output
         let json = result.to_json();
@@ -31792,7 +35954,19 @@ mod tests {
         // First orient as worker (dry_run: false to test session state writing)
         orient(temp.path(), "worker", false, None, None, false).unwrap();
 
-        // Second orient as planner
+        // A registered agent can't change type
+        assert!(matches!(
+            orient(temp.path(), "planner", false, None, None, false),
+            Err(Error::PermissionDenied(_))
+        ));
+
+        // Once it leaves, the next orient registers a planner
+        {
+            let mut storage = Storage::open(temp.path()).unwrap();
+            for agent in storage.list_agents(None).unwrap() {
+                storage.remove_agent(agent.pid).unwrap();
+            }
+        }
         orient(temp.path(), "planner", false, None, None, false).unwrap();
 
         // Verify session state reflects latest orient
@@ -12,6 +12,10 @@
 //! - `editor` - Preferred editor command
 //! - `output-format` - "json" or "human"
 //! - `default-priority` - Default task priority (0-4)
+//! - `webhook` blocks - Outbound webhook subscriptions (session server)
+//! - `supervisor` block - Agent pool supervisor settings (`bn agent supervise`)
+//! - `budgets` block - Model usage budgets per milestone, queue or agent type
+//! - `report` blocks - Markdown templates for `bn report pr` / `bn report changelog`
 //!
 //! ## state.kdl - Runtime state (machine-specific, contains secrets)
 //!
@@ -24,6 +28,7 @@
 //! - `token-validated-at` - ISO 8601 timestamp of last token validation
 //! - `last-copilot-version` - Last known Copilot CLI version
 //! - `serve` block - Session server state (pid, port, host, etc.)
+//! - `gui-token` blocks - Hashed bearer tokens for the GUI/session server
 //!
 //! ## Security
 //!
@@ -44,6 +49,10 @@ pub use resolver::{
     COPILOT_GITHUB_TOKEN_ENV, ConfigOverrides, Resolved, ResolvedConfig, ResolvedSettings,
     ResolvedState, ValueSource, resolve_config, resolve_state, resolve_state_with_override,
 };
-pub use schema::{BinnacleConfig, BinnacleState, OutputFormat, ServeState};
+pub use schema::{
+    AgentTimeouts, BinnacleConfig, BinnacleState, Budget, BudgetConfig, BudgetLimits, BudgetScope,
+    GuiRole, GuiToken, OutputFormat, ReportTemplate, ServeState, SupervisorConfig, WebhookConfig,
+    WebhookFilter,
+};
 #[cfg(unix)]
 pub use schema::{CONFIG_FILE_MODE, STATE_FILE_MODE};
@@ -209,6 +209,9 @@ pub struct ServeState {
 
     /// Optional upstream hub URL (e.g., wss://hub.example.com/sessions)
     pub upstream: Option<String>,
+
+    /// TLS certificate fingerprint (`sha256:<hex>`) when serving over TLS
+    pub tls_fingerprint: Option<String>,
 }
 
 impl ServeState {
@@ -224,6 +227,7 @@ impl ServeState {
             branch,
             last_heartbeat: now,
             upstream: None,
+            tls_fingerprint: None,
         }
     }
 
@@ -246,9 +250,21 @@ impl ServeState {
             branch,
             last_heartbeat: now,
             upstream,
+            tls_fingerprint: None,
         }
     }
 
+    /// Record the TLS certificate fingerprint the server is using.
+    pub fn with_tls_fingerprint(mut self, fingerprint: Option<String>) -> Self {
+        self.tls_fingerprint = fingerprint;
+        self
+    }
+
+    /// Whether the server is serving over TLS.
+    pub fn is_tls(&self) -> bool {
+        self.tls_fingerprint.is_some()
+    }
+
     /// Update the heartbeat timestamp to now.
     pub fn touch_heartbeat(&mut self) {
         self.last_heartbeat = Utc::now();
@@ -327,6 +343,13 @@ impl ServeState {
                 .and_then(|e| e.value().as_string().map(|s| s.to_string()))
         });
 
+        // Optional TLS fingerprint
+        let tls_fingerprint = children.get("tls-fingerprint").and_then(|n| {
+            n.entries()
+                .first()
+                .and_then(|e| e.value().as_string().map(|s| s.to_string()))
+        });
+
         Some(Self {
             pid,
             port,
@@ -336,6 +359,7 @@ impl ServeState {
             branch,
             last_heartbeat,
             upstream,
+            tls_fingerprint,
         })
     }
 
@@ -390,11 +414,879 @@ impl ServeState {
             children.nodes_mut().push(upstream_node);
         }
 
+        // tls-fingerprint (optional)
+        if let Some(ref fingerprint) = self.tls_fingerprint {
+            let mut tls_node = KdlNode::new("tls-fingerprint");
+            tls_node.push(KdlEntry::new(KdlValue::String(fingerprint.clone())));
+            children.nodes_mut().push(tls_node);
+        }
+
         node.set_children(children);
         node
     }
 }
 
+/// Access role granted by a GUI bearer token.
+///
+/// Roles are ordered: `Viewer < Editor < Admin`. A token satisfies a
+/// requirement if its role is at least the required role.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GuiRole {
+    /// Read-only access (GET endpoints, WebSocket subscriptions, read commands)
+    Viewer,
+    /// Read and write access to graph entities
+    Editor,
+    /// Full access, including destructive operations and agent control
+    Admin,
+}
+
+impl GuiRole {
+    /// Parse from string, case-insensitive.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "viewer" => Some(GuiRole::Viewer),
+            "editor" => Some(GuiRole::Editor),
+            "admin" => Some(GuiRole::Admin),
+            _ => None,
+        }
+    }
+
+    /// Convert to string representation.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GuiRole::Viewer => "viewer",
+            GuiRole::Editor => "editor",
+            GuiRole::Admin => "admin",
+        }
+    }
+
+    /// Check whether this role satisfies the required role.
+    pub fn allows(&self, required: GuiRole) -> bool {
+        *self >= required
+    }
+}
+
+impl std::fmt::Display for GuiRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A bearer token for the GUI/session server, stored in state.kdl.
+///
+/// Only the SHA-256 hash of the secret is persisted; the secret itself is
+/// shown once when the token is created.
+///
+/// # KDL Schema
+///
+/// ```kdl
+/// gui-token "gtk-1a2b3c4d" {
+///   role "editor"
+///   hash "9f86d081884c7d659a2feaa0c55ad015..."
+///   created-at "2026-01-31T10:00:00Z"
+///   name "ci-bot"
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuiToken {
+    /// Public token identifier (recorded in audit logs)
+    pub id: String,
+
+    /// Optional human-readable label
+    pub name: Option<String>,
+
+    /// Role granted by this token
+    pub role: GuiRole,
+
+    /// Hex-encoded SHA-256 hash of the secret
+    pub hash: String,
+
+    /// Timestamp when the token was created
+    pub created_at: DateTime<Utc>,
+}
+
+impl GuiToken {
+    /// Hash a token secret for storage and comparison.
+    pub fn hash_secret(secret: &str) -> String {
+        use sha2::{Digest, Sha256};
+        let mut hasher = Sha256::new();
+        hasher.update(secret.as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Check whether the given secret matches this token.
+    pub fn matches(&self, secret: &str) -> bool {
+        Self::hash_secret(secret) == self.hash
+    }
+
+    /// Parse a token from a `gui-token` KDL node.
+    pub fn from_kdl_node(node: &KdlNode) -> Option<Self> {
+        let id = node.entries().first()?.value().as_string()?.to_string();
+        let children = node.children()?;
+
+        let child_str = |name: &str| -> Option<String> {
+            children
+                .get(name)
+                .and_then(|n| n.entries().first())
+                .and_then(|e| e.value().as_string())
+                .map(|s| s.to_string())
+        };
+
+        let role = GuiRole::parse(&child_str("role")?)?;
+        let hash = child_str("hash")?;
+        let created_at = child_str("created-at")?.parse::<DateTime<Utc>>().ok()?;
+        let name = child_str("name");
+
+        Some(Self {
+            id,
+            name,
+            role,
+            hash,
+            created_at,
+        })
+    }
+
+    /// Convert the token to a `gui-token` KDL node.
+    pub fn to_kdl_node(&self) -> KdlNode {
+        let mut node = KdlNode::new("gui-token");
+        node.push(KdlEntry::new(KdlValue::String(self.id.clone())));
+        let mut children = KdlDocument::new();
+
+        let mut role_node = KdlNode::new("role");
+        role_node.push(KdlEntry::new(KdlValue::String(self.role.to_string())));
+        children.nodes_mut().push(role_node);
+
+        let mut hash_node = KdlNode::new("hash");
+        hash_node.push(KdlEntry::new(KdlValue::String(self.hash.clone())));
+        children.nodes_mut().push(hash_node);
+
+        let mut created_node = KdlNode::new("created-at");
+        created_node.push(KdlEntry::new(KdlValue::String(
+            self.created_at.to_rfc3339(),
+        )));
+        children.nodes_mut().push(created_node);
+
+        if let Some(ref name) = self.name {
+            let mut name_node = KdlNode::new("name");
+            name_node.push(KdlEntry::new(KdlValue::String(name.clone())));
+            children.nodes_mut().push(name_node);
+        }
+
+        node.set_children(children);
+        node
+    }
+}
+
+/// Default number of delivery attempts for a webhook.
+pub const DEFAULT_WEBHOOK_MAX_ATTEMPTS: u32 = 5;
+
+/// Default delay before the first webhook retry, in milliseconds.
+pub const DEFAULT_WEBHOOK_BACKOFF_MS: u64 = 1000;
+
+/// Event filter for a webhook subscription.
+///
+/// Every criterion that is set must match; values within a list are
+/// alternatives. An empty filter matches every change.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookFilter {
+    /// Change kinds to deliver ("create", "update", "delete")
+    pub changes: Vec<String>,
+
+    /// Entity types to deliver ("task", "bug", "link", ...)
+    pub entity_types: Vec<String>,
+
+    /// Edge types to deliver for link changes ("blocks", "depends_on", ...)
+    pub edge_types: Vec<String>,
+
+    /// Required previous status for a status transition
+    pub status_from: Option<String>,
+
+    /// Required new status for a status transition
+    pub status_to: Option<String>,
+
+    /// Entity fields that must have one of the given values
+    pub fields: Vec<(String, Vec<String>)>,
+}
+
+impl WebhookFilter {
+    /// Whether the filter requires a status transition.
+    pub fn requires_transition(&self) -> bool {
+        self.status_from.is_some() || self.status_to.is_some()
+    }
+}
+
+/// An outbound webhook subscription, configured in config.kdl.
+///
+/// # KDL Schema
+///
+/// ```kdl
+/// webhook "critical-bugs" url="https://chat.example.com/hook" {
+///   secret-env "CI_HOOK_SECRET" // env var holding the HMAC-SHA256 key
+///   // or: secret "s3cret"      // inline key (config.kdl may be synced)
+///   change "create"             // create, update, delete
+///   entity-type "bug"           // any entity type, or "link"
+///   edge-type "blocks"          // only for link changes
+///   transition from="pending" to="done"  // either side may be omitted
+///   match "severity" "critical" "high"   // entity field equals any value
+///   max-attempts 5
+///   backoff-ms 1000
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// Unique name of the subscription
+    pub name: String,
+
+    /// URL that receives POSTed events
+    pub url: String,
+
+    /// Secret used to sign payloads (`X-Binnacle-Signature-256`)
+    #[serde(skip_serializing)]
+    pub secret: Option<String>,
+
+    /// Environment variable holding the signing secret
+    pub secret_env: Option<String>,
+
+    /// Which changes are delivered
+    pub filter: WebhookFilter,
+
+    /// Total delivery attempts before the event is dead-lettered
+    pub max_attempts: u32,
+
+    /// Delay before the first retry; doubled after each failure
+    pub backoff_ms: u64,
+}
+
+impl WebhookConfig {
+    /// Parse a subscription from a `webhook` KDL node.
+    pub fn from_kdl_node(node: &KdlNode) -> Result<Self, String> {
+        let name = node
+            .entries()
+            .iter()
+            .find(|e| e.name().is_none())
+            .and_then(|e| e.value().as_string())
+            .ok_or("webhook node requires a name argument")?
+            .to_string();
+        let url = node
+            .get("url")
+            .and_then(|v| v.as_string())
+            .ok_or_else(|| format!("webhook '{}' requires a url property", name))?
+            .to_string();
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(format!(
+                "webhook '{}' url must start with http:// or https://",
+                name
+            ));
+        }
+
+        let mut config = Self {
+            name,
+            url,
+            secret: node
+                .get("secret")
+                .and_then(|v| v.as_string())
+                .map(|s| s.to_string()),
+            secret_env: None,
+            filter: WebhookFilter::default(),
+            max_attempts: DEFAULT_WEBHOOK_MAX_ATTEMPTS,
+            backoff_ms: DEFAULT_WEBHOOK_BACKOFF_MS,
+        };
+
+        let strings = |n: &KdlNode| -> Vec<String> {
+            n.entries()
+                .iter()
+                .filter(|e| e.name().is_none())
+                .filter_map(|e| e.value().as_string())
+                .map(|s| s.to_lowercase().replace('-', "_"))
+                .collect()
+        };
+
+        for child in node.children().map(|c| c.nodes()).unwrap_or_default() {
+            let first_int = || child.entries().first().and_then(|e| e.value().as_integer());
+            match child.name().value() {
+                "secret" => {
+                    config.secret = child
+                        .entries()
+                        .first()
+                        .and_then(|e| e.value().as_string())
+                        .map(|s| s.to_string());
+                }
+                "secret-env" => {
+                    config.secret_env = child
+                        .entries()
+                        .first()
+                        .and_then(|e| e.value().as_string())
+                        .map(|s| s.to_string());
+                }
+                "change" => {
+                    for change in strings(child) {
+                        if !["create", "update", "delete"].contains(&change.as_str()) {
+                            return Err(format!(
+                                "webhook '{}': unknown change '{}' (expected create, update or delete)",
+                                config.name, change
+                            ));
+                        }
+                        config.filter.changes.push(change);
+                    }
+                }
+                "entity-type" => config.filter.entity_types.extend(strings(child)),
+                "edge-type" => config.filter.edge_types.extend(strings(child)),
+                "transition" => {
+                    config.filter.status_from = child
+                        .get("from")
+                        .and_then(|v| v.as_string())
+                        .map(|s| s.to_lowercase().replace('-', "_"));
+                    config.filter.status_to = child
+                        .get("to")
+                        .and_then(|v| v.as_string())
+                        .map(|s| s.to_lowercase().replace('-', "_"));
+                }
+                "match" => {
+                    let mut args = child
+                        .entries()
+                        .iter()
+                        .filter(|e| e.name().is_none())
+                        .filter_map(|e| e.value().as_string())
+                        .map(|s| s.to_string());
+                    let field = args.next().ok_or_else(|| {
+                        format!("webhook '{}': match requires a field name", config.name)
+                    })?;
+                    let values: Vec<String> = args.collect();
+                    if values.is_empty() {
+                        return Err(format!(
+                            "webhook '{}': match \"{}\" requires at least one value",
+                            config.name, field
+                        ));
+                    }
+                    config.filter.fields.push((field, values));
+                }
+                "max-attempts" => {
+                    config.max_attempts = first_int().filter(|n| *n >= 1).ok_or_else(|| {
+                        format!("webhook '{}': max-attempts must be >= 1", config.name)
+                    })? as u32;
+                }
+                "backoff-ms" => {
+                    config.backoff_ms = first_int().filter(|n| *n >= 0).ok_or_else(|| {
+                        format!("webhook '{}': backoff-ms must be >= 0", config.name)
+                    })? as u64;
+                }
+                other => {
+                    return Err(format!(
+                        "webhook '{}': unknown setting '{}'",
+                        config.name, other
+                    ));
+                }
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Resolve the signing secret, preferring `secret-env` over an inline secret.
+    pub fn signing_secret(&self) -> Option<String> {
+        self.secret_env
+            .as_ref()
+            .and_then(|var| std::env::var(var).ok())
+            .filter(|s| !s.is_empty())
+            .or_else(|| self.secret.clone())
+    }
+
+    /// Parse all `webhook` nodes in a config.kdl document.
+    ///
+    /// Fails on the first invalid subscription or duplicate name.
+    pub fn all_from_kdl(doc: &KdlDocument) -> Result<Vec<Self>, String> {
+        let mut webhooks: Vec<Self> = Vec::new();
+        for node in doc.nodes().iter().filter(|n| n.name().value() == "webhook") {
+            let webhook = Self::from_kdl_node(node)?;
+            if webhooks.iter().any(|w| w.name == webhook.name) {
+                return Err(format!("duplicate webhook name '{}'", webhook.name));
+            }
+            webhooks.push(webhook);
+        }
+        Ok(webhooks)
+    }
+}
+
+/// Default delay between agent supervisor passes, in seconds.
+pub const DEFAULT_SUPERVISOR_INTERVAL_SECS: u64 = 15;
+
+/// Default delay before restarting a crashed agent, in seconds.
+pub const DEFAULT_SUPERVISOR_BACKOFF_SECS: u64 = 10;
+
+/// Default upper bound for the restart delay, in seconds.
+pub const DEFAULT_SUPERVISOR_MAX_BACKOFF_SECS: u64 = 600;
+
+/// Default time a new container gets to start before it can be considered crashed.
+pub const DEFAULT_SUPERVISOR_STARTUP_GRACE_SECS: u64 = 60;
+
+/// Wall-clock and idle limits for one agent type.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentTimeouts {
+    /// Agent type the limits apply to (worker, planner, buddy, ask)
+    pub agent_type: String,
+
+    /// Maximum time since the agent started
+    pub wall_clock_secs: Option<u64>,
+
+    /// Maximum time since the agent last ran a binnacle command
+    pub idle_secs: Option<u64>,
+}
+
+/// Agent pool supervisor settings, configured in config.kdl.
+///
+/// Scaling bounds come from the `agents` block (`bn agent scale`); this
+/// block only controls how the supervisor loop behaves.
+///
+/// # KDL Schema
+///
+/// ```kdl
+/// supervisor {
+///   interval-secs 15         // delay between passes
+///   backoff-secs 10          // first restart delay, doubled per crash
+///   max-backoff-secs 600
+///   startup-grace-secs 60    // new containers are not crash-checked yet
+///   timeout "worker" wall-clock-secs=7200 idle-secs=1800
+///   timeout "planner" idle-secs=3600
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SupervisorConfig {
+    /// Delay between passes
+    pub interval_secs: u64,
+
+    /// Delay before the first restart of a crashed agent
+    pub backoff_secs: u64,
+
+    /// Upper bound for the restart delay
+    pub max_backoff_secs: u64,
+
+    /// Time a new container gets to start before crash detection applies
+    pub startup_grace_secs: u64,
+
+    /// Per-type wall-clock and idle limits
+    pub timeouts: Vec<AgentTimeouts>,
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: DEFAULT_SUPERVISOR_INTERVAL_SECS,
+            backoff_secs: DEFAULT_SUPERVISOR_BACKOFF_SECS,
+            max_backoff_secs: DEFAULT_SUPERVISOR_MAX_BACKOFF_SECS,
+            startup_grace_secs: DEFAULT_SUPERVISOR_STARTUP_GRACE_SECS,
+            timeouts: Vec::new(),
+        }
+    }
+}
+
+impl SupervisorConfig {
+    /// Parse the `supervisor` node of a config.kdl document.
+    ///
+    /// Returns None if the document has no `supervisor` node.
+    pub fn from_kdl(doc: &KdlDocument) -> Result<Option<Self>, String> {
+        let Some(node) = doc.get("supervisor") else {
+            return Ok(None);
+        };
+
+        let mut config = Self::default();
+        for child in node.children().map(|c| c.nodes()).unwrap_or_default() {
+            let name = child.name().value();
+            let secs = || {
+                child
+                    .entries()
+                    .first()
+                    .and_then(|e| e.value().as_integer())
+                    .filter(|n| *n >= 0)
+                    .map(|n| n as u64)
+                    .ok_or_else(|| format!("supervisor: {} must be >= 0", name))
+            };
+            match name {
+                "interval-secs" => {
+                    config.interval_secs = secs()?.max(1);
+                }
+                "backoff-secs" => config.backoff_secs = secs()?,
+                "max-backoff-secs" => config.max_backoff_secs = secs()?,
+                "startup-grace-secs" => config.startup_grace_secs = secs()?,
+                "timeout" => {
+                    let agent_type = child
+                        .entries()
+                        .iter()
+                        .find(|e| e.name().is_none())
+                        .and_then(|e| e.value().as_string())
+                        .ok_or("supervisor: timeout requires an agent type argument")?
+                        .to_string();
+                    if !["worker", "planner", "buddy", "ask"].contains(&agent_type.as_str()) {
+                        return Err(format!(
+                            "supervisor: unknown agent type '{}' (expected worker, planner, buddy or ask)",
+                            agent_type
+                        ));
+                    }
+                    let limit = |key: &str| -> Result<Option<u64>, String> {
+                        match child.get(key) {
+                            None => Ok(None),
+                            Some(v) => v
+                                .as_integer()
+                                .filter(|n| *n > 0)
+                                .map(|n| Some(n as u64))
+                                .ok_or_else(|| {
+                                    format!(
+                                        "supervisor: timeout \"{}\" {} must be > 0",
+                                        agent_type, key
+                                    )
+                                }),
+                        }
+                    };
+                    let timeouts = AgentTimeouts {
+                        wall_clock_secs: limit("wall-clock-secs")?,
+                        idle_secs: limit("idle-secs")?,
+                        agent_type,
+                    };
+                    config
+                        .timeouts
+                        .retain(|t| t.agent_type != timeouts.agent_type);
+                    config.timeouts.push(timeouts);
+                }
+                other => {
+                    return Err(format!("supervisor: unknown setting '{}'", other));
+                }
+            }
+        }
+
+        if config.max_backoff_secs < config.backoff_secs {
+            config.max_backoff_secs = config.backoff_secs;
+        }
+        Ok(Some(config))
+    }
+
+    /// Limits configured for an agent type, if any.
+    pub fn timeouts_for(&self, agent_type: &str) -> Option<&AgentTimeouts> {
+        self.timeouts.iter().find(|t| t.agent_type == agent_type)
+    }
+}
+
+/// Usage limits for one budget. Unset limits are not enforced.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BudgetLimits {
+    /// Maximum input plus output tokens
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<u64>,
+
+    /// Maximum model requests
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requests: Option<u64>,
+
+    /// Maximum cost in US dollars
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+}
+
+/// What a budget is charged for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "id", rename_all = "snake_case")]
+pub enum BudgetScope {
+    /// Usage attributed to the milestone's child tasks and bugs
+    Milestone(String),
+    /// Usage attributed to tasks and bugs in the work queue
+    Queue,
+    /// All usage reported by agents of one type
+    AgentType(String),
+}
+
+impl std::fmt::Display for BudgetScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Milestone(id) => write!(f, "milestone {}", id),
+            Self::Queue => write!(f, "queue"),
+            Self::AgentType(agent_type) => write!(f, "agent-type {}", agent_type),
+        }
+    }
+}
+
+/// One configured budget.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Budget {
+    pub scope: BudgetScope,
+    pub limits: BudgetLimits,
+}
+
+/// Model usage budgets, configured in config.kdl.
+///
+/// Usage comes from `bn agent usage report`. Once a budget is exhausted the
+/// supervisor stops spawning the agents it covers and affected workers are
+/// told to `bn goodbye`.
+///
+/// # KDL Schema
+///
+/// ```kdl
+/// budgets {
+///   milestone "bn-a1b2" tokens=2000000 cost-usd=40.0
+///   queue requests=500
+///   agent-type "worker" cost-usd=100
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BudgetConfig {
+    pub budgets: Vec<Budget>,
+}
+
+impl BudgetConfig {
+    /// Parse the `budgets` node of a config.kdl document.
+    ///
+    /// Returns None if the document has no `budgets` node.
+    pub fn from_kdl(doc: &KdlDocument) -> Result<Option<Self>, String> {
+        let Some(node) = doc.get("budgets") else {
+            return Ok(None);
+        };
+
+        let mut config = Self::default();
+        for child in node.children().map(|c| c.nodes()).unwrap_or_default() {
+            let argument = child
+                .entries()
+                .iter()
+                .find(|e| e.name().is_none())
+                .and_then(|e| e.value().as_string());
+            let scope = match child.name().value() {
+                "milestone" => BudgetScope::Milestone(
+                    argument
+                        .ok_or("budgets: milestone requires a milestone ID argument")?
+                        .to_string(),
+                ),
+                "queue" => BudgetScope::Queue,
+                "agent-type" => {
+                    let agent_type =
+                        argument.ok_or("budgets: agent-type requires an agent type argument")?;
+                    if !["worker", "planner", "buddy", "ask"].contains(&agent_type) {
+                        return Err(format!(
+                            "budgets: unknown agent type '{}' (expected worker, planner, buddy or ask)",
+                            agent_type
+                        ));
+                    }
+                    BudgetScope::AgentType(agent_type.to_string())
+                }
+                other => return Err(format!("budgets: unknown budget '{}'", other)),
+            };
+
+            let count = |key: &str| -> Result<Option<u64>, String> {
+                match child.get(key) {
+                    None => Ok(None),
+                    Some(v) => v
+                        .as_integer()
+                        .filter(|n| *n > 0)
+                        .map(|n| Some(n as u64))
+                        .ok_or_else(|| format!("budgets: {} {} must be > 0", scope, key)),
+                }
+            };
+            let limits = BudgetLimits {
+                tokens: count("tokens")?,
+                requests: count("requests")?,
+                cost_usd: match child.get("cost-usd") {
+                    None => None,
+                    Some(v) => Some(
+                        v.as_float()
+                            .or_else(|| v.as_integer().map(|n| n as f64))
+                            .filter(|n| *n > 0.0)
+                            .ok_or_else(|| format!("budgets: {} cost-usd must be > 0", scope))?,
+                    ),
+                },
+            };
+            if let Some(key) = child
+                .entries()
+                .iter()
+                .filter_map(|e| e.name())
+                .map(|n| n.value())
+                .find(|n| !["tokens", "requests", "cost-usd"].contains(n))
+            {
+                return Err(format!("budgets: {} has unknown limit '{}'", scope, key));
+            }
+            if limits == BudgetLimits::default() {
+                return Err(format!(
+                    "budgets: {} needs at least one of tokens, requests or cost-usd",
+                    scope
+                ));
+            }
+
+            config.budgets.retain(|b| b.scope != scope);
+            config.budgets.push(Budget { scope, limits });
+        }
+        Ok(Some(config))
+    }
+}
+
+/// Report kinds generated by `bn report`.
+pub const REPORT_KINDS: [&str; 2] = ["pr", "changelog"];
+
+/// Dimensions report entries can be grouped by.
+pub const REPORT_GROUPS: [&str; 4] = ["mission", "milestone", "tag", "type"];
+
+/// Markdown template for a `bn report`, configured in config.kdl.
+///
+/// Placeholders are written `{name}`. The title takes `{since}`, `{head}`,
+/// `{milestone}`, `{milestone_id}` and `{date}`; headings take `{group}`;
+/// entries take `{id}`, `{title}`, `{type}`, `{reason}`, `{commits}` and
+/// `{tags}`. An entry is grouped under the first `group-by` dimension it
+/// has a value for. Settings left out keep their defaults.
+///
+/// # KDL Schema
+///
+/// ```kdl
+/// report "pr" {
+///   title "## Changes since {since}"
+///   group-by "mission" "milestone" "tag"   // also "type" (Tasks / Bugs)
+///   heading "### {group}"
+///   entry "- {id} {title}: {reason}"
+///   entry-no-reason "- {id} {title}"       // for entries closed without a reason
+///   ungrouped "Other changes"
+///   empty "No closed tasks or bugs."
+///   link "https://tracker.example.com/{id}"           // renders {id} as a link
+///   commit-link "https://github.com/o/r/commit/{sha}" // same for {commits}
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportTemplate {
+    pub title: String,
+    pub group_by: Vec<String>,
+    pub heading: String,
+    pub entry: String,
+    pub entry_no_reason: String,
+    pub ungrouped: String,
+    pub empty: String,
+    pub link: Option<String>,
+    pub commit_link: Option<String>,
+}
+
+impl ReportTemplate {
+    /// Built-in template for a report kind.
+    pub fn default_for(kind: &str) -> Self {
+        let pr = Self {
+            title: "## Changes since {since}".to_string(),
+            group_by: vec![
+                "mission".to_string(),
+                "milestone".to_string(),
+                "tag".to_string(),
+            ],
+            heading: "### {group}".to_string(),
+            entry: "- {id} {title}: {reason}".to_string(),
+            entry_no_reason: "- {id} {title}".to_string(),
+            ungrouped: "Other changes".to_string(),
+            empty: "No closed tasks or bugs.".to_string(),
+            link: None,
+            commit_link: None,
+        };
+        match kind {
+            "changelog" => Self {
+                title: "## {milestone} ({date})".to_string(),
+                group_by: vec!["type".to_string()],
+                entry: "- {title} ({id}): {reason}".to_string(),
+                entry_no_reason: "- {title} ({id})".to_string(),
+                ungrouped: "Other".to_string(),
+                ..pr
+            },
+            _ => pr,
+        }
+    }
+
+    /// Parse the `report "<kind>"` node of a config.kdl document.
+    ///
+    /// Returns None if the document has no such node.
+    pub fn from_kdl(doc: &KdlDocument, kind: &str) -> Result<Option<Self>, String> {
+        let mut found = None;
+        for node in doc.nodes().iter().filter(|n| n.name().value() == "report") {
+            let name = node
+                .entries()
+                .iter()
+                .find(|e| e.name().is_none())
+                .and_then(|e| e.value().as_string())
+                .ok_or("report node requires a kind argument")?;
+            if !REPORT_KINDS.contains(&name) {
+                return Err(format!(
+                    "unknown report '{}' (expected pr or changelog)",
+                    name
+                ));
+            }
+            if name == kind {
+                found = Some(node);
+            }
+        }
+        let Some(node) = found else {
+            return Ok(None);
+        };
+
+        let mut template = Self::default_for(kind);
+        for child in node.children().map(|c| c.nodes()).unwrap_or_default() {
+            let name = child.name().value();
+            let mut strings = child
+                .entries()
+                .iter()
+                .filter(|e| e.name().is_none())
+                .filter_map(|e| e.value().as_string())
+                .map(|s| s.to_string());
+            let mut text = || {
+                strings
+                    .next()
+                    .ok_or_else(|| format!("report \"{}\": {} requires a string", kind, name))
+            };
+            match name {
+                "title" => template.title = text()?,
+                "heading" => template.heading = text()?,
+                "entry" => template.entry = text()?,
+                "entry-no-reason" => template.entry_no_reason = text()?,
+                "ungrouped" => template.ungrouped = text()?,
+                "empty" => template.empty = text()?,
+                "link" => template.link = Some(text()?),
+                "commit-link" => template.commit_link = Some(text()?),
+                "group-by" => {
+                    template.group_by = strings.by_ref().collect();
+                    if let Some(bad) = template
+                        .group_by
+                        .iter()
+                        .find(|g| !REPORT_GROUPS.contains(&g.as_str()))
+                    {
+                        return Err(format!(
+                            "report \"{}\": unknown group '{}' (expected mission, milestone, tag or type)",
+                            kind, bad
+                        ));
+                    }
+                }
+                other => {
+                    return Err(format!("report \"{}\": unknown setting '{}'", kind, other));
+                }
+            }
+        }
+        Ok(Some(template))
+    }
+
+    /// Fill `{name}` placeholders in one pass, so substituted text is never
+    /// expanded again. Unknown placeholders are left as written.
+    pub fn render(template: &str, values: &[(&str, &str)]) -> String {
+        let mut out = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            let value = after.find('}').and_then(|end| {
+                let key = &after[..end];
+                values
+                    .iter()
+                    .find(|(k, _)| *k == key)
+                    .map(|(_, v)| (*v, end))
+            });
+            match value {
+                Some((value, end)) => {
+                    out.push_str(value);
+                    rest = &after[end + 1..];
+                }
+                None => {
+                    out.push('{');
+                    rest = after;
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
 /// Runtime state stored in state.kdl.
 ///
 /// This file contains machine-specific state and secrets.
@@ -417,6 +1309,12 @@ impl ServeState {
 ///   branch "main"
 ///   last-heartbeat "2026-01-31T10:30:00Z"
 /// }
+///
+/// gui-token "gtk-1a2b3c4d" {
+///   role "viewer"
+///   hash "9f86d081884c7d659a2feaa0c55ad015..."
+///   created-at "2026-01-31T10:00:00Z"
+/// }
 /// ```
 #[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BinnacleState {
@@ -431,6 +1329,9 @@ pub struct BinnacleState {
 
     /// Session server state (if a server is running)
     pub serve: Option<ServeState>,
+
+    /// Bearer tokens accepted by the GUI/session server
+    pub gui_tokens: Vec<GuiToken>,
 }
 
 impl BinnacleState {
@@ -497,6 +1398,14 @@ impl BinnacleState {
             state.serve = ServeState::from_kdl_node(node);
         }
 
+        // Parse gui-token blocks (may appear multiple times)
+        state.gui_tokens = doc
+            .nodes()
+            .iter()
+            .filter(|n| n.name().value() == "gui-token")
+            .filter_map(GuiToken::from_kdl_node)
+            .collect();
+
         state
     }
 
@@ -526,6 +1435,10 @@ impl BinnacleState {
             doc.nodes_mut().push(serve.to_kdl_node());
         }
 
+        for token in &self.gui_tokens {
+            doc.nodes_mut().push(token.to_kdl_node());
+        }
+
         doc
     }
 
@@ -544,6 +1457,9 @@ impl BinnacleState {
         if other.serve.is_some() {
             self.serve = other.serve.clone();
         }
+        if !other.gui_tokens.is_empty() {
+            self.gui_tokens = other.gui_tokens.clone();
+        }
     }
 
     /// Set the serve state (for session server startup).
@@ -566,6 +1482,26 @@ impl BinnacleState {
             false
         }
     }
+
+    /// Whether the GUI/session server should require bearer tokens.
+    ///
+    /// Authentication is enabled as soon as at least one token exists.
+    pub fn gui_auth_enabled(&self) -> bool {
+        !self.gui_tokens.is_empty()
+    }
+
+    /// Find the GUI token matching a presented secret.
+    pub fn find_gui_token(&self, secret: &str) -> Option<&GuiToken> {
+        let hash = GuiToken::hash_secret(secret);
+        self.gui_tokens.iter().find(|t| t.hash == hash)
+    }
+
+    /// Remove a GUI token by ID. Returns true if a token was removed.
+    pub fn revoke_gui_token(&mut self, id: &str) -> bool {
+        let original_len = self.gui_tokens.len();
+        self.gui_tokens.retain(|t| t.id != id);
+        self.gui_tokens.len() < original_len
+    }
 }
 
 /// Required permissions for state.kdl (Unix: 0600, owner read/write only).
@@ -797,6 +1733,7 @@ mod tests {
             token_validated_at: Some(Utc::now()),
             last_copilot_version: Some("2.0.0".to_string()),
             serve: None,
+            gui_tokens: Vec::new(),
         };
 
         let doc = state.to_kdl();
@@ -816,6 +1753,7 @@ mod tests {
             token_validated_at: None,
             last_copilot_version: Some("1.0.0".to_string()),
             serve: None,
+            gui_tokens: Vec::new(),
         };
 
         let override_state = BinnacleState {
@@ -829,6 +1767,7 @@ mod tests {
                 "test-repo".to_string(),
                 "main".to_string(),
             )),
+            gui_tokens: Vec::new(),
         };
 
         base.merge(&override_state);
@@ -890,6 +1829,7 @@ mod tests {
             branch: "main".to_string(),
             last_heartbeat: Utc::now() - chrono::Duration::seconds(60),
             upstream: None,
+            tls_fingerprint: None,
         };
 
         assert!(serve.is_heartbeat_stale(30)); // Stale if threshold is 30s
@@ -917,6 +1857,23 @@ mod tests {
         // DateTime precision may differ due to RFC3339 formatting
     }
 
+    #[test]
+    fn test_serve_state_tls_fingerprint_roundtrip() {
+        let serve = ServeState::new(
+            12345,
+            3030,
+            "0.0.0.0".to_string(),
+            "test-repo".to_string(),
+            "main".to_string(),
+        );
+        assert!(!serve.is_tls());
+
+        let serve = serve.with_tls_fingerprint(Some("sha256:abcd".to_string()));
+        let parsed = ServeState::from_kdl_node(&serve.to_kdl_node()).unwrap();
+        assert!(parsed.is_tls());
+        assert_eq!(parsed.tls_fingerprint.as_deref(), Some("sha256:abcd"));
+    }
+
     #[test]
     fn test_serve_state_from_kdl_full_document() {
         let kdl = r#"
@@ -956,6 +1913,7 @@ mod tests {
                 "binnacle".to_string(),
                 "main".to_string(),
             )),
+            gui_tokens: Vec::new(),
         };
 
         let doc = state.to_kdl();
@@ -1110,4 +2068,281 @@ mod tests {
         let removed = remove_token_from_kdl_doc(&mut doc);
         assert!(!removed);
     }
+
+    // ==================== GUI Token Tests ====================
+
+    #[test]
+    fn test_gui_role_parse_and_ordering() {
+        assert_eq!(GuiRole::parse("viewer"), Some(GuiRole::Viewer));
+        assert_eq!(GuiRole::parse("EDITOR"), Some(GuiRole::Editor));
+        assert_eq!(GuiRole::parse("admin"), Some(GuiRole::Admin));
+        assert_eq!(GuiRole::parse("root"), None);
+
+        assert!(GuiRole::Admin.allows(GuiRole::Editor));
+        assert!(GuiRole::Editor.allows(GuiRole::Viewer));
+        assert!(GuiRole::Editor.allows(GuiRole::Editor));
+        assert!(!GuiRole::Viewer.allows(GuiRole::Editor));
+        assert!(!GuiRole::Editor.allows(GuiRole::Admin));
+    }
+
+    #[test]
+    fn test_webhook_config_from_kdl() {
+        let doc: KdlDocument = r#"
+            editor "nvim"
+            webhook "ci" url="http://127.0.0.1:9000/hook" {
+                secret "s3cret"
+                change "update"
+                entity-type "task" "bug"
+                edge-type "depends-on"
+                transition to="done"
+                match "severity" "critical" "high"
+                max-attempts 3
+                backoff-ms 10
+            }
+            webhook "all" url="https://example.com/all"
+        "#
+        .parse()
+        .unwrap();
+
+        let webhooks = WebhookConfig::all_from_kdl(&doc).unwrap();
+        assert_eq!(webhooks.len(), 2);
+
+        let ci = &webhooks[0];
+        assert_eq!(ci.name, "ci");
+        assert_eq!(ci.secret.as_deref(), Some("s3cret"));
+        assert_eq!(ci.filter.changes, vec!["update"]);
+        assert_eq!(ci.filter.entity_types, vec!["task", "bug"]);
+        assert_eq!(ci.filter.edge_types, vec!["depends_on"]);
+        assert_eq!(ci.filter.status_from, None);
+        assert_eq!(ci.filter.status_to.as_deref(), Some("done"));
+        assert!(ci.filter.requires_transition());
+        assert_eq!(
+            ci.filter.fields,
+            vec![(
+                "severity".to_string(),
+                vec!["critical".to_string(), "high".to_string()]
+            )]
+        );
+        assert_eq!((ci.max_attempts, ci.backoff_ms), (3, 10));
+
+        let all = &webhooks[1];
+        assert_eq!(all.filter, WebhookFilter::default());
+        assert_eq!(all.max_attempts, DEFAULT_WEBHOOK_MAX_ATTEMPTS);
+    }
+
+    #[test]
+    fn test_webhook_config_rejects_invalid() {
+        for kdl in [
+            r#"webhook "a""#,
+            r#"webhook "a" url="ftp://example.com""#,
+            r#"webhook "a" url="http://x" { change "explode" }"#,
+            r#"webhook "a" url="http://x" { bogus 1 }"#,
+            r#"webhook "a" url="http://x" { max-attempts 0 }"#,
+            r#"webhook "a" url="http://x" { match "severity" }"#,
+            "webhook \"a\" url=\"http://x\"\nwebhook \"a\" url=\"http://y\"",
+        ] {
+            let doc: KdlDocument = kdl.parse().unwrap();
+            assert!(WebhookConfig::all_from_kdl(&doc).is_err(), "{}", kdl);
+        }
+    }
+
+    #[test]
+    fn test_supervisor_config_from_kdl() {
+        let doc: KdlDocument = "editor \"vim\"".parse().unwrap();
+        assert_eq!(SupervisorConfig::from_kdl(&doc).unwrap(), None);
+
+        let doc: KdlDocument = r#"
+            supervisor {
+                interval-secs 5
+                backoff-secs 2
+                max-backoff-secs 1
+                timeout "worker" wall-clock-secs=3600 idle-secs=600
+                timeout "planner" idle-secs=900
+            }
+        "#
+        .parse()
+        .unwrap();
+        let config = SupervisorConfig::from_kdl(&doc).unwrap().unwrap();
+        assert_eq!(config.interval_secs, 5);
+        // max-backoff-secs is raised to backoff-secs
+        assert_eq!((config.backoff_secs, config.max_backoff_secs), (2, 2));
+        assert_eq!(
+            config.startup_grace_secs,
+            DEFAULT_SUPERVISOR_STARTUP_GRACE_SECS
+        );
+        let worker = config.timeouts_for("worker").unwrap();
+        assert_eq!(
+            (worker.wall_clock_secs, worker.idle_secs),
+            (Some(3600), Some(600))
+        );
+        let planner = config.timeouts_for("planner").unwrap();
+        assert_eq!(
+            (planner.wall_clock_secs, planner.idle_secs),
+            (None, Some(900))
+        );
+        assert!(config.timeouts_for("buddy").is_none());
+    }
+
+    #[test]
+    fn test_supervisor_config_rejects_invalid() {
+        for kdl in [
+            "supervisor { interval-secs -1 }",
+            "supervisor { bogus 1 }",
+            "supervisor { timeout idle-secs=10 }",
+            r#"supervisor { timeout "robot" idle-secs=10 }"#,
+            r#"supervisor { timeout "worker" idle-secs=0 }"#,
+        ] {
+            let doc: KdlDocument = kdl.parse().unwrap();
+            assert!(SupervisorConfig::from_kdl(&doc).is_err(), "{}", kdl);
+        }
+    }
+
+    #[test]
+    fn test_budget_config_from_kdl() {
+        let doc: KdlDocument = r#"
+budgets {
+  milestone "bn-a1b2" tokens=2000000 cost-usd=40.5
+  queue requests=500
+  agent-type "worker" cost-usd=100
+  agent-type "worker" tokens=10
+}
+"#
+        .parse()
+        .unwrap();
+        let config = BudgetConfig::from_kdl(&doc).unwrap().unwrap();
+        assert_eq!(config.budgets.len(), 3);
+        assert_eq!(
+            config.budgets[0].scope,
+            BudgetScope::Milestone("bn-a1b2".to_string())
+        );
+        assert_eq!(config.budgets[0].limits.tokens, Some(2_000_000));
+        assert_eq!(config.budgets[0].limits.cost_usd, Some(40.5));
+        assert_eq!(config.budgets[1].scope, BudgetScope::Queue);
+        assert_eq!(config.budgets[1].limits.requests, Some(500));
+        // A repeated scope replaces the earlier entry
+        assert_eq!(
+            config.budgets[2].scope,
+            BudgetScope::AgentType("worker".to_string())
+        );
+        assert_eq!(config.budgets[2].limits.tokens, Some(10));
+        assert_eq!(config.budgets[2].limits.cost_usd, None);
+
+        let empty: KdlDocument = "editor \"vim\"".parse().unwrap();
+        assert_eq!(BudgetConfig::from_kdl(&empty).unwrap(), None);
+    }
+
+    #[test]
+    fn test_budget_config_rejects_invalid() {
+        for kdl in [
+            "budgets { milestone tokens=10 }",
+            r#"budgets { agent-type "robot" tokens=10 }"#,
+            "budgets { queue }",
+            "budgets { queue tokens=0 }",
+            "budgets { queue cost-usd=-1.0 }",
+            "budgets { queue dollars=5 }",
+            "budgets { sprint tokens=10 }",
+        ] {
+            let doc: KdlDocument = kdl.parse().unwrap();
+            assert!(BudgetConfig::from_kdl(&doc).is_err(), "{}", kdl);
+        }
+    }
+
+    #[test]
+    fn test_report_template_from_kdl() {
+        let doc: KdlDocument = r#"
+            report "pr" {
+                group-by "tag"
+                entry "* {id}: {title}"
+                link "https://example.com/{id}"
+            }
+        "#
+        .parse()
+        .unwrap();
+
+        let pr = ReportTemplate::from_kdl(&doc, "pr").unwrap().unwrap();
+        assert_eq!(pr.group_by, vec!["tag"]);
+        assert_eq!(pr.entry, "* {id}: {title}");
+        assert_eq!(pr.link.as_deref(), Some("https://example.com/{id}"));
+        // Unset settings keep the defaults
+        assert_eq!(pr.heading, ReportTemplate::default_for("pr").heading);
+        assert!(
+            ReportTemplate::from_kdl(&doc, "changelog")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_report_template_rejects_invalid() {
+        for kdl in [
+            "report { title \"x\" }",
+            r#"report "release" { title "x" }"#,
+            r#"report "pr" { group-by "sprint" }"#,
+            r#"report "pr" { footer "x" }"#,
+            r#"report "pr" { title }"#,
+        ] {
+            let doc: KdlDocument = kdl.parse().unwrap();
+            assert!(ReportTemplate::from_kdl(&doc, "pr").is_err(), "{}", kdl);
+        }
+    }
+
+    #[test]
+    fn test_report_template_render() {
+        let values = [("id", "bn-1"), ("title", "Use {id} literally")];
+        assert_eq!(
+            ReportTemplate::render("- {id} {title} {missing}", &values),
+            "- bn-1 Use {id} literally {missing}"
+        );
+        assert_eq!(ReportTemplate::render("{ {id}", &values), "{ bn-1");
+    }
+
+    #[test]
+    fn test_gui_token_kdl_roundtrip() {
+        let token = GuiToken {
+            id: "gtk-1a2b3c4d".to_string(),
+            name: Some("ci-bot".to_string()),
+            role: GuiRole::Editor,
+            hash: GuiToken::hash_secret("bng_secret"),
+            created_at: Utc::now(),
+        };
+
+        let node = token.to_kdl_node();
+        let parsed = GuiToken::from_kdl_node(&node).unwrap();
+
+        assert_eq!(parsed.id, token.id);
+        assert_eq!(parsed.name, token.name);
+        assert_eq!(parsed.role, GuiRole::Editor);
+        assert_eq!(parsed.hash, token.hash);
+        assert!(parsed.matches("bng_secret"));
+        assert!(!parsed.matches("bng_other"));
+    }
+
+    #[test]
+    fn test_state_gui_tokens_roundtrip_and_lookup() {
+        let mut state = BinnacleState::default();
+        assert!(!state.gui_auth_enabled());
+
+        for (id, secret, role) in [
+            ("gtk-aaaa", "secret-a", GuiRole::Viewer),
+            ("gtk-bbbb", "secret-b", GuiRole::Admin),
+        ] {
+            state.gui_tokens.push(GuiToken {
+                id: id.to_string(),
+                name: None,
+                role,
+                hash: GuiToken::hash_secret(secret),
+                created_at: Utc::now(),
+            });
+        }
+
+        let parsed = BinnacleState::from_kdl(&state.to_kdl());
+        assert!(parsed.gui_auth_enabled());
+        assert_eq!(parsed.gui_tokens.len(), 2);
+        assert_eq!(parsed.find_gui_token("secret-b").unwrap().id, "gtk-bbbb");
+        assert!(parsed.find_gui_token("nope").is_none());
+
+        assert!(state.revoke_gui_token("gtk-aaaa"));
+        assert!(!state.revoke_gui_token("gtk-aaaa"));
+        assert_eq!(state.gui_tokens.len(), 1);
+    }
 }
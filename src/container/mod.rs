@@ -1,6 +1,7 @@
 //! Container definition management and config.kdl parsing.
 
 pub mod errors;
+pub mod runtime;
 pub mod validation;
 
 use crate::storage::get_storage_dir;
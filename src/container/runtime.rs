@@ -0,0 +1,1545 @@
+//! Container runtime abstraction.
+//!
+//! `bn container build`, `bn container run` and `bn agent spawn` describe what
+//! they want as a [`BuildSpec`] or [`RunSpec`] and hand it to a
+//! [`ContainerRuntime`]. Four runtimes are supported:
+//!
+//! - **containerd**: `buildah` builds, imported into containerd and run with `ctr`
+//!   (rootless via rootlesskit, or system-wide with `BN_ALLOW_SUDO=1`)
+//! - **podman**: rootless podman, with `--userns=keep-id` for file ownership
+//! - **docker**: the docker CLI (any docker-compatible daemon)
+//! - **bubblewrap**: a lightweight namespace sandbox over the host filesystem,
+//!   with no images at all
+//!
+//! The runtime is picked from `BN_CONTAINER_RUNTIME`, then a top-level
+//! `runtime "<name>"` node in the project or host containers `config.kdl`,
+//! then auto-detection (containerd, podman, docker, bubblewrap, in that order).
+
+use super::{ContainerDefinition, MountMode, errors, validate_mounts};
+use crate::storage::get_storage_dir;
+use crate::{Error, Result};
+use kdl::KdlDocument;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Environment variable that overrides the configured container runtime.
+pub const RUNTIME_ENV_VAR: &str = "BN_CONTAINER_RUNTIME";
+
+/// Label applied to podman/docker containers so `bn container list` only shows ours.
+const CONTAINER_LABEL: &str = "binnacle";
+
+/// Available container runtimes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuntimeKind {
+    Containerd,
+    Podman,
+    Docker,
+    Bubblewrap,
+}
+
+impl RuntimeKind {
+    /// All runtimes, in auto-detection order.
+    pub const ALL: [RuntimeKind; 4] = [
+        RuntimeKind::Containerd,
+        RuntimeKind::Podman,
+        RuntimeKind::Docker,
+        RuntimeKind::Bubblewrap,
+    ];
+
+    /// Binaries this runtime needs on PATH.
+    pub fn required_commands(&self) -> &'static [&'static str] {
+        match self {
+            RuntimeKind::Containerd => &["ctr", "buildah"],
+            RuntimeKind::Podman => &["podman"],
+            RuntimeKind::Docker => &["docker"],
+            RuntimeKind::Bubblewrap => &["bwrap"],
+        }
+    }
+
+    /// Whether all required binaries are installed.
+    pub fn is_installed(&self) -> bool {
+        self.required_commands()
+            .iter()
+            .all(|cmd| command_exists(cmd))
+    }
+
+    /// Whether this runtime runs images (bubblewrap uses the host filesystem).
+    pub fn uses_images(&self) -> bool {
+        *self != RuntimeKind::Bubblewrap
+    }
+}
+
+impl std::fmt::Display for RuntimeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuntimeKind::Containerd => write!(f, "containerd"),
+            RuntimeKind::Podman => write!(f, "podman"),
+            RuntimeKind::Docker => write!(f, "docker"),
+            RuntimeKind::Bubblewrap => write!(f, "bubblewrap"),
+        }
+    }
+}
+
+impl std::str::FromStr for RuntimeKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "containerd" | "ctr" => Ok(RuntimeKind::Containerd),
+            "podman" => Ok(RuntimeKind::Podman),
+            "docker" => Ok(RuntimeKind::Docker),
+            "bubblewrap" | "bwrap" => Ok(RuntimeKind::Bubblewrap),
+            _ => Err(Error::Other(errors::format_error(
+                errors::ErrorCategory::Config,
+                &format!("unknown container runtime '{}'", s),
+                None,
+                Some("Valid runtimes: containerd, podman, docker, bubblewrap (or auto)"),
+            ))),
+        }
+    }
+}
+
+/// A bind mount for a container.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunMount {
+    pub source: PathBuf,
+    pub target: String,
+    pub read_only: bool,
+}
+
+/// Everything needed to start a container, independent of runtime.
+#[derive(Debug, Clone, Default)]
+pub struct RunSpec {
+    /// Image reference (ignored by bubblewrap)
+    pub image: String,
+    /// Container name
+    pub name: String,
+    /// Allocate a TTY (only when stdin is a terminal)
+    pub tty: bool,
+    pub mounts: Vec<RunMount>,
+    pub env: Vec<(String, String)>,
+    pub cpus: Option<f64>,
+    pub memory_bytes: Option<u64>,
+    /// Host UID/GID owning the workspace, for runtimes that need explicit user mapping
+    pub user: Option<(u32, u32)>,
+    /// Command overriding the image entrypoint (empty = image default)
+    pub command: Vec<String>,
+}
+
+/// An image build request.
+#[derive(Debug, Clone)]
+pub struct BuildSpec {
+    /// Image reference to tag (e.g. `localhost/binnacle-self:latest`)
+    pub image: String,
+    pub containerfile: PathBuf,
+    pub context: PathBuf,
+    pub no_cache: bool,
+}
+
+/// A container known to the runtime.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeContainer {
+    pub name: String,
+    pub image: String,
+    pub running: bool,
+}
+
+/// A container runtime that can build images and run binnacle containers.
+pub trait ContainerRuntime {
+    /// Which runtime this is.
+    fn kind(&self) -> RuntimeKind;
+
+    /// Build an image from a Containerfile and make it available to `run`.
+    fn build_image(&self, spec: &BuildSpec) -> Result<()>;
+
+    /// Check whether an image is available locally.
+    fn image_exists(&self, image: &str) -> Result<bool>;
+
+    /// Build the command that runs a container in the foreground.
+    ///
+    /// The caller chooses stdio and whether to wait or detach.
+    fn run_command(&self, spec: &RunSpec) -> Result<Command>;
+
+    /// Check whether a container's main process is running.
+    fn is_running(&self, name: &str) -> Result<bool>;
+
+    /// Send a signal to a container's main process.
+    fn signal(&self, name: &str, signal: i32) -> Result<()>;
+
+    /// Remove a (stopped) container.
+    fn remove(&self, name: &str) -> Result<()>;
+
+    /// List binnacle containers, running or not.
+    fn list(&self) -> Result<Vec<RuntimeContainer>>;
+
+    /// Gracefully stop a container: SIGTERM, wait up to `timeout`, then SIGKILL.
+    fn stop(&self, name: &str, timeout: Duration) -> Result<()> {
+        if !self.is_running(name).unwrap_or(false) {
+            return Ok(());
+        }
+
+        self.signal(name, 15)?;
+
+        let start = Instant::now();
+        while start.elapsed() < timeout {
+            if !self.is_running(name).unwrap_or(true) {
+                return Ok(());
+            }
+            std::thread::sleep(Duration::from_millis(500));
+        }
+
+        let _ = self.signal(name, 9);
+        std::thread::sleep(Duration::from_millis(100));
+        Ok(())
+    }
+}
+
+/// Check if a command exists on the system.
+pub(crate) fn command_exists(cmd: &str) -> bool {
+    Command::new("which")
+        .arg(cmd)
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// Run a command, returning stdout or an error with its stderr.
+fn run_checked(mut cmd: Command, what: &str) -> Result<String> {
+    let output = cmd.output()?;
+    if !output.status.success() {
+        return Err(Error::Other(format!(
+            "{}: {}",
+            what,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// ============================================================================
+// Selection
+// ============================================================================
+
+/// Read the `runtime` node from a containers config.kdl, if present.
+///
+/// `runtime "auto"` is treated the same as no setting.
+pub fn parse_runtime_node(doc: &KdlDocument) -> Result<Option<RuntimeKind>> {
+    let Some(node) = doc.nodes().iter().find(|n| n.name().value() == "runtime") else {
+        return Ok(None);
+    };
+    let value = node
+        .entries()
+        .iter()
+        .find(|e| e.name().is_none())
+        .and_then(|e| e.value().as_string())
+        .ok_or_else(|| {
+            Error::Other(errors::format_error(
+                errors::ErrorCategory::Config,
+                "runtime requires a name",
+                None,
+                Some("Example: runtime \"podman\""),
+            ))
+        })?;
+    if value.eq_ignore_ascii_case("auto") {
+        return Ok(None);
+    }
+    value.parse().map(Some)
+}
+
+/// Find the runtime configured for a repository.
+///
+/// Checks `BN_CONTAINER_RUNTIME`, then the project config
+/// (`.binnacle/containers/config.kdl`), then the host config
+/// (`~/.local/share/binnacle/<hash>/containers/config.kdl`).
+pub fn configured_runtime(repo_path: &Path) -> Result<Option<RuntimeKind>> {
+    if let Ok(value) = std::env::var(RUNTIME_ENV_VAR)
+        && !value.is_empty()
+        && !value.eq_ignore_ascii_case("auto")
+    {
+        return value.parse().map(Some);
+    }
+
+    let mut configs = vec![repo_path.join(".binnacle/containers/config.kdl")];
+    if let Ok(storage_dir) = get_storage_dir(repo_path) {
+        configs.push(storage_dir.join("containers").join("config.kdl"));
+    }
+
+    for path in configs.iter().filter(|p| p.exists()) {
+        let content = fs::read_to_string(path).map_err(|e| {
+            Error::Other(errors::config_read_failed(
+                &path.display().to_string(),
+                &e.to_string(),
+            ))
+        })?;
+        let doc: KdlDocument = content.parse().map_err(|e: kdl::KdlError| {
+            Error::Other(errors::config_parse_failed(
+                &path.display().to_string(),
+                &e.to_string(),
+            ))
+        })?;
+        if let Some(kind) = parse_runtime_node(&doc)? {
+            return Ok(Some(kind));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Pick the first installed runtime in [`RuntimeKind::ALL`] order.
+///
+/// Containerd is only preferred when it can be used without further setup
+/// (rootless, or system mode with `BN_ALLOW_SUDO=1`); otherwise podman or
+/// docker win, and containerd is still chosen over bubblewrap so that its
+/// setup instructions are shown.
+pub fn detect_runtime() -> Option<RuntimeKind> {
+    let containerd_installed = RuntimeKind::Containerd.is_installed();
+    if containerd_installed && detect_containerd_mode().is_ok() {
+        return Some(RuntimeKind::Containerd);
+    }
+    [RuntimeKind::Podman, RuntimeKind::Docker]
+        .into_iter()
+        .find(|k| k.is_installed())
+        .or(containerd_installed.then_some(RuntimeKind::Containerd))
+        .or(RuntimeKind::Bubblewrap
+            .is_installed()
+            .then_some(RuntimeKind::Bubblewrap))
+}
+
+/// Create a runtime of the given kind.
+pub fn runtime_for(kind: RuntimeKind) -> Result<Box<dyn ContainerRuntime>> {
+    Ok(match kind {
+        RuntimeKind::Containerd => Box::new(Containerd::new(detect_containerd_mode()?)),
+        RuntimeKind::Podman => Box::new(OciCli::podman()),
+        RuntimeKind::Docker => Box::new(OciCli::docker()),
+        RuntimeKind::Bubblewrap => Box::new(Bubblewrap::new()?),
+    })
+}
+
+/// Select the runtime for a repository: configured, else auto-detected.
+///
+/// # Errors
+/// Returns an error if the configured runtime isn't installed, or if no
+/// runtime is installed at all.
+pub fn select_runtime(repo_path: &Path) -> Result<Box<dyn ContainerRuntime>> {
+    find_runtime(repo_path)?.ok_or_else(|| Error::Other(runtime_missing_message()))
+}
+
+/// Like [`select_runtime`], but returns `None` when no runtime is installed.
+///
+/// Commands use this to report missing tools in their result while still
+/// propagating setup errors such as the containerd sudo guard.
+///
+/// # Errors
+/// Returns an error if the configured runtime isn't installed, or if the
+/// selected runtime can't be set up.
+pub fn find_runtime(repo_path: &Path) -> Result<Option<Box<dyn ContainerRuntime>>> {
+    let kind = match configured_runtime(repo_path)? {
+        Some(kind) => {
+            if !kind.is_installed() {
+                return Err(Error::Other(errors::format_error(
+                    errors::ErrorCategory::Run,
+                    &format!("container runtime '{}' is not installed", kind),
+                    Some(&format!(
+                        "Required commands: {}",
+                        kind.required_commands().join(", ")
+                    )),
+                    Some("Install it, or change `runtime` in config.kdl (or BN_CONTAINER_RUNTIME)"),
+                )));
+            }
+            kind
+        }
+        None => match detect_runtime() {
+            Some(kind) => kind,
+            None => return Ok(None),
+        },
+    };
+
+    if kind == RuntimeKind::Containerd {
+        let mode = detect_containerd_mode()?;
+        warn_system_containerd_mode(&mode);
+        return Ok(Some(Box::new(Containerd::new(mode))));
+    }
+    runtime_for(kind).map(Some)
+}
+
+/// Get the helpful installation message for container dependencies.
+pub fn runtime_missing_message() -> String {
+    r#"Error: no container runtime found
+
+Binnacle containers need one of:
+
+  containerd + buildah   sudo dnf install containerd buildah   (or apt)
+  podman                 sudo dnf install podman               (or apt)
+  docker                 https://docs.docker.com/engine/install/
+  bubblewrap             sudo dnf install bubblewrap           (or apt)
+
+Pick one explicitly with `runtime "podman"` in .binnacle/containers/config.kdl
+or BN_CONTAINER_RUNTIME=podman."#
+        .to_string()
+}
+
+// ============================================================================
+// Spec helpers
+// ============================================================================
+
+/// Map a container definition's mounts onto the standard binnacle mounts.
+///
+/// The workspace is always mounted at `/workspace` and binnacle data at
+/// `/binnacle`, since the entrypoint scripts rely on those paths. A
+/// definition's `workspace` mount may make the workspace read-only; its
+/// `binnacle` mount is implied. Other mounts are resolved relative to
+/// `repo_root`, with missing optional sources skipped.
+pub fn definition_mounts(
+    definition: Option<&ContainerDefinition>,
+    repo_root: &Path,
+    workspace: &Path,
+    readonly_workspace: bool,
+    binnacle_data: &Path,
+) -> Result<Vec<RunMount>> {
+    let is_special = |name: &str, source: Option<&str>| match source {
+        Some(source) => source == name,
+        None => true,
+    };
+
+    let mut workspace_read_only = readonly_workspace;
+    let mut extra = Vec::new();
+    if let Some(def) = definition {
+        for validated in validate_mounts(&def.mounts, repo_root)? {
+            let mount = validated.mount;
+            let source = mount.source.as_deref();
+            if mount.name == "workspace" && is_special("workspace", source) {
+                workspace_read_only |= mount.mode == MountMode::ReadOnly;
+                continue;
+            }
+            if mount.name == "binnacle" && is_special("binnacle", source) {
+                continue;
+            }
+            if validated.resolved_source.as_os_str().is_empty() {
+                // No source and not a special mount: nothing to bind
+                continue;
+            }
+            extra.push(RunMount {
+                source: validated.resolved_source,
+                target: mount.target,
+                read_only: mount.mode == MountMode::ReadOnly,
+            });
+        }
+    }
+
+    let mut mounts = vec![
+        RunMount {
+            source: workspace.to_path_buf(),
+            target: "/workspace".to_string(),
+            read_only: workspace_read_only,
+        },
+        RunMount {
+            source: binnacle_data.to_path_buf(),
+            target: "/binnacle".to_string(),
+            read_only: false,
+        },
+    ];
+    mounts.extend(extra);
+    Ok(mounts)
+}
+
+// ============================================================================
+// containerd
+// ============================================================================
+
+/// Containerd runtime mode.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContainerdMode {
+    /// System-wide containerd using sudo (default socket at /run/containerd/containerd.sock)
+    System,
+    /// Rootless containerd (socket at $XDG_RUNTIME_DIR/containerd/containerd.sock)
+    /// This mode requires nsenter to access the rootlesskit namespace.
+    Rootless {
+        /// Path to the containerd socket (inside the namespace, typically /run/containerd/containerd.sock)
+        socket_path: String,
+        /// PID of the rootlesskit child process (for nsenter)
+        child_pid: u32,
+        /// Path to resolv.conf for DNS (from containerd-rootless directory)
+        resolv_conf_path: String,
+    },
+}
+
+/// Detect the containerd runtime mode.
+/// Checks for rootless containerd first, falls back to system containerd.
+///
+/// # Errors
+/// Returns an error if system containerd would be used but `BN_ALLOW_SUDO=1` is not set.
+/// This prevents binnacle from running sudo commands without explicit user consent.
+pub fn detect_containerd_mode() -> Result<ContainerdMode> {
+    // Check for rootless containerd setup at XDG_RUNTIME_DIR/containerd-rootless
+    if let Ok(xdg_runtime) = std::env::var("XDG_RUNTIME_DIR") {
+        let rootless_dir = format!("{}/containerd-rootless", xdg_runtime);
+        let child_pid_file = format!("{}/child_pid", rootless_dir);
+        let resolv_conf = format!("{}/resolv.conf", rootless_dir);
+
+        // Check if the rootless containerd is set up (has child_pid file)
+        if let Ok(pid_str) = fs::read_to_string(&child_pid_file) {
+            if let Ok(child_pid) = pid_str.trim().parse::<u32>() {
+                // Verify the process is still running
+                if Path::new(&format!("/proc/{}", child_pid)).exists() {
+                    return Ok(ContainerdMode::Rootless {
+                        // Inside the namespace, the socket is at the standard location
+                        socket_path: "/run/containerd/containerd.sock".to_string(),
+                        child_pid,
+                        resolv_conf_path: resolv_conf,
+                    });
+                }
+            }
+        }
+
+        // Also check for the simple rootless socket (without rootlesskit)
+        let rootless_socket = format!("{}/containerd/containerd.sock", xdg_runtime);
+        if Path::new(&rootless_socket).exists() {
+            // This is a simpler rootless setup without user namespaces
+            // It won't work for container run, but we detect it for better error messages
+            return Ok(ContainerdMode::Rootless {
+                socket_path: rootless_socket,
+                child_pid: 0, // No namespace to enter
+                resolv_conf_path: String::new(),
+            });
+        }
+    }
+
+    // Fall back to system containerd - but require explicit opt-in for sudo
+    if std::env::var("BN_ALLOW_SUDO")
+        .map(|v| v == "1")
+        .unwrap_or(false)
+    {
+        Ok(ContainerdMode::System)
+    } else {
+        Err(Error::Other(
+            "System containerd requires sudo, which is blocked by default.\n\n\
+             To enable sudo-based container operations:\n\n\
+                 export BN_ALLOW_SUDO=1\n\n\
+             Recommended: Set up rootless containerd instead (no sudo needed):\n\n\
+                 bn help container\n\n\
+             For more information, see the rootless containerd documentation."
+                .to_string(),
+        ))
+    }
+}
+
+/// Create a Command for ctr with the appropriate mode.
+/// For rootless mode, uses nsenter to enter the rootlesskit namespace.
+/// For system mode, uses sudo.
+pub(crate) fn ctr_command(mode: &ContainerdMode) -> Command {
+    match mode {
+        ContainerdMode::Rootless {
+            socket_path,
+            child_pid,
+            ..
+        } => {
+            if *child_pid > 0 {
+                // Use nsenter to enter the rootlesskit namespace
+                let mut cmd = Command::new("nsenter");
+                cmd.arg("-U")
+                    .arg("--preserve-credentials")
+                    .arg("-m")
+                    .arg("-n")
+                    .arg("-t")
+                    .arg(child_pid.to_string())
+                    .arg("ctr")
+                    .arg("-a")
+                    .arg(socket_path);
+                cmd
+            } else {
+                // Simple rootless mode (no namespace)
+                let mut cmd = Command::new("ctr");
+                cmd.arg("-a").arg(socket_path);
+                cmd
+            }
+        }
+        ContainerdMode::System => {
+            let mut cmd = Command::new("sudo");
+            cmd.arg("ctr");
+            cmd
+        }
+    }
+}
+
+/// Print a warning when using system containerd (requires sudo).
+/// This is called once per operation that needs containerd access.
+pub(crate) fn warn_system_containerd_mode(mode: &ContainerdMode) {
+    if matches!(mode, ContainerdMode::System) {
+        eprintln!("⚠️  Using system containerd (requires sudo)");
+        eprintln!("   For rootless operation without sudo, see: bn help container");
+    }
+}
+
+/// Check if an error message indicates permission denied on containerd socket
+/// and provide a helpful error message.
+pub(crate) fn check_containerd_permission_error(stderr: &str) -> Option<String> {
+    let stderr_lower = stderr.to_lowercase();
+
+    // Common permission denied patterns for containerd socket
+    if (stderr_lower.contains("permission denied")
+        && (stderr_lower.contains("containerd.sock")
+            || stderr_lower.contains("/run/containerd")
+            || stderr_lower.contains("connect to containerd")))
+        || stderr_lower.contains("failed to dial")
+    {
+        return Some(
+            "Permission denied accessing containerd socket.\n\
+             \n\
+             To fix this, run with sudo:\n\
+             \n\
+             \x20   sudo bn container run <worktree>\n\
+             \n\
+             Or set up rootless containerd (see: bn help container)"
+                .to_string(),
+        );
+    }
+
+    None
+}
+
+/// containerd, with images built by buildah and imported via `ctr`.
+pub struct Containerd {
+    mode: ContainerdMode,
+}
+
+impl Containerd {
+    pub fn new(mode: ContainerdMode) -> Self {
+        Self { mode }
+    }
+
+    fn ctr(&self) -> Command {
+        ctr_command(&self.mode)
+    }
+
+    /// Names of containers with a running task.
+    fn running_tasks(&self) -> Result<Vec<String>> {
+        let mut cmd = self.ctr();
+        cmd.args(["-n", "binnacle", "tasks", "list"]);
+        let stdout = run_checked(cmd, "Failed to list containerd tasks")?;
+        Ok(stdout
+            .lines()
+            .skip(1) // Skip header
+            .filter_map(|line| line.split_whitespace().next())
+            .map(|s| s.to_string())
+            .collect())
+    }
+}
+
+impl ContainerRuntime for Containerd {
+    fn kind(&self) -> RuntimeKind {
+        RuntimeKind::Containerd
+    }
+
+    fn build_image(&self, spec: &BuildSpec) -> Result<()> {
+        let mut build_cmd = Command::new("buildah");
+        build_cmd
+            .arg("bud")
+            .arg("--layers") // Enable layer caching for faster rebuilds
+            .arg("--network")
+            .arg("slirp4netns") // pasta fails with large route tables (e.g. VPN split-tunnel)
+            .arg("-t")
+            .arg(&spec.image)
+            .arg("-f")
+            .arg(&spec.containerfile)
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit());
+        if spec.no_cache {
+            build_cmd.arg("--no-cache");
+        }
+        build_cmd.arg(&spec.context);
+
+        if !build_cmd.status()?.success() {
+            return Err(Error::Other("Build failed (see output above)".to_string()));
+        }
+
+        // Export and import to containerd
+        // Use docker-archive format (not oci-archive) because it embeds the full image
+        // reference in the manifest, allowing ctr to import it with the correct name.
+        // OCI archives require annotations that buildah doesn't always include.
+        eprintln!("📤 Exporting image to docker archive...");
+        let archive_name = spec.image.replace(['/', ':'], "-");
+        let temp_archive =
+            crate::commands::get_binnacle_temp_dir()?.join(format!("{}.tar", archive_name));
+        // Remove existing archive if present (docker-archive can't overwrite)
+        if temp_archive.exists() {
+            let _ = fs::remove_file(&temp_archive);
+        }
+        let temp_archive_str = temp_archive.to_string_lossy();
+
+        let mut push_cmd = Command::new("buildah");
+        push_cmd.args([
+            "push",
+            &spec.image,
+            &format!("docker-archive:{}:{}", temp_archive_str, spec.image),
+        ]);
+        run_checked(push_cmd, "Failed to export image")?;
+
+        eprintln!("📥 Importing image to containerd...");
+        let import_output = self
+            .ctr()
+            .args([
+                "-n",
+                "binnacle",
+                "images",
+                "import",
+                temp_archive_str.as_ref(),
+            ])
+            .output()?;
+
+        // Clean up temp file
+        eprintln!("🧹 Cleaning up...");
+        let _ = fs::remove_file(&temp_archive);
+
+        if !import_output.status.success() {
+            let stderr = String::from_utf8_lossy(&import_output.stderr);
+
+            // Check for permission denied errors and provide helpful message
+            let error_msg = if let Some(helpful_msg) = check_containerd_permission_error(&stderr) {
+                helpful_msg
+            } else {
+                format!("Failed to import image to containerd: {}", stderr)
+            };
+
+            return Err(Error::Other(error_msg));
+        }
+
+        Ok(())
+    }
+
+    fn image_exists(&self, image: &str) -> Result<bool> {
+        let filter = format!("name=={}", image);
+        Ok(self
+            .ctr()
+            .args(["-n", "binnacle", "images", "check", &filter])
+            .output()
+            .map(|o| {
+                // The output includes a header line, so we need more than 1 line
+                o.status.success() && String::from_utf8_lossy(&o.stdout).lines().count() > 1
+            })
+            .unwrap_or(false))
+    }
+
+    fn run_command(&self, spec: &RunSpec) -> Result<Command> {
+        let mut args = vec![
+            "-n".to_string(),
+            "binnacle".to_string(),
+            "run".to_string(),
+            "--rm".to_string(),
+        ];
+
+        // ctr panics if --tty is used without a real console
+        if spec.tty {
+            args.push("--tty".to_string());
+        }
+
+        // Handle networking based on containerd mode
+        match &self.mode {
+            ContainerdMode::Rootless {
+                child_pid,
+                resolv_conf_path,
+                ..
+            } if *child_pid > 0 => {
+                // Rootless mode: disable cgroups (permission issues) and share network namespace
+                args.push("--cgroup".to_string());
+                args.push(String::new()); // Empty string disables cgroup
+
+                // Share network namespace with rootlesskit for slirp4netns connectivity
+                args.push("--with-ns".to_string());
+                args.push(format!("network:/proc/{}/ns/net", child_pid));
+
+                // Mount resolv.conf for DNS resolution
+                // Priority: rootlesskit state dir > namespace's /etc/resolv.conf
+                // rootlesskit with slirp4netns/pasta creates resolv.conf inside the
+                // namespace via --copy-up=/etc. Since ctr runs inside the namespace
+                // (via nsenter), /etc/resolv.conf points to the namespace's copy.
+                let resolv = if Path::new(resolv_conf_path).exists() {
+                    resolv_conf_path.as_str()
+                } else {
+                    "/etc/resolv.conf"
+                };
+                args.push("--mount".to_string());
+                args.push(format!(
+                    "type=bind,src={},dst=/etc/resolv.conf,options=rbind:ro",
+                    resolv
+                ));
+                // In rootless mode with user namespaces, container UID 0 maps to host user
+                // So we don't need --user flag - running as root inside = host user outside
+            }
+            _ => {
+                // SECURITY: Host networking removes network namespace isolation.
+                // Required for AI agent API calls (OpenAI, Anthropic, etc.) and package installs.
+                // The container can access all host network interfaces including localhost services.
+                args.push("--net-host".to_string());
+
+                // Run as host user's UID/GID to preserve file ownership in mounted workspace
+                // Container uses nss_wrapper to provide user identity for Node.js, git, etc.
+                // SECURITY: User mapping is mandatory to prevent running as root
+                let (uid, gid) = spec.user.ok_or_else(|| {
+                    Error::Other(
+                        "Container cannot run without user mapping (workspace owner unknown)"
+                            .to_string(),
+                    )
+                })?;
+                args.push("--user".to_string());
+                args.push(format!("{}:{}", uid, gid));
+            }
+        }
+
+        if let Some(cpus) = spec.cpus {
+            args.push("--cpus".to_string());
+            args.push(cpus.to_string());
+        }
+        if let Some(bytes) = spec.memory_bytes {
+            args.push("--memory-limit".to_string());
+            args.push(bytes.to_string());
+        }
+
+        for mount in &spec.mounts {
+            args.push("--mount".to_string());
+            args.push(format!(
+                "type=bind,src={},dst={},options=rbind:{}",
+                mount.source.display(),
+                mount.target,
+                if mount.read_only { "ro" } else { "rw" }
+            ));
+        }
+
+        for (key, value) in &spec.env {
+            args.push("--env".to_string());
+            args.push(format!("{}={}", key, value));
+        }
+
+        args.push(spec.image.clone());
+        args.push(spec.name.clone());
+        args.extend(spec.command.iter().cloned());
+
+        let mut cmd = self.ctr();
+        cmd.args(&args);
+        Ok(cmd)
+    }
+
+    fn is_running(&self, name: &str) -> Result<bool> {
+        Ok(self.running_tasks()?.iter().any(|task| task == name))
+    }
+
+    fn signal(&self, name: &str, signal: i32) -> Result<()> {
+        let mut cmd = self.ctr();
+        cmd.args([
+            "-n",
+            "binnacle",
+            "tasks",
+            "kill",
+            "--signal",
+            &signal.to_string(),
+            name,
+        ]);
+        run_checked(cmd, "Failed to signal container").map(|_| ())
+    }
+
+    fn remove(&self, name: &str) -> Result<()> {
+        let mut cmd = self.ctr();
+        cmd.args(["-n", "binnacle", "containers", "rm", name]);
+        run_checked(cmd, "Failed to remove container").map(|_| ())
+    }
+
+    fn list(&self) -> Result<Vec<RuntimeContainer>> {
+        let output = self
+            .ctr()
+            .args(["-n", "binnacle", "containers", "list"])
+            .output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(Error::Other(
+                check_containerd_permission_error(&stderr).unwrap_or_else(|| stderr.to_string()),
+            ));
+        }
+
+        let running = self.running_tasks().unwrap_or_default();
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let parts: Vec<&str> = line.split_whitespace().collect();
+                (parts.len() >= 2).then(|| RuntimeContainer {
+                    name: parts[0].to_string(),
+                    image: parts[1].to_string(),
+                    running: running.iter().any(|t| t == parts[0]),
+                })
+            })
+            .collect())
+    }
+}
+
+// ============================================================================
+// podman / docker
+// ============================================================================
+
+/// A docker-compatible CLI (podman or docker).
+pub struct OciCli {
+    kind: RuntimeKind,
+    program: &'static str,
+}
+
+impl OciCli {
+    pub fn podman() -> Self {
+        Self {
+            kind: RuntimeKind::Podman,
+            program: "podman",
+        }
+    }
+
+    pub fn docker() -> Self {
+        Self {
+            kind: RuntimeKind::Docker,
+            program: "docker",
+        }
+    }
+
+    fn cli(&self) -> Command {
+        Command::new(self.program)
+    }
+}
+
+impl ContainerRuntime for OciCli {
+    fn kind(&self) -> RuntimeKind {
+        self.kind
+    }
+
+    fn build_image(&self, spec: &BuildSpec) -> Result<()> {
+        let mut cmd = self.cli();
+        cmd.arg("build");
+        if self.kind == RuntimeKind::Podman {
+            cmd.arg("--layers");
+        }
+        cmd.arg("-t")
+            .arg(&spec.image)
+            .arg("-f")
+            .arg(&spec.containerfile)
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit());
+        if spec.no_cache {
+            cmd.arg("--no-cache");
+        }
+        cmd.arg(&spec.context);
+
+        if !cmd.status()?.success() {
+            return Err(Error::Other("Build failed (see output above)".to_string()));
+        }
+        Ok(())
+    }
+
+    fn image_exists(&self, image: &str) -> Result<bool> {
+        Ok(self
+            .cli()
+            .args(["image", "inspect", image])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false))
+    }
+
+    fn run_command(&self, spec: &RunSpec) -> Result<Command> {
+        let mut cmd = self.cli();
+        cmd.args(["run", "--rm", "-i", "--name", &spec.name])
+            .arg("--label")
+            .arg(format!("{}=1", CONTAINER_LABEL));
+        if spec.tty {
+            cmd.arg("-t");
+        }
+
+        // SECURITY: Host networking removes network namespace isolation.
+        // Required for AI agent API calls and package installs.
+        cmd.args(["--network", "host"]);
+
+        // Preserve file ownership in the mounted workspace
+        // SECURITY: User mapping is mandatory to prevent running as root
+        let (uid, gid) = spec.user.ok_or_else(|| {
+            Error::Other(
+                "Container cannot run without user mapping (workspace owner unknown)".to_string(),
+            )
+        })?;
+        if self.kind == RuntimeKind::Podman && uid != 0 {
+            // Rootless podman maps the host user into the container as itself
+            cmd.arg("--userns=keep-id");
+        } else {
+            cmd.arg("--user").arg(format!("{}:{}", uid, gid));
+        }
+
+        if let Some(cpus) = spec.cpus {
+            cmd.arg("--cpus").arg(cpus.to_string());
+        }
+        if let Some(bytes) = spec.memory_bytes {
+            cmd.arg("--memory").arg(bytes.to_string());
+        }
+
+        for mount in &spec.mounts {
+            let mut value = format!(
+                "type=bind,source={},target={}",
+                mount.source.display(),
+                mount.target
+            );
+            if mount.read_only {
+                value.push_str(",readonly");
+            }
+            cmd.arg("--mount").arg(value);
+        }
+
+        for (key, value) in &spec.env {
+            cmd.arg("--env").arg(format!("{}={}", key, value));
+        }
+
+        // Override the entrypoint (ctr replaces the whole process, so match that)
+        let mut command = spec.command.iter();
+        if let Some(entrypoint) = command.next() {
+            cmd.arg("--entrypoint").arg(entrypoint);
+        }
+        cmd.arg(&spec.image);
+        cmd.args(command);
+        Ok(cmd)
+    }
+
+    fn is_running(&self, name: &str) -> Result<bool> {
+        let output = self
+            .cli()
+            .args(["inspect", "--format", "{{.State.Running}}", name])
+            .output()?;
+        Ok(output.status.success() && String::from_utf8_lossy(&output.stdout).trim() == "true")
+    }
+
+    fn signal(&self, name: &str, signal: i32) -> Result<()> {
+        let mut cmd = self.cli();
+        cmd.args(["kill", "--signal", &signal.to_string(), name]);
+        run_checked(cmd, "Failed to signal container").map(|_| ())
+    }
+
+    fn remove(&self, name: &str) -> Result<()> {
+        let output = self.cli().args(["rm", "-f", name]).output()?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // Containers run with --rm disappear on their own once stopped
+        if output.status.success() || stderr.to_lowercase().contains("no such container") {
+            Ok(())
+        } else {
+            Err(Error::Other(format!(
+                "Failed to remove container: {}",
+                stderr.trim()
+            )))
+        }
+    }
+
+    fn list(&self) -> Result<Vec<RuntimeContainer>> {
+        let mut cmd = self.cli();
+        cmd.args([
+            "ps",
+            "-a",
+            "--filter",
+            &format!("label={}", CONTAINER_LABEL),
+            "--format",
+            "{{.Names}}\t{{.Image}}\t{{.State}}",
+        ]);
+        let stdout = run_checked(cmd, &format!("`{} ps` failed", self.program))?;
+        Ok(parse_ps_output(&stdout))
+    }
+}
+
+/// Parse `ps --format '{{.Names}}\t{{.Image}}\t{{.State}}'` output.
+fn parse_ps_output(stdout: &str) -> Vec<RuntimeContainer> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split('\t');
+            let name = parts.next()?.trim();
+            let image = parts.next()?.trim();
+            let state = parts.next().unwrap_or("").trim();
+            (!name.is_empty()).then(|| RuntimeContainer {
+                name: name.to_string(),
+                image: image.to_string(),
+                running: state.eq_ignore_ascii_case("running"),
+            })
+        })
+        .collect()
+}
+
+// ============================================================================
+// bubblewrap
+// ============================================================================
+
+const EMBEDDED_ENTRYPOINT: &[u8] = include_bytes!("../../container/entrypoint.sh");
+const EMBEDDED_BN_ENTRY: &[u8] = include_bytes!("../../container/bn-entry.sh");
+const EMBEDDED_GIT_WRAPPER: &[u8] = include_bytes!("../../container/git-wrapper.sh");
+
+/// Directory inside the sandbox holding `bn` and the git wrapper.
+const SANDBOX_BIN_DIR: &str = "/.binnacle/bin";
+
+/// Top-level host directories not bound into the sandbox.
+const SANDBOX_SKIPPED_ROOTS: &[&str] = &[
+    "proc",
+    "dev",
+    "tmp",
+    "workspace",
+    "binnacle",
+    "lost+found",
+    ".binnacle",
+];
+
+/// A bubblewrap sandbox over the host filesystem.
+///
+/// There are no images: the host root is bound read-only and the embedded
+/// entrypoint scripts plus the running `bn` binary are bound at the paths
+/// the container images use, so the host needs the agent's tooling
+/// installed. Processes are tracked with pid files. CPU and memory limits
+/// are applied through `systemd-run --user --scope` when available.
+pub struct Bubblewrap {
+    state_dir: PathBuf,
+}
+
+impl Bubblewrap {
+    pub fn new() -> Result<Self> {
+        let state_dir = crate::commands::get_binnacle_temp_dir()?.join("bwrap");
+        fs::create_dir_all(&state_dir)?;
+        Ok(Self { state_dir })
+    }
+
+    fn pid_file(&self, name: &str) -> PathBuf {
+        self.state_dir.join(format!("{}.pid", name))
+    }
+
+    fn pid(&self, name: &str) -> Option<u32> {
+        fs::read_to_string(self.pid_file(name))
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+    }
+
+    /// Write the entry scripts the images would contain.
+    fn write_entry_files(&self) -> Result<PathBuf> {
+        let dir = self.state_dir.join("entry");
+        fs::create_dir_all(&dir)?;
+        for (name, content) in [
+            ("entrypoint.sh", EMBEDDED_ENTRYPOINT),
+            ("bn-entry.sh", EMBEDDED_BN_ENTRY),
+            ("git", EMBEDDED_GIT_WRAPPER),
+        ] {
+            let path = dir.join(name);
+            fs::write(&path, content)?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+            }
+        }
+        Ok(dir)
+    }
+
+    /// bwrap arguments for a spec, given the entry files directory.
+    fn bwrap_args(&self, spec: &RunSpec, entry_dir: &Path, bn_binary: &Path) -> Vec<String> {
+        let mut args: Vec<String> = [
+            "--die-with-parent",
+            "--unshare-all",
+            "--share-net",
+            "--hostname",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        args.push(spec.name.clone());
+
+        // Host root, read-only, minus the pseudo filesystems set up below
+        if let Ok(entries) = fs::read_dir("/") {
+            let mut entries: Vec<_> = entries.flatten().collect();
+            entries.sort_by_key(|e| e.file_name());
+            for entry in entries {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if SANDBOX_SKIPPED_ROOTS.contains(&name.as_str()) {
+                    continue;
+                }
+                let path = format!("/{}", name);
+                match fs::read_link(&path) {
+                    Ok(target) => {
+                        args.push("--symlink".to_string());
+                        args.push(target.display().to_string());
+                        args.push(path);
+                    }
+                    Err(_) if entry.path().is_dir() => {
+                        args.push("--ro-bind".to_string());
+                        args.push(path.clone());
+                        args.push(path);
+                    }
+                    Err(_) => {}
+                }
+            }
+        }
+        for arg in ["--dev", "/dev", "--proc", "/proc", "--tmpfs", "/tmp"] {
+            args.push(arg.to_string());
+        }
+
+        // What the image would provide
+        let mut ro_bind = |src: &Path, dst: &str| {
+            args.push("--ro-bind".to_string());
+            args.push(src.display().to_string());
+            args.push(dst.to_string());
+        };
+        ro_bind(&entry_dir.join("entrypoint.sh"), "/entrypoint.sh");
+        ro_bind(&entry_dir.join("bn-entry.sh"), "/bn-entry.sh");
+        ro_bind(bn_binary, &format!("{}/bn", SANDBOX_BIN_DIR));
+        ro_bind(&entry_dir.join("git"), &format!("{}/git", SANDBOX_BIN_DIR));
+
+        for mount in &spec.mounts {
+            args.push(
+                if mount.read_only {
+                    "--ro-bind"
+                } else {
+                    "--bind"
+                }
+                .to_string(),
+            );
+            args.push(mount.source.display().to_string());
+            args.push(mount.target.clone());
+        }
+
+        let path = std::env::var("PATH").unwrap_or_else(|_| "/usr/bin:/bin".to_string());
+        args.push("--setenv".to_string());
+        args.push("PATH".to_string());
+        args.push(format!("{}:{}", SANDBOX_BIN_DIR, path));
+        for (key, value) in &spec.env {
+            args.push("--setenv".to_string());
+            args.push(key.clone());
+            args.push(value.clone());
+        }
+
+        args.push("--chdir".to_string());
+        args.push("/workspace".to_string());
+        args.push("--".to_string());
+        if spec.command.is_empty() {
+            args.push("/entrypoint.sh".to_string());
+        } else {
+            args.extend(spec.command.iter().cloned());
+        }
+        args
+    }
+}
+
+impl ContainerRuntime for Bubblewrap {
+    fn kind(&self) -> RuntimeKind {
+        RuntimeKind::Bubblewrap
+    }
+
+    fn build_image(&self, spec: &BuildSpec) -> Result<()> {
+        eprintln!(
+            "⏭️  bubblewrap runs on the host filesystem; skipping image build for {}",
+            spec.image
+        );
+        Ok(())
+    }
+
+    fn image_exists(&self, _image: &str) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn run_command(&self, spec: &RunSpec) -> Result<Command> {
+        let entry_dir = self.write_entry_files()?;
+        let bn_binary = std::env::current_exe()
+            .map_err(|e| Error::Other(format!("Failed to get current executable path: {}", e)))?;
+
+        // Record the sandbox PID, then exec into the sandbox
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg("echo $$ > \"$0\"; exec \"$@\"")
+            .arg(self.pid_file(&spec.name));
+
+        if spec.cpus.is_some() || spec.memory_bytes.is_some() {
+            if command_exists("systemd-run") {
+                cmd.args(["systemd-run", "--user", "--scope", "--quiet"]);
+                if let Some(cpus) = spec.cpus {
+                    cmd.arg("-p")
+                        .arg(format!("CPUQuota={}%", (cpus * 100.0).round() as u64));
+                }
+                if let Some(bytes) = spec.memory_bytes {
+                    cmd.arg("-p").arg(format!("MemoryMax={}", bytes));
+                }
+            } else {
+                eprintln!(
+                    "{}",
+                    errors::format_warning(
+                        errors::ErrorCategory::Run,
+                        "resource limits ignored",
+                        Some(
+                            "bubblewrap applies cpus/memory via systemd-run, which was not found."
+                        ),
+                    )
+                );
+            }
+        }
+
+        cmd.arg("bwrap")
+            .args(self.bwrap_args(spec, &entry_dir, &bn_binary));
+        Ok(cmd)
+    }
+
+    fn is_running(&self, name: &str) -> Result<bool> {
+        Ok(self
+            .pid(name)
+            .is_some_and(|pid| Path::new(&format!("/proc/{}", pid)).exists()))
+    }
+
+    fn signal(&self, name: &str, signal: i32) -> Result<()> {
+        let pid = self
+            .pid(name)
+            .ok_or_else(|| Error::NotFound(format!("Container not found: {}", name)))?;
+        let mut cmd = Command::new("kill");
+        cmd.arg(format!("-{}", signal)).arg(pid.to_string());
+        run_checked(cmd, "Failed to signal container").map(|_| ())
+    }
+
+    fn remove(&self, name: &str) -> Result<()> {
+        if self.is_running(name)? {
+            return Err(Error::Other(format!(
+                "Failed to remove container: {} is still running",
+                name
+            )));
+        }
+        match fs::remove_file(self.pid_file(name)) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn list(&self) -> Result<Vec<RuntimeContainer>> {
+        let mut containers = Vec::new();
+        for entry in fs::read_dir(&self.state_dir)?.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "pid")
+                && let Some(name) = path.file_stem().map(|s| s.to_string_lossy().to_string())
+            {
+                containers.push(RuntimeContainer {
+                    running: self.is_running(&name)?,
+                    name,
+                    image: "host".to_string(),
+                });
+            }
+        }
+        containers.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(containers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::container::Mount;
+
+    fn args(cmd: &Command) -> Vec<String> {
+        cmd.get_args()
+            .map(|a| a.to_string_lossy().to_string())
+            .collect()
+    }
+
+    fn spec() -> RunSpec {
+        RunSpec {
+            image: "localhost/binnacle-self:latest".to_string(),
+            name: "binnacle-worker-a1b2".to_string(),
+            mounts: vec![
+                RunMount {
+                    source: PathBuf::from("/repo"),
+                    target: "/workspace".to_string(),
+                    read_only: false,
+                },
+                RunMount {
+                    source: PathBuf::from("/cache"),
+                    target: "/cache".to_string(),
+                    read_only: true,
+                },
+            ],
+            env: vec![("BN_AGENT_ID".to_string(), "bn-a1b2".to_string())],
+            cpus: Some(2.0),
+            memory_bytes: Some(1024),
+            user: Some((1000, 1000)),
+            command: vec!["/entrypoint.sh".to_string(), "shell".to_string()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_runtime_kind_parse() {
+        assert_eq!(
+            "podman".parse::<RuntimeKind>().unwrap(),
+            RuntimeKind::Podman
+        );
+        assert_eq!(
+            "bwrap".parse::<RuntimeKind>().unwrap(),
+            RuntimeKind::Bubblewrap
+        );
+        assert_eq!(
+            "Containerd".parse::<RuntimeKind>().unwrap(),
+            RuntimeKind::Containerd
+        );
+        assert!("lxc".parse::<RuntimeKind>().is_err());
+        for kind in RuntimeKind::ALL {
+            assert_eq!(kind.to_string().parse::<RuntimeKind>().unwrap(), kind);
+        }
+    }
+
+    #[test]
+    fn test_parse_runtime_node() {
+        let doc: KdlDocument = "runtime \"docker\"\ncontainer \"x\" {}".parse().unwrap();
+        assert_eq!(parse_runtime_node(&doc).unwrap(), Some(RuntimeKind::Docker));
+
+        let doc: KdlDocument = "runtime \"auto\"".parse().unwrap();
+        assert_eq!(parse_runtime_node(&doc).unwrap(), None);
+
+        let doc: KdlDocument = "container \"x\" {}".parse().unwrap();
+        assert_eq!(parse_runtime_node(&doc).unwrap(), None);
+
+        let doc: KdlDocument = "runtime \"lxc\"".parse().unwrap();
+        assert!(parse_runtime_node(&doc).is_err());
+    }
+
+    #[test]
+    fn test_definition_mounts() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache = temp.path().join("cache");
+        fs::create_dir_all(&cache).unwrap();
+
+        let def = ContainerDefinition {
+            name: "worker".to_string(),
+            description: None,
+            parent: None,
+            defaults: None,
+            mounts: vec![
+                Mount {
+                    name: "workspace".to_string(),
+                    source: None,
+                    target: "/workspace".to_string(),
+                    mode: MountMode::ReadOnly,
+                    optional: false,
+                },
+                Mount {
+                    name: "cache".to_string(),
+                    source: Some("cache".to_string()),
+                    target: "/cache".to_string(),
+                    mode: MountMode::ReadOnly,
+                    optional: false,
+                },
+                Mount {
+                    name: "missing".to_string(),
+                    source: Some("nope".to_string()),
+                    target: "/nope".to_string(),
+                    mode: MountMode::ReadWrite,
+                    optional: true,
+                },
+            ],
+        };
+
+        let mounts = definition_mounts(
+            Some(&def),
+            temp.path(),
+            Path::new("/wt"),
+            false,
+            Path::new("/data"),
+        )
+        .unwrap();
+        assert_eq!(mounts.len(), 3);
+        assert_eq!(mounts[0].target, "/workspace");
+        assert!(
+            mounts[0].read_only,
+            "definition made the workspace read-only"
+        );
+        assert_eq!(mounts[1].source, PathBuf::from("/data"));
+        assert_eq!(mounts[2].source, cache);
+        assert!(mounts[2].read_only);
+
+        // Without a definition only the standard mounts are present
+        let mounts =
+            definition_mounts(None, temp.path(), Path::new("/wt"), false, Path::new("/d")).unwrap();
+        assert_eq!(mounts.len(), 2);
+        assert!(!mounts[0].read_only);
+    }
+
+    #[test]
+    fn test_containerd_run_command() {
+        let runtime = Containerd::new(ContainerdMode::System);
+        let cmd = runtime.run_command(&spec()).unwrap();
+        assert_eq!(cmd.get_program(), "sudo");
+        let args = args(&cmd).join(" ");
+        assert!(args.contains("--net-host --user 1000:1000 --cpus 2 --memory-limit 1024"));
+        assert!(args.contains("type=bind,src=/cache,dst=/cache,options=rbind:ro"));
+        assert!(args.contains("--env BN_AGENT_ID=bn-a1b2"));
+        assert!(
+            args.ends_with(
+                "localhost/binnacle-self:latest binnacle-worker-a1b2 /entrypoint.sh shell"
+            )
+        );
+
+        // System mode refuses to run as root without a user mapping
+        let no_user = RunSpec {
+            user: None,
+            ..spec()
+        };
+        assert!(runtime.run_command(&no_user).is_err());
+    }
+
+    #[test]
+    fn test_oci_run_command() {
+        let cmd = OciCli::podman().run_command(&spec()).unwrap();
+        assert_eq!(cmd.get_program(), "podman");
+        let podman = args(&cmd).join(" ");
+        assert!(podman.contains("--userns=keep-id"));
+        assert!(podman.contains("--mount type=bind,source=/cache,target=/cache,readonly"));
+        assert!(podman.contains("--memory 1024"));
+        assert!(
+            podman.ends_with("--entrypoint /entrypoint.sh localhost/binnacle-self:latest shell")
+        );
+
+        let docker = args(&OciCli::docker().run_command(&spec()).unwrap()).join(" ");
+        assert!(docker.contains("--user 1000:1000"));
+        assert!(docker.contains("--label binnacle=1"));
+        assert!(!docker.contains("--userns"));
+
+        // Like containerd, podman and docker refuse to run without a user mapping
+        let no_user = RunSpec {
+            user: None,
+            ..spec()
+        };
+        assert!(OciCli::podman().run_command(&no_user).is_err());
+        assert!(OciCli::docker().run_command(&no_user).is_err());
+    }
+
+    #[test]
+    fn test_parse_ps_output() {
+        let containers = parse_ps_output(
+            "binnacle-worker-a1b2\tlocalhost/binnacle-self:latest\trunning\nold\timg\texited\n",
+        );
+        assert_eq!(containers.len(), 2);
+        assert!(containers[0].running);
+        assert_eq!(containers[1].image, "img");
+        assert!(!containers[1].running);
+    }
+
+    #[test]
+    fn test_bubblewrap_args() {
+        let temp = tempfile::tempdir().unwrap();
+        let runtime = Bubblewrap {
+            state_dir: temp.path().to_path_buf(),
+        };
+        let args = runtime.bwrap_args(&spec(), Path::new("/entry"), Path::new("/bin/bn"));
+        let joined = args.join(" ");
+        assert!(joined.starts_with("--die-with-parent --unshare-all --share-net"));
+        assert!(joined.contains("--ro-bind /entry/entrypoint.sh /entrypoint.sh"));
+        assert!(joined.contains("--ro-bind /bin/bn /.binnacle/bin/bn"));
+        assert!(joined.contains("--bind /repo /workspace"));
+        assert!(joined.contains("--ro-bind /cache /cache"));
+        assert!(joined.contains("--setenv BN_AGENT_ID bn-a1b2"));
+        assert!(joined.ends_with("--chdir /workspace -- /entrypoint.sh shell"));
+        assert!(!args.iter().any(|a| a == "/proc/self"));
+
+        // Pid files drive list/is_running
+        fs::write(runtime.pid_file("gone"), "999999999").unwrap();
+        let listed = runtime.list().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert!(!listed[0].running);
+        runtime.remove("gone").unwrap();
+        assert!(runtime.list().unwrap().is_empty());
+    }
+}
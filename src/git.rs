@@ -0,0 +1,427 @@
+//! Read-only queries against the user's git repository.
+//!
+//! Commands that look at history (commit scans, blame, changelogs, archives,
+//! affected tests) go through these helpers rather than spawning git
+//! themselves. Worktree and branch management lives in
+//! [`crate::agents::worktree`], which shares the runner below.
+
+use chrono::{DateTime, Utc};
+use std::path::Path;
+use std::process::Command;
+
+use crate::{Error, Result};
+
+/// Run git in `dir` and return its trimmed stdout.
+pub(crate) fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| Error::Other(format!("Failed to run git: {}", e)))?;
+    if !output.status.success() {
+        return Err(Error::Other(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Whether git succeeds in `dir`, discarding output.
+pub(crate) fn git_ok(dir: &Path, args: &[&str]) -> bool {
+    Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .is_ok_and(|o| o.status.success())
+}
+
+/// The branch checked out in `dir`, if any.
+pub fn current_branch(dir: &Path) -> Option<String> {
+    git(dir, &["symbolic-ref", "--quiet", "--short", "HEAD"]).ok()
+}
+
+/// The repository's default branch: what `origin/HEAD` points at, else a
+/// local `main` or `master`. Never falls back to the checked-out branch.
+pub fn default_branch(dir: &Path) -> Option<String> {
+    if let Ok(remote_head) = git(
+        dir,
+        &[
+            "symbolic-ref",
+            "--quiet",
+            "--short",
+            "refs/remotes/origin/HEAD",
+        ],
+    ) && rev_parse(dir, &remote_head).is_ok()
+    {
+        return Some(remote_head);
+    }
+    ["main", "master"]
+        .into_iter()
+        .find(|branch| {
+            git_ok(
+                dir,
+                &[
+                    "rev-parse",
+                    "--verify",
+                    "--quiet",
+                    &format!("refs/heads/{}", branch),
+                ],
+            )
+        })
+        .map(str::to_string)
+}
+
+/// Whether `dir` has uncommitted changes.
+pub fn is_dirty(dir: &Path) -> bool {
+    git(dir, &["status", "--porcelain"]).is_ok_and(|s| !s.is_empty())
+}
+
+/// Resolve a revision to a commit SHA.
+pub fn rev_parse(dir: &Path, rev: &str) -> Result<String> {
+    git(
+        dir,
+        &["rev-parse", "--verify", &format!("{}^{{commit}}", rev)],
+    )
+}
+
+/// Commits reachable from `to` but not from `from`, oldest first.
+pub fn commits_between(dir: &Path, from: &str, to: &str) -> Result<Vec<String>> {
+    let range = format!("{}..{}", from, to);
+    Ok(git(dir, &["rev-list", "--reverse", &range])?
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// Commits on `rev` made after `since`, oldest first, as `<short sha> <subject>`.
+pub fn commits_since(dir: &Path, rev: &str, since: DateTime<Utc>) -> Result<Vec<String>> {
+    let since = format!("--since={}", since.to_rfc3339());
+    Ok(git(
+        dir,
+        &[
+            "log",
+            "--reverse",
+            "--no-merges",
+            "--format=%h %s",
+            &since,
+            rev,
+        ],
+    )?
+    .lines()
+    .map(str::to_string)
+    .collect())
+}
+
+/// Files that differ between `rev` and the working tree of `dir`, including
+/// untracked ones, as paths relative to the repository root.
+pub fn changed_files(dir: &Path, rev: &str) -> Result<Vec<String>> {
+    let commit = rev_parse(dir, rev)?;
+    let mut files: Vec<String> = git(dir, &["diff", "--name-only", &commit])?
+        .lines()
+        .chain(
+            git(
+                dir,
+                &["ls-files", "--others", "--exclude-standard", "--full-name"],
+            )?
+            .lines(),
+        )
+        .map(str::to_string)
+        .collect();
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Files touched by a single commit, relative to the repository root.
+pub fn files_in_commit(dir: &Path, sha: &str) -> Result<Vec<String>> {
+    Ok(git(
+        dir,
+        &["show", "--name-only", "--format=", "--no-renames", sha],
+    )?
+    .lines()
+    .filter(|l| !l.is_empty())
+    .map(str::to_string)
+    .collect())
+}
+
+/// A commit and its full message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommitMessage {
+    pub sha: String,
+    pub committed_at: DateTime<Utc>,
+    pub message: String,
+}
+
+/// Commits on HEAD, oldest first, with their messages. With `since`, only
+/// commits made after that revision.
+pub fn commit_log(dir: &Path, since: Option<&str>) -> Result<Vec<CommitMessage>> {
+    let range = match since {
+        Some(rev) => format!("{}..HEAD", rev_parse(dir, rev)?),
+        None => "HEAD".to_string(),
+    };
+    let log = git(
+        dir,
+        &["log", "--reverse", "--format=%H%x1f%cI%x1f%B%x1e", &range],
+    )?;
+    Ok(log
+        .split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_start().splitn(3, '\x1f');
+            let sha = fields.next().filter(|s| !s.is_empty())?.to_string();
+            let committed_at = DateTime::parse_from_rfc3339(fields.next()?)
+                .ok()?
+                .with_timezone(&Utc);
+            let message = fields.next().unwrap_or_default().trim().to_string();
+            Some(CommitMessage {
+                sha,
+                committed_at,
+                message,
+            })
+        })
+        .collect())
+}
+
+/// When `sha` was committed (its committer date).
+pub fn committed_at(dir: &Path, sha: &str) -> Result<DateTime<Utc>> {
+    let date = git(dir, &["show", "-s", "--format=%cI", sha])?;
+    DateTime::parse_from_rfc3339(&date)
+        .map(|d| d.with_timezone(&Utc))
+        .map_err(|e| Error::Other(format!("Bad commit date for {}: {}", sha, e)))
+}
+
+/// `rev` and the commits reachable from it, newest first.
+pub fn ancestors(dir: &Path, rev: &str) -> Result<Vec<String>> {
+    Ok(git(dir, &["rev-list", rev])?
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// One line of `git blame` output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlameLine {
+    /// 1-based line number in the current file
+    pub line: usize,
+    /// Commit that last changed the line (all zeros if uncommitted)
+    pub sha: String,
+    /// Subject of that commit
+    pub summary: String,
+    pub content: String,
+}
+
+/// Blame `file` (relative to `dir`), optionally only lines `start..=end`.
+pub fn blame(dir: &Path, file: &str, lines: Option<(usize, usize)>) -> Result<Vec<BlameLine>> {
+    let range = lines.map(|(start, end)| format!("{},{}", start, end));
+    let mut args = vec!["blame", "--line-porcelain"];
+    if let Some(range) = &range {
+        args.extend(["-L", range.as_str()]);
+    }
+    args.extend(["--", file]);
+
+    let output = Command::new("git")
+        .args(&args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| Error::Other(format!("Failed to run git: {}", e)))?;
+    if !output.status.success() {
+        return Err(Error::Other(format!(
+            "git blame failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    // Each line is a header `<sha> <orig> <final> [<count>]`, then
+    // `key value` fields, then the content prefixed with a tab
+    let mut blamed = Vec::new();
+    let mut current: Option<BlameLine> = None;
+    for raw in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(content) = raw.strip_prefix('\t') {
+            if let Some(mut line) = current.take() {
+                line.content = content.to_string();
+                blamed.push(line);
+            }
+        } else if let Some(line) = current.as_mut() {
+            if let Some(summary) = raw.strip_prefix("summary ") {
+                line.summary = summary.to_string();
+            }
+        } else {
+            let mut fields = raw.split(' ');
+            let sha = fields.next().unwrap_or_default();
+            let final_line = fields.nth(1).and_then(|n| n.parse().ok()).unwrap_or(0);
+            current = Some(BlameLine {
+                line: final_line,
+                sha: sha.to_string(),
+                summary: String::new(),
+                content: String::new(),
+            });
+        }
+    }
+    Ok(blamed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TestEnv;
+    use std::fs;
+
+    fn init_repo(dir: &Path) {
+        for args in [
+            vec!["init", "-q", "-b", "main"],
+            vec!["config", "user.email", "test@example.com"],
+            vec!["config", "user.name", "Test"],
+        ] {
+            git(dir, &args).unwrap();
+        }
+        fs::write(dir.join("README.md"), "hello\n").unwrap();
+        git(dir, &["add", "-A"]).unwrap();
+        git(dir, &["commit", "-q", "-m", "initial"]).unwrap();
+    }
+
+    fn commit_file(dir: &Path, name: &str, content: &str) {
+        fs::write(dir.join(name), content).unwrap();
+        git(dir, &["add", "-A"]).unwrap();
+        git(dir, &["commit", "-q", "-m", name]).unwrap();
+    }
+
+    #[test]
+    fn test_default_branch() {
+        let env = TestEnv::new();
+        init_repo(env.path());
+        git(env.path(), &["checkout", "-q", "-b", "feature"]).unwrap();
+        assert_eq!(default_branch(env.path()).as_deref(), Some("main"));
+
+        git(env.path(), &["branch", "-q", "-m", "main", "master"]).unwrap();
+        assert_eq!(default_branch(env.path()).as_deref(), Some("master"));
+
+        git(
+            env.path(),
+            &["update-ref", "refs/remotes/origin/trunk", "HEAD"],
+        )
+        .unwrap();
+        git(
+            env.path(),
+            &[
+                "symbolic-ref",
+                "refs/remotes/origin/HEAD",
+                "refs/remotes/origin/trunk",
+            ],
+        )
+        .unwrap();
+        assert_eq!(default_branch(env.path()).as_deref(), Some("origin/trunk"));
+
+        let other = TestEnv::new();
+        git(other.path(), &["init", "-q", "-b", "trunk"]).unwrap();
+        assert_eq!(default_branch(other.path()), None);
+    }
+
+    #[test]
+    fn test_commits_since() {
+        let env = TestEnv::new();
+        init_repo(env.path());
+        commit_file(env.path(), "feature.txt", "feature\n");
+
+        let hour_ago = Utc::now() - chrono::Duration::hours(1);
+        let commits = commits_since(env.path(), "main", hour_ago).unwrap();
+        assert_eq!(commits.len(), 2);
+        assert!(commits[0].ends_with(" initial"));
+        assert!(commits[1].ends_with(" feature.txt"));
+
+        let in_an_hour = Utc::now() + chrono::Duration::hours(1);
+        assert!(
+            commits_since(env.path(), "main", in_an_hour)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_changed_files_include_untracked() {
+        let env = TestEnv::new();
+        init_repo(env.path());
+        commit_file(env.path(), "feature.txt", "feature\n");
+        let feature = rev_parse(env.path(), "HEAD").unwrap();
+        assert!(changed_files(env.path(), "HEAD").unwrap().is_empty());
+
+        fs::write(env.path().join("README.md"), "changed\n").unwrap();
+        fs::create_dir(env.path().join("src")).unwrap();
+        fs::write(env.path().join("src/new.rs"), "\n").unwrap();
+        assert_eq!(
+            changed_files(env.path(), "HEAD").unwrap(),
+            vec!["README.md", "src/new.rs"]
+        );
+        assert_eq!(
+            changed_files(env.path(), "HEAD~1").unwrap(),
+            vec!["README.md", "feature.txt", "src/new.rs"]
+        );
+        assert_eq!(
+            files_in_commit(env.path(), &feature).unwrap(),
+            vec!["feature.txt"]
+        );
+        assert!(changed_files(env.path(), "no-such-ref").is_err());
+    }
+
+    #[test]
+    fn test_commit_log_with_messages() {
+        let env = TestEnv::new();
+        init_repo(env.path());
+        let initial = rev_parse(env.path(), "HEAD").unwrap();
+        fs::write(env.path().join("a.txt"), "a\n").unwrap();
+        git(env.path(), &["add", "-A"]).unwrap();
+        git(
+            env.path(),
+            &[
+                "commit",
+                "-q",
+                "-m",
+                "Add a",
+                "-m",
+                "Binnacle-Task: bn-a1b2",
+            ],
+        )
+        .unwrap();
+
+        let log = commit_log(env.path(), None).unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].sha, initial);
+        assert_eq!(log[0].message, "initial");
+        assert_eq!(log[1].message, "Add a\n\nBinnacle-Task: bn-a1b2");
+
+        let since = commit_log(env.path(), Some(&initial)).unwrap();
+        assert_eq!(since.len(), 1);
+        assert_eq!(since[0].sha, log[1].sha);
+        assert_eq!(
+            committed_at(env.path(), &initial).unwrap(),
+            log[0].committed_at
+        );
+        assert!(committed_at(env.path(), "no-such-ref").is_err());
+    }
+
+    #[test]
+    fn test_blame_lines() {
+        let env = TestEnv::new();
+        init_repo(env.path());
+        let initial = rev_parse(env.path(), "HEAD").unwrap();
+        commit_file(env.path(), "README.md", "hello\n\tworld\nuncommitted\n");
+        let second = rev_parse(env.path(), "HEAD").unwrap();
+        fs::write(env.path().join("README.md"), "hello\n\tworld\nlocal\n").unwrap();
+
+        let lines = blame(env.path(), "README.md", None).unwrap();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            (lines[0].line, lines[0].sha.as_str()),
+            (1, initial.as_str())
+        );
+        assert_eq!(lines[0].summary, "initial");
+        assert_eq!(lines[1].sha, second);
+        assert_eq!(lines[1].content, "\tworld");
+        assert!(lines[2].sha.chars().all(|c| c == '0'));
+
+        let lines = blame(env.path(), "README.md", Some((2, 2))).unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].line, 2);
+        assert!(blame(env.path(), "missing.txt", None).is_err());
+    }
+}
@@ -0,0 +1,379 @@
+//! Bearer token authentication for the GUI and session servers
+//!
+//! Tokens are created with `bn gui token create --role viewer|editor|admin` and
+//! stored (hashed) in the session `state.kdl`. Authentication is only enforced
+//! once at least one token exists, so existing local setups keep working.
+//!
+//! Tokens are accepted from (in order):
+//! - `Authorization: Bearer <token>` header (CLI clients, TUI, scripts)
+//! - `bn_token` cookie (browsers, set automatically after `?token=` login)
+//! - `token` query parameter (initial browser login and WebSocket clients)
+//!
+//! Token hashes are cached in memory and reloaded whenever `state.kdl`
+//! changes on disk, so requests don't re-read the file.
+
+use axum::{
+    Json,
+    extract::{Request, State},
+    http::{HeaderMap, HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
+
+use super::server::AppState;
+use crate::config::{BinnacleState, GuiRole};
+
+/// Name of the cookie used to persist a browser login
+const TOKEN_COOKIE: &str = "bn_token";
+
+/// Authenticated identity attached to each request (as a request extension).
+#[derive(Debug, Clone)]
+pub struct AuthContext {
+    /// ID of the token that authorized the request (None when auth is disabled)
+    pub token_id: Option<String>,
+    /// Effective role for this request
+    pub role: GuiRole,
+}
+
+impl AuthContext {
+    /// Context used when no tokens are configured (everything allowed).
+    pub fn unauthenticated() -> Self {
+        Self {
+            token_id: None,
+            role: GuiRole::Admin,
+        }
+    }
+}
+
+/// In-memory copy of the session `state.kdl`, used to look up GUI tokens.
+///
+/// The file's modification time and size are checked on every request and the
+/// file is re-parsed only when either changes (token create or revoke).
+pub struct GuiTokenCache {
+    path: PathBuf,
+    cached: Mutex<Option<(StateStamp, Arc<BinnacleState>)>>,
+}
+
+/// Modification time and size of `state.kdl` (None when the file is missing).
+type StateStamp = Option<(SystemTime, u64)>;
+
+impl GuiTokenCache {
+    /// Create a cache for the given `state.kdl` path.
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// Current state, reloading it if `state.kdl` changed since the last call.
+    pub fn state(&self) -> Arc<BinnacleState> {
+        let stamp = std::fs::metadata(&self.path)
+            .ok()
+            .and_then(|m| Some((m.modified().ok()?, m.len())));
+        let mut cached = self.cached.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((cached_stamp, state)) = cached.as_ref()
+            && *cached_stamp == stamp
+        {
+            return state.clone();
+        }
+        let state = Arc::new(self.load());
+        *cached = Some((stamp, state.clone()));
+        state
+    }
+
+    fn load(&self) -> BinnacleState {
+        std::fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| content.parse::<kdl::KdlDocument>().ok())
+            .map(|doc| BinnacleState::from_kdl(&doc))
+            .unwrap_or_default()
+    }
+}
+
+/// Determine the role required for an HTTP route.
+///
+/// Returns None for public routes (static assets and the health check).
+pub fn required_role(method: &Method, path: &str) -> Option<GuiRole> {
+    if path == "/health" || !(path.starts_with("/api/") || path == "/ws") {
+        return None;
+    }
+
+    if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        return Some(GuiRole::Viewer);
+    }
+
+    // Destructive operations and agent control require admin
+    if *method == Method::DELETE || path.starts_with("/api/agents/") {
+        return Some(GuiRole::Admin);
+    }
+
+    Some(GuiRole::Editor)
+}
+
+/// Determine the role required for a WebSocket `command` message.
+///
+/// Read commands (`list`, `show`, `ready`, ...) need viewer, deletes need
+/// admin, and every other command needs editor.
+pub fn command_required_role(cmd: &str) -> GuiRole {
+    let parts: Vec<&str> = cmd.split_whitespace().collect();
+    match parts.as_slice() {
+        ["ready"] | ["blocked"] => GuiRole::Viewer,
+        [_, "list", ..] | [_, "show", ..] | [_, "history", ..] => GuiRole::Viewer,
+        [_, "delete", ..] => GuiRole::Admin,
+        _ => GuiRole::Editor,
+    }
+}
+
+/// Extract a presented token from the request headers or query string.
+///
+/// Returns the token and whether it came from the query string.
+fn extract_token(headers: &HeaderMap, query: Option<&str>) -> Option<(String, bool)> {
+    if let Some(value) = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        && let Some(token) = value.strip_prefix("Bearer ")
+    {
+        return Some((token.trim().to_string(), false));
+    }
+
+    if let Some(cookies) = headers.get(header::COOKIE).and_then(|v| v.to_str().ok()) {
+        for cookie in cookies.split(';') {
+            if let Some((name, value)) = cookie.trim().split_once('=')
+                && name == TOKEN_COOKIE
+                && !value.is_empty()
+            {
+                return Some((value.to_string(), false));
+            }
+        }
+    }
+
+    query.and_then(|q| {
+        q.split('&').find_map(|pair| {
+            pair.split_once('=')
+                .filter(|(k, v)| *k == "token" && !v.is_empty())
+                .map(|(_, v)| (v.to_string(), true))
+        })
+    })
+}
+
+/// Build a JSON error response for auth failures.
+fn auth_error(status: StatusCode, message: String) -> Response {
+    let mut response = (status, Json(serde_json::json!({ "error": message }))).into_response();
+    if status == StatusCode::UNAUTHORIZED {
+        response.headers_mut().insert(
+            header::WWW_AUTHENTICATE,
+            HeaderValue::from_static("Bearer realm=\"binnacle\""),
+        );
+    }
+    response
+}
+
+/// Axum middleware enforcing token roles on every route.
+///
+/// Attaches an [`AuthContext`] to the request and records an audit entry in
+/// the action log for every write request, including the token ID.
+pub async fn require_token(
+    State(state): State<AppState>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let presented = extract_token(req.headers(), req.uri().query());
+
+    let gui_state = state.gui_tokens.state();
+
+    let mut set_cookie = None;
+    let context = if !gui_state.gui_auth_enabled() {
+        AuthContext::unauthenticated()
+    } else {
+        let matched = presented.as_ref().and_then(|(secret, from_query)| {
+            gui_state
+                .find_gui_token(secret)
+                .map(|t| (t, secret, *from_query))
+        });
+
+        match (required_role(&method, &path), matched) {
+            (_, Some((token, secret, from_query))) => {
+                if from_query {
+                    // Persist browser logins so subsequent fetch/WebSocket requests
+                    // authenticate without the token in the URL.
+                    set_cookie = HeaderValue::from_str(&format!(
+                        "{}={}; Path=/; HttpOnly; SameSite=Strict",
+                        TOKEN_COOKIE, secret
+                    ))
+                    .ok();
+                }
+                AuthContext {
+                    token_id: Some(token.id.clone()),
+                    role: token.role,
+                }
+            }
+            // Public routes stay reachable without a token
+            (None, None) => AuthContext {
+                token_id: None,
+                role: GuiRole::Viewer,
+            },
+            (Some(_), None) => {
+                let message = if presented.is_some() {
+                    "Invalid or revoked token"
+                } else {
+                    "Authentication required: provide a bearer token (bn gui token create)"
+                };
+                return auth_error(StatusCode::UNAUTHORIZED, message.to_string());
+            }
+        }
+    };
+
+    if let Some(required) = required_role(&method, &path)
+        && !context.role.allows(required)
+    {
+        return auth_error(
+            StatusCode::FORBIDDEN,
+            format!(
+                "Token role '{}' cannot perform this operation (requires '{}')",
+                context.role, required
+            ),
+        );
+    }
+
+    let is_write = required_role(&method, &path).is_some_and(|r| r > GuiRole::Viewer);
+    let token_id = context.token_id.clone();
+    req.extensions_mut().insert(context);
+
+    let start = Instant::now();
+    let mut response = next.run(req).await;
+
+    if is_write {
+        let status = response.status();
+        let repo_path = state.repo_path.clone();
+        let command = format!("gui {} {}", method, path);
+        let duration = start.elapsed().as_millis() as u64;
+        let error = (!status.is_success()).then(|| format!("HTTP {}", status.as_u16()));
+        tokio::task::spawn_blocking(move || {
+            let _ = crate::action_log::log_action_with_token(
+                &repo_path,
+                &command,
+                serde_json::json!({}),
+                status.is_success(),
+                error,
+                duration,
+                token_id.as_deref(),
+            );
+        });
+    }
+
+    if let Some(cookie) = set_cookie {
+        response.headers_mut().append(header::SET_COOKIE, cookie);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::GuiToken;
+
+    fn write_tokens(path: &std::path::Path, secrets: &[&str]) {
+        let state = BinnacleState {
+            gui_tokens: secrets
+                .iter()
+                .enumerate()
+                .map(|(i, secret)| GuiToken {
+                    id: format!("gtk-{}", i),
+                    name: None,
+                    role: GuiRole::Viewer,
+                    hash: GuiToken::hash_secret(secret),
+                    created_at: chrono::Utc::now(),
+                })
+                .collect(),
+            ..Default::default()
+        };
+        std::fs::write(path, state.to_kdl().to_string()).unwrap();
+    }
+
+    #[test]
+    fn test_token_cache_reloads_on_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.kdl");
+        let cache = GuiTokenCache::new(path.clone());
+        assert!(!cache.state().gui_auth_enabled());
+
+        write_tokens(&path, &["bng_one"]);
+        let state = cache.state();
+        assert!(state.find_gui_token("bng_one").is_some());
+        assert!(Arc::ptr_eq(&state, &cache.state()));
+
+        write_tokens(&path, &["bng_one", "bng_two"]);
+        assert!(cache.state().find_gui_token("bng_two").is_some());
+
+        write_tokens(&path, &["bng_two"]);
+        assert!(cache.state().find_gui_token("bng_one").is_none());
+    }
+
+    #[test]
+    fn test_required_role_for_routes() {
+        assert_eq!(required_role(&Method::GET, "/index.html"), None);
+        assert_eq!(required_role(&Method::GET, "/health"), None);
+        assert_eq!(
+            required_role(&Method::GET, "/api/tasks"),
+            Some(GuiRole::Viewer)
+        );
+        assert_eq!(required_role(&Method::GET, "/ws"), Some(GuiRole::Viewer));
+        assert_eq!(
+            required_role(&Method::POST, "/api/edges"),
+            Some(GuiRole::Editor)
+        );
+        assert_eq!(
+            required_role(&Method::DELETE, "/api/log/annotations/x"),
+            Some(GuiRole::Admin)
+        );
+        assert_eq!(
+            required_role(&Method::POST, "/api/agents/bna-1234/terminate"),
+            Some(GuiRole::Admin)
+        );
+    }
+
+    #[test]
+    fn test_command_required_role() {
+        assert_eq!(command_required_role("ready"), GuiRole::Viewer);
+        assert_eq!(command_required_role("task list"), GuiRole::Viewer);
+        assert_eq!(command_required_role("task show bn-1234"), GuiRole::Viewer);
+        assert_eq!(command_required_role("task create"), GuiRole::Editor);
+        assert_eq!(command_required_role("bug close bn-1"), GuiRole::Editor);
+        assert_eq!(command_required_role("task delete bn-1"), GuiRole::Admin);
+    }
+
+    #[test]
+    fn test_extract_token_sources() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(extract_token(&headers, None), None);
+
+        assert_eq!(
+            extract_token(&headers, Some("foo=1&token=bng_q")),
+            Some(("bng_q".to_string(), true))
+        );
+
+        headers.insert(
+            header::COOKIE,
+            HeaderValue::from_static("theme=dark; bn_token=bng_c"),
+        );
+        assert_eq!(
+            extract_token(&headers, Some("token=bng_q")),
+            Some(("bng_c".to_string(), false))
+        );
+
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer bng_h"),
+        );
+        assert_eq!(
+            extract_token(&headers, None),
+            Some(("bng_h".to_string(), false))
+        );
+    }
+}
@@ -3,6 +3,8 @@
 //! This module provides a local web server with live-updating views of tasks,
 //! dependencies, tests, and activity logs.
 
+#[cfg(feature = "gui")]
+pub mod auth;
 #[cfg(feature = "gui")]
 pub mod embedded;
 #[cfg(feature = "gui")]
@@ -11,16 +13,22 @@ mod pid_file;
 #[cfg(feature = "gui")]
 pub mod protocol;
 #[cfg(feature = "gui")]
+mod rest;
+#[cfg(feature = "gui")]
 mod server;
 #[cfg(feature = "gui")]
 pub mod session_log;
 #[cfg(feature = "gui")]
+mod supervisor;
+#[cfg(feature = "gui")]
 pub mod tunnel;
 #[cfg(feature = "gui")]
 pub mod upstream;
 #[cfg(feature = "gui")]
 mod watcher;
 #[cfg(feature = "gui")]
+pub mod webhooks;
+#[cfg(feature = "gui")]
 mod websocket;
 
 /// Shared rendering module for platform-agnostic graph visualization
@@ -27,6 +27,16 @@ pub struct GuiPidInfo {
     pub port: u16,
     /// Host/address the server is bound to
     pub host: String,
+    /// Whether the server is serving HTTPS/WSS
+    pub tls: bool,
+}
+
+impl GuiPidInfo {
+    /// Base URL of the server (https:// when serving TLS).
+    pub fn url(&self) -> String {
+        let scheme = if self.tls { "https" } else { "http" };
+        format!("{}://{}:{}", scheme, self.host, self.port)
+    }
 }
 
 /// Manages the GUI server PID file for process lifecycle tracking.
@@ -37,7 +47,10 @@ pub struct GuiPidInfo {
 /// PID=12345
 /// PORT=3030
 /// HOST=127.0.0.1
+/// TLS=1
 /// ```
+///
+/// The `TLS` line is only written for servers serving HTTPS.
 #[derive(Debug)]
 pub struct GuiPidFile {
     path: PathBuf,
@@ -73,7 +86,10 @@ impl GuiPidFile {
             fs::create_dir_all(parent)?;
         }
 
-        let contents = format!("PID={}\nPORT={}\nHOST={}\n", info.pid, info.port, info.host);
+        let mut contents = format!("PID={}\nPORT={}\nHOST={}\n", info.pid, info.port, info.host);
+        if info.tls {
+            contents.push_str("TLS=1\n");
+        }
 
         let mut file = fs::File::create(&self.path)?;
         file.write_all(contents.as_bytes())?;
@@ -145,6 +161,7 @@ impl GuiPidFile {
         let mut pid: Option<u32> = None;
         let mut port: Option<u16> = None;
         let mut host: Option<String> = None;
+        let mut tls = false;
 
         for line in contents.lines() {
             let line = line.trim();
@@ -167,6 +184,9 @@ impl GuiPidFile {
                     "HOST" => {
                         host = Some(value.to_string());
                     }
+                    "TLS" => {
+                        tls = value == "1";
+                    }
                     _ => {} // Ignore unknown keys for forward compatibility
                 }
             }
@@ -179,7 +199,12 @@ impl GuiPidFile {
         let host =
             host.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Missing HOST field"))?;
 
-        Ok(GuiPidInfo { pid, port, host })
+        Ok(GuiPidInfo {
+            pid,
+            port,
+            host,
+            tls,
+        })
     }
 }
 
@@ -336,6 +361,7 @@ mod tests {
             pid: 12345,
             port: 3030,
             host: "127.0.0.1".to_string(),
+            tls: false,
         };
 
         pid_file.write(&info).unwrap();
@@ -358,6 +384,7 @@ mod tests {
             pid: 12345,
             port: 3030,
             host: "127.0.0.1".to_string(),
+            tls: false,
         };
 
         pid_file.write(&info).unwrap();
@@ -386,6 +413,7 @@ mod tests {
             pid: 1,
             port: 8080,
             host: "localhost".to_string(),
+            tls: false,
         };
         pid_file.write(&info).unwrap();
 
@@ -440,6 +468,28 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_write_and_read_tls() {
+        let (_temp_dir, pid_file) = setup();
+
+        let info = GuiPidInfo {
+            pid: 12345,
+            port: 3030,
+            host: "127.0.0.1".to_string(),
+            tls: true,
+        };
+        pid_file.write(&info).unwrap();
+
+        let read_info = pid_file.read().unwrap().unwrap();
+        assert!(read_info.tls);
+        assert_eq!(read_info.url(), "https://127.0.0.1:3030");
+        assert!(
+            !GuiPidFile::parse_contents("PID=1\nPORT=80\nHOST=localhost\n")
+                .unwrap()
+                .tls
+        );
+    }
+
     #[test]
     fn test_write_creates_parent_directory() {
         let temp_dir = TempDir::new().unwrap();
@@ -450,6 +500,7 @@ mod tests {
             pid: 1,
             port: 3030,
             host: "127.0.0.1".to_string(),
+            tls: false,
         };
 
         pid_file.write(&info).unwrap();
@@ -464,6 +515,7 @@ mod tests {
             pid: 12345,
             port: 3030,
             host: "::1".to_string(),
+            tls: false,
         };
 
         pid_file.write(&info).unwrap();
@@ -488,6 +540,7 @@ mod tests {
             pid: 999999999,
             port: 3030,
             host: "127.0.0.1".to_string(),
+            tls: false,
         };
         pid_file.write(&info).unwrap();
 
@@ -16,8 +16,19 @@
 //! ## Server → Client Messages ([`ServerMessage`])
 //! - `state`: Full state snapshot
 //! - `delta`: Incremental state update
+//! - `subscribed`: Subscription acknowledgment (after any replay or snapshot)
 //! - `result`: Command execution result
 //! - `pong`: Keepalive response
+//!
+//! ## Resuming a Subscription
+//!
+//! Every `delta` carries a monotonic `version`. A client that reconnects sends
+//! `subscribe` with the last version it applied as `since_version`; the server
+//! replays the messages it missed from its history, or falls back to a `state`
+//! snapshot when the history no longer reaches back that far. Replayed and live
+//! messages are delivered in version order, so clients can apply them as-is.
+
+use std::collections::HashSet;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -34,6 +45,7 @@ use serde::{Deserialize, Serialize};
 ///
 /// ```json
 /// {"type": "subscribe", "topics": ["tasks", "bugs"]}
+/// {"type": "subscribe", "topics": ["entity:bn-1234", "agent:bn-a1b2"], "since_version": 42}
 /// {"type": "command", "id": "cmd-123", "cmd": "task list", "args": {"status": "open"}}
 /// {"type": "ping"}
 /// ```
@@ -42,14 +54,19 @@ use serde::{Deserialize, Serialize};
 pub enum ClientMessage {
     /// Subscribe to state change notifications.
     ///
-    /// Topics control which updates the client receives:
-    /// - `"tasks"`: Task create/update/delete events
-    /// - `"bugs"`: Bug create/update/delete events
-    /// - `"tests"`: Test node and result events
+    /// Topics control which updates the client receives (see [`TopicFilter`]):
+    /// - `"tasks"`, `"bugs"`, `"tests"`, `"milestones"`, `"ideas"`, `"docs"`,
+    ///   `"links"`, `"queues"`, `"agents"`: events for that entity type
+    /// - `"entity:<id>"`: events for one entity (and links touching it)
+    /// - `"agent:<id>"`: events for one agent and the work assigned to it
     /// - `"*"` or empty: All events
     Subscribe {
         /// Topics to subscribe to. Empty or `["*"]` means all.
         topics: Vec<String>,
+        /// Last version the client applied. When set, missed messages are
+        /// replayed instead of sending a full snapshot (if history allows).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        since_version: Option<u64>,
     },
 
     /// Execute a binnacle command.
@@ -116,6 +133,24 @@ pub enum ServerMessage {
         timestamp: DateTime<Utc>,
     },
 
+    /// Subscription acknowledgment.
+    ///
+    /// Sent after the replayed messages (or snapshot) for a `subscribe`, so
+    /// the client knows where live updates begin.
+    Subscribed {
+        /// Normalized topics now in effect.
+        topics: Vec<String>,
+        /// Version the client is caught up to.
+        version: u64,
+        /// True if missed messages were replayed (no snapshot was needed).
+        resumed: bool,
+        /// Number of messages replayed from history.
+        replayed: usize,
+        /// Error message if the subscription could not be served.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+
     /// Command execution result.
     ///
     /// Sent in response to a `command` message.
@@ -134,6 +169,18 @@ pub enum ServerMessage {
 
     /// Keepalive response to a ping.
     Pong,
+
+    /// Agent pool supervisor state.
+    ///
+    /// Broadcast after every supervisor pass when the session server runs
+    /// with `--supervise`. Delivered to subscribers of all topics, `agents`,
+    /// or any `agent:<id>` topic.
+    Supervisor {
+        /// Supervisor snapshot (boxed to reduce enum size).
+        status: Box<crate::agents::SupervisorStatus>,
+        /// Server timestamp when this snapshot was sent.
+        timestamp: DateTime<Utc>,
+    },
 }
 
 // ============================================================================
@@ -225,6 +272,240 @@ pub enum Change {
     },
 }
 
+// ============================================================================
+// Topic Filtering
+// ============================================================================
+
+/// Parsed subscription topics used to filter outgoing messages.
+///
+/// A message matches if it concerns a subscribed entity type, a subscribed
+/// entity ID, or a subscribed agent. Messages that are not about a specific
+/// entity (e.g., `reload`) are always delivered.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TopicFilter {
+    /// Match everything (`"*"` or no topics)
+    all: bool,
+    /// Singular entity types (e.g., "task", "link")
+    entity_types: HashSet<String>,
+    /// Entity IDs (`entity:<id>`)
+    entity_ids: HashSet<String>,
+    /// Agent IDs (`agent:<id>`)
+    agents: HashSet<String>,
+}
+
+impl TopicFilter {
+    /// A filter that matches every message.
+    pub fn all() -> Self {
+        Self {
+            all: true,
+            ..Self::default()
+        }
+    }
+
+    /// Parse subscription topics. Empty topics or `"*"` match everything.
+    pub fn parse(topics: &[String]) -> Result<Self, String> {
+        let mut filter = Self::default();
+        if topics.is_empty() {
+            filter.all = true;
+        }
+
+        for topic in topics {
+            let topic = topic.trim();
+            if topic == "*" {
+                filter.all = true;
+            } else if let Some(id) = topic.strip_prefix("entity:") {
+                filter.entity_ids.insert(id.to_string());
+            } else if let Some(id) = topic.strip_prefix("agent:") {
+                filter.agents.insert(id.to_string());
+            } else {
+                let entity_type = normalize_entity_type(topic)
+                    .ok_or_else(|| format!("Unknown subscription topic '{}'", topic))?;
+                filter.entity_types.insert(entity_type.to_string());
+            }
+        }
+
+        Ok(filter)
+    }
+
+    /// Whether this filter matches every message.
+    pub fn is_all(&self) -> bool {
+        self.all
+    }
+
+    /// Canonical topic list for acknowledgments.
+    pub fn topics(&self) -> Vec<String> {
+        if self.all {
+            return vec!["*".to_string()];
+        }
+        let mut topics: Vec<String> = self
+            .entity_types
+            .iter()
+            .cloned()
+            .chain(self.entity_ids.iter().map(|id| format!("entity:{}", id)))
+            .chain(self.agents.iter().map(|id| format!("agent:{}", id)))
+            .collect();
+        topics.sort();
+        topics
+    }
+
+    /// Check whether an event about an entity matches.
+    ///
+    /// `data` is the entity (or edge) JSON when available; it is used to match
+    /// links by endpoint and work items by assignee.
+    pub fn matches(&self, entity_type: &str, id: &str, data: Option<&serde_json::Value>) -> bool {
+        if self.all {
+            return true;
+        }
+
+        let entity_type = normalize_entity_type(entity_type).unwrap_or(entity_type);
+        if self.entity_types.contains(entity_type) || self.entity_ids.contains(id) {
+            return true;
+        }
+        if entity_type == "agent" && self.agents.contains(id) {
+            return true;
+        }
+
+        let field = |name: &str| data.and_then(|d| d.get(name)).and_then(|v| v.as_str());
+        if entity_type == "link"
+            && [field("source"), field("target")]
+                .into_iter()
+                .flatten()
+                .any(|endpoint| {
+                    self.entity_ids.contains(endpoint) || self.agents.contains(endpoint)
+                })
+        {
+            return true;
+        }
+        field("assignee").is_some_and(|assignee| self.agents.contains(assignee))
+    }
+
+    /// Check whether a single delta change matches.
+    pub fn matches_change(&self, change: &Change) -> bool {
+        match change {
+            Change::Create { entity_type, data } => {
+                let id = data.get("id").and_then(|v| v.as_str()).unwrap_or_default();
+                self.matches(entity_type, id, Some(data))
+            }
+            Change::Update {
+                entity_type,
+                id,
+                changes,
+            } => self.matches(entity_type, id, Some(changes)),
+            Change::Delete { entity_type, id } => self.matches(entity_type, id, None),
+        }
+    }
+
+    /// Filter an outgoing message (as JSON).
+    ///
+    /// Returns None if the message should not be delivered. `delta` messages
+    /// are trimmed to their matching changes and dropped if none remain.
+    pub fn filter_message(&self, message: serde_json::Value) -> Option<serde_json::Value> {
+        if self.all {
+            return Some(message);
+        }
+
+        let msg_type = message.get("type").and_then(|t| t.as_str()).unwrap_or("");
+        let str_field = |value: &serde_json::Value, name: &str| -> String {
+            value
+                .get(name)
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+
+        match msg_type {
+            "delta" => {
+                let mut message = message;
+                let changes: Vec<Change> =
+                    serde_json::from_value(message.get("changes")?.clone()).ok()?;
+                let kept: Vec<Change> = changes
+                    .into_iter()
+                    .filter(|c| self.matches_change(c))
+                    .collect();
+                if kept.is_empty() {
+                    return None;
+                }
+                message["changes"] = serde_json::to_value(kept).ok()?;
+                Some(message)
+            }
+            "entity_added" | "entity_updated" | "entity_removed" => self
+                .matches(
+                    &str_field(&message, "entity_type"),
+                    &str_field(&message, "id"),
+                    message.get("entity"),
+                )
+                .then_some(message),
+            "edge_added" | "edge_removed" => self
+                .matches("link", &str_field(&message, "id"), message.get("edge"))
+                .then_some(message),
+            "log_entry" => {
+                let entry = message.get("entry")?;
+                let actor_matches = entry
+                    .get("actor")
+                    .and_then(|a| a.as_str())
+                    .is_some_and(|actor| self.agents.contains(actor));
+                (actor_matches
+                    || self.matches(
+                        &str_field(entry, "entity_type"),
+                        &str_field(entry, "entity_id"),
+                        None,
+                    ))
+                .then_some(message)
+            }
+            "supervisor" => {
+                (self.entity_types.contains("agent") || !self.agents.is_empty()).then_some(message)
+            }
+            // Not entity-specific (reload, connected, result, ...): always deliver
+            _ => Some(message),
+        }
+    }
+
+    /// Trim a state snapshot to the entities this filter matches.
+    pub fn filter_state(&self, state: &mut GraphState) {
+        if self.all {
+            return;
+        }
+
+        let keep = |entity_type: &str, values: &mut Vec<serde_json::Value>| {
+            values.retain(|v| {
+                let id = v.get("id").and_then(|i| i.as_str()).unwrap_or_default();
+                self.matches(entity_type, id, Some(v))
+            });
+        };
+        keep("task", &mut state.tasks);
+        keep("bug", &mut state.bugs);
+        keep("test", &mut state.tests);
+        keep("milestone", &mut state.milestones);
+        keep("idea", &mut state.ideas);
+        keep("doc", &mut state.docs);
+        keep("link", &mut state.links);
+
+        if let Some(ref queue) = state.queue {
+            let id = queue.get("id").and_then(|i| i.as_str()).unwrap_or_default();
+            if !self.matches("queue", id, Some(queue)) {
+                state.queue = None;
+            }
+        }
+    }
+}
+
+/// Map a topic or entity type name (singular or plural) to its canonical form.
+fn normalize_entity_type(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "task" | "tasks" => "task",
+        "bug" | "bugs" => "bug",
+        "issue" | "issues" => "issue",
+        "test" | "tests" => "test",
+        "milestone" | "milestones" => "milestone",
+        "idea" | "ideas" => "idea",
+        "doc" | "docs" => "doc",
+        "link" | "links" | "edge" | "edges" => "link",
+        "queue" | "queues" => "queue",
+        "agent" | "agents" => "agent",
+        _ => return None,
+    })
+}
+
 // ============================================================================
 // Upstream Hub Protocol
 // ============================================================================
@@ -342,6 +623,7 @@ mod tests {
     fn test_client_message_subscribe_serialization() {
         let msg = ClientMessage::Subscribe {
             topics: vec!["tasks".to_string(), "bugs".to_string()],
+            since_version: None,
         };
         let json = serde_json::to_string(&msg).unwrap();
         assert!(json.contains(r#""type":"subscribe""#));
@@ -669,4 +951,166 @@ mod tests {
         let parsed: DownstreamMessage = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed, msg);
     }
+
+    #[test]
+    fn test_client_message_subscribe_since_version() {
+        let json = r#"{"type":"subscribe","topics":["*"],"since_version":42}"#;
+        let parsed: ClientMessage = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            parsed,
+            ClientMessage::Subscribe {
+                topics: vec!["*".to_string()],
+                since_version: Some(42),
+            }
+        );
+
+        // Older clients omit since_version
+        let json = r#"{"type":"subscribe","topics":[]}"#;
+        let parsed: ClientMessage = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            parsed,
+            ClientMessage::Subscribe {
+                since_version: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_server_message_subscribed_serialization() {
+        let msg = ServerMessage::Subscribed {
+            topics: vec!["task".to_string()],
+            version: 7,
+            resumed: true,
+            replayed: 3,
+            error: None,
+        };
+        let json = serde_json::to_string(&msg).unwrap();
+        assert!(json.contains(r#""type":"subscribed""#));
+        assert!(json.contains(r#""resumed":true"#));
+        assert!(!json.contains("error"));
+
+        let parsed: ServerMessage = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, msg);
+    }
+
+    #[test]
+    fn test_topic_filter_parse() {
+        assert!(TopicFilter::parse(&[]).unwrap().is_all());
+        assert!(TopicFilter::parse(&["*".to_string()]).unwrap().is_all());
+
+        let filter = TopicFilter::parse(&[
+            "tasks".to_string(),
+            "edges".to_string(),
+            "entity:bnb-1".to_string(),
+            "agent:bn-a1".to_string(),
+        ])
+        .unwrap();
+        assert!(!filter.is_all());
+        assert_eq!(
+            filter.topics(),
+            vec!["agent:bn-a1", "entity:bnb-1", "link", "task"]
+        );
+
+        assert!(TopicFilter::parse(&["widgets".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_topic_filter_matches_entities_and_agents() {
+        let filter =
+            TopicFilter::parse(&["entity:bn-1".to_string(), "agent:bn-a1".to_string()]).unwrap();
+
+        assert!(filter.matches("task", "bn-1", None));
+        assert!(!filter.matches("task", "bn-2", None));
+        assert!(filter.matches("agent", "bn-a1", None));
+        assert!(filter.matches(
+            "task",
+            "bn-2",
+            Some(&serde_json::json!({"id": "bn-2", "assignee": "bn-a1"}))
+        ));
+        assert!(filter.matches(
+            "link",
+            "bne-1",
+            Some(&serde_json::json!({"source": "bn-9", "target": "bn-1"}))
+        ));
+        assert!(!filter.matches("bug", "bnb-1", None));
+    }
+
+    #[test]
+    fn test_topic_filter_trims_delta_messages() {
+        let filter = TopicFilter::parse(&["bugs".to_string()]).unwrap();
+        let delta = ServerMessage::Delta {
+            changes: vec![
+                Change::Create {
+                    entity_type: "task".to_string(),
+                    data: serde_json::json!({"id": "bn-1"}),
+                },
+                Change::Delete {
+                    entity_type: "bug".to_string(),
+                    id: "bnb-1".to_string(),
+                },
+            ],
+            version: 5,
+            timestamp: Utc::now(),
+        };
+        let value = serde_json::to_value(&delta).unwrap();
+
+        let filtered = filter.filter_message(value).unwrap();
+        let changes = filtered["changes"].as_array().unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0]["id"], "bnb-1");
+
+        let tasks_only = serde_json::json!({
+            "type": "delta",
+            "changes": [{"op": "delete", "entity_type": "task", "id": "bn-1"}],
+            "version": 6,
+        });
+        assert!(filter.filter_message(tasks_only).is_none());
+
+        // Legacy per-entity messages and non-entity messages
+        let added =
+            serde_json::json!({"type": "entity_added", "entity_type": "task", "id": "bn-1"});
+        assert!(filter.filter_message(added).is_none());
+        let reload = serde_json::json!({"type": "reload", "version": 7});
+        assert!(filter.filter_message(reload).is_some());
+    }
+
+    #[test]
+    fn test_topic_filter_supervisor_messages() {
+        let supervisor = serde_json::json!({"type": "supervisor", "status": {}});
+        for (topics, delivered) in [
+            (vec!["bugs"], false),
+            (vec!["agents"], true),
+            (vec!["agent:bn-a1"], true),
+            (vec!["*"], true),
+        ] {
+            let topics: Vec<String> = topics.into_iter().map(String::from).collect();
+            let filter = TopicFilter::parse(&topics).unwrap();
+            assert_eq!(
+                filter.filter_message(supervisor.clone()).is_some(),
+                delivered,
+                "{:?}",
+                topics
+            );
+        }
+    }
+
+    #[test]
+    fn test_topic_filter_state_snapshot() {
+        let filter = TopicFilter::parse(&["entity:bn-1".to_string()]).unwrap();
+        let mut state = GraphState {
+            tasks: vec![
+                serde_json::json!({"id": "bn-1"}),
+                serde_json::json!({"id": "bn-2"}),
+            ],
+            bugs: vec![serde_json::json!({"id": "bnb-1"})],
+            links: vec![serde_json::json!({"id": "bne-1", "source": "bn-1", "target": "bn-2"})],
+            ..GraphState::default()
+        };
+
+        filter.filter_state(&mut state);
+        assert_eq!(state.tasks.len(), 1);
+        assert!(state.bugs.is_empty());
+        assert_eq!(state.links.len(), 1);
+    }
 }
@@ -0,0 +1,1578 @@
+//! REST API for graph entities.
+//!
+//! Full CRUD for tasks, bugs, issues, ideas, docs, milestones, missions and
+//! tests, plus commit links. Writes go through the same `commands::*`
+//! functions as the CLI, so validation and side effects match `bn`.
+//!
+//! # Endpoints
+//!
+//! For each collection (`/api/tasks`, `/api/bugs`, ...):
+//!
+//! - `POST /api/<collection>` - create (201, `Location` header)
+//! - `GET /api/<collection>/{id}` - fetch one
+//! - `PATCH /api/<collection>/{id}` - update
+//! - `DELETE /api/<collection>/{id}` - delete
+//! - `POST /api/<collection>/{id}/close` and `/reopen` where supported
+//!
+//! Commit links live under `/api/node/{id}/commits`. The generated OpenAPI
+//! document is served at `/api/openapi.json`.
+//!
+//! # Optimistic Concurrency
+//!
+//! Entity responses carry an `ETag` derived from `updated_at` (tests, which
+//! have no `updated_at`, use a content hash). Send it back in `If-Match` on
+//! `PATCH`, `DELETE`, `close` or `reopen`; if the entity changed in the
+//! meantime the request fails with 412 and the current ETag. Updating a doc
+//! creates a new version, returned with its own ID and ETag.
+
+use axum::{
+    Json, Router,
+    body::Body as HttpBody,
+    extract::{Path as AxumPath, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use serde_json::{Map, Value, json};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+use super::server::AppState;
+use crate::commands;
+use crate::models::DocType;
+use crate::storage::Storage;
+
+/// Error response: status code plus `{"error": ...}` body.
+type ApiError = (StatusCode, Json<Value>);
+
+fn api_error(status: StatusCode, message: impl Into<String>) -> ApiError {
+    (status, Json(json!({ "error": message.into() })))
+}
+
+/// Map a command error to an HTTP status.
+fn command_error(e: crate::Error) -> ApiError {
+    let status = match &e {
+        crate::Error::NotFound(_) => StatusCode::NOT_FOUND,
+        crate::Error::InvalidInput(_) | crate::Error::InvalidId(_) | crate::Error::Other(_) => {
+            StatusCode::BAD_REQUEST
+        }
+        crate::Error::CycleDetected | crate::Error::QueueAlreadyExists => StatusCode::CONFLICT,
+        crate::Error::PermissionDenied(_) => StatusCode::FORBIDDEN,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    api_error(status, e.to_string())
+}
+
+/// JSON type of a request field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldKind {
+    String,
+    Integer,
+    Boolean,
+    StringArray,
+}
+
+impl FieldKind {
+    fn schema(&self) -> Value {
+        match self {
+            FieldKind::String => json!({ "type": "string" }),
+            FieldKind::Integer => json!({ "type": "integer", "minimum": 0 }),
+            FieldKind::Boolean => json!({ "type": "boolean" }),
+            FieldKind::StringArray => json!({ "type": "array", "items": { "type": "string" } }),
+        }
+    }
+
+    fn accepts(&self, value: &Value) -> bool {
+        match self {
+            FieldKind::String => value.is_string(),
+            FieldKind::Integer => value.is_u64(),
+            FieldKind::Boolean => value.is_boolean(),
+            FieldKind::StringArray => value
+                .as_array()
+                .is_some_and(|items| items.iter().all(|v| v.is_string())),
+        }
+    }
+}
+
+/// A field accepted in a request body.
+#[derive(Debug, Clone, Copy)]
+struct Field {
+    name: &'static str,
+    kind: FieldKind,
+    required: bool,
+    description: &'static str,
+}
+
+const fn field(name: &'static str, kind: FieldKind, description: &'static str) -> Field {
+    Field {
+        name,
+        kind,
+        required: false,
+        description,
+    }
+}
+
+const fn required(name: &'static str, kind: FieldKind, description: &'static str) -> Field {
+    Field {
+        name,
+        kind,
+        required: true,
+        description,
+    }
+}
+
+use FieldKind::{Boolean, Integer, StringArray};
+
+const TITLE: Field = required("title", FieldKind::String, "Title");
+const SHORT_NAME: Field = field("short_name", FieldKind::String, "Short display name");
+const DESCRIPTION: Field = field("description", FieldKind::String, "Description");
+const PRIORITY: Field = field("priority", Integer, "Priority (0-4, 0 is highest)");
+const TAGS: Field = field("tags", StringArray, "Tags");
+const ASSIGNEE: Field = field("assignee", FieldKind::String, "Assignee");
+const STATUS: Field = field("status", FieldKind::String, "New status");
+const ADD_TAGS: Field = field("add_tags", StringArray, "Tags to add");
+const REMOVE_TAGS: Field = field("remove_tags", StringArray, "Tags to remove");
+const FORCE: Field = field("force", Boolean, "Bypass dependency checks");
+const DUE_DATE: Field = field("due_date", FieldKind::String, "Due date (RFC 3339)");
+const SEVERITY: Field = field(
+    "severity",
+    FieldKind::String,
+    "Severity (triage, low, medium, high, critical)",
+);
+const REPRODUCTION_STEPS: Field = field(
+    "reproduction_steps",
+    FieldKind::String,
+    "Steps to reproduce",
+);
+const AFFECTED_COMPONENT: Field = field(
+    "affected_component",
+    FieldKind::String,
+    "Affected component",
+);
+const REASON: Field = field("reason", FieldKind::String, "Reason for closing");
+
+/// Optional version of a required field (for updates).
+const fn optional(f: Field) -> Field {
+    Field {
+        required: false,
+        ..f
+    }
+}
+
+const UPDATE_TITLE: Field = optional(TITLE);
+const DOC_CREATE: &[Field] = &[
+    TITLE,
+    required(
+        "entity_ids",
+        StringArray,
+        "Entities to attach the doc to (at least one)",
+    ),
+    field(
+        "doc_type",
+        FieldKind::String,
+        "prd, note (default) or handoff",
+    ),
+    SHORT_NAME,
+    field("content", FieldKind::String, "Markdown content"),
+    field("summary", FieldKind::String, "Summary section"),
+    TAGS,
+];
+const TEST_CREATE: &[Field] = &[
+    required("name", FieldKind::String, "Test name"),
+    required("command", FieldKind::String, "Command to run"),
+    field(
+        "working_dir",
+        FieldKind::String,
+        "Working directory (default .)",
+    ),
+    field("task_id", FieldKind::String, "Task to link"),
+    field("bug_id", FieldKind::String, "Bug to link"),
+    field(
+        "pattern",
+        FieldKind::String,
+        "Comma-separated globs of the files the test covers",
+    ),
+];
+const DOC_UPDATE: &[Field] = &[
+    UPDATE_TITLE,
+    SHORT_NAME,
+    DESCRIPTION,
+    field("content", FieldKind::String, "New markdown content"),
+    field(
+        "editor",
+        FieldKind::String,
+        "Editor attribution (agent:<id> or user:<name>)",
+    ),
+    field("clear_dirty", Boolean, "Mark the summary as up to date"),
+    field(
+        "merge",
+        Boolean,
+        "If the doc has a newer version, three-way merge this update onto it",
+    ),
+    field(
+        "fork",
+        Boolean,
+        "If the doc has a newer version, branch the version chain here anyway",
+    ),
+];
+const TEST_UPDATE: &[Field] = &[
+    field("name", FieldKind::String, "Test name"),
+    field("command", FieldKind::String, "Command to run"),
+    field("working_dir", FieldKind::String, "Working directory"),
+];
+
+/// An entity collection exposed over REST.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resource {
+    Task,
+    Bug,
+    Issue,
+    Idea,
+    Doc,
+    Milestone,
+    Mission,
+    Test,
+}
+
+impl Resource {
+    /// All resources, in documentation order.
+    pub const ALL: [Resource; 8] = [
+        Resource::Task,
+        Resource::Bug,
+        Resource::Issue,
+        Resource::Idea,
+        Resource::Doc,
+        Resource::Milestone,
+        Resource::Mission,
+        Resource::Test,
+    ];
+
+    /// Singular name, used as the response key ("task").
+    pub fn singular(&self) -> &'static str {
+        match self {
+            Resource::Task => "task",
+            Resource::Bug => "bug",
+            Resource::Issue => "issue",
+            Resource::Idea => "idea",
+            Resource::Doc => "doc",
+            Resource::Milestone => "milestone",
+            Resource::Mission => "mission",
+            Resource::Test => "test",
+        }
+    }
+
+    /// Collection path segment ("tasks").
+    pub fn collection(&self) -> &'static str {
+        match self {
+            Resource::Task => "tasks",
+            Resource::Bug => "bugs",
+            Resource::Issue => "issues",
+            Resource::Idea => "ideas",
+            Resource::Doc => "docs",
+            Resource::Milestone => "milestones",
+            Resource::Mission => "missions",
+            Resource::Test => "tests",
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Resource::Task => "Task",
+            Resource::Bug => "Bug",
+            Resource::Issue => "Issue",
+            Resource::Idea => "Idea",
+            Resource::Doc => "Doc",
+            Resource::Milestone => "Milestone",
+            Resource::Mission => "Mission",
+            Resource::Test => "Test",
+        }
+    }
+
+    /// Fields accepted when closing, or None if the resource can't be closed.
+    fn close_fields(&self) -> Option<&'static [Field]> {
+        match self {
+            Resource::Task | Resource::Bug | Resource::Milestone | Resource::Mission => {
+                Some(&[REASON, FORCE])
+            }
+            Resource::Issue | Resource::Idea => Some(&[REASON]),
+            Resource::Doc | Resource::Test => None,
+        }
+    }
+
+    fn can_reopen(&self) -> bool {
+        matches!(
+            self,
+            Resource::Task
+                | Resource::Bug
+                | Resource::Issue
+                | Resource::Milestone
+                | Resource::Mission
+        )
+    }
+
+    fn create_fields(&self) -> &'static [Field] {
+        match self {
+            Resource::Task | Resource::Issue => {
+                &[TITLE, SHORT_NAME, DESCRIPTION, PRIORITY, TAGS, ASSIGNEE]
+            }
+            Resource::Bug => &[
+                TITLE,
+                SHORT_NAME,
+                DESCRIPTION,
+                PRIORITY,
+                SEVERITY,
+                TAGS,
+                ASSIGNEE,
+                REPRODUCTION_STEPS,
+                AFFECTED_COMPONENT,
+            ],
+            Resource::Idea => &[TITLE, SHORT_NAME, DESCRIPTION, TAGS],
+            Resource::Doc => DOC_CREATE,
+            Resource::Milestone | Resource::Mission => &[
+                TITLE,
+                SHORT_NAME,
+                DESCRIPTION,
+                PRIORITY,
+                TAGS,
+                ASSIGNEE,
+                DUE_DATE,
+            ],
+            Resource::Test => TEST_CREATE,
+        }
+    }
+
+    fn update_fields(&self) -> &'static [Field] {
+        match self {
+            Resource::Task => &[
+                UPDATE_TITLE,
+                SHORT_NAME,
+                DESCRIPTION,
+                PRIORITY,
+                STATUS,
+                ADD_TAGS,
+                REMOVE_TAGS,
+                ASSIGNEE,
+                FORCE,
+            ],
+            Resource::Bug => &[
+                UPDATE_TITLE,
+                SHORT_NAME,
+                DESCRIPTION,
+                PRIORITY,
+                STATUS,
+                SEVERITY,
+                ADD_TAGS,
+                REMOVE_TAGS,
+                ASSIGNEE,
+                REPRODUCTION_STEPS,
+                AFFECTED_COMPONENT,
+                FORCE,
+            ],
+            Resource::Issue => &[
+                UPDATE_TITLE,
+                SHORT_NAME,
+                DESCRIPTION,
+                PRIORITY,
+                STATUS,
+                ADD_TAGS,
+                REMOVE_TAGS,
+                ASSIGNEE,
+            ],
+            Resource::Idea => &[
+                UPDATE_TITLE,
+                SHORT_NAME,
+                DESCRIPTION,
+                STATUS,
+                ADD_TAGS,
+                REMOVE_TAGS,
+            ],
+            Resource::Doc => DOC_UPDATE,
+            Resource::Milestone | Resource::Mission => &[
+                UPDATE_TITLE,
+                SHORT_NAME,
+                DESCRIPTION,
+                PRIORITY,
+                STATUS,
+                ADD_TAGS,
+                REMOVE_TAGS,
+                ASSIGNEE,
+                DUE_DATE,
+            ],
+            Resource::Test => TEST_UPDATE,
+        }
+    }
+
+    /// Load an entity as JSON.
+    fn load(&self, storage: &Storage, id: &str) -> crate::Result<Value> {
+        if !storage.is_cached(id)? {
+            return Err(crate::Error::NotFound(format!(
+                "{} {}",
+                self.singular(),
+                id
+            )));
+        }
+        let value = match self {
+            Resource::Task => serde_json::to_value(storage.get_task(id)?)?,
+            Resource::Bug => serde_json::to_value(storage.get_bug(id)?)?,
+            Resource::Issue => serde_json::to_value(storage.get_issue(id)?)?,
+            Resource::Idea => serde_json::to_value(storage.get_idea(id)?)?,
+            Resource::Milestone => serde_json::to_value(storage.get_milestone(id)?)?,
+            Resource::Mission => serde_json::to_value(storage.get_mission(id)?)?,
+            Resource::Test => serde_json::to_value(storage.get_test(id)?)?,
+            Resource::Doc => {
+                let doc = storage.get_doc(id)?;
+                let content = doc
+                    .get_content()
+                    .map_err(|e| crate::Error::Other(e.to_string()))?;
+                let mut value = serde_json::to_value(&doc)?;
+                value["content"] = Value::String(content);
+                value
+            }
+        };
+        Ok(value)
+    }
+
+    /// Create an entity, returning its ID.
+    fn create(&self, repo_path: &Path, body: &Body) -> crate::Result<String> {
+        let id = match self {
+            Resource::Task => {
+                commands::task_create(
+                    repo_path,
+                    body.required_string("title"),
+                    body.string("short_name"),
+                    body.string("description"),
+                    body.priority()?,
+                    body.strings("tags"),
+                    body.string("assignee"),
+                )?
+                .id
+            }
+            Resource::Bug => {
+                commands::bug_create(
+                    repo_path,
+                    body.required_string("title"),
+                    body.string("short_name"),
+                    body.string("description"),
+                    body.priority()?,
+                    body.string("severity"),
+                    body.strings("tags"),
+                    body.string("assignee"),
+                    body.string("reproduction_steps"),
+                    body.string("affected_component"),
+                )?
+                .id
+            }
+            Resource::Issue => {
+                commands::issue_create(
+                    repo_path,
+                    body.required_string("title"),
+                    body.string("short_name"),
+                    body.string("description"),
+                    body.priority()?,
+                    body.strings("tags"),
+                    body.string("assignee"),
+                )?
+                .id
+            }
+            Resource::Idea => {
+                commands::idea_create(
+                    repo_path,
+                    body.required_string("title"),
+                    body.string("short_name"),
+                    body.string("description"),
+                    body.strings("tags"),
+                )?
+                .id
+            }
+            Resource::Doc => {
+                commands::doc_create(
+                    repo_path,
+                    body.required_string("title"),
+                    parse_doc_type(body.string("doc_type").as_deref().unwrap_or("note"))?,
+                    body.string("short_name"),
+                    body.string("content"),
+                    body.string("summary"),
+                    body.strings("tags"),
+                    body.strings("entity_ids"),
+                )?
+                .id
+            }
+            Resource::Milestone => {
+                commands::milestone_create(
+                    repo_path,
+                    body.required_string("title"),
+                    body.string("short_name"),
+                    body.string("description"),
+                    body.priority()?,
+                    body.strings("tags"),
+                    body.string("assignee"),
+                    body.string("due_date"),
+                )?
+                .id
+            }
+            Resource::Mission => {
+                commands::mission_create(
+                    repo_path,
+                    body.required_string("title"),
+                    body.string("short_name"),
+                    body.string("description"),
+                    body.priority()?,
+                    body.strings("tags"),
+                    body.string("assignee"),
+                    body.string("due_date"),
+                )?
+                .id
+            }
+            Resource::Test => {
+                commands::test_create(
+                    repo_path,
+                    body.required_string("name"),
+                    body.required_string("command"),
+                    body.string("working_dir")
+                        .unwrap_or_else(|| ".".to_string()),
+                    body.string("task_id"),
+                    body.string("bug_id"),
+                    None,
+                    vec![],
+                    body.string("pattern"),
+                )?
+                .id
+            }
+        };
+        Ok(id)
+    }
+
+    /// Update an entity, returning the ID of the current version.
+    fn update(&self, repo_path: &Path, id: &str, body: &Body) -> crate::Result<String> {
+        let status = body.string("status");
+        match self {
+            Resource::Task => {
+                commands::task_update(
+                    repo_path,
+                    id,
+                    body.string("title"),
+                    body.string("short_name"),
+                    body.string("description"),
+                    body.priority()?,
+                    status.as_deref(),
+                    body.strings("add_tags"),
+                    body.strings("remove_tags"),
+                    body.string("assignee"),
+                    body.bool("force"),
+                    false,
+                    false,
+                )?;
+            }
+            Resource::Bug => {
+                commands::bug_update(
+                    repo_path,
+                    id,
+                    body.string("title"),
+                    body.string("short_name"),
+                    body.string("description"),
+                    body.priority()?,
+                    status.as_deref(),
+                    body.string("severity"),
+                    body.strings("add_tags"),
+                    body.strings("remove_tags"),
+                    body.string("assignee"),
+                    body.string("reproduction_steps"),
+                    body.string("affected_component"),
+                    body.bool("force"),
+                    false,
+                    false,
+                )?;
+            }
+            Resource::Issue => {
+                commands::issue_update(
+                    repo_path,
+                    id,
+                    body.string("title"),
+                    body.string("short_name"),
+                    body.string("description"),
+                    body.priority()?,
+                    status.as_deref(),
+                    body.strings("add_tags"),
+                    body.strings("remove_tags"),
+                    body.string("assignee"),
+                )?;
+            }
+            Resource::Idea => {
+                commands::idea_update(
+                    repo_path,
+                    id,
+                    body.string("title"),
+                    body.string("short_name"),
+                    body.string("description"),
+                    status.as_deref(),
+                    body.strings("add_tags"),
+                    body.strings("remove_tags"),
+                )?;
+            }
+            Resource::Doc => {
+                if body.bool("merge") && body.bool("fork") {
+                    return Err(crate::Error::InvalidInput(
+                        "merge and fork cannot be combined".to_string(),
+                    ));
+                }
+                let editor = body.string("editor");
+                return Ok(commands::doc_update(
+                    repo_path,
+                    id,
+                    body.string("content"),
+                    body.string("title"),
+                    body.string("short_name"),
+                    body.string("description"),
+                    editor.as_deref(),
+                    body.bool("clear_dirty"),
+                    body.bool("merge"),
+                    body.bool("fork"),
+                )?
+                .new_id);
+            }
+            Resource::Milestone => {
+                commands::milestone_update(
+                    repo_path,
+                    id,
+                    body.string("title"),
+                    body.string("short_name"),
+                    body.string("description"),
+                    body.priority()?,
+                    status.as_deref(),
+                    body.strings("add_tags"),
+                    body.strings("remove_tags"),
+                    body.string("assignee"),
+                    body.string("due_date"),
+                )?;
+            }
+            Resource::Mission => {
+                commands::mission_update(
+                    repo_path,
+                    id,
+                    body.string("title"),
+                    body.string("short_name"),
+                    body.string("description"),
+                    body.priority()?,
+                    status.as_deref(),
+                    body.strings("add_tags"),
+                    body.strings("remove_tags"),
+                    body.string("assignee"),
+                    body.string("due_date"),
+                )?;
+            }
+            Resource::Test => {
+                commands::test_update(
+                    repo_path,
+                    id,
+                    body.string("name"),
+                    body.string("command"),
+                    body.string("working_dir"),
+                )?;
+            }
+        }
+        Ok(id.to_string())
+    }
+
+    fn close(&self, repo_path: &Path, id: &str, body: &Body) -> crate::Result<()> {
+        let reason = body.string("reason");
+        let force = body.bool("force");
+        match self {
+            Resource::Task => commands::task_close(repo_path, id, reason, force).map(|_| ()),
+            Resource::Bug => commands::bug_close(repo_path, id, reason, force).map(|_| ()),
+            Resource::Issue => commands::issue_close(repo_path, id, reason).map(|_| ()),
+            Resource::Idea => commands::idea_close(repo_path, id, reason).map(|_| ()),
+            Resource::Milestone => {
+                commands::milestone_close(repo_path, id, reason, force).map(|_| ())
+            }
+            Resource::Mission => commands::mission_close(repo_path, id, reason, force).map(|_| ()),
+            Resource::Doc | Resource::Test => Err(crate::Error::InvalidInput(format!(
+                "{}s cannot be closed",
+                self.singular()
+            ))),
+        }
+    }
+
+    fn reopen(&self, repo_path: &Path, id: &str) -> crate::Result<()> {
+        match self {
+            Resource::Task => commands::task_reopen(repo_path, id).map(|_| ()),
+            Resource::Bug => commands::bug_reopen(repo_path, id).map(|_| ()),
+            Resource::Issue => commands::issue_reopen(repo_path, id).map(|_| ()),
+            Resource::Milestone => commands::milestone_reopen(repo_path, id).map(|_| ()),
+            Resource::Mission => commands::mission_reopen(repo_path, id).map(|_| ()),
+            Resource::Idea | Resource::Doc | Resource::Test => Err(crate::Error::InvalidInput(
+                format!("{}s cannot be reopened", self.singular()),
+            )),
+        }
+    }
+
+    fn delete(&self, repo_path: &Path, id: &str) -> crate::Result<()> {
+        match self {
+            Resource::Task => commands::task_delete(repo_path, id).map(|_| ()),
+            Resource::Bug => commands::bug_delete(repo_path, id).map(|_| ()),
+            Resource::Issue => commands::issue_delete(repo_path, id).map(|_| ()),
+            Resource::Idea => commands::idea_delete(repo_path, id).map(|_| ()),
+            Resource::Doc => commands::doc_delete(repo_path, id).map(|_| ()),
+            Resource::Milestone => commands::milestone_delete(repo_path, id).map(|_| ()),
+            Resource::Mission => commands::mission_delete(repo_path, id).map(|_| ()),
+            Resource::Test => commands::test_delete(repo_path, id).map(|_| ()),
+        }
+    }
+}
+
+/// Parse a doc type name.
+fn parse_doc_type(s: &str) -> crate::Result<DocType> {
+    match s.to_lowercase().as_str() {
+        "prd" => Ok(DocType::Prd),
+        "note" => Ok(DocType::Note),
+        "handoff" => Ok(DocType::Handoff),
+        _ => Err(crate::Error::InvalidInput(format!(
+            "Invalid doc type '{}'. Must be prd, note, or handoff",
+            s
+        ))),
+    }
+}
+
+/// A request body validated against a field list.
+#[derive(Debug, Default)]
+struct Body {
+    fields: Map<String, Value>,
+}
+
+impl Body {
+    /// Validate a JSON body: it must be an object with only known fields of
+    /// the right type, and every required field present.
+    fn parse(value: Option<Value>, fields: &[Field]) -> Result<Self, ApiError> {
+        let fields_map = match value {
+            None | Some(Value::Null) => Map::new(),
+            Some(Value::Object(map)) => map,
+            Some(_) => {
+                return Err(api_error(
+                    StatusCode::BAD_REQUEST,
+                    "Request body must be a JSON object",
+                ));
+            }
+        };
+
+        for (name, value) in &fields_map {
+            let Some(spec) = fields.iter().find(|f| f.name == name) else {
+                return Err(api_error(
+                    StatusCode::BAD_REQUEST,
+                    format!("Unknown field '{}'", name),
+                ));
+            };
+            if !value.is_null() && !spec.kind.accepts(value) {
+                return Err(api_error(
+                    StatusCode::BAD_REQUEST,
+                    format!("Field '{}' must be of type {}", name, spec.kind.schema()),
+                ));
+            }
+        }
+        for spec in fields.iter().filter(|f| f.required) {
+            if fields_map.get(spec.name).is_none_or(|v| v.is_null()) {
+                return Err(api_error(
+                    StatusCode::BAD_REQUEST,
+                    format!("Missing required field '{}'", spec.name),
+                ));
+            }
+        }
+
+        Ok(Self { fields: fields_map })
+    }
+
+    fn string(&self, name: &str) -> Option<String> {
+        self.fields
+            .get(name)
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+    }
+
+    /// A field validated as required by [`Body::parse`].
+    fn required_string(&self, name: &str) -> String {
+        self.string(name).unwrap_or_default()
+    }
+
+    fn bool(&self, name: &str) -> bool {
+        self.fields
+            .get(name)
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    }
+
+    fn strings(&self, name: &str) -> Vec<String> {
+        self.fields
+            .get(name)
+            .and_then(|v| v.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn priority(&self) -> crate::Result<Option<u8>> {
+        match self.fields.get("priority").and_then(|v| v.as_u64()) {
+            None => Ok(None),
+            Some(p) if p <= 4 => Ok(Some(p as u8)),
+            Some(p) => Err(crate::Error::InvalidInput(format!(
+                "Priority must be 0-4, got {}",
+                p
+            ))),
+        }
+    }
+}
+
+/// Compute the ETag for an entity.
+///
+/// Uses `updated_at` when the entity has one, otherwise a content hash.
+pub fn etag_for(entity: &Value) -> String {
+    match entity.get("updated_at").and_then(|v| v.as_str()) {
+        Some(updated_at) => format!("\"{}\"", updated_at),
+        None => {
+            let digest = format!("{:x}", Sha256::digest(entity.to_string().as_bytes()));
+            format!("\"sha256-{}\"", &digest[..16])
+        }
+    }
+}
+
+/// Check an `If-Match` header against the current ETag.
+fn check_if_match(headers: &HeaderMap, current: &str) -> Result<(), ApiError> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(());
+    };
+    let value = value
+        .to_str()
+        .map_err(|_| api_error(StatusCode::BAD_REQUEST, "Invalid If-Match header"))?;
+    let matches = value
+        .split(',')
+        .map(|t| t.trim())
+        .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == current);
+    if matches {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::PRECONDITION_FAILED,
+            Json(json!({
+                "error": "Entity was modified since it was read (If-Match does not match)",
+                "etag": current,
+            })),
+        ))
+    }
+}
+
+/// Build a JSON response for an entity with its ETag.
+fn entity_response(status: StatusCode, resource: Resource, entity: Value) -> Response {
+    let etag = etag_for(&entity);
+    let mut response = (status, Json(json!({ resource.singular(): entity }))).into_response();
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        response.headers_mut().insert(header::ETAG, value);
+    }
+    response
+}
+
+/// Load an entity, mapping errors to HTTP.
+fn load_entity(repo_path: &Path, resource: Resource, id: &str) -> Result<Value, ApiError> {
+    let storage = Storage::open(repo_path).map_err(command_error)?;
+    resource.load(&storage, id).map_err(command_error)
+}
+
+/// Reject writes when the server is readonly.
+fn ensure_writable(state: &AppState) -> Result<(), ApiError> {
+    if state.readonly {
+        Err(super::server::readonly_error())
+    } else {
+        Ok(())
+    }
+}
+
+async fn get_entity(
+    resource: Resource,
+    state: AppState,
+    headers: HeaderMap,
+    id: String,
+) -> Result<Response, ApiError> {
+    let entity = load_entity(&state.repo_path, resource, &id)?;
+    let etag = etag_for(&entity);
+    if headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|t| t.trim() == etag || t.trim() == "*"))
+    {
+        let mut response = StatusCode::NOT_MODIFIED.into_response();
+        if let Ok(value) = HeaderValue::from_str(&etag) {
+            response.headers_mut().insert(header::ETAG, value);
+        }
+        return Ok(response);
+    }
+    Ok(entity_response(StatusCode::OK, resource, entity))
+}
+
+async fn create_entity(
+    resource: Resource,
+    state: AppState,
+    body: Option<Value>,
+) -> Result<Response, ApiError> {
+    ensure_writable(&state)?;
+    let body = Body::parse(body, resource.create_fields())?;
+
+    // Serialize REST writes through the server's storage lock
+    let _guard = state.storage.lock().await;
+    let id = resource
+        .create(&state.repo_path, &body)
+        .map_err(command_error)?;
+    let entity = load_entity(&state.repo_path, resource, &id)?;
+
+    let mut response = entity_response(StatusCode::CREATED, resource, entity);
+    if let Ok(location) = HeaderValue::from_str(&format!("/api/{}/{}", resource.collection(), id)) {
+        response.headers_mut().insert(header::LOCATION, location);
+    }
+    Ok(response)
+}
+
+async fn update_entity(
+    resource: Resource,
+    state: AppState,
+    headers: HeaderMap,
+    id: String,
+    body: Option<Value>,
+) -> Result<Response, ApiError> {
+    ensure_writable(&state)?;
+    let body = Body::parse(body, resource.update_fields())?;
+
+    let _guard = state.storage.lock().await;
+    let current = load_entity(&state.repo_path, resource, &id)?;
+    check_if_match(&headers, &etag_for(&current))?;
+
+    let id = resource
+        .update(&state.repo_path, &id, &body)
+        .map_err(command_error)?;
+    let entity = load_entity(&state.repo_path, resource, &id)?;
+    Ok(entity_response(StatusCode::OK, resource, entity))
+}
+
+async fn delete_entity(
+    resource: Resource,
+    state: AppState,
+    headers: HeaderMap,
+    id: String,
+) -> Result<Response, ApiError> {
+    ensure_writable(&state)?;
+
+    let _guard = state.storage.lock().await;
+    let current = load_entity(&state.repo_path, resource, &id)?;
+    check_if_match(&headers, &etag_for(&current))?;
+
+    resource
+        .delete(&state.repo_path, &id)
+        .map_err(command_error)?;
+    Ok(Json(json!({ "deleted": id, "type": resource.singular() })).into_response())
+}
+
+async fn close_entity(
+    resource: Resource,
+    state: AppState,
+    headers: HeaderMap,
+    id: String,
+    body: Option<Value>,
+) -> Result<Response, ApiError> {
+    ensure_writable(&state)?;
+    let body = Body::parse(body, resource.close_fields().unwrap_or_default())?;
+
+    let _guard = state.storage.lock().await;
+    let current = load_entity(&state.repo_path, resource, &id)?;
+    check_if_match(&headers, &etag_for(&current))?;
+
+    resource
+        .close(&state.repo_path, &id, &body)
+        .map_err(command_error)?;
+    let entity = load_entity(&state.repo_path, resource, &id)?;
+    Ok(entity_response(StatusCode::OK, resource, entity))
+}
+
+async fn reopen_entity(
+    resource: Resource,
+    state: AppState,
+    headers: HeaderMap,
+    id: String,
+) -> Result<Response, ApiError> {
+    ensure_writable(&state)?;
+
+    let _guard = state.storage.lock().await;
+    let current = load_entity(&state.repo_path, resource, &id)?;
+    check_if_match(&headers, &etag_for(&current))?;
+
+    resource
+        .reopen(&state.repo_path, &id)
+        .map_err(command_error)?;
+    let entity = load_entity(&state.repo_path, resource, &id)?;
+    Ok(entity_response(StatusCode::OK, resource, entity))
+}
+
+/// Parse an optional JSON body (empty bodies are allowed).
+fn json_body(bytes: &[u8]) -> Result<Option<Value>, ApiError> {
+    if bytes.iter().all(|b| b.is_ascii_whitespace()) {
+        return Ok(None);
+    }
+    serde_json::from_slice(bytes)
+        .map(Some)
+        .map_err(|e| api_error(StatusCode::BAD_REQUEST, format!("Invalid JSON body: {}", e)))
+}
+
+/// Collect a request body into bytes.
+async fn body_bytes(body: HttpBody) -> Result<Vec<u8>, ApiError> {
+    axum::body::to_bytes(body, 1024 * 1024 * 16)
+        .await
+        .map(|b| b.to_vec())
+        .map_err(|e| api_error(StatusCode::BAD_REQUEST, e.to_string()))
+}
+
+/// List missions (the other collections already have list endpoints).
+async fn list_missions(State(state): State<AppState>) -> Result<Json<Value>, ApiError> {
+    let storage = Storage::open(&state.repo_path).map_err(command_error)?;
+    let missions = storage
+        .list_missions(None, None, None)
+        .map_err(command_error)?;
+    Ok(Json(json!({ "missions": missions })))
+}
+
+/// List commits linked to an entity.
+async fn list_commit_links(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+) -> Result<Json<Value>, ApiError> {
+    let list = commands::commit_list(&state.repo_path, &id).map_err(command_error)?;
+    Ok(Json(json!(list)))
+}
+
+/// Link a commit to an entity (`{"sha": "..."}`).
+async fn link_commit(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+    body: HttpBody,
+) -> Result<Response, ApiError> {
+    ensure_writable(&state)?;
+    let body = Body::parse(
+        json_body(&body_bytes(body).await?)?,
+        &[required("sha", FieldKind::String, "Commit SHA")],
+    )?;
+
+    let _guard = state.storage.lock().await;
+    let linked = commands::commit_link(&state.repo_path, &body.required_string("sha"), &id)
+        .map_err(command_error)?;
+    Ok((StatusCode::CREATED, Json(json!(linked))).into_response())
+}
+
+/// Unlink a commit from an entity.
+async fn unlink_commit(
+    State(state): State<AppState>,
+    AxumPath((id, sha)): AxumPath<(String, String)>,
+) -> Result<Json<Value>, ApiError> {
+    ensure_writable(&state)?;
+
+    let _guard = state.storage.lock().await;
+    let unlinked = commands::commit_unlink(&state.repo_path, &sha, &id).map_err(command_error)?;
+    Ok(Json(json!(unlinked)))
+}
+
+/// Serve the OpenAPI document.
+async fn openapi_json() -> Json<Value> {
+    Json(openapi_document())
+}
+
+/// Add the REST routes to the GUI router.
+pub(super) fn add_routes(mut router: Router<AppState>) -> Router<AppState> {
+    router = router
+        .route("/api/openapi.json", get(openapi_json))
+        .route("/api/missions", get(list_missions))
+        .route(
+            "/api/node/:id/commits",
+            get(list_commit_links).post(link_commit),
+        )
+        .route(
+            "/api/node/:id/commits/:sha",
+            axum::routing::delete(unlink_commit),
+        );
+
+    for resource in Resource::ALL {
+        let collection = format!("/api/{}", resource.collection());
+        let item = format!("{}/:id", collection);
+
+        router = router.route(
+            &collection,
+            post(
+                move |State(state): State<AppState>, body: HttpBody| async move {
+                    let body = json_body(&body_bytes(body).await?)?;
+                    create_entity(resource, state, body).await
+                },
+            ),
+        );
+
+        let mut item_routes = axum::routing::patch(
+            move |State(state): State<AppState>,
+                  AxumPath(id): AxumPath<String>,
+                  headers: HeaderMap,
+                  body: HttpBody| async move {
+                let body = json_body(&body_bytes(body).await?)?;
+                update_entity(resource, state, headers, id, body).await
+            },
+        )
+        .delete(
+            move |State(state): State<AppState>,
+                  AxumPath(id): AxumPath<String>,
+                  headers: HeaderMap| async move {
+                delete_entity(resource, state, headers, id).await
+            },
+        );
+        // Docs keep their existing GET handler (which adds version history fields)
+        if resource != Resource::Doc {
+            item_routes = item_routes.get(
+                move |State(state): State<AppState>,
+                      AxumPath(id): AxumPath<String>,
+                      headers: HeaderMap| async move {
+                    get_entity(resource, state, headers, id).await
+                },
+            );
+        }
+        router = router.route(&item, item_routes);
+
+        if resource.close_fields().is_some() {
+            router = router.route(
+                &format!("{}/close", item),
+                post(
+                    move |State(state): State<AppState>,
+                          AxumPath(id): AxumPath<String>,
+                          headers: HeaderMap,
+                          body: HttpBody| async move {
+                        let body = json_body(&body_bytes(body).await?)?;
+                        close_entity(resource, state, headers, id, body).await
+                    },
+                ),
+            );
+        }
+        if resource.can_reopen() {
+            router = router.route(
+                &format!("{}/reopen", item),
+                post(
+                    move |State(state): State<AppState>,
+                          AxumPath(id): AxumPath<String>,
+                          headers: HeaderMap| async move {
+                        reopen_entity(resource, state, headers, id).await
+                    },
+                ),
+            );
+        }
+    }
+
+    router
+}
+
+// ============================================================================
+// OpenAPI
+// ============================================================================
+
+/// JSON schema for a request body.
+fn body_schema(fields: &[Field]) -> Value {
+    let properties: Map<String, Value> = fields
+        .iter()
+        .map(|f| {
+            let mut schema = f.kind.schema();
+            schema["description"] = json!(f.description);
+            (f.name.to_string(), schema)
+        })
+        .collect();
+    let required: Vec<&str> = fields
+        .iter()
+        .filter(|f| f.required)
+        .map(|f| f.name)
+        .collect();
+
+    let mut schema = json!({
+        "type": "object",
+        "additionalProperties": false,
+        "properties": properties,
+    });
+    if !required.is_empty() {
+        schema["required"] = json!(required);
+    }
+    schema
+}
+
+fn json_content(schema: Value) -> Value {
+    json!({ "application/json": { "schema": schema } })
+}
+
+/// Response wrapping a single entity under its singular key.
+fn entity_responses(resource: Resource, success: &str, description: &str) -> Value {
+    json!({
+        success: {
+            "description": description,
+            "headers": { "ETag": { "schema": { "type": "string" } } },
+            "content": json_content(json!({
+                "type": "object",
+                "properties": {
+                    resource.singular(): { "$ref": format!("#/components/schemas/{}", resource.title()) }
+                }
+            }))
+        },
+        "400": { "$ref": "#/components/responses/BadRequest" },
+        "404": { "$ref": "#/components/responses/NotFound" },
+        "412": { "$ref": "#/components/responses/PreconditionFailed" }
+    })
+}
+
+/// Generate the OpenAPI 3.0 document for the REST API.
+pub fn openapi_document() -> Value {
+    let mut paths = Map::new();
+    let mut schemas = Map::new();
+    let id_param = json!({
+        "name": "id", "in": "path", "required": true, "schema": { "type": "string" }
+    });
+    let if_match = json!({
+        "name": "If-Match", "in": "header", "required": false,
+        "description": "ETag from a previous response; the request fails with 412 if the entity changed",
+        "schema": { "type": "string" }
+    });
+
+    for resource in Resource::ALL {
+        let name = resource.title();
+        let tag = resource.collection();
+        schemas.insert(
+            name.to_string(),
+            json!({ "type": "object", "additionalProperties": true }),
+        );
+        schemas.insert(
+            format!("{}Create", name),
+            body_schema(resource.create_fields()),
+        );
+        schemas.insert(
+            format!("{}Update", name),
+            body_schema(resource.update_fields()),
+        );
+
+        paths.insert(
+            format!("/api/{}", resource.collection()),
+            json!({
+                "get": {
+                    "tags": [tag],
+                    "summary": format!("List {}", resource.collection()),
+                    "responses": { "200": { "description": "OK", "content": json_content(json!({
+                        "type": "object",
+                        "properties": { resource.collection(): {
+                            "type": "array",
+                            "items": { "$ref": format!("#/components/schemas/{}", name) }
+                        } }
+                    })) } }
+                },
+                "post": {
+                    "tags": [tag],
+                    "summary": format!("Create a {}", resource.singular()),
+                    "requestBody": { "required": true, "content": json_content(
+                        json!({ "$ref": format!("#/components/schemas/{}Create", name) })
+                    ) },
+                    "responses": entity_responses(resource, "201", "Created"),
+                }
+            }),
+        );
+
+        let mut item = json!({
+            "parameters": [id_param],
+            "get": {
+                "tags": [tag],
+                "summary": format!("Get a {}", resource.singular()),
+                "responses": entity_responses(resource, "200", "OK"),
+            },
+            "patch": {
+                "tags": [tag],
+                "summary": format!("Update a {}", resource.singular()),
+                "parameters": [if_match],
+                "requestBody": { "required": true, "content": json_content(
+                    json!({ "$ref": format!("#/components/schemas/{}Update", name) })
+                ) },
+                "responses": entity_responses(resource, "200", "Updated"),
+            },
+            "delete": {
+                "tags": [tag],
+                "summary": format!("Delete a {}", resource.singular()),
+                "parameters": [if_match],
+                "responses": {
+                    "200": { "description": "Deleted" },
+                    "404": { "$ref": "#/components/responses/NotFound" },
+                    "412": { "$ref": "#/components/responses/PreconditionFailed" }
+                }
+            }
+        });
+        if resource == Resource::Doc {
+            item["patch"]["description"] =
+                json!("Creates a new doc version; the response contains the new version's ID");
+        }
+        paths.insert(format!("/api/{}/{{id}}", resource.collection()), item);
+
+        if let Some(fields) = resource.close_fields() {
+            schemas.insert(format!("{}Close", name), body_schema(fields));
+            paths.insert(
+                format!("/api/{}/{{id}}/close", resource.collection()),
+                json!({
+                    "parameters": [id_param],
+                    "post": {
+                        "tags": [tag],
+                        "summary": format!("Close a {}", resource.singular()),
+                        "parameters": [if_match],
+                        "requestBody": { "required": false, "content": json_content(
+                            json!({ "$ref": format!("#/components/schemas/{}Close", name) })
+                        ) },
+                        "responses": entity_responses(resource, "200", "Closed"),
+                    }
+                }),
+            );
+        }
+        if resource.can_reopen() {
+            paths.insert(
+                format!("/api/{}/{{id}}/reopen", resource.collection()),
+                json!({
+                    "parameters": [id_param],
+                    "post": {
+                        "tags": [tag],
+                        "summary": format!("Reopen a {}", resource.singular()),
+                        "parameters": [if_match],
+                        "responses": entity_responses(resource, "200", "Reopened"),
+                    }
+                }),
+            );
+        }
+    }
+
+    paths.insert(
+        "/api/node/{id}/commits".to_string(),
+        json!({
+            "parameters": [id_param],
+            "get": {
+                "tags": ["commits"],
+                "summary": "List commits linked to an entity",
+                "responses": { "200": { "description": "OK" } }
+            },
+            "post": {
+                "tags": ["commits"],
+                "summary": "Link a commit to an entity",
+                "requestBody": { "required": true, "content": json_content(
+                    body_schema(&[required("sha", FieldKind::String, "Commit SHA")])
+                ) },
+                "responses": {
+                    "201": { "description": "Linked" },
+                    "400": { "$ref": "#/components/responses/BadRequest" }
+                }
+            }
+        }),
+    );
+    paths.insert(
+        "/api/node/{id}/commits/{sha}".to_string(),
+        json!({
+            "parameters": [id_param, {
+                "name": "sha", "in": "path", "required": true, "schema": { "type": "string" }
+            }],
+            "delete": {
+                "tags": ["commits"],
+                "summary": "Unlink a commit from an entity",
+                "responses": {
+                    "200": { "description": "Unlinked" },
+                    "404": { "$ref": "#/components/responses/NotFound" }
+                }
+            }
+        }),
+    );
+
+    schemas.insert(
+        "Error".to_string(),
+        json!({
+            "type": "object",
+            "properties": {
+                "error": { "type": "string" },
+                "etag": { "type": "string", "description": "Current ETag (412 responses only)" }
+            },
+            "required": ["error"]
+        }),
+    );
+    let error_response = |description: &str| {
+        json!({
+            "description": description,
+            "content": json_content(json!({ "$ref": "#/components/schemas/Error" }))
+        })
+    };
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Binnacle REST API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "CRUD for binnacle graph entities. Authenticate with `Authorization: Bearer <token>` when GUI tokens are configured."
+        },
+        "paths": paths,
+        "components": {
+            "schemas": schemas,
+            "responses": {
+                "BadRequest": error_response("Invalid request or validation error"),
+                "NotFound": error_response("Entity not found"),
+                "PreconditionFailed": error_response("If-Match does not match the current ETag"),
+            },
+            "securitySchemes": {
+                "bearer": { "type": "http", "scheme": "bearer" }
+            }
+        },
+        "security": [{ "bearer": [] }]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_body_validation() {
+        let fields = Resource::Task.create_fields();
+
+        let body = Body::parse(
+            Some(json!({"title": "A", "priority": 2, "tags": ["x"]})),
+            fields,
+        )
+        .unwrap();
+        assert_eq!(body.required_string("title"), "A");
+        assert_eq!(body.priority().unwrap(), Some(2));
+        assert_eq!(body.strings("tags"), vec!["x"]);
+
+        for invalid in [
+            json!({}),
+            json!({"title": "A", "bogus": 1}),
+            json!({"title": 5}),
+            json!({"title": "A", "tags": [1]}),
+            json!(["not", "an", "object"]),
+        ] {
+            let err = Body::parse(Some(invalid.clone()), fields).unwrap_err();
+            assert_eq!(err.0, StatusCode::BAD_REQUEST, "{}", invalid);
+        }
+
+        let body = Body::parse(Some(json!({"title": "A", "priority": 9})), fields).unwrap();
+        assert!(body.priority().is_err());
+
+        // Empty bodies are fine when nothing is required
+        assert!(Body::parse(None, Resource::Task.update_fields()).is_ok());
+    }
+
+    #[test]
+    fn test_etag_and_if_match() {
+        let entity = json!({"id": "bn-1", "updated_at": "2026-01-01T00:00:00Z"});
+        let etag = etag_for(&entity);
+        assert_eq!(etag, "\"2026-01-01T00:00:00Z\"");
+
+        // Entities without updated_at hash their content
+        let test = json!({"id": "bnt-1", "name": "a"});
+        assert!(etag_for(&test).starts_with("\"sha256-"));
+        assert_ne!(
+            etag_for(&test),
+            etag_for(&json!({"id": "bnt-1", "name": "b"}))
+        );
+
+        let mut headers = HeaderMap::new();
+        assert!(check_if_match(&headers, &etag).is_ok());
+        headers.insert(header::IF_MATCH, HeaderValue::from_str(&etag).unwrap());
+        assert!(check_if_match(&headers, &etag).is_ok());
+        headers.insert(header::IF_MATCH, HeaderValue::from_static("W/\"stale\", *"));
+        assert!(check_if_match(&headers, &etag).is_ok());
+        headers.insert(header::IF_MATCH, HeaderValue::from_static("\"stale\""));
+        let err = check_if_match(&headers, &etag).unwrap_err();
+        assert_eq!(err.0, StatusCode::PRECONDITION_FAILED);
+        assert_eq!(err.1.0["etag"], etag);
+    }
+
+    #[test]
+    fn test_openapi_document_covers_resources() {
+        let doc = openapi_document();
+        assert_eq!(doc["openapi"], "3.0.3");
+        let paths = doc["paths"].as_object().unwrap();
+
+        for resource in Resource::ALL {
+            let collection = format!("/api/{}", resource.collection());
+            let item = format!("{}/{{id}}", collection);
+            assert!(paths[&collection]["post"].is_object(), "{}", collection);
+            assert!(paths[&item]["patch"].is_object(), "{}", item);
+            assert!(paths[&item]["delete"].is_object(), "{}", item);
+            assert_eq!(
+                paths.contains_key(&format!("{}/close", item)),
+                resource.close_fields().is_some()
+            );
+        }
+        assert!(paths.contains_key("/api/node/{id}/commits/{sha}"));
+
+        let task_create = &doc["components"]["schemas"]["TaskCreate"];
+        assert_eq!(task_create["required"], json!(["title"]));
+        assert!(task_create["properties"]["priority"].is_object());
+        let test_create = &doc["components"]["schemas"]["TestCreate"];
+        assert!(test_create["properties"]["pattern"].is_object());
+        let doc_update = &doc["components"]["schemas"]["DocUpdate"]["properties"];
+        assert_eq!(doc_update["merge"]["type"], "boolean");
+        assert_eq!(doc_update["fork"]["type"], "boolean");
+    }
+
+    #[test]
+    fn test_create_test_with_pattern() {
+        let env = crate::test_utils::TestEnv::new_isolated();
+        env.init_storage();
+        let body = Body::parse(
+            Some(json!({"name": "unit", "command": "true", "pattern": "src/**/*.rs"})),
+            Resource::Test.create_fields(),
+        )
+        .unwrap();
+        let id = Resource::Test.create(env.path(), &body).unwrap();
+        let test = Resource::Test
+            .load(&Storage::open(env.path()).unwrap(), &id)
+            .unwrap();
+        assert_eq!(test["pattern"], "src/**/*.rs");
+    }
+
+    #[test]
+    fn test_update_superseded_doc_with_merge_or_fork() {
+        let env = crate::test_utils::TestEnv::new_isolated();
+        env.init_storage();
+        let parse = |resource: Resource, body: Value, update: bool| {
+            let fields = if update {
+                resource.update_fields()
+            } else {
+                resource.create_fields()
+            };
+            Body::parse(Some(body), fields).unwrap()
+        };
+        let task = Resource::Task
+            .create(
+                env.path(),
+                &parse(Resource::Task, json!({"title": "T"}), false),
+            )
+            .unwrap();
+        let original = Resource::Doc
+            .create(
+                env.path(),
+                &parse(
+                    Resource::Doc,
+                    json!({"title": "Notes", "entity_ids": [task], "content": "a\n"}),
+                    false,
+                ),
+            )
+            .unwrap();
+        let update = |body: Value| {
+            Resource::Doc.update(env.path(), &original, &parse(Resource::Doc, body, true))
+        };
+        let head = update(json!({"content": "b\n"})).unwrap();
+
+        // The original is superseded now
+        assert!(update(json!({"content": "c\n"})).is_err());
+        assert!(update(json!({"content": "c\n", "merge": true, "fork": true})).is_err());
+        let merged = update(json!({"title": "Renamed", "merge": true})).unwrap();
+        let doc = Resource::Doc
+            .load(&Storage::open(env.path()).unwrap(), &merged)
+            .unwrap();
+        assert_eq!(doc["supersedes"], head.as_str());
+        let forked = update(json!({"content": "c\n", "fork": true})).unwrap();
+        let doc = Resource::Doc
+            .load(&Storage::open(env.path()).unwrap(), &forked)
+            .unwrap();
+        assert_eq!(doc["supersedes"], original.as_str());
+    }
+}
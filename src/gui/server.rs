@@ -4,6 +4,7 @@ use axum::{
     Json, Router,
     extract::{Path as AxumPath, Query, State},
     http::StatusCode,
+    response::{IntoResponse, Response},
     routing::{get, post},
 };
 use serde::Deserialize;
@@ -45,7 +46,7 @@ use crate::storage::{Storage, generate_id};
 use chrono::Utc;
 
 /// Error response for readonly mode rejection
-fn readonly_error() -> (StatusCode, Json<serde_json::Value>) {
+pub(super) fn readonly_error() -> (StatusCode, Json<serde_json::Value>) {
     (
         StatusCode::FORBIDDEN,
         Json(serde_json::json!({
@@ -54,6 +55,31 @@ fn readonly_error() -> (StatusCode, Json<serde_json::Value>) {
     )
 }
 
+/// Print whether token authentication is active, warning when the server is
+/// reachable from the network without any tokens configured.
+fn print_auth_status(repo_path: &Path, host: &str) {
+    let token_count = Storage::open(repo_path)
+        .and_then(|s| s.read_binnacle_state())
+        .map(|s| s.gui_tokens.len())
+        .unwrap_or(0);
+
+    if token_count > 0 {
+        println!("Authentication: enabled ({} token(s))", token_count);
+    } else {
+        let loopback = host
+            .parse::<std::net::IpAddr>()
+            .map(|ip| ip.is_loopback())
+            .unwrap_or(false);
+        if !loopback {
+            eprintln!(
+                "Warning: no GUI tokens configured - anyone who can reach {} has full access.",
+                host
+            );
+            eprintln!("         Create one with: bn gui token create --role admin");
+        }
+    }
+}
+
 /// Derive repository name from git remote URL.
 ///
 /// Parses the git remote URL (origin) to extract the repository name.
@@ -262,7 +288,8 @@ pub struct MessageHistory {
 struct MessageHistoryInner {
     /// Circular buffer of recent messages
     messages: Vec<VersionedMessage>,
-    /// Oldest version number we can serve (start of buffer)
+    /// Oldest version a client can resume from: every message with a newer
+    /// version is still in the buffer
     oldest_version: u64,
     /// Maximum buffer size
     capacity: usize,
@@ -274,7 +301,7 @@ impl MessageHistory {
         Self {
             buffer: Arc::new(Mutex::new(MessageHistoryInner {
                 messages: Vec::with_capacity(capacity),
-                oldest_version: 1,
+                oldest_version: 0,
                 capacity,
             })),
         }
@@ -295,36 +322,32 @@ impl MessageHistory {
         // If we exceed capacity, remove oldest messages
         if inner.messages.len() > inner.capacity {
             let excess = inner.messages.len() - inner.capacity;
-            inner.messages.drain(0..excess);
-
-            // Update oldest_version to reflect what's available
-            if let Some(first) = inner.messages.first() {
-                inner.oldest_version = first.version;
+            // Several messages can share a version, so a client is only
+            // resumable from the newest version we dropped
+            let dropped = inner.messages.drain(0..excess).next_back();
+            if let Some(dropped) = dropped {
+                inner.oldest_version = inner.oldest_version.max(dropped.version);
             }
         }
     }
 
     /// Clear the message history (e.g., when a full reload is sent)
-    pub async fn clear(&self) {
+    ///
+    /// Clients older than `version` can no longer resume and must reload.
+    pub async fn clear(&self, version: u64) {
         let mut inner = self.buffer.lock().await;
         inner.messages.clear();
-        // Keep track of where we are, even though buffer is empty
-        // This will be updated when new messages arrive
+        inner.oldest_version = inner.oldest_version.max(version);
     }
 
-    /// Get messages since the given version (inclusive)
+    /// Get messages newer than the given version
     ///
     /// Returns None if the requested version is too old (not in history)
     /// Returns Some(vec) with the messages if available
     pub async fn get_since(&self, since_version: u64) -> Option<Vec<VersionedMessage>> {
         let inner = self.buffer.lock().await;
 
-        // Check if we can serve this request
-        if inner.messages.is_empty() {
-            return Some(Vec::new());
-        }
-
-        // If requested version is older than our oldest, we can't help
+        // If messages after the requested version were dropped, we can't help
         if since_version < inner.oldest_version {
             return None;
         }
@@ -385,6 +408,10 @@ pub struct AppState {
     pub message_history: MessageHistory,
     /// Summarize agent session (max 1 concurrent)
     pub summarize_session: Arc<Mutex<Option<SummarizeSession>>>,
+    /// Latest agent supervisor snapshot (session server with `--supervise`)
+    pub supervisor: Arc<Mutex<Option<crate::agents::SupervisorStatus>>>,
+    /// GUI tokens from state.kdl, reloaded when the file changes
+    pub gui_tokens: Arc<super::auth::GuiTokenCache>,
 }
 
 /// Start the GUI web server
@@ -405,6 +432,7 @@ pub async fn start_server(
     let version = StateVersion::new();
     let message_history = MessageHistory::default();
 
+    let gui_tokens = Arc::new(super::auth::GuiTokenCache::new(storage.state_kdl_path()));
     let state = AppState {
         storage: Arc::new(Mutex::new(storage)),
         update_tx,
@@ -415,6 +443,8 @@ pub async fn start_server(
         version,
         message_history: message_history.clone(),
         summarize_session: Arc::new(Mutex::new(None)),
+        supervisor: Arc::new(Mutex::new(None)),
+        gui_tokens,
     };
 
     // Start file watcher in background
@@ -438,7 +468,7 @@ pub async fn start_server(
     });
 
     // Build the app router with API routes
-    let mut app = Router::new()
+    let api = Router::new()
         .route("/api/config", get(get_config))
         .route("/api/tasks", get(get_tasks))
         .route("/api/bugs", get(get_bugs))
@@ -451,6 +481,7 @@ pub async fn start_server(
         .route("/api/docs", get(get_docs))
         .route("/api/docs/:id", get(get_doc))
         .route("/api/docs/:id/history", get(get_doc_history))
+        .route("/api/docs/:id/diff", get(get_doc_diff))
         .route("/api/node/:id", get(get_node))
         .route("/api/queue", get(get_queue))
         .route("/api/queue/toggle", post(toggle_queue_membership))
@@ -473,14 +504,21 @@ pub async fn start_server(
         .route("/api/agents", get(get_agents))
         .route("/api/agents/:pid/kill", post(kill_agent))
         .route("/api/agents/:id/terminate", post(terminate_agent))
+        .route("/api/agents/:id/transcript", get(get_agent_transcript))
+        .route("/api/merge-queue", get(get_merge_queue))
+        .route("/api/messages", get(get_messages))
+        .route("/api/messages/:id/reply", post(reply_to_message))
         .route("/api/commits", get(get_git_commits))
+        .route("/api/blame", get(get_blame))
         .route("/api/metrics/ws", get(get_ws_metrics))
         .route("/api/version", get(get_version))
         .route("/api/summarize/start", post(summarize_start))
         .route("/api/summarize/chat", post(summarize_chat))
         .route("/api/summarize/action", post(summarize_action))
-        .route("/ws", get(crate::gui::websocket::ws_handler))
-        .with_state(state);
+        .route("/ws", get(crate::gui::websocket::ws_handler));
+    // REST CRUD endpoints and the OpenAPI document
+    let mut app = super::rest::add_routes(api).with_state(state.clone());
+    let auth_state = state;
 
     // Add asset service based on dev mode
     if dev {
@@ -523,6 +561,12 @@ pub async fn start_server(
         app = app.fallback_service(EmbeddedAssetService::new());
     }
 
+    // Enforce token roles on every route (including assets and /ws)
+    let app = app.layer(axum::middleware::from_fn_with_state(
+        auth_state,
+        crate::gui::auth::require_token,
+    ));
+
     let host_addr: std::net::IpAddr = host
         .parse()
         .map_err(|e| format!("Invalid host address '{}': {}", host, e))?;
@@ -542,6 +586,7 @@ pub async fn start_server(
     } else {
         println!("Starting binnacle GUI at http://{}", addr);
     }
+    print_auth_status(repo_path, host);
     println!("Press Ctrl+C to stop");
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
@@ -785,7 +830,7 @@ async fn get_docs(State(state): State<AppState>) -> Result<Json<serde_json::Valu
 async fn get_doc(
     State(state): State<AppState>,
     AxumPath(id): AxumPath<String>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Response, StatusCode> {
     let storage = state.storage.lock().await;
     let doc = storage.get_doc(&id).map_err(|_| StatusCode::NOT_FOUND)?;
 
@@ -800,7 +845,8 @@ async fn get_doc(
     // Check if this doc has been superseded by another doc
     let superseded_by = storage.get_doc_superseded_by(&id).unwrap_or(None);
 
-    Ok(Json(serde_json::json!({
+    let etag = super::rest::etag_for(&serde_json::json!({ "updated_at": doc.core.updated_at }));
+    let mut response = Json(serde_json::json!({
         "doc": {
             "id": doc.core.id,
             "title": doc.core.title,
@@ -817,7 +863,14 @@ async fn get_doc(
             "created_at": doc.core.created_at,
             "updated_at": doc.core.updated_at
         }
-    })))
+    }))
+    .into_response();
+    if let Ok(value) = axum::http::HeaderValue::from_str(&etag) {
+        response
+            .headers_mut()
+            .insert(axum::http::header::ETAG, value);
+    }
+    Ok(response)
 }
 
 /// Get version history for a doc
@@ -866,6 +919,34 @@ async fn get_doc_history(
     })))
 }
 
+/// Query parameters for the doc diff endpoint
+#[derive(Deserialize)]
+struct DocDiffQueryParams {
+    /// Older version (`v1`, `1` or a doc ID)
+    from: Option<String>,
+    /// Newer version (`v3`, `3` or a doc ID)
+    to: Option<String>,
+}
+
+/// Unified diff between two versions of a doc (same output as `bn doc diff`)
+async fn get_doc_diff(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+    Query(params): Query<DocDiffQueryParams>,
+) -> Result<Json<crate::commands::DocDiff>, (StatusCode, Json<serde_json::Value>)> {
+    let storage = state.storage.lock().await;
+    let result =
+        crate::commands::doc_diff_with(&storage, &id, params.from.as_deref(), params.to.as_deref())
+            .map_err(|e| {
+                let status = match e {
+                    crate::Error::InvalidInput(_) => StatusCode::BAD_REQUEST,
+                    _ => StatusCode::NOT_FOUND,
+                };
+                (status, Json(serde_json::json!({ "error": e.to_string() })))
+            })?;
+    Ok(Json(result))
+}
+
 /// Get any node by ID with its edges
 /// This endpoint returns the node data regardless of type (task, bug, idea, etc.)
 /// along with its edges for navigation purposes
@@ -1128,7 +1209,95 @@ async fn get_agents(State(state): State<AppState>) -> Result<Json<serde_json::Va
         })
         .collect();
 
-    Ok(Json(serde_json::json!({ "agents": agents_with_health })))
+    let mut body = serde_json::json!({ "agents": agents_with_health });
+    if let Some(status) = state.supervisor.lock().await.as_ref() {
+        body["supervisor"] = serde_json::to_value(status).unwrap_or_default();
+    }
+    Ok(Json(body))
+}
+
+/// Get an agent's transcript interleaved with graph changes to its tasks
+async fn get_agent_transcript(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let storage = state.storage.lock().await;
+    let timeline = crate::agents::agent_timeline(&storage, &id).map_err(|e| match e {
+        crate::Error::NotFound(_) => StatusCode::NOT_FOUND,
+        crate::Error::InvalidInput(_) => StatusCode::BAD_REQUEST,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    })?;
+    let tasks = crate::agents::transcript::touched_tasks(&storage, &id)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(serde_json::json!({
+        "agent_id": id,
+        "tasks": tasks,
+        "timeline": timeline,
+    })))
+}
+
+/// Get the merge queue, including finished entries
+async fn get_merge_queue(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let storage = state.storage.lock().await;
+    let entries = storage
+        .list_merge_queue()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(serde_json::json!({ "entries": entries })))
+}
+
+/// Get all messages and the questions still waiting for a human answer
+async fn get_messages(
+    State(state): State<AppState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let storage = state.storage.lock().await;
+    let messages = storage
+        .list_messages()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let open_questions =
+        crate::commands::open_questions(&storage).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(serde_json::json!({
+        "messages": messages,
+        "open_questions": open_questions,
+    })))
+}
+
+#[derive(Deserialize)]
+struct MessageReplyRequest {
+    body: String,
+}
+
+/// Reply to a message as `human`; answering a question unparks `bn ask --wait`
+async fn reply_to_message(
+    State(state): State<AppState>,
+    AxumPath(id): AxumPath<String>,
+    Json(req): Json<MessageReplyRequest>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    if state.readonly {
+        return Err(readonly_error());
+    }
+
+    let mut storage = state.storage.lock().await;
+    let reply = crate::commands::reply_to_message(
+        &mut storage,
+        &id,
+        crate::models::HUMAN_RECIPIENT,
+        &req.body,
+    )
+    .map_err(|e| {
+        let status = match e {
+            crate::Error::NotFound(_) => StatusCode::NOT_FOUND,
+            crate::Error::InvalidInput(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, Json(serde_json::json!({ "error": e.to_string() })))
+    })?;
+
+    Ok(Json(serde_json::json!({ "message": reply })))
 }
 
 /// Kill an agent by PID
@@ -1195,13 +1364,11 @@ async fn terminate_agent(
 
     // Terminate the agent (container or process)
     if let Some(cid) = container_id {
-        // Containerized agent: use containerd to stop the container
+        // Containerized agent: signal the container through its runtime
         #[cfg(unix)]
         {
-            use std::process::Command;
-            let result = Command::new("ctr")
-                .args(["task", "kill", "--signal", "SIGTERM", &cid])
-                .status();
+            let result = crate::container::runtime::select_runtime(&state.repo_path)
+                .and_then(|runtime| runtime.signal(&cid, 15));
 
             if let Err(e) = result {
                 return Err((
@@ -2357,6 +2524,31 @@ async fn get_git_commits(
     })))
 }
 
+/// Query parameters for the blame endpoint
+#[derive(Deserialize)]
+struct BlameQueryParams {
+    /// `file`, `file:line` or `file:start-end`, relative to the repository
+    target: String,
+}
+
+/// Tasks and bugs behind each line of a file (same output as `bn blame`)
+async fn get_blame(
+    State(state): State<AppState>,
+    Query(params): Query<BlameQueryParams>,
+) -> Result<Json<crate::commands::BlameResult>, (StatusCode, Json<serde_json::Value>)> {
+    let storage = state.storage.lock().await;
+    let result =
+        crate::commands::blame_with(&storage, &state.repo_path, &params.target).map_err(|e| {
+            let status = match e {
+                crate::Error::InvalidInput(_) => StatusCode::BAD_REQUEST,
+                // git blame fails on unknown or untracked files
+                _ => StatusCode::NOT_FOUND,
+            };
+            (status, Json(serde_json::json!({ "error": e.to_string() })))
+        })?;
+    Ok(Json(result))
+}
+
 /// Request body for starting a summarize session
 #[derive(Debug, serde::Deserialize)]
 #[allow(dead_code)]
@@ -2526,12 +2718,22 @@ async fn summarize_action(
 /// * `port` - Port to listen on
 /// * `host` - Host address to bind to
 /// * `tunnel` - If true, create a public URL via devtunnel
+/// * `upstream` - Optional upstream hub URL to register with
+/// * `upstream_pins` - Normalized certificate fingerprints trusted for the upstream hub
+/// * `upstream_token` - Bearer token presented to the upstream hub
+/// * `tls` - Serve over TLS (HTTPS/WSS) with the given certificate options
+/// * `supervise` - Run the agent pool supervisor (see `bn agent supervise`)
+#[allow(clippy::too_many_arguments)]
 pub async fn start_session_server(
     repo_path: &Path,
     port: u16,
     host: &str,
     tunnel: bool,
     upstream: Option<&str>,
+    upstream_pins: Vec<String>,
+    upstream_token: Option<String>,
+    tls: Option<crate::tls::TlsOptions>,
+    supervise: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // Start tunnel if requested
     let _tunnel_manager = if tunnel {
@@ -2553,6 +2755,12 @@ pub async fn start_session_server(
     let storage_dir = crate::storage::get_storage_dir(repo_path)?;
     let (update_tx, _) = broadcast::channel(100);
 
+    // Load (or generate) the TLS certificate before anything is started
+    let server_tls = match tls {
+        Some(options) => Some(options.load(&storage_dir, host)?),
+        None => None,
+    };
+
     // Extract project name from git remote (falls back to directory name)
     let project_name = derive_repo_name(repo_path);
 
@@ -2584,6 +2792,7 @@ pub async fn start_session_server(
     let version = StateVersion::new();
     let message_history = MessageHistory::default();
 
+    let gui_tokens = Arc::new(super::auth::GuiTokenCache::new(storage.state_kdl_path()));
     let state = AppState {
         storage: Arc::new(Mutex::new(storage)),
         update_tx,
@@ -2594,11 +2803,16 @@ pub async fn start_session_server(
         version,
         message_history: message_history.clone(),
         summarize_session: Arc::new(Mutex::new(None)),
+        supervisor: Arc::new(Mutex::new(None)),
+        gui_tokens,
     };
 
     // Clone values needed for upstream client before consuming state
     let upstream_storage = state.storage.clone();
     let upstream_update_tx = state.update_tx.clone();
+    let webhook_update_tx = state.update_tx.clone();
+    let supervisor_status = state.supervisor.clone();
+    let supervisor_update_tx = state.update_tx.clone();
 
     // Start file watcher in background
     let watcher_tx = state.update_tx.clone();
@@ -2621,13 +2835,20 @@ pub async fn start_session_server(
     });
 
     // Build minimal router with WebSocket, health check, and essential API endpoints
-    // The TUI uses /api/ready and /api/node/:id for data fetching
+    // The TUI uses /api/ready and /api/node/:id for data fetching and
+    // /api/messages/:id/reply for `:answer`
     let app = Router::new()
         .route("/ws", get(crate::gui::websocket::ws_handler))
         .route("/health", get(|| async { "ok" }))
         .route("/api/ready", get(get_ready))
         .route("/api/node/:id", get(get_node))
-        .with_state(state);
+        .route("/api/agents", get(get_agents))
+        .route("/api/messages/:id/reply", post(reply_to_message))
+        .with_state(state.clone())
+        .layer(axum::middleware::from_fn_with_state(
+            state,
+            crate::gui::auth::require_token,
+        ));
 
     let host_addr: std::net::IpAddr = host
         .parse()
@@ -2645,7 +2866,8 @@ pub async fn start_session_server(
         serve_project_name.clone(),
         serve_branch.clone(),
         upstream.map(|s| s.to_string()),
-    );
+    )
+    .with_tls_fingerprint(server_tls.as_ref().map(|t| t.fingerprint.clone()));
     {
         let storage_for_state = Storage::open(repo_path)?;
         let mut binnacle_state = storage_for_state.read_binnacle_state()?;
@@ -2668,6 +2890,22 @@ pub async fn start_session_server(
         }
     });
 
+    // Deliver graph changes to webhooks configured in config.kdl
+    let webhook_handle = crate::gui::webhooks::spawn_webhook_dispatcher(
+        repo_path.to_path_buf(),
+        storage_dir.clone(),
+        webhook_update_tx.subscribe(),
+    );
+
+    // Supervise the agent pool if requested
+    let supervisor_handle = supervise.then(|| {
+        crate::gui::supervisor::spawn_supervisor(
+            repo_path.to_path_buf(),
+            supervisor_status,
+            supervisor_update_tx,
+        )
+    });
+
     // Start upstream client if URL provided
     let upstream_handle = if let Some(upstream_url) = upstream {
         let session_id = crate::gui::upstream::derive_session_id(repo_path);
@@ -2685,6 +2923,8 @@ pub async fn start_session_server(
             session_info,
             upstream_storage,
             upstream_rx,
+            upstream_pins,
+            upstream_token,
         ))
     } else {
         None
@@ -2694,24 +2934,69 @@ pub async fn start_session_server(
         "Session server started: {} on {}:{}",
         display_name, host, port
     );
-    println!("WebSocket endpoint: ws://{}:{}/ws", host, port);
+    let ws_scheme = if server_tls.is_some() { "wss" } else { "ws" };
+    println!("WebSocket endpoint: {}://{}:{}/ws", ws_scheme, host, port);
+    if let Some(ref tls) = server_tls {
+        println!("TLS certificate: {}", tls.cert_path.display());
+        println!("TLS fingerprint: {}", tls.fingerprint);
+        if tls.self_signed {
+            println!(
+                "Self-signed certificate: connect with `bn tui --url wss://<host>:{}/ws --tls-pin {}`",
+                port, tls.fingerprint
+            );
+        }
+    }
     if let Some(upstream_url) = upstream {
         println!("Upstream hub: {}", upstream_url);
     }
+    match Storage::open(repo_path).and_then(|s| s.get_webhooks_kdl()) {
+        Ok(webhooks) if !webhooks.is_empty() => println!(
+            "Webhooks: {}",
+            webhooks
+                .iter()
+                .map(|w| w.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Ok(_) => {}
+        Err(e) => eprintln!("Warning: webhooks disabled until config is fixed: {}", e),
+    }
+    if supervise {
+        println!("Agent supervisor: enabled (state on /api/agents)");
+    }
+    print_auth_status(repo_path, host);
     println!("Press Ctrl+C to stop");
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-
     // Run server with graceful shutdown
     let cleanup_repo_path = repo_path.to_path_buf();
-    let server_result = axum::serve(listener, app)
-        .with_graceful_shutdown(async {
+    let server_result = if let Some(tls) = server_tls {
+        let handle = axum_server::Handle::new();
+        let shutdown_handle = handle.clone();
+        tokio::spawn(async move {
             // Wait for Ctrl+C
             tokio::signal::ctrl_c()
                 .await
                 .expect("Failed to install Ctrl+C handler");
-        })
-        .await;
+            shutdown_handle.graceful_shutdown(None);
+        });
+        axum_server::bind_rustls(
+            addr,
+            axum_server::tls_rustls::RustlsConfig::from_config(tls.config),
+        )
+        .handle(handle)
+        .serve(app.into_make_service())
+        .await
+    } else {
+        let listener = tokio::net::TcpListener::bind(addr).await?;
+        axum::serve(listener, app)
+            .with_graceful_shutdown(async {
+                // Wait for Ctrl+C
+                tokio::signal::ctrl_c()
+                    .await
+                    .expect("Failed to install Ctrl+C handler");
+            })
+            .await
+    };
 
     // Stop heartbeat task
     heartbeat_handle.abort();
@@ -2721,6 +3006,14 @@ pub async fn start_session_server(
         handle.abort();
     }
 
+    // Stop webhook dispatcher
+    webhook_handle.abort();
+
+    // Stop agent supervisor
+    if let Some(handle) = supervisor_handle {
+        handle.abort();
+    }
+
     // Clear serve state from state.kdl on shutdown
     if let Ok(storage) = Storage::open(&cleanup_repo_path) {
         if let Ok(mut state) = storage.read_binnacle_state() {
@@ -2791,6 +3084,44 @@ mod tests {
         assert_eq!(result, Some("repo".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_message_history_get_since() {
+        let history = MessageHistory::new(10);
+        assert_eq!(history.get_since(0).await.unwrap().len(), 0);
+
+        history.push(1, "a".to_string()).await;
+        history.push(2, "b".to_string()).await;
+        history.push(2, "c".to_string()).await;
+
+        let since_0: Vec<String> = history
+            .get_since(0)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.message)
+            .collect();
+        assert_eq!(since_0, vec!["a", "b", "c"]);
+        assert_eq!(history.get_since(1).await.unwrap().len(), 2);
+        assert!(history.get_since(2).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_message_history_overflow_and_clear() {
+        let history = MessageHistory::new(2);
+        history.push(1, "a".to_string()).await;
+        history.push(2, "b".to_string()).await;
+        history.push(2, "c".to_string()).await;
+
+        // Part of version 2 survives, but "a" (version 1) was dropped
+        assert!(history.get_since(0).await.is_none());
+        assert_eq!(history.get_since(1).await.unwrap().len(), 2);
+
+        // After a reload nobody older than the reload version can resume
+        history.clear(3).await;
+        assert!(history.get_since(2).await.is_none());
+        assert!(history.get_since(3).await.unwrap().is_empty());
+    }
+
     #[test]
     fn test_extract_repo_name_from_path_no_suffix() {
         let result = extract_repo_name_from_path("owner/repo");
@@ -0,0 +1,59 @@
+//! Agent pool supervisor running inside the session server.
+//!
+//! With `bn session serve --supervise`, the server runs the same passes as
+//! `bn agent supervise` (see [`crate::agents::supervisor`]) on a blocking
+//! worker thread. After every pass the status is stored for `/api/agents`
+//! and broadcast to WebSocket clients as a `supervisor` message.
+
+use chrono::Utc;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, broadcast};
+
+use super::protocol::ServerMessage;
+use crate::agents::{Supervisor, SupervisorStatus};
+
+/// Spawn the supervisor loop for a session server.
+///
+/// `status` receives the latest snapshot after every pass.
+pub fn spawn_supervisor(
+    repo_path: PathBuf,
+    status: Arc<Mutex<Option<SupervisorStatus>>>,
+    update_tx: broadcast::Sender<String>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut supervisor = Supervisor::new(&repo_path, false);
+        loop {
+            // Passes spawn and stop containers, so keep them off the async workers
+            let pass = tokio::task::spawn_blocking(move || {
+                if let Err(e) = supervisor.tick() {
+                    tracing::warn!("Supervisor pass failed: {}", e);
+                }
+                supervisor
+            })
+            .await;
+            supervisor = match pass {
+                Ok(supervisor) => supervisor,
+                Err(e) => {
+                    tracing::error!("Supervisor stopped: {}", e);
+                    return;
+                }
+            };
+
+            let snapshot = supervisor.status().clone();
+            let interval = Duration::from_secs(snapshot.interval_secs.max(1));
+            let message = ServerMessage::Supervisor {
+                status: Box::new(snapshot.clone()),
+                timestamp: Utc::now(),
+            };
+            *status.lock().await = Some(snapshot);
+            if let Ok(json) = serde_json::to_string(&message) {
+                // No receivers is fine: nobody is connected yet
+                let _ = update_tx.send(json);
+            }
+
+            tokio::time::sleep(interval).await;
+        }
+    })
+}
@@ -5,7 +5,8 @@
 //!
 //! # Protocol
 //!
-//! 1. Connect to upstream WebSocket URL
+//! 1. Connect to upstream WebSocket URL (with `Authorization: Bearer` when
+//!    `--upstream-token` or `BN_UPSTREAM_TOKEN` is set)
 //! 2. Send `register` message with session identity
 //! 3. Send `heartbeat` every 30 seconds with ready_count and in_progress tasks
 //! 4. Forward graph events as they occur
@@ -18,7 +19,9 @@ use std::time::Duration;
 use tokio::sync::Mutex;
 use tokio::sync::broadcast;
 use tokio_tungstenite::tungstenite::Message;
-use tokio_tungstenite::{MaybeTlsStream, connect_async};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{HeaderValue, header};
+use tokio_tungstenite::{Connector, MaybeTlsStream, connect_async_tls_with_config};
 
 use crate::gui::protocol::{Change, DownstreamMessage, UpstreamMessage};
 use crate::gui::websocket::execute_command;
@@ -56,6 +59,10 @@ pub struct UpstreamClient {
     storage: Arc<Mutex<Storage>>,
     /// Channel for receiving graph updates to forward upstream.
     update_rx: broadcast::Receiver<String>,
+    /// Pinned certificate fingerprints for the hub (empty = system trust roots).
+    tls_pins: Vec<String>,
+    /// Bearer token presented to the hub (None = no Authorization header).
+    token: Option<String>,
 }
 
 impl UpstreamClient {
@@ -71,9 +78,25 @@ impl UpstreamClient {
             session_info,
             storage,
             update_rx,
+            tls_pins: Vec::new(),
+            token: None,
         }
     }
 
+    /// Trust the hub only if its certificate matches one of these fingerprints.
+    ///
+    /// Pins must be normalized with [`crate::tls::parse_pins`].
+    pub fn with_tls_pins(mut self, pins: Vec<String>) -> Self {
+        self.tls_pins = pins;
+        self
+    }
+
+    /// Authenticate to the hub with `Authorization: Bearer <token>`.
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
+
     /// Start the upstream connection loop.
     ///
     /// This method runs indefinitely, connecting to the upstream hub and
@@ -98,7 +121,16 @@ impl UpstreamClient {
     async fn connect_and_run(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         eprintln!("[upstream] Connecting to {}...", self.url);
 
-        let (ws_stream, _response) = connect_async(&self.url).await?;
+        let connector = if self.tls_pins.is_empty() {
+            None
+        } else {
+            Some(Connector::Rustls(crate::tls::pinned_client_config(
+                self.tls_pins.clone(),
+            )?))
+        };
+        let request = hub_request(&self.url, self.token.as_deref())?;
+        let (ws_stream, _response) =
+            connect_async_tls_with_config(request, None, false, connector).await?;
         let (mut write, mut read): (
             futures::stream::SplitSink<WsStream, Message>,
             futures::stream::SplitStream<WsStream>,
@@ -300,6 +332,24 @@ fn parse_graph_event(json: &str) -> Option<Change> {
     }
 }
 
+/// Build the WebSocket handshake request for the hub, with the bearer token if set.
+fn hub_request(
+    url: &str,
+    token: Option<&str>,
+) -> Result<
+    tokio_tungstenite::tungstenite::handshake::client::Request,
+    Box<dyn std::error::Error + Send + Sync>,
+> {
+    let mut request = url.into_client_request()?;
+    if let Some(token) = token {
+        request.headers_mut().insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {}", token))?,
+        );
+    }
+    Ok(request)
+}
+
 /// Spawn an upstream connection task.
 ///
 /// Returns a handle to the spawned task that can be used to abort it on shutdown.
@@ -308,9 +358,13 @@ pub fn spawn_upstream_client(
     session_info: SessionInfo,
     storage: Arc<Mutex<Storage>>,
     update_rx: broadcast::Receiver<String>,
+    tls_pins: Vec<String>,
+    token: Option<String>,
 ) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
-        let mut client = UpstreamClient::new(url, session_info, storage, update_rx);
+        let mut client = UpstreamClient::new(url, session_info, storage, update_rx)
+            .with_tls_pins(tls_pins)
+            .with_token(token);
         client.run().await;
     })
 }
@@ -324,6 +378,18 @@ pub fn derive_session_id(repo_path: &Path) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_hub_request_bearer_token() {
+        let request = hub_request("wss://hub.example.com/sessions", Some("bng_hub")).unwrap();
+        assert_eq!(
+            request.headers().get(header::AUTHORIZATION).unwrap(),
+            "Bearer bng_hub"
+        );
+
+        let request = hub_request("wss://hub.example.com/sessions", None).unwrap();
+        assert!(request.headers().get(header::AUTHORIZATION).is_none());
+    }
+
     #[test]
     fn test_session_info_creation() {
         let info = SessionInfo {
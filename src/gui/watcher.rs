@@ -191,6 +191,15 @@ impl EntitySnapshot {
             }
         }
 
+        // Load messages
+        if let Ok(messages) = storage.list_messages() {
+            for message in messages {
+                if let Ok(value) = serde_json::to_value(&message) {
+                    entities.insert(message.id.clone(), value);
+                }
+            }
+        }
+
         Self { entities }
     }
 
@@ -241,6 +250,14 @@ impl EntitySnapshot {
     }
 }
 
+/// Load every entity from storage as JSON, keyed by ID.
+///
+/// Used by consumers of [`Change`] events that need the state preceding the
+/// first delta (e.g., webhook status transitions).
+pub(super) fn load_entity_values(storage: &Storage) -> HashMap<String, Value> {
+    EntitySnapshot::load(storage).entities
+}
+
 /// Determine entity type from entity JSON value.
 /// Extracts the "type" field from the entity, falling back to ID prefix detection.
 fn entity_type_from_value(value: &Value) -> &'static str {
@@ -257,6 +274,7 @@ fn entity_type_from_value(value: &Value) -> &'static str {
             "queue" => return "queue",
             "doc" => return "doc",
             "agent" => return "agent",
+            "message" => return "message",
             _ => {}
         }
     }
@@ -306,6 +324,8 @@ fn entity_type_from_id(id: &str) -> &'static str {
         "doc"
     } else if id.starts_with("bnq-") {
         "queue"
+    } else if id.starts_with("bnmsg-") {
+        "message"
     } else if id.starts_with("bnm-") {
         "milestone"
     } else if id.starts_with("bnb-") {
@@ -505,7 +525,7 @@ pub async fn watch_storage(
                             .to_string();
                             let _ = update_tx.send(reload_msg);
                             // Clear message history on reload - clients will sync from scratch
-                            message_history.clear().await;
+                            message_history.clear(new_version).await;
                         } else {
                             // Send incremental messages and record them in history
                             for change in &diff.added {
@@ -689,6 +709,7 @@ pub async fn watch_storage(
                             })
                             .to_string(),
                         );
+                        message_history.clear(new_version).await;
                     }
                 }
 
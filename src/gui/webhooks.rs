@@ -0,0 +1,736 @@
+//! Outbound webhooks for graph events.
+//!
+//! The session server delivers [`Change`] events to the `webhook`
+//! subscriptions configured in the session `config.kdl` (see
+//! [`WebhookConfig`]). Subscriptions are re-read on every delta, so edits
+//! take effect without restarting the server.
+//!
+//! # Delivery
+//!
+//! Each matching change is POSTed as JSON with these headers:
+//!
+//! - `X-Binnacle-Event` - change kind (`create`, `update`, `delete`)
+//! - `X-Binnacle-Delivery` - unique delivery ID (stable across retries)
+//! - `X-Binnacle-Signature-256` - `sha256=<hex>` HMAC of the body, when a
+//!   secret is configured
+//!
+//! Any 2xx response is success. Network errors, 408, 429 and 5xx responses
+//! are retried with exponential backoff (starting at `backoff-ms`, capped at
+//! [`MAX_BACKOFF`]) up to `max-attempts`; other 4xx responses fail
+//! immediately. Events that cannot be delivered are appended to
+//! [`DEAD_LETTER_FILE`] in the session directory.
+//!
+//! Deliveries run concurrently, so events are not guaranteed to arrive in
+//! order; use the `version` field to order them.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+use super::protocol::{Change, ServerMessage};
+use crate::config::{WebhookConfig, WebhookFilter};
+use crate::storage::Storage;
+
+/// File (in the session directory) that receives undeliverable events
+pub const DEAD_LETTER_FILE: &str = "webhooks-dead-letter.jsonl";
+
+/// Upper bound for the delay between retries
+pub const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Timeout for a single delivery attempt
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A status change observed on an update.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StatusTransition {
+    /// Previous status, if it was known
+    pub from: Option<String>,
+    /// New status
+    pub to: String,
+}
+
+/// JSON body POSTed to a webhook.
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookPayload {
+    /// Unique delivery ID (also sent as `X-Binnacle-Delivery`)
+    pub delivery_id: String,
+    /// Name of the subscription that matched
+    pub webhook: String,
+    /// Change kind (`create`, `update`, `delete`)
+    pub event: String,
+    /// Entity type of the change
+    pub entity_type: String,
+    /// ID of the changed entity
+    pub entity_id: Option<String>,
+    /// Status transition, for updates that changed the status
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transition: Option<StatusTransition>,
+    /// The change itself, as sent to WebSocket clients
+    pub change: Change,
+    /// Graph version that produced the change
+    pub version: u64,
+    /// Repository the session server is serving
+    pub repo_path: String,
+    /// When the change was observed
+    pub timestamp: DateTime<Utc>,
+}
+
+/// An entry in the dead-letter file.
+#[derive(Debug, Serialize)]
+struct DeadLetter<'a> {
+    webhook: &'a str,
+    url: &'a str,
+    attempts: u32,
+    error: &'a str,
+    failed_at: DateTime<Utc>,
+    payload: &'a WebhookPayload,
+}
+
+/// Result of a delivery attempt that did not succeed.
+#[derive(Debug)]
+struct DeliveryError {
+    message: String,
+    retryable: bool,
+}
+
+/// Last known facts about an entity, used to derive transitions and edge
+/// types for changes that don't carry them.
+#[derive(Debug, Default, Clone)]
+struct Known {
+    status: Option<String>,
+    edge_type: Option<String>,
+}
+
+/// Matches graph changes against webhook subscriptions.
+///
+/// Tracks entity statuses so that updates can be reported as transitions.
+#[derive(Debug, Default)]
+pub struct WebhookRouter {
+    known: HashMap<String, Known>,
+}
+
+impl WebhookRouter {
+    /// Create a router seeded with the current storage state.
+    pub fn from_storage(storage: &Storage) -> Self {
+        let mut router = Self::default();
+        for (id, value) in super::watcher::load_entity_values(storage) {
+            router.known.insert(
+                id,
+                Known {
+                    status: string_field(&value, "status"),
+                    edge_type: None,
+                },
+            );
+        }
+        if let Ok(edges) = storage.list_edges(None, None, None) {
+            for edge in edges {
+                let edge_type = serde_json::to_value(edge.edge_type)
+                    .ok()
+                    .and_then(|v| v.as_str().map(|s| s.to_string()));
+                router.known.insert(
+                    edge.id,
+                    Known {
+                        status: None,
+                        edge_type,
+                    },
+                );
+            }
+        }
+        router
+    }
+
+    /// Build the payloads to deliver for a delta.
+    ///
+    /// Must be called for every delta, even without subscriptions, so that
+    /// tracked statuses stay current.
+    pub fn route(
+        &mut self,
+        webhooks: &[WebhookConfig],
+        changes: &[Change],
+        version: u64,
+        repo_path: &Path,
+    ) -> Vec<(WebhookConfig, WebhookPayload)> {
+        let mut deliveries = Vec::new();
+        let timestamp = Utc::now();
+
+        for change in changes {
+            let (event, entity_type, entity_id, data) = match change {
+                Change::Create { entity_type, data } => {
+                    ("create", entity_type, string_field(data, "id"), Some(data))
+                }
+                Change::Update {
+                    entity_type,
+                    id,
+                    changes,
+                } => ("update", entity_type, Some(id.clone()), Some(changes)),
+                Change::Delete { entity_type, id } => {
+                    ("delete", entity_type, Some(id.clone()), None)
+                }
+            };
+
+            // Update tracked state, remembering what it was before
+            let previous = entity_id
+                .as_ref()
+                .and_then(|id| self.known.get(id))
+                .cloned()
+                .unwrap_or_default();
+            let new_status = data.and_then(|d| string_field(d, "status"));
+            let edge_type = data
+                .and_then(|d| string_field(d, "edge_type"))
+                .or(previous.edge_type.clone());
+            let transition = match (event, &new_status) {
+                ("update", Some(to)) if previous.status.as_ref() != Some(to) => {
+                    Some(StatusTransition {
+                        from: previous.status.clone(),
+                        to: to.clone(),
+                    })
+                }
+                _ => None,
+            };
+            if let Some(id) = &entity_id {
+                if event == "delete" {
+                    self.known.remove(id);
+                } else {
+                    self.known.insert(
+                        id.clone(),
+                        Known {
+                            status: new_status.or(previous.status),
+                            edge_type: edge_type.clone(),
+                        },
+                    );
+                }
+            }
+
+            for webhook in webhooks {
+                if !matches_filter(
+                    &webhook.filter,
+                    event,
+                    entity_type,
+                    edge_type.as_deref(),
+                    data,
+                    transition.as_ref(),
+                ) {
+                    continue;
+                }
+                deliveries.push((
+                    webhook.clone(),
+                    WebhookPayload {
+                        delivery_id: format!("whd-{}", uuid::Uuid::new_v4().simple()),
+                        webhook: webhook.name.clone(),
+                        event: event.to_string(),
+                        entity_type: entity_type.clone(),
+                        entity_id: entity_id.clone(),
+                        transition: transition.clone(),
+                        change: change.clone(),
+                        version,
+                        repo_path: repo_path.to_string_lossy().to_string(),
+                        timestamp,
+                    },
+                ));
+            }
+        }
+
+        deliveries
+    }
+}
+
+/// Read a string field from entity JSON.
+fn string_field(value: &Value, field: &str) -> Option<String> {
+    value
+        .get(field)
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+}
+
+/// Normalize a type or status name for comparison.
+fn normalize(s: &str) -> String {
+    s.to_lowercase().replace('-', "_")
+}
+
+/// Check whether a change satisfies a subscription filter.
+fn matches_filter(
+    filter: &WebhookFilter,
+    event: &str,
+    entity_type: &str,
+    edge_type: Option<&str>,
+    data: Option<&Value>,
+    transition: Option<&StatusTransition>,
+) -> bool {
+    if !filter.changes.is_empty() && !filter.changes.iter().any(|c| c == event) {
+        return false;
+    }
+
+    let entity_type = normalize(entity_type);
+    if !filter.entity_types.is_empty()
+        && !filter
+            .entity_types
+            .iter()
+            .any(|t| t.trim_end_matches('s') == entity_type)
+    {
+        return false;
+    }
+
+    if !filter.edge_types.is_empty() {
+        let Some(edge_type) = edge_type.map(normalize) else {
+            return false;
+        };
+        if !filter.edge_types.contains(&edge_type) {
+            return false;
+        }
+    }
+
+    if filter.requires_transition() {
+        let Some(transition) = transition else {
+            return false;
+        };
+        if let Some(from) = &filter.status_from
+            && transition.from.as_deref().map(normalize).as_ref() != Some(from)
+        {
+            return false;
+        }
+        if let Some(to) = &filter.status_to
+            && normalize(&transition.to) != *to
+        {
+            return false;
+        }
+    }
+
+    filter.fields.iter().all(|(field, values)| {
+        data.and_then(|d| d.get(field))
+            .map(|v| match v {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .is_some_and(|actual| values.iter().any(|v| v.eq_ignore_ascii_case(&actual)))
+    })
+}
+
+/// Compute the `X-Binnacle-Signature-256` header value for a body.
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    format!("sha256={:x}", mac.finalize().into_bytes())
+}
+
+/// Make a single (blocking) delivery attempt.
+fn post_once(
+    webhook: &WebhookConfig,
+    payload: &WebhookPayload,
+    body: &str,
+) -> Result<(), DeliveryError> {
+    let agent = ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build();
+    let mut request = agent
+        .post(&webhook.url)
+        .set("Content-Type", "application/json")
+        .set(
+            "User-Agent",
+            concat!("binnacle/", env!("CARGO_PKG_VERSION")),
+        )
+        .set("X-Binnacle-Event", &payload.event)
+        .set("X-Binnacle-Delivery", &payload.delivery_id);
+    if let Some(secret) = webhook.signing_secret() {
+        request = request.set(
+            "X-Binnacle-Signature-256",
+            &sign_payload(&secret, body.as_bytes()),
+        );
+    }
+
+    match request.send_string(body) {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(code, _)) => Err(DeliveryError {
+            message: format!("HTTP {}", code),
+            retryable: code == 408 || code == 429 || code >= 500,
+        }),
+        Err(e) => Err(DeliveryError {
+            message: e.to_string(),
+            retryable: true,
+        }),
+    }
+}
+
+/// Deliver a payload, retrying with backoff and dead-lettering on failure.
+///
+/// Returns true if the webhook accepted the payload.
+pub async fn deliver(
+    webhook: &WebhookConfig,
+    payload: &WebhookPayload,
+    dead_letter_path: &Path,
+) -> bool {
+    let body = match serde_json::to_string(payload) {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!("Failed to serialize webhook payload: {}", e);
+            return false;
+        }
+    };
+
+    let mut backoff = Duration::from_millis(webhook.backoff_ms);
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let result = {
+            let webhook = webhook.clone();
+            let payload = payload.clone();
+            let body = body.clone();
+            tokio::task::spawn_blocking(move || post_once(&webhook, &payload, &body))
+                .await
+                .unwrap_or_else(|e| {
+                    Err(DeliveryError {
+                        message: e.to_string(),
+                        retryable: false,
+                    })
+                })
+        };
+
+        let error = match result {
+            Ok(()) => return true,
+            Err(e) => e,
+        };
+        if !error.retryable || attempts >= webhook.max_attempts {
+            tracing::warn!(
+                "Webhook '{}' failed after {} attempt(s): {}",
+                webhook.name,
+                attempts,
+                error.message
+            );
+            if let Err(e) = write_dead_letter(
+                dead_letter_path,
+                &DeadLetter {
+                    webhook: &webhook.name,
+                    url: &webhook.url,
+                    attempts,
+                    error: &error.message,
+                    failed_at: Utc::now(),
+                    payload,
+                },
+            ) {
+                tracing::error!("Failed to write webhook dead letter: {}", e);
+            }
+            return false;
+        }
+
+        tracing::debug!(
+            "Webhook '{}' attempt {} failed ({}), retrying in {:?}",
+            webhook.name,
+            attempts,
+            error.message,
+            backoff
+        );
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+/// Append an undeliverable event to the dead-letter file.
+fn write_dead_letter(path: &Path, letter: &DeadLetter<'_>) -> std::io::Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    let line = serde_json::to_string(letter)?;
+    writeln!(file, "{}", line)
+}
+
+/// Spawn the webhook dispatcher for a session server.
+///
+/// Listens for `delta` messages on the update channel and delivers matching
+/// changes to the subscriptions in config.kdl.
+pub fn spawn_webhook_dispatcher(
+    repo_path: PathBuf,
+    storage_dir: PathBuf,
+    mut update_rx: broadcast::Receiver<String>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let (mut router, config_storage) = match Storage::open(&repo_path) {
+            Ok(storage) => (WebhookRouter::from_storage(&storage), storage),
+            Err(e) => {
+                tracing::error!("Webhooks disabled: failed to open storage: {}", e);
+                return;
+            }
+        };
+        let dead_letter_path = storage_dir.join(DEAD_LETTER_FILE);
+
+        loop {
+            let msg = match update_rx.recv().await {
+                Ok(msg) => msg,
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("Webhook dispatcher skipped {} updates", n);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let Ok(ServerMessage::Delta {
+                changes, version, ..
+            }) = serde_json::from_str::<ServerMessage>(&msg)
+            else {
+                continue;
+            };
+
+            let webhooks = match config_storage.get_webhooks_kdl() {
+                Ok(webhooks) => webhooks,
+                Err(e) => {
+                    tracing::warn!("Skipping webhooks: {}", e);
+                    Vec::new()
+                }
+            };
+            for (webhook, payload) in router.route(&webhooks, &changes, version, &repo_path) {
+                let dead_letter_path = dead_letter_path.clone();
+                tokio::spawn(async move {
+                    deliver(&webhook, &payload, &dead_letter_path).await;
+                });
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    /// A request received by the stand-in server.
+    #[derive(Debug, Clone)]
+    struct Received {
+        headers: HashMap<String, String>,
+        body: String,
+    }
+
+    /// Minimal HTTP stand-in that replies with the given statuses in order
+    /// (200 once they run out) and records every request.
+    fn stand_in(statuses: Vec<u16>) -> (String, Arc<Mutex<Vec<Received>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = received.clone();
+
+        std::thread::spawn(move || {
+            let mut statuses = statuses.into_iter();
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { break };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut headers = HashMap::new();
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                loop {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                    let trimmed = line.trim_end();
+                    if trimmed.is_empty() {
+                        break;
+                    }
+                    if let Some((k, v)) = trimmed.split_once(':') {
+                        headers.insert(k.trim().to_lowercase(), v.trim().to_string());
+                    }
+                }
+                let len = headers
+                    .get("content-length")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0);
+                let mut body = vec![0; len];
+                reader.read_exact(&mut body).unwrap();
+                log.lock().unwrap().push(Received {
+                    headers,
+                    body: String::from_utf8(body).unwrap(),
+                });
+
+                let status = statuses.next().unwrap_or(200);
+                let response = format!(
+                    "HTTP/1.1 {} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        (url, received)
+    }
+
+    fn webhook(url: &str, kdl_children: &str) -> WebhookConfig {
+        let doc: kdl::KdlDocument =
+            format!("webhook \"test\" url=\"{}\" {{\n{}\n}}", url, kdl_children)
+                .parse()
+                .unwrap();
+        WebhookConfig::from_kdl_node(&doc.nodes()[0]).unwrap()
+    }
+
+    fn update(id: &str, status: &str) -> Change {
+        Change::Update {
+            entity_type: "task".to_string(),
+            id: id.to_string(),
+            changes: serde_json::json!({"id": id, "type": "task", "status": status}),
+        }
+    }
+
+    #[test]
+    fn test_route_status_transitions() {
+        let hooks = [webhook(
+            "http://localhost/",
+            "transition from=\"pending\" to=\"done\"",
+        )];
+        let mut router = WebhookRouter::default();
+        let repo = Path::new("/tmp/repo");
+
+        let create = Change::Create {
+            entity_type: "task".to_string(),
+            data: serde_json::json!({"id": "bn-a", "type": "task", "status": "pending"}),
+        };
+        assert!(router.route(&hooks, &[create], 1, repo).is_empty());
+
+        // A title edit without a status change is not a transition
+        assert!(
+            router
+                .route(&hooks, &[update("bn-a", "pending")], 2, repo)
+                .is_empty()
+        );
+
+        let routed = router.route(&hooks, &[update("bn-a", "done")], 3, repo);
+        assert_eq!(routed.len(), 1);
+        let payload = &routed[0].1;
+        assert_eq!(payload.event, "update");
+        assert_eq!(payload.entity_id.as_deref(), Some("bn-a"));
+        assert_eq!(payload.version, 3);
+        assert_eq!(
+            payload.transition,
+            Some(StatusTransition {
+                from: Some("pending".to_string()),
+                to: "done".to_string()
+            })
+        );
+
+        // An unknown entity has no "from" status, so it can't match from="pending"
+        assert!(
+            router
+                .route(&hooks, &[update("bn-b", "done")], 4, repo)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_route_entity_edge_and_field_filters() {
+        let bugs = webhook(
+            "http://localhost/",
+            "change \"create\"\nentity-type \"bugs\"\nmatch \"severity\" \"critical\"",
+        );
+        let blocks = webhook("http://localhost/", "edge-type \"blocks\"");
+        let hooks = [bugs, blocks];
+        let mut router = WebhookRouter::default();
+        let repo = Path::new("/tmp/repo");
+
+        let bug = |id: &str, severity: &str| Change::Create {
+            entity_type: "bug".to_string(),
+            data: serde_json::json!({"id": id, "type": "bug", "severity": severity}),
+        };
+        let routed = router.route(
+            &hooks,
+            &[bug("bn-1", "low"), bug("bn-2", "critical")],
+            1,
+            repo,
+        );
+        assert_eq!(routed.len(), 1);
+        assert_eq!(routed[0].1.entity_id.as_deref(), Some("bn-2"));
+
+        let link = Change::Create {
+            entity_type: "link".to_string(),
+            data: serde_json::json!({"id": "bne-1", "edge_type": "blocks"}),
+        };
+        let routed = router.route(&hooks, &[link], 2, repo);
+        assert_eq!(routed.len(), 1);
+        assert_eq!(routed[0].0.filter.edge_types, vec!["blocks"]);
+
+        // Deleted links carry no edge type; the router remembers it
+        let unlink = Change::Delete {
+            entity_type: "link".to_string(),
+            id: "bne-1".to_string(),
+        };
+        assert_eq!(router.route(&hooks, &[unlink], 3, repo).len(), 1);
+    }
+
+    #[test]
+    fn test_sign_payload() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign_payload("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deliver_signs_and_retries() {
+        let (url, received) = stand_in(vec![503, 500]);
+        let hook = webhook(&url, "secret \"s3cret\"\nmax-attempts 3\nbackoff-ms 1");
+        let mut router = WebhookRouter::default();
+        let routed = router.route(&[hook], &[update("bn-a", "done")], 7, Path::new("/tmp/r"));
+        let (hook, payload) = &routed[0];
+        let dir = tempfile::tempdir().unwrap();
+        let dead_letter = dir.path().join(DEAD_LETTER_FILE);
+
+        assert!(deliver(hook, payload, &dead_letter).await);
+        assert!(!dead_letter.exists());
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 3);
+        let last = &received[2];
+        assert_eq!(
+            last.headers.get("x-binnacle-signature-256"),
+            Some(&sign_payload("s3cret", last.body.as_bytes()))
+        );
+        assert_eq!(last.headers.get("x-binnacle-event").unwrap(), "update");
+        // The delivery ID is stable across retries
+        assert_eq!(
+            received[0].headers.get("x-binnacle-delivery"),
+            last.headers.get("x-binnacle-delivery")
+        );
+        let body: Value = serde_json::from_str(&last.body).unwrap();
+        assert_eq!(body["version"], 7);
+        assert_eq!(body["change"]["op"], "update");
+    }
+
+    #[tokio::test]
+    async fn test_deliver_dead_letters_after_failures() {
+        let (url, received) = stand_in(vec![500, 500, 200]);
+        let hook = webhook(&url, "max-attempts 2\nbackoff-ms 1");
+        let mut router = WebhookRouter::default();
+        let routed = router.route(&[hook], &[update("bn-a", "done")], 1, Path::new("/tmp/r"));
+        let dir = tempfile::tempdir().unwrap();
+        let dead_letter = dir.path().join(DEAD_LETTER_FILE);
+
+        assert!(!deliver(&routed[0].0, &routed[0].1, &dead_letter).await);
+        assert_eq!(received.lock().unwrap().len(), 2);
+
+        let content = std::fs::read_to_string(&dead_letter).unwrap();
+        let entry: Value = serde_json::from_str(content.lines().next().unwrap()).unwrap();
+        assert_eq!(entry["webhook"], "test");
+        assert_eq!(entry["attempts"], 2);
+        assert_eq!(entry["error"], "HTTP 500");
+        assert_eq!(entry["payload"]["entity_id"], "bn-a");
+    }
+
+    #[tokio::test]
+    async fn test_deliver_does_not_retry_client_errors() {
+        let (url, received) = stand_in(vec![404]);
+        let hook = webhook(&url, "max-attempts 5\nbackoff-ms 1");
+        let mut router = WebhookRouter::default();
+        let routed = router.route(&[hook], &[update("bn-a", "done")], 1, Path::new("/tmp/r"));
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(
+            !deliver(
+                &routed[0].0,
+                &routed[0].1,
+                &dir.path().join(DEAD_LETTER_FILE)
+            )
+            .await
+        );
+        assert_eq!(received.lock().unwrap().len(), 1);
+    }
+}
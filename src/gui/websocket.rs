@@ -4,6 +4,7 @@
 //! It supports both the legacy sync protocol and the new session server protocol.
 
 use axum::{
+    Extension,
     extract::{
         State,
         ws::{Message, WebSocket, WebSocketUpgrade},
@@ -16,11 +17,13 @@ use serde::Deserialize;
 use std::path::Path;
 use tokio::sync::mpsc;
 
+use super::auth::{AuthContext, command_required_role};
 use super::protocol::{
-    ClientMessage as ProtocolClientMessage, GraphState, ServerMessage, StateSummary,
+    ClientMessage as ProtocolClientMessage, GraphState, ServerMessage, StateSummary, TopicFilter,
 };
-use super::server::AppState;
+use super::server::{AppState, MessageHistory, StateVersion};
 use crate::commands;
+use crate::config::GuiRole;
 use crate::storage::Storage;
 
 /// Legacy incoming WebSocket message from client (for backward compatibility)
@@ -36,6 +39,21 @@ enum LegacyClientMessage {
     Ping,
 }
 
+/// A subscription handed from the receive task to the send task.
+///
+/// The send task applies it in one step relative to live updates: it sends
+/// `messages` (replayed history or a snapshot, then the acknowledgment) and
+/// only afterwards forwards live updates, filtered by `filter` and skipping
+/// anything at or below `floor` that the client already has.
+struct Subscription {
+    /// Topic filter for live updates
+    filter: TopicFilter,
+    /// Live messages with a version at or below this are not forwarded
+    floor: u64,
+    /// Messages to send before resuming live updates
+    messages: Vec<String>,
+}
+
 /// Combined message type that can handle both legacy and new protocol messages
 #[derive(Debug)]
 enum AnyClientMessage {
@@ -59,12 +77,19 @@ impl AnyClientMessage {
 }
 
 /// WebSocket upgrade handler
-pub async fn ws_handler(ws: WebSocketUpgrade, State(state): State<AppState>) -> impl IntoResponse {
-    ws.on_upgrade(|socket| handle_socket(socket, state))
+pub async fn ws_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    auth: Option<Extension<AuthContext>>,
+) -> impl IntoResponse {
+    let auth = auth
+        .map(|Extension(ctx)| ctx)
+        .unwrap_or_else(AuthContext::unauthenticated);
+    ws.on_upgrade(|socket| handle_socket(socket, state, auth))
 }
 
 /// Handle WebSocket connection
-async fn handle_socket(socket: WebSocket, state: AppState) {
+async fn handle_socket(socket: WebSocket, state: AppState, auth: AuthContext) {
     let (mut sender, mut receiver) = socket.split();
     let metrics = state.ws_metrics.clone();
     let version = state.version.clone();
@@ -93,18 +118,26 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
 
     // Create channel for sending responses from recv_task to send_task
     let (response_tx, mut response_rx) = mpsc::channel::<String>(32);
+    let (subscription_tx, mut subscription_rx) = mpsc::channel::<Subscription>(4);
 
     // Clone metrics for the send task
     let send_metrics = metrics.clone();
 
     // Spawn a task to forward updates and responses to the client
     let mut send_task = tokio::spawn(async move {
-        loop {
+        // Until the client subscribes, every update is forwarded (legacy clients)
+        let mut filter = TopicFilter::all();
+        let mut floor = 0;
+
+        'outer: loop {
             tokio::select! {
                 // Handle broadcast updates from file watcher
                 msg = rx.recv() => {
                     match msg {
                         Ok(msg) => {
+                            let Some(msg) = filter_outgoing(&filter, floor, msg) else {
+                                continue;
+                            };
                             if sender.send(Message::Text(msg)).await.is_err() {
                                 break;
                             }
@@ -113,6 +146,17 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
                         Err(_) => break,
                     }
                 }
+                // Apply a new subscription (replay or snapshot, then ack)
+                Some(subscription) = subscription_rx.recv() => {
+                    for msg in subscription.messages {
+                        if sender.send(Message::Text(msg)).await.is_err() {
+                            break 'outer;
+                        }
+                        send_metrics.message_sent();
+                    }
+                    filter = subscription.filter;
+                    floor = subscription.floor;
+                }
                 // Handle direct responses from recv_task
                 Some(msg) = response_rx.recv() => {
                     if sender.send(Message::Text(msg)).await.is_err() {
@@ -140,47 +184,69 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
                         match client_msg {
                             AnyClientMessage::Protocol(proto_msg) => {
                                 match proto_msg {
-                                    ProtocolClientMessage::Subscribe { topics } => {
+                                    ProtocolClientMessage::Subscribe {
+                                        topics,
+                                        since_version,
+                                    } => {
                                         // Log subscription
                                         tracing::debug!(
-                                            "Client subscribed to topics: {:?}",
-                                            topics
+                                            "Client subscribed to topics: {:?} (since {:?})",
+                                            topics,
+                                            since_version
                                         );
 
-                                        // Build and send full state snapshot
-                                        match build_graph_state(&recv_repo_path) {
-                                            Ok(graph_state) => {
-                                                let state_msg = ServerMessage::State {
-                                                    data: Box::new(graph_state),
+                                        match build_subscription(
+                                            &recv_repo_path,
+                                            &recv_version,
+                                            &message_history,
+                                            &topics,
+                                            since_version,
+                                        )
+                                        .await
+                                        {
+                                            Ok(subscription) => {
+                                                let _ = subscription_tx.send(subscription).await;
+                                            }
+                                            Err(e) => {
+                                                tracing::error!("Failed to subscribe: {}", e);
+                                                let response = ServerMessage::Subscribed {
+                                                    topics,
                                                     version: recv_version.current(),
-                                                    timestamp: Utc::now(),
+                                                    resumed: false,
+                                                    replayed: 0,
+                                                    error: Some(e),
                                                 };
-                                                if let Ok(json) = serde_json::to_string(&state_msg)
-                                                {
+                                                if let Ok(json) = serde_json::to_string(&response) {
                                                     let _ = response_tx.send(json).await;
                                                 }
                                             }
-                                            Err(e) => {
-                                                tracing::error!(
-                                                    "Failed to build state snapshot: {}",
-                                                    e
-                                                );
-                                                // Send error as a subscribed acknowledgment with error
-                                                let response = serde_json::json!({
-                                                    "type": "subscribed",
-                                                    "topics": topics,
-                                                    "error": e,
-                                                    "timestamp": Utc::now().to_rfc3339()
-                                                });
-                                                let _ =
-                                                    response_tx.send(response.to_string()).await;
-                                            }
                                         }
                                     }
                                     ProtocolClientMessage::Command { id, cmd, args } => {
-                                        // Execute the command and return result
-                                        let result =
-                                            execute_command(&recv_repo_path, &cmd, args).await;
+                                        // Enforce the connection's token role before executing
+                                        let required = command_required_role(&cmd);
+                                        let start = std::time::Instant::now();
+                                        let result = if auth.role.allows(required) {
+                                            execute_command(&recv_repo_path, &cmd, args).await
+                                        } else {
+                                            Err(format!(
+                                                "Token role '{}' cannot run '{}' (requires '{}')",
+                                                auth.role, cmd, required
+                                            ))
+                                        };
+
+                                        // Audit writes with the token that performed them
+                                        if required > GuiRole::Viewer {
+                                            let _ = crate::action_log::log_action_with_token(
+                                                &recv_repo_path,
+                                                &format!("ws {}", cmd),
+                                                serde_json::json!({ "id": id }),
+                                                result.is_ok(),
+                                                result.as_ref().err().cloned(),
+                                                start.elapsed().as_millis() as u64,
+                                                auth.token_id.as_deref(),
+                                            );
+                                        }
                                         let response = match result {
                                             Ok(data) => ServerMessage::Result {
                                                 id,
@@ -307,6 +373,101 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
     metrics.connection_closed();
 }
 
+/// Apply a connection's subscription to an outgoing live message.
+///
+/// Returns None if the message is at or below the client's floor version or
+/// does not match its topics.
+fn filter_outgoing(filter: &TopicFilter, floor: u64, message: String) -> Option<String> {
+    if filter.is_all() && floor == 0 {
+        return Some(message);
+    }
+
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(&message) else {
+        return Some(message);
+    };
+    if value
+        .get("version")
+        .and_then(|v| v.as_u64())
+        .is_some_and(|v| v <= floor)
+    {
+        return None;
+    }
+    if filter.is_all() {
+        return Some(message);
+    }
+    filter.filter_message(value).map(|v| v.to_string())
+}
+
+/// Handle a `subscribe` handshake.
+///
+/// With `since_version`, missed messages are replayed from history when it
+/// still covers that version; otherwise a (topic-filtered) state snapshot is
+/// sent. Either way the client receives a `subscribed` acknowledgment last.
+///
+/// Messages broadcast while the client was connecting may be delivered twice
+/// around a resume; clients should ignore deltas at or below the last version
+/// they applied.
+async fn build_subscription(
+    repo_path: &Path,
+    version: &StateVersion,
+    history: &MessageHistory,
+    topics: &[String],
+    since_version: Option<u64>,
+) -> Result<Subscription, String> {
+    let filter = TopicFilter::parse(topics)?;
+    let current = version.current();
+
+    // Resume from history when the client's version is still covered
+    if let Some(since) = since_version.filter(|v| *v <= current)
+        && let Some(missed) = history.get_since(since).await
+    {
+        let mut messages: Vec<String> = missed
+            .into_iter()
+            .filter_map(|m| filter_outgoing(&filter, since, m.message))
+            .collect();
+        let replayed = messages.len();
+        let ack = ServerMessage::Subscribed {
+            topics: filter.topics(),
+            version: current,
+            resumed: true,
+            replayed,
+            error: None,
+        };
+        messages.push(serde_json::to_string(&ack).map_err(|e| e.to_string())?);
+        // Everything up to `current` was replayed, so skip it on the live channel
+        return Ok(Subscription {
+            filter,
+            floor: current,
+            messages,
+        });
+    }
+
+    // Fall back to a full (filtered) snapshot
+    let mut graph_state = build_graph_state(repo_path)?;
+    filter.filter_state(&mut graph_state);
+    let state_msg = ServerMessage::State {
+        data: Box::new(graph_state),
+        version: current,
+        timestamp: Utc::now(),
+    };
+    let ack = ServerMessage::Subscribed {
+        topics: filter.topics(),
+        version: current,
+        resumed: false,
+        replayed: 0,
+        error: None,
+    };
+    let messages = vec![
+        serde_json::to_string(&state_msg).map_err(|e| e.to_string())?,
+        serde_json::to_string(&ack).map_err(|e| e.to_string())?,
+    ];
+    Ok(Subscription {
+        filter,
+        floor: current,
+        messages,
+    })
+}
+
 /// Build a complete GraphState snapshot from storage.
 ///
 /// This function collects all entities from the binnacle storage and constructs
@@ -916,4 +1077,62 @@ mod tests {
         assert!(json_str.contains(r#""version":42"#));
         assert!(json_str.contains(r#""id":"bn-test""#));
     }
+
+    #[tokio::test]
+    async fn test_resumed_subscription_skips_replayed_versions() {
+        let version = StateVersion::new();
+        let history = MessageHistory::default();
+        for _ in 0..3 {
+            let v = version.increment();
+            let msg = serde_json::json!({
+                "type": "entity_added",
+                "entity_type": "task",
+                "id": format!("bn-{}", v),
+                "entity": {},
+                "version": v
+            });
+            history.push(v, msg.to_string()).await;
+        }
+
+        let subscription = build_subscription(Path::new("/tmp"), &version, &history, &[], Some(1))
+            .await
+            .unwrap();
+        // Versions 2 and 3 plus the ack
+        assert_eq!(subscription.messages.len(), 3);
+        assert_eq!(subscription.floor, 3);
+    }
+
+    #[test]
+    fn test_filter_outgoing_applies_floor_and_topics() {
+        let added = |version: u64, entity_type: &str, id: &str| {
+            serde_json::json!({
+                "type": "entity_added",
+                "entity_type": entity_type,
+                "id": id,
+                "entity": {"id": id},
+                "version": version
+            })
+            .to_string()
+        };
+
+        // Legacy connections get everything untouched
+        let all = TopicFilter::all();
+        let msg = added(1, "task", "bn-a");
+        assert_eq!(filter_outgoing(&all, 0, msg.clone()), Some(msg));
+
+        // Messages the client already has are skipped
+        assert!(filter_outgoing(&all, 5, added(5, "task", "bn-a")).is_none());
+        assert!(filter_outgoing(&all, 5, added(6, "task", "bn-a")).is_some());
+
+        // Topic filtering drops non-matching entities
+        let tasks = TopicFilter::parse(&["tasks".to_string()]).unwrap();
+        assert!(filter_outgoing(&tasks, 0, added(7, "task", "bn-a")).is_some());
+        assert!(filter_outgoing(&tasks, 0, added(7, "bug", "bn-b")).is_none());
+
+        // Non-JSON messages pass through
+        assert_eq!(
+            filter_outgoing(&tasks, 3, "not json".to_string()),
+            Some("not json".to_string())
+        );
+    }
 }
@@ -15,6 +15,8 @@ pub mod config;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod container;
 #[cfg(not(target_arch = "wasm32"))]
+pub mod git;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod github;
 pub mod gui;
 #[cfg(not(target_arch = "wasm32"))]
@@ -24,6 +26,8 @@ pub mod models;
 pub mod storage;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod sys;
+#[cfg(all(any(feature = "gui", feature = "tui"), not(target_arch = "wasm32")))]
+pub mod tls;
 #[cfg(all(feature = "tmux", not(target_arch = "wasm32")))]
 pub mod tmux;
 #[cfg(all(feature = "tui", not(target_arch = "wasm32")))]
@@ -214,6 +218,9 @@ pub enum Error {
     #[error("A queue already exists for this repository")]
     QueueAlreadyExists,
 
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+
     #[error("{0}")]
     Other(String),
 }
@@ -1,19 +1,20 @@
 //! Binnacle CLI - A project state tracking tool for AI agents and humans.
 
 use binnacle::action_log;
-#[cfg(feature = "gui")]
-use binnacle::cli::GuiCommands;
 #[cfg(feature = "tmux")]
 use binnacle::cli::SessionTmuxCommands;
 #[cfg(feature = "tmux")]
 use binnacle::cli::SystemTmuxCommands;
 use binnacle::cli::{
-    AgentCommands, BugCommands, Cli, Commands, CommitCommands, ConfigAgentsCommands,
-    ConfigCommands, ContainerCommands, CopilotCommands, DocCommands, EmitTemplate, GraphCommands,
-    HooksCommands, IdeaCommands, IssueCommands, LinkCommands, LogCommands, McpCommands,
-    MilestoneCommands, MissionCommands, QueueCommands, SearchCommands, SessionCommands,
-    StoreCommands, SystemCommands, TaskCommands, TestCommands, TokenCommands,
+    AgentCommands, AgentUsageCommands, AgentWorktreeCommands, BugCommands, Cli, Commands,
+    CommitCommands, ConfigAgentsCommands, ConfigCommands, ContainerCommands, CopilotCommands,
+    DocCommands, EmitTemplate, GraphCommands, HooksCommands, IdeaCommands, IssueCommands,
+    LinkCommands, LogCommands, McpCommands, MergeQueueCommands, MilestoneCommands, MissionCommands,
+    MsgCommands, QueueCommands, ReportCommands, SearchCommands, SessionCommands, StoreCommands,
+    SystemCommands, TaskCommands, TestCommands, TokenCommands,
 };
+#[cfg(feature = "gui")]
+use binnacle::cli::{GuiCommands, GuiTokenCommands};
 use binnacle::commands::{self, Output};
 use binnacle::mcp;
 use binnacle::models::DocType;
@@ -37,8 +38,9 @@ fn main() {
     // Start timing
     let start = Instant::now();
 
-    // Execute command
-    let result = run_command(cli.command, &repo_path, human);
+    // Execute command, unless the calling agent's policy denies it
+    let result = commands::check_command_permission(&repo_path, &cmd_name)
+        .and_then(|_| run_command(cli.command, &repo_path, human));
 
     // Track agent activity (if this process is a registered agent)
     commands::track_agent_activity(&repo_path);
@@ -143,10 +145,11 @@ fn run_command(
 
         Some(Commands::Goodbye {
             reason,
+            next,
             dry_run,
             force,
         }) => {
-            let result = commands::goodbye(repo_path, reason, force)?;
+            let result = commands::goodbye(repo_path, reason, next, force)?;
             output(&result, human);
 
             // Actually terminate the grandparent process after output (unless dry-run)
@@ -158,8 +161,35 @@ fn run_command(
             }
         }
 
-        Some(Commands::Show { id }) => {
-            let result = commands::generic_show(repo_path, &id)?;
+        Some(Commands::Show { id, at }) => match at {
+            Some(rev) => {
+                let result = commands::generic_show_at(repo_path, &id, &rev)?;
+                output(&result, human);
+            }
+            None => {
+                let result = commands::generic_show(repo_path, &id)?;
+                output(&result, human);
+            }
+        },
+
+        Some(Commands::Blame { target }) => {
+            let result = commands::blame(repo_path, &target)?;
+            output(&result, human);
+        }
+
+        Some(Commands::Report { command }) => match command {
+            ReportCommands::Pr { since } => {
+                let result = commands::report_pr(repo_path, &since)?;
+                output(&result, human);
+            }
+            ReportCommands::Changelog { milestone, since } => {
+                let result = commands::report_changelog(repo_path, &milestone, since.as_deref())?;
+                output(&result, human);
+            }
+        },
+
+        Some(Commands::Diff { rev_a, rev_b }) => {
+            let result = commands::graph_diff(repo_path, &rev_a, &rev_b)?;
             output(&result, human);
         }
 
@@ -253,8 +283,13 @@ fn run_command(
                 output(&result, human);
             }
 
-            TaskCommands::Close { id, reason, force } => {
-                let result = commands::task_close(repo_path, &id, reason, force)?;
+            TaskCommands::Close {
+                id,
+                reason,
+                force,
+                run_tests,
+            } => {
+                let result = commands::task_close_with(repo_path, &id, reason, force, run_tests)?;
                 output(&result, human);
             }
 
@@ -357,8 +392,13 @@ fn run_command(
                 )?;
                 output(&result, human);
             }
-            BugCommands::Close { id, reason, force } => {
-                let result = commands::bug_close(repo_path, &id, reason, force)?;
+            BugCommands::Close {
+                id,
+                reason,
+                force,
+                run_tests,
+            } => {
+                let result = commands::bug_close_with(repo_path, &id, reason, force, run_tests)?;
                 output(&result, human);
             }
             BugCommands::Reopen { id } => {
@@ -624,6 +664,8 @@ fn run_command(
                 description,
                 editor,
                 clear_dirty,
+                merge,
+                fork,
             } => {
                 // Read content from the appropriate source
                 let final_content = if stdin {
@@ -646,6 +688,8 @@ fn run_command(
                     description,
                     editor.as_deref(),
                     clear_dirty,
+                    merge,
+                    fork,
                 )?;
                 output(&result, human);
             }
@@ -653,6 +697,10 @@ fn run_command(
                 let result = commands::doc_history(repo_path, &id)?;
                 output(&result, human);
             }
+            DocCommands::Diff { id, from, to } => {
+                let result = commands::doc_diff(repo_path, &id, from.as_deref(), to.as_deref())?;
+                output(&result, human);
+            }
             DocCommands::Attach { doc_id, target_id } => {
                 let result = commands::doc_attach(repo_path, &doc_id, &target_id)?;
                 output(&result, human);
@@ -887,8 +935,13 @@ fn run_command(
                 dir,
                 task,
                 bug,
+                timeout,
+                env,
+                pattern,
             } => {
-                let result = commands::test_create(repo_path, name, cmd, dir, task, bug)?;
+                let result = commands::test_create(
+                    repo_path, name, cmd, dir, task, bug, timeout, env, pattern,
+                )?;
                 output(&result, human);
             }
             TestCommands::List { task } => {
@@ -920,9 +973,56 @@ fn run_command(
                 task,
                 all,
                 failed,
+                affected,
+                since,
+                jobs,
+                timeout,
             } => {
+                let options = commands::TestRunOptions {
+                    jobs: jobs as usize,
+                    timeout_secs: timeout,
+                };
+                // Human output streams each result as its test finishes
+                let result = commands::test_run(
+                    repo_path,
+                    id.as_deref(),
+                    task.as_deref(),
+                    all,
+                    failed,
+                    affected.then_some(since.as_str()),
+                    options,
+                    &mut |r| {
+                        if human {
+                            println!("{}", r.to_human());
+                        }
+                    },
+                )?;
+                if human {
+                    if !result.results.is_empty() {
+                        println!();
+                    }
+                    println!("{}", result.summary());
+                } else {
+                    output(&result, human);
+                }
+            }
+            TestCommands::Import {
+                file,
+                format,
+                cmd,
+                link_tags,
+            } => {
+                let format = format.parse().map_err(binnacle::Error::InvalidInput)?;
                 let result =
-                    commands::test_run(repo_path, id.as_deref(), task.as_deref(), all, failed)?;
+                    commands::test_import(repo_path, format, &file, cmd.as_deref(), link_tags)?;
+                output(&result, human);
+            }
+            TestCommands::Flaky => {
+                let result = commands::test_flaky(repo_path)?;
+                output(&result, human);
+            }
+            TestCommands::Quarantine { id, release } => {
+                let result = commands::test_quarantine(repo_path, &id, release)?;
                 output(&result, human);
             }
         },
@@ -939,6 +1039,10 @@ fn run_command(
                 let result = commands::commit_list(repo_path, &entity_id)?;
                 output(&result, human);
             }
+            CommitCommands::Scan { since } => {
+                let result = commands::commit_scan(repo_path, since.as_deref())?;
+                output(&result, human);
+            }
         },
         Some(Commands::Ready {
             bugs_only,
@@ -1069,6 +1173,33 @@ fn run_command(
                     let result = commands::config_agents_copilot_config(repo_path, &name);
                     output(&result, human);
                 }
+                ConfigAgentsCommands::Launch {
+                    name,
+                    prompt,
+                    interactive,
+                    allow_all,
+                    deny,
+                    backend,
+                    config_dir,
+                    write,
+                } => {
+                    let request = binnacle::agents::LaunchRequest {
+                        prompt: prompt.unwrap_or_default(),
+                        interactive,
+                        allow_all,
+                        extra_deny: deny,
+                        config_dir: config_dir.unwrap_or_default(),
+                        ..Default::default()
+                    };
+                    let result = commands::config_agents_launch(
+                        repo_path,
+                        &name,
+                        backend.as_deref(),
+                        request,
+                        write,
+                    )?;
+                    output(&result, human);
+                }
             },
         },
         Some(Commands::Mcp { command }) => match command {
@@ -1845,6 +1976,12 @@ fn run_command(
                     public,
                     tunnel,
                     upstream,
+                    upstream_pin,
+                    upstream_token,
+                    tls,
+                    tls_cert,
+                    tls_key,
+                    supervise,
                 } => {
                     // Ensure storage is initialized
                     if !binnacle::storage::Storage::exists(repo_path)? {
@@ -1852,6 +1989,18 @@ fn run_command(
                     }
 
                     let actual_host = if public { "0.0.0.0" } else { &host };
+                    let upstream_pins =
+                        binnacle::tls::parse_pins(&upstream_pin).map_err(binnacle::Error::Other)?;
+                    if tls_key.is_some() && tls_cert.is_none() {
+                        return Err(binnacle::Error::InvalidInput(
+                            "--tls-key requires --tls-cert".to_string(),
+                        ));
+                    }
+                    let tls_options =
+                        (tls || tls_cert.is_some()).then_some(binnacle::tls::TlsOptions {
+                            cert: tls_cert,
+                            key: tls_key,
+                        });
 
                     // Create tokio runtime and run the session server
                     let result = tokio::runtime::Builder::new_multi_thread()
@@ -1867,6 +2016,10 @@ fn run_command(
                                 actual_host,
                                 tunnel,
                                 upstream.as_deref(),
+                                upstream_pins,
+                                upstream_token,
+                                tls_options,
+                                supervise,
                             )
                             .await
                             .map_err(|e| {
@@ -2424,9 +2577,11 @@ fn run_command(
                 cpus,
                 memory,
                 worktree,
+                task,
                 merge_target,
                 no_merge,
                 prompt,
+                backend,
             } => {
                 let result = commands::agent_spawn(
                     repo_path,
@@ -2435,9 +2590,11 @@ fn run_command(
                     cpus,
                     memory.as_deref(),
                     worktree.as_deref(),
+                    task.as_deref(),
                     &merge_target,
                     no_merge,
                     prompt.as_deref(),
+                    backend.as_deref(),
                 )?;
                 output(&result, human);
             }
@@ -2445,7 +2602,139 @@ fn run_command(
                 let result = commands::agent_reconcile(repo_path, dry_run)?;
                 output(&result, human);
             }
+            AgentCommands::Supervise {
+                dry_run,
+                once,
+                interval,
+            } => {
+                binnacle::agents::supervise(repo_path, dry_run, once, interval, |status| {
+                    output(status, human)
+                })?;
+            }
+            AgentCommands::Usage { command } => match command {
+                AgentUsageCommands::Report {
+                    agent,
+                    input_tokens,
+                    output_tokens,
+                    requests,
+                    cost,
+                } => {
+                    let usage = binnacle::models::AgentUsage {
+                        input_tokens,
+                        output_tokens,
+                        requests,
+                        cost_usd: cost,
+                    };
+                    let result = commands::agent_usage_report(repo_path, agent.as_deref(), usage)?;
+                    output(&result, human);
+                }
+                AgentUsageCommands::Show => {
+                    let result = commands::agent_usage_show(repo_path)?;
+                    output(&result, human);
+                }
+            },
+            AgentCommands::Transcript {
+                id,
+                follow,
+                capture,
+                raw_log,
+            } => {
+                if let Some(stream) = capture {
+                    let result = commands::agent_transcript_capture(
+                        repo_path,
+                        &id,
+                        &stream,
+                        raw_log.as_deref(),
+                    )?;
+                    output(&result, human);
+                } else if follow {
+                    commands::agent_transcript_follow(repo_path, &id, &mut |entry| {
+                        if human {
+                            println!("{}", commands::format_transcript_entry(entry));
+                        } else {
+                            println!("{}", serde_json::to_string(entry).unwrap_or_default());
+                        }
+                    })?;
+                } else {
+                    let result = commands::agent_transcript(repo_path, &id)?;
+                    output(&result, human);
+                }
+            }
+            AgentCommands::Worktree { command } => match command {
+                AgentWorktreeCommands::Create { task, base } => {
+                    let result =
+                        commands::agent_worktree_create(repo_path, &task, base.as_deref())?;
+                    output(&result, human);
+                }
+                AgentWorktreeCommands::List => {
+                    let result = commands::agent_worktree_list(repo_path)?;
+                    output(&result, human);
+                }
+                AgentWorktreeCommands::Finish {
+                    task,
+                    target,
+                    no_tests,
+                } => {
+                    let result = commands::agent_worktree_finish(
+                        repo_path,
+                        &task,
+                        target.as_deref(),
+                        !no_tests,
+                    )?;
+                    output(&result, human);
+                }
+                AgentWorktreeCommands::Gc {
+                    target,
+                    force,
+                    dry_run,
+                } => {
+                    let result =
+                        commands::agent_worktree_gc(repo_path, target.as_deref(), force, dry_run)?;
+                    output(&result, human);
+                }
+            },
+        },
+        Some(Commands::MergeQueue { command }) => match command {
+            MergeQueueCommands::Add { task, target } => {
+                let result = commands::merge_queue_add(repo_path, &task, target.as_deref())?;
+                output(&result, human);
+            }
+            MergeQueueCommands::List { all } => {
+                let result = commands::merge_queue_list(repo_path, all)?;
+                output(&result, human);
+            }
+            MergeQueueCommands::Run { once } => {
+                let result = commands::merge_queue_run(repo_path, once)?;
+                output(&result, human);
+            }
+            MergeQueueCommands::Remove { id } => {
+                let result = commands::merge_queue_remove(repo_path, &id)?;
+                output(&result, human);
+            }
+        },
+        Some(Commands::Msg { command }) => match command {
+            MsgCommands::Send { to, body } => {
+                let result = commands::msg_send(repo_path, &to, &body)?;
+                output(&result, human);
+            }
+            MsgCommands::Inbox { all } => {
+                let result = commands::msg_inbox(repo_path, all)?;
+                output(&result, human);
+            }
+            MsgCommands::Reply { id, body } => {
+                let result = commands::msg_reply(repo_path, &id, &body)?;
+                output(&result, human);
+            }
         },
+        Some(Commands::Ask {
+            question,
+            to,
+            wait,
+            timeout,
+        }) => {
+            let result = commands::ask(repo_path, &question, Some(&to), wait, timeout)?;
+            output(&result, human);
+        }
         Some(Commands::Container { command }) => match command {
             ContainerCommands::Build {
                 definition,
@@ -2518,11 +2807,11 @@ fn run_command(
                 output(&result, human);
             }
             ContainerCommands::Stop { name, all } => {
-                let result = commands::container_stop(name, all)?;
+                let result = commands::container_stop(repo_path, name, all)?;
                 output(&result, human);
             }
             ContainerCommands::List { all, quiet } => {
-                let result = commands::container_list(all, quiet)?;
+                let result = commands::container_list(repo_path, all, quiet)?;
                 output(&result, human);
             }
             ContainerCommands::ListDefinitions => {
@@ -2539,70 +2828,103 @@ fn run_command(
             dev,
             tunnel,
             archive,
-        }) => match command {
-            Some(GuiCommands::Serve {
-                port: sub_port,
-                host: sub_host,
-                replace,
-                readonly: sub_readonly,
-                dev: sub_dev,
-                tunnel: sub_tunnel,
-            }) => {
-                let actual_port = sub_port.or(port);
-                let actual_host = &sub_host;
-                let actual_readonly = sub_readonly || readonly;
-                let actual_dev = sub_dev || dev;
-                let actual_tunnel = sub_tunnel || tunnel;
-                if replace {
-                    replace_gui(
-                        repo_path,
-                        actual_port,
-                        actual_host,
-                        actual_readonly,
-                        archive.as_deref(),
-                        human,
-                        actual_dev,
-                        actual_tunnel,
-                    )?;
-                } else {
+            rev,
+        }) => {
+            // A revision stands in for the commit archive closest to it
+            let archive = match rev {
+                Some(rev) => Some(
+                    commands::commit_archive_for_rev(repo_path, &rev)?
+                        .path
+                        .to_string_lossy()
+                        .to_string(),
+                ),
+                None => archive,
+            };
+            match command {
+                Some(GuiCommands::Serve {
+                    port: sub_port,
+                    host: sub_host,
+                    replace,
+                    readonly: sub_readonly,
+                    dev: sub_dev,
+                    tunnel: sub_tunnel,
+                }) => {
+                    let actual_port = sub_port.or(port);
+                    let actual_host = &sub_host;
+                    let actual_readonly = sub_readonly || readonly;
+                    let actual_dev = sub_dev || dev;
+                    let actual_tunnel = sub_tunnel || tunnel;
+                    if replace {
+                        replace_gui(
+                            repo_path,
+                            actual_port,
+                            actual_host,
+                            actual_readonly,
+                            archive.as_deref(),
+                            human,
+                            actual_dev,
+                            actual_tunnel,
+                        )?;
+                    } else {
+                        run_gui(
+                            repo_path,
+                            actual_port,
+                            actual_host,
+                            actual_readonly,
+                            archive.as_deref(),
+                            actual_dev,
+                            actual_tunnel,
+                        )?;
+                    }
+                }
+                Some(GuiCommands::Status) => {
+                    show_gui_status(repo_path, human)?;
+                }
+                Some(GuiCommands::Stop { force }) => {
+                    stop_gui(repo_path, force, human)?;
+                }
+                Some(GuiCommands::Kill { force }) => {
+                    kill_gui(repo_path, force, human)?;
+                }
+                Some(GuiCommands::Export { output, archive }) => {
+                    export_static_gui(repo_path, &output, archive.as_deref(), human)?;
+                }
+                Some(GuiCommands::Token { command }) => match command {
+                    GuiTokenCommands::Create { role, name } => {
+                        let result = commands::gui_token_create(repo_path, &role, name)?;
+                        output(&result, human);
+                    }
+                    GuiTokenCommands::List => {
+                        let result = commands::gui_token_list(repo_path)?;
+                        output(&result, human);
+                    }
+                    GuiTokenCommands::Revoke { id } => {
+                        let result = commands::gui_token_revoke(repo_path, &id)?;
+                        output(&result, human);
+                    }
+                },
+                None => {
+                    // Default: start server (same as `bn gui serve`)
                     run_gui(
                         repo_path,
-                        actual_port,
-                        actual_host,
-                        actual_readonly,
+                        port,
+                        &host,
+                        readonly,
                         archive.as_deref(),
-                        actual_dev,
-                        actual_tunnel,
+                        dev,
+                        tunnel,
                     )?;
                 }
             }
-            Some(GuiCommands::Status) => {
-                show_gui_status(repo_path, human)?;
-            }
-            Some(GuiCommands::Stop { force }) => {
-                stop_gui(repo_path, force, human)?;
-            }
-            Some(GuiCommands::Kill { force }) => {
-                kill_gui(repo_path, force, human)?;
-            }
-            Some(GuiCommands::Export { output, archive }) => {
-                export_static_gui(repo_path, &output, archive.as_deref(), human)?;
-            }
-            None => {
-                // Default: start server (same as `bn gui serve`)
-                run_gui(
-                    repo_path,
-                    port,
-                    &host,
-                    readonly,
-                    archive.as_deref(),
-                    dev,
-                    tunnel,
-                )?;
-            }
-        },
+        }
         #[cfg(feature = "tui")]
-        Some(Commands::Tui { port, host, url }) => {
+        Some(Commands::Tui {
+            port,
+            host,
+            url,
+            token,
+            tls_pin,
+        }) => {
             // Determine the port to use (CLI arg, env var, or default)
             let effective_port = port.unwrap_or(binnacle::tui::DEFAULT_PORT);
 
@@ -2612,11 +2934,32 @@ fn run_command(
                 let _session_child = ensure_session_server(repo_path, effective_port)?;
             }
 
+            let mut tls_pins =
+                binnacle::tls::parse_pins(&tls_pin).map_err(binnacle::Error::Other)?;
+
+            // A local session server running TLS is reached over https/wss, trusting
+            // the certificate fingerprint it recorded in state.kdl
+            let mut local_tls = false;
+            if url.is_none()
+                && let Some(serve) = binnacle::storage::Storage::open(repo_path)?
+                    .read_binnacle_state()?
+                    .serve
+                && serve.port == effective_port
+                && let Some(fingerprint) = serve.tls_fingerprint
+            {
+                local_tls = true;
+                if tls_pins.is_empty() {
+                    tls_pins.extend(binnacle::tls::normalize_fingerprint(&fingerprint));
+                }
+            }
+
             // Create a tokio runtime and run the TUI
             let rt = tokio::runtime::Runtime::new()
                 .map_err(|e| binnacle::Error::Other(format!("Failed to create runtime: {}", e)))?;
-            rt.block_on(async { binnacle::tui::run_tui(port, Some(host), url).await })
-                .map_err(|e| binnacle::Error::Other(e.to_string()))?;
+            rt.block_on(async {
+                binnacle::tui::run_tui(port, Some(host), url, token, tls_pins, local_tls).await
+            })
+            .map_err(|e| binnacle::Error::Other(e.to_string()))?;
         }
         Some(Commands::Serve) => {
             // Migration message for removed 'bn serve' command
@@ -2939,6 +3282,7 @@ fn run_gui(
         pid: current_pid,
         port: actual_port,
         host: host.to_string(),
+        tls: false, // the GUI server only serves plain HTTP
     };
     pid_file
         .write(&pid_info)
@@ -3120,7 +3464,7 @@ fn show_gui_status(repo_path: &Path, human: bool) -> Result<(), binnacle::Error>
                         println!("  PID:  {}", info.pid);
                         println!("  Port: {}", info.port);
                         println!("  Host: {}", info.host);
-                        println!("  URL:  http://{}:{}", info.host, info.port);
+                        println!("  URL:  {}", info.url());
                     }
                     ProcessStatus::NotRunning => {
                         println!("GUI server is not running (stale PID file found)");
@@ -3534,14 +3878,43 @@ fn serialize_command(command: &Option<Commands>) -> (String, serde_json::Value)
 
         Some(Commands::Goodbye {
             reason,
+            next,
             dry_run,
             force,
         }) => (
             "goodbye".to_string(),
-            serde_json::json!({ "reason": reason, "dry_run": dry_run, "force": force }),
+            serde_json::json!({
+                "reason": reason,
+                "next": next,
+                "dry_run": dry_run,
+                "force": force,
+            }),
         ),
 
-        Some(Commands::Show { id }) => ("show".to_string(), serde_json::json!({ "id": id })),
+        Some(Commands::Show { id, at }) => (
+            "show".to_string(),
+            serde_json::json!({ "id": id, "at": at }),
+        ),
+
+        Some(Commands::Blame { target }) => {
+            ("blame".to_string(), serde_json::json!({ "target": target }))
+        }
+
+        Some(Commands::Report { command }) => match command {
+            ReportCommands::Pr { since } => (
+                "report pr".to_string(),
+                serde_json::json!({ "since": since }),
+            ),
+            ReportCommands::Changelog { milestone, since } => (
+                "report changelog".to_string(),
+                serde_json::json!({ "milestone": milestone, "since": since }),
+            ),
+        },
+
+        Some(Commands::Diff { rev_a, rev_b }) => (
+            "diff".to_string(),
+            serde_json::json!({ "rev_a": rev_a, "rev_b": rev_b }),
+        ),
 
         Some(Commands::Task { command }) => match command {
             TaskCommands::Create {
@@ -3611,12 +3984,18 @@ fn serialize_command(command: &Option<Commands>) -> (String, serde_json::Value)
                     "reopen": reopen,
                 }),
             ),
-            TaskCommands::Close { id, reason, force } => (
+            TaskCommands::Close {
+                id,
+                reason,
+                force,
+                run_tests,
+            } => (
                 "task close".to_string(),
                 serde_json::json!({
                     "id": id,
                     "reason": reason,
                     "force": force,
+                    "run_tests": run_tests,
                 }),
             ),
             TaskCommands::Reopen { id } => {
@@ -3709,12 +4088,18 @@ fn serialize_command(command: &Option<Commands>) -> (String, serde_json::Value)
                     "reopen": reopen,
                 }),
             ),
-            BugCommands::Close { id, reason, force } => (
+            BugCommands::Close {
+                id,
+                reason,
+                force,
+                run_tests,
+            } => (
                 "bug close".to_string(),
                 serde_json::json!({
                     "id": id,
                     "reason": reason,
                     "force": force,
+                    "run_tests": run_tests,
                 }),
             ),
             BugCommands::Reopen { id } => {
@@ -3946,6 +4331,8 @@ fn serialize_command(command: &Option<Commands>) -> (String, serde_json::Value)
                 description,
                 editor,
                 clear_dirty,
+                merge,
+                fork,
             } => (
                 "doc update".to_string(),
                 serde_json::json!({
@@ -3958,11 +4345,17 @@ fn serialize_command(command: &Option<Commands>) -> (String, serde_json::Value)
                     "description": description,
                     "editor": editor,
                     "clear_dirty": clear_dirty,
+                    "merge": merge,
+                    "fork": fork,
                 }),
             ),
             DocCommands::History { id } => {
                 ("doc history".to_string(), serde_json::json!({ "id": id }))
             }
+            DocCommands::Diff { id, from, to } => (
+                "doc diff".to_string(),
+                serde_json::json!({ "id": id, "from": from, "to": to }),
+            ),
             DocCommands::Attach { doc_id, target_id } => (
                 "doc attach".to_string(),
                 serde_json::json!({
@@ -4217,6 +4610,9 @@ fn serialize_command(command: &Option<Commands>) -> (String, serde_json::Value)
                 dir,
                 task,
                 bug,
+                timeout,
+                env,
+                pattern,
             } => (
                 "test create".to_string(),
                 serde_json::json!({
@@ -4225,6 +4621,9 @@ fn serialize_command(command: &Option<Commands>) -> (String, serde_json::Value)
                     "dir": dir,
                     "task": task,
                     "bug": bug,
+                    "timeout": timeout,
+                    "env": env,
+                    "pattern": pattern,
                 }),
             ),
             TestCommands::List { task } => {
@@ -4264,6 +4663,10 @@ fn serialize_command(command: &Option<Commands>) -> (String, serde_json::Value)
                 task,
                 all,
                 failed,
+                affected,
+                since,
+                jobs,
+                timeout,
             } => (
                 "test run".to_string(),
                 serde_json::json!({
@@ -4271,6 +4674,32 @@ fn serialize_command(command: &Option<Commands>) -> (String, serde_json::Value)
                     "task": task,
                     "all": all,
                     "failed": failed,
+                    "affected": affected,
+                    "since": since,
+                    "jobs": jobs,
+                    "timeout": timeout,
+                }),
+            ),
+            TestCommands::Import {
+                file,
+                format,
+                cmd,
+                link_tags,
+            } => (
+                "test import".to_string(),
+                serde_json::json!({
+                    "file": file,
+                    "format": format,
+                    "cmd": cmd,
+                    "link_tags": link_tags,
+                }),
+            ),
+            TestCommands::Flaky => ("test flaky".to_string(), serde_json::json!({})),
+            TestCommands::Quarantine { id, release } => (
+                "test quarantine".to_string(),
+                serde_json::json!({
+                    "id": id,
+                    "release": release,
                 }),
             ),
         },
@@ -4294,6 +4723,10 @@ fn serialize_command(command: &Option<Commands>) -> (String, serde_json::Value)
                 "commit list".to_string(),
                 serde_json::json!({ "entity_id": entity_id }),
             ),
+            CommitCommands::Scan { since } => (
+                "commit scan".to_string(),
+                serde_json::json!({ "since": since }),
+            ),
         },
 
         Some(Commands::Ready {
@@ -4416,6 +4849,25 @@ fn serialize_command(command: &Option<Commands>) -> (String, serde_json::Value)
                     "config agents copilot-config".to_string(),
                     serde_json::json!({ "name": name }),
                 ),
+                ConfigAgentsCommands::Launch {
+                    name,
+                    interactive,
+                    allow_all,
+                    deny,
+                    backend,
+                    write,
+                    ..
+                } => (
+                    "config agents launch".to_string(),
+                    serde_json::json!({
+                        "name": name,
+                        "interactive": interactive,
+                        "allow_all": allow_all,
+                        "deny": deny,
+                        "backend": backend,
+                        "write": write,
+                    }),
+                ),
             },
         },
 
@@ -4680,6 +5132,10 @@ fn serialize_command(command: &Option<Commands>) -> (String, serde_json::Value)
                 public,
                 tunnel,
                 upstream,
+                tls,
+                tls_cert,
+                supervise,
+                ..
             } => (
                 "session serve".to_string(),
                 serde_json::json!({
@@ -4688,6 +5144,8 @@ fn serialize_command(command: &Option<Commands>) -> (String, serde_json::Value)
                     "public": public,
                     "tunnel": tunnel,
                     "upstream": upstream,
+                    "tls": *tls || tls_cert.is_some(),
+                    "supervise": supervise,
                 }),
             ),
             SessionCommands::Status => ("session status".to_string(), serde_json::json!({})),
@@ -4751,9 +5209,11 @@ fn serialize_command(command: &Option<Commands>) -> (String, serde_json::Value)
                 cpus,
                 memory,
                 worktree,
+                task,
                 merge_target,
                 no_merge,
                 prompt,
+                backend,
             } => (
                 "agent spawn".to_string(),
                 serde_json::json!({
@@ -4762,17 +5222,132 @@ fn serialize_command(command: &Option<Commands>) -> (String, serde_json::Value)
                     "cpus": cpus,
                     "memory": memory,
                     "worktree": worktree,
+                    "task": task,
                     "merge_target": merge_target,
                     "no_merge": no_merge,
-                    "prompt": prompt.is_some()
+                    "prompt": prompt.is_some(),
+                    "backend": backend
                 }),
             ),
             AgentCommands::Reconcile { dry_run } => (
                 "agent reconcile".to_string(),
                 serde_json::json!({ "dry_run": dry_run }),
             ),
+            AgentCommands::Supervise {
+                dry_run,
+                once,
+                interval,
+            } => (
+                "agent supervise".to_string(),
+                serde_json::json!({ "dry_run": dry_run, "once": once, "interval": interval }),
+            ),
+            AgentCommands::Usage { command } => match command {
+                AgentUsageCommands::Report {
+                    agent,
+                    input_tokens,
+                    output_tokens,
+                    requests,
+                    cost,
+                } => (
+                    "agent usage report".to_string(),
+                    serde_json::json!({
+                        "agent": agent,
+                        "input_tokens": input_tokens,
+                        "output_tokens": output_tokens,
+                        "requests": requests,
+                        "cost": cost,
+                    }),
+                ),
+                AgentUsageCommands::Show => ("agent usage show".to_string(), serde_json::json!({})),
+            },
+            AgentCommands::Transcript {
+                id,
+                follow,
+                capture,
+                raw_log: _,
+            } => (
+                "agent transcript".to_string(),
+                serde_json::json!({
+                    "id": id,
+                    "follow": follow,
+                    "capture": capture,
+                }),
+            ),
+            AgentCommands::Worktree { command } => match command {
+                AgentWorktreeCommands::Create { task, base } => (
+                    "agent worktree create".to_string(),
+                    serde_json::json!({ "task": task, "base": base }),
+                ),
+                AgentWorktreeCommands::List => {
+                    ("agent worktree list".to_string(), serde_json::json!({}))
+                }
+                AgentWorktreeCommands::Finish {
+                    task,
+                    target,
+                    no_tests,
+                } => (
+                    "agent worktree finish".to_string(),
+                    serde_json::json!({ "task": task, "target": target, "no_tests": no_tests }),
+                ),
+                AgentWorktreeCommands::Gc {
+                    target,
+                    force,
+                    dry_run,
+                } => (
+                    "agent worktree gc".to_string(),
+                    serde_json::json!({ "target": target, "force": force, "dry_run": dry_run }),
+                ),
+            },
+        },
+
+        Some(Commands::MergeQueue { command }) => match command {
+            MergeQueueCommands::Add { task, target } => (
+                "merge-queue add".to_string(),
+                serde_json::json!({ "task": task, "target": target }),
+            ),
+            MergeQueueCommands::List { all } => (
+                "merge-queue list".to_string(),
+                serde_json::json!({ "all": all }),
+            ),
+            MergeQueueCommands::Run { once } => (
+                "merge-queue run".to_string(),
+                serde_json::json!({ "once": once }),
+            ),
+            MergeQueueCommands::Remove { id } => (
+                "merge-queue remove".to_string(),
+                serde_json::json!({ "id": id }),
+            ),
+        },
+
+        Some(Commands::Msg { command }) => match command {
+            MsgCommands::Send { to, body } => (
+                "msg send".to_string(),
+                serde_json::json!({ "to": to, "body": body }),
+            ),
+            MsgCommands::Inbox { all } => {
+                ("msg inbox".to_string(), serde_json::json!({ "all": all }))
+            }
+            MsgCommands::Reply { id, body } => (
+                "msg reply".to_string(),
+                serde_json::json!({ "id": id, "body": body }),
+            ),
         },
 
+        Some(Commands::Ask {
+            question,
+            to,
+            wait,
+            timeout,
+        }) => (
+            "ask".to_string(),
+            serde_json::json!({
+                "question": question,
+                "to": to,
+                "wait": wait,
+                "timeout": timeout,
+            }),
+        ),
+
         Some(Commands::Container { command }) => match command {
             ContainerCommands::Build {
                 definition,
@@ -4849,6 +5424,7 @@ fn serialize_command(command: &Option<Commands>) -> (String, serde_json::Value)
             dev,
             tunnel,
             archive,
+            rev,
         }) => {
             let subcommand = match command {
                 Some(GuiCommands::Serve {
@@ -4859,7 +5435,7 @@ fn serialize_command(command: &Option<Commands>) -> (String, serde_json::Value)
                     dev: sub_dev,
                     tunnel: sub_tunnel,
                 }) => {
-                    serde_json::json!({ "subcommand": "serve", "port": sub_port.or(*port), "host": sub_host, "replace": replace, "readonly": *sub_readonly || *readonly, "dev": *sub_dev || *dev, "tunnel": *sub_tunnel || *tunnel, "archive": archive })
+                    serde_json::json!({ "subcommand": "serve", "port": sub_port.or(*port), "host": sub_host, "replace": replace, "readonly": *sub_readonly || *readonly, "dev": *sub_dev || *dev, "tunnel": *sub_tunnel || *tunnel, "archive": archive, "rev": rev })
                 }
                 Some(GuiCommands::Status) => serde_json::json!({ "subcommand": "status" }),
                 Some(GuiCommands::Stop { force }) => {
@@ -4871,15 +5447,26 @@ fn serialize_command(command: &Option<Commands>) -> (String, serde_json::Value)
                 Some(GuiCommands::Export { output, archive }) => {
                     serde_json::json!({ "subcommand": "export", "output": output, "archive": archive })
                 }
+                Some(GuiCommands::Token { command }) => match command {
+                    GuiTokenCommands::Create { role, name } => {
+                        serde_json::json!({ "subcommand": "token create", "role": role, "name": name })
+                    }
+                    GuiTokenCommands::List => serde_json::json!({ "subcommand": "token list" }),
+                    GuiTokenCommands::Revoke { id } => {
+                        serde_json::json!({ "subcommand": "token revoke", "id": id })
+                    }
+                },
                 None => {
-                    serde_json::json!({ "subcommand": null, "port": port, "host": host, "readonly": readonly, "dev": dev, "tunnel": tunnel, "archive": archive })
+                    serde_json::json!({ "subcommand": null, "port": port, "host": host, "readonly": readonly, "dev": dev, "tunnel": tunnel, "archive": archive, "rev": rev })
                 }
             };
             ("gui".to_string(), subcommand)
         }
 
         #[cfg(feature = "tui")]
-        Some(Commands::Tui { port, host, url }) => (
+        Some(Commands::Tui {
+            port, host, url, ..
+        }) => (
             "tui".to_string(),
             serde_json::json!({ "port": port, "host": host, "url": url }),
         ),
@@ -462,8 +462,16 @@ impl McpServer {
             .and_then(|v| v.as_str())
             .unwrap_or("Session ended via MCP");
 
+        let mut goodbye_args = vec!["goodbye".to_string(), summary.to_string()];
+        if let Some(steps) = args.get("next_steps").and_then(|v| v.as_array()) {
+            for step in steps.iter().filter_map(|v| v.as_str()) {
+                goodbye_args.push("--next".to_string());
+                goodbye_args.push(step.to_string());
+            }
+        }
+
         let output = Command::new(&self.bn_path)
-            .args(["goodbye", summary])
+            .args(&goodbye_args)
             .current_dir(cwd)
             .env("BN_MCP_SESSION", &self.session_id)
             .output()
@@ -930,6 +938,11 @@ fn get_tool_definitions() -> Vec<ToolDef> {
                     "summary": {
                         "type": "string",
                         "description": "Summary of what was accomplished in the session"
+                    },
+                    "next_steps": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Next steps for whoever picks up your in-progress tasks"
                     }
                 },
                 "required": []
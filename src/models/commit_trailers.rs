@@ -0,0 +1,131 @@
+//! Task and bug references in commit messages.
+//!
+//! A commit message can point at binnacle entities in three ways:
+//!
+//! - a `Binnacle-Task: bn-a1b2` trailer links the commit to a task or bug
+//! - a `Fixes: bn-a1b2` trailer links it too, and closes the bug once the
+//!   commit reaches the default branch
+//! - a bare `bn-a1b2` anywhere in the message links it, unless the message
+//!   has trailers, which then say exactly what it links to
+//!
+//! As with `git interpret-trailers`, trailers are only read from the last
+//! paragraph of a message that has a body. A trailer value can name several
+//! IDs (`Binnacle-Task: bn-a1b2, bn-c3d4`).
+
+/// Trailer linking a commit to a task or bug.
+pub const TASK_TRAILER: &str = "Binnacle-Task";
+
+/// Trailer marking a bug as fixed by a commit.
+pub const FIXES_TRAILER: &str = "Fixes";
+
+/// Entity IDs referenced by a commit message.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommitRefs {
+    /// IDs named in `Binnacle-Task:` or `Fixes:` trailers
+    pub trailers: Vec<String>,
+    /// IDs named in `Fixes:` trailers
+    pub fixes: Vec<String>,
+    /// Every ID the message mentions, trailers included
+    pub mentions: Vec<String>,
+}
+
+impl CommitRefs {
+    /// IDs the commit should be linked to: its trailers if it has any,
+    /// otherwise every ID it mentions.
+    pub fn linked_ids(&self) -> &[String] {
+        if self.trailers.is_empty() {
+            &self.mentions
+        } else {
+            &self.trailers
+        }
+    }
+}
+
+/// Parse the entity references out of a commit message.
+pub fn parse_commit_message(message: &str) -> CommitRefs {
+    let mut refs = CommitRefs {
+        mentions: mentioned_ids(message),
+        ..CommitRefs::default()
+    };
+
+    let paragraphs: Vec<&str> = message
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .collect();
+    // The subject paragraph is never a trailer block
+    let Some(block) = paragraphs.last().filter(|_| paragraphs.len() > 1) else {
+        return refs;
+    };
+    for line in block.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim();
+        let is_fix = key.eq_ignore_ascii_case(FIXES_TRAILER);
+        if !is_fix && !key.eq_ignore_ascii_case(TASK_TRAILER) {
+            continue;
+        }
+        for id in mentioned_ids(value) {
+            if is_fix && !refs.fixes.contains(&id) {
+                refs.fixes.push(id.clone());
+            }
+            if !refs.trailers.contains(&id) {
+                refs.trailers.push(id);
+            }
+        }
+    }
+    refs
+}
+
+/// IDs of the form `bn-xxxx` (four or more lowercase hex digits) that are
+/// not embedded in a longer word, in order of first appearance.
+pub fn mentioned_ids(text: &str) -> Vec<String> {
+    super::ids::scan_ids(text, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_trailers_and_mentions() {
+        let refs = parse_commit_message(
+            "Fix crash in bn-1111 parser\n\n\
+             Also touches bn-2222.\n\n\
+             Binnacle-Task: bn-3333, bn-4444\n\
+             fixes: bn-5555\n\
+             Signed-off-by: Someone <someone@example.com>\n",
+        );
+        assert_eq!(refs.trailers, vec!["bn-3333", "bn-4444", "bn-5555"]);
+        assert_eq!(refs.fixes, vec!["bn-5555"]);
+        assert_eq!(
+            refs.mentions,
+            vec!["bn-1111", "bn-2222", "bn-3333", "bn-4444", "bn-5555"]
+        );
+        assert_eq!(refs.linked_ids(), refs.trailers.as_slice());
+
+        let refs = parse_commit_message("Start bn-1111 work");
+        assert_eq!(refs.linked_ids(), ["bn-1111"]);
+    }
+
+    #[test]
+    fn test_trailers_only_in_last_paragraph() {
+        // A subject alone has no trailer block
+        let refs = parse_commit_message("Fixes: bn-1111");
+        assert!(refs.trailers.is_empty());
+        assert_eq!(refs.mentions, vec!["bn-1111"]);
+
+        let refs = parse_commit_message("Subject\n\nFixes: bn-1111\n\nTrailing notes");
+        assert!(refs.fixes.is_empty());
+        assert_eq!(refs.mentions, vec!["bn-1111"]);
+    }
+
+    #[test]
+    fn test_mentioned_ids_are_word_bounded() {
+        assert_eq!(
+            mentioned_ids("see bn-a1b2, xbn-cccc, bn-12, bn-ABCD, bn-dddd1x (bn-eeee)"),
+            vec!["bn-a1b2", "bn-eeee"]
+        );
+    }
+}
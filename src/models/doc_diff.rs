@@ -0,0 +1,321 @@
+//! Line diffs and three-way merges of doc content.
+//!
+//! Doc versions form a `supersedes` chain. These helpers compare two
+//! versions as a unified diff (`bn doc diff`) and merge an edit made against
+//! an older version onto the latest one (`bn doc update --merge`).
+//!
+//! Lines are matched by a longest common subsequence. Inputs whose changed
+//! middle section would need more than [`MAX_MATCH_CELLS`] comparisons are
+//! treated as a full replacement of that section instead.
+
+/// Upper bound on the LCS table size before giving up on line matching.
+pub const MAX_MATCH_CELLS: usize = 16_000_000;
+
+/// Lines of context around each hunk of a unified diff.
+pub const DIFF_CONTEXT: usize = 3;
+
+/// Pairs `(i, j)` of equal lines `a[i] == b[j]` in a longest common
+/// subsequence, in increasing order.
+fn matching_lines(a: &[&str], b: &[&str]) -> Vec<(usize, usize)> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_mid, b_mid) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut pairs: Vec<(usize, usize)> = (0..prefix).map(|i| (i, i)).collect();
+    let (n, m) = (a_mid.len(), b_mid.len());
+    if n > 0 && m > 0 && (n + 1) * (m + 1) <= MAX_MATCH_CELLS {
+        // lengths[i][j] = LCS length of a_mid[i..] and b_mid[j..]
+        let width = m + 1;
+        let mut lengths = vec![0u32; (n + 1) * width];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lengths[i * width + j] = if a_mid[i] == b_mid[j] {
+                    lengths[(i + 1) * width + j + 1] + 1
+                } else {
+                    lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n && j < m {
+            if a_mid[i] == b_mid[j] {
+                pairs.push((prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+    }
+    pairs.extend((0..suffix).map(|k| (a.len() - suffix + k, b.len() - suffix + k)));
+    pairs
+}
+
+/// One line of an edit script from old to new.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    /// Line present in both, at (old index, new index)
+    Keep(usize, usize),
+    Remove(usize),
+    Add(usize),
+}
+
+fn edit_script(a: &[&str], b: &[&str]) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for (mi, mj) in matching_lines(a, b)
+        .into_iter()
+        .chain(std::iter::once((a.len(), b.len())))
+    {
+        edits.extend((i..mi).map(Edit::Remove));
+        edits.extend((j..mj).map(Edit::Add));
+        if mi < a.len() && mj < b.len() {
+            edits.push(Edit::Keep(mi, mj));
+        }
+        i = mi + 1;
+        j = mj + 1;
+    }
+    edits
+}
+
+/// A unified diff between two texts.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnifiedDiff {
+    /// Diff text with `---`/`+++` headers, empty if the texts are equal
+    pub text: String,
+    pub added: usize,
+    pub removed: usize,
+}
+
+/// Unified diff of `old` and `new`, labelled `old_label` and `new_label`.
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> UnifiedDiff {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let edits = edit_script(&a, &b);
+
+    let changed: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| !matches!(e, Edit::Keep(..)))
+        .map(|(k, _)| k)
+        .collect();
+    let mut diff = UnifiedDiff::default();
+    if changed.is_empty() {
+        return diff;
+    }
+
+    // Group changes whose context overlaps into hunks of edit indices
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &k in &changed {
+        let start = k.saturating_sub(DIFF_CONTEXT);
+        let end = (k + DIFF_CONTEXT + 1).min(edits.len());
+        match hunks.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    diff.text = format!("--- {}\n+++ {}\n", old_label, new_label);
+    for (start, end) in hunks {
+        // Line numbers before the hunk, for empty ranges
+        let (mut old_before, mut new_before) = (0, 0);
+        for edit in &edits[..start] {
+            match edit {
+                Edit::Keep(..) => {
+                    old_before += 1;
+                    new_before += 1;
+                }
+                Edit::Remove(_) => old_before += 1,
+                Edit::Add(_) => new_before += 1,
+            }
+        }
+        let mut body = String::new();
+        let (mut old_len, mut new_len) = (0, 0);
+        for edit in &edits[start..end] {
+            match *edit {
+                Edit::Keep(i, _) => {
+                    body.push_str(&format!(" {}\n", a[i]));
+                    old_len += 1;
+                    new_len += 1;
+                }
+                Edit::Remove(i) => {
+                    body.push_str(&format!("-{}\n", a[i]));
+                    old_len += 1;
+                    diff.removed += 1;
+                }
+                Edit::Add(j) => {
+                    body.push_str(&format!("+{}\n", b[j]));
+                    new_len += 1;
+                    diff.added += 1;
+                }
+            }
+        }
+        let range = |before: usize, len: usize| {
+            let first = if len == 0 { before } else { before + 1 };
+            if len == 1 {
+                first.to_string()
+            } else {
+                format!("{},{}", first, len)
+            }
+        };
+        diff.text.push_str(&format!(
+            "@@ -{} +{} @@\n{}",
+            range(old_before, old_len),
+            range(new_before, new_len),
+            body
+        ));
+    }
+    diff
+}
+
+/// Result of a three-way merge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merged {
+    /// Merged text, with conflict markers around unresolved sections
+    pub text: String,
+    /// Number of sections both sides changed differently
+    pub conflicts: usize,
+}
+
+/// Merge the changes from `base` to `ours` and from `base` to `theirs`.
+///
+/// Sections changed on only one side take that side; sections changed the
+/// same way on both sides are taken once. Anything else is a conflict,
+/// written with diff3-style markers using the given labels.
+pub fn merge3(base: &str, ours: &str, theirs: &str, labels: [&str; 3]) -> Merged {
+    let [base_label, ours_label, theirs_label] = labels;
+    let o: Vec<&str> = base.lines().collect();
+    let a: Vec<&str> = ours.lines().collect();
+    let b: Vec<&str> = theirs.lines().collect();
+
+    // Base line -> matching line in ours / theirs
+    let mut in_a = vec![None; o.len()];
+    for (i, j) in matching_lines(&o, &a) {
+        in_a[i] = Some(j);
+    }
+    let mut in_b = vec![None; o.len()];
+    for (i, j) in matching_lines(&o, &b) {
+        in_b[i] = Some(j);
+    }
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut conflicts = 0;
+    let (mut io, mut ia, mut ib) = (0, 0, 0);
+    loop {
+        if io < o.len() && in_a[io] == Some(ia) && in_b[io] == Some(ib) {
+            lines.push(o[io].to_string());
+            io += 1;
+            ia += 1;
+            ib += 1;
+            continue;
+        }
+        // Next base line kept by both sides ends the unstable section
+        let next = (io..o.len()).find(|&k| in_a[k].is_some() && in_b[k].is_some());
+        let (eo, ea, eb) = match next {
+            Some(k) => (k, in_a[k].unwrap(), in_b[k].unwrap()),
+            None => (o.len(), a.len(), b.len()),
+        };
+        let (so, sa, sb) = (&o[io..eo], &a[ia..ea], &b[ib..eb]);
+        let owned = |section: &[&str]| section.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        if sa == so || sa == sb {
+            lines.extend(owned(sb));
+        } else if sb == so {
+            lines.extend(owned(sa));
+        } else {
+            conflicts += 1;
+            lines.push(format!("<<<<<<< {}", ours_label));
+            lines.extend(owned(sa));
+            lines.push(format!("||||||| {}", base_label));
+            lines.extend(owned(so));
+            lines.push("=======".to_string());
+            lines.extend(owned(sb));
+            lines.push(format!(">>>>>>> {}", theirs_label));
+        }
+        if next.is_none() {
+            break;
+        }
+        io = eo;
+        ia = ea;
+        ib = eb;
+    }
+
+    let mut text = lines.join("\n");
+    if !text.is_empty() && (ours.ends_with('\n') || theirs.ends_with('\n')) {
+        text.push('\n');
+    }
+    Merged { text, conflicts }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        let old = "# Title\n\none\ntwo\nthree\nfour\nfive\nsix\nseven\n";
+        let new = "# Title\n\none\ntwo\n3\nfour\nfive\nsix\nseven\neight\n";
+        let diff = unified_diff(old, new, "a", "b");
+        assert_eq!(
+            diff.text,
+            "--- a\n+++ b\n@@ -2,8 +2,9 @@\n \n one\n two\n-three\n+3\n four\n five\n six\n seven\n+eight\n"
+        );
+        assert_eq!((diff.added, diff.removed), (2, 1));
+
+        assert_eq!(unified_diff(old, old, "a", "b"), UnifiedDiff::default());
+        let diff = unified_diff("", "new\n", "a", "b");
+        assert_eq!(diff.text, "--- a\n+++ b\n@@ -0,0 +1 @@\n+new\n");
+    }
+
+    #[test]
+    fn test_unified_diff_separate_hunks() {
+        let old: String = (1..=20).map(|n| format!("{}\n", n)).collect();
+        let new: String = (1..=20)
+            .map(|n| match n {
+                2 => "two\n".to_string(),
+                18 => "eighteen\n".to_string(),
+                n => format!("{}\n", n),
+            })
+            .collect();
+        let diff = unified_diff(&old, &new, "a", "b");
+        assert_eq!(diff.text.matches("@@ -").count(), 2);
+        assert!(diff.text.contains("@@ -1,5 +1,5 @@\n 1\n-2\n+two\n"));
+        assert!(diff.text.contains("@@ -15,6 +15,6 @@\n"));
+    }
+
+    #[test]
+    fn test_merge3_clean() {
+        let base = "intro\nalpha\nbeta\ngamma\n";
+        let ours = "intro\nALPHA\nbeta\ngamma\n";
+        let theirs = "intro\nalpha\nbeta\ngamma\ndelta\n";
+        let merged = merge3(base, ours, theirs, ["base", "ours", "theirs"]);
+        assert_eq!(merged.conflicts, 0);
+        assert_eq!(merged.text, "intro\nALPHA\nbeta\ngamma\ndelta\n");
+
+        // The same change on both sides is taken once
+        let merged = merge3(base, ours, ours, ["base", "ours", "theirs"]);
+        assert_eq!(merged.text, ours);
+    }
+
+    #[test]
+    fn test_merge3_conflict() {
+        let base = "intro\nalpha\nend\n";
+        let merged = merge3(
+            base,
+            "intro\nours\nend\n",
+            "intro\ntheirs\nend\n",
+            ["v1", "update", "v2"],
+        );
+        assert_eq!(merged.conflicts, 1);
+        assert_eq!(
+            merged.text,
+            "intro\n<<<<<<< update\nours\n||||||| v1\nalpha\n=======\ntheirs\n>>>>>>> v2\nend\n"
+        );
+    }
+}
@@ -0,0 +1,57 @@
+//! Entity IDs mentioned in free text.
+//!
+//! Commit messages and test names refer to tasks and bugs by ID. Both are
+//! scanned with [`scan_ids`] so they agree on what counts as an ID: `bn-`
+//! followed by four or more lowercase hex digits, not embedded in a longer
+//! word.
+
+/// IDs in `text`, normalized to `bn-xxxx`, in order of first appearance.
+///
+/// With `underscore`, `bn_xxxx` is accepted too, for identifiers that can't
+/// contain dashes (test function names).
+pub fn scan_ids(text: &str, underscore: bool) -> Vec<String> {
+    let bytes = text.as_bytes();
+    let mut ids = Vec::new();
+    let mut i = 0;
+    while i + 3 <= bytes.len() {
+        let starts_word = i == 0 || !bytes[i - 1].is_ascii_alphanumeric();
+        let separator = match bytes.get(i + 2) {
+            Some(b'-') => true,
+            Some(b'_') => underscore,
+            _ => false,
+        };
+        if starts_word && separator && bytes[i..].starts_with(b"bn") {
+            let hex_start = i + 3;
+            let hex_len = bytes[hex_start..]
+                .iter()
+                .take_while(|b| b.is_ascii_hexdigit() && !b.is_ascii_uppercase())
+                .count();
+            let end = hex_start + hex_len;
+            let ends_word = bytes.get(end).is_none_or(|b| !b.is_ascii_alphanumeric());
+            if hex_len >= 4 && ends_word {
+                let id = format!("bn-{}", &text[hex_start..end]);
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+                i = end;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_ids_underscore_separator() {
+        assert_eq!(
+            scan_ids("test_bn_a1b2 bn-c3d4", true),
+            vec!["bn-a1b2", "bn-c3d4"]
+        );
+        assert_eq!(scan_ids("test_bn_a1b2 bn-c3d4", false), vec!["bn-c3d4"]);
+    }
+}
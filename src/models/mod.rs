@@ -11,13 +11,19 @@
 //! - `Agent` - AI agent registration for lifecycle management
 //! - `Editor` - Attribution for document version editors (agent or user)
 //! - `complexity` - Heuristics for detecting complex task descriptions
+//! - `ids` - Entity IDs mentioned in commit messages and test names
 
+pub mod commit_trailers;
 pub mod complexity;
+pub mod doc_diff;
 pub mod graph;
+pub mod ids;
+pub mod test_report;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::fmt;
 
 /// Default empty string for serde deserialization.
@@ -351,6 +357,10 @@ pub struct Task {
     /// None for tasks created locally.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub imported_on: Option<DateTime<Utc>>,
+
+    /// Set when the task was force-closed past the test gate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_gate_override: Option<TestGateOverride>,
 }
 
 impl Task {
@@ -366,6 +376,7 @@ impl Task {
             closed_at: None,
             closed_reason: None,
             imported_on: None,
+            test_gate_override: None,
         }
     }
 }
@@ -443,6 +454,10 @@ pub struct Bug {
     /// Reason for closing
     #[serde(skip_serializing_if = "Option::is_none")]
     pub closed_reason: Option<String>,
+
+    /// Set when the bug was force-closed past the test gate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_gate_override: Option<TestGateOverride>,
 }
 
 impl Bug {
@@ -459,6 +474,7 @@ impl Bug {
             depends_on: Vec::new(),
             closed_at: None,
             closed_reason: None,
+            test_gate_override: None,
         }
     }
 }
@@ -1178,7 +1194,8 @@ pub struct TestNode {
     #[serde(default = "default_working_dir")]
     pub working_dir: String,
 
-    /// Optional pattern for matching test files
+    /// Comma-separated globs of the files this test covers, used by
+    /// `bn test run --affected` (e.g. `src/storage/**, *.sql`)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pattern: Option<String>,
 
@@ -1190,6 +1207,27 @@ pub struct TestNode {
     #[serde(default)]
     pub linked_bugs: Vec<String>,
 
+    /// Timeout in seconds, overriding `bn test run --timeout`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+
+    /// Environment variables set when running the command
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+
+    /// Quarantined as flaky: failures no longer reopen linked tasks
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub quarantined: bool,
+
+    /// Bug filed when the test was quarantined
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flaky_bug: Option<String>,
+
+    /// When the test was last released from quarantine; flakiness only
+    /// counts results after this
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quarantine_released_at: Option<DateTime<Utc>>,
+
     /// Creation timestamp
     #[serde(default = "default_timestamp")]
     pub created_at: DateTime<Utc>,
@@ -1211,9 +1249,76 @@ impl TestNode {
             pattern: None,
             linked_tasks: Vec::new(),
             linked_bugs: Vec::new(),
+            timeout_secs: None,
+            env: BTreeMap::new(),
+            quarantined: false,
+            flaky_bug: None,
+            quarantine_released_at: None,
             created_at: Utc::now(),
         }
     }
+
+    /// Whether `path` (relative to the repository root) matches any of the
+    /// test's pattern globs.
+    ///
+    /// `*` and `?` match within one path segment and `**` matches any number
+    /// of segments. A glob without a `/` matches the file name at any depth,
+    /// and a glob naming a directory covers everything beneath it.
+    pub fn matches_path(&self, path: &str) -> bool {
+        let Some(ref pattern) = self.pattern else {
+            return false;
+        };
+        let path: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        pattern
+            .split(',')
+            .map(str::trim)
+            .filter(|glob| !glob.is_empty())
+            .any(|glob| {
+                let glob: Vec<&str> = if glob.contains('/') {
+                    glob.split('/').filter(|s| !s.is_empty()).collect()
+                } else {
+                    vec!["**", glob]
+                };
+                glob_segments_match(&glob, &path)
+            })
+    }
+}
+
+/// Match glob segments against path segments; see [`TestNode::matches_path`].
+fn glob_segments_match(glob: &[&str], path: &[&str]) -> bool {
+    match glob.split_first() {
+        // Every segment matched: the path is this file or something beneath it
+        None => true,
+        Some((&"**", rest)) => (0..=path.len()).any(|i| glob_segments_match(rest, &path[i..])),
+        Some((segment, rest)) => path.split_first().is_some_and(|(name, tail)| {
+            let segment: Vec<char> = segment.chars().collect();
+            let name: Vec<char> = name.chars().collect();
+            glob_segment_matches(&segment, &name) && glob_segments_match(rest, tail)
+        }),
+    }
+}
+
+/// Match one glob segment (`*` and `?` wildcards) against a file name.
+fn glob_segment_matches(glob: &[char], name: &[char]) -> bool {
+    match glob.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|i| glob_segment_matches(rest, &name[i..])),
+        Some(('?', rest)) => !name.is_empty() && glob_segment_matches(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && glob_segment_matches(rest, &name[1..]),
+    }
+}
+
+/// A close that went past the test gate with `--force`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestGateOverride {
+    /// Why the entity was closed anyway (the close reason)
+    pub justification: String,
+
+    /// Linked tests that had not passed since the latest commit
+    pub tests: Vec<String>,
+
+    /// When the gate was overridden
+    pub at: DateTime<Utc>,
 }
 
 /// Result of a test run.
@@ -1239,11 +1344,55 @@ pub struct TestResult {
     /// Duration in milliseconds
     pub duration_ms: u64,
 
+    /// Whether the test was killed for running past its timeout
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub timed_out: bool,
+
+    /// Commit checked out when the test ran
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+
     /// Execution timestamp
     #[serde(default = "default_timestamp")]
     pub executed_at: DateTime<Utc>,
 }
 
+/// How often a test's outcome flips between reruns of the same commit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Flakiness {
+    /// Results that share a commit with at least one other result
+    pub reruns: usize,
+    /// Consecutive results on the same commit with different outcomes
+    pub flips: usize,
+    /// `flips` over the number of consecutive same-commit pairs (0.0 to 1.0)
+    pub score: f64,
+}
+
+impl Flakiness {
+    /// Score a test's results, oldest first. Results without a commit are
+    /// ignored, since a change in outcome across commits isn't flakiness.
+    pub fn from_results(results: &[TestResult]) -> Self {
+        let mut by_commit: BTreeMap<&str, Vec<bool>> = BTreeMap::new();
+        for result in results {
+            if let Some(commit) = result.commit.as_deref() {
+                by_commit.entry(commit).or_default().push(result.passed);
+            }
+        }
+
+        let mut flakiness = Self::default();
+        let mut pairs = 0;
+        for outcomes in by_commit.values().filter(|o| o.len() > 1) {
+            flakiness.reruns += outcomes.len();
+            pairs += outcomes.len() - 1;
+            flakiness.flips += outcomes.windows(2).filter(|w| w[0] != w[1]).count();
+        }
+        if pairs > 0 {
+            flakiness.score = flakiness.flips as f64 / pairs as f64;
+        }
+        flakiness
+    }
+}
+
 /// Association between a commit and a task or bug.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommitLink {
@@ -1299,6 +1448,330 @@ pub struct AgentHealth {
     pub stuck_task_ids: Vec<String>,
 }
 
+/// Model usage (tokens, requests, cost) reported by or for an agent.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AgentUsage {
+    /// Prompt tokens sent to the model
+    #[serde(default)]
+    pub input_tokens: u64,
+    /// Completion tokens returned by the model
+    #[serde(default)]
+    pub output_tokens: u64,
+    /// Number of model requests
+    #[serde(default)]
+    pub requests: u64,
+    /// Cost in US dollars
+    #[serde(default)]
+    pub cost_usd: f64,
+}
+
+impl AgentUsage {
+    /// Input plus output tokens.
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens.saturating_add(self.output_tokens)
+    }
+
+    /// Returns true if nothing has been reported.
+    pub fn is_empty(&self) -> bool {
+        self.input_tokens == 0
+            && self.output_tokens == 0
+            && self.requests == 0
+            && self.cost_usd == 0.0
+    }
+
+    /// Add another usage report to this one.
+    pub fn add(&mut self, other: &AgentUsage) {
+        self.input_tokens = self.input_tokens.saturating_add(other.input_tokens);
+        self.output_tokens = self.output_tokens.saturating_add(other.output_tokens);
+        self.requests = self.requests.saturating_add(other.requests);
+        self.cost_usd += other.cost_usd;
+    }
+
+    /// This usage split evenly `parts` ways (integer counts round down).
+    pub fn share(&self, parts: usize) -> AgentUsage {
+        let parts = parts.max(1) as u64;
+        AgentUsage {
+            input_tokens: self.input_tokens / parts,
+            output_tokens: self.output_tokens / parts,
+            requests: self.requests / parts,
+            cost_usd: self.cost_usd / parts as f64,
+        }
+    }
+}
+
+/// One usage report, appended to usage.jsonl.
+///
+/// The ledger outlives the agent registry, so budgets keep counting after
+/// agents say goodbye.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageRecord {
+    /// When the usage was reported
+    pub at: DateTime<Utc>,
+    /// Reporting agent ID
+    pub agent_id: String,
+    /// Reporting agent type
+    pub agent_type: AgentType,
+    /// Tasks and bugs the usage is attributed to, split evenly
+    #[serde(default)]
+    pub tasks: Vec<String>,
+    /// Reported usage
+    #[serde(flatten)]
+    pub usage: AgentUsage,
+}
+
+/// What produced a transcript line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptKind {
+    /// A line the agent wrote to stdout
+    Stdout,
+    /// A line the agent wrote to stderr
+    Stderr,
+    /// A bn command the agent ran (mirrored from the action log)
+    Command,
+}
+
+impl fmt::Display for TranscriptKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscriptKind::Stdout => write!(f, "stdout"),
+            TranscriptKind::Stderr => write!(f, "stderr"),
+            TranscriptKind::Command => write!(f, "command"),
+        }
+    }
+}
+
+/// One line of an agent transcript (`transcripts/<agent-id>.jsonl`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptEntry {
+    /// When the line was captured
+    pub at: DateTime<Utc>,
+    pub kind: TranscriptKind,
+    /// The output line, or the command name for command entries
+    pub text: String,
+    /// Command arguments (command entries only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub args: Option<serde_json::Value>,
+    /// Whether the command succeeded (command entries only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub success: Option<bool>,
+    /// Command duration in milliseconds (command entries only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+}
+
+impl TranscriptEntry {
+    /// A captured stdout or stderr line.
+    pub fn output(kind: TranscriptKind, text: impl Into<String>) -> Self {
+        Self {
+            at: Utc::now(),
+            kind,
+            text: text.into(),
+            args: None,
+            success: None,
+            duration_ms: None,
+        }
+    }
+}
+
+/// State of a merge queue entry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeQueueStatus {
+    /// Waiting for its turn
+    #[default]
+    Queued,
+    /// Being rebased and tested
+    Running,
+    /// Fast-forwarded into the target
+    Merged,
+    /// The rebase conflicted, tests failed or the target could not be updated
+    Failed,
+    /// Removed from the queue before it ran
+    Cancelled,
+}
+
+impl MergeQueueStatus {
+    /// Whether the entry is still waiting or being processed.
+    pub fn is_active(&self) -> bool {
+        matches!(self, MergeQueueStatus::Queued | MergeQueueStatus::Running)
+    }
+}
+
+impl fmt::Display for MergeQueueStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeQueueStatus::Queued => write!(f, "queued"),
+            MergeQueueStatus::Running => write!(f, "running"),
+            MergeQueueStatus::Merged => write!(f, "merged"),
+            MergeQueueStatus::Failed => write!(f, "failed"),
+            MergeQueueStatus::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+/// A task branch in the merge queue (`merge-queue.jsonl`).
+///
+/// Every state change appends the whole entry again; the last line for an
+/// ID wins.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MergeQueueEntry {
+    /// Entry ID (`bnmq-xxxx`)
+    pub id: String,
+    /// Task or bug the branch belongs to
+    pub task_id: String,
+    pub branch: String,
+    /// Branch to merge into
+    pub target: String,
+    #[serde(default)]
+    pub status: MergeQueueStatus,
+    pub enqueued_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Agent that enqueued the branch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<String>,
+    /// Target commit after merging
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// Number of linked tests that were run
+    #[serde(default)]
+    pub tests_run: usize,
+    /// Bug filed when the rebase conflicted or tests failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bug_id: Option<String>,
+    /// Why the entry failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl MergeQueueEntry {
+    /// A queued entry for a task branch.
+    pub fn new(id: String, task_id: &str, branch: &str, target: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            id,
+            task_id: task_id.to_string(),
+            branch: branch.to_string(),
+            target: target.to_string(),
+            status: MergeQueueStatus::Queued,
+            enqueued_at: now,
+            updated_at: now,
+            agent_id: None,
+            commit: None,
+            tests_run: 0,
+            bug_id: None,
+            message: None,
+        }
+    }
+}
+
+/// Recipient (and sender) name for messages to or from humans.
+pub const HUMAN_RECIPIENT: &str = "human";
+
+/// Kind of a [`Message`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageKind {
+    /// A plain message
+    #[default]
+    Message,
+    /// A question that expects a reply (`bn ask`)
+    Question,
+}
+
+impl fmt::Display for MessageKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageKind::Message => write!(f, "message"),
+            MessageKind::Question => write!(f, "question"),
+        }
+    }
+}
+
+/// A message between agents and humans (`messages.jsonl`).
+///
+/// Recipients are an agent ID, a role (an agent type such as `worker`) or
+/// [`HUMAN_RECIPIENT`]. Marking a message read appends a [`MessageReceipt`]
+/// rather than the whole message. A question is answered by a message whose
+/// `reply_to` is the question's ID.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    /// Message ID (`bnmsg-xxxx`)
+    pub id: String,
+    /// Entity type marker
+    #[serde(rename = "type", default = "message_entity_type")]
+    pub entity_type: String,
+    #[serde(default)]
+    pub kind: MessageKind,
+    /// Sender: an agent ID or `human`
+    pub from: String,
+    /// Recipient: an agent ID, a role or `human`
+    pub to: String,
+    pub body: String,
+    /// Message this one replies to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Readers that have seen the message in their inbox
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub read_by: Vec<String>,
+}
+
+fn message_entity_type() -> String {
+    "message".to_string()
+}
+
+impl Message {
+    /// A new unread message.
+    pub fn new(id: String, from: &str, to: &str, body: &str) -> Self {
+        Self {
+            id,
+            entity_type: message_entity_type(),
+            kind: MessageKind::Message,
+            from: from.to_string(),
+            to: to.to_string(),
+            body: body.to_string(),
+            reply_to: None,
+            created_at: Utc::now(),
+            read_by: Vec::new(),
+        }
+    }
+
+    /// Whether the message is addressed to `reader` directly or to its role.
+    pub fn is_for(&self, reader: &str, role: Option<&str>) -> bool {
+        self.to == reader || role.is_some_and(|r| self.to == r)
+    }
+}
+
+/// A reader having seen a message, appended to `messages.jsonl` after it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageReceipt {
+    /// ID of the message that was read
+    pub id: String,
+    /// Entity type marker
+    #[serde(rename = "type", default = "message_receipt_entity_type")]
+    pub entity_type: String,
+    /// Agent ID or `human`
+    pub reader: String,
+    pub read_at: DateTime<Utc>,
+}
+
+fn message_receipt_entity_type() -> String {
+    "message_receipt".to_string()
+}
+
+impl MessageReceipt {
+    /// A receipt for `reader` reading message `id` now.
+    pub fn new(id: &str, reader: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            entity_type: message_receipt_entity_type(),
+            reader: reader.to_string(),
+            read_at: Utc::now(),
+        }
+    }
+}
+
 /// An AI agent registered with Binnacle for lifecycle management.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Agent {
@@ -1376,6 +1849,15 @@ pub struct Agent {
     /// Enables reanimating dormant/failed agents by identifying their Copilot session.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub copilot_session_guid: Option<String>,
+
+    /// Model usage reported for this agent session (`bn agent usage report`)
+    #[serde(default, skip_serializing_if = "AgentUsage::is_empty")]
+    pub usage: AgentUsage,
+
+    /// Transcript file capturing the agent's output and bn commands
+    /// (`bn agent transcript`). Set when the agent is spawned by binnacle.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transcript_path: Option<String>,
 }
 
 fn agent_entity_type() -> String {
@@ -1422,6 +1904,8 @@ impl Agent {
             goodbye_at: None,
             container_id: None,
             copilot_session_guid: None,
+            usage: AgentUsage::default(),
+            transcript_path: None,
         }
     }
 
@@ -1453,6 +1937,8 @@ impl Agent {
             goodbye_at: None,
             container_id: None,
             copilot_session_guid: None,
+            usage: AgentUsage::default(),
+            transcript_path: None,
         }
     }
 
@@ -1483,6 +1969,8 @@ impl Agent {
             goodbye_at: None,
             container_id: None,
             copilot_session_guid: None,
+            usage: AgentUsage::default(),
+            transcript_path: None,
         }
     }
 
@@ -1514,6 +2002,8 @@ impl Agent {
             goodbye_at: None,
             container_id: None,
             copilot_session_guid: None,
+            usage: AgentUsage::default(),
+            transcript_path: None,
         }
     }
 
@@ -2629,6 +3119,11 @@ mod tests {
         task.closed_at = Some(chrono::Utc::now());
         task.closed_reason = Some("done".to_string());
         task.imported_on = Some(chrono::Utc::now());
+        task.test_gate_override = Some(super::TestGateOverride {
+            justification: "known failure".to_string(),
+            tests: vec!["bnt-test".to_string()],
+            at: chrono::Utc::now(),
+        });
 
         let json = serde_json::to_string(&task).unwrap();
         let keys = extract_json_keys(&json);
@@ -2636,7 +3131,7 @@ mod tests {
 
         // Expected schema fingerprint for Task
         // If this fails, you've changed the Task schema - see comment above!
-        let expected = "assignee|closed_at|closed_reason|created_at|depends_on|description|id|imported_on|parent|priority|short_name|status|tags|title|type|updated_at";
+        let expected = "assignee|closed_at|closed_reason|created_at|depends_on|description|id|imported_on|parent|priority|short_name|status|tags|test_gate_override|test_gate_override.at|test_gate_override.justification|test_gate_override.tests|title|type|updated_at";
         assert_eq!(
             fp, expected,
             "Task schema changed! Update expected fingerprint if intentional."
@@ -2655,12 +3150,17 @@ mod tests {
         bug.depends_on = vec!["bn-dep".to_string()];
         bug.closed_at = Some(chrono::Utc::now());
         bug.closed_reason = Some("fixed".to_string());
+        bug.test_gate_override = Some(super::TestGateOverride {
+            justification: "known failure".to_string(),
+            tests: vec!["bnt-test".to_string()],
+            at: chrono::Utc::now(),
+        });
 
         let json = serde_json::to_string(&bug).unwrap();
         let keys = extract_json_keys(&json);
         let fp = fingerprint(&keys);
 
-        let expected = "affected_component|assignee|closed_at|closed_reason|created_at|depends_on|description|id|priority|reproduction_steps|severity|short_name|status|tags|title|type|updated_at";
+        let expected = "affected_component|assignee|closed_at|closed_reason|created_at|depends_on|description|id|priority|reproduction_steps|severity|short_name|status|tags|test_gate_override|test_gate_override.at|test_gate_override.justification|test_gate_override.tests|title|type|updated_at";
         assert_eq!(
             fp, expected,
             "Bug schema changed! Update expected fingerprint if intentional."
@@ -2720,13 +3220,17 @@ mod tests {
         test_node.pattern = Some("test_*".to_string());
         test_node.linked_tasks = vec!["bn-task".to_string()];
         test_node.linked_bugs = vec!["bn-bug".to_string()];
+        test_node.timeout_secs = Some(60);
+        test_node.env = BTreeMap::from([("KEY".to_string(), "value".to_string())]);
+        test_node.quarantined = true;
+        test_node.flaky_bug = Some("bn-flaky".to_string());
+        test_node.quarantine_released_at = Some(chrono::Utc::now());
 
         let json = serde_json::to_string(&test_node).unwrap();
         let keys = extract_json_keys(&json);
         let fp = fingerprint(&keys);
 
-        let expected =
-            "command|created_at|id|linked_bugs|linked_tasks|name|pattern|type|working_dir";
+        let expected = "command|created_at|env|env.KEY|flaky_bug|id|linked_bugs|linked_tasks|name|pattern|quarantine_released_at|quarantined|timeout_secs|type|working_dir";
         assert_eq!(
             fp, expected,
             "TestNode schema changed! Update expected fingerprint if intentional."
@@ -2742,6 +3246,8 @@ mod tests {
             stdout: Some("output".to_string()),
             stderr: Some("errors".to_string()),
             duration_ms: 100,
+            timed_out: true,
+            commit: Some("abc1234".to_string()),
             executed_at: chrono::Utc::now(),
         };
 
@@ -2749,13 +3255,69 @@ mod tests {
         let keys = extract_json_keys(&json);
         let fp = fingerprint(&keys);
 
-        let expected = "duration_ms|executed_at|exit_code|passed|stderr|stdout|test_id";
+        let expected =
+            "commit|duration_ms|executed_at|exit_code|passed|stderr|stdout|test_id|timed_out";
         assert_eq!(
             fp, expected,
             "TestResult schema changed! Update expected fingerprint if intentional."
         );
     }
 
+    #[test]
+    fn test_flakiness_counts_same_commit_flips() {
+        let result = |commit: Option<&str>, passed: bool| super::TestResult {
+            test_id: "bnt-test".to_string(),
+            passed,
+            exit_code: if passed { 0 } else { 1 },
+            stdout: None,
+            stderr: None,
+            duration_ms: 1,
+            timed_out: false,
+            commit: commit.map(str::to_string),
+            executed_at: chrono::Utc::now(),
+        };
+
+        // Outcome changes across commits and unknown commits don't count
+        let steady = [
+            result(Some("a"), false),
+            result(Some("b"), true),
+            result(None, false),
+            result(None, true),
+        ];
+        assert_eq!(super::Flakiness::from_results(&steady).flips, 0);
+        assert_eq!(super::Flakiness::from_results(&steady).score, 0.0);
+
+        let flaky = [
+            result(Some("a"), true),
+            result(Some("a"), false),
+            result(Some("a"), true),
+            result(Some("b"), true),
+            result(Some("b"), true),
+        ];
+        let flakiness = super::Flakiness::from_results(&flaky);
+        assert_eq!(flakiness.reruns, 5);
+        assert_eq!(flakiness.flips, 2);
+        assert!((flakiness.score - 2.0 / 3.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_test_node_pattern_globs() {
+        let mut test = super::TestNode::new("bnt-0001".into(), "t".into(), "true".into());
+        assert!(!test.matches_path("src/lib.rs"));
+
+        test.pattern = Some("src/storage/**/*.rs, *.sql, docs, tests/cli_?_test.rs".into());
+        assert!(test.matches_path("src/storage/mod.rs"));
+        assert!(test.matches_path("src/storage/cache/index.rs"));
+        assert!(!test.matches_path("src/storage.rs"));
+        assert!(test.matches_path("schema.sql"));
+        assert!(test.matches_path("db/migrations/001.sql"));
+        assert!(test.matches_path("docs/guide/intro.md"));
+        assert!(!test.matches_path("src/docs.rs"));
+        assert!(test.matches_path("tests/cli_a_test.rs"));
+        assert!(!test.matches_path("tests/cli_ab_test.rs"));
+        assert!(!test.matches_path("src/models/mod.rs"));
+    }
+
     #[test]
     fn test_schema_fingerprint_agent() {
         let mut agent = super::Agent::new_with_purpose(
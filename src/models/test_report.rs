@@ -0,0 +1,512 @@
+//! Parsers for test reports produced outside binnacle.
+//!
+//! `bn test import` reads the results of CI or `cargo test` runs and records
+//! them against test nodes. Each supported format is parsed into a flat list
+//! of [`ReportCase`]s:
+//!
+//! - **JUnit XML**: `<testcase>` elements, with `<failure>`/`<error>` and
+//!   `<skipped>` children. Cases are named `classname.name`.
+//! - **TAP**: `ok`/`not ok` lines, with `# SKIP`/`# TODO` directives and an
+//!   optional YAML diagnostic block (`message`, `duration_ms`).
+//! - **cargo JSON**: the libtest JSON stream from
+//!   `cargo test -- -Z unstable-options --format json --report-time`.
+//!
+//! Case names can carry task or bug IDs (`[bn-a1b2]`, or `bn_a1b2` where
+//! identifiers can't contain dashes); see [`tagged_ids`].
+
+use quick_xml::events::{BytesRef, BytesStart, Event};
+use quick_xml::reader::Reader;
+use serde::Serialize;
+
+/// A supported test report format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReportFormat {
+    Junit,
+    Tap,
+    CargoJson,
+}
+
+impl ReportFormat {
+    /// The format's name, as accepted by `--format`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportFormat::Junit => "junit",
+            ReportFormat::Tap => "tap",
+            ReportFormat::CargoJson => "cargo-json",
+        }
+    }
+}
+
+impl std::str::FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "junit" => Ok(ReportFormat::Junit),
+            "tap" => Ok(ReportFormat::Tap),
+            "cargo-json" => Ok(ReportFormat::CargoJson),
+            _ => Err(format!(
+                "unknown report format '{}' (use junit, tap or cargo-json)",
+                s
+            )),
+        }
+    }
+}
+
+/// How a reported test case ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaseOutcome {
+    Passed,
+    Failed,
+    Skipped,
+}
+
+/// A single test case from a report.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportCase {
+    /// Case name, qualified by its class or module where the format has one
+    pub name: String,
+    pub outcome: CaseOutcome,
+    pub duration_ms: u64,
+    /// Failure message (and details, where the format has them)
+    pub message: Option<String>,
+    /// Output captured while the case ran
+    pub output: Option<String>,
+}
+
+impl ReportCase {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            outcome: CaseOutcome::Passed,
+            duration_ms: 0,
+            message: None,
+            output: None,
+        }
+    }
+}
+
+/// Parse a report in `format`.
+pub fn parse_report(format: ReportFormat, content: &str) -> Result<Vec<ReportCase>, String> {
+    match format {
+        ReportFormat::Junit => parse_junit(content),
+        ReportFormat::Tap => parse_tap(content),
+        ReportFormat::CargoJson => parse_cargo_json(content),
+    }
+}
+
+/// Task or bug IDs tagged in a case name: `bn-xxxx` or `bn_xxxx` (four or
+/// more hex digits) not embedded in a longer word.
+pub fn tagged_ids(name: &str) -> Vec<String> {
+    super::ids::scan_ids(name, true)
+}
+
+fn non_empty(text: String) -> Option<String> {
+    let trimmed = text.trim();
+    (!trimmed.is_empty()).then(|| trimmed.to_string())
+}
+
+fn secs_to_ms(secs: &str) -> u64 {
+    secs.trim()
+        .parse::<f64>()
+        .map(|s| (s * 1000.0).round().max(0.0) as u64)
+        .unwrap_or(0)
+}
+
+// === JUnit XML ===
+
+/// Attributes of an element as unescaped `(name, value)` pairs.
+fn xml_attrs(element: &BytesStart) -> Result<Vec<(String, String)>, String> {
+    element
+        .attributes()
+        .map(|a| {
+            let a = a.map_err(|e| e.to_string())?;
+            let value = a.unescape_value().map_err(|e| e.to_string())?;
+            Ok((
+                String::from_utf8_lossy(a.key.as_ref()).into_owned(),
+                value.into_owned(),
+            ))
+        })
+        .collect()
+}
+
+/// Text of an entity reference such as `&amp;` or `&#x41;`.
+fn xml_entity(reference: &BytesRef) -> Result<String, String> {
+    if let Some(c) = reference.resolve_char_ref().map_err(|e| e.to_string())? {
+        return Ok(c.to_string());
+    }
+    let name = reference.decode().map_err(|e| e.to_string())?;
+    quick_xml::escape::resolve_predefined_entity(&name)
+        .map(str::to_string)
+        .ok_or_else(|| format!("unknown entity '&{};'", name))
+}
+
+fn attr<'a>(attrs: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    attrs
+        .iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, v)| v.as_str())
+}
+
+fn parse_junit(content: &str) -> Result<Vec<ReportCase>, String> {
+    /// Where text inside a test case goes.
+    enum Capture {
+        None,
+        Message,
+        Output,
+    }
+
+    let mut cases = Vec::new();
+    let mut current: Option<ReportCase> = None;
+    let mut capture = Capture::None;
+    let mut message = String::new();
+    let mut output = String::new();
+
+    let mut saw_suite = false;
+
+    let mut reader = Reader::from_str(content);
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("at byte {}: {}", reader.error_position(), e))?;
+        let text = match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let empty = matches!(event, Event::Empty(_));
+                let attrs = xml_attrs(e)?;
+                match e.name().as_ref() {
+                    b"testsuite" | b"testsuites" => saw_suite = true,
+                    b"testcase" => {
+                        let case_name = attr(&attrs, "name").unwrap_or_default();
+                        let full_name = match attr(&attrs, "classname").filter(|c| !c.is_empty()) {
+                            Some(class) => format!("{}.{}", class, case_name),
+                            None => case_name.to_string(),
+                        };
+                        let mut case = ReportCase::new(full_name);
+                        case.duration_ms = attr(&attrs, "time").map(secs_to_ms).unwrap_or(0);
+                        message.clear();
+                        output.clear();
+                        if empty {
+                            cases.push(case);
+                        } else {
+                            current = Some(case);
+                        }
+                    }
+                    name => {
+                        let Some(case) = current.as_mut() else {
+                            continue;
+                        };
+                        match name {
+                            b"failure" | b"error" => {
+                                case.outcome = CaseOutcome::Failed;
+                                if let Some(m) = attr(&attrs, "message") {
+                                    message.push_str(m);
+                                    message.push('\n');
+                                }
+                                if !empty {
+                                    capture = Capture::Message;
+                                }
+                            }
+                            b"skipped" if case.outcome == CaseOutcome::Passed => {
+                                case.outcome = CaseOutcome::Skipped;
+                            }
+                            b"system-out" | b"system-err" if !empty => capture = Capture::Output,
+                            _ => {}
+                        }
+                    }
+                }
+                continue;
+            }
+            Event::End(e) => {
+                if e.name().as_ref() == b"testcase"
+                    && let Some(mut case) = current.take()
+                {
+                    case.message = non_empty(std::mem::take(&mut message));
+                    case.output = non_empty(std::mem::take(&mut output));
+                    cases.push(case);
+                }
+                capture = Capture::None;
+                continue;
+            }
+            Event::Text(t) => t.decode().map_err(|e| e.to_string())?.into_owned(),
+            Event::CData(t) => t.decode().map_err(|e| e.to_string())?.into_owned(),
+            Event::GeneralRef(r) => xml_entity(&r)?,
+            Event::Eof => break,
+            _ => continue,
+        };
+        match capture {
+            Capture::Message => message.push_str(&text),
+            Capture::Output => output.push_str(&text),
+            Capture::None => {}
+        }
+    }
+
+    if current.is_some() {
+        return Err("unterminated <testcase> element".to_string());
+    }
+    if !saw_suite && cases.is_empty() {
+        return Err("no <testsuite> or <testcase> elements found".to_string());
+    }
+    Ok(cases)
+}
+
+// === TAP ===
+
+fn parse_tap(content: &str) -> Result<Vec<ReportCase>, String> {
+    let mut cases: Vec<ReportCase> = Vec::new();
+    let mut in_yaml = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if in_yaml {
+            if trimmed == "..." {
+                in_yaml = false;
+            } else if let Some(case) = cases.last_mut()
+                && let Some((key, value)) = trimmed.split_once(':')
+            {
+                let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+                match key.trim() {
+                    "message" if !value.is_empty() => case.message = Some(value.to_string()),
+                    "duration_ms" => {
+                        case.duration_ms = value.parse::<f64>().map(|d| d as u64).unwrap_or(0)
+                    }
+                    _ => {}
+                }
+            }
+            continue;
+        }
+        if trimmed == "---" && !cases.is_empty() {
+            in_yaml = true;
+            continue;
+        }
+        if let Some(reason) = trimmed.strip_prefix("Bail out!") {
+            return Err(format!("TAP run bailed out:{}", reason));
+        }
+
+        // Subtest lines are indented; only the top level counts
+        let (passed, rest) = if let Some(rest) = line.strip_prefix("not ok") {
+            (false, rest)
+        } else if let Some(rest) = line.strip_prefix("ok") {
+            (true, rest)
+        } else {
+            continue;
+        };
+        let rest = rest.trim_start_matches(|c: char| c.is_ascii_digit() || c.is_whitespace());
+        let rest = rest.strip_prefix('-').unwrap_or(rest).trim();
+        let (description, directive) = match rest.split_once(" # ") {
+            Some((d, dir)) => (d.trim(), Some(dir.trim().to_uppercase())),
+            None => match rest.strip_prefix('#') {
+                Some(dir) => ("", Some(dir.trim().to_uppercase())),
+                None => (rest, None),
+            },
+        };
+
+        let name = if description.is_empty() {
+            format!("test {}", cases.len() + 1)
+        } else {
+            description.to_string()
+        };
+        let mut case = ReportCase::new(name);
+        case.outcome = match directive.as_deref() {
+            // TODO failures are expected, so they don't count as failures
+            Some(d) if d.starts_with("SKIP") || d.starts_with("TODO") => CaseOutcome::Skipped,
+            _ if passed => CaseOutcome::Passed,
+            _ => CaseOutcome::Failed,
+        };
+        cases.push(case);
+    }
+    Ok(cases)
+}
+
+// === cargo test JSON ===
+
+fn parse_cargo_json(content: &str) -> Result<Vec<ReportCase>, String> {
+    let mut cases = Vec::new();
+    let mut saw_event = false;
+
+    for line in content.lines() {
+        // cargo interleaves its own progress lines with the JSON stream
+        let Ok(event) = serde_json::from_str::<serde_json::Value>(line.trim()) else {
+            continue;
+        };
+        saw_event = true;
+        if event["type"] != "test" {
+            continue;
+        }
+        let outcome = match event["event"].as_str() {
+            Some("ok") => CaseOutcome::Passed,
+            Some("failed") => CaseOutcome::Failed,
+            Some("ignored") => CaseOutcome::Skipped,
+            _ => continue,
+        };
+        let Some(name) = event["name"].as_str() else {
+            continue;
+        };
+        let mut case = ReportCase::new(name.to_string());
+        case.outcome = outcome;
+        case.duration_ms = event["exec_time"]
+            .as_f64()
+            .map(|s| (s * 1000.0).round() as u64)
+            .unwrap_or(0);
+        let stdout = event["stdout"].as_str().unwrap_or_default().to_string();
+        if outcome == CaseOutcome::Failed {
+            case.message = event["message"]
+                .as_str()
+                .map(str::to_string)
+                .or_else(|| non_empty(stdout.clone()));
+        }
+        case.output = non_empty(stdout);
+        cases.push(case);
+    }
+
+    if !saw_event && !content.trim().is_empty() {
+        return Err("no libtest JSON events found".to_string());
+    }
+    Ok(cases)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_junit() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- generated -->
+<testsuites>
+  <testsuite name="auth" tests="4">
+    <testcase classname="auth.Login" name="accepts valid" time="0.25"/>
+    <testcase classname="auth.Login" name="rejects &quot;bad&quot;" time="1.5">
+      <failure message="expected 401 > 200" type="AssertionError"><![CDATA[at login.js:12
+  <stack>]]></failure>
+      <system-out>request sent</system-out>
+    </testcase>
+    <testcase name="crashes">
+      <error message="boom"/>
+    </testcase>
+    <testcase classname="auth" name="later"><skipped/></testcase>
+  </testsuite>
+</testsuites>"#;
+        let cases = parse_report(ReportFormat::Junit, xml).unwrap();
+        assert_eq!(cases.len(), 4);
+        assert_eq!(cases[0].name, "auth.Login.accepts valid");
+        assert_eq!(cases[0].outcome, CaseOutcome::Passed);
+        assert_eq!(cases[0].duration_ms, 250);
+
+        assert_eq!(cases[1].name, "auth.Login.rejects \"bad\"");
+        assert_eq!(cases[1].outcome, CaseOutcome::Failed);
+        assert_eq!(cases[1].duration_ms, 1500);
+        assert_eq!(
+            cases[1].message.as_deref(),
+            Some("expected 401 > 200\nat login.js:12\n  <stack>")
+        );
+        assert_eq!(cases[1].output.as_deref(), Some("request sent"));
+
+        assert_eq!(cases[2].name, "crashes");
+        assert_eq!(cases[2].outcome, CaseOutcome::Failed);
+        assert_eq!(cases[2].message.as_deref(), Some("boom"));
+        assert_eq!(cases[3].outcome, CaseOutcome::Skipped);
+
+        assert!(parse_report(ReportFormat::Junit, "<testcase name=\"x\">").is_err());
+        assert!(parse_report(ReportFormat::Junit, "<testcase name=x/>").is_err());
+        assert!(parse_report(ReportFormat::Junit, "ok 1 - not xml").is_err());
+        assert_eq!(
+            parse_report(ReportFormat::Junit, "<testsuites/>"),
+            Ok(vec![])
+        );
+    }
+
+    #[test]
+    fn test_parse_junit_entities_and_malformed() {
+        let xml = r#"<testsuite><testcase name="a&amp;b">
+  <failure>1 &lt; 2 &#x2713; &#65;</failure>
+</testcase></testsuite>"#;
+        let cases = parse_report(ReportFormat::Junit, xml).unwrap();
+        assert_eq!(cases[0].name, "a&b");
+        assert_eq!(cases[0].message.as_deref(), Some("1 < 2 \u{2713} A"));
+
+        assert!(parse_report(ReportFormat::Junit, "<testsuite></testcase>").is_err());
+        assert!(
+            parse_report(
+                ReportFormat::Junit,
+                "<testsuite><testcase name=\"x\"><failure>&bogus;</failure></testcase></testsuite>"
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_tap() {
+        let tap = "TAP version 13
+1..5
+ok 1 - adds numbers
+not ok 2 - divides by zero
+  ---
+  message: 'expected Infinity'
+  duration_ms: 12
+  ...
+ok 3 - network # SKIP offline
+not ok 4 - flaky thing # TODO fix later
+ok 5
+# tests 5
+";
+        let cases = parse_report(ReportFormat::Tap, tap).unwrap();
+        assert_eq!(cases.len(), 5);
+        assert_eq!(cases[0].name, "adds numbers");
+        assert_eq!(cases[0].outcome, CaseOutcome::Passed);
+        assert_eq!(cases[1].outcome, CaseOutcome::Failed);
+        assert_eq!(cases[1].message.as_deref(), Some("expected Infinity"));
+        assert_eq!(cases[1].duration_ms, 12);
+        assert_eq!(cases[2].outcome, CaseOutcome::Skipped);
+        assert_eq!(cases[3].outcome, CaseOutcome::Skipped);
+        assert_eq!(cases[4].name, "test 5");
+
+        assert!(parse_report(ReportFormat::Tap, "1..2\nBail out! no db").is_err());
+    }
+
+    #[test]
+    fn test_parse_cargo_json() {
+        let json = r#"    Running unittests src/lib.rs
+{ "type": "suite", "event": "started", "test_count": 3 }
+{ "type": "test", "event": "started", "name": "math::adds" }
+{ "type": "test", "name": "math::adds", "event": "ok", "exec_time": 0.002 }
+{ "type": "test", "name": "math::divides", "event": "failed", "exec_time": 0.1, "stdout": "thread panicked at 'attempt to divide by zero'\n" }
+{ "type": "test", "name": "math::slow", "event": "ignored" }
+{ "type": "suite", "event": "failed", "passed": 1, "failed": 1, "ignored": 1 }
+"#;
+        let cases = parse_report(ReportFormat::CargoJson, json).unwrap();
+        assert_eq!(cases.len(), 3);
+        assert_eq!(cases[0].name, "math::adds");
+        assert_eq!(cases[0].duration_ms, 2);
+        assert_eq!(cases[1].outcome, CaseOutcome::Failed);
+        assert_eq!(
+            cases[1].message.as_deref(),
+            Some("thread panicked at 'attempt to divide by zero'")
+        );
+        assert_eq!(cases[2].outcome, CaseOutcome::Skipped);
+
+        assert!(parse_report(ReportFormat::CargoJson, "test result: ok").is_err());
+    }
+
+    #[test]
+    fn test_tagged_ids() {
+        assert_eq!(tagged_ids("login works [bn-a1b2]"), vec!["bn-a1b2"]);
+        assert_eq!(
+            tagged_ids("auth::test_login_bn_a1b2_and_bn-c3d4e5"),
+            vec!["bn-a1b2", "bn-c3d4e5"]
+        );
+        assert!(tagged_ids("test_bn_task").is_empty());
+        assert!(tagged_ids("xbn-a1b2").is_empty());
+        assert!(tagged_ids("bn-a1").is_empty());
+    }
+
+    #[test]
+    fn test_format_from_str() {
+        assert_eq!(
+            "cargo-json".parse::<ReportFormat>(),
+            Ok(ReportFormat::CargoJson)
+        );
+        assert!("xml".parse::<ReportFormat>().is_err());
+    }
+}
@@ -27,8 +27,9 @@ use crate::config::{BinnacleConfig, BinnacleState};
 use crate::config::{CONFIG_FILE_MODE, STATE_FILE_MODE};
 use crate::models::{
     Agent, AgentStatus, Bug, CommitLink, Doc, DocType, Edge, EdgeDirection, EdgeType, HydratedEdge,
-    Idea, IdeaStatus, Issue, LogAnnotation, Milestone, MilestoneProgress, Mission, MissionProgress,
-    Queue, Task, TaskStatus, TestNode, TestResult,
+    Idea, IdeaStatus, Issue, LogAnnotation, MergeQueueEntry, Message, MessageReceipt, Milestone,
+    MilestoneProgress, Mission, MissionProgress, Queue, Task, TaskStatus, TestNode, TestResult,
+    TranscriptEntry, UsageRecord,
 };
 use crate::{Error, Result};
 use chrono::Utc;
@@ -38,7 +39,7 @@ use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::cell::RefCell;
 use std::fs::{self, File, OpenOptions};
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 
 // Thread-local data directory override for test isolation.
@@ -136,8 +137,9 @@ impl Storage {
         Self::open_at_root(root)
     }
 
-    /// Internal: open storage at a specific root directory.
-    fn open_at_root(root: PathBuf) -> Result<Self> {
+    /// Open storage at an explicit root directory rather than one derived
+    /// from a repository path.
+    pub fn open_at_root(root: PathBuf) -> Result<Self> {
         // Defense-in-depth: verify we're not accessing production paths in test mode
         check_test_mode_write_protection(&root)?;
 
@@ -189,8 +191,9 @@ impl Storage {
         Ok(storage)
     }
 
-    /// Internal: initialize storage at a specific root directory.
-    fn init_at_root(root: PathBuf) -> Result<Self> {
+    /// Initialize storage at an explicit root directory rather than one
+    /// derived from a repository path.
+    pub fn init_at_root(root: PathBuf) -> Result<Self> {
         // Defense-in-depth: verify we're not accessing production paths in test mode
         check_test_mode_write_protection(&root)?;
 
@@ -211,6 +214,9 @@ impl Storage {
             "commits.jsonl",
             "test-results.jsonl",
             "agents.jsonl",
+            "usage.jsonl",
+            "merge-queue.jsonl",
+            "messages.jsonl",
         ];
         for file in files {
             let path = root.join(file);
@@ -479,6 +485,8 @@ impl Storage {
                 stderr TEXT,
                 duration_ms INTEGER NOT NULL,
                 executed_at TEXT NOT NULL,
+                timed_out INTEGER NOT NULL DEFAULT 0,
+                commit_sha TEXT,
                 FOREIGN KEY (test_id) REFERENCES tests(id) ON DELETE CASCADE
             );
 
@@ -695,6 +703,35 @@ impl Storage {
             conn.execute("ALTER TABLE docs ADD COLUMN supersedes TEXT", [])?;
         }
 
+        // Migration: Add timed_out column to test_results if it doesn't exist
+        let has_timed_out: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('test_results') WHERE name = 'timed_out'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap_or(false);
+
+        if !has_timed_out {
+            conn.execute(
+                "ALTER TABLE test_results ADD COLUMN timed_out INTEGER NOT NULL DEFAULT 0",
+                [],
+            )?;
+        }
+
+        // Migration: Add commit_sha column to test_results if it doesn't exist
+        let has_commit_sha: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('test_results') WHERE name = 'commit_sha'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap_or(false);
+
+        if !has_commit_sha {
+            conn.execute("ALTER TABLE test_results ADD COLUMN commit_sha TEXT", [])?;
+        }
+
         // Migration: Add mcp_session_id column to agents table if it doesn't exist
         let has_mcp_session_id: bool = conn
             .query_row(
@@ -808,6 +845,20 @@ impl Storage {
             )?;
         }
 
+        // Migration: Add token_id column to action_logs (GUI token audit trail)
+        let has_token_id: bool = conn
+            .query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('action_logs') WHERE name='token_id'",
+                [],
+                |row| row.get::<_, i64>(0),
+            )
+            .unwrap_or(0)
+            > 0;
+
+        if !has_token_id {
+            conn.execute("ALTER TABLE action_logs ADD COLUMN token_id TEXT", [])?;
+        }
+
         // Migration: Add actor and actor_type columns to log_annotations
         let has_log_annotation_actor: bool = conn
             .query_row(
@@ -934,12 +985,16 @@ impl Storage {
             DELETE FROM bug_dependencies;
             DELETE FROM bug_tags;
             DELETE FROM bugs;
+            DELETE FROM issue_tags;
+            DELETE FROM issues;
             DELETE FROM idea_tags;
             DELETE FROM ideas;
             DELETE FROM doc_tags;
             DELETE FROM docs;
             DELETE FROM milestone_tags;
             DELETE FROM milestones;
+            DELETE FROM mission_tags;
+            DELETE FROM missions;
             DELETE FROM test_links;
             DELETE FROM test_bug_links;
             DELETE FROM tests;
@@ -1006,6 +1061,25 @@ impl Storage {
             }
         }
 
+        // Re-read issues from issues.jsonl
+        let issues_path = self.root.join("issues.jsonl");
+        if issues_path.exists() {
+            let file = File::open(&issues_path)?;
+            let reader = BufReader::new(file);
+
+            for line in reader.lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                if let Ok(issue) = serde_json::from_str::<Issue>(&line)
+                    && issue.core.entity_type == "issue"
+                {
+                    self.cache_issue(&issue)?;
+                }
+            }
+        }
+
         // Re-read ideas from ideas.jsonl
         let ideas_path = self.root.join("ideas.jsonl");
         if ideas_path.exists() {
@@ -1147,13 +1221,20 @@ impl Storage {
 
     /// Cache a task in SQLite for fast querying.
     fn cache_task(&self, task: &Task) -> Result<()> {
-        // Insert or replace task
+        // Upsert task (REPLACE would delete the row and cascade to test_links)
         self.conn.execute(
             r#"
-            INSERT OR REPLACE INTO tasks
+            INSERT INTO tasks
             (id, title, short_name, description, priority, status, parent, assignee,
              created_at, updated_at, closed_at, closed_reason)
             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+            ON CONFLICT(id) DO UPDATE SET
+                title = excluded.title, short_name = excluded.short_name,
+                description = excluded.description, priority = excluded.priority,
+                status = excluded.status, parent = excluded.parent,
+                assignee = excluded.assignee, created_at = excluded.created_at,
+                updated_at = excluded.updated_at, closed_at = excluded.closed_at,
+                closed_reason = excluded.closed_reason
             "#,
             params![
                 task.core.id,
@@ -1198,12 +1279,20 @@ impl Storage {
 
     /// Cache a bug in SQLite for fast querying.
     fn cache_bug(&self, bug: &Bug) -> Result<()> {
+        // Upsert bug (REPLACE would delete the row and cascade to test_bug_links)
         self.conn.execute(
             r#"
-            INSERT OR REPLACE INTO bugs
+            INSERT INTO bugs
             (id, title, description, priority, status, severity, reproduction_steps,
              affected_component, assignee, created_at, updated_at, closed_at, closed_reason)
             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+            ON CONFLICT(id) DO UPDATE SET
+                title = excluded.title, description = excluded.description,
+                priority = excluded.priority, status = excluded.status,
+                severity = excluded.severity, reproduction_steps = excluded.reproduction_steps,
+                affected_component = excluded.affected_component, assignee = excluded.assignee,
+                created_at = excluded.created_at, updated_at = excluded.updated_at,
+                closed_at = excluded.closed_at, closed_reason = excluded.closed_reason
             "#,
             params![
                 bug.core.id,
@@ -2113,17 +2202,35 @@ impl Storage {
 
     /// Find the document that supersedes the given document ID.
     /// Returns None if no document supersedes this one (i.e., this is the latest version).
+    /// If the chain forked, the most recently created successor is returned.
     pub fn get_doc_superseded_by(&self, id: &str) -> Result<Option<String>> {
         let superseding_id: Option<String> = self
             .conn
-            .query_row("SELECT id FROM docs WHERE supersedes = ?", [id], |row| {
-                row.get(0)
-            })
+            .query_row(
+                "SELECT id FROM docs WHERE supersedes = ? ORDER BY created_at DESC, id DESC LIMIT 1",
+                [id],
+                |row| row.get(0),
+            )
             .ok();
 
         Ok(superseding_id)
     }
 
+    /// Find the latest version of a doc by following the supersedes chain forward.
+    pub fn get_doc_head(&self, id: &str) -> Result<String> {
+        self.get_doc(id)?;
+        let mut head = id.to_string();
+        let mut seen = std::collections::HashSet::from([head.clone()]);
+        while let Some(next) = self.get_doc_superseded_by(&head)? {
+            // Prevent infinite loops from circular references
+            if !seen.insert(next.clone()) {
+                break;
+            }
+            head = next;
+        }
+        Ok(head)
+    }
+
     /// List all docs, optionally filtered.
     ///
     /// # Arguments
@@ -3439,6 +3546,26 @@ impl Storage {
 
     // === Entity Type Detection ===
 
+    /// Check whether an entity is live (present in the cache).
+    ///
+    /// Getters read the latest record from the append-only logs, which keep
+    /// deleted entities around; the cache only holds live ones.
+    pub fn is_cached(&self, id: &str) -> Result<bool> {
+        let exists = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM tasks WHERE id = ?1)
+                 OR EXISTS(SELECT 1 FROM bugs WHERE id = ?1)
+                 OR EXISTS(SELECT 1 FROM issues WHERE id = ?1)
+                 OR EXISTS(SELECT 1 FROM ideas WHERE id = ?1)
+                 OR EXISTS(SELECT 1 FROM docs WHERE id = ?1)
+                 OR EXISTS(SELECT 1 FROM milestones WHERE id = ?1)
+                 OR EXISTS(SELECT 1 FROM missions WHERE id = ?1)
+                 OR EXISTS(SELECT 1 FROM tests WHERE id = ?1)",
+            [id],
+            |row| row.get(0),
+        )?;
+        Ok(exists)
+    }
+
     /// Detect the type of an entity by its ID.
     /// Tries each entity type (task, bug, issue, test, milestone, edge, queue) and returns the first match.
     pub fn get_entity_type(&self, id: &str) -> Result<EntityType> {
@@ -3632,6 +3759,60 @@ impl Storage {
         Self::write_config_kdl_to_path(&path, doc)
     }
 
+    /// Get outbound webhook subscriptions from the session config.kdl.
+    pub fn get_webhooks_kdl(&self) -> Result<Vec<crate::config::WebhookConfig>> {
+        let doc = self.read_config_kdl()?;
+        crate::config::WebhookConfig::all_from_kdl(&doc)
+            .map_err(|e| Error::InvalidInput(format!("config.kdl: {}", e)))
+    }
+
+    /// Get the agent supervisor settings from config.kdl.
+    /// Checks session config first, then falls back to system config and defaults.
+    pub fn get_supervisor_kdl(&self) -> Result<crate::config::SupervisorConfig> {
+        let invalid = |e: String| Error::InvalidInput(format!("config.kdl: {}", e));
+        let doc = self.read_config_kdl()?;
+        if let Some(config) = crate::config::SupervisorConfig::from_kdl(&doc).map_err(invalid)? {
+            return Ok(config);
+        }
+
+        let system_doc = Self::read_system_config_kdl()?;
+        Ok(crate::config::SupervisorConfig::from_kdl(&system_doc)
+            .map_err(invalid)?
+            .unwrap_or_default())
+    }
+
+    /// Get model usage budgets from config.kdl.
+    /// Checks session config first, then falls back to system config.
+    pub fn get_budgets_kdl(&self) -> Result<crate::config::BudgetConfig> {
+        let invalid = |e: String| Error::InvalidInput(format!("config.kdl: {}", e));
+        let doc = self.read_config_kdl()?;
+        if let Some(config) = crate::config::BudgetConfig::from_kdl(&doc).map_err(invalid)? {
+            return Ok(config);
+        }
+
+        let system_doc = Self::read_system_config_kdl()?;
+        Ok(crate::config::BudgetConfig::from_kdl(&system_doc)
+            .map_err(invalid)?
+            .unwrap_or_default())
+    }
+
+    /// Get the `bn report` template for a report kind from config.kdl.
+    /// Checks session config first, then falls back to system config and defaults.
+    pub fn get_report_template_kdl(&self, kind: &str) -> Result<crate::config::ReportTemplate> {
+        let invalid = |e: String| Error::InvalidInput(format!("config.kdl: {}", e));
+        let doc = self.read_config_kdl()?;
+        if let Some(template) =
+            crate::config::ReportTemplate::from_kdl(&doc, kind).map_err(invalid)?
+        {
+            return Ok(template);
+        }
+
+        let system_doc = Self::read_system_config_kdl()?;
+        Ok(crate::config::ReportTemplate::from_kdl(&system_doc, kind)
+            .map_err(invalid)?
+            .unwrap_or_else(|| crate::config::ReportTemplate::default_for(kind)))
+    }
+
     /// Get agent scaling config from config.kdl.
     /// Checks session config first, then falls back to system config.
     /// Returns Some((min, max)) if the agent type is explicitly configured, None otherwise.
@@ -4517,7 +4698,16 @@ impl Storage {
             }
         }
 
-        latest.ok_or_else(|| Error::NotFound(format!("Test not found: {}", id)))
+        // Deleted tests stay in the append-only log but not in the cache
+        let cached: bool = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM tests WHERE id = ?)",
+            [id],
+            |row| row.get(0),
+        )?;
+
+        latest
+            .filter(|_| cached)
+            .ok_or_else(|| Error::NotFound(format!("Test not found: {}", id)))
     }
 
     /// List all test nodes, optionally filtered by linked task.
@@ -4699,8 +4889,8 @@ impl Storage {
         self.conn.execute(
             r#"
             INSERT INTO test_results
-            (test_id, passed, exit_code, stdout, stderr, duration_ms, executed_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            (test_id, passed, exit_code, stdout, stderr, duration_ms, executed_at, timed_out, commit_sha)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
             "#,
             params![
                 result.test_id,
@@ -4710,17 +4900,43 @@ impl Storage {
                 result.stderr,
                 result.duration_ms as i64,
                 result.executed_at.to_rfc3339(),
+                result.timed_out as i32,
+                result.commit,
             ],
         )?;
 
         Ok(())
     }
 
+    /// Get every recorded result for a test node, oldest first.
+    pub fn get_test_results(&self, test_id: &str) -> Result<Vec<TestResult>> {
+        let results_path = self.root.join("test-results.jsonl");
+        if !results_path.exists() {
+            return Ok(Vec::new());
+        }
+        let reader = BufReader::new(File::open(&results_path)?);
+
+        let mut results = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Ok(result) = serde_json::from_str::<TestResult>(&line)
+                && result.test_id == test_id
+            {
+                results.push(result);
+            }
+        }
+        Ok(results)
+    }
+
     /// Get the last test result for a test node.
     pub fn get_last_test_result(&self, test_id: &str) -> Result<Option<TestResult>> {
         let mut stmt = self.conn.prepare(
             r#"
-            SELECT test_id, passed, exit_code, stdout, stderr, duration_ms, executed_at
+            SELECT test_id, passed, exit_code, stdout, stderr, duration_ms, executed_at, timed_out,
+                   commit_sha
             FROM test_results
             WHERE test_id = ?1
             ORDER BY executed_at DESC
@@ -4733,6 +4949,7 @@ impl Storage {
                 let passed: i32 = row.get(1)?;
                 let duration_ms: i64 = row.get(5)?;
                 let executed_at_str: String = row.get(6)?;
+                let timed_out: i32 = row.get(7)?;
                 Ok(TestResult {
                     test_id: row.get(0)?,
                     passed: passed != 0,
@@ -4740,6 +4957,8 @@ impl Storage {
                     stdout: row.get(3)?,
                     stderr: row.get(4)?,
                     duration_ms: duration_ms as u64,
+                    timed_out: timed_out != 0,
+                    commit: row.get(8)?,
                     executed_at: chrono::DateTime::parse_from_rfc3339(&executed_at_str)
                         .map(|dt| dt.with_timezone(&Utc))
                         .unwrap_or_else(|_| Utc::now()),
@@ -4768,10 +4987,14 @@ impl Storage {
 
     /// Reopen tasks linked to a failed test (regression detection).
     ///
-    /// Returns the list of task IDs that were reopened.
+    /// Quarantined (flaky) tests reopen nothing. Returns the list of task IDs
+    /// that were reopened.
     pub fn reopen_linked_tasks_on_failure(&mut self, test_id: &str) -> Result<Vec<String>> {
         let test = self.get_test(test_id)?;
         let mut reopened = Vec::new();
+        if test.quarantined {
+            return Ok(reopened);
+        }
 
         for task_id in &test.linked_tasks {
             if let Ok(mut task) = self.get_task(task_id) {
@@ -4794,6 +5017,17 @@ impl Storage {
     /// Link a commit to a task.
     /// Link a commit to a task or bug.
     pub fn link_commit(&mut self, sha: &str, entity_id: &str) -> Result<CommitLink> {
+        self.link_commit_at(sha, entity_id, Utc::now())
+    }
+
+    /// Link a commit to a task or bug, recording `linked_at` as the link
+    /// time (e.g. the commit time when backfilling from git history).
+    pub fn link_commit_at(
+        &mut self,
+        sha: &str,
+        entity_id: &str,
+        linked_at: chrono::DateTime<Utc>,
+    ) -> Result<CommitLink> {
         // Validate SHA format
         validate_sha(sha)?;
 
@@ -4830,8 +5064,6 @@ impl Storage {
             )));
         }
 
-        let linked_at = Utc::now();
-
         // Insert into SQLite cache
         self.conn.execute(
             "INSERT INTO commit_links (sha, task_id, linked_at) VALUES (?1, ?2, ?3)",
@@ -4958,12 +5190,16 @@ impl Storage {
     }
 
     /// Get all tasks linked to a commit.
+    ///
+    /// Links and lookups may use abbreviated SHAs, so a link matches when
+    /// either SHA is a prefix of the other.
     pub fn get_tasks_for_commit(&self, sha: &str) -> Result<Vec<String>> {
         validate_sha(sha)?;
 
-        let mut stmt = self
-            .conn
-            .prepare("SELECT task_id FROM commit_links WHERE sha = ?1")?;
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT task_id FROM commit_links
+             WHERE sha LIKE ?1 || '%' OR ?1 LIKE sha || '%'",
+        )?;
 
         let task_ids: Vec<String> = stmt
             .query_map([sha], |row| row.get(0))?
@@ -5181,6 +5417,215 @@ impl Storage {
         self.register_agent(agent)
     }
 
+    /// Append a usage report to usage.jsonl.
+    pub fn append_usage_record(&mut self, record: &UsageRecord) -> Result<()> {
+        let usage_path = self.root.join("usage.jsonl");
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&usage_path)?;
+
+        let json = serde_json::to_string(record)?;
+        writeln!(file, "{}", json)?;
+        Ok(())
+    }
+
+    /// List all usage reports, oldest first.
+    pub fn list_usage_records(&self) -> Result<Vec<UsageRecord>> {
+        let usage_path = self.root.join("usage.jsonl");
+        if !usage_path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let reader = BufReader::new(File::open(&usage_path)?);
+        let mut records = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Ok(record) = serde_json::from_str::<UsageRecord>(&line) {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+
+    /// Append a merge queue entry (a new one or a state change) to merge-queue.jsonl.
+    pub fn save_merge_queue_entry(&mut self, entry: &MergeQueueEntry) -> Result<()> {
+        let queue_path = self.root.join("merge-queue.jsonl");
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&queue_path)?;
+
+        let json = serde_json::to_string(entry)?;
+        writeln!(file, "{}", json)?;
+        Ok(())
+    }
+
+    /// List merge queue entries in the order they were enqueued.
+    pub fn list_merge_queue(&self) -> Result<Vec<MergeQueueEntry>> {
+        let queue_path = self.root.join("merge-queue.jsonl");
+        if !queue_path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let reader = BufReader::new(File::open(&queue_path)?);
+        let mut entries: Vec<MergeQueueEntry> = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Ok(entry) = serde_json::from_str::<MergeQueueEntry>(&line) else {
+                continue;
+            };
+            // Later lines are newer states of the same entry
+            match entries.iter_mut().find(|e| e.id == entry.id) {
+                Some(existing) => *existing = entry,
+                None => entries.push(entry),
+            }
+        }
+        entries.sort_by_key(|e| e.enqueued_at);
+        Ok(entries)
+    }
+
+    /// Append a new message to messages.jsonl.
+    pub fn save_message(&mut self, message: &Message) -> Result<()> {
+        self.append_message_line(&serde_json::to_string(message)?)
+    }
+
+    /// Record that `reader` has read message `id`.
+    pub fn mark_message_read(&mut self, id: &str, reader: &str) -> Result<()> {
+        self.append_message_line(&serde_json::to_string(&MessageReceipt::new(id, reader))?)
+    }
+
+    fn append_message_line(&self, json: &str) -> Result<()> {
+        let messages_path = self.root.join("messages.jsonl");
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&messages_path)?;
+        writeln!(file, "{}", json)?;
+        Ok(())
+    }
+
+    /// List messages with their read receipts applied, oldest first.
+    pub fn list_messages(&self) -> Result<Vec<Message>> {
+        use std::collections::HashMap;
+
+        let messages_path = self.root.join("messages.jsonl");
+        if !messages_path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let reader = BufReader::new(File::open(&messages_path)?);
+        let mut messages: Vec<Message> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Ok(message) = serde_json::from_str::<Message>(&line) {
+                // Later lines are newer states of the same message
+                match index.get(&message.id) {
+                    Some(&i) => messages[i] = message,
+                    None => {
+                        index.insert(message.id.clone(), messages.len());
+                        messages.push(message);
+                    }
+                }
+            } else if let Ok(receipt) = serde_json::from_str::<MessageReceipt>(&line)
+                && let Some(&i) = index.get(&receipt.id)
+                && !messages[i].read_by.contains(&receipt.reader)
+            {
+                messages[i].read_by.push(receipt.reader);
+            }
+        }
+        messages.sort_by_key(|m| m.created_at);
+        Ok(messages)
+    }
+
+    /// Get a message by ID.
+    pub fn get_message(&self, id: &str) -> Result<Message> {
+        self.list_messages()?
+            .into_iter()
+            .find(|m| m.id == id)
+            .ok_or_else(|| Error::NotFound(format!("Message not found: {}", id)))
+    }
+
+    /// Path of an agent's transcript file (`transcripts/<agent-id>.jsonl`).
+    pub fn transcript_path(&self, agent_id: &str) -> Result<PathBuf> {
+        // Agent IDs come from URLs and the command line; keep them inside the directory
+        if agent_id.is_empty()
+            || !agent_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(Error::InvalidInput(format!(
+                "Invalid agent ID for transcript: '{}'",
+                agent_id
+            )));
+        }
+        Ok(self
+            .root
+            .join("transcripts")
+            .join(format!("{}.jsonl", agent_id)))
+    }
+
+    /// Append a line to an agent's transcript.
+    pub fn append_transcript_entry(&self, agent_id: &str, entry: &TranscriptEntry) -> Result<()> {
+        let path = self.transcript_path(agent_id)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+        let json = serde_json::to_string(entry)?;
+        writeln!(file, "{}", json)?;
+        Ok(())
+    }
+
+    /// Read an agent's transcript starting at byte `offset`.
+    ///
+    /// Returns the complete lines after `offset` and the offset to resume
+    /// from, so callers can follow a transcript that is still being written.
+    pub fn read_transcript(
+        &self,
+        agent_id: &str,
+        offset: u64,
+    ) -> Result<(Vec<TranscriptEntry>, u64)> {
+        let path = self.transcript_path(agent_id)?;
+        if !path.exists() {
+            return Err(Error::NotFound(format!(
+                "No transcript for agent {}",
+                agent_id
+            )));
+        }
+
+        let mut file = File::open(&path)?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut reader = BufReader::new(file);
+        let mut entries = Vec::new();
+        let mut next = offset;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let read = reader.read_line(&mut line)?;
+            // Stop at EOF or at a line that is still being written
+            if read == 0 || !line.ends_with('\n') {
+                break;
+            }
+            next += read as u64;
+            if let Ok(entry) = serde_json::from_str::<TranscriptEntry>(line.trim()) {
+                entries.push(entry);
+            }
+        }
+        Ok((entries, next))
+    }
+
     /// Update agent status.
     pub fn update_agent_status(&mut self, pid: u32, status: AgentStatus) -> Result<()> {
         let mut agent = self.get_agent(pid)?;
@@ -5385,6 +5830,27 @@ impl Storage {
                 false
             };
 
+            // Agents that said goodbye already wrote their handoffs
+            if should_remove && agent.goodbye_at.is_none() && !agent.tasks.is_empty() {
+                let repo_root = self
+                    .read_session_metadata()
+                    .ok()
+                    .flatten()
+                    .map(|m| PathBuf::from(m.repo_path));
+                let reason = if agent.status == AgentStatus::Stale {
+                    "Agent went stale and was cleaned up"
+                } else {
+                    "Agent process exited without saying goodbye"
+                };
+                let _ = crate::agents::write_handoffs(
+                    self,
+                    repo_root.as_deref(),
+                    &agent,
+                    Some(reason),
+                    &[],
+                );
+            }
+
             if should_remove && self.remove_agent(agent.pid).is_ok() {
                 removed.push(agent.pid);
             }
@@ -5486,8 +5952,8 @@ impl Storage {
         let args_str = serde_json::to_string(&log.args)?;
         self.conn.execute(
             r#"
-            INSERT INTO action_logs (timestamp, repo_path, command, args, success, error, duration_ms, user, agent_id)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            INSERT INTO action_logs (timestamp, repo_path, command, args, success, error, duration_ms, user, agent_id, token_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
             "#,
             params![
                 log.timestamp.to_rfc3339(),
@@ -5499,6 +5965,7 @@ impl Storage {
                 log.duration_ms as i64,
                 log.user,
                 log.agent_id,
+                log.token_id,
             ],
         )?;
         Ok(())
@@ -5529,7 +5996,7 @@ impl Storage {
 
         // Build the query dynamically based on filters
         let mut sql = String::from(
-            "SELECT timestamp, repo_path, command, args, success, error, duration_ms, user, agent_id FROM action_logs WHERE 1=1",
+            "SELECT timestamp, repo_path, command, args, success, error, duration_ms, user, agent_id, token_id FROM action_logs WHERE 1=1",
         );
         let mut params: Vec<Box<dyn rusqlite::ToSql>> = vec![];
 
@@ -5582,6 +6049,7 @@ impl Storage {
                 duration_ms: duration_ms as u64,
                 user: row.get(7)?,
                 agent_id: row.get(8)?,
+                token_id: row.get(9)?,
             })
         })?;
 
@@ -7059,6 +7527,8 @@ mod tests {
             stdout: Some("output".to_string()),
             stderr: None,
             duration_ms: 100,
+            timed_out: false,
+            commit: None,
             executed_at: chrono::Utc::now(),
         };
         storage.save_test_result(&result).unwrap();
@@ -7096,6 +7566,8 @@ mod tests {
             stdout: None,
             stderr: None,
             duration_ms: 10,
+            timed_out: false,
+            commit: None,
             executed_at: chrono::Utc::now(),
         };
         let fail_result = crate::models::TestResult {
@@ -7105,6 +7577,8 @@ mod tests {
             stdout: None,
             stderr: None,
             duration_ms: 10,
+            timed_out: false,
+            commit: None,
             executed_at: chrono::Utc::now(),
         };
         storage.save_test_result(&pass_result).unwrap();
@@ -7262,6 +7736,67 @@ mod tests {
         assert!(result.unwrap_err().to_string().contains("not linked"));
     }
 
+    #[test]
+    fn test_task_update_keeps_test_links() {
+        let (_temp_dir, mut storage) = create_test_storage();
+
+        let mut task = Task::new("bn-aaaa".to_string(), "Task A".to_string());
+        storage.create_task(&task).unwrap();
+        let mut test = TestNode::new(
+            "bnt-aaaa".to_string(),
+            "Test A".to_string(),
+            "true".to_string(),
+        );
+        test.linked_tasks.push("bn-aaaa".to_string());
+        storage.create_test(&test).unwrap();
+
+        task.status = TaskStatus::Done;
+        storage.update_task(&task).unwrap();
+        assert_eq!(storage.get_tests_for_task("bn-aaaa").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_merge_queue_latest_state_wins() {
+        use crate::models::{MergeQueueEntry, MergeQueueStatus};
+
+        let (_temp_dir, mut storage) = create_test_storage();
+
+        let mut first =
+            MergeQueueEntry::new("bnmq-aaaa".to_string(), "bn-aaaa", "bn/bn-aaaa", "main");
+        let second = MergeQueueEntry::new("bnmq-bbbb".to_string(), "bn-bbbb", "bn/bn-bbbb", "main");
+        storage.save_merge_queue_entry(&first).unwrap();
+        storage.save_merge_queue_entry(&second).unwrap();
+        first.status = MergeQueueStatus::Merged;
+        storage.save_merge_queue_entry(&first).unwrap();
+
+        let entries = storage.list_merge_queue().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].id, "bnmq-aaaa");
+        assert_eq!(entries[0].status, MergeQueueStatus::Merged);
+        assert_eq!(entries[1].status, MergeQueueStatus::Queued);
+    }
+
+    #[test]
+    fn test_messages_apply_read_receipts() {
+        use crate::models::Message;
+
+        let (_temp_dir, mut storage) = create_test_storage();
+
+        let first = Message::new("bnmsg-aaaa".to_string(), "bn-a1b2", "human", "Hi");
+        let second = Message::new("bnmsg-bbbb".to_string(), "human", "worker", "Hello");
+        storage.save_message(&first).unwrap();
+        storage.save_message(&second).unwrap();
+        storage.mark_message_read("bnmsg-aaaa", "human").unwrap();
+        storage.mark_message_read("bnmsg-aaaa", "human").unwrap();
+        storage.mark_message_read("bnmsg-cccc", "human").unwrap();
+
+        let messages = storage.list_messages().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].read_by, vec!["human".to_string()]);
+        assert_eq!(storage.get_message("bnmsg-bbbb").unwrap().to, "worker");
+        assert!(storage.get_message("bnmsg-cccc").is_err());
+    }
+
     #[test]
     fn test_get_commits_for_task() {
         let (_temp_dir, mut storage) = create_test_storage();
@@ -7293,6 +7828,13 @@ mod tests {
         assert_eq!(tasks.len(), 2);
         assert!(tasks.contains(&"bn-aaaa".to_string()));
         assert!(tasks.contains(&"bn-bbbb".to_string()));
+
+        // Abbreviated links match the full SHA and vice versa
+        let full = "a1b2c3d4e5f60718293a4b5c6d7e8f9012345678";
+        assert_eq!(storage.get_tasks_for_commit(full).unwrap().len(), 2);
+        storage.link_commit(full, "bn-aaaa").unwrap();
+        assert_eq!(storage.get_tasks_for_commit(full).unwrap().len(), 2);
+        assert!(storage.get_tasks_for_commit("a1b2c3e").unwrap().is_empty());
     }
 
     #[test]
@@ -8011,6 +8553,27 @@ mod tests {
         assert_eq!(retrieved.supersedes, Some("bn-doc1".to_string()));
     }
 
+    #[test]
+    fn test_doc_head_follows_latest_fork() {
+        let (_env, mut storage) = create_test_storage();
+
+        let v1 = Doc::new("bn-doc1".to_string(), "Doc".to_string());
+        storage.add_doc(&v1).unwrap();
+        let mut v2 = Doc::new("bn-doc2".to_string(), "Doc".to_string());
+        v2.supersedes = Some("bn-doc1".to_string());
+        storage.add_doc(&v2).unwrap();
+        assert_eq!(storage.get_doc_head("bn-doc1").unwrap(), "bn-doc2");
+        assert_eq!(storage.get_doc_head("bn-doc2").unwrap(), "bn-doc2");
+
+        // A later fork off v1 becomes the head
+        let mut fork = Doc::new("bn-doc3".to_string(), "Doc".to_string());
+        fork.supersedes = Some("bn-doc1".to_string());
+        fork.core.created_at = v2.core.created_at + chrono::Duration::seconds(1);
+        storage.add_doc(&fork).unwrap();
+        assert_eq!(storage.get_doc_head("bn-doc1").unwrap(), "bn-doc3");
+        assert!(storage.get_doc_head("bn-missing").is_err());
+    }
+
     #[test]
     fn test_doc_list_basic() {
         let (_env, mut storage) = create_test_storage();
@@ -8118,6 +8681,7 @@ mod tests {
             duration_ms: 42,
             user: "testuser".to_string(),
             agent_id: None,
+            token_id: None,
         };
 
         // Add the entry
@@ -8149,6 +8713,7 @@ mod tests {
                 duration_ms: 10,
                 user: "testuser".to_string(),
                 agent_id: None,
+                token_id: None,
             };
             storage.add_action_log(&log_entry).unwrap();
         }
@@ -8187,6 +8752,7 @@ mod tests {
             duration_ms: 10,
             user: "alice".to_string(),
             agent_id: None,
+            token_id: None,
         };
         storage.add_action_log(&log_success).unwrap();
 
@@ -8200,6 +8766,7 @@ mod tests {
             duration_ms: 5,
             user: "bob".to_string(),
             agent_id: None,
+            token_id: None,
         };
         storage.add_action_log(&log_failure).unwrap();
 
@@ -8241,6 +8808,7 @@ mod tests {
                 duration_ms: 10,
                 user: "testuser".to_string(),
                 agent_id: None,
+                token_id: None,
             };
             storage.add_action_log(&log_entry).unwrap();
         }
@@ -8299,6 +8867,7 @@ mod tests {
                 duration_ms: 10,
                 user: "testuser".to_string(),
                 agent_id: None,
+                token_id: None,
             };
             storage.add_action_log(&old_entry).unwrap();
         }
@@ -8314,6 +8883,7 @@ mod tests {
                 duration_ms: 10,
                 user: "testuser".to_string(),
                 agent_id: None,
+                token_id: None,
             };
             storage.add_action_log(&recent_entry).unwrap();
         }
@@ -8363,6 +8933,7 @@ mod tests {
                 duration_ms: 10,
                 user: "testuser".to_string(),
                 agent_id: None,
+                token_id: None,
             };
             storage.add_action_log(&log_entry).unwrap();
         }
@@ -8981,6 +9552,7 @@ mod tests {
             token_validated_at: Some(chrono::Utc::now()),
             last_copilot_version: Some("1.0.0".to_string()),
             serve: None,
+            gui_tokens: Vec::new(),
         };
 
         // Write and read back
@@ -9014,6 +9586,7 @@ mod tests {
             token_validated_at: None,
             last_copilot_version: Some("1.0.0".to_string()),
             serve: None,
+            gui_tokens: Vec::new(),
         };
         storage.write_binnacle_state(&initial).unwrap();
 
@@ -9136,6 +9709,7 @@ mod tests {
             token_validated_at: None,
             last_copilot_version: None, // Don't override
             serve: None,
+            gui_tokens: Vec::new(),
         };
         storage.write_binnacle_state(&session_state).unwrap();
 
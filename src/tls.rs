@@ -0,0 +1,343 @@
+//! TLS support for session servers and their clients
+//!
+//! The session server can serve HTTPS/WSS using either a user-provided
+//! certificate (`--tls-cert`/`--tls-key`) or a self-signed certificate that is
+//! generated once and stored in the session directory.
+//!
+//! Clients (the upstream hub connection and the TUI) can pin server
+//! certificates by SHA-256 fingerprint. Pinning is how self-signed certificates
+//! are trusted: when pins are configured, the certificate chain is not checked
+//! against CA roots, only the leaf fingerprint and handshake signatures.
+//!
+//! Fingerprints are printed as `sha256:<hex>`. Pins are accepted with or
+//! without the `sha256:` prefix, in any case, with or without `:` separators.
+
+use std::sync::Arc;
+
+use rustls::DigitallySignedStruct;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{CryptoProvider, verify_tls12_signature, verify_tls13_signature};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use sha2::{Digest, Sha256};
+
+/// Compute the SHA-256 fingerprint of a DER-encoded certificate (`sha256:<hex>`).
+pub fn cert_fingerprint(der: &[u8]) -> String {
+    format!("sha256:{:x}", Sha256::digest(der))
+}
+
+/// Normalize a user-supplied fingerprint pin to lowercase hex.
+///
+/// Returns None if the pin is not a valid SHA-256 fingerprint.
+pub fn normalize_fingerprint(pin: &str) -> Option<String> {
+    let trimmed = pin.trim();
+    let without_prefix = trimmed
+        .strip_prefix("sha256:")
+        .or_else(|| trimmed.strip_prefix("SHA256:"))
+        .unwrap_or(trimmed);
+    let hex: String = without_prefix
+        .chars()
+        .filter(|c| *c != ':')
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+    (hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit())).then_some(hex)
+}
+
+/// Parse a list of fingerprint pins, failing on the first invalid one.
+pub fn parse_pins(pins: &[String]) -> Result<Vec<String>, String> {
+    pins.iter()
+        .map(|pin| {
+            normalize_fingerprint(pin).ok_or_else(|| {
+                format!(
+                    "Invalid certificate pin '{}': expected sha256:<64 hex>",
+                    pin
+                )
+            })
+        })
+        .collect()
+}
+
+/// Certificate verifier that trusts servers by leaf certificate fingerprint.
+#[derive(Debug)]
+struct PinnedCertVerifier {
+    /// Normalized (lowercase hex) SHA-256 fingerprints
+    pins: Vec<String>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let actual = format!("{:x}", Sha256::digest(end_entity.as_ref()));
+        if self.pins.contains(&actual) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(format!(
+                "certificate fingerprint sha256:{} does not match any pinned fingerprint",
+                actual
+            )))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+/// Build a rustls client configuration that only trusts the pinned certificates.
+///
+/// `pins` must already be normalized (see [`parse_pins`]).
+pub fn pinned_client_config(pins: Vec<String>) -> Result<Arc<rustls::ClientConfig>, String> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = rustls::ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("Failed to configure TLS: {}", e))?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(PinnedCertVerifier { pins, provider }))
+        .with_no_client_auth();
+    Ok(Arc::new(config))
+}
+
+#[cfg(feature = "gui")]
+pub use server::{SELF_SIGNED_CERT_FILE, SELF_SIGNED_KEY_FILE, ServerTls, TlsOptions};
+
+#[cfg(feature = "gui")]
+mod server {
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
+    use super::cert_fingerprint;
+
+    /// File name of the generated self-signed certificate in the session directory
+    pub const SELF_SIGNED_CERT_FILE: &str = "tls-cert.pem";
+
+    /// File name of the generated self-signed private key in the session directory
+    pub const SELF_SIGNED_KEY_FILE: &str = "tls-key.pem";
+
+    /// TLS options for a server, as given on the command line.
+    ///
+    /// When both paths are None a self-signed certificate is used.
+    #[derive(Debug, Clone, Default)]
+    pub struct TlsOptions {
+        /// PEM certificate chain
+        pub cert: Option<PathBuf>,
+        /// PEM private key
+        pub key: Option<PathBuf>,
+    }
+
+    /// Loaded server TLS material.
+    pub struct ServerTls {
+        /// rustls server configuration
+        pub config: Arc<rustls::ServerConfig>,
+        /// Fingerprint of the leaf certificate (`sha256:<hex>`)
+        pub fingerprint: String,
+        /// Path of the certificate in use
+        pub cert_path: PathBuf,
+        /// Whether the certificate was generated by binnacle
+        pub self_signed: bool,
+    }
+
+    impl TlsOptions {
+        /// Load the configured certificate, generating a self-signed one in
+        /// `storage_dir` if no certificate was provided.
+        ///
+        /// Generated certificates are reused across restarts so that client
+        /// pins remain valid.
+        pub fn load(&self, storage_dir: &Path, host: &str) -> Result<ServerTls, String> {
+            let (cert_path, key_path, self_signed) = match (&self.cert, &self.key) {
+                (Some(cert), Some(key)) => (cert.clone(), key.clone(), false),
+                (None, None) => {
+                    let cert = storage_dir.join(SELF_SIGNED_CERT_FILE);
+                    let key = storage_dir.join(SELF_SIGNED_KEY_FILE);
+                    if !cert.exists() || !key.exists() {
+                        generate_self_signed(&cert, &key, host)?;
+                    }
+                    (cert, key, true)
+                }
+                _ => return Err("--tls-cert and --tls-key must be given together".to_string()),
+            };
+
+            let certs: Vec<CertificateDer<'static>> = CertificateDer::pem_file_iter(&cert_path)
+                .and_then(|iter| iter.collect::<Result<_, _>>())
+                .map_err(|e| {
+                    format!("Failed to read certificate {}: {}", cert_path.display(), e)
+                })?;
+            let leaf = certs
+                .first()
+                .ok_or_else(|| format!("No certificate found in {}", cert_path.display()))?;
+            let fingerprint = cert_fingerprint(leaf.as_ref());
+
+            let key = PrivateKeyDer::from_pem_file(&key_path)
+                .map_err(|e| format!("Failed to read private key {}: {}", key_path.display(), e))?;
+
+            let provider = Arc::new(rustls::crypto::ring::default_provider());
+            let mut config = rustls::ServerConfig::builder_with_provider(provider)
+                .with_safe_default_protocol_versions()
+                .map_err(|e| format!("Failed to configure TLS: {}", e))?
+                .with_no_client_auth()
+                .with_single_cert(certs, key)
+                .map_err(|e| format!("Invalid TLS certificate/key: {}", e))?;
+            config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+            Ok(ServerTls {
+                config: Arc::new(config),
+                fingerprint,
+                cert_path,
+                self_signed,
+            })
+        }
+    }
+
+    /// Generate a self-signed certificate and key, writing them as PEM files.
+    fn generate_self_signed(cert_path: &Path, key_path: &Path, host: &str) -> Result<(), String> {
+        let mut names = vec![
+            "localhost".to_string(),
+            "127.0.0.1".to_string(),
+            "::1".to_string(),
+        ];
+        if !host.is_empty() && host != "0.0.0.0" && host != "::" && !names.iter().any(|n| n == host)
+        {
+            names.push(host.to_string());
+        }
+
+        let certified = rcgen::generate_simple_self_signed(names)
+            .map_err(|e| format!("Failed to generate self-signed certificate: {}", e))?;
+
+        if let Some(parent) = cert_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        std::fs::write(cert_path, certified.cert.pem())
+            .map_err(|e| format!("Failed to write {}: {}", cert_path.display(), e))?;
+        write_private_key(key_path, &certified.key_pair.serialize_pem())
+            .map_err(|e| format!("Failed to write {}: {}", key_path.display(), e))?;
+
+        Ok(())
+    }
+
+    /// Write a private key readable only by the owner.
+    ///
+    /// The file is created with mode 0600 rather than restricted after the
+    /// fact, so the key is never readable by other users.
+    fn write_private_key(path: &Path, pem: &str) -> std::io::Result<()> {
+        use std::io::Write;
+
+        match std::fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options.open(path)?.write_all(pem.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_fingerprint_formats() {
+        let hex = "ab".repeat(32);
+        assert_eq!(normalize_fingerprint(&hex), Some(hex.clone()));
+        assert_eq!(
+            normalize_fingerprint(&format!("sha256:{}", hex.to_uppercase())),
+            Some(hex.clone())
+        );
+
+        let colons = hex
+            .as_bytes()
+            .chunks(2)
+            .map(|c| std::str::from_utf8(c).unwrap())
+            .collect::<Vec<_>>()
+            .join(":");
+        assert_eq!(normalize_fingerprint(&colons), Some(hex));
+
+        assert_eq!(normalize_fingerprint("sha256:abcd"), None);
+        assert_eq!(normalize_fingerprint(&"zz".repeat(32)), None);
+    }
+
+    #[test]
+    fn test_cert_fingerprint_roundtrips_through_pin() {
+        let fingerprint = cert_fingerprint(b"not really a certificate");
+        assert!(fingerprint.starts_with("sha256:"));
+        let pins = parse_pins(std::slice::from_ref(&fingerprint)).unwrap();
+        assert_eq!(format!("sha256:{}", pins[0]), fingerprint);
+        assert!(parse_pins(&["bogus".to_string()]).is_err());
+    }
+
+    #[cfg(feature = "gui")]
+    #[test]
+    fn test_self_signed_certificate_is_generated_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = TlsOptions::default();
+
+        let first = options.load(dir.path(), "127.0.0.1").unwrap();
+        assert!(first.self_signed);
+        assert!(dir.path().join(SELF_SIGNED_CERT_FILE).exists());
+        assert!(dir.path().join(SELF_SIGNED_KEY_FILE).exists());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(dir.path().join(SELF_SIGNED_KEY_FILE))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // Reloading reuses the stored certificate so pins stay valid
+        let second = options.load(dir.path(), "127.0.0.1").unwrap();
+        assert_eq!(first.fingerprint, second.fingerprint);
+
+        let partial = TlsOptions {
+            cert: Some(dir.path().join(SELF_SIGNED_CERT_FILE)),
+            key: None,
+        };
+        assert!(partial.load(dir.path(), "127.0.0.1").is_err());
+    }
+}
@@ -7,6 +7,7 @@
 //! - View switching between Work, Recently Completed, and Node Detail
 
 use std::io::{self, stdout};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use chrono::{DateTime, Utc};
@@ -32,7 +33,7 @@ use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
 use super::connection::{
     ConnectionState, MAX_RECONNECT_ATTEMPTS, RECONNECT_DEBOUNCE_MS, calculate_backoff,
 };
-use super::notifications::NotificationManager;
+use super::notifications::{NotificationLevel, NotificationManager};
 use super::views::{
     CompletedItem, EdgeInfo, LogEntry, LogPanelView, NodeDetail, NodeDetailView,
     RecentlyCompletedView, WorkItem, WorkView,
@@ -44,6 +45,47 @@ pub const DEFAULT_PORT: u16 = 3030;
 /// Cooldown period after a fetch error before retrying (in seconds)
 const FETCH_ERROR_COOLDOWN_SECS: u64 = 5;
 
+/// Connect to a session server WebSocket, attaching a bearer token if provided.
+///
+/// When `tls_config` is set (certificate pinning), it is used for wss:// URLs
+/// instead of the default webpki trust roots.
+async fn connect_ws(
+    endpoint: &str,
+    token: Option<&str>,
+    tls_config: Option<Arc<rustls::ClientConfig>>,
+) -> Result<
+    (
+        tokio_tungstenite::WebSocketStream<
+            tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+        >,
+        tokio_tungstenite::tungstenite::handshake::client::Response,
+    ),
+    Box<tokio_tungstenite::tungstenite::Error>,
+> {
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+    use tokio_tungstenite::tungstenite::http::header::AUTHORIZATION;
+
+    let mut request = endpoint.into_client_request()?;
+    if let Some(token) = token
+        && let Ok(value) = format!("Bearer {}", token).parse()
+    {
+        request.headers_mut().insert(AUTHORIZATION, value);
+    }
+    let connector = tls_config.map(tokio_tungstenite::Connector::Rustls);
+    Ok(tokio_tungstenite::connect_async_tls_with_config(request, None, false, connector).await?)
+}
+
+/// Derive the HTTP API base URL from a WebSocket URL (wss:// maps to https://).
+fn api_base_from_ws_url(url: &str) -> String {
+    let (scheme, without_scheme) = if let Some(rest) = url.strip_prefix("wss://") {
+        ("https", rest)
+    } else {
+        ("http", url.strip_prefix("ws://").unwrap_or(url))
+    };
+    let host_port = without_scheme.split('/').next().unwrap_or(without_scheme);
+    format!("{}://{}", scheme, host_port)
+}
+
 /// Parse a WebSocket URL to extract host and port for display purposes.
 ///
 /// Handles both ws:// and wss:// URLs. Returns defaults if parsing fails.
@@ -352,6 +394,8 @@ pub struct TuiApp {
     needs_refresh: bool,
     /// Flag indicating node detail needs fetch
     needs_node_fetch: Option<String>,
+    /// Answer to post for a message (`:answer <id> <text>`)
+    pending_answer: Option<(String, String)>,
     /// Last key pressed (for gg detection)
     last_key: Option<KeyCode>,
     /// Flag indicating reconnection was requested
@@ -364,11 +408,23 @@ pub struct TuiApp {
     layout_chunks: Option<std::rc::Rc<[Rect]>>,
     /// Time of last fetch error (for rate limiting)
     last_fetch_error: Option<Instant>,
+    /// Bearer token for servers with authentication enabled
+    auth_token: Option<String>,
+    /// Pinned TLS configuration for wss:// servers (None = default trust roots)
+    tls_config: Option<Arc<rustls::ClientConfig>>,
+    /// HTTP client for API requests (shares the pinned TLS configuration)
+    http_client: reqwest::Client,
 }
 
 impl TuiApp {
-    /// Create a new TUI application
-    pub fn new(host: &str, port: u16) -> Self {
+    /// Create a new TUI application for the server at `host:port`
+    /// (https/wss when `tls` is set)
+    pub fn new(host: &str, port: u16, tls: bool) -> Self {
+        let (http_scheme, ws_scheme) = if tls {
+            ("https", "wss")
+        } else {
+            ("http", "ws")
+        };
         Self {
             connection_state: ConnectionState::Disconnected,
             should_quit: false,
@@ -381,16 +437,20 @@ impl TuiApp {
             node_detail_view: NodeDetailView::new(),
             log_panel: LogPanelView::new(),
             notifications: NotificationManager::new(),
-            api_base: format!("http://{}:{}", host, port),
-            ws_endpoint: format!("ws://{}:{}/ws", host, port),
+            api_base: format!("{}://{}:{}", http_scheme, host, port),
+            ws_endpoint: format!("{}://{}:{}/ws", ws_scheme, host, port),
             needs_refresh: true,
             needs_node_fetch: None,
+            pending_answer: None,
             last_key: None,
             reconnect_requested: false,
             help_visible: false,
             help_scroll_offset: 0,
             layout_chunks: None,
             last_fetch_error: None,
+            auth_token: None,
+            tls_config: None,
+            http_client: reqwest::Client::new(),
         }
     }
 
@@ -442,7 +502,7 @@ impl TuiApp {
     /// List of available commands for autocompletion.
     /// Primary names come first, followed by aliases.
     const COMMANDS: &'static [&'static str] = &[
-        "quit", "q", "help", "h", "refresh", "r", "log", "history", "hist", "clear",
+        "quit", "q", "help", "h", "refresh", "r", "log", "history", "hist", "clear", "answer",
     ];
 
     /// Attempt to autocomplete the current command input.
@@ -506,10 +566,26 @@ impl TuiApp {
 
     /// Execute the current command and return to normal mode
     fn execute_command(&mut self) {
-        let cmd = self.command_input.trim().to_lowercase();
+        let input = self.command_input.trim().to_string();
+        let cmd = input.to_lowercase();
         self.input_mode = InputMode::Normal;
         self.command_input.clear();
 
+        // `answer` keeps the case of its text
+        if cmd == "answer" || cmd.starts_with("answer ") {
+            let args = input["answer".len()..].trim();
+            match args.split_once(char::is_whitespace) {
+                Some((id, text)) if !text.trim().is_empty() => {
+                    self.pending_answer = Some((id.to_string(), text.trim().to_string()));
+                }
+                _ => {
+                    self.notifications
+                        .warning_brief("Usage: answer <message-id> <text>");
+                }
+            }
+            return;
+        }
+
         match cmd.as_str() {
             "q" | "quit" => {
                 self.should_quit = true;
@@ -1022,6 +1098,9 @@ impl TuiApp {
                                     if entity_type == "task" || entity_type == "bug" {
                                         self.needs_refresh = true;
                                     }
+                                    if entity_type == "message" {
+                                        self.notify_message(&data);
+                                    }
                                 }
                                 Change::Update {
                                     entity_type, id, ..
@@ -1044,11 +1123,71 @@ impl TuiApp {
         }
     }
 
+    /// Show a toast for a new message to `human`. Questions stay until
+    /// dismissed since an agent may be parked waiting for the answer.
+    fn notify_message(&mut self, data: &serde_json::Value) {
+        let field = |name: &str| data.get(name).and_then(|v| v.as_str()).unwrap_or("?");
+        if field("to") != "human" {
+            return;
+        }
+        if field("kind") == "question" {
+            self.notifications.notify_sticky(
+                NotificationLevel::Warning,
+                format!(
+                    "{} asks: {} (:answer {} <text>)",
+                    field("from"),
+                    field("body"),
+                    field("id")
+                ),
+            );
+        } else {
+            self.notifications
+                .info(format!("Message from {}: {}", field("from"), field("body")));
+        }
+    }
+
+    /// Post an answer to a message as `human`
+    async fn post_answer(
+        &self,
+        id: &str,
+        text: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!("{}/api/messages/{}/reply", self.api_base, id);
+        let mut request = self
+            .http_client
+            .post(&url)
+            .json(&serde_json::json!({ "body": text }));
+        if let Some(ref token) = self.auth_token {
+            request = request.bearer_auth(token);
+        }
+        let resp = request.send().await?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body: serde_json::Value = resp.json().await.unwrap_or_default();
+            let error = body
+                .get("error")
+                .and_then(|e| e.as_str())
+                .map(str::to_string)
+                .unwrap_or_else(|| status.to_string());
+            return Err(error.into());
+        }
+        Ok(())
+    }
+
+    /// Issue a GET request against the server API, authenticating if a token is set
+    async fn api_get(&self, url: &str) -> reqwest::Result<reqwest::Response> {
+        let mut request = self.http_client.get(url);
+        if let Some(ref token) = self.auth_token {
+            request = request.bearer_auth(token);
+        }
+        request.send().await
+    }
+
     /// Fetch data from the server API
     async fn fetch_data(&mut self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         // Fetch ready tasks
         let ready_url = format!("{}/api/ready", self.api_base);
-        let ready_resp = reqwest::get(&ready_url).await?;
+        let ready_resp = self.api_get(&ready_url).await?;
 
         // Check for HTTP errors before attempting to parse JSON
         if !ready_resp.status().is_success() {
@@ -1110,7 +1249,7 @@ impl TuiApp {
         node_id: &str,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let node_url = format!("{}/api/node/{}", self.api_base, node_id);
-        let node_resp = reqwest::get(&node_url).await?;
+        let node_resp = self.api_get(&node_url).await?;
 
         if !node_resp.status().is_success() {
             return Err(format!("Node not found: {}", node_id).into());
@@ -1675,6 +1814,9 @@ fn restore_terminal() -> io::Result<()> {
 /// * `port` - Server port to connect to (default: 3030)
 /// * `host` - Server host to connect to (default: localhost)
 /// * `url` - Optional WebSocket URL for remote connections (overrides port/host)
+/// * `token` - Bearer token sent to the server
+/// * `tls_pins` - Normalized certificate fingerprints trusted for the server
+/// * `tls` - Connect to `host:port` over https/wss (ignored when `url` is set)
 ///
 /// # Errors
 /// Returns an error if the server is not running or connection fails.
@@ -1682,6 +1824,9 @@ pub async fn run_tui(
     port: Option<u16>,
     host: Option<String>,
     url: Option<String>,
+    token: Option<String>,
+    tls_pins: Vec<String>,
+    tls: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // If URL is provided, use it directly; otherwise construct from host/port
     let (endpoint, display_host, display_port) = if let Some(ref ws_url) = url {
@@ -1691,33 +1836,48 @@ pub async fn run_tui(
     } else {
         let port = port.unwrap_or(DEFAULT_PORT);
         let host = host.unwrap_or_else(|| "localhost".to_string());
-        let endpoint = format!("ws://{}:{}/ws", host, port);
+        let scheme = if tls { "wss" } else { "ws" };
+        let endpoint = format!("{}://{}:{}/ws", scheme, host, port);
         (endpoint, host, port)
     };
 
-    let mut app = TuiApp::new(&display_host, display_port);
+    let mut app = TuiApp::new(&display_host, display_port, tls && url.is_none());
+    app.auth_token = token;
+    if let Some(ref ws_url) = url {
+        // Keep the URL's scheme and path (e.g., wss://) rather than rebuilding it
+        app.ws_endpoint = ws_url.clone();
+        app.api_base = api_base_from_ws_url(ws_url);
+    }
+    if !tls_pins.is_empty() {
+        let tls_config = crate::tls::pinned_client_config(tls_pins)?;
+        app.http_client = reqwest::Client::builder()
+            .use_preconfigured_tls((*tls_config).clone())
+            .build()?;
+        app.tls_config = Some(tls_config);
+    }
 
     // Try to connect to the server
-    let (ws_stream, _response) = match tokio_tungstenite::connect_async(&endpoint).await {
-        Ok(result) => result,
-        Err(e) => {
-            if url.is_some() {
-                // Remote URL connection failed
-                eprintln!("Error: Failed to connect to remote session at {}", endpoint);
-                eprintln!("\nDetails: {}", e);
-            } else {
-                // Local connection failed - auto-launch should have started the server
-                eprintln!(
-                    "Error: No session server detected at {}:{}",
-                    display_host, display_port
-                );
-                eprintln!("Session server should start automatically. If problems persist,");
-                eprintln!("check for errors in: bn session serve");
-                eprintln!("\nDetails: {}", e);
+    let (ws_stream, _response) =
+        match connect_ws(&endpoint, app.auth_token.as_deref(), app.tls_config.clone()).await {
+            Ok(result) => result,
+            Err(e) => {
+                if url.is_some() {
+                    // Remote URL connection failed
+                    eprintln!("Error: Failed to connect to remote session at {}", endpoint);
+                    eprintln!("\nDetails: {}", e);
+                } else {
+                    // Local connection failed - auto-launch should have started the server
+                    eprintln!(
+                        "Error: No session server detected at {}:{}",
+                        display_host, display_port
+                    );
+                    eprintln!("Session server should start automatically. If problems persist,");
+                    eprintln!("check for errors in: bn session serve");
+                    eprintln!("\nDetails: {}", e);
+                }
+                std::process::exit(1);
             }
-            std::process::exit(1);
-        }
-    };
+        };
 
     app.set_connected();
     let (_write, read) = ws_stream.split();
@@ -1759,6 +1919,16 @@ pub async fn run_tui(
             }
         }
 
+        // Post a pending answer (`:answer <id> <text>`)
+        if let Some((id, text)) = app.pending_answer.take() {
+            match app.post_answer(&id, &text).await {
+                Ok(()) => app.notifications.success(format!("Answered {}", id)),
+                Err(e) => app
+                    .notifications
+                    .error(format!("Failed to answer {}: {}", id, e)),
+            }
+        }
+
         // Render the UI
         terminal.draw(|f| app.render(f))?;
 
@@ -1774,7 +1944,13 @@ pub async fn run_tui(
 
         // Attempt reconnection if it's time
         if app.should_attempt_reconnect() {
-            match tokio_tungstenite::connect_async(&app.ws_endpoint).await {
+            match connect_ws(
+                &app.ws_endpoint,
+                app.auth_token.as_deref(),
+                app.tls_config.clone(),
+            )
+            .await
+            {
                 Ok((new_ws, _)) => {
                     app.handle_reconnect_success();
                     let (_, new_read) = new_ws.split();
@@ -1890,6 +2066,18 @@ mod tests {
         assert_eq!(port, 9000);
     }
 
+    #[test]
+    fn test_api_base_from_ws_url() {
+        assert_eq!(
+            api_base_from_ws_url("wss://remote.example.com:3030/ws"),
+            "https://remote.example.com:3030"
+        );
+        assert_eq!(
+            api_base_from_ws_url("ws://localhost:9000"),
+            "http://localhost:9000"
+        );
+    }
+
     #[test]
     fn test_common_prefix_single_string() {
         let result = TuiApp::common_prefix(&["hello"]);
@@ -1920,6 +2108,33 @@ mod tests {
         assert_eq!(result, None);
     }
 
+    #[test]
+    fn test_local_endpoints_follow_tls() {
+        let app = TuiApp::new("localhost", 3030, false);
+        assert_eq!(app.api_base, "http://localhost:3030");
+        assert_eq!(app.ws_endpoint, "ws://localhost:3030/ws");
+
+        let app = TuiApp::new("localhost", 3030, true);
+        assert_eq!(app.api_base, "https://localhost:3030");
+        assert_eq!(app.ws_endpoint, "wss://localhost:3030/ws");
+    }
+
+    #[test]
+    fn test_answer_command_keeps_case() {
+        let mut app = TuiApp::new("localhost", 3030, false);
+        app.command_input = "answer bnmsg-a1b2 Use the Staging DB".to_string();
+        app.execute_command();
+        assert_eq!(
+            app.pending_answer,
+            Some(("bnmsg-a1b2".to_string(), "Use the Staging DB".to_string()))
+        );
+
+        app.pending_answer = None;
+        app.command_input = "answer bnmsg-a1b2".to_string();
+        app.execute_command();
+        assert_eq!(app.pending_answer, None);
+    }
+
     #[test]
     fn test_commands_list_not_empty() {
         assert!(!TuiApp::COMMANDS.is_empty());
@@ -270,6 +270,24 @@ impl NotificationManager {
         self.update_overflow();
     }
 
+    /// Add a notification that stays until dismissed
+    pub fn notify_sticky(&mut self, level: NotificationLevel, message: impl Into<String>) {
+        let toast = Toast::sticky(self.next_id, level, message);
+        self.next_id += 1;
+
+        // Add to history
+        self.history.push_front(HistoryEntry::from_toast(&toast));
+        if self.history.len() > MAX_HISTORY_ENTRIES {
+            self.history.pop_back();
+        }
+
+        // Add to active toasts
+        self.toasts.push_front(toast);
+
+        // Update overflow count
+        self.update_overflow();
+    }
+
     /// Dismiss any toasts that are marked as dismiss_on_keypress
     /// Returns true if any toasts were dismissed
     pub fn dismiss_on_keypress(&mut self) -> bool {
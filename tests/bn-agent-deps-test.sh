@@ -39,13 +39,13 @@ else
 fi
 echo ""
 
-# Test 4: Verify copilot check exists
-echo "Test 4: copilot CLI check exists"
-if grep -q "bn system copilot path" ./scripts/bn-agent && \
-   grep -q "GitHub Copilot CLI: Not found" ./scripts/bn-agent; then
-    echo "✓ copilot CLI check found with error message"
+# Test 4: Verify agent CLI check exists
+echo "Test 4: agent CLI check exists"
+if grep -q "bn config agents launch" ./scripts/bn-agent && \
+   grep -q "bn system copilot install" ./scripts/bn-agent; then
+    echo "✓ agent CLI check found with error message"
 else
-    echo "✗ copilot CLI check missing or incomplete"
+    echo "✗ agent CLI check missing or incomplete"
     exit 1
 fi
 echo ""
@@ -0,0 +1,207 @@
+//! Integration tests for per-agent command policies.
+//!
+//! These tests verify that:
+//! - Planners can create tasks but not close them
+//! - Ask agents are read-only
+//! - Workers can't delete, via registration or `BN_AGENT_TYPE`
+//! - A registered agent can't re-orient as another type
+//! - Unidentified callers in an agent container are refused
+//! - Humans are unrestricted and denials reach the action log
+//! - Project `commands` blocks extend the built-in policy
+
+mod common;
+
+use assert_cmd::Command;
+use common::TestEnv;
+use predicates::prelude::*;
+use std::fs;
+
+fn run_json(cmd: &mut Command) -> serde_json::Value {
+    let output = cmd.assert().success().get_output().stdout.clone();
+    serde_json::from_slice(&output).expect("Failed to parse JSON output")
+}
+
+/// Register an agent of `agent_type` under `id`.
+fn register(env: &TestEnv, id: &str, agent_type: &str) {
+    env.bn()
+        .env("BN_AGENT_ID", id)
+        .args(["orient", "--type", agent_type, "--name", id])
+        .assert()
+        .success();
+}
+
+fn as_agent(env: &TestEnv, id: &str) -> Command {
+    let mut cmd = env.bn();
+    cmd.env("BN_AGENT_ID", id);
+    cmd
+}
+
+fn create_task(env: &TestEnv, title: &str) -> String {
+    let task = run_json(env.bn().args(["task", "create", title]));
+    task["id"].as_str().unwrap().to_string()
+}
+
+#[test]
+fn test_planner_creates_but_cannot_close() {
+    let env = TestEnv::init();
+    register(&env, "bn-planner", "planner");
+
+    let task = run_json(as_agent(&env, "bn-planner").args(["task", "create", "Planned"]));
+    let task_id = task["id"].as_str().unwrap();
+    as_agent(&env, "bn-planner")
+        .args(["task", "close", task_id, "--reason", "done"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Permission denied: prd agents may not run `bn task close`",
+        ));
+
+    // The denial is in the action log
+    let log = fs::read_to_string(env.data_path().join("action.log")).unwrap();
+    let denial = log
+        .lines()
+        .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap())
+        .find(|e| e["command"] == "task close")
+        .expect("denied command should be logged");
+    assert_eq!(denial["success"], false);
+    assert!(
+        denial["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("Permission denied")
+    );
+
+    // Humans are not restricted
+    env.bn()
+        .args(["task", "close", task_id, "--reason", "done"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_ask_agents_are_read_only() {
+    let env = TestEnv::init();
+    let task_id = create_task(&env, "Existing");
+    register(&env, "bn-asker", "ask");
+
+    as_agent(&env, "bn-asker")
+        .args(["task", "list"])
+        .assert()
+        .success();
+    as_agent(&env, "bn-asker")
+        .args(["task", "show", &task_id])
+        .assert()
+        .success();
+    as_agent(&env, "bn-asker")
+        .args(["task", "create", "Not allowed"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not in the allowed commands"));
+    as_agent(&env, "bn-asker")
+        .args(["goodbye", "--dry-run"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_orient_cannot_change_agent_type() {
+    let env = TestEnv::init();
+    register(&env, "bn-asker", "ask");
+
+    as_agent(&env, "bn-asker")
+        .args(["orient", "--type", "worker"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "agent bn-asker is registered as ask",
+        ));
+    // Still read-only
+    as_agent(&env, "bn-asker")
+        .args(["task", "create", "Not allowed"])
+        .assert()
+        .failure();
+    // Re-orienting as the same type is fine
+    as_agent(&env, "bn-asker")
+        .args(["orient", "--type", "ask"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_unidentified_container_caller_is_refused() {
+    let env = TestEnv::init();
+
+    env.bn()
+        .env("BN_CONTAINER_MODE", "true")
+        .args(["task", "create", "Not allowed"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unidentified agents may not run"));
+    env.bn()
+        .env("BN_CONTAINER_MODE", "true")
+        .args(["goodbye", "--dry-run"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_workers_cannot_delete() {
+    let env = TestEnv::init();
+    let task_id = create_task(&env, "Keep me");
+    register(&env, "bn-worker", "worker");
+
+    as_agent(&env, "bn-worker")
+        .args(["task", "update", &task_id, "--status", "in_progress"])
+        .assert()
+        .success();
+    as_agent(&env, "bn-worker")
+        .args(["task", "delete", &task_id])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("denied by '* delete'"));
+
+    // Before registering, BN_AGENT_TYPE decides
+    env.bn()
+        .env("BN_AGENT_TYPE", "worker")
+        .args(["task", "delete", &task_id])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Permission denied"));
+    env.bn().args(["task", "show", &task_id]).assert().success();
+}
+
+#[test]
+fn test_project_config_extends_policy() {
+    let env = TestEnv::init();
+    let config_dir = env.repo_path().join(".binnacle").join("agents");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        config_dir.join("config.kdl"),
+        r#"
+agent "worker" {
+    commands {
+        deny "milestone *"
+    }
+}
+"#,
+    )
+    .unwrap();
+    register(&env, "bn-worker", "worker");
+
+    as_agent(&env, "bn-worker")
+        .args(["milestone", "create", "v1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("denied by 'milestone *'"));
+    as_agent(&env, "bn-worker")
+        .args(["task", "create", "Still fine"])
+        .assert()
+        .success();
+
+    env.bn()
+        .args(["config", "agents", "show", "worker", "-H"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("bn Commands Denied:"))
+        .stdout(predicate::str::contains("  milestone *"));
+}
@@ -0,0 +1,121 @@
+//! Integration tests for the `bn agent supervise` command.
+//!
+//! These tests run single dry-run passes, so no containers are started:
+//! - Scaling decisions are reported as supervisor events
+//! - Supervisor settings are read from config.kdl
+//! - Invalid settings fail the pass
+
+mod common;
+
+use assert_cmd::Command;
+use common::TestEnv;
+use predicates::prelude::*;
+use std::fs;
+
+/// Get a Command for the bn binary in a TestEnv.
+fn bn_in(env: &TestEnv) -> Command {
+    env.bn()
+}
+
+/// Session config.kdl path for a TestEnv.
+fn session_config_path(env: &TestEnv) -> std::path::PathBuf {
+    use sha2::{Digest, Sha256};
+    let canonical = env.repo_path().canonicalize().unwrap();
+    let mut hasher = Sha256::new();
+    hasher.update(canonical.to_string_lossy().as_bytes());
+    let hash_hex = format!("{:x}", hasher.finalize());
+    env.data_path().join(&hash_hex[..12]).join("config.kdl")
+}
+
+/// Append a snippet to the session config.kdl.
+fn append_config(env: &TestEnv, kdl: &str) {
+    let path = session_config_path(env);
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+    fs::write(&path, format!("{}\n{}\n", existing, kdl)).unwrap();
+}
+
+fn parse_json(output: &[u8]) -> serde_json::Value {
+    serde_json::from_slice(output).expect("Failed to parse JSON output")
+}
+
+#[test]
+fn test_agent_supervise_help() {
+    let env = TestEnv::new();
+
+    bn_in(&env)
+        .args(["agent", "supervise", "--help"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--once"))
+        .stdout(predicate::str::contains("--interval"))
+        .stdout(predicate::str::contains("--dry-run"));
+}
+
+#[test]
+fn test_agent_supervise_once_without_work() {
+    let env = TestEnv::init();
+
+    let output = bn_in(&env)
+        .args(["agent", "supervise", "--once", "--dry-run"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let status = parse_json(&output);
+    assert_eq!(status["passes"], 1);
+    assert_eq!(status["dry_run"], true);
+    assert_eq!(status["work_count"], 0);
+    assert_eq!(status["interval_secs"], 15);
+    assert!(status["events"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn test_agent_supervise_reports_scale_up_event() {
+    let env = TestEnv::init();
+
+    bn_in(&env)
+        .args(["agent", "scale", "worker", "--min", "0", "--max", "2"])
+        .assert()
+        .success();
+    bn_in(&env)
+        .args(["task", "create", "Supervised work"])
+        .assert()
+        .success();
+    append_config(&env, "supervisor {\n  interval-secs 30\n}");
+
+    let output = bn_in(&env)
+        .args(["agent", "supervise", "--once", "--dry-run"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let status = parse_json(&output);
+    assert_eq!(status["interval_secs"], 30);
+    assert_eq!(status["desired_counts"]["worker"], 1);
+    let events = status["events"].as_array().unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["kind"], "spawn");
+    assert_eq!(events[0]["agent_type"], "worker");
+    assert_eq!(events[0]["executed"], false);
+
+    bn_in(&env)
+        .args(["agent", "supervise", "--once", "--dry-run", "-H"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Supervisor pass 1 (dry run):"))
+        .stdout(predicate::str::contains("spawn worker"));
+}
+
+#[test]
+fn test_agent_supervise_invalid_config_fails() {
+    let env = TestEnv::init();
+    append_config(&env, "supervisor {\n  bogus 1\n}");
+
+    bn_in(&env)
+        .args(["agent", "supervise", "--once", "--dry-run"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown setting 'bogus'"));
+}
@@ -0,0 +1,138 @@
+//! Integration tests for `bn agent transcript`.
+//!
+//! These tests verify that:
+//! - bn commands run by an agent are mirrored into its transcript
+//! - Captured output lines are timestamped into the transcript and raw log
+//! - Transcripts are linked from the tasks the agent worked on
+//! - `--follow` ends once the agent is no longer registered
+
+mod common;
+
+use assert_cmd::Command;
+use common::TestEnv;
+use predicates::prelude::*;
+use std::fs;
+
+const AGENT_ID: &str = "bn-tran-1";
+
+/// Get a Command for the bn binary in a TestEnv.
+fn bn_in(env: &TestEnv) -> Command {
+    env.bn()
+}
+
+/// Get a Command running as the registered test agent.
+fn bn_as_agent(env: &TestEnv) -> Command {
+    let mut cmd = env.bn();
+    cmd.env("BN_AGENT_ID", AGENT_ID);
+    cmd
+}
+
+fn parse_json(output: &[u8]) -> serde_json::Value {
+    serde_json::from_slice(output).expect("Failed to parse JSON output")
+}
+
+fn run_json(cmd: &mut Command) -> serde_json::Value {
+    let output = cmd.assert().success().get_output().stdout.clone();
+    parse_json(&output)
+}
+
+#[test]
+fn test_transcript_records_agent_commands() {
+    let env = TestEnv::init();
+    bn_as_agent(&env)
+        .args(["orient", "--type", "worker", "--name", "transcript-worker"])
+        .assert()
+        .success();
+    let task = run_json(bn_in(&env).args(["task", "create", "Recorded work"]));
+    let task_id = task["id"].as_str().unwrap().to_string();
+    bn_as_agent(&env)
+        .args(["task", "update", &task_id, "--status", "in_progress"])
+        .assert()
+        .success();
+
+    let transcript = run_json(bn_in(&env).args(["agent", "transcript", AGENT_ID]));
+    assert_eq!(transcript["agent_id"], AGENT_ID);
+    assert_eq!(transcript["tasks"], serde_json::json!([task_id]));
+    let entries = transcript["entries"].as_array().unwrap();
+    let commands: Vec<&str> = entries
+        .iter()
+        .filter(|e| e["kind"] == "command")
+        .map(|e| e["text"].as_str().unwrap())
+        .collect();
+    assert_eq!(commands, vec!["orient", "task update"]);
+    // Commands run without BN_AGENT_ID are not attributed to the agent
+    assert!(!commands.contains(&"task create"));
+
+    // Agents can be looked up by name, too
+    bn_in(&env)
+        .args(["agent", "transcript", "transcript-worker", "-H"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("$ bn task update (ok,"));
+
+    let show = run_json(bn_in(&env).args(["task", "show", &task_id]));
+    assert_eq!(show["transcripts"][0]["agent_id"], AGENT_ID);
+    bn_in(&env)
+        .args(["task", "show", &task_id, "-H"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Agent Transcripts (1):"));
+}
+
+#[test]
+fn test_transcript_capture_and_follow() {
+    let env = TestEnv::init();
+    let raw_log = env.data_path().join("raw.log");
+
+    let capture = run_json(
+        bn_in(&env)
+            .args(["agent", "transcript", "bn-cap-1", "--capture", "stderr"])
+            .arg("--raw-log")
+            .arg(&raw_log)
+            .write_stdin("compiling\nwarning: unused\n"),
+    );
+    assert_eq!(capture["lines"], 2);
+    assert_eq!(
+        fs::read_to_string(&raw_log).unwrap(),
+        "compiling\nwarning: unused\n"
+    );
+
+    // The agent is not registered, so --follow prints what exists and exits
+    let output = bn_in(&env)
+        .args(["agent", "transcript", "bn-cap-1", "--follow"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let lines: Vec<serde_json::Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["kind"], "stderr");
+    assert_eq!(lines[1]["text"], "warning: unused");
+
+    bn_in(&env)
+        .args(["agent", "transcript", "bn-cap-1", "--follow", "-H"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("! compiling"));
+}
+
+#[test]
+fn test_transcript_missing_or_invalid() {
+    let env = TestEnv::init();
+
+    bn_in(&env)
+        .args(["agent", "transcript", "bn-none"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No transcript for agent bn-none"));
+
+    bn_in(&env)
+        .args(["agent", "transcript", "bn-none", "--capture", "stdin"])
+        .assert()
+        .failure();
+}
@@ -0,0 +1,226 @@
+//! Integration tests for `bn agent usage` and usage budgets.
+//!
+//! These tests verify that:
+//! - Usage reports are stored on the agent and attributed to its tasks
+//! - Budgets from config.kdl are charged and reported as exhausted
+//! - Exhausted budgets pause supervisor spawning and show up in `bn ready`
+
+mod common;
+
+use assert_cmd::Command;
+use common::TestEnv;
+use predicates::prelude::*;
+use std::fs;
+
+const AGENT_ID: &str = "bn-usage-1";
+
+/// Get a Command for the bn binary in a TestEnv.
+fn bn_in(env: &TestEnv) -> Command {
+    env.bn()
+}
+
+/// Get a Command running as the registered test agent.
+fn bn_as_agent(env: &TestEnv) -> Command {
+    let mut cmd = env.bn();
+    cmd.env("BN_AGENT_ID", AGENT_ID);
+    cmd
+}
+
+/// Session config.kdl path for a TestEnv.
+fn session_config_path(env: &TestEnv) -> std::path::PathBuf {
+    use sha2::{Digest, Sha256};
+    let canonical = env.repo_path().canonicalize().unwrap();
+    let mut hasher = Sha256::new();
+    hasher.update(canonical.to_string_lossy().as_bytes());
+    let hash_hex = format!("{:x}", hasher.finalize());
+    env.data_path().join(&hash_hex[..12]).join("config.kdl")
+}
+
+/// Append a snippet to the session config.kdl.
+fn append_config(env: &TestEnv, kdl: &str) {
+    let path = session_config_path(env);
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+    fs::write(&path, format!("{}\n{}\n", existing, kdl)).unwrap();
+}
+
+fn parse_json(output: &[u8]) -> serde_json::Value {
+    serde_json::from_slice(output).expect("Failed to parse JSON output")
+}
+
+fn run_json(cmd: &mut Command) -> serde_json::Value {
+    let output = cmd.assert().success().get_output().stdout.clone();
+    parse_json(&output)
+}
+
+/// Initialize a repo with a registered worker claiming one task.
+fn setup_worker_with_task() -> (TestEnv, String) {
+    let env = TestEnv::init();
+    bn_as_agent(&env)
+        .args(["orient", "--type", "worker", "--name", "usage-worker"])
+        .assert()
+        .success();
+    let task = run_json(bn_in(&env).args(["task", "create", "Metered work"]));
+    let task_id = task["id"].as_str().unwrap().to_string();
+    bn_as_agent(&env)
+        .args(["task", "update", &task_id, "--status", "in_progress"])
+        .assert()
+        .success();
+    (env, task_id)
+}
+
+#[test]
+fn test_usage_report_requires_registered_agent() {
+    let env = TestEnv::init();
+
+    bn_in(&env)
+        .args([
+            "agent",
+            "usage",
+            "report",
+            "--agent",
+            "bn-missing",
+            "--requests",
+            "1",
+        ])
+        .assert()
+        .failure();
+
+    bn_in(&env)
+        .args(["agent", "usage", "report"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Nothing to report"));
+}
+
+#[test]
+fn test_usage_report_records_and_attributes() {
+    let (env, task_id) = setup_worker_with_task();
+
+    for _ in 0..2 {
+        let report = run_json(bn_as_agent(&env).args([
+            "agent",
+            "usage",
+            "report",
+            "--input-tokens",
+            "1000",
+            "--output-tokens",
+            "500",
+            "--requests",
+            "2",
+            "--cost",
+            "0.25",
+        ]));
+        assert_eq!(report["agent_id"], AGENT_ID);
+        assert_eq!(report["tasks"], serde_json::json!([task_id]));
+        assert_eq!(report["should_goodbye"], false);
+    }
+
+    let show = run_json(bn_in(&env).args(["agent", "usage", "show"]));
+    assert_eq!(show["total"]["input_tokens"], 2000);
+    assert_eq!(show["total"]["requests"], 4);
+    assert_eq!(show["by_agent_type"]["worker"]["output_tokens"], 1000);
+    assert_eq!(show["by_task"][&task_id]["cost_usd"], 0.5);
+    assert_eq!(show["agents"][0]["id"], AGENT_ID);
+    assert_eq!(show["agents"][0]["usage"]["input_tokens"], 2000);
+
+    bn_in(&env)
+        .args(["agent", "usage", "show", "-H"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Total usage: 3000 tokens"));
+}
+
+#[test]
+fn test_milestone_budget_exhaustion_pauses_workers() {
+    let (env, task_id) = setup_worker_with_task();
+    let milestone = run_json(bn_in(&env).args(["milestone", "create", "Metered milestone"]));
+    let milestone_id = milestone["id"].as_str().unwrap().to_string();
+    bn_in(&env)
+        .args([
+            "link",
+            "add",
+            &task_id,
+            &milestone_id,
+            "-t",
+            "child_of",
+            "--reason",
+            "test",
+        ])
+        .assert()
+        .success();
+    bn_in(&env)
+        .args(["agent", "scale", "worker", "--min", "0", "--max", "2"])
+        .assert()
+        .success();
+    bn_in(&env)
+        .args(["task", "create", "More work"])
+        .assert()
+        .success();
+    append_config(
+        &env,
+        &format!(
+            "budgets {{\n  milestone \"{}\" tokens=1000\n}}",
+            milestone_id
+        ),
+    );
+
+    let report =
+        run_json(bn_as_agent(&env).args(["agent", "usage", "report", "--input-tokens", "1200"]));
+    assert_eq!(report["should_goodbye"], true);
+    assert_eq!(
+        report["exhausted_budgets"][0]["exhausted"],
+        "tokens 1200/1000"
+    );
+
+    let ready = run_json(bn_in(&env).args(["ready"]));
+    let exhausted = ready["budget_exhausted"].as_array().unwrap();
+    assert_eq!(exhausted.len(), 1);
+    assert!(exhausted[0].as_str().unwrap().contains(&milestone_id));
+
+    let status = run_json(bn_in(&env).args(["agent", "supervise", "--once", "--dry-run"]));
+    assert_eq!(status["paused"], serde_json::json!(["worker"]));
+    let events = status["events"].as_array().unwrap();
+    assert!(events.iter().any(|e| e["kind"] == "pause"));
+    assert!(!events.iter().any(|e| e["kind"] == "spawn"));
+}
+
+#[test]
+fn test_milestone_budget_counts_nested_tasks() {
+    let (env, task_id) = setup_worker_with_task();
+    let milestone = run_json(bn_in(&env).args(["milestone", "create", "Metered milestone"]));
+    let milestone_id = milestone["id"].as_str().unwrap().to_string();
+    let parent = run_json(bn_in(&env).args(["task", "create", "Parent task"]));
+    let parent_id = parent["id"].as_str().unwrap().to_string();
+    for (child, parent) in [(&parent_id, &milestone_id), (&task_id, &parent_id)] {
+        bn_in(&env)
+            .args([
+                "link", "add", child, parent, "-t", "child_of", "--reason", "test",
+            ])
+            .assert()
+            .success();
+    }
+    append_config(
+        &env,
+        &format!(
+            "budgets {{\n  milestone \"{}\" tokens=1000\n}}",
+            milestone_id
+        ),
+    );
+
+    // Usage on a grandchild task counts against the milestone
+    let report =
+        run_json(bn_as_agent(&env).args(["agent", "usage", "report", "--input-tokens", "1200"]));
+    assert_eq!(report["should_goodbye"], true);
+}
+
+#[test]
+fn test_invalid_budget_config_fails() {
+    let env = TestEnv::init();
+    append_config(&env, "budgets {\n  queue dollars=5\n}");
+
+    bn_in(&env)
+        .args(["agent", "usage", "show"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown limit 'dollars'"));
+}
@@ -0,0 +1,204 @@
+//! Integration tests for `bn agent worktree`.
+//!
+//! These tests verify that:
+//! - Task worktrees are created on `bn/<task-id>` branches and listed
+//! - `finish` runs the task's linked tests before fast-forwarding the target
+//! - Merge conflicts and failing tests file a bug linked to the task
+//! - `gc` removes worktrees of closed, merged tasks and keeps the rest
+
+mod common;
+
+use assert_cmd::Command;
+use common::{TestEnv, git};
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+
+fn commit_file(dir: &Path, name: &str, contents: &str) {
+    fs::write(dir.join(name), contents).unwrap();
+    git(dir, &["add", "-A"]);
+    git(dir, &["commit", "-q", "-m", &format!("Update {}", name)]);
+}
+
+fn run_json(cmd: &mut Command) -> serde_json::Value {
+    let output = cmd.assert().success().get_output().stdout.clone();
+    serde_json::from_slice(&output).expect("Failed to parse JSON output")
+}
+
+fn create_task(env: &TestEnv, title: &str) -> String {
+    let task = run_json(env.bn().args(["task", "create", title]));
+    task["id"].as_str().unwrap().to_string()
+}
+
+fn create_worktree(env: &TestEnv, task_id: &str) -> String {
+    let created = run_json(env.bn().args(["agent", "worktree", "create", task_id]));
+    assert_eq!(created["created"], true);
+    assert_eq!(created["branch"], format!("bn/{}", task_id));
+    created["path"].as_str().unwrap().to_string()
+}
+
+fn caused_by_target(env: &TestEnv, bug_id: &str) -> String {
+    let links = run_json(env.bn().args(["link", "list", bug_id]));
+    links["edges"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|l| l["edge_type"] == "caused_by")
+        .map(|l| l["target"].as_str().unwrap().to_string())
+        .expect("bug should have a caused_by link")
+}
+
+#[test]
+fn test_finish_runs_tests_and_merges() {
+    let env = TestEnv::init_git();
+    let task_id = create_task(&env, "Add feature");
+    env.bn()
+        .args([
+            "test",
+            "create",
+            "Feature exists",
+            "--cmd",
+            "test -f feature.txt",
+        ])
+        .args(["--task", &task_id])
+        .assert()
+        .success();
+
+    let path = create_worktree(&env, &task_id);
+    let list = run_json(env.bn().args(["agent", "worktree", "list"]));
+    assert_eq!(list["count"], 1);
+    assert_eq!(list["worktrees"][0]["task_id"], task_id.as_str());
+    assert_eq!(list["worktrees"][0]["task_status"], "pending");
+
+    commit_file(Path::new(&path), "feature.txt", "done\n");
+    let finish = run_json(
+        env.bn()
+            .args(["agent", "worktree", "finish", &task_id, "--target", "main"]),
+    );
+    assert_eq!(finish["merged"], true);
+    assert_eq!(finish["tests_run"], 1);
+    assert!(finish.get("bug_id").is_none());
+    // main is checked out in the repo, so its files were updated too
+    assert!(env.repo_path().join("feature.txt").exists());
+
+    // Still open: gc keeps it
+    let gc = run_json(
+        env.bn()
+            .args(["agent", "worktree", "gc", "--target", "main"]),
+    );
+    assert_eq!(gc["removed"].as_array().unwrap().len(), 0);
+    assert_eq!(gc["kept"][0]["reason"], "task is pending");
+
+    env.bn()
+        .args(["task", "close", &task_id, "--reason", "done", "--force"])
+        .assert()
+        .success();
+    env.bn()
+        .args([
+            "agent",
+            "worktree",
+            "gc",
+            "--target",
+            "main",
+            "--dry-run",
+            "-H",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would remove 1 worktree(s)"));
+    let gc = run_json(
+        env.bn()
+            .args(["agent", "worktree", "gc", "--target", "main"]),
+    );
+    assert_eq!(gc["removed"][0]["task_id"], task_id.as_str());
+    assert!(!Path::new(&path).exists());
+    assert_eq!(git(env.repo_path(), &["branch", "--list", "bn/*"]), "");
+}
+
+#[test]
+fn test_failing_test_files_bug() {
+    let env = TestEnv::init_git();
+    let task_id = create_task(&env, "Broken feature");
+    env.bn()
+        .args(["test", "create", "Always fails", "--cmd", "false"])
+        .args(["--task", &task_id])
+        .assert()
+        .success();
+    let path = create_worktree(&env, &task_id);
+    commit_file(Path::new(&path), "feature.txt", "wip\n");
+    let main_before = git(env.repo_path(), &["rev-parse", "main"]);
+
+    let finish = run_json(
+        env.bn()
+            .args(["agent", "worktree", "finish", &task_id, "--target", "main"]),
+    );
+    assert_eq!(finish["merged"], false);
+    assert_eq!(finish["failed_tests"].as_array().unwrap().len(), 1);
+    let bug_id = finish["bug_id"].as_str().unwrap();
+    assert_eq!(caused_by_target(&env, bug_id), task_id);
+    assert_eq!(git(env.repo_path(), &["rev-parse", "main"]), main_before);
+
+    // Skipping tests merges anyway
+    let finish = run_json(env.bn().args([
+        "agent",
+        "worktree",
+        "finish",
+        &task_id,
+        "--target",
+        "main",
+        "--no-tests",
+    ]));
+    assert_eq!(finish["merged"], true);
+    assert_eq!(finish["tests_run"], 0);
+}
+
+#[test]
+fn test_conflict_files_bug() {
+    let env = TestEnv::init_git();
+    let task_id = create_task(&env, "Edit readme");
+    let path = create_worktree(&env, &task_id);
+    commit_file(Path::new(&path), "README.md", "from the task\n");
+    commit_file(env.repo_path(), "README.md", "from main\n");
+
+    let finish = run_json(
+        env.bn()
+            .args(["agent", "worktree", "finish", &task_id, "--target", "main"]),
+    );
+    assert_eq!(finish["merged"], false);
+    assert_eq!(finish["conflicts"], serde_json::json!(["README.md"]));
+    let bug_id = finish["bug_id"].as_str().unwrap();
+    assert_eq!(caused_by_target(&env, bug_id), task_id);
+    // The aborted merge leaves the worktree clean
+    assert_eq!(git(Path::new(&path), &["status", "--porcelain"]), "");
+}
+
+#[test]
+fn test_worktree_errors() {
+    let env = TestEnv::init_git();
+    env.bn()
+        .args(["agent", "worktree", "create", "bn-none"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Task not found: bn-none"));
+
+    let task_id = create_task(&env, "No worktree yet");
+    env.bn()
+        .args(["agent", "worktree", "finish", &task_id])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No worktree for"));
+
+    env.bn()
+        .args([
+            "agent",
+            "spawn",
+            "worker",
+            "--task",
+            &task_id,
+            "--worktree",
+            ".",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
@@ -0,0 +1,111 @@
+//! Integration tests for `bn blame`.
+//!
+//! These tests verify that:
+//! - Lines are annotated with the tasks and bugs linked to their commits
+//! - A line or range limits the output
+//! - Uncommitted lines and unlinked commits carry no entities
+
+mod common;
+
+use assert_cmd::Command;
+use common::{TestEnv, git};
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+
+/// Write `content` to `file` and commit it, returning the new SHA.
+fn commit(dir: &Path, file: &str, content: &str) -> String {
+    fs::write(dir.join(file), content).unwrap();
+    git(dir, &["add", file]);
+    git(dir, &["commit", "-q", "-m", &format!("Update {}", file)]);
+    git(dir, &["rev-parse", "HEAD"])
+}
+
+fn run_json(cmd: &mut Command) -> serde_json::Value {
+    let output = cmd.assert().success().get_output().stdout.clone();
+    serde_json::from_slice(&output).expect("Failed to parse JSON output")
+}
+
+fn create(env: &TestEnv, args: &[&str]) -> String {
+    run_json(env.bn().args(args))["id"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn test_blame_annotates_lines() {
+    let env = TestEnv::init_git();
+    let repo = env.repo_path();
+    let task_id = create(&env, &["task", "create", "Parser"]);
+    let bug_id = create(&env, &["bug", "create", "Parser crash"]);
+
+    let first = commit(repo, "parser.rs", "fn parse() {}\n");
+    let second = commit(repo, "parser.rs", "fn parse() {}\nfn guard() {}\n");
+    env.bn()
+        .args(["commit", "link", &first, &task_id])
+        .assert()
+        .success();
+    // Abbreviated SHAs work too
+    env.bn()
+        .args(["commit", "link", &second[..8], &bug_id])
+        .assert()
+        .success();
+    env.bn()
+        .args(["commit", "link", &second, &task_id])
+        .assert()
+        .success();
+    fs::write(
+        repo.join("parser.rs"),
+        "fn parse() {}\nfn guard() {}\nfn wip() {}\n",
+    )
+    .unwrap();
+
+    let blame = run_json(env.bn().args(["blame", "parser.rs"]));
+    assert_eq!(blame["file"], "parser.rs");
+    let lines = blame["lines"].as_array().unwrap();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0]["commit"], first.as_str());
+    assert_eq!(lines[0]["content"], "fn parse() {}");
+    assert_eq!(lines[0]["entities"][0]["id"], task_id.as_str());
+    assert_eq!(lines[0]["entities"][0]["title"], "Parser");
+    assert_eq!(lines[0]["entities"][0]["type"], "task");
+    let ids: Vec<&str> = lines[1]["entities"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids.len(), 2);
+    assert!(ids.contains(&bug_id.as_str()) && ids.contains(&task_id.as_str()));
+    assert!(lines[2]["commit"].is_null());
+    assert_eq!(lines[2]["entities"], serde_json::json!([]));
+
+    let blame = run_json(env.bn().args(["blame", "parser.rs:2"]));
+    assert_eq!(blame["lines"].as_array().unwrap().len(), 1);
+    assert_eq!(blame["lines"][0]["line"], 2);
+
+    env.bn()
+        .args(["-H", "blame", "parser.rs:1-2"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("| fn parse() {}"))
+        .stdout(predicate::str::contains(format!(
+            "{} [task] Parser",
+            task_id
+        )));
+}
+
+#[test]
+fn test_blame_errors() {
+    let env = TestEnv::init_git();
+    let blame = run_json(env.bn().args(["blame", "README.md"]));
+    assert_eq!(blame["lines"][0]["entities"], serde_json::json!([]));
+
+    env.bn()
+        .args(["blame", "missing.rs"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("git blame failed"));
+    env.bn().args(["blame", "README.md:5-2"]).assert().failure();
+}
@@ -0,0 +1,202 @@
+//! Integration tests for commit trailers.
+//!
+//! These tests verify that:
+//! - `bn commit scan` links commits from trailers, or bare mentions when a
+//!   message has no trailers
+//! - `Fixes:` trailers close bugs once the commit is on the default branch,
+//!   not whichever branch is checked out
+//! - `bn doctor` reports trailers without matching links
+
+mod common;
+
+use assert_cmd::Command;
+use common::{TestEnv, git};
+use std::fs;
+use std::path::Path;
+
+/// Commit a change to `file` with `message`, returning the new SHA.
+fn commit(dir: &Path, file: &str, message: &str) -> String {
+    fs::write(dir.join(file), message).unwrap();
+    git(dir, &["add", file]);
+    git(dir, &["commit", "-q", "-m", message]);
+    git(dir, &["rev-parse", "HEAD"])
+}
+
+fn run_json(cmd: &mut Command) -> serde_json::Value {
+    let output = cmd.assert().success().get_output().stdout.clone();
+    serde_json::from_slice(&output).expect("Failed to parse JSON output")
+}
+
+fn create(env: &TestEnv, args: &[&str]) -> String {
+    run_json(env.bn().args(args))["id"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+fn linked_shas(env: &TestEnv, id: &str) -> Vec<String> {
+    let list = run_json(env.bn().args(["commit", "list", id]));
+    list["commits"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["sha"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn test_scan_links_and_closes_fixed_bugs() {
+    let env = TestEnv::init_git();
+    let repo = env.repo_path();
+    let task_id = create(&env, &["task", "create", "Parser"]);
+    let bug_id = create(&env, &["bug", "create", "Crash"]);
+    let branch_bug = create(&env, &["bug", "create", "Branch crash"]);
+
+    let mention = commit(repo, "a.txt", &format!("Start {} parser work", task_id));
+    let fix = commit(
+        repo,
+        "b.txt",
+        &format!(
+            "Handle empty input\n\nBinnacle-Task: {}\nFixes: {}",
+            task_id, bug_id
+        ),
+    );
+    commit(repo, "c.txt", "Mention an unknown bn-ffff9999");
+
+    let scan = run_json(env.bn().args(["commit", "scan"]));
+    assert_eq!(scan["scanned"], 4);
+    assert_eq!(scan["linked"].as_array().unwrap().len(), 3);
+    assert_eq!(scan["closed_bugs"], serde_json::json!([bug_id.as_str()]));
+    assert_eq!(linked_shas(&env, &task_id).len(), 2);
+    assert!(linked_shas(&env, &task_id).contains(&mention));
+    assert_eq!(linked_shas(&env, &bug_id), vec![fix]);
+    let bug = run_json(env.bn().args(["bug", "show", &bug_id]));
+    assert_eq!(bug["status"], "done");
+
+    // Rescanning finds nothing new
+    let scan = run_json(env.bn().args(["commit", "scan"]));
+    assert_eq!(scan["linked"], serde_json::json!([]));
+
+    // A fix that hasn't reached the merge target only links the bug
+    git(repo, &["checkout", "-q", "-b", "feature"]);
+    commit(
+        repo,
+        "d.txt",
+        &format!("Guard the branch\n\nFixes: {}", branch_bug),
+    );
+    let scan = run_json(
+        env.bn()
+            .env("BN_MERGE_TARGET", "main")
+            .args(["commit", "scan", "--since", "HEAD~1"]),
+    );
+    assert_eq!(scan["scanned"], 1);
+    assert_eq!(scan["linked"][0]["entity_id"], branch_bug.as_str());
+    assert!(scan.get("closed_bugs").is_none());
+    let bug = run_json(env.bn().args(["bug", "show", &branch_bug]));
+    assert_eq!(bug["status"], "pending");
+}
+
+#[test]
+fn test_scan_closes_fixes_only_on_default_branch() {
+    let env = TestEnv::init_git();
+    let repo = env.repo_path();
+    let bug_id = create(&env, &["bug", "create", "Crash"]);
+
+    // No BN_MERGE_TARGET: the checked-out feature branch doesn't count
+    git(repo, &["checkout", "-q", "-b", "feature"]);
+    commit(repo, "a.txt", &format!("Guard input\n\nFixes: {}", bug_id));
+    let scan = run_json(
+        env.bn()
+            .env_remove("BN_MERGE_TARGET")
+            .args(["commit", "scan"]),
+    );
+    assert_eq!(scan["linked"][0]["entity_id"], bug_id.as_str());
+    assert!(scan.get("closed_bugs").is_none());
+
+    git(repo, &["checkout", "-q", "main"]);
+    git(repo, &["merge", "-q", "--ff-only", "feature"]);
+    git(repo, &["checkout", "-q", "feature"]);
+    let scan = run_json(
+        env.bn()
+            .env_remove("BN_MERGE_TARGET")
+            .args(["commit", "scan"]),
+    );
+    assert_eq!(scan["closed_bugs"], serde_json::json!([bug_id.as_str()]));
+    let bug = run_json(env.bn().args(["bug", "show", &bug_id]));
+    assert_eq!(bug["status"], "done");
+    assert!(bug.to_string().contains("on main"), "{}", bug);
+}
+
+#[test]
+fn test_trailers_override_mentions() {
+    let env = TestEnv::init_git();
+    let repo = env.repo_path();
+    let task_id = create(&env, &["task", "create", "Parser"]);
+    let other_id = create(&env, &["task", "create", "Lexer"]);
+
+    let sha = commit(
+        repo,
+        "a.txt",
+        &format!(
+            "Parser work, unlike {}\n\nBinnacle-Task: {}",
+            other_id, task_id
+        ),
+    );
+    let scan = run_json(env.bn().args(["commit", "scan"]));
+    assert_eq!(scan["linked"].as_array().unwrap().len(), 1);
+    assert_eq!(linked_shas(&env, &task_id), vec![sha]);
+    assert!(linked_shas(&env, &other_id).is_empty());
+
+    // So doctor has nothing to say about the scan's own links
+    let doctor = run_json(env.bn().args(["doctor"]));
+    assert!(
+        !doctor["issues"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|i| i["category"] == "commits"),
+        "{}",
+        doctor
+    );
+}
+
+#[test]
+fn test_doctor_reports_trailer_mismatches() {
+    let env = TestEnv::init_git();
+    let repo = env.repo_path();
+    let task_id = create(&env, &["task", "create", "Parser"]);
+    let other_id = create(&env, &["task", "create", "Lexer"]);
+
+    let sha = commit(
+        repo,
+        "a.txt",
+        &format!("Parser work\n\nBinnacle-Task: {}", task_id),
+    );
+    env.bn()
+        .args(["commit", "link", &sha, &other_id])
+        .assert()
+        .success();
+
+    let doctor = run_json(env.bn().args(["doctor"]));
+    let messages: Vec<String> = doctor["issues"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|i| i["category"] == "commits")
+        .map(|i| i["message"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(messages.len(), 2, "unexpected issues: {:?}", messages);
+    assert!(messages[0].contains("is not linked to it"));
+    assert!(messages[1].contains(&format!("is linked to {}", other_id)));
+
+    // Scanning adds the missing link; the manual one still disagrees
+    env.bn().args(["commit", "scan"]).assert().success();
+    let doctor = run_json(env.bn().args(["doctor"]));
+    let remaining = doctor["issues"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|i| i["category"] == "commits")
+        .count();
+    assert_eq!(remaining, 1);
+}
@@ -333,3 +333,85 @@ agent "worker" {
         "Non-overridden reasoning_effort should stay at default"
     );
 }
+
+#[test]
+fn test_config_agents_launch_uses_project_backend() {
+    let env = init_binnacle();
+
+    let agents_dir = env.repo_path().join(".binnacle/agents");
+    std::fs::create_dir_all(&agents_dir).unwrap();
+    std::fs::write(
+        agents_dir.join("config.kdl"),
+        "agent \"ask\" {\n    backend \"claude\"\n}\n",
+    )
+    .unwrap();
+
+    let config_dir = env.repo_path().join("launch");
+    let output = bn_in(&env)
+        .env_remove("BN_AGENT_BACKEND")
+        .args(["config", "agents", "launch", "ask", "--prompt", "hello"])
+        .arg("--config-dir")
+        .arg(&config_dir)
+        .arg("--write")
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    let parsed: serde_json::Value = serde_json::from_str(&stdout).expect("Must be valid JSON");
+
+    assert_eq!(parsed["backend"], "claude");
+    let argv: Vec<&str> = parsed["argv"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| a.as_str().unwrap())
+        .collect();
+    assert_eq!(argv[0], "claude");
+    assert_eq!(argv[argv.len() - 2..], ["-p", "hello"]);
+    assert!(argv.contains(&"--disallowedTools"));
+    assert!(config_dir.join("claude-mcp.json").exists());
+}
+
+#[test]
+fn test_config_agents_launch_fake_backend_runs() {
+    let env = init_binnacle();
+
+    let output = bn_in(&env)
+        .args(["config", "agents", "launch", "worker", "--backend", "fake"])
+        .args(["--prompt", "pick a task", "--allow-all"])
+        .assert()
+        .success();
+
+    let stdout = String::from_utf8_lossy(&output.get_output().stdout);
+    let parsed: serde_json::Value = serde_json::from_str(&stdout).expect("Must be valid JSON");
+    assert_eq!(parsed["backend"], "fake");
+
+    let argv: Vec<String> = serde_json::from_value(parsed["argv"].clone()).unwrap();
+    let run = std::process::Command::new(&argv[0])
+        .args(&argv[1..])
+        .output()
+        .unwrap();
+    assert!(run.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout),
+        "fake agent: pick a task\n"
+    );
+}
+
+#[test]
+fn test_config_agents_launch_invalid_backend_fails() {
+    let env = init_binnacle();
+
+    bn_in(&env)
+        .args([
+            "config",
+            "agents",
+            "launch",
+            "worker",
+            "--backend",
+            "cursor",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid agent backend"));
+}
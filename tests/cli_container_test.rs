@@ -1726,20 +1726,23 @@ fn test_container_list_blocked_without_bn_allow_sudo() {
     // This verifies the security guard that prevents binnacle from
     // running sudo commands without explicit user consent.
     //
-    // We need to bypass the "ctr not found" check by creating a fake ctr binary.
+    // We need to bypass the "ctr not found" check by creating fake ctr and
+    // buildah binaries, and pin containerd so podman/docker aren't auto-detected.
     let env = TestEnv::new();
 
-    // Create a fake ctr binary that just exists (doesn't need to do anything)
+    // Create fake ctr/buildah binaries that just exist (don't need to do anything)
     let fake_bin = env.repo_path().join("fake_bin");
     fs::create_dir_all(&fake_bin).unwrap();
-    let fake_ctr = fake_bin.join("ctr");
-    fs::write(&fake_ctr, "#!/bin/sh\nexit 0\n").unwrap();
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = fs::metadata(&fake_ctr).unwrap().permissions();
-        perms.set_mode(0o755);
-        fs::set_permissions(&fake_ctr, perms).unwrap();
+    for tool in ["ctr", "buildah"] {
+        let fake_tool = fake_bin.join(tool);
+        fs::write(&fake_tool, "#!/bin/sh\nexit 0\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = fs::metadata(&fake_tool).unwrap().permissions();
+            perms.set_mode(0o755);
+            fs::set_permissions(&fake_tool, perms).unwrap();
+        }
     }
 
     // Create a test environment without rootless containerd
@@ -1756,6 +1759,7 @@ fn test_container_list_blocked_without_bn_allow_sudo() {
     cmd.env("PATH", format!("{}:{}", fake_bin.display(), current_path));
     // Ensure BN_ALLOW_SUDO is NOT set
     cmd.env_remove("BN_ALLOW_SUDO");
+    cmd.env("BN_CONTAINER_RUNTIME", "containerd");
     cmd.args(["container", "list"]);
 
     cmd.assert()
@@ -1768,17 +1772,19 @@ fn test_container_list_error_includes_opt_in_instructions() {
     // Verify the error message includes clear instructions for opting in
     let env = TestEnv::new();
 
-    // Create a fake ctr binary
+    // Create fake ctr/buildah binaries
     let fake_bin = env.repo_path().join("fake_bin");
     fs::create_dir_all(&fake_bin).unwrap();
-    let fake_ctr = fake_bin.join("ctr");
-    fs::write(&fake_ctr, "#!/bin/sh\nexit 0\n").unwrap();
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = fs::metadata(&fake_ctr).unwrap().permissions();
-        perms.set_mode(0o755);
-        fs::set_permissions(&fake_ctr, perms).unwrap();
+    for tool in ["ctr", "buildah"] {
+        let fake_tool = fake_bin.join(tool);
+        fs::write(&fake_tool, "#!/bin/sh\nexit 0\n").unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = fs::metadata(&fake_tool).unwrap().permissions();
+            perms.set_mode(0o755);
+            fs::set_permissions(&fake_tool, perms).unwrap();
+        }
     }
 
     // Create environment without rootless containerd
@@ -1791,6 +1797,7 @@ fn test_container_list_error_includes_opt_in_instructions() {
     let current_path = std::env::var("PATH").unwrap_or_default();
     cmd.env("PATH", format!("{}:{}", fake_bin.display(), current_path));
     cmd.env_remove("BN_ALLOW_SUDO");
+    cmd.env("BN_CONTAINER_RUNTIME", "containerd");
     cmd.args(["container", "list"]);
 
     // Should mention the exact environment variable setting
@@ -874,8 +874,10 @@ fn test_doc_history_multiple_versions() {
     let versions = json["versions"].as_array().expect("No versions array");
     assert_eq!(versions.len(), 3);
 
-    // First should be current
+    // First should be current, numbered from the original version
     assert!(versions[0]["is_current"].as_bool().unwrap());
+    assert_eq!(versions[0]["version"], 3);
+    assert_eq!(versions[2]["version"], 1);
     assert!(!versions[1]["is_current"].as_bool().unwrap());
     assert!(!versions[2]["is_current"].as_bool().unwrap());
 }
@@ -927,7 +929,7 @@ fn test_doc_history_human_readable() {
         .success()
         .stdout(predicate::str::contains("history"))
         .stdout(predicate::str::contains("2 versions"))
-        .stdout(predicate::str::contains("(current)"))
+        .stdout(predicate::str::contains(format!("v2 {} (current)", new_id)))
         .stdout(predicate::str::contains("user:alice"));
 }
 
@@ -991,6 +993,145 @@ fn test_doc_update_invalid_editor_type() {
         .stderr(predicate::str::contains("Invalid editor type"));
 }
 
+// === Doc Diff and Conflict Tests ===
+
+/// Run a bn command that must succeed and parse its JSON output.
+fn run_json(cmd: &mut Command) -> serde_json::Value {
+    let output = cmd.assert().success().get_output().stdout.clone();
+    serde_json::from_slice(&output).expect("Invalid JSON")
+}
+
+/// Create a doc with `content` and return its ID.
+fn create_doc(temp: &TestEnv, task_id: &str, content: &str) -> String {
+    run_json(bn_in(temp).args(["doc", "create", task_id, "-T", "Design", "-c", content]))["id"]
+        .as_str()
+        .expect("No doc ID")
+        .to_string()
+}
+
+/// Update a doc with `content` (plus extra args) and return the new version's ID.
+fn update_doc(temp: &TestEnv, id: &str, content: &str, extra: &[&str]) -> String {
+    run_json(
+        bn_in(temp)
+            .args(["doc", "update", id, "-c", content])
+            .args(extra),
+    )["new_id"]
+        .as_str()
+        .expect("No new_id")
+        .to_string()
+}
+
+#[test]
+fn test_doc_diff_between_versions() {
+    let (temp, task_id) = init_with_task();
+    let v1 = create_doc(&temp, &task_id, "# Design\n\nUse SQLite.\nCache reads.\n");
+    let v2 = update_doc(
+        &temp,
+        &v1,
+        "# Design\n\nUse SQLite.\nCache reads and writes.\n",
+        &[],
+    );
+    let v3 = update_doc(
+        &temp,
+        &v2,
+        "# Design\n\nUse SQLite.\nCache reads and writes.\n\n## Risks\n",
+        &[],
+    );
+
+    // Defaults to the given version against the one before it
+    let diff = run_json(bn_in(&temp).args(["doc", "diff", &v2]));
+    assert_eq!(diff["from"]["id"], v1.as_str());
+    assert_eq!(diff["from"]["version"], 1);
+    assert_eq!(diff["to"]["version"], 2);
+    assert_eq!(
+        (diff["added"].as_u64(), diff["removed"].as_u64()),
+        (Some(1), Some(1))
+    );
+    let text = diff["diff"].as_str().unwrap();
+    assert!(text.starts_with(&format!("--- {} (v1)\n+++ {} (v2)\n", v1, v2)));
+    assert!(text.contains("-Cache reads.\n+Cache reads and writes.\n"));
+
+    // Versions can be picked from any doc in the chain
+    let diff = run_json(bn_in(&temp).args(["doc", "diff", &v1, "--from", "v1", "--to", "3"]));
+    assert_eq!(diff["to"]["id"], v3.as_str());
+    assert_eq!(diff["added"], 3);
+
+    // The original is diffed against an empty doc
+    let diff = run_json(bn_in(&temp).args(["doc", "diff", &v1]));
+    assert!(diff["from"].is_null());
+    assert_eq!(diff["added"], 4);
+
+    bn_in(&temp)
+        .args(["doc", "diff", &v3, "--from", "v2", "--to", "v2", "-H"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No content changes"));
+    bn_in(&temp)
+        .args(["doc", "diff", &v3, "--from", "v9"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("has 3 version(s)"));
+}
+
+#[test]
+fn test_doc_update_of_superseded_version() {
+    let (temp, task_id) = init_with_task();
+    let base = "# Design\n\nIntro.\n\n## Storage\n\nSQLite.\n\n## API\n\nREST.\n";
+    let v1 = create_doc(&temp, &task_id, base);
+    // Another agent updates v1 first
+    let v2 = update_doc(&temp, &v1, &base.replace("REST.", "REST and gRPC."), &[]);
+
+    // Updating v1 again would silently fork the chain
+    let ours = base.replace("SQLite.", "SQLite with WAL.");
+    bn_in(&temp)
+        .args(["doc", "update", &v1, "-c", &ours])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "latest version is {}",
+            v2
+        )))
+        .stderr(predicate::str::contains("--merge"));
+
+    // --merge applies both edits on top of v2
+    let merged = run_json(bn_in(&temp).args(["doc", "update", &v1, "-c", &ours, "--merge"]));
+    assert_eq!(merged["previous_id"], v2.as_str());
+    assert_eq!(merged["merged_from"], v1.as_str());
+    let v3 = merged["new_id"].as_str().unwrap().to_string();
+    let shown = run_json(bn_in(&temp).args(["doc", "show", &v3, "--full"]));
+    let content = shown["doc"]["content"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string();
+    assert!(content.contains("SQLite with WAL."), "{}", shown);
+    assert!(content.contains("REST and gRPC."), "{}", shown);
+
+    // Conflicting edits are refused
+    bn_in(&temp)
+        .args([
+            "doc",
+            "update",
+            &v1,
+            "-c",
+            &base.replace("REST.", "GraphQL."),
+            "--merge",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("1 conflicting section(s)"));
+
+    // --fork branches from v1 explicitly
+    let fork = run_json(bn_in(&temp).args(["doc", "update", &v1, "-c", "Forked", "--fork"]));
+    assert_eq!(fork["previous_id"], v1.as_str());
+    assert_eq!(fork["forked"], true);
+
+    // Updating the latest version needs no flags
+    let latest = fork["new_id"].as_str().unwrap();
+    let next = run_json(bn_in(&temp).args(["doc", "update", latest, "-c", "Next"]));
+    assert!(next.get("merged_from").is_none());
+    assert!(next.get("forked").is_none());
+}
+
 // === Summary Dirty Detection Tests ===
 
 #[test]
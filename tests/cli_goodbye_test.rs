@@ -518,3 +518,82 @@ fn test_agent_id_sets_goodbye_at_timestamp() {
         );
     }
 }
+
+// === Handoff Tests ===
+
+fn run_json(cmd: &mut Command) -> serde_json::Value {
+    let output = cmd.assert().success().get_output().stdout.clone();
+    serde_json::from_slice(&output).expect("Output should be valid JSON")
+}
+
+#[test]
+fn test_goodbye_writes_handoff_for_in_progress_tasks() {
+    let temp = init_binnacle();
+    let task = run_json(bn_in(&temp).args(["task", "create", "Half done"]));
+    let task_id = task["id"].as_str().unwrap();
+    let untouched = run_json(bn_in(&temp).args(["task", "create", "Not claimed"]));
+
+    bn_in(&temp)
+        .env("BN_AGENT_ID", "bn-handoff")
+        .args(["orient", "--type", "worker", "--name", "handoff-agent"])
+        .assert()
+        .success();
+    bn_in(&temp)
+        .env("BN_AGENT_ID", "bn-handoff")
+        .args(["task", "update", task_id, "--status", "in_progress"])
+        .assert()
+        .success();
+
+    let json = run_json(bn_in(&temp).env("BN_AGENT_ID", "bn-handoff").args([
+        "goodbye",
+        "Out of context",
+        "--next",
+        "Finish the parser",
+        "--next",
+        "Add tests",
+    ]));
+    let handoffs = json["handoffs"].as_array().expect("Expected handoffs");
+    assert_eq!(handoffs.len(), 1);
+    let doc_id = handoffs[0].as_str().unwrap();
+
+    let doc = run_json(bn_in(&temp).args(["doc", "show", doc_id, "--full"]));
+    assert_eq!(doc["doc"]["doc_type"], "handoff");
+    let content = doc["doc"]["content"].as_str().unwrap();
+    assert!(content.contains("Reason: Out of context"));
+    assert!(content.contains("- Finish the parser"));
+    assert!(content.contains("## Commits"));
+    assert!(content.contains("## Tests"));
+    assert!(content.contains("`bn task update`"));
+
+    // The next agent sees the handoff when it orients
+    let orient = run_json(bn_in(&temp).args(["orient", "--type", "worker", "--dry-run"]));
+    assert_eq!(orient["handoffs"][0]["doc_id"], doc_id);
+    assert_eq!(orient["handoffs"][0]["task_id"], task_id);
+    assert_eq!(orient["handoffs"][0]["agent_id"], "bn-handoff");
+    assert_ne!(orient["handoffs"][0]["task_id"], untouched["id"]);
+
+    // Closed tasks no longer surface their handoffs
+    bn_in(&temp)
+        .args(["task", "close", task_id, "--reason", "done", "--force"])
+        .assert()
+        .success();
+    let orient = run_json(bn_in(&temp).args(["orient", "--type", "worker", "--dry-run"]));
+    assert!(orient.get("handoffs").is_none());
+}
+
+#[test]
+fn test_goodbye_without_tasks_writes_no_handoff() {
+    let temp = init_binnacle();
+    bn_in(&temp)
+        .env("BN_AGENT_ID", "bn-idle")
+        .args(["orient", "--type", "worker"])
+        .assert()
+        .success();
+
+    let json = run_json(
+        bn_in(&temp)
+            .env("BN_AGENT_ID", "bn-idle")
+            .args(["goodbye", "Nothing to do"]),
+    );
+    assert!(json.get("handoffs").is_none());
+}
@@ -0,0 +1,160 @@
+//! Integration tests for browsing per-commit graph archives.
+//!
+//! These tests verify that:
+//! - `bn show <id> --at <rev>` loads the archive closest to the revision
+//! - `bn diff <rev-a> <rev-b>` reports added, changed, and closed entities and new edges,
+//!   including missions, issues and the queue
+//! - Revisions with no archived ancestor are reported clearly
+
+mod common;
+
+use assert_cmd::Command;
+use common::{TestEnv, git};
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+
+/// Commit a change to `file`, returning the new SHA.
+fn commit(dir: &Path, file: &str) -> String {
+    fs::write(dir.join(file), file).unwrap();
+    git(dir, &["add", file]);
+    git(dir, &["commit", "-q", "-m", file]);
+    git(dir, &["rev-parse", "HEAD"])
+}
+
+fn run_json(cmd: &mut Command) -> serde_json::Value {
+    let output = cmd.assert().success().get_output().stdout.clone();
+    serde_json::from_slice(&output).expect("Failed to parse JSON output")
+}
+
+fn create(env: &TestEnv, args: &[&str]) -> String {
+    run_json(env.bn().args(args))["id"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+fn archive(env: &TestEnv, sha: &str) {
+    let result = run_json(env.bn().args(["session", "store", "archive", sha]));
+    assert_eq!(result["created"], true);
+}
+
+/// Archives two commits: one with tasks A and B, and a later one where A is
+/// closed, B is renamed, and C is added with an edge to B. HEAD is one commit
+/// past the second archive.
+fn history(env: &TestEnv) -> (String, String, [String; 3]) {
+    let repo = env.repo_path();
+    let a = create(env, &["task", "create", "Task A"]);
+    let b = create(env, &["task", "create", "Task B"]);
+    let first = commit(repo, "first.txt");
+    archive(env, &first);
+
+    env.bn()
+        .args(["task", "close", &a, "--reason", "shipped"])
+        .assert()
+        .success();
+    env.bn()
+        .args(["task", "update", &b, "--title", "Task B, renamed"])
+        .assert()
+        .success();
+    let c = create(env, &["task", "create", "Task C"]);
+    env.bn()
+        .args(["link", "add", &c, &b, "--type", "related_to"])
+        .assert()
+        .success();
+    let second = commit(repo, "second.txt");
+    archive(env, &second);
+    commit(repo, "third.txt");
+
+    (first, second, [a, b, c])
+}
+
+#[test]
+fn test_show_at_revision() {
+    let env = TestEnv::init_git();
+    let (first, second, [a, _, c]) = history(&env);
+
+    let shown = run_json(env.bn().args(["show", &a, "--at", &first]));
+    assert_eq!(shown["task"]["status"], "pending");
+    assert_eq!(shown["at"]["archive_commit"], first.as_str());
+    assert_eq!(shown["at"]["commits_back"], 0);
+    let current = run_json(env.bn().args(["show", &a]));
+    assert_eq!(current["task"]["status"], "done");
+
+    // HEAD has no archive of its own; its parent's is used
+    let shown = run_json(env.bn().args(["show", &a, "--at", "HEAD"]));
+    assert_eq!(shown["task"]["status"], "done");
+    assert_eq!(shown["at"]["archive_commit"], second.as_str());
+    assert_eq!(shown["at"]["commits_back"], 1);
+    env.bn()
+        .args(["-H", "show", &a, "--at", "HEAD"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 commit earlier"));
+
+    env.bn()
+        .args(["show", &c, "--at", &first])
+        .assert()
+        .failure();
+    env.bn()
+        .args(["show", &a, "--at", "HEAD~3"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No graph archive at or before"));
+}
+
+#[test]
+fn test_diff_between_revisions() {
+    let env = TestEnv::init_git();
+    let (first, _, [a, b, c]) = history(&env);
+
+    let diff = run_json(env.bn().args(["diff", &first, "HEAD"]));
+    assert_eq!(diff["from"]["archive_commit"], first.as_str());
+    assert_eq!(diff["added"].as_array().unwrap().len(), 1);
+    assert_eq!(diff["added"][0]["id"], c.as_str());
+    assert_eq!(diff["closed"][0]["id"], a.as_str());
+    assert_eq!(diff["closed"][0]["status"], "done");
+    assert_eq!(diff["changed"].as_array().unwrap().len(), 1);
+    assert_eq!(diff["changed"][0]["id"], b.as_str());
+    assert_eq!(diff["changed"][0]["fields"], serde_json::json!(["title"]));
+    assert_eq!(diff["edges_added"][0]["source"], c.as_str());
+    assert_eq!(diff["removed"], serde_json::json!([]));
+
+    env.bn()
+        .args(["-H", "diff", "HEAD", "HEAD~1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No changes."));
+}
+
+#[test]
+fn test_diff_covers_missions_issues_and_queues() {
+    let env = TestEnv::init_git();
+    let repo = env.repo_path();
+    let issue = create(&env, &["issue", "create", "Slow startup"]);
+    let first = commit(repo, "first.txt");
+    archive(&env, &first);
+
+    let mission = create(&env, &["mission", "create", "Ship v1"]);
+    let queue = create(&env, &["queue", "create", "Sprint"]);
+    env.bn()
+        .args(["issue", "close", &issue, "--reason", "fixed"])
+        .assert()
+        .success();
+    let second = commit(repo, "second.txt");
+    archive(&env, &second);
+
+    let diff = run_json(env.bn().args(["diff", &first, &second]));
+    let added: Vec<&str> = diff["added"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["id"].as_str().unwrap())
+        .collect();
+    assert!(added.contains(&mission.as_str()), "{}", diff);
+    assert!(added.contains(&queue.as_str()), "{}", diff);
+    assert_eq!(diff["closed"][0]["id"], issue.as_str());
+
+    let shown = run_json(env.bn().args(["show", &issue, "--at", &first]));
+    assert_eq!(shown["issue"]["status"], "open");
+}
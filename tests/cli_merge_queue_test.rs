@@ -0,0 +1,204 @@
+//! Integration tests for `bn merge-queue`.
+//!
+//! These tests verify that:
+//! - Task branches are queued once and listed in order
+//! - `run` rebases each branch onto the target, tests it and merges it
+//! - Merged commits are linked to the task and the task is closed, passing
+//!   the test gate on the queue's own test run
+//! - Conflicts and failing tests file a bug and reopen closed tasks
+
+mod common;
+
+use assert_cmd::Command;
+use common::{TestEnv, git};
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+
+fn commit_file(dir: &Path, name: &str, contents: &str) {
+    fs::write(dir.join(name), contents).unwrap();
+    git(dir, &["add", "-A"]);
+    git(dir, &["commit", "-q", "-m", &format!("Update {}", name)]);
+}
+
+fn run_json(cmd: &mut Command) -> serde_json::Value {
+    let output = cmd.assert().success().get_output().stdout.clone();
+    serde_json::from_slice(&output).expect("Failed to parse JSON output")
+}
+
+/// Create a task with a worktree and commit `file` on its branch.
+fn task_with_commit(env: &TestEnv, title: &str, file: &str, contents: &str) -> String {
+    let task = run_json(env.bn().args(["task", "create", title]));
+    let task_id = task["id"].as_str().unwrap().to_string();
+    let created = run_json(env.bn().args(["agent", "worktree", "create", &task_id]));
+    commit_file(Path::new(created["path"].as_str().unwrap()), file, contents);
+    task_id
+}
+
+fn enqueue(env: &TestEnv, task_id: &str) -> serde_json::Value {
+    run_json(
+        env.bn()
+            .args(["merge-queue", "add", task_id, "--target", "main"]),
+    )
+}
+
+#[test]
+fn test_queue_rebases_tests_and_merges_in_order() {
+    let env = TestEnv::init_git();
+    let first = task_with_commit(&env, "First", "first.txt", "1\n");
+    let second = task_with_commit(&env, "Second", "second.txt", "2\n");
+    env.bn()
+        .args([
+            "test",
+            "create",
+            "Second exists",
+            "--cmd",
+            "test -f second.txt",
+        ])
+        .args(["--task", &second])
+        .assert()
+        .success();
+
+    let added = enqueue(&env, &first);
+    assert_eq!(added["position"], 1);
+    assert_eq!(added["status"], "queued");
+    assert_eq!(enqueue(&env, &second)["position"], 2);
+    // Queuing again returns the existing entry
+    let again = enqueue(&env, &first);
+    assert_eq!(again["already_queued"], true);
+    assert_eq!(again["id"], added["id"]);
+
+    let list = run_json(env.bn().args(["merge-queue", "list"]));
+    assert_eq!(list["queued"], 2);
+    assert_eq!(list["entries"][0]["task_id"], first.as_str());
+
+    let run = run_json(env.bn().args(["merge-queue", "run"]));
+    let processed = run["processed"].as_array().unwrap();
+    assert_eq!(processed.len(), 2);
+    assert_eq!(processed[0]["status"], "merged");
+    // The second branch was cut before the first merged, so it was rebased
+    assert_eq!(processed[1]["status"], "merged");
+    assert_eq!(processed[1]["tests_run"], 1);
+    assert!(env.repo_path().join("first.txt").exists());
+    assert!(env.repo_path().join("second.txt").exists());
+    assert_eq!(
+        git(
+            env.repo_path(),
+            &["rev-list", "--count", "--merges", "main"]
+        ),
+        "0"
+    );
+
+    // Merged commits are linked and the tasks closed
+    let show = run_json(env.bn().args(["task", "show", &second]));
+    assert_eq!(show["status"], "done");
+    let head = git(env.repo_path(), &["rev-parse", "main"]);
+    let commits = run_json(env.bn().args(["commit", "list", &second]));
+    assert!(commits.to_string().contains(&head));
+
+    assert_eq!(
+        run_json(env.bn().args(["merge-queue", "list"]))["entries"],
+        serde_json::json!([])
+    );
+    env.bn()
+        .args(["merge-queue", "list", "--all", "-H"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[merged]"));
+}
+
+#[test]
+fn test_merged_task_passes_test_gate() {
+    let env = TestEnv::init_git();
+    env.bn()
+        .args(["config", "set", "test.require_for_close", "true"])
+        .assert()
+        .success();
+    let task_id = task_with_commit(&env, "Feature", "feature.txt", "1\n");
+    env.bn()
+        .args([
+            "test", "create", "Passes", "--cmd", "true", "--task", &task_id,
+        ])
+        .assert()
+        .success();
+
+    enqueue(&env, &task_id);
+    let run = run_json(env.bn().args(["merge-queue", "run"]));
+    assert_eq!(run["processed"][0]["status"], "merged");
+
+    // The queue's own test run counts, so the close needs no override
+    let show = run_json(env.bn().args(["task", "show", &task_id]));
+    assert_eq!(show["status"], "done");
+    assert!(show.get("test_gate_override").is_none(), "{}", show);
+}
+
+#[test]
+fn test_failing_tests_file_bug_and_reopen_task() {
+    let env = TestEnv::init_git();
+    let task_id = task_with_commit(&env, "Broken", "broken.txt", "x\n");
+    env.bn()
+        .args(["test", "create", "Always fails", "--cmd", "false"])
+        .args(["--task", &task_id])
+        .assert()
+        .success();
+    env.bn()
+        .args(["task", "close", &task_id, "--reason", "done", "--force"])
+        .assert()
+        .success();
+    let main_before = git(env.repo_path(), &["rev-parse", "main"]);
+
+    enqueue(&env, &task_id);
+    let run = run_json(env.bn().args(["merge-queue", "run"]));
+    let entry = &run["processed"][0];
+    assert_eq!(entry["status"], "failed");
+    assert_eq!(entry["message"], "1 of 1 test(s) failed");
+    assert!(entry["bug_id"].as_str().is_some());
+    assert_eq!(git(env.repo_path(), &["rev-parse", "main"]), main_before);
+
+    let show = run_json(env.bn().args(["task", "show", &task_id]));
+    assert_eq!(show["status"], "reopened");
+}
+
+#[test]
+fn test_conflict_files_bug() {
+    let env = TestEnv::init_git();
+    let task_id = task_with_commit(&env, "Edit readme", "README.md", "task\n");
+    commit_file(env.repo_path(), "README.md", "main\n");
+
+    enqueue(&env, &task_id);
+    let run = run_json(env.bn().args(["merge-queue", "run", "--once"]));
+    let entry = &run["processed"][0];
+    assert_eq!(entry["status"], "failed");
+    assert_eq!(entry["message"], "conflict in README.md");
+    let bug_id = entry["bug_id"].as_str().unwrap();
+    let links = run_json(env.bn().args(["link", "list", bug_id]));
+    assert_eq!(links["edges"][0]["edge_type"], "caused_by");
+    assert_eq!(links["edges"][0]["target"], task_id.as_str());
+}
+
+#[test]
+fn test_add_and_remove_errors() {
+    let env = TestEnv::init_git();
+    let task = run_json(env.bn().args(["task", "create", "No branch"]));
+    let task_id = task["id"].as_str().unwrap();
+    env.bn()
+        .args(["merge-queue", "add", task_id])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Branch bn/"));
+
+    let queued = task_with_commit(&env, "Queued", "q.txt", "q\n");
+    enqueue(&env, &queued);
+    let removed = run_json(env.bn().args(["merge-queue", "remove", &queued]));
+    assert_eq!(removed["status"], "cancelled");
+    env.bn()
+        .args(["merge-queue", "remove", &queued])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No queued merge"));
+    env.bn()
+        .args(["merge-queue", "run", "-H"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Merge queue is empty."));
+}
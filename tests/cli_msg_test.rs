@@ -0,0 +1,174 @@
+//! Integration tests for `bn msg` and `bn ask`.
+//!
+//! These tests verify that:
+//! - Messages reach agents by ID, name or role, and humans
+//! - `bn msg inbox` shows unread messages once and marks them read
+//! - Replies go back to the sender
+//! - `bn ask --wait` parks until a human answers or the timeout passes
+
+mod common;
+
+use assert_cmd::Command;
+use common::TestEnv;
+use predicates::prelude::*;
+use std::time::{Duration, Instant};
+
+const AGENT_ID: &str = "bn-msgagent";
+
+fn run_json(cmd: &mut Command) -> serde_json::Value {
+    let output = cmd.assert().success().get_output().stdout.clone();
+    serde_json::from_slice(&output).expect("Failed to parse JSON output")
+}
+
+/// A binnacle repo with a registered worker agent named `alpha`.
+fn init_env() -> TestEnv {
+    let env = TestEnv::init();
+    env.bn()
+        .env("BN_AGENT_ID", AGENT_ID)
+        .args(["orient", "--type", "worker", "--name", "alpha"])
+        .assert()
+        .success();
+    env
+}
+
+fn as_agent(env: &TestEnv) -> Command {
+    let mut cmd = env.bn();
+    cmd.env("BN_AGENT_ID", AGENT_ID);
+    cmd
+}
+
+#[test]
+fn test_send_inbox_and_reply() {
+    let env = init_env();
+
+    // By name and by role
+    let sent = run_json(
+        env.bn()
+            .args(["msg", "send", "--to", "alpha", "Rebase first"]),
+    );
+    assert_eq!(sent["to"], AGENT_ID);
+    assert_eq!(sent["from"], "human");
+    assert!(sent["id"].as_str().unwrap().starts_with("bnmsg-"));
+    run_json(
+        env.bn()
+            .args(["msg", "send", "--to", "worker", "Standup at 10"]),
+    );
+    // Not for this agent
+    run_json(env.bn().args(["msg", "send", "--to", "planner", "Replan"]));
+
+    let inbox = run_json(as_agent(&env).args(["msg", "inbox"]));
+    assert_eq!(inbox["reader"], AGENT_ID);
+    assert_eq!(inbox["unread"], 2);
+    assert_eq!(inbox["messages"][0]["body"], "Rebase first");
+    assert_eq!(inbox["messages"][1]["to"], "worker");
+
+    // Read messages are only listed with --all
+    let inbox = run_json(as_agent(&env).args(["msg", "inbox"]));
+    assert_eq!(inbox["unread"], 0);
+    assert_eq!(inbox["messages"], serde_json::json!([]));
+    let inbox = run_json(as_agent(&env).args(["msg", "inbox", "--all"]));
+    assert_eq!(inbox["messages"].as_array().unwrap().len(), 2);
+
+    let reply =
+        run_json(as_agent(&env).args(["msg", "reply", sent["id"].as_str().unwrap(), "Done"]));
+    assert_eq!(reply["to"], "human");
+    assert_eq!(reply["reply_to"], sent["id"]);
+
+    env.bn()
+        .args(["msg", "inbox", "-H"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Inbox for human"))
+        .stdout(predicate::str::contains(format!(
+            "from {} to human",
+            AGENT_ID
+        )));
+}
+
+#[test]
+fn test_send_errors() {
+    let env = init_env();
+    env.bn()
+        .args(["msg", "send", "--to", "nobody", "Hello"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No agent or role named 'nobody'"));
+    env.bn()
+        .args(["msg", "send", "--to", "human", "  "])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Message body cannot be empty"));
+    env.bn()
+        .args(["msg", "reply", "bnmsg-none", "Hi"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Message not found: bnmsg-none"));
+}
+
+#[test]
+fn test_ask_without_answer() {
+    let env = init_env();
+
+    let asked = run_json(as_agent(&env).args(["ask", "Which database?"]));
+    assert_eq!(asked["question"]["kind"], "question");
+    assert_eq!(asked["question"]["to"], "human");
+    assert_eq!(asked["timed_out"], false);
+    assert!(asked.get("answer").is_none());
+
+    let waited = run_json(as_agent(&env).args(["ask", "Still there?", "--wait", "--timeout", "1"]));
+    assert_eq!(waited["timed_out"], true);
+
+    let inbox = run_json(env.bn().args(["msg", "inbox"]));
+    assert_eq!(inbox["unread"], 2);
+    assert_eq!(inbox["messages"][0]["kind"], "question");
+}
+
+#[test]
+fn test_ask_wait_returns_answer() {
+    let env = init_env();
+
+    // Park an agent in a separate process
+    let child = std::process::Command::new(env!("CARGO_BIN_EXE_bn"))
+        .current_dir(env.repo_path())
+        .env("BN_DATA_DIR", env.data_path())
+        .env("BN_CONFIG_DIR", env.data_path())
+        .env("BN_TEST_MODE", "1")
+        .env("BN_TEST_ID", env.test_id())
+        .env("BN_AGENT_ID", AGENT_ID)
+        .args(["ask", "Ship it?", "--wait", "--timeout", "60"])
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn bn ask");
+
+    // The agent shows what it is waiting for
+    let started = Instant::now();
+    while !String::from_utf8_lossy(
+        &env.bn()
+            .args(["show", AGENT_ID, "-H"])
+            .output()
+            .unwrap()
+            .stdout,
+    )
+    .contains("Current action: waiting for answer to bnmsg-")
+    {
+        assert!(
+            started.elapsed() < Duration::from_secs(30),
+            "agent never parked"
+        );
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    let inbox = run_json(env.bn().args(["msg", "inbox"]));
+    let question_id = inbox["messages"][0]["id"].as_str().unwrap().to_string();
+
+    run_json(
+        env.bn()
+            .args(["msg", "reply", &question_id, "Yes, ship it"]),
+    );
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["timed_out"], false);
+    assert_eq!(result["answer"]["body"], "Yes, ship it");
+    assert_eq!(result["answer"]["from"], "human");
+}
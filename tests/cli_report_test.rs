@@ -0,0 +1,256 @@
+//! Integration tests for `bn report`.
+//!
+//! These tests verify that:
+//! - `bn report pr` lists the tasks and bugs closed by commits in range
+//! - Entries are grouped by milestone and tag
+//! - `bn report changelog` covers the closed entities under a milestone
+//! - `report` blocks in config.kdl customize the markdown
+
+mod common;
+
+use assert_cmd::Command;
+use common::{TestEnv, git};
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+
+/// Commit a new file and return the new SHA.
+fn commit(dir: &Path, file: &str) -> String {
+    fs::write(dir.join(file), file).unwrap();
+    git(dir, &["add", file]);
+    git(dir, &["commit", "-q", "-m", &format!("Add {}", file)]);
+    git(dir, &["rev-parse", "HEAD"])
+}
+
+fn run_json(cmd: &mut Command) -> serde_json::Value {
+    let output = cmd.assert().success().get_output().stdout.clone();
+    serde_json::from_slice(&output).expect("Failed to parse JSON output")
+}
+
+fn create(env: &TestEnv, args: &[&str]) -> String {
+    run_json(env.bn().args(args))["id"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+fn child_of(env: &TestEnv, child: &str, parent: &str) {
+    env.bn()
+        .args([
+            "link", "add", child, parent, "-t", "child_of", "--reason", "test",
+        ])
+        .assert()
+        .success();
+}
+
+fn link_commit(env: &TestEnv, sha: &str, id: &str) {
+    env.bn()
+        .args(["commit", "link", sha, id])
+        .assert()
+        .success();
+}
+
+/// Append a snippet to the session config.kdl.
+fn append_config(env: &TestEnv, kdl: &str) {
+    use sha2::{Digest, Sha256};
+    let canonical = env.repo_path().canonicalize().unwrap();
+    let mut hasher = Sha256::new();
+    hasher.update(canonical.to_string_lossy().as_bytes());
+    let hash_hex = format!("{:x}", hasher.finalize());
+    let path = env.data_path().join(&hash_hex[..12]).join("config.kdl");
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+    fs::write(&path, format!("{}\n{}\n", existing, kdl)).unwrap();
+}
+
+struct Fixture {
+    env: TestEnv,
+    milestone: String,
+    parser: String,
+    crash: String,
+    docs: String,
+    open: String,
+}
+
+/// Milestone > parser task and crash bug, plus a tagged docs task
+/// and an open task, each with its own commit after `main`.
+fn fixture() -> Fixture {
+    let env = TestEnv::init_git();
+    let repo = env.repo_path();
+    git(repo, &["checkout", "-q", "-b", "feature"]);
+
+    let milestone = create(&env, &["milestone", "create", "v1.0"]);
+    let parser = create(&env, &["task", "create", "Incremental parser"]);
+    let crash = create(&env, &["bug", "create", "Parser crash"]);
+    child_of(&env, &parser, &milestone);
+    child_of(&env, &crash, &milestone);
+    let docs = create(&env, &["task", "create", "Build guide", "-t", "docs"]);
+    let open = create(&env, &["task", "create", "Cache warmup"]);
+
+    for (file, id) in [
+        ("parser.rs", &parser),
+        ("crash.rs", &crash),
+        ("guide.md", &docs),
+        ("cache.rs", &open),
+    ] {
+        let sha = commit(repo, file);
+        link_commit(&env, &sha, id);
+    }
+    env.bn()
+        .args([
+            "task",
+            "close",
+            &parser,
+            "--reason",
+            "Reparses only dirty files",
+        ])
+        .assert()
+        .success();
+    env.bn()
+        .args(["bug", "close", &crash, "--reason", "Guarded empty input"])
+        .assert()
+        .success();
+    env.bn()
+        .args(["task", "close", &docs, "--reason", "Written"])
+        .assert()
+        .success();
+
+    Fixture {
+        env,
+        milestone,
+        parser,
+        crash,
+        docs,
+        open,
+    }
+}
+
+#[test]
+fn test_report_pr_groups_closed_entities() {
+    let f = fixture();
+
+    let report = run_json(f.env.bn().args(["report", "pr", "--since", "main"]));
+    assert_eq!(report["kind"], "pr");
+    assert_eq!(report["commits"], 4);
+    assert_eq!(report["still_open"], serde_json::json!([f.open]));
+    let entries = report["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0]["id"], f.parser.as_str());
+    assert_eq!(entries[0]["group"], "v1.0");
+    assert_eq!(entries[0]["reason"], "Reparses only dirty files");
+    assert_eq!(entries[0]["commits"].as_array().unwrap().len(), 1);
+    assert_eq!(entries[2]["id"], f.docs.as_str());
+    assert_eq!(entries[2]["group"], "docs");
+
+    let markdown = report["markdown"].as_str().unwrap();
+    assert!(markdown.starts_with("## Changes since main\n"));
+    assert!(markdown.contains("### v1.0"));
+    assert!(markdown.contains(&format!(
+        "- `{}` Incremental parser: Reparses only dirty files",
+        f.parser
+    )));
+    assert!(markdown.contains(&format!(
+        "- `{}` Parser crash: Guarded empty input",
+        f.crash
+    )));
+    assert!(markdown.contains("### docs"));
+    assert!(!markdown.contains("Cache warmup"));
+    // Tagged docs come after the milestone group
+    assert!(markdown.find("### docs") > markdown.find("### v1.0"));
+
+    // Nothing in range
+    f.env
+        .bn()
+        .args(["-H", "report", "pr", "--since", "HEAD"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No closed tasks or bugs."));
+}
+
+#[test]
+fn test_report_changelog_with_kdl_template() {
+    let f = fixture();
+    append_config(
+        &f.env,
+        r###"report "changelog" {
+            title "# Release {milestone}"
+            group-by "type"
+            heading "## {group}"
+            entry "* {title} ({id}, {commits})"
+            link "https://tracker.example.com/{id}"
+            commit-link "https://git.example.com/commit/{sha}"
+        }"###,
+    );
+
+    let report = run_json(
+        f.env
+            .bn()
+            .args(["report", "changelog", "--milestone", &f.milestone]),
+    );
+    let entries = report["entries"].as_array().unwrap();
+    let ids: Vec<&str> = entries.iter().map(|e| e["id"].as_str().unwrap()).collect();
+    assert_eq!(ids, vec![f.parser.as_str(), f.crash.as_str()]);
+    let sha = entries[0]["commits"][0].as_str().unwrap();
+
+    let markdown = report["markdown"].as_str().unwrap();
+    assert!(markdown.starts_with("# Release v1.0\n"));
+    assert!(markdown.contains("## Tasks"));
+    assert!(markdown.contains("## Bugs"));
+    assert!(markdown.contains(&format!(
+        "* Incremental parser ([{id}](https://tracker.example.com/{id}), [{short}](https://git.example.com/commit/{sha}))",
+        id = f.parser,
+        short = &sha[..7],
+        sha = sha
+    )));
+
+    // --since limits the changelog to commits in range
+    let report = run_json(f.env.bn().args([
+        "report",
+        "changelog",
+        "--milestone",
+        &f.milestone,
+        "--since",
+        "HEAD~3",
+    ]));
+    assert_eq!(report["commits"], 3);
+    assert_eq!(report["entries"].as_array().unwrap().len(), 1);
+    assert_eq!(report["entries"][0]["id"], f.crash.as_str());
+
+    append_config(&f.env, r#"report "pr" { group-by "sprint" }"#);
+    f.env
+        .bn()
+        .args(["report", "pr", "--since", "main"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown group 'sprint'"));
+}
+
+#[test]
+fn test_report_changelog_since_includes_nested_children() {
+    let f = fixture();
+    // A subtask of the parser task, which has no commits in range itself
+    let subtask = create(&f.env, &["task", "create", "Parser cache"]);
+    child_of(&f.env, &subtask, &f.parser);
+    let sha = commit(f.env.repo_path(), "parser_cache.rs");
+    link_commit(&f.env, &sha, &subtask);
+    f.env
+        .bn()
+        .args(["task", "close", &subtask, "--reason", "Cached"])
+        .assert()
+        .success();
+
+    let report = run_json(f.env.bn().args([
+        "report",
+        "changelog",
+        "--milestone",
+        &f.milestone,
+        "--since",
+        "HEAD~1",
+    ]));
+    let ids: Vec<&str> = report["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, vec![subtask.as_str()]);
+}
@@ -0,0 +1,143 @@
+//! Integration tests for `bn test run --affected`.
+//!
+//! These tests verify that:
+//! - Tests whose pattern globs match changed files are selected
+//! - Tests linked to tasks whose commits touched changed files are selected
+//! - `--since` diffs against an earlier ref
+
+mod common;
+
+use assert_cmd::Command;
+use common::{TestEnv, git};
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+
+fn commit_all(dir: &Path, message: &str) -> String {
+    git(dir, &["add", "-A"]);
+    git(dir, &["commit", "-q", "-m", message]);
+    git(dir, &["rev-parse", "HEAD"])
+}
+
+/// A git-backed env with a few committed source files.
+fn init_env() -> TestEnv {
+    let env = TestEnv::init_git();
+    let repo = env.repo_path();
+    fs::create_dir_all(repo.join("src/storage")).unwrap();
+    fs::write(repo.join("src/storage/mod.rs"), "// storage\n").unwrap();
+    fs::write(repo.join("src/cli.rs"), "// cli\n").unwrap();
+    commit_all(repo, "Add sources");
+    env
+}
+
+fn run_json(cmd: &mut Command) -> serde_json::Value {
+    let output = cmd.assert().success().get_output().stdout.clone();
+    serde_json::from_slice(&output).expect("Failed to parse JSON output")
+}
+
+fn create_test(env: &TestEnv, args: &[&str]) -> String {
+    let mut full = vec!["test", "create"];
+    full.extend_from_slice(args);
+    let test = run_json(env.bn().args(&full));
+    test["id"].as_str().unwrap().to_string()
+}
+
+fn affected_ids(run: &serde_json::Value) -> Vec<String> {
+    let mut ids: Vec<String> = run["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["test_id"].as_str().unwrap().to_string())
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn test_affected_selects_by_pattern() {
+    let env = init_env();
+    let repo = env.repo_path();
+    let storage_test = create_test(
+        &env,
+        &["Storage", "--cmd", "true", "--pattern", "src/storage/**"],
+    );
+    let cli_test = create_test(&env, &["CLI", "--cmd", "true", "--pattern", "cli.rs, *.md"]);
+    create_test(&env, &["Unpatterned", "--cmd", "true"]);
+
+    env.bn()
+        .args(["-H", "test", "show", &storage_test])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Pattern: src/storage/**"));
+
+    // Clean worktree: nothing is affected
+    env.bn()
+        .args(["-H", "test", "run", "--affected"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "No tests affected by 0 changed files.",
+        ));
+
+    fs::write(repo.join("src/storage/mod.rs"), "// changed\n").unwrap();
+    let run = run_json(env.bn().args(["test", "run", "--affected"]));
+    assert_eq!(affected_ids(&run), vec![storage_test.clone()]);
+    assert_eq!(
+        run["changed_files"],
+        serde_json::json!(["src/storage/mod.rs"])
+    );
+
+    // Untracked files count as changes too
+    fs::write(repo.join("NOTES.md"), "notes\n").unwrap();
+    let run = run_json(env.bn().args(["test", "run", "--affected"]));
+    let mut expected = vec![storage_test.clone(), cli_test.clone()];
+    expected.sort();
+    assert_eq!(affected_ids(&run), expected);
+
+    // Once committed, only --since an earlier ref sees them
+    commit_all(repo, "Change storage");
+    let run = run_json(env.bn().args(["test", "run", "--affected"]));
+    assert_eq!(run["total"], 0);
+    let run = run_json(
+        env.bn()
+            .args(["test", "run", "--affected", "--since", "HEAD~1"]),
+    );
+    assert_eq!(affected_ids(&run), expected);
+
+    env.bn()
+        .args(["test", "run", "--affected", "--since", "no-such-ref"])
+        .assert()
+        .failure();
+    env.bn()
+        .args(["test", "run", "--since", "HEAD"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_affected_selects_by_linked_commits() {
+    let env = init_env();
+    let repo = env.repo_path();
+    let task = run_json(env.bn().args(["task", "create", "CLI work"]));
+    let task_id = task["id"].as_str().unwrap().to_string();
+    let linked_test = create_test(&env, &["CLI linked", "--cmd", "true", "--task", &task_id]);
+    create_test(&env, &["Other", "--cmd", "true"]);
+
+    fs::write(repo.join("src/cli.rs"), "// cli v2\n").unwrap();
+    let sha = commit_all(repo, "Work on the CLI");
+    env.bn()
+        .args(["commit", "link", &sha, &task_id])
+        .assert()
+        .success();
+
+    // A later edit to a file the task's commit touched selects its tests
+    fs::write(repo.join("src/cli.rs"), "// cli v3\n").unwrap();
+    let run = run_json(env.bn().args(["test", "run", "--affected"]));
+    assert_eq!(affected_ids(&run), vec![linked_test]);
+
+    // Files the task never touched select nothing
+    git(repo, &["checkout", "--", "src/cli.rs"]);
+    fs::write(repo.join("src/storage/mod.rs"), "// changed\n").unwrap();
+    let run = run_json(env.bn().args(["test", "run", "--affected"]));
+    assert_eq!(run["total"], 0);
+}
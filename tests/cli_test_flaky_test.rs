@@ -0,0 +1,175 @@
+//! Integration tests for flaky test detection.
+//!
+//! These tests verify that:
+//! - Tests that flip outcome on the same commit are quarantined with a bug
+//! - Quarantined tests no longer reopen linked tasks
+//! - `bn test flaky` lists them and `bn test quarantine --release` resets them
+//!   and closes the flaky bug
+//! - Runs on a dirty work tree are not attributed to a commit
+
+mod common;
+
+use assert_cmd::Command;
+use common::{TestEnv, git};
+use predicates::prelude::*;
+use std::fs;
+
+/// A git-backed env that ignores the flaky test's `.runs` counter, so reruns
+/// happen on a clean tree.
+fn init_env() -> TestEnv {
+    let env = TestEnv::init_git();
+    let repo = env.repo_path();
+    fs::write(repo.join(".gitignore"), ".runs\n").unwrap();
+    git(repo, &["add", "-A"]);
+    git(repo, &["commit", "-q", "-m", "Ignore .runs"]);
+    env
+}
+
+fn run_json(cmd: &mut Command) -> serde_json::Value {
+    let output = cmd.assert().success().get_output().stdout.clone();
+    serde_json::from_slice(&output).expect("Failed to parse JSON output")
+}
+
+/// A test that passes and fails on alternate runs.
+const ALTERNATING: &str =
+    "n=$(cat .runs 2>/dev/null || echo 0); echo $((n + 1)) > .runs; [ $((n % 2)) -eq 0 ]";
+
+fn close_task(env: &TestEnv, task_id: &str) {
+    env.bn()
+        .args(["task", "close", task_id, "--reason", "done"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_flaky_test_is_quarantined_with_bug() {
+    let env = init_env();
+    let task = run_json(env.bn().args(["task", "create", "Feature"]));
+    let task_id = task["id"].as_str().unwrap().to_string();
+    let test = run_json(env.bn().args([
+        "test",
+        "create",
+        "Alternating",
+        "--cmd",
+        ALTERNATING,
+        "--task",
+        &task_id,
+    ]));
+    let test_id = test["id"].as_str().unwrap().to_string();
+    close_task(&env, &task_id);
+
+    // pass, then fail: one flip, which still reopens the task
+    run_json(env.bn().args(["test", "run", &test_id]));
+    let run = run_json(env.bn().args(["test", "run", &test_id]));
+    assert_eq!(run["results"][0]["reopened_tasks"][0], task_id.as_str());
+    close_task(&env, &task_id);
+
+    // pass again: a second flip on the same commit quarantines it
+    let run = run_json(env.bn().args(["test", "run", &test_id]));
+    assert_eq!(run["results"][0]["quarantined"], true);
+
+    // Failures no longer reopen the task
+    let run = run_json(env.bn().args(["test", "run", &test_id]));
+    assert_eq!(run["results"][0]["passed"], false);
+    assert!(run["results"][0].get("reopened_tasks").is_none());
+    env.bn()
+        .args(["task", "show", &task_id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"status\":\"done\""));
+
+    let flaky = run_json(env.bn().args(["test", "flaky"]));
+    assert_eq!(flaky["threshold"], 0.3);
+    let entry = &flaky["tests"][0];
+    assert_eq!(entry["id"], test_id.as_str());
+    assert_eq!(entry["quarantined"], true);
+    assert_eq!(entry["flips"], 3);
+    assert_eq!(entry["score"], 1.0);
+    let bug_id = entry["flaky_bug"].as_str().unwrap().to_string();
+
+    let bug = run_json(env.bn().args(["bug", "show", &bug_id]));
+    assert!(
+        bug.to_string().contains("Flaky test: Alternating"),
+        "unexpected bug: {}",
+        bug
+    );
+    env.bn()
+        .args(["link", "list", &test_id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(&bug_id))
+        .stdout(predicate::str::contains("tests"));
+
+    env.bn()
+        .args(["-H", "test", "flaky"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[quarantined, bug"));
+
+    let released = run_json(env.bn().args(["test", "quarantine", &test_id, "--release"]));
+    assert_eq!(released["quarantined"], false);
+    assert_eq!(released["closed_bug"], bug_id.as_str());
+    let bug = run_json(env.bn().args(["bug", "show", &bug_id]));
+    assert!(
+        bug.to_string().contains("\"status\":\"done\""),
+        "flaky bug not closed: {}",
+        bug
+    );
+    let test = run_json(env.bn().args(["test", "show", &test_id]));
+    assert!(
+        !test.to_string().contains(&bug_id),
+        "flaky bug still attached: {}",
+        test
+    );
+}
+
+#[test]
+fn test_dirty_tree_reruns_are_not_flaky() {
+    let env = init_env();
+    let test = run_json(
+        env.bn()
+            .args(["test", "create", "Alternating", "--cmd", ALTERNATING]),
+    );
+    let test_id = test["id"].as_str().unwrap().to_string();
+
+    // Uncommitted edits: the runs can't be attributed to HEAD
+    fs::write(env.repo_path().join("README.md"), "changed\n").unwrap();
+    for _ in 0..4 {
+        let run = run_json(env.bn().args(["test", "run", &test_id]));
+        assert!(run["results"][0].get("quarantined").is_none(), "{}", run);
+    }
+    let flaky = run_json(env.bn().args(["test", "flaky"]));
+    assert_eq!(flaky["tests"], serde_json::json!([]));
+}
+
+#[test]
+fn test_manual_quarantine_and_release() {
+    let env = init_env();
+    let test = run_json(env.bn().args(["test", "create", "Steady", "--cmd", "true"]));
+    let test_id = test["id"].as_str().unwrap().to_string();
+
+    run_json(env.bn().args(["test", "run", &test_id]));
+    let flaky = run_json(env.bn().args(["test", "flaky"]));
+    assert_eq!(flaky["tests"], serde_json::json!([]));
+
+    let quarantined = run_json(env.bn().args(["test", "quarantine", &test_id]));
+    assert_eq!(quarantined["quarantined"], true);
+    env.bn()
+        .args(["-H", "test", "show", &test_id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Quarantined: yes"));
+
+    env.bn()
+        .args(["-H", "test", "quarantine", &test_id, "--release"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Released test"));
+    let flaky = run_json(env.bn().args(["test", "flaky"]));
+    assert_eq!(flaky["tests"], serde_json::json!([]));
+
+    env.bn()
+        .args(["config", "set", "test.flaky_threshold", "1.5"])
+        .assert()
+        .failure();
+}
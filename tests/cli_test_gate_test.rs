@@ -0,0 +1,202 @@
+//! Integration tests for test gating on task and bug close.
+//!
+//! These tests verify that:
+//! - With `test.require_for_close`, closing needs linked tests passing since the
+//!   last linked commit was made (by its committer date)
+//! - `--run-tests` runs the blocking tests first
+//! - `--force` needs a `--reason`, which is recorded on the entity
+//! - `test.require_for_close.<tag>` overrides the global setting
+
+mod common;
+
+use assert_cmd::Command;
+use common::{TestEnv, git};
+use predicates::prelude::*;
+use std::fs;
+
+fn run_json(cmd: &mut Command) -> serde_json::Value {
+    let output = cmd.assert().success().get_output().stdout.clone();
+    serde_json::from_slice(&output).expect("Failed to parse JSON output")
+}
+
+fn create(env: &TestEnv, args: &[&str]) -> String {
+    run_json(env.bn().args(args))["id"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+fn set_config(env: &TestEnv, key: &str, value: &str) {
+    env.bn()
+        .args(["config", "set", key, value])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_close_requires_passing_tests() {
+    let env = TestEnv::init_git();
+    let task_id = create(&env, &["task", "create", "Feature"]);
+    let test_id = create(
+        &env,
+        &[
+            "test", "create", "Passes", "--cmd", "true", "--task", &task_id,
+        ],
+    );
+
+    // Off by default
+    env.bn()
+        .args(["config", "set", "test.require_for_close", "maybe"])
+        .assert()
+        .failure();
+    set_config(&env, "test.require_for_close", "true");
+
+    env.bn()
+        .args(["task", "close", &task_id, "--reason", "done"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("never run"))
+        .stderr(predicate::str::contains("--run-tests"));
+
+    let closed =
+        run_json(
+            env.bn()
+                .args(["task", "close", &task_id, "--reason", "done", "--run-tests"]),
+        );
+    assert_eq!(closed["status"], "done");
+    assert_eq!(closed["tests_run"][0]["test_id"], test_id.as_str());
+    assert_eq!(closed["tests_run"][0]["passed"], true);
+
+    // Linking a commit made before the last passing run keeps the result
+    env.bn()
+        .args(["task", "reopen", &task_id])
+        .assert()
+        .success();
+    let initial = git(env.repo_path(), &["rev-parse", "HEAD"]);
+    env.bn()
+        .args(["commit", "link", &initial, &task_id])
+        .assert()
+        .success();
+    run_json(env.bn().args(["task", "close", &task_id]));
+
+    // A commit made after it makes the result stale
+    env.bn()
+        .args(["task", "reopen", &task_id])
+        .assert()
+        .success();
+    // Committer dates have one-second resolution
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    fs::write(env.repo_path().join("feature.txt"), "feature\n").unwrap();
+    git(env.repo_path(), &["add", "-A"]);
+    git(env.repo_path(), &["commit", "-q", "-m", "Feature"]);
+    let head = git(env.repo_path(), &["rev-parse", "HEAD"]);
+    env.bn()
+        .args(["commit", "link", &head, &task_id])
+        .assert()
+        .success();
+    env.bn()
+        .args(["task", "close", &task_id])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not run since the last commit"));
+    env.bn().args(["test", "run", &test_id]).assert().success();
+    env.bn()
+        .args(["-H", "task", "close", &task_id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Closed task"));
+}
+
+#[test]
+fn test_force_close_records_justification() {
+    let env = TestEnv::init_git();
+    set_config(&env, "test.require_for_close", "true");
+    let bug_id = create(&env, &["bug", "create", "Crash"]);
+    let test_id = create(
+        &env,
+        &[
+            "test", "create", "Repro", "--cmd", "false", "--bug", &bug_id,
+        ],
+    );
+
+    env.bn()
+        .args(["bug", "close", &bug_id, "--run-tests"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("failing"));
+    env.bn()
+        .args(["bug", "close", &bug_id, "--force"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("justification"));
+
+    let closed = run_json(env.bn().args([
+        "bug",
+        "close",
+        &bug_id,
+        "--force",
+        "--reason",
+        "Repro depends on a service that is down",
+    ]));
+    assert!(
+        closed["warning"]
+            .as_str()
+            .unwrap()
+            .contains("Closed past the test gate")
+    );
+    let bug = run_json(env.bn().args(["bug", "show", &bug_id]));
+    let gate_override = &bug["test_gate_override"];
+    assert_eq!(
+        gate_override["justification"],
+        "Repro depends on a service that is down"
+    );
+    assert_eq!(gate_override["tests"][0], test_id.as_str());
+}
+
+#[test]
+fn test_tag_overrides_global_setting() {
+    let env = TestEnv::init_git();
+    let docs_task = create(&env, &["task", "create", "Docs", "--tag", "docs"]);
+    create(
+        &env,
+        &[
+            "test", "create", "Fails", "--cmd", "false", "--task", &docs_task,
+        ],
+    );
+    let release_task = create(&env, &["task", "create", "Release", "--tag", "release"]);
+    create(
+        &env,
+        &[
+            "test",
+            "create",
+            "Also fails",
+            "--cmd",
+            "false",
+            "--task",
+            &release_task,
+        ],
+    );
+
+    // Gate off globally, on for release
+    set_config(&env, "test.require_for_close.release", "yes");
+    env.bn()
+        .args(["task", "close", &release_task])
+        .assert()
+        .failure();
+    env.bn()
+        .args(["task", "close", &docs_task])
+        .assert()
+        .success();
+
+    // Gate on globally, off for docs
+    env.bn()
+        .args(["task", "reopen", &docs_task])
+        .assert()
+        .success();
+    set_config(&env, "test.require_for_close", "true");
+    set_config(&env, "test.require_for_close.docs", "false");
+    env.bn()
+        .args(["task", "close", &docs_task])
+        .assert()
+        .success();
+}
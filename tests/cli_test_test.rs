@@ -1,7 +1,7 @@
 //! Integration tests for Test Node operations via CLI.
 //!
 //! These tests verify that test commands work correctly through the CLI:
-//! - `bn test create/list/show/link/unlink/run` all work
+//! - `bn test create/list/show/link/unlink/run/import` all work
 //! - Test execution and result capture
 //! - Regression detection (auto-reopening closed tasks on test failure)
 
@@ -972,3 +972,323 @@ fn test_test_create_with_both_task_and_bug() {
         .stdout(predicate::str::contains(&task_id))
         .stdout(predicate::str::contains(&bug_id));
 }
+
+// === Parallel Runs and Timeouts ===
+
+#[test]
+fn test_test_create_with_timeout_and_env() {
+    let temp = init_binnacle();
+
+    let output = bn_in(&temp)
+        .args([
+            "test",
+            "create",
+            "Env test",
+            "--cmd",
+            "test \"$GREETING\" = hello",
+            "--timeout",
+            "2m",
+            "--env",
+            "GREETING=hello",
+        ])
+        .output()
+        .unwrap();
+    let test_id = extract_id(&output);
+
+    bn_in(&temp)
+        .args(["-H", "test", "show", &test_id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Timeout: 120s"))
+        .stdout(predicate::str::contains("Env: GREETING=hello"));
+
+    bn_in(&temp)
+        .args(["test", "run", &test_id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"passed\":1"));
+
+    bn_in(&temp)
+        .args([
+            "test", "create", "Bad env", "--cmd", "true", "--env", "NOPE",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected KEY=VALUE"));
+    bn_in(&temp)
+        .args([
+            "test",
+            "create",
+            "Bad timeout",
+            "--cmd",
+            "true",
+            "--timeout",
+            "soon",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid duration"));
+}
+
+#[test]
+fn test_test_run_timeout_kills_process_group() {
+    let temp = init_binnacle();
+
+    let task_output = bn_in(&temp)
+        .args(["task", "create", "Slow feature"])
+        .output()
+        .unwrap();
+    let task_id = extract_id(&task_output);
+    bn_in(&temp)
+        .args(["task", "close", &task_id, "--reason", "done"])
+        .assert()
+        .success();
+
+    // The background child writes a marker unless it is killed with the group
+    let marker = temp.repo_path().join("survived");
+    let output = bn_in(&temp)
+        .args([
+            "test",
+            "create",
+            "Hangs",
+            "--cmd",
+            "(sleep 3; touch survived) & sleep 30",
+            "--task",
+            &task_id,
+        ])
+        .output()
+        .unwrap();
+    let test_id = extract_id(&output);
+
+    let output = bn_in(&temp)
+        .args(["test", "run", &test_id, "--timeout", "1s"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["timed_out"], 1);
+    assert_eq!(result["failed"], 1);
+    assert_eq!(result["results"][0]["timed_out"], true);
+    assert!(result["results"][0]["duration_ms"].as_u64().unwrap() < 10_000);
+    assert!(
+        result["results"][0]["stderr"]
+            .as_str()
+            .unwrap()
+            .contains("Timed out after 1s")
+    );
+    assert_eq!(result["results"][0]["reopened_tasks"][0], task_id);
+
+    std::thread::sleep(std::time::Duration::from_secs(4));
+    assert!(!marker.exists(), "background process outlived the timeout");
+
+    // Timed-out tests count as failed
+    bn_in(&temp)
+        .args(["-H", "test", "run", "--failed", "--timeout", "1s"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("TIMEOUT Hangs"))
+        .stdout(predicate::str::contains("(1 timed out)"));
+}
+
+#[test]
+fn test_test_run_per_test_timeout_overrides_default() {
+    let temp = init_binnacle();
+
+    bn_in(&temp)
+        .args([
+            "test",
+            "create",
+            "Needs longer",
+            "--cmd",
+            "sleep 2",
+            "--timeout",
+            "30s",
+        ])
+        .assert()
+        .success();
+
+    let output = bn_in(&temp)
+        .args(["test", "run", "--all", "--timeout", "1s"])
+        .output()
+        .unwrap();
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["passed"], 1);
+    assert_eq!(result["timed_out"], 0);
+}
+
+#[test]
+fn test_test_run_jobs_in_parallel() {
+    let temp = init_binnacle();
+
+    for name in ["One", "Two", "Three", "Four"] {
+        bn_in(&temp)
+            .args(["test", "create", name, "--cmd", "sleep 2"])
+            .assert()
+            .success();
+    }
+
+    let start = std::time::Instant::now();
+    let output = bn_in(&temp)
+        .args(["-H", "test", "run", "--all", "--jobs", "4"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(
+        start.elapsed() < std::time::Duration::from_secs(7),
+        "tests did not run in parallel"
+    );
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout.matches("PASSED").count(), 4);
+    assert!(stdout.contains("Results: 4 passed, 0 failed, 4 total"));
+
+    bn_in(&temp)
+        .args(["test", "run", "--all", "--jobs", "0"])
+        .assert()
+        .failure();
+}
+
+// === Report Import Tests ===
+
+#[test]
+fn test_test_import_junit_creates_links_and_reopens() {
+    let temp = init_binnacle();
+
+    let task_output = bn_in(&temp)
+        .args(["task", "create", "Login"])
+        .output()
+        .unwrap();
+    let task_id = extract_id(&task_output);
+    bn_in(&temp)
+        .args(["task", "close", &task_id, "--reason", "done"])
+        .assert()
+        .success();
+
+    let report = temp.repo_path().join("junit.xml");
+    std::fs::write(
+        &report,
+        format!(
+            r#"<?xml version="1.0"?>
+<testsuite name="auth">
+  <testcase classname="auth" name="login [{task_id}]" time="1.25">
+    <failure message="expected 200">got 500</failure>
+  </testcase>
+  <testcase classname="auth" name="logout" time="0.5"/>
+  <testcase classname="auth" name="sso"><skipped/></testcase>
+</testsuite>"#
+        ),
+    )
+    .unwrap();
+
+    // Without a command, unknown cases can't become test nodes
+    bn_in(&temp)
+        .args(["test", "import", "--format", "junit"])
+        .arg(&report)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("pass --cmd"));
+
+    let output = bn_in(&temp)
+        .args([
+            "test",
+            "import",
+            "--format",
+            "junit",
+            "--cmd",
+            "pytest -k '{name}'",
+            "--link-tags",
+        ])
+        .arg(&report)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["total"], 3);
+    assert_eq!(result["passed"], 1);
+    assert_eq!(result["failed"], 1);
+    assert_eq!(result["skipped"], 1);
+    assert_eq!(result["created"].as_array().unwrap().len(), 2);
+    assert_eq!(result["reopened_tasks"][0], task_id.as_str());
+    let case = &result["cases"][0];
+    assert_eq!(case["outcome"], "failed");
+    assert_eq!(case["duration_ms"], 1250);
+    assert_eq!(case["message"], "expected 200\ngot 500");
+    let test_id = case["test_id"].as_str().unwrap().to_string();
+
+    bn_in(&temp)
+        .args(["-H", "test", "show", &test_id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "auth.login [{}]",
+            task_id
+        )))
+        .stdout(predicate::str::contains("pytest -k 'auth.login"))
+        .stdout(predicate::str::contains(format!(
+            "Linked tasks: {}",
+            task_id
+        )));
+    bn_in(&temp)
+        .args(["task", "show", &task_id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"status\":\"reopened\""));
+
+    // Importing again reuses the nodes
+    let output = bn_in(&temp)
+        .args(["test", "import", "--format", "junit"])
+        .arg(&report)
+        .output()
+        .unwrap();
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(result["created"], serde_json::json!([]));
+    assert_eq!(result["cases"][0]["test_id"], test_id.as_str());
+}
+
+#[test]
+fn test_test_import_cargo_json_and_tap() {
+    let temp = init_binnacle();
+
+    let cargo = temp.repo_path().join("cargo.json");
+    std::fs::write(
+        &cargo,
+        r#"{ "type": "suite", "event": "started", "test_count": 2 }
+{ "type": "test", "name": "math::adds", "event": "ok", "exec_time": 0.004 }
+{ "type": "test", "name": "math::divides", "event": "failed", "exec_time": 0.01, "stdout": "attempt to divide by zero\n" }
+"#,
+    )
+    .unwrap();
+    bn_in(&temp)
+        .args(["-H", "test", "import", "--format", "cargo-json"])
+        .arg(&cargo)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 passed, 1 failed, 0 skipped"))
+        .stdout(predicate::str::contains("attempt to divide by zero"));
+
+    // Created nodes rerun the case with cargo
+    bn_in(&temp)
+        .args(["-H", "test", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("math::divides"));
+    let output = bn_in(&temp).args(["test", "list"]).output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("cargo test -- --exact math::adds"));
+
+    let tap = temp.repo_path().join("results.tap");
+    std::fs::write(&tap, "1..2\nok 1 - math::adds\nnot ok 2 - renders\n").unwrap();
+    bn_in(&temp)
+        .args(["test", "import", "--format", "tap"])
+        .arg(&tap)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No test node named 'renders'"));
+
+    bn_in(&temp)
+        .args(["test", "import", "--format", "junit"])
+        .arg(&tap)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid junit report"));
+}
@@ -6,8 +6,25 @@
 #![allow(dead_code)]
 
 use assert_cmd::Command;
+use std::path::Path;
 pub use tempfile::TempDir;
 
+/// Run `git` in `dir`, panicking if it fails, and return its trimmed stdout.
+pub fn git(dir: &Path, args: &[&str]) -> String {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("Failed to run git");
+    assert!(
+        output.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
 /// A test environment with isolated data storage in test mode.
 ///
 /// Each `TestEnv` creates two temporary directories:
@@ -60,6 +77,24 @@ impl TestEnv {
         env
     }
 
+    /// Create a new test environment whose repo is a git repo with one
+    /// commit on `main`, and initialize binnacle.
+    pub fn init_git() -> Self {
+        let env = Self::new();
+        let repo = env.repo_path();
+        git(repo, &["init", "-q", "-b", "main"]);
+        git(repo, &["config", "user.email", "test@test.com"]);
+        git(repo, &["config", "user.name", "Test User"]);
+        std::fs::write(repo.join("README.md"), "hello\n").unwrap();
+        git(repo, &["add", "-A"]);
+        git(repo, &["commit", "-q", "-m", "Initial commit"]);
+        env.bn()
+            .args(["session", "init", "--auto-global", "-y"])
+            .assert()
+            .success();
+        env
+    }
+
     /// Get a Command for the bn binary with isolated data directory.
     ///
     /// Sets `BN_DATA_DIR` and `BN_CONFIG_DIR` per-command for parallel safety,
@@ -624,6 +624,100 @@ mod gui_enabled {
             .success()
             .stdout(predicates::str::contains(r#""status":"not_running""#));
     }
+
+    #[test]
+    fn test_session_serve_tls_flags_in_help() {
+        let mut cmd = bn_isolated();
+        cmd.args(["session", "serve", "--help"]);
+        cmd.assert()
+            .success()
+            .stdout(predicates::str::contains("--tls"))
+            .stdout(predicates::str::contains("--tls-cert"))
+            .stdout(predicates::str::contains("--tls-key"))
+            .stdout(predicates::str::contains("--upstream-pin"));
+    }
+
+    #[test]
+    fn test_session_serve_tls_cert_requires_key() {
+        let env = TestEnv::init();
+        env.bn()
+            .args(["session", "serve", "--tls-cert", "cert.pem"])
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("--tls-key"));
+    }
+
+    #[test]
+    fn test_session_serve_tls_key_requires_cert() {
+        let env = TestEnv::init();
+        env.bn()
+            .args(["session", "serve", "--tls-key", "key.pem"])
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("--tls-cert"));
+    }
+
+    #[test]
+    fn test_session_serve_rejects_invalid_upstream_pin() {
+        let env = TestEnv::init();
+        env.bn()
+            .args([
+                "session",
+                "serve",
+                "--upstream",
+                "wss://hub.example.com/sessions",
+                "--upstream-pin",
+                "not-a-fingerprint",
+            ])
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("Invalid certificate pin"));
+    }
+
+    #[test]
+    fn test_gui_token_create_list_revoke() {
+        let env = TestEnv::init();
+
+        let output = env
+            .bn()
+            .args(["gui", "token", "create", "--role", "viewer", "--name", "ci"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let created: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let id = created["id"].as_str().unwrap().to_string();
+        assert!(id.starts_with("gtk-"));
+        assert_eq!(created["role"], "viewer");
+        assert!(created["token"].as_str().unwrap().starts_with("bng_"));
+
+        // The secret is never listed, only its ID and role
+        let output = env.bn().args(["gui", "token", "list"]).output().unwrap();
+        let listed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(listed["auth_enabled"], true);
+        assert_eq!(listed["count"], 1);
+        assert_eq!(listed["tokens"][0]["id"], id.as_str());
+        assert!(listed["tokens"][0].get("token").is_none());
+
+        env.bn()
+            .args(["gui", "token", "revoke", &id])
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(r#""remaining":0"#));
+
+        env.bn()
+            .args(["gui", "token", "revoke", &id])
+            .assert()
+            .failure();
+    }
+
+    #[test]
+    fn test_gui_token_create_rejects_unknown_role() {
+        let env = TestEnv::init();
+        env.bn()
+            .args(["gui", "token", "create", "--role", "root"])
+            .assert()
+            .failure();
+    }
 }
 
 #[cfg(not(feature = "gui"))]
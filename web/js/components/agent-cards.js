@@ -13,6 +13,7 @@ import {
 } from '../state.js';
 import { createClickableId } from '../utils/clickable-ids.js';
 import { showNodeDetailModal } from './node-detail-modal.js';
+import { showTranscriptViewer } from './transcript-viewer.js';
 
 /**
  * Get status badge configuration for an agent
@@ -291,6 +292,17 @@ function createActionButtonsFooter(agent) {
     });
     footer.appendChild(viewBtn);
     
+    // Transcript button (output and commands interleaved with graph changes)
+    const transcriptBtn = document.createElement('button');
+    transcriptBtn.className = 'agent-card-action-btn agent-card-transcript-btn';
+    transcriptBtn.innerHTML = '📜 Transcript';
+    transcriptBtn.title = 'Show this agent\'s transcript and graph changes';
+    transcriptBtn.addEventListener('click', (e) => {
+        e.stopPropagation();
+        showTranscriptViewer(agent.id, agent.name || agent.title);
+    });
+    footer.appendChild(transcriptBtn);
+    
     // Copy ID button
     const copyBtn = document.createElement('button');
     copyBtn.className = 'agent-card-action-btn agent-card-copy-btn';
@@ -0,0 +1,183 @@
+/**
+ * Merge Queue Panel Component
+ *
+ * Shows the merge queue in the agents view: task branches waiting to be
+ * rebased, tested and merged, plus the most recent outcomes.
+ * Polls /api/merge-queue while mounted.
+ */
+
+import { viewNodeOnGraph } from '../state.js';
+
+/**
+ * How often to refresh the queue (ms)
+ */
+const POLL_INTERVAL_MS = 5000;
+
+/**
+ * Number of finished entries to show below the active ones
+ */
+const RECENT_LIMIT = 5;
+
+/**
+ * Status badge emoji per queue status
+ */
+const STATUS_EMOJI = {
+    queued: '⏳',
+    running: '🔄',
+    merged: '✅',
+    failed: '❌',
+    cancelled: '⚪'
+};
+
+/**
+ * Simple HTML escaping
+ * @param {string} str - String to escape
+ * @returns {string} Escaped string
+ */
+function escapeHtml(str) {
+    const div = document.createElement('div');
+    div.textContent = str;
+    return div.innerHTML;
+}
+
+/**
+ * Split entries into active ones (in queue order) and recent finished ones
+ * (newest first)
+ * @param {Array} entries - Entries from /api/merge-queue
+ * @returns {{active: Array, recent: Array}}
+ */
+export function splitEntries(entries) {
+    const active = entries.filter(e => e.status === 'queued' || e.status === 'running');
+    const recent = entries
+        .filter(e => e.status !== 'queued' && e.status !== 'running')
+        .sort((a, b) => new Date(b.updated_at) - new Date(a.updated_at))
+        .slice(0, RECENT_LIMIT);
+    return { active, recent };
+}
+
+/**
+ * Render one queue entry as a row
+ * @param {Object} entry - Merge queue entry
+ * @returns {HTMLElement} Row element
+ */
+export function renderQueueEntry(entry) {
+    const row = document.createElement('div');
+    row.className = `merge-queue-entry merge-queue-${entry.status}`;
+
+    const emoji = STATUS_EMOJI[entry.status] || '⚪';
+    let detail = '';
+    if (entry.status === 'merged' && entry.commit) {
+        detail = `at ${entry.commit.slice(0, 7)}`;
+    } else if (entry.message) {
+        detail = entry.message;
+    }
+    const bug = entry.bug_id
+        ? `<a href="#" class="merge-queue-link" data-entity-id="${escapeHtml(entry.bug_id)}">${escapeHtml(entry.bug_id)}</a>`
+        : '';
+
+    row.innerHTML = `
+        <span class="merge-queue-status" title="${escapeHtml(entry.status)}">${emoji}</span>
+        <a href="#" class="merge-queue-link" data-entity-id="${escapeHtml(entry.task_id)}">${escapeHtml(entry.task_id)}</a>
+        <span class="merge-queue-branch">${escapeHtml(entry.branch)} → ${escapeHtml(entry.target)}</span>
+        <span class="merge-queue-detail">${escapeHtml(detail)}</span>
+        ${bug}
+    `;
+    return row;
+}
+
+/**
+ * Render the queue into the panel's list
+ * @param {HTMLElement} panel - The panel element
+ * @param {Array} entries - Entries from /api/merge-queue
+ */
+function renderPanel(panel, entries) {
+    const listEl = panel.querySelector('.merge-queue-list');
+    const { active, recent } = splitEntries(entries);
+
+    listEl.innerHTML = '';
+    if (active.length === 0 && recent.length === 0) {
+        listEl.innerHTML = '<div class="merge-queue-placeholder">The merge queue is empty</div>';
+        return;
+    }
+    if (active.length === 0) {
+        listEl.innerHTML = '<div class="merge-queue-placeholder">Nothing waiting to merge</div>';
+    }
+    for (const entry of active) {
+        listEl.appendChild(renderQueueEntry(entry));
+    }
+    if (recent.length > 0) {
+        const heading = document.createElement('div');
+        heading.className = 'merge-queue-subheading';
+        heading.textContent = 'Recent';
+        listEl.appendChild(heading);
+        for (const entry of recent) {
+            listEl.appendChild(renderQueueEntry(entry));
+        }
+    }
+}
+
+/**
+ * Fetch the queue and re-render the panel
+ * @param {HTMLElement} panel - The panel element
+ */
+async function refresh(panel) {
+    try {
+        const response = await fetch('/api/merge-queue');
+        if (!response.ok) {
+            throw new Error(`Failed to fetch merge queue: ${response.status}`);
+        }
+        const data = await response.json();
+        renderPanel(panel, data.entries || []);
+    } catch (error) {
+        console.error('Error loading merge queue:', error);
+    }
+}
+
+/**
+ * Create the merge queue panel element
+ * @returns {HTMLElement} The panel element
+ */
+export function createMergeQueuePanel() {
+    const panel = document.createElement('div');
+    panel.className = 'merge-queue-panel';
+    panel.id = 'merge-queue-panel';
+    panel.innerHTML = `
+        <h3 class="merge-queue-title">Merge Queue</h3>
+        <div class="merge-queue-list"></div>
+    `;
+
+    // Task and bug IDs navigate the graph
+    panel.addEventListener('click', (e) => {
+        const link = e.target.closest('.merge-queue-link');
+        if (!link) return;
+
+        e.preventDefault();
+        viewNodeOnGraph(link.dataset.entityId);
+    });
+
+    return panel;
+}
+
+/**
+ * Mount the merge queue panel and start polling
+ * @param {HTMLElement|string} target - Target container element or selector
+ * @returns {HTMLElement|null} The panel element, or null if target not found
+ */
+export function mountMergeQueuePanel(target) {
+    const container = typeof target === 'string'
+        ? document.querySelector(target)
+        : target;
+
+    if (!container) {
+        console.warn('Merge queue panel: target container not found');
+        return null;
+    }
+
+    const panel = createMergeQueuePanel();
+    container.appendChild(panel);
+
+    refresh(panel);
+    setInterval(() => refresh(panel), POLL_INTERVAL_MS);
+
+    return panel;
+}
@@ -0,0 +1,49 @@
+/**
+ * Unit tests for Merge Queue Panel Component
+ */
+
+import { describe, it, expect } from '../test-component.js';
+
+describe('Merge Queue Panel', () => {
+    it('should create the panel with a title and list', async () => {
+        const { createMergeQueuePanel } = await import('./merge-queue-panel.js');
+
+        const panel = createMergeQueuePanel();
+        expect(panel.id).toBe('merge-queue-panel');
+        expect(panel.querySelector('.merge-queue-title').textContent).toBe('Merge Queue');
+        expect(panel.querySelector('.merge-queue-list')).toBeDefined();
+    });
+
+    it('should split active and recent entries', async () => {
+        const { splitEntries } = await import('./merge-queue-panel.js');
+
+        const { active, recent } = splitEntries([
+            { id: 'bnmq-1', status: 'merged', updated_at: '2026-01-01T12:00:00Z' },
+            { id: 'bnmq-2', status: 'queued', updated_at: '2026-01-01T12:01:00Z' },
+            { id: 'bnmq-3', status: 'failed', updated_at: '2026-01-01T12:02:00Z' },
+            { id: 'bnmq-4', status: 'running', updated_at: '2026-01-01T12:03:00Z' }
+        ]);
+        expect(active.map(e => e.id).join(',')).toBe('bnmq-2,bnmq-4');
+        expect(recent.map(e => e.id).join(',')).toBe('bnmq-3,bnmq-1');
+    });
+
+    it('should render failed entries with their bug', async () => {
+        const { renderQueueEntry } = await import('./merge-queue-panel.js');
+
+        const row = renderQueueEntry({
+            id: 'bnmq-1',
+            task_id: 'bn-a1b2',
+            branch: 'bn/bn-a1b2',
+            target: 'main',
+            status: 'failed',
+            message: '1 of 2 test(s) failed',
+            bug_id: 'bn-c3d4'
+        });
+        expect(row.className).toContain('merge-queue-failed');
+        expect(row.textContent).toContain('bn/bn-a1b2 → main');
+        expect(row.textContent).toContain('1 of 2 test(s) failed');
+        const links = row.querySelectorAll('.merge-queue-link');
+        expect(links.length).toBe(2);
+        expect(links[1].dataset.entityId).toBe('bn-c3d4');
+    });
+});
@@ -0,0 +1,215 @@
+/**
+ * Messages Panel Component
+ *
+ * Shows questions agents have asked humans (`bn ask`) with an answer box,
+ * plus recent messages to and from humans. Answering a question unparks an
+ * agent waiting in `bn ask --wait`.
+ * Polls /api/messages while mounted.
+ */
+
+/**
+ * How often to refresh messages (ms)
+ */
+const POLL_INTERVAL_MS = 5000;
+
+/**
+ * Number of recent messages to show below the open questions
+ */
+const RECENT_LIMIT = 10;
+
+/**
+ * Simple HTML escaping
+ * @param {string} str - String to escape
+ * @returns {string} Escaped string
+ */
+function escapeHtml(str) {
+    const div = document.createElement('div');
+    div.textContent = str;
+    return div.innerHTML;
+}
+
+/**
+ * Recent messages to or from humans that aren't open questions (newest first)
+ * @param {Array} messages - Messages from /api/messages
+ * @param {Array} openQuestions - Open questions from /api/messages
+ * @returns {Array} Recent messages
+ */
+export function recentHumanMessages(messages, openQuestions) {
+    const open = new Set(openQuestions.map(q => q.id));
+    return messages
+        .filter(m => (m.to === 'human' || m.from === 'human') && !open.has(m.id))
+        .sort((a, b) => new Date(b.created_at) - new Date(a.created_at))
+        .slice(0, RECENT_LIMIT);
+}
+
+/**
+ * Render an open question with an answer form
+ * @param {Object} question - Question message
+ * @returns {HTMLElement} Question element
+ */
+export function renderQuestion(question) {
+    const row = document.createElement('form');
+    row.className = 'messages-question';
+    row.dataset.messageId = question.id;
+    row.innerHTML = `
+        <div class="messages-header">
+            <span class="messages-from">${escapeHtml(question.from)}</span>
+            <span class="messages-id">${escapeHtml(question.id)}</span>
+        </div>
+        <div class="messages-body">${escapeHtml(question.body)}</div>
+        <div class="messages-answer write-action-container" data-readonly-tooltip="Answering unavailable in readonly mode">
+            <input type="text" class="messages-answer-input" placeholder="Answer…" required>
+            <button type="submit" class="messages-answer-button">Answer</button>
+        </div>
+        <div class="messages-error"></div>
+    `;
+    return row;
+}
+
+/**
+ * Render a message as a row
+ * @param {Object} message - Message
+ * @returns {HTMLElement} Row element
+ */
+export function renderMessage(message) {
+    const row = document.createElement('div');
+    row.className = `messages-entry messages-${message.kind || 'message'}`;
+    const reply = message.reply_to
+        ? `<span class="messages-reply">↳ ${escapeHtml(message.reply_to)}</span>`
+        : '';
+    row.innerHTML = `
+        <span class="messages-route">${escapeHtml(message.from)} → ${escapeHtml(message.to)}</span>
+        <span class="messages-text">${escapeHtml(message.body)}</span>
+        ${reply}
+    `;
+    return row;
+}
+
+/**
+ * Render messages into the panel's list
+ * @param {HTMLElement} panel - The panel element
+ * @param {Object} data - Response from /api/messages
+ */
+function renderPanel(panel, data) {
+    const listEl = panel.querySelector('.messages-list');
+    const questions = data.open_questions || [];
+    const recent = recentHumanMessages(data.messages || [], questions);
+
+    // Keep half-typed answers across refreshes
+    const drafts = {};
+    for (const form of listEl.querySelectorAll('.messages-question')) {
+        drafts[form.dataset.messageId] = form.querySelector('.messages-answer-input').value;
+    }
+
+    listEl.innerHTML = '';
+    if (questions.length === 0 && recent.length === 0) {
+        listEl.innerHTML = '<div class="messages-placeholder">No messages</div>';
+        return;
+    }
+    for (const question of questions) {
+        const form = renderQuestion(question);
+        form.querySelector('.messages-answer-input').value = drafts[question.id] || '';
+        listEl.appendChild(form);
+    }
+    if (recent.length > 0) {
+        const heading = document.createElement('div');
+        heading.className = 'messages-subheading';
+        heading.textContent = 'Recent';
+        listEl.appendChild(heading);
+        for (const message of recent) {
+            listEl.appendChild(renderMessage(message));
+        }
+    }
+}
+
+/**
+ * Fetch messages and re-render the panel
+ * @param {HTMLElement} panel - The panel element
+ */
+async function refresh(panel) {
+    try {
+        const response = await fetch('/api/messages');
+        if (!response.ok) {
+            throw new Error(`Failed to fetch messages: ${response.status}`);
+        }
+        renderPanel(panel, await response.json());
+    } catch (error) {
+        console.error('Error loading messages:', error);
+    }
+}
+
+/**
+ * Post an answer to a question as human
+ * @param {string} id - Question ID
+ * @param {string} body - Answer text
+ */
+async function postAnswer(id, body) {
+    const response = await fetch(`/api/messages/${encodeURIComponent(id)}/reply`, {
+        method: 'POST',
+        headers: {
+            'Content-Type': 'application/json'
+        },
+        body: JSON.stringify({ body })
+    });
+    if (!response.ok) {
+        const error = await response.json().catch(() => ({ error: 'Unknown error' }));
+        throw new Error(error.error || `HTTP ${response.status}`);
+    }
+}
+
+/**
+ * Create the messages panel element
+ * @returns {HTMLElement} The panel element
+ */
+export function createMessagesPanel() {
+    const panel = document.createElement('div');
+    panel.className = 'messages-panel';
+    panel.id = 'messages-panel';
+    panel.innerHTML = `
+        <h3 class="messages-title">Messages</h3>
+        <div class="messages-list"></div>
+    `;
+
+    panel.addEventListener('submit', async (e) => {
+        const form = e.target.closest('.messages-question');
+        if (!form) return;
+
+        e.preventDefault();
+        const input = form.querySelector('.messages-answer-input');
+        const errorEl = form.querySelector('.messages-error');
+        errorEl.textContent = '';
+        try {
+            await postAnswer(form.dataset.messageId, input.value);
+            input.value = '';
+            await refresh(panel);
+        } catch (error) {
+            errorEl.textContent = error.message;
+        }
+    });
+
+    return panel;
+}
+
+/**
+ * Mount the messages panel and start polling
+ * @param {HTMLElement|string} target - Target container element or selector
+ * @returns {HTMLElement|null} The panel element, or null if target not found
+ */
+export function mountMessagesPanel(target) {
+    const container = typeof target === 'string'
+        ? document.querySelector(target)
+        : target;
+
+    if (!container) {
+        console.warn('Messages panel: target container not found');
+        return null;
+    }
+
+    const panel = createMessagesPanel();
+    container.appendChild(panel);
+
+    refresh(panel);
+    setInterval(() => refresh(panel), POLL_INTERVAL_MS);
+
+    return panel;
+}
@@ -0,0 +1,44 @@
+/**
+ * Unit tests for Messages Panel Component
+ */
+
+import { describe, it, expect } from '../test-component.js';
+
+describe('Messages Panel', () => {
+    it('should create the panel with a title and list', async () => {
+        const { createMessagesPanel } = await import('./messages-panel.js');
+
+        const panel = createMessagesPanel();
+        expect(panel.id).toBe('messages-panel');
+        expect(panel.querySelector('.messages-title').textContent).toBe('Messages');
+        expect(panel.querySelector('.messages-list')).toBeDefined();
+    });
+
+    it('should list recent human messages except open questions', async () => {
+        const { recentHumanMessages } = await import('./messages-panel.js');
+
+        const question = { id: 'bnmsg-1', from: 'bn-a1b2', to: 'human', created_at: '2026-01-01T12:00:00Z' };
+        const recent = recentHumanMessages([
+            question,
+            { id: 'bnmsg-2', from: 'bn-a1b2', to: 'worker', created_at: '2026-01-01T12:01:00Z' },
+            { id: 'bnmsg-3', from: 'human', to: 'bn-a1b2', created_at: '2026-01-01T12:02:00Z' },
+            { id: 'bnmsg-4', from: 'bn-c3d4', to: 'human', created_at: '2026-01-01T12:03:00Z' }
+        ], [question]);
+        expect(recent.map(m => m.id).join(',')).toBe('bnmsg-4,bnmsg-3');
+    });
+
+    it('should render questions with an answer form', async () => {
+        const { renderQuestion } = await import('./messages-panel.js');
+
+        const form = renderQuestion({
+            id: 'bnmsg-1',
+            kind: 'question',
+            from: 'bn-a1b2',
+            to: 'human',
+            body: 'Which database?'
+        });
+        expect(form.dataset.messageId).toBe('bnmsg-1');
+        expect(form.textContent).toContain('Which database?');
+        expect(form.querySelector('.messages-answer-input')).toBeDefined();
+    });
+});
@@ -0,0 +1,208 @@
+/**
+ * Transcript Viewer Overlay Component
+ *
+ * Full-screen overlay showing an agent's transcript as a timeline:
+ * - stdout/stderr lines and bn commands the agent ran
+ * - graph changes to the tasks the agent touched, interleaved by time
+ */
+
+import { viewNodeOnGraph } from '../state.js';
+
+/**
+ * Simple HTML escaping
+ * @param {string} str - String to escape
+ * @returns {string} Escaped string
+ */
+function escapeHtml(str) {
+    const div = document.createElement('div');
+    div.textContent = str;
+    return div.innerHTML;
+}
+
+/**
+ * Format a transcript timestamp (RFC 3339) as a local time
+ * @param {string} isoString - ISO date string
+ * @returns {string} Formatted time
+ */
+function formatTime(isoString) {
+    try {
+        return new Date(isoString).toLocaleTimeString();
+    } catch (e) {
+        return isoString;
+    }
+}
+
+/**
+ * Render one timeline item as a row
+ * @param {Object} item - Timeline item from /api/agents/:id/transcript
+ * @returns {HTMLElement} Row element
+ */
+export function renderTimelineItem(item) {
+    const row = document.createElement('div');
+
+    if (item.source === 'change') {
+        // Graph change: "2026-01-01 12:00:00" (UTC, from the change log)
+        row.className = 'transcript-row transcript-row-change';
+        const time = formatTime(item.timestamp.replace(' ', 'T') + 'Z');
+        const details = item.details ? ` - ${escapeHtml(item.details)}` : '';
+        row.innerHTML = `
+            <span class="transcript-time">${escapeHtml(time)}</span>
+            <span class="transcript-kind">graph</span>
+            <span class="transcript-text">
+                ${escapeHtml(item.entity_type)}
+                <a href="#" class="transcript-entity" data-entity-id="${escapeHtml(item.entity_id)}">${escapeHtml(item.entity_id)}</a>
+                ${escapeHtml(item.action)}${details}
+            </span>
+        `;
+        return row;
+    }
+
+    row.className = `transcript-row transcript-row-${item.kind}`;
+    let text = escapeHtml(item.text);
+    if (item.kind === 'command') {
+        const status = item.success === false ? 'failed' : 'ok';
+        text = `$ bn ${text} <span class="transcript-status transcript-status-${status}">${status}, ${item.duration_ms || 0}ms</span>`;
+    }
+    row.innerHTML = `
+        <span class="transcript-time">${escapeHtml(formatTime(item.at))}</span>
+        <span class="transcript-kind">${escapeHtml(item.kind)}</span>
+        <span class="transcript-text">${text}</span>
+    `;
+    return row;
+}
+
+/**
+ * Create the transcript viewer overlay HTML
+ * @returns {HTMLElement} The transcript viewer overlay element
+ */
+export function createTranscriptViewer() {
+    const overlay = document.createElement('div');
+    overlay.className = 'transcript-viewer-overlay hidden';
+    overlay.id = 'transcript-viewer';
+
+    overlay.innerHTML = `
+        <div class="transcript-viewer">
+            <div class="transcript-viewer-header">
+                <h2 class="transcript-viewer-title" id="transcript-viewer-title">Transcript</h2>
+                <button class="transcript-viewer-close" id="transcript-viewer-close" title="Close">&times;</button>
+            </div>
+            <div class="transcript-viewer-content" id="transcript-viewer-content">
+                <div class="transcript-viewer-loading">Loading transcript...</div>
+            </div>
+        </div>
+    `;
+
+    return overlay;
+}
+
+/**
+ * Show the transcript viewer for an agent
+ * @param {string} agentId - The agent ID
+ * @param {string} [agentName] - Display name for the title
+ */
+export async function showTranscriptViewer(agentId, agentName) {
+    const overlay = document.getElementById('transcript-viewer');
+    if (!overlay) {
+        console.error('Transcript viewer overlay not found in DOM');
+        return;
+    }
+
+    const titleEl = document.getElementById('transcript-viewer-title');
+    titleEl.textContent = `Transcript: ${agentName || agentId}`;
+
+    const contentEl = document.getElementById('transcript-viewer-content');
+    contentEl.innerHTML = '<div class="transcript-viewer-loading">Loading transcript...</div>';
+    overlay.classList.remove('hidden');
+
+    try {
+        const response = await fetch(`/api/agents/${encodeURIComponent(agentId)}/transcript`);
+        if (response.status === 404) {
+            contentEl.innerHTML = '<p class="transcript-viewer-note">No transcript recorded for this agent yet.</p>';
+            return;
+        }
+        if (!response.ok) {
+            throw new Error(`Failed to fetch transcript: ${response.status}`);
+        }
+        const data = await response.json();
+        const timeline = data.timeline || [];
+
+        contentEl.innerHTML = '';
+        if (timeline.length === 0) {
+            contentEl.innerHTML = '<p class="transcript-viewer-note">The transcript is empty.</p>';
+            return;
+        }
+        for (const item of timeline) {
+            contentEl.appendChild(renderTimelineItem(item));
+        }
+    } catch (error) {
+        console.error('Error loading transcript:', error);
+        contentEl.innerHTML = '<p class="transcript-viewer-note">Error loading transcript. Please try again.</p>';
+    }
+}
+
+/**
+ * Hide the transcript viewer overlay
+ */
+export function hideTranscriptViewer() {
+    const overlay = document.getElementById('transcript-viewer');
+    if (overlay) {
+        overlay.classList.add('hidden');
+    }
+}
+
+/**
+ * Initialize the transcript viewer with event handlers
+ */
+export function initTranscriptViewer() {
+    const overlay = document.getElementById('transcript-viewer');
+    if (!overlay) {
+        console.error('Transcript viewer overlay not found in DOM');
+        return;
+    }
+
+    document.getElementById('transcript-viewer-close')
+        .addEventListener('click', hideTranscriptViewer);
+
+    // Close on overlay click (but not on content click)
+    overlay.addEventListener('click', (e) => {
+        if (e.target === overlay) {
+            hideTranscriptViewer();
+        }
+    });
+
+    // Close on Escape key
+    document.addEventListener('keydown', (e) => {
+        if (e.key === 'Escape' && !overlay.classList.contains('hidden')) {
+            hideTranscriptViewer();
+        }
+    });
+
+    // Entity links in graph changes navigate the graph
+    const contentEl = document.getElementById('transcript-viewer-content');
+    contentEl.addEventListener('click', (e) => {
+        const link = e.target.closest('.transcript-entity');
+        if (!link) return;
+
+        e.preventDefault();
+        hideTranscriptViewer();
+        viewNodeOnGraph(link.dataset.entityId);
+    });
+}
+
+/**
+ * Mount the transcript viewer to the DOM
+ * @param {HTMLElement|string} target - Target element or selector
+ */
+export function mountTranscriptViewer(target) {
+    const container = typeof target === 'string'
+        ? document.querySelector(target)
+        : target;
+
+    if (!container) {
+        console.error('Transcript viewer target not found');
+        return;
+    }
+
+    container.appendChild(createTranscriptViewer());
+    initTranscriptViewer();
+}
@@ -0,0 +1,73 @@
+/**
+ * Unit tests for Transcript Viewer Overlay Component
+ */
+
+import { describe, it, expect, beforeEach, afterEach } from '../test-component.js';
+
+describe('Transcript Viewer Overlay', () => {
+    let container;
+
+    beforeEach(() => {
+        container = document.createElement('div');
+        container.id = 'test-container';
+        document.body.appendChild(container);
+    });
+
+    afterEach(() => {
+        if (container && container.parentNode) {
+            container.parentNode.removeChild(container);
+        }
+    });
+
+    it('should mount hidden with header and content', async () => {
+        const { mountTranscriptViewer } = await import('./transcript-viewer.js');
+
+        mountTranscriptViewer(container);
+
+        const overlay = container.querySelector('#transcript-viewer');
+        expect(overlay).toBeDefined();
+        expect(overlay.classList.contains('hidden')).toBe(true);
+        expect(overlay.querySelector('.transcript-viewer-title')).toBeDefined();
+        expect(overlay.querySelector('.transcript-viewer-content')).toBeDefined();
+    });
+
+    it('should render command and change rows', async () => {
+        const { renderTimelineItem } = await import('./transcript-viewer.js');
+
+        const command = renderTimelineItem({
+            source: 'transcript',
+            at: '2026-01-01T12:00:00Z',
+            kind: 'command',
+            text: 'task update',
+            success: false,
+            duration_ms: 42
+        });
+        expect(command.className).toContain('transcript-row-command');
+        expect(command.textContent).toContain('$ bn task update');
+        expect(command.textContent).toContain('failed, 42ms');
+
+        const change = renderTimelineItem({
+            source: 'change',
+            timestamp: '2026-01-01 12:00:01',
+            entity_type: 'task',
+            entity_id: 'bn-a1b2',
+            action: 'closed'
+        });
+        expect(change.className).toContain('transcript-row-change');
+        const link = change.querySelector('.transcript-entity');
+        expect(link.dataset.entityId).toBe('bn-a1b2');
+    });
+
+    it('should escape output text', async () => {
+        const { renderTimelineItem } = await import('./transcript-viewer.js');
+
+        const row = renderTimelineItem({
+            source: 'transcript',
+            at: '2026-01-01T12:00:00Z',
+            kind: 'stdout',
+            text: '<script>alert(1)</script>'
+        });
+        expect(row.querySelector('script')).toBe(null);
+        expect(row.textContent).toContain('<script>');
+    });
+});